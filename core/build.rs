@@ -11,7 +11,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build_server(true)
         .protoc_arg("--experimental_allow_proto3_optional")
         .file_descriptor_set_path(out_dir.join(NODE_DESCRIPTOR_FILE))
-        .compile(&["proto/node/v1alpha2/stream.proto"], &["proto/node"])?;
+        .compile(
+            &[
+                "proto/node/v1alpha2/stream.proto",
+                "proto/node/v1alpha2/auth.proto",
+                "proto/node/v1alpha2/quota.proto",
+                "proto/node/v1alpha2/admin.proto",
+                "proto/node/v1alpha2/usage.proto",
+                "proto/node/v1alpha2/error.proto",
+                "proto/node/v1alpha3/stream.proto",
+            ],
+            &["proto/node"],
+        )?;
 
     tonic_build::configure()
         .build_client(true)
@@ -24,6 +35,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             &[
                 "proto/starknet/v1alpha2/starknet.proto",
                 "proto/starknet/v1alpha2/filter.proto",
+                "proto/starknet/v1alpha2/sync.proto",
             ],
             &["proto/starknet"],
         )?;
@@ -35,5 +47,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .preserve_proto_field_names()
         .exclude([".apibara.starknet.v1alpha2.FieldElement"])
         .build(&[".apibara"])?;
+
+    // same for the v1alpha3 stream messages. Cursors and finality are
+    // serialized by hand, like their v1alpha2 version.
+    let node_description_set = std::fs::read(out_dir.join(NODE_DESCRIPTOR_FILE))?;
+    pbjson_build::Builder::new()
+        .register_descriptors(&node_description_set)?
+        .preserve_proto_field_names()
+        .exclude([
+            ".apibara.node.v1alpha3.Cursor",
+            ".apibara.node.v1alpha3.DataFinality",
+        ])
+        .build(&[".apibara.node.v1alpha3"])?;
     Ok(())
 }
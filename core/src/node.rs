@@ -1,10 +1,12 @@
 pub mod v1alpha2 {
-    use std::fmt;
+    use std::{fmt, time::Duration};
 
+    use prost::Message;
     use serde::{
         de::{self, Deserialize, Deserializer, Visitor},
         ser::{Serialize, SerializeStruct, Serializer},
     };
+    use tonic::metadata::MetadataMap;
 
     tonic::include_proto!("apibara.node.v1alpha2");
 
@@ -15,6 +17,99 @@ pub mod v1alpha2 {
         FILE_DESCRIPTOR_SET
     }
 
+    /// The `google.rpc.Status` message sent in the `grpc-status-details-bin`
+    /// trailer.
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct RpcStatus {
+        #[prost(int32, tag = "1")]
+        code: i32,
+        #[prost(string, tag = "2")]
+        message: String,
+        #[prost(message, repeated, tag = "3")]
+        details: Vec<RpcAny>,
+    }
+
+    /// The `google.protobuf.Any` message.
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct RpcAny {
+        #[prost(string, tag = "1")]
+        type_url: String,
+        #[prost(bytes = "vec", tag = "2")]
+        value: Vec<u8>,
+    }
+
+    impl StreamErrorDetails {
+        pub const TYPE_URL: &'static str =
+            "type.googleapis.com/apibara.node.v1alpha2.StreamErrorDetails";
+
+        pub fn new(code: StreamErrorCode, retryable: bool) -> Self {
+            StreamErrorDetails {
+                code: code as i32,
+                retryable,
+                ..StreamErrorDetails::default()
+            }
+        }
+
+        /// Ask clients to wait before retrying.
+        pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+            self.retry_after_ms = Some(retry_after.as_millis() as u64);
+            self
+        }
+
+        /// Tell clients the range of blocks available on the node.
+        pub fn with_available(mut self, earliest: Option<Cursor>, latest: Option<Cursor>) -> Self {
+            self.earliest_available = earliest;
+            self.latest_available = latest;
+            self
+        }
+
+        /// Tell clients where to resume the stream.
+        pub fn with_resume_cursor(mut self, cursor: Option<Cursor>) -> Self {
+            self.resume_cursor = cursor;
+            self
+        }
+
+        /// Tell clients when their quota resets.
+        pub fn with_quota_reset_at(mut self, reset_at: u64) -> Self {
+            self.quota_reset_at = Some(reset_at);
+            self
+        }
+
+        /// Returns a status with these details.
+        pub fn into_status(
+            self,
+            code: tonic::Code,
+            message: impl Into<String>,
+            metadata: MetadataMap,
+        ) -> tonic::Status {
+            let message = message.into();
+            let status = RpcStatus {
+                code: code as i32,
+                message: message.clone(),
+                details: vec![RpcAny {
+                    type_url: Self::TYPE_URL.to_string(),
+                    value: self.encode_to_vec(),
+                }],
+            };
+            tonic::Status::with_details_and_metadata(
+                code,
+                message,
+                status.encode_to_vec().into(),
+                metadata,
+            )
+        }
+
+        /// Returns the details of the status, if any.
+        pub fn from_status(status: &tonic::Status) -> Option<Self> {
+            let status = RpcStatus::decode(status.details()).ok()?;
+            status
+                .details
+                .iter()
+                .find(|detail| detail.type_url == Self::TYPE_URL)
+                .and_then(|detail| StreamErrorDetails::decode(detail.value.as_slice()).ok())
+        }
+    }
+
     impl Serialize for Cursor {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
@@ -140,6 +235,244 @@ pub mod v1alpha2 {
     }
 }
 
+pub mod v1alpha3 {
+    use std::fmt;
+
+    use serde::{
+        de::{self, Deserialize, Deserializer, Visitor},
+        ser::{Serialize, SerializeStruct, Serializer},
+    };
+
+    use super::v1alpha2;
+
+    tonic::include_proto!("apibara.node.v1alpha3");
+    tonic::include_proto!("apibara.node.v1alpha3.serde");
+
+    impl Cursor {
+        /// Returns the v1alpha3 cursor of `cursor`, on the given chain.
+        pub fn from_v1alpha2(cursor: v1alpha2::Cursor, chain_id: &[u8]) -> Self {
+            Cursor {
+                order_key: cursor.order_key,
+                unique_key: cursor.unique_key,
+                timestamp: None,
+                chain_id: chain_id.to_vec(),
+            }
+        }
+
+        /// Returns the cursor without timestamp and chain id.
+        pub fn to_v1alpha2(&self) -> v1alpha2::Cursor {
+            v1alpha2::Cursor {
+                order_key: self.order_key,
+                unique_key: self.unique_key.clone(),
+            }
+        }
+
+        /// Returns true if the cursor belongs to the given chain, or if
+        /// either chain is unknown.
+        pub fn is_on_chain(&self, chain_id: &[u8]) -> bool {
+            self.chain_id.is_empty() || chain_id.is_empty() || self.chain_id == chain_id
+        }
+    }
+
+    impl fmt::Display for Cursor {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "Cursor({}, 0x{})",
+                self.order_key,
+                hex::encode(&self.unique_key)
+            )
+        }
+    }
+
+    impl From<v1alpha2::DataFinality> for DataFinality {
+        fn from(finality: v1alpha2::DataFinality) -> Self {
+            match finality {
+                v1alpha2::DataFinality::DataStatusUnknown => DataFinality::DataStatusUnknown,
+                v1alpha2::DataFinality::DataStatusPending => DataFinality::DataStatusPending,
+                v1alpha2::DataFinality::DataStatusAccepted => DataFinality::DataStatusAccepted,
+                v1alpha2::DataFinality::DataStatusFinalized => DataFinality::DataStatusFinalized,
+            }
+        }
+    }
+
+    impl From<DataFinality> for v1alpha2::DataFinality {
+        fn from(finality: DataFinality) -> Self {
+            match finality {
+                DataFinality::DataStatusUnknown => v1alpha2::DataFinality::DataStatusUnknown,
+                DataFinality::DataStatusPending => v1alpha2::DataFinality::DataStatusPending,
+                DataFinality::DataStatusAccepted => v1alpha2::DataFinality::DataStatusAccepted,
+                DataFinality::DataStatusFinalized => v1alpha2::DataFinality::DataStatusFinalized,
+            }
+        }
+    }
+
+    impl Serialize for Cursor {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            let mut state = serializer.serialize_struct("Cursor", 4)?;
+            state.serialize_field("order_key", &self.order_key)?;
+            let hex_key = format!("0x{}", hex::encode(&self.unique_key));
+            state.serialize_field("unique_key", &hex_key)?;
+            if let Some(timestamp) = self.timestamp {
+                state.serialize_field("timestamp", &timestamp)?;
+            } else {
+                state.skip_field("timestamp")?;
+            }
+            if self.chain_id.is_empty() {
+                state.skip_field("chain_id")?;
+            } else {
+                let hex_chain_id = format!("0x{}", hex::encode(&self.chain_id));
+                state.serialize_field("chain_id", &hex_chain_id)?;
+            }
+            state.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Cursor {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct CursorVisitor;
+
+            impl<'de> Visitor<'de> for CursorVisitor {
+                type Value = Cursor;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("struct Cursor")
+                }
+
+                fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+                where
+                    A: serde::de::MapAccess<'de>,
+                {
+                    let mut order_key = None;
+                    let mut unique_key = None;
+                    let mut timestamp = None;
+                    let mut chain_id = None;
+                    while let Some(key) = map.next_key::<String>()? {
+                        match key.as_str() {
+                            "order_key" | "orderKey" => {
+                                order_key = Some(map.next_value()?);
+                            }
+                            "unique_key" | "uniqueKey" => {
+                                unique_key = Some(decode_hex(&map.next_value::<String>()?)?);
+                            }
+                            "timestamp" => {
+                                timestamp = map.next_value()?;
+                            }
+                            "chain_id" | "chainId" => {
+                                chain_id = Some(decode_hex(&map.next_value::<String>()?)?);
+                            }
+                            field => return Err(de::Error::unknown_field(field, FIELDS)),
+                        }
+                    }
+                    let order_key =
+                        order_key.ok_or_else(|| de::Error::missing_field("order_key"))?;
+                    let unique_key =
+                        unique_key.ok_or_else(|| de::Error::missing_field("unique_key"))?;
+                    Ok(Cursor {
+                        order_key,
+                        unique_key,
+                        timestamp,
+                        chain_id: chain_id.unwrap_or_default(),
+                    })
+                }
+            }
+
+            const FIELDS: &[&str] = &["order_key", "unique_key", "timestamp", "chain_id"];
+            deserializer.deserialize_struct("Cursor", FIELDS, CursorVisitor)
+        }
+    }
+
+    /// Decodes a hex value with 0x prefix.
+    fn decode_hex<E: de::Error>(value: &str) -> Result<Vec<u8>, E> {
+        value
+            .strip_prefix("0x")
+            .and_then(|value| hex::decode(value).ok())
+            .ok_or_else(|| {
+                de::Error::invalid_value(de::Unexpected::Str(value), &"a hex value with 0x prefix")
+            })
+    }
+
+    impl Serialize for DataFinality {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            v1alpha2::DataFinality::from(*self).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for DataFinality {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            v1alpha2::DataFinality::deserialize(deserializer).map(DataFinality::from)
+        }
+    }
+
+    impl StreamDataRequest {
+        /// Returns the v1alpha2 request, failing if the starting cursor
+        /// belongs to a different chain.
+        pub fn to_v1alpha2(
+            &self,
+            chain_id: &[u8],
+        ) -> Result<v1alpha2::StreamDataRequest, ChainMismatch> {
+            let starting_cursor = match self.starting_cursor {
+                None => None,
+                Some(ref cursor) if !cursor.is_on_chain(chain_id) => {
+                    return Err(ChainMismatch {
+                        expected: chain_id.to_vec(),
+                        actual: cursor.chain_id.clone(),
+                    })
+                }
+                Some(ref cursor) => Some(cursor.to_v1alpha2()),
+            };
+            let finality = self
+                .finality
+                .and_then(DataFinality::from_i32)
+                .map(|finality| v1alpha2::DataFinality::from(finality) as i32);
+            Ok(v1alpha2::StreamDataRequest {
+                stream_id: self.stream_id,
+                batch_size: self.batch_size,
+                starting_cursor,
+                finality,
+                filter: self.filter.clone(),
+            })
+        }
+    }
+
+    impl StatusResponse {
+        /// Returns the v1alpha3 status of `status`, on the given chain.
+        pub fn from_v1alpha2(status: v1alpha2::StatusResponse, chain_id: &[u8]) -> Self {
+            let cursor = |cursor: Option<v1alpha2::Cursor>| {
+                cursor.map(|cursor| Cursor::from_v1alpha2(cursor, chain_id))
+            };
+            StatusResponse {
+                current_head: cursor(status.current_head),
+                last_finalized: cursor(status.last_finalized),
+                earliest_available: cursor(status.earliest_available),
+                provider_head: status.provider_head,
+                ingestion_lag: status.ingestion_lag,
+                chain_id: chain_id.to_vec(),
+            }
+        }
+    }
+
+    /// A cursor belongs to a different chain than the node.
+    #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+    #[error("cursor is on chain 0x{}, but the node serves chain 0x{}", hex::encode(.actual), hex::encode(.expected))]
+    pub struct ChainMismatch {
+        pub expected: Vec<u8>,
+        pub actual: Vec<u8>,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::node::v1alpha2::DataFinality;
@@ -156,6 +489,37 @@ mod tests {
         assert_eq!(cursor, back);
     }
 
+    #[test]
+    fn test_stream_error_details() {
+        use std::time::Duration;
+
+        use tonic::{metadata::MetadataMap, Code};
+
+        use crate::node::v1alpha2::{Cursor, StreamErrorCode, StreamErrorDetails};
+
+        let details = StreamErrorDetails::new(StreamErrorCode::CursorUnavailable, false)
+            .with_available(
+                Some(Cursor {
+                    order_key: 10,
+                    unique_key: vec![1],
+                }),
+                None,
+            )
+            .with_retry_after(Duration::from_secs(2));
+        let status =
+            details
+                .clone()
+                .into_status(Code::OutOfRange, "cursor unavailable", MetadataMap::new());
+        assert_eq!(status.code(), Code::OutOfRange);
+        assert_eq!(status.message(), "cursor unavailable");
+        let back = StreamErrorDetails::from_status(&status).unwrap();
+        assert_eq!(back, details);
+        assert_eq!(back.code(), StreamErrorCode::CursorUnavailable);
+        assert_eq!(back.retry_after_ms, Some(2000));
+
+        assert!(StreamErrorDetails::from_status(&tonic::Status::internal("no details")).is_none());
+    }
+
     #[test]
     fn test_data_finality_serialization() {
         let serialized = serde_json::to_string(&DataFinality::DataStatusUnknown).unwrap();
@@ -178,4 +542,82 @@ mod tests {
         let back: DataFinality = serde_json::from_str(&serialized).unwrap();
         assert_eq!(back, DataFinality::DataStatusFinalized);
     }
+
+    #[test]
+    fn test_v1alpha3_request() {
+        use crate::node::{v1alpha2, v1alpha3};
+
+        let request = v1alpha3::StreamDataRequest {
+            stream_id: Some(1),
+            batch_size: Some(10),
+            starting_cursor: Some(v1alpha3::Cursor {
+                order_key: 100,
+                unique_key: vec![1, 2],
+                timestamp: Some(1_700_000_000),
+                chain_id: vec![0xaa],
+            }),
+            finality: Some(v1alpha3::DataFinality::DataStatusFinalized as i32),
+            filter: vec![3],
+        };
+        let v1alpha2 = request.to_v1alpha2(&[0xaa]).unwrap();
+        assert_eq!(
+            v1alpha2.starting_cursor,
+            Some(v1alpha2::Cursor {
+                order_key: 100,
+                unique_key: vec![1, 2],
+            })
+        );
+        assert_eq!(
+            v1alpha2.finality,
+            Some(v1alpha2::DataFinality::DataStatusFinalized as i32)
+        );
+        assert_eq!(v1alpha2.filter, vec![3]);
+
+        let err = request.to_v1alpha2(&[0xbb]).unwrap_err();
+        assert_eq!(err.actual, vec![0xaa]);
+
+        // cursors without chain id are accepted on any chain.
+        let mut request = request;
+        request.starting_cursor.as_mut().unwrap().chain_id.clear();
+        assert!(request.to_v1alpha2(&[0xbb]).is_ok());
+    }
+
+    #[test]
+    fn test_v1alpha3_json() {
+        use crate::node::v1alpha3::{Block, Cursor, Data, DataFinality};
+
+        let cursor = Cursor {
+            order_key: 100,
+            unique_key: vec![0xab, 0xcd],
+            timestamp: Some(1_700_000_000),
+            chain_id: vec![0x01],
+        };
+        let data = Data {
+            end_cursor: Some(cursor.clone()),
+            finality: DataFinality::DataStatusAccepted as i32,
+            blocks: vec![Block {
+                cursor: Some(cursor),
+                finality: DataFinality::DataStatusFinalized as i32,
+                data: vec![1, 2, 3],
+            }],
+            cursor: None,
+            sequence: 3,
+        };
+
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["end_cursor"]["unique_key"], "0xabcd");
+        assert_eq!(json["end_cursor"]["chain_id"], "0x01");
+        assert_eq!(json["finality"], "accepted");
+        assert_eq!(json["blocks"][0]["finality"], "finalized");
+        let back: Data = serde_json::from_str(&json.to_string()).unwrap();
+        assert_eq!(back, data);
+
+        // cursors without timestamp and chain id, in camel case.
+        let cursor: Cursor =
+            serde_json::from_str(r#"{"orderKey": 1, "uniqueKey": "0x02"}"#).unwrap();
+        assert_eq!(cursor.timestamp, None);
+        assert!(cursor.chain_id.is_empty());
+        let json = serde_json::to_string(&cursor).unwrap();
+        assert_eq!(json, r#"{"order_key":1,"unique_key":"0x02"}"#);
+    }
 }
@@ -53,6 +53,32 @@ impl Filter {
         self
     }
 
+    /// Add function invocation to filter.
+    pub fn add_invocation<F>(&mut self, closure: F) -> &mut Self
+    where
+        F: Fn(FunctionInvocationFilter) -> FunctionInvocationFilter,
+    {
+        self.invocations
+            .push(closure(FunctionInvocationFilter::default()));
+        self
+    }
+
+    /// Add declared class to filter.
+    pub fn add_declared_class<F>(&mut self, closure: F) -> &mut Self
+    where
+        F: Fn(DeclaredClassFilter) -> DeclaredClassFilter,
+    {
+        self.declared_classes
+            .push(closure(DeclaredClassFilter::default()));
+        self
+    }
+
+    /// Include the L1 data availability of blocks.
+    pub fn with_data_availability(&mut self) -> &mut Self {
+        self.data_availability = true;
+        self
+    }
+
     /// Build final version of Filter
     pub fn build(&mut self) -> Self {
         // As the ::prost::Message already impl Default trait and doesn't seems to be overridable
@@ -279,6 +305,40 @@ impl L2ToL1MessageFilter {
     }
 }
 
+impl FunctionInvocationFilter {
+    /// Filter invocation of contract.
+    pub fn with_contract_address(mut self, address: FieldElement) -> Self {
+        self.contract_address = Some(address);
+        self
+    }
+
+    /// Filter invocation with selector.
+    pub fn with_entry_point_selector(mut self, selector: FieldElement) -> Self {
+        self.entry_point_selector = Some(selector);
+        self
+    }
+
+    /// Filter invocation with calldata.
+    pub fn with_calldata(mut self, calldata: Vec<FieldElement>) -> Self {
+        self.calldata = calldata;
+        self
+    }
+}
+
+impl DeclaredClassFilter {
+    /// Filter class with hash.
+    pub fn with_class_hash(mut self, class_hash: FieldElement) -> Self {
+        self.class_hash = Some(class_hash);
+        self
+    }
+
+    /// Filter class declared by account.
+    pub fn with_sender_address(mut self, address: FieldElement) -> Self {
+        self.sender_address = Some(address);
+        self
+    }
+}
+
 impl StateUpdateFilter {
     /// Add storage diff filter to state update filter.
     pub fn add_storage_diff<F>(mut self, closure: F) -> Self
@@ -518,6 +578,30 @@ impl L2ToL1MessageFilter {
     }
 }
 
+impl FunctionInvocationFilter {
+    pub fn matches(&self, invocation: &FunctionInvocation) -> bool {
+        self.contract_address.matches(&invocation.contract_address)
+            && self
+                .entry_point_selector
+                .matches(&invocation.entry_point_selector)
+            && self.calldata.prefix_matches(&invocation.calldata)
+    }
+}
+
+impl DeclaredClassFilter {
+    pub fn matches(&self, class: &DeclaredClass) -> bool {
+        let sender_address = match class
+            .transaction
+            .as_ref()
+            .and_then(|tx| tx.transaction.as_ref())
+        {
+            Some(transaction::Transaction::Declare(tx)) => tx.sender_address.clone(),
+            _ => None,
+        };
+        self.class_hash.matches(&class.class_hash) && self.sender_address.matches(&sender_address)
+    }
+}
+
 impl StorageDiffFilter {
     pub fn matches(&self, storage_diff: &StorageDiff) -> bool {
         self.contract_address
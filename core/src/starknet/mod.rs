@@ -1,5 +1,6 @@
 mod data;
 mod filter;
+mod normalize;
 mod proto;
 
 pub mod v1alpha2 {
@@ -0,0 +1,206 @@
+//! Canonical form, hash and merge of filters.
+
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use super::proto::v1alpha2::*;
+
+impl Filter {
+    /// Returns the canonical form of the filter.
+    ///
+    /// Filters that differ only by the order or repetition of their matchers
+    /// have the same canonical form. Lists of matchers that contain a matcher
+    /// that matches everything are reduced to that matcher.
+    ///
+    /// Prefix lists, such as event keys and calldata, keep their order.
+    pub fn canonical(&self) -> Filter {
+        let mut filter = self.clone();
+        filter.canonicalize();
+        filter
+    }
+
+    /// Puts the filter in its canonical form, see [Filter::canonical].
+    pub fn canonicalize(&mut self) {
+        canonicalize_matchers(&mut self.transactions);
+        canonicalize_matchers(&mut self.events);
+        canonicalize_matchers(&mut self.messages);
+        canonicalize_matchers(&mut self.invocations);
+        canonicalize_matchers(&mut self.declared_classes);
+        if let Some(state_update) = self.state_update.as_mut() {
+            state_update.canonicalize();
+        }
+    }
+
+    /// Returns the SHA-256 hash of the canonical filter.
+    ///
+    /// Equivalent filters have the same hash, so it can be used to cache
+    /// data by filter.
+    pub fn canonical_hash(&self) -> [u8; 32] {
+        Sha256::digest(self.canonical().encode_to_vec()).into()
+    }
+
+    /// Merges `other` into the filter.
+    ///
+    /// The merged filter matches all data matched by either filter, so that
+    /// several subscriptions can be served by one stream. Clients need to
+    /// filter the data again to split it between subscriptions.
+    pub fn merge_with(&mut self, other: &Filter) {
+        self.header = match (self.header.take(), other.header.as_ref()) {
+            (None, None) => None,
+            (Some(header), None) => Some(header),
+            (None, Some(header)) => Some(header.clone()),
+            // a strong header is included in all blocks, so it wins.
+            (Some(header), Some(other)) => Some(HeaderFilter {
+                weak: header.weak && other.weak,
+            }),
+        };
+        self.transactions.extend(other.transactions.iter().cloned());
+        self.events.extend(other.events.iter().cloned());
+        self.messages.extend(other.messages.iter().cloned());
+        self.invocations.extend(other.invocations.iter().cloned());
+        self.declared_classes
+            .extend(other.declared_classes.iter().cloned());
+        self.state_update = match (self.state_update.take(), other.state_update.as_ref()) {
+            (None, None) => None,
+            (Some(state_update), None) => Some(state_update),
+            (None, Some(state_update)) => Some(state_update.clone()),
+            (Some(mut state_update), Some(other)) => {
+                state_update.merge_with(other);
+                Some(state_update)
+            }
+        };
+        self.data_availability |= other.data_availability;
+        self.canonicalize();
+    }
+
+    /// Returns the merge of all `filters`, see [Filter::merge_with].
+    pub fn merged<'a>(filters: impl IntoIterator<Item = &'a Filter>) -> Filter {
+        let mut merged = Filter::default();
+        for filter in filters {
+            merged.merge_with(filter);
+        }
+        merged
+    }
+}
+
+impl StateUpdateFilter {
+    /// Puts the filter in its canonical form, see [Filter::canonical].
+    pub fn canonicalize(&mut self) {
+        canonicalize_matchers(&mut self.storage_diffs);
+        canonicalize_matchers(&mut self.declared_contracts);
+        canonicalize_matchers(&mut self.deployed_contracts);
+        canonicalize_matchers(&mut self.nonces);
+    }
+
+    /// Merges `other` into the filter, see [Filter::merge_with].
+    pub fn merge_with(&mut self, other: &StateUpdateFilter) {
+        self.storage_diffs
+            .extend(other.storage_diffs.iter().cloned());
+        self.declared_contracts
+            .extend(other.declared_contracts.iter().cloned());
+        self.deployed_contracts
+            .extend(other.deployed_contracts.iter().cloned());
+        self.nonces.extend(other.nonces.iter().cloned());
+        self.canonicalize();
+    }
+}
+
+/// Sorts and deduplicates matchers.
+///
+/// An empty matcher matches everything, so it replaces all other matchers.
+fn canonicalize_matchers<T>(matchers: &mut Vec<T>)
+where
+    T: Message + Default + PartialEq,
+{
+    if matchers.iter().any(|matcher| *matcher == T::default()) {
+        matchers.clear();
+        matchers.push(T::default());
+        return;
+    }
+    // the encoding of messages is deterministic, use it as sort key.
+    matchers.sort_by_cached_key(|matcher| matcher.encode_to_vec());
+    matchers.dedup();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::starknet::v1alpha2::{
+        EventFilter, FieldElement, Filter, HeaderFilter, StateUpdateFilter, StorageDiffFilter,
+        TransactionFilter,
+    };
+
+    fn event(address: u64, keys: &[u64]) -> EventFilter {
+        EventFilter::default()
+            .with_from_address(FieldElement::from_u64(address))
+            .with_keys(keys.iter().copied().map(FieldElement::from_u64).collect())
+    }
+
+    #[test]
+    fn test_canonical_filter() {
+        let a = Filter {
+            events: vec![event(2, &[1, 2]), event(1, &[3]), event(2, &[1, 2])],
+            ..Filter::default()
+        };
+        let mut b = Filter {
+            events: vec![event(1, &[3]), event(2, &[1, 2])],
+            ..Filter::default()
+        };
+        assert_eq!(a.canonical(), b.canonical());
+        assert_eq!(a.canonical().events.len(), 2);
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+
+        // prefix lists keep their order.
+        b.events = vec![event(1, &[3]), event(2, &[2, 1])];
+        assert_ne!(a.canonical_hash(), b.canonical_hash());
+
+        // the empty matcher matches all transactions.
+        let mut filter = Filter::default();
+        filter.add_transaction(|mut tx| {
+            tx.invoke_transaction_v1(|v1| v1.with_sender_address(FieldElement::from_u64(1)));
+            tx
+        });
+        filter.transactions.push(TransactionFilter::default());
+        filter.canonicalize();
+        assert_eq!(filter.transactions, vec![TransactionFilter::default()]);
+    }
+
+    #[test]
+    fn test_merge_filters() {
+        let mut a = Filter {
+            events: vec![event(1, &[3])],
+            ..Filter::default()
+        };
+        a.with_header(HeaderFilter::weak()).with_state_update(
+            StateUpdateFilter::default()
+                .add_storage_diff(|diff| diff.with_contract_address(FieldElement::from_u64(1))),
+        );
+
+        let mut b = Filter {
+            events: vec![event(2, &[]), event(1, &[3])],
+            ..Filter::default()
+        };
+        b.with_header(HeaderFilter::new())
+            .with_data_availability()
+            .with_state_update(
+                StateUpdateFilter::default()
+                    .add_storage_diff(|diff| diff.with_contract_address(FieldElement::from_u64(2))),
+            );
+
+        let merged = Filter::merged([&a, &b]);
+        assert_eq!(merged.header, Some(HeaderFilter::new()));
+        assert_eq!(merged.events.len(), 2);
+        assert!(merged.data_availability);
+        let storage_diffs = merged.state_update.unwrap().storage_diffs;
+        assert_eq!(storage_diffs.len(), 2);
+        assert!(storage_diffs.contains(
+            &StorageDiffFilter::default().with_contract_address(FieldElement::from_u64(2))
+        ));
+
+        // merging is commutative.
+        assert_eq!(
+            Filter::merged([&a, &b]).canonical_hash(),
+            Filter::merged([&b, &a]).canonical_hash()
+        );
+        assert_eq!(Filter::merged([]), Filter::default());
+    }
+}
@@ -25,4 +25,35 @@ mod tests {
         let back = serde_json::from_str::<v1alpha2::FieldElement>(&as_hex).unwrap();
         assert_eq!(fe, back);
     }
+
+    #[test]
+    pub fn test_filter_json() {
+        let address = v1alpha2::FieldElement::from_u64(0x1234);
+        let key = v1alpha2::FieldElement::from_u64(0x99);
+        let filter = v1alpha2::Filter::default()
+            .with_header(v1alpha2::HeaderFilter::weak())
+            .add_event(|event| {
+                event
+                    .with_from_address(address.clone())
+                    .with_keys(vec![key.clone()])
+            })
+            .build();
+
+        // fields use the proto names.
+        let json = serde_json::to_value(&filter).unwrap();
+        assert_eq!(json["header"]["weak"], true);
+        assert_eq!(json["events"][0]["from_address"], address.to_hex());
+        assert_eq!(json["events"][0]["keys"][0], key.to_hex());
+        let back: v1alpha2::Filter = serde_json::from_value(json).unwrap();
+        assert_eq!(back, filter);
+
+        // the json names of fields are accepted too.
+        let camel_case = format!(
+            r#"{{"header": {{"weak": true}}, "events": [{{"fromAddress": "{}", "keys": ["{}"]}}]}}"#,
+            address.to_hex(),
+            key.to_hex()
+        );
+        let back: v1alpha2::Filter = serde_json::from_str(&camel_case).unwrap();
+        assert_eq!(back, filter);
+    }
 }
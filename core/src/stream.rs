@@ -1,5 +1,7 @@
 use std::{fmt::Debug, marker::PhantomData, ops::Range};
 
+use prost::bytes::Bytes;
+
 /// Unique id for an input stream.
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub struct StreamId(u64);
@@ -104,9 +106,10 @@ impl<T> MessageData for T where T: prost::Message + Default + Clone {}
 /// A [MessageData] that is never decoded.
 ///
 /// Use this in place of a [Vec] of bytes to not lose type safety.
+/// The content is reference counted, so cloning it doesn't copy the message.
 #[derive(Debug, Clone)]
 pub struct RawMessageData<M: MessageData> {
-    data: Vec<u8>,
+    data: Bytes,
     _phantom: PhantomData<M>,
 }
 
@@ -176,6 +179,11 @@ where
 {
     /// Creates a new [RawMessageData] from [Vec].
     pub fn from_vec(data: Vec<u8>) -> Self {
+        Self::from_bytes(data.into())
+    }
+
+    /// Creates a new [RawMessageData] from [Bytes].
+    pub fn from_bytes(data: Bytes) -> Self {
         RawMessageData {
             data,
             _phantom: PhantomData::default(),
@@ -187,6 +195,11 @@ where
         self.data.as_ref()
     }
 
+    /// Returns the underlying [Bytes], without copying them.
+    pub fn into_bytes(self) -> Bytes {
+        self.data
+    }
+
     /// Decodes the raw message to a [prost::Message].
     pub fn to_proto(&self) -> Result<D, prost::DecodeError> {
         D::decode(self.data.as_ref())
@@ -0,0 +1,71 @@
+//! Chain-specific pieces of a node.
+//!
+//! The stream, storage and server layers in this crate are generic over the
+//! chain. A [ChainAdapter] provides the rest: cursor, filter and block types,
+//! where data and ingestion messages come from, and the chain tables.
+
+use async_trait::async_trait;
+use futures::{stream::FusedStream, Stream};
+use libmdbx::{Environment, EnvironmentKind, Error as MdbxError, Transaction, RW};
+use prost::Message;
+
+use crate::{
+    core::Cursor,
+    db::{MigrationError, MigrationRunner},
+    stream::{BatchProducer, BlockDescriptor, CursorProducer, IngestionMessage, StreamError},
+};
+
+/// Adapts a chain to the generic node.
+#[async_trait]
+pub trait ChainAdapter: Send + Sync + 'static {
+    /// Position of a block in the chain.
+    type Cursor: Cursor + Send + Sync;
+    /// Filter sent by clients.
+    type Filter: Message + Default + Clone;
+    /// Block sent to clients.
+    type Block: Message + Default + Clone;
+    /// Messages about ingested blocks.
+    type IngestionStream: Stream<Item = Result<IngestionMessage<Self::Cursor>, StreamError>>
+        + Unpin
+        + Send;
+    /// Produces the cursors of the blocks to stream.
+    type CursorProducer: CursorProducer<Cursor = Self::Cursor, Filter = Self::Filter>
+        + Unpin
+        + FusedStream
+        + Send;
+    /// Produces the blocks at the cursors.
+    type BatchProducer: BatchProducer<Cursor = Self::Cursor, Filter = Self::Filter, Block = Self::Block>
+        + Send;
+    /// Describes encoded blocks to v1alpha3 clients.
+    type BlockDescriptor: BlockDescriptor + Send + Sync + 'static;
+
+    /// Creates the chain tables, if they don't exist.
+    fn ensure_tables<E: EnvironmentKind>(txn: &Transaction<RW, E>) -> Result<(), MdbxError>;
+
+    /// Returns the migrations of the chain tables, in order of version.
+    fn migrations<E: EnvironmentKind>() -> MigrationRunner<E>;
+
+    /// Subscribes to messages about ingested blocks.
+    async fn subscribe(&self) -> Self::IngestionStream;
+
+    /// Returns a new cursor producer, one per stream.
+    fn cursor_producer(&self) -> Self::CursorProducer;
+
+    /// Returns a new batch producer, one per stream.
+    fn batch_producer(&self) -> Self::BatchProducer;
+
+    /// Returns the block descriptor.
+    fn block_descriptor(&self) -> Self::BlockDescriptor;
+}
+
+/// Creates the chain tables, then migrates them to the latest version.
+pub fn prepare_database<A, E>(db: &Environment<E>) -> Result<(), MigrationError>
+where
+    A: ChainAdapter,
+    E: EnvironmentKind,
+{
+    let txn = db.begin_rw_txn()?;
+    A::ensure_tables(&txn)?;
+    txn.commit()?;
+    A::migrations::<E>().run(db)
+}
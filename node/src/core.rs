@@ -1,10 +1,34 @@
 use apibara_core::node::v1alpha2::Cursor as ProtoCursor;
 
 /// A cursor is a position in a stream.
-pub trait Cursor: Sized + Default + Clone + std::fmt::Debug {
+///
+/// Cursors are totally ordered, by order key first. Cursors with the same
+/// order key are on different branches of the chain.
+pub trait Cursor: Sized + Default + Clone + Ord + std::fmt::Debug {
     /// Create a new cursor from a proto cursor.
     fn from_proto(cursor: &ProtoCursor) -> Option<Self>;
 
     /// Returns the proto cursor.
     fn to_proto(&self) -> ProtoCursor;
+
+    /// Returns the key used to order cursors, usually the block number.
+    fn order_key(&self) -> u64;
+
+    /// Returns the timestamp of the block, in seconds since the unix epoch.
+    ///
+    /// Cursors that don't carry the timestamp return `None`.
+    fn timestamp(&self) -> Option<u64> {
+        None
+    }
+
+    /// Returns the number of blocks between the two cursors.
+    fn distance(&self, other: &Self) -> u64 {
+        self.order_key().abs_diff(other.order_key())
+    }
+
+    /// Returns the number of blocks the cursor is behind `head`, `0` if it's
+    /// at or after it.
+    fn lag(&self, head: &Self) -> u64 {
+        head.order_key().saturating_sub(self.order_key())
+    }
 }
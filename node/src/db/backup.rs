@@ -0,0 +1,344 @@
+//! Online database backup.
+//!
+//! Backups use the compacting copy of mdbx, which writes a consistent
+//! snapshot of the database to a new file while the node keeps ingesting
+//! and serving data. Only live pages are copied, so the backup is also
+//! defragmented. Table flags are part of the copied pages, so tables like
+//! dupsort tables are restored as they were.
+//!
+//! Backups are written to the backup directory, then optionally uploaded to
+//! a remote target.
+
+use std::{
+    ffi::CString,
+    fmt, fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use libmdbx::{Environment, EnvironmentKind, Error as MdbxError};
+use serde::{Serialize, Serializer};
+use tracing::{error, info};
+
+/// Name of the data file inside an environment directory.
+pub const DATA_FILE_NAME: &str = "mdbx.dat";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("database operation failed")]
+    Database(#[from] MdbxError),
+    #[error("failed to create backup directory")]
+    Io(#[from] std::io::Error),
+    #[error("backup target {0:?} is not empty")]
+    TargetNotEmpty(PathBuf),
+    #[error("invalid backup name {0:?}")]
+    InvalidName(String),
+    #[error("a backup is already running")]
+    AlreadyRunning,
+    #[error("backup task failed")]
+    Task(#[from] tokio::task::JoinError),
+    #[error("failed to upload backup: {0}")]
+    Upload(String),
+}
+
+/// Uploads completed backups to a remote target.
+#[async_trait]
+pub trait BackupUploader: Send + Sync + 'static {
+    /// Uploads the data file at `path` as the backup with the given name.
+    async fn upload(&self, name: &str, path: &Path) -> Result<(), BackupError>;
+
+    /// Returns a description of the remote target, without secrets.
+    fn target(&self) -> String;
+}
+
+/// Backup configuration.
+#[derive(Clone, Serialize)]
+pub struct BackupConfig {
+    /// Backups are written to a new directory inside this directory.
+    pub dir: PathBuf,
+    /// Upload completed backups with this uploader.
+    #[serde(rename = "upload", serialize_with = "serialize_upload_target")]
+    pub uploader: Option<Arc<dyn BackupUploader>>,
+}
+
+/// Progress of a backup.
+#[derive(Debug, Clone, Default)]
+pub struct BackupProgress {
+    /// Size of the backup, in bytes.
+    pub bytes: u64,
+    /// Whether the backup was uploaded to the remote target.
+    pub uploaded: bool,
+}
+
+/// Status of the backup managed by [BackupService].
+#[derive(Debug, Clone, Default)]
+pub enum BackupStatus {
+    /// No backup was started.
+    #[default]
+    Idle,
+    /// Copying the database to the backup directory.
+    Copying { target: PathBuf },
+    /// Uploading the copy to the remote target.
+    Uploading {
+        target: PathBuf,
+        progress: BackupProgress,
+    },
+    /// Backup completed successfully.
+    Completed {
+        target: PathBuf,
+        progress: BackupProgress,
+        elapsed: Duration,
+    },
+    /// Backup failed.
+    Failed { target: PathBuf, error: String },
+}
+
+/// Runs backups in the background and keeps track of their status.
+pub struct BackupService<E: EnvironmentKind> {
+    db: Arc<Environment<E>>,
+    config: BackupConfig,
+    status: Arc<Mutex<BackupStatus>>,
+}
+
+impl<E: EnvironmentKind> BackupService<E> {
+    pub fn new(db: Arc<Environment<E>>, config: BackupConfig) -> Self {
+        BackupService {
+            db,
+            config,
+            status: Arc::new(Mutex::new(BackupStatus::Idle)),
+        }
+    }
+
+    /// Returns the status of the most recent backup.
+    pub fn status(&self) -> BackupStatus {
+        self.status.lock().expect("backup status lock").clone()
+    }
+
+    /// Starts a new backup with the given name.
+    ///
+    /// The name must be a single path component. Returns an error if another
+    /// backup is running.
+    pub fn start(&self, name: String) -> Result<(), BackupError> {
+        if !is_valid_backup_name(&name) {
+            return Err(BackupError::InvalidName(name));
+        }
+        let target = self.config.dir.join(&name);
+        {
+            let mut status = self.status.lock().expect("backup status lock");
+            if matches!(
+                *status,
+                BackupStatus::Copying { .. } | BackupStatus::Uploading { .. }
+            ) {
+                return Err(BackupError::AlreadyRunning);
+            }
+            *status = BackupStatus::Copying {
+                target: target.clone(),
+            };
+        }
+
+        let db = self.db.clone();
+        let status = self.status.clone();
+        let uploader = self.config.uploader.clone();
+        tokio::spawn(async move {
+            let started_at = Instant::now();
+            let result = run_backup(db, &name, &target, uploader, &status).await;
+
+            let new_status = match result {
+                Ok(progress) => {
+                    info!(target = ?target, progress = ?progress, "backup completed");
+                    BackupStatus::Completed {
+                        target,
+                        progress,
+                        elapsed: started_at.elapsed(),
+                    }
+                }
+                Err(err) => {
+                    error!(target = ?target, error = ?err, "backup failed");
+                    BackupStatus::Failed {
+                        target,
+                        error: err.to_string(),
+                    }
+                }
+            };
+            *status.lock().expect("backup status lock") = new_status;
+        });
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BackupConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackupConfig")
+            .field("dir", &self.dir)
+            .field("uploader", &self.uploader.as_ref().map(|u| u.target()))
+            .finish()
+    }
+}
+
+/// Serializes the uploader as the description of its target, which has no
+/// secrets.
+fn serialize_upload_target<S: Serializer>(
+    uploader: &Option<Arc<dyn BackupUploader>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    uploader
+        .as_ref()
+        .map(|uploader| uploader.target())
+        .serialize(serializer)
+}
+
+async fn run_backup<E: EnvironmentKind>(
+    db: Arc<Environment<E>>,
+    name: &str,
+    target: &Path,
+    uploader: Option<Arc<dyn BackupUploader>>,
+    status: &Mutex<BackupStatus>,
+) -> Result<BackupProgress, BackupError> {
+    let mut progress = tokio::task::spawn_blocking({
+        let target = target.to_path_buf();
+        move || backup_environment(&db, &target)
+    })
+    .await??;
+
+    if let Some(uploader) = uploader {
+        *status.lock().expect("backup status lock") = BackupStatus::Uploading {
+            target: target.to_path_buf(),
+            progress: progress.clone(),
+        };
+        info!(target = %uploader.target(), name = %name, "uploading backup");
+        uploader.upload(name, &target.join(DATA_FILE_NAME)).await?;
+        progress.uploaded = true;
+    }
+
+    Ok(progress)
+}
+
+/// Returns true if `name` can be used as the name of a backup directory.
+fn is_valid_backup_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Copies a consistent, compacted snapshot of `db` to a new environment in
+/// the `target` directory.
+pub fn backup_environment<E: EnvironmentKind>(
+    db: &Environment<E>,
+    target: &Path,
+) -> Result<BackupProgress, BackupError> {
+    fs::create_dir_all(target)?;
+    if fs::read_dir(target)?.next().is_some() {
+        return Err(BackupError::TargetNotEmpty(target.to_path_buf()));
+    }
+
+    let data_file = target.join(DATA_FILE_NAME);
+    copy_environment(db, &data_file)?;
+    let bytes = fs::metadata(&data_file)?.len();
+    Ok(BackupProgress {
+        bytes,
+        uploaded: false,
+    })
+}
+
+/// Writes a compacted copy of `db` to the data file at `path`.
+///
+/// The copy reads from a single snapshot of the database, so writers are
+/// never blocked. `path` must not exist.
+pub fn copy_environment<E: EnvironmentKind>(
+    db: &Environment<E>,
+    path: &Path,
+) -> Result<(), MdbxError> {
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(|_| MdbxError::Invalid)?;
+    // safety: the environment pointer is valid for the lifetime of `db`, and
+    // the path is a nul-terminated string.
+    let code =
+        unsafe { mdbx_sys::mdbx_env_copy(db.env(), path.as_ptr(), mdbx_sys::MDBX_CP_COMPACT) };
+    if code != mdbx_sys::MDBX_SUCCESS {
+        return Err(MdbxError::from_err_code(code));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::stream::StreamId;
+    use libmdbx::{DatabaseFlags, Environment, NoWriteMap, WriteFlags};
+    use tempfile::tempdir;
+
+    use crate::db::{
+        tables::{StreamState, StreamStateTable},
+        MdbxEnvironmentExt, MdbxRWTransactionExt, MdbxTransactionExt,
+    };
+
+    use super::{backup_environment, is_valid_backup_name};
+
+    #[test]
+    fn test_backup_environment() {
+        let path = tempdir().unwrap();
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+        let txn = db.begin_rw_txn().unwrap();
+        txn.ensure_table::<StreamStateTable>(None).unwrap();
+        let mut cursor = txn.open_cursor::<StreamStateTable>().unwrap();
+        for i in 0..100 {
+            let state = StreamState { sequence: Some(i) };
+            cursor.put(&StreamId::from_u64(i), &state).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let target = tempdir().unwrap();
+        let progress = backup_environment(&db, target.path()).unwrap();
+        assert!(progress.bytes > 0);
+
+        // backing up to the same directory again fails.
+        assert!(backup_environment(&db, target.path()).is_err());
+
+        let backup = Environment::<NoWriteMap>::open(target.path()).unwrap();
+        let txn = backup.begin_ro_txn().unwrap();
+        let table = txn.open_table::<StreamStateTable>().unwrap();
+        for i in 0..100 {
+            let state = table.get(&StreamId::from_u64(i)).unwrap().unwrap();
+            assert_eq!(state.sequence, Some(i));
+        }
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_backup_keeps_table_flags() {
+        let path = tempdir().unwrap();
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+        let txn = db.begin_rw_txn().unwrap();
+        let table = txn.create_db(Some("Dup"), DatabaseFlags::DUP_SORT).unwrap();
+        for value in [b"a", b"b", b"c"] {
+            txn.put(&table, b"key", value, WriteFlags::default())
+                .unwrap();
+        }
+        txn.commit().unwrap();
+
+        let target = tempdir().unwrap();
+        backup_environment(&db, target.path()).unwrap();
+
+        let backup = Environment::<NoWriteMap>::open(target.path()).unwrap();
+        let txn = backup.begin_ro_txn().unwrap();
+        let table = txn.open_db(Some("Dup")).unwrap();
+        // all duplicates are kept only if the table is still dupsort.
+        assert_eq!(txn.db_stat(&table).unwrap().entries(), 3);
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_backup_name() {
+        assert!(is_valid_backup_name("2023-05-01"));
+        assert!(is_valid_backup_name("nightly_1.bak"));
+        assert!(!is_valid_backup_name(""));
+        assert!(!is_valid_backup_name(".."));
+        assert!(!is_valid_backup_name("../data"));
+        assert!(!is_valid_backup_name("/tmp/backup"));
+    }
+}
@@ -0,0 +1,175 @@
+//! Offline database compaction.
+//!
+//! MDBX never moves live pages, so a database that saw many deletions and
+//! updates ends up with live data scattered over the whole file. Compaction
+//! writes a compacting copy of the database, which contains only live pages,
+//! and replaces the data file with it.
+//!
+//! The data file can't be replaced under an open environment, so compaction
+//! requires exclusive access to the database: the node must be stopped.
+
+use std::{fs, os::unix::fs::MetadataExt, path::Path};
+
+use libmdbx::{EnvironmentKind, Error as MdbxError};
+
+use super::{backup::copy_environment, mdbx::MdbxEnvironmentBuilder, DATA_FILE_NAME};
+
+/// Name of the lock file inside an environment directory.
+pub(super) const LOCK_FILE_NAME: &str = "mdbx.lck";
+
+/// Name of the directory the compacted copy is written to.
+pub(super) const COMPACT_DIR_NAME: &str = "compact.tmp";
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompactError {
+    #[error("database operation failed")]
+    Database(#[from] MdbxError),
+    #[error("database is in use by another process")]
+    Busy,
+    #[error("failed to replace the data file")]
+    Io(#[from] std::io::Error),
+    #[error("compaction was interrupted")]
+    Interrupted,
+}
+
+/// Result of a compaction.
+#[derive(Debug, Clone)]
+pub struct CompactSummary {
+    /// Disk space used by the data file before compaction, in bytes.
+    pub size_before: u64,
+    /// Disk space used by the data file after compaction, in bytes.
+    pub size_after: u64,
+}
+
+/// Compacts the environment in the `path` directory, opening it with
+/// `builder`.
+///
+/// The compacted copy is written next to the data file and renamed over it
+/// once complete, so an interrupted compaction leaves the database as it was.
+pub fn compact_environment<E: EnvironmentKind>(
+    builder: MdbxEnvironmentBuilder<E>,
+    path: &Path,
+) -> Result<CompactSummary, CompactError> {
+    let data_file = path.join(DATA_FILE_NAME);
+    let size_before = disk_usage(&data_file)?;
+
+    // remove leftovers of an interrupted compaction.
+    let compact_dir = path.join(COMPACT_DIR_NAME);
+    if compact_dir.exists() {
+        fs::remove_dir_all(&compact_dir)?;
+    }
+    fs::create_dir(&compact_dir)?;
+    let compact_file = compact_dir.join(DATA_FILE_NAME);
+
+    {
+        let db = builder.with_exclusive().open(path).map_err(|err| {
+            if is_busy(&err) {
+                CompactError::Busy
+            } else {
+                CompactError::Database(err)
+            }
+        })?;
+        copy_environment(&db, &compact_file)?;
+    }
+
+    fs::File::open(&compact_file)?.sync_all()?;
+    fs::rename(&compact_file, &data_file)?;
+    // the lock file describes the old data file.
+    match fs::remove_file(path.join(LOCK_FILE_NAME)) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    fs::File::open(path)?.sync_all()?;
+    fs::remove_dir(&compact_dir)?;
+
+    let size_after = disk_usage(&data_file)?;
+    Ok(CompactSummary {
+        size_before,
+        size_after,
+    })
+}
+
+/// Returns the disk space used by the file at `path`.
+///
+/// The data file is sparse, so its length is usually larger.
+pub(super) fn disk_usage(path: &Path) -> std::io::Result<u64> {
+    // blocks are always 512 bytes.
+    Ok(fs::metadata(path)?.blocks() * 512)
+}
+
+/// Returns true if the environment couldn't be locked because it's in use.
+pub(super) fn is_busy(err: &MdbxError) -> bool {
+    match err {
+        MdbxError::Busy => true,
+        // mdbx returns the errno of the failed lock.
+        MdbxError::Other(code) => {
+            std::io::Error::from_raw_os_error(*code).kind() == std::io::ErrorKind::WouldBlock
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::stream::StreamId;
+    use libmdbx::{Environment, NoWriteMap};
+    use tempfile::tempdir;
+
+    use crate::db::{
+        tables::{StreamState, StreamStateTable},
+        MdbxEnvironmentBuilder, MdbxEnvironmentExt, MdbxRWTransactionExt, MdbxTransactionExt,
+    };
+
+    use super::{compact_environment, CompactError};
+
+    fn builder() -> MdbxEnvironmentBuilder<NoWriteMap> {
+        Environment::<NoWriteMap>::builder().with_size_gib(0, 1)
+    }
+
+    #[test]
+    fn test_compact_environment() {
+        let path = tempdir().unwrap();
+        {
+            let db = builder().open(path.path()).unwrap();
+            let txn = db.begin_rw_txn().unwrap();
+            txn.ensure_table::<StreamStateTable>(None).unwrap();
+            let mut cursor = txn.open_cursor::<StreamStateTable>().unwrap();
+            for i in 0..10_000 {
+                let state = StreamState { sequence: Some(i) };
+                cursor.put(&StreamId::from_u64(i), &state).unwrap();
+            }
+            txn.commit().unwrap();
+
+            // delete most entries, leaving the live ones scattered.
+            let txn = db.begin_rw_txn().unwrap();
+            let mut cursor = txn.open_cursor::<StreamStateTable>().unwrap();
+            for i in (0..10_000).filter(|i| i % 100 != 0) {
+                cursor.seek_exact(&StreamId::from_u64(i)).unwrap();
+                cursor.del().unwrap();
+            }
+            txn.commit().unwrap();
+
+            // compaction needs exclusive access.
+            assert!(matches!(
+                compact_environment(builder(), path.path()),
+                Err(CompactError::Busy)
+            ));
+        }
+
+        let summary = compact_environment(builder(), path.path()).unwrap();
+        assert!(summary.size_after < summary.size_before);
+
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+        let txn = db.begin_ro_txn().unwrap();
+        let table = txn.open_table::<StreamStateTable>().unwrap();
+        for i in 0..10_000 {
+            let state = table.get(&StreamId::from_u64(i)).unwrap();
+            if i % 100 == 0 {
+                assert_eq!(state.unwrap().sequence, Some(i));
+            } else {
+                assert!(state.is_none());
+            }
+        }
+        txn.commit().unwrap();
+    }
+}
@@ -0,0 +1,715 @@
+//! Scheduled database compaction.
+//!
+//! MDBX never moves live pages, so a database that saw many deletions and
+//! updates ends up with live data scattered over the whole file. Inside the
+//! configured time window, the maintenance task writes a compacting copy of
+//! the database next to the data file, while the node keeps ingesting and
+//! serving data. The copy is rate limited, so that it doesn't compete with
+//! ingestion and streaming for disk bandwidth.
+//!
+//! The data file can't be replaced under an open environment, so the copy is
+//! swapped in the next time the database is opened. Blocks ingested after
+//! the copy was taken are ingested again, and tables that can't be rebuilt
+//! from the chain are carried over from the replaced data file.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use libmdbx::{
+    Database, DatabaseFlags, Environment, EnvironmentKind, Error as MdbxError, Transaction,
+    TransactionKind, WriteFlags,
+};
+use serde::{Serialize, Serializer};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use super::{
+    compact::{
+        disk_usage, is_busy, CompactError, CompactSummary, COMPACT_DIR_NAME, LOCK_FILE_NAME,
+    },
+    mdbx::{MdbxEnvironmentBuilder, MdbxEnvironmentExt, MdbxGeometry},
+    DATA_FILE_NAME,
+};
+
+/// How often the scheduler checks if the maintenance window is open.
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The copy reports progress and waits for the rate limit every time this
+/// many bytes are copied.
+const COPY_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Entries are written to the copy in transactions of this many bytes.
+const COPY_BATCH_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Name of the file marking the compacted copy as complete.
+const READY_FILE_NAME: &str = "ready";
+
+/// Older copies are discarded instead of swapped in, since the node would
+/// need to ingest again all blocks written after them.
+const MAX_COPY_AGE: Duration = Duration::from_secs(2 * 24 * 60 * 60);
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MaintenanceWindowError {
+    #[error("maintenance window must have format HH:MM-HH:MM")]
+    InvalidFormat,
+    #[error("invalid time {0}")]
+    InvalidTime(String),
+}
+
+/// A daily time window, in UTC.
+///
+/// Windows that end before they start wrap around midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    /// Start of the window, in minutes since midnight.
+    start: u32,
+    /// End of the window, in minutes since midnight.
+    end: u32,
+}
+
+/// Configuration of the maintenance task.
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceConfig {
+    /// The database directory.
+    pub path: PathBuf,
+    /// Geometry of the compacted copy.
+    #[serde(skip)]
+    pub geometry: MdbxGeometry,
+    /// Maintenance only runs inside this window.
+    pub window: MaintenanceWindow,
+    /// Maximum number of bytes written per second by the compacting copy.
+    pub max_bytes_per_second: u64,
+}
+
+/// Progress of a compacting copy.
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceProgress {
+    /// Disk space used by the data file, in bytes.
+    pub size_before: u64,
+    /// Number of bytes of the copy written so far.
+    pub bytes_copied: u64,
+}
+
+/// Status of the maintenance task.
+#[derive(Debug, Clone, Default)]
+pub enum MaintenanceStatus {
+    /// No compaction was started.
+    #[default]
+    Idle,
+    /// Writing the compacted copy.
+    Copying { progress: MaintenanceProgress },
+    /// The window closed before the copy completed. The copy starts over in
+    /// the next window.
+    Interrupted { progress: MaintenanceProgress },
+    /// The compacted copy is complete, and is swapped in the next time the
+    /// node starts.
+    Ready {
+        progress: MaintenanceProgress,
+        elapsed: Duration,
+    },
+    /// Compaction failed.
+    Failed { error: String },
+}
+
+/// Runs compaction in the configured window and keeps track of its status.
+pub struct MaintenanceService<E: EnvironmentKind> {
+    db: Arc<Environment<E>>,
+    config: MaintenanceConfig,
+    status: Arc<Mutex<MaintenanceStatus>>,
+}
+
+impl MaintenanceWindow {
+    /// Creates a new window between the given times, in minutes since midnight.
+    pub fn new(start: u32, end: u32) -> Self {
+        MaintenanceWindow {
+            start: start % MINUTES_PER_DAY,
+            end: end % MINUTES_PER_DAY,
+        }
+    }
+
+    /// Returns true if the given time, in minutes since midnight, is inside
+    /// the window.
+    pub fn contains(&self, minute: u32) -> bool {
+        if self.start <= self.end {
+            self.start <= minute && minute < self.end
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// Returns true if the current time is inside the window.
+    pub fn is_open(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let minute = (now.as_secs() / 60) % MINUTES_PER_DAY as u64;
+        self.contains(minute as u32)
+    }
+}
+
+impl FromStr for MaintenanceWindow {
+    type Err = MaintenanceWindowError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or(MaintenanceWindowError::InvalidFormat)?;
+        Ok(MaintenanceWindow::new(parse_time(start)?, parse_time(end)?))
+    }
+}
+
+impl fmt::Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start / 60,
+            self.start % 60,
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+impl Serialize for MaintenanceWindow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn parse_time(s: &str) -> Result<u32, MaintenanceWindowError> {
+    let invalid = || MaintenanceWindowError::InvalidTime(s.to_string());
+    let (hours, minutes) = s.trim().split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+impl<E: EnvironmentKind> MaintenanceService<E> {
+    pub fn new(db: Arc<Environment<E>>, config: MaintenanceConfig) -> Self {
+        MaintenanceService {
+            db,
+            config,
+            status: Arc::new(Mutex::new(MaintenanceStatus::Idle)),
+        }
+    }
+
+    /// Returns the status of the most recent compaction.
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.lock().expect("maintenance status lock").clone()
+    }
+
+    /// Writes one compacted copy per window, until cancelled.
+    pub async fn start(self: Arc<Self>, ct: CancellationToken) {
+        info!(window = %self.config.window, "starting maintenance scheduler");
+        // only one copy per window.
+        let mut completed_in_window = false;
+
+        loop {
+            if !self.config.window.is_open() {
+                completed_in_window = false;
+            } else if !completed_in_window {
+                let started_at = Instant::now();
+                let service = self.clone();
+                let task_ct = ct.clone();
+                let result =
+                    tokio::task::spawn_blocking(move || service.write_copy(&task_ct)).await;
+
+                let progress = self.progress();
+                match result {
+                    Ok(Ok(())) => {
+                        info!(progress = ?progress, "compacted copy ready");
+                        self.set_status(MaintenanceStatus::Ready {
+                            progress,
+                            elapsed: started_at.elapsed(),
+                        });
+                        completed_in_window = true;
+                    }
+                    Ok(Err(CompactError::Interrupted)) => {
+                        info!(progress = ?progress, "compaction interrupted");
+                        self.set_status(MaintenanceStatus::Interrupted { progress });
+                    }
+                    Ok(Err(err)) => {
+                        error!(error = ?err, "compaction failed");
+                        self.set_status(MaintenanceStatus::Failed {
+                            error: err.to_string(),
+                        });
+                        completed_in_window = true;
+                    }
+                    Err(err) => {
+                        error!(error = ?err, "maintenance task panicked");
+                        return;
+                    }
+                }
+            }
+
+            tokio::select! {
+                _ = ct.cancelled() => break,
+                _ = tokio::time::sleep(SCHEDULER_INTERVAL) => {},
+            }
+        }
+    }
+
+    /// Writes the compacted copy to the compaction directory, stopping if
+    /// the window closes or the task is cancelled.
+    fn write_copy(&self, ct: &CancellationToken) -> Result<(), CompactError> {
+        let path = &self.config.path;
+        let compact_dir = path.join(COMPACT_DIR_NAME);
+        // an older copy is replaced by the new one.
+        if compact_dir.exists() {
+            fs::remove_dir_all(&compact_dir)?;
+        }
+        fs::create_dir(&compact_dir)?;
+
+        let size_before = disk_usage(&path.join(DATA_FILE_NAME))?;
+        self.set_status(MaintenanceStatus::Copying {
+            progress: MaintenanceProgress {
+                size_before,
+                bytes_copied: 0,
+            },
+        });
+
+        let result = copy_environment_throttled(
+            &self.db,
+            Environment::<E>::builder().with_geometry(&self.config.geometry),
+            &compact_dir,
+            self.config.max_bytes_per_second,
+            |bytes_copied| {
+                self.set_status(MaintenanceStatus::Copying {
+                    progress: MaintenanceProgress {
+                        size_before,
+                        bytes_copied,
+                    },
+                });
+                !ct.is_cancelled() && self.config.window.is_open()
+            },
+        );
+        if let Err(err) = result {
+            fs::remove_dir_all(&compact_dir)?;
+            return Err(err);
+        }
+
+        // the copy is only swapped in once marked as complete.
+        fs::File::create(compact_dir.join(READY_FILE_NAME))?.sync_all()?;
+        fs::File::open(&compact_dir)?.sync_all()?;
+        Ok(())
+    }
+
+    fn progress(&self) -> MaintenanceProgress {
+        match self.status() {
+            MaintenanceStatus::Copying { progress } => progress,
+            _ => MaintenanceProgress::default(),
+        }
+    }
+
+    fn set_status(&self, status: MaintenanceStatus) {
+        *self.status.lock().expect("maintenance status lock") = status;
+    }
+}
+
+/// Writes a compacted copy of `db` to a new environment in the `path`
+/// directory, opened with `builder`, at most `max_bytes_per_second` bytes
+/// per second.
+///
+/// Tables are copied entry by entry in key order, so the copy has no free
+/// pages and its pages are filled completely. `on_progress` is called with the
+/// number of bytes copied so far, and the copy stops with
+/// [CompactError::Interrupted] once it returns false. Returns the number of
+/// bytes copied.
+pub fn copy_environment_throttled<E: EnvironmentKind>(
+    db: &Environment<E>,
+    builder: MdbxEnvironmentBuilder<E>,
+    path: &Path,
+    max_bytes_per_second: u64,
+    on_progress: impl FnMut(u64) -> bool,
+) -> Result<u64, CompactError> {
+    let compacted = builder.open(path)?;
+    // all tables are copied from the same snapshot.
+    let from_txn = db.begin_ro_txn()?;
+    let mut throttle = Throttle::new(max_bytes_per_second, on_progress);
+    for name in table_names(&from_txn)? {
+        copy_table(&from_txn, &compacted, &name, &mut throttle)?;
+    }
+    throttle.report()?;
+    Ok(throttle.bytes)
+}
+
+/// Paces a copy and reports its progress.
+struct Throttle<F> {
+    started_at: Instant,
+    max_bytes_per_second: u64,
+    bytes: u64,
+    reported: u64,
+    on_progress: F,
+}
+
+impl<F: FnMut(u64) -> bool> Throttle<F> {
+    fn new(max_bytes_per_second: u64, on_progress: F) -> Self {
+        Throttle {
+            started_at: Instant::now(),
+            max_bytes_per_second: max_bytes_per_second.max(1),
+            bytes: 0,
+            reported: 0,
+            on_progress,
+        }
+    }
+
+    fn add(&mut self, bytes: usize) -> Result<(), CompactError> {
+        self.bytes += bytes as u64;
+        if self.bytes - self.reported >= COPY_CHUNK_SIZE {
+            self.report()?;
+        }
+        Ok(())
+    }
+
+    fn report(&mut self) -> Result<(), CompactError> {
+        self.reported = self.bytes;
+        if !(self.on_progress)(self.bytes) {
+            return Err(CompactError::Interrupted);
+        }
+
+        // rate limit by waiting until the bytes copied took their share of time.
+        let min_elapsed =
+            Duration::from_secs_f64(self.bytes as f64 / self.max_bytes_per_second as f64);
+        let elapsed = self.started_at.elapsed();
+        if elapsed < min_elapsed {
+            std::thread::sleep(min_elapsed - elapsed);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the names of all tables in the environment.
+fn table_names<K: TransactionKind, E: EnvironmentKind>(
+    txn: &Transaction<'_, K, E>,
+) -> Result<Vec<String>, MdbxError> {
+    // the main table maps table names to their root.
+    let main = txn.open_db(None)?;
+    let mut cursor = txn.cursor(&main)?;
+    let mut names = Vec::new();
+    let mut item = cursor.first::<Vec<u8>, Vec<u8>>()?;
+    while let Some((name, _)) = item {
+        names.push(String::from_utf8(name).map_err(|_| MdbxError::Corrupted)?);
+        item = cursor.next::<Vec<u8>, Vec<u8>>()?;
+    }
+    Ok(names)
+}
+
+/// Returns the flags of the `db` table.
+fn table_flags<K: TransactionKind, E: EnvironmentKind>(
+    txn: &Transaction<'_, K, E>,
+    db: &Database<'_>,
+) -> Result<DatabaseFlags, MdbxError> {
+    let mut flags = 0;
+    let mut state = 0;
+    // safety: the transaction pointer is valid for the lifetime of `txn`.
+    // `Transaction::db_flags` can't be used since it passes a null state,
+    // which mdbx rejects.
+    let code = unsafe { mdbx_sys::mdbx_dbi_flags_ex(txn.txn(), db.dbi(), &mut flags, &mut state) };
+    if code != mdbx_sys::MDBX_SUCCESS {
+        return Err(MdbxError::from_err_code(code));
+    }
+    Ok(DatabaseFlags::from_bits_truncate(flags))
+}
+
+/// Replaces the content of the `name` table in `to` with its content in
+/// `from_txn`, committing every [COPY_BATCH_SIZE] bytes.
+fn copy_table<K: TransactionKind, E: EnvironmentKind>(
+    from_txn: &Transaction<'_, K, E>,
+    to: &Environment<E>,
+    name: &str,
+    throttle: &mut Throttle<impl FnMut(u64) -> bool>,
+) -> Result<(), CompactError> {
+    let from_db = match from_txn.open_db(Some(name)) {
+        Ok(db) => db,
+        Err(MdbxError::NotFound) => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    let flags = table_flags(from_txn, &from_db)?;
+    // entries are read in order, so they can be appended.
+    let write_flags = if flags.contains(DatabaseFlags::DUP_SORT) {
+        WriteFlags::APPEND | WriteFlags::APPEND_DUP
+    } else {
+        WriteFlags::APPEND
+    };
+
+    let mut cursor = from_txn.cursor(&from_db)?;
+    let mut item = cursor.first::<Vec<u8>, Vec<u8>>()?;
+    let mut clear = true;
+    loop {
+        let to_txn = to.begin_rw_txn()?;
+        let to_db = to_txn.create_db(Some(name), flags)?;
+        if clear {
+            to_txn.clear_db(&to_db)?;
+            clear = false;
+        }
+        let mut batch = 0;
+        while let Some((key, value)) = item.take() {
+            to_txn.put(&to_db, &key, &value, write_flags)?;
+            let bytes = key.len() + value.len();
+            batch += bytes as u64;
+            throttle.add(bytes)?;
+            item = cursor.next::<Vec<u8>, Vec<u8>>()?;
+            if batch >= COPY_BATCH_SIZE {
+                break;
+            }
+        }
+        to_txn.commit()?;
+        if item.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+/// Replaces the data file of the environment in the `path` directory with
+/// the compacted copy written by the maintenance task, if any.
+///
+/// The `carry_over` tables are copied from the replaced data file, since
+/// their content written after the copy was taken would be lost otherwise.
+/// Must be called before the environment is opened.
+pub fn swap_compacted_environment<E: EnvironmentKind>(
+    path: &Path,
+    geometry: &MdbxGeometry,
+    carry_over: &[&str],
+) -> Result<Option<CompactSummary>, CompactError> {
+    let compact_dir = path.join(COMPACT_DIR_NAME);
+    if !compact_dir.exists() {
+        return Ok(None);
+    }
+
+    let age = fs::metadata(compact_dir.join(READY_FILE_NAME))
+        .and_then(|ready| ready.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    match age {
+        None => {
+            // leftovers of an interrupted copy.
+            fs::remove_dir_all(&compact_dir)?;
+            return Ok(None);
+        }
+        Some(age) if age > MAX_COPY_AGE => {
+            warn!(age = ?age, "discarding outdated compacted copy");
+            fs::remove_dir_all(&compact_dir)?;
+            return Ok(None);
+        }
+        Some(_) => {}
+    }
+
+    let data_file = path.join(DATA_FILE_NAME);
+    let size_before = disk_usage(&data_file)?;
+
+    if !carry_over.is_empty() {
+        let open = |path: &Path| {
+            Environment::<E>::builder()
+                .with_geometry(geometry)
+                .with_exclusive()
+                .open(path)
+                .map_err(|err| {
+                    if is_busy(&err) {
+                        CompactError::Busy
+                    } else {
+                        CompactError::Database(err)
+                    }
+                })
+        };
+        let db = open(path)?;
+        let compacted = open(&compact_dir)?;
+        carry_over_tables(&db, &compacted, carry_over)?;
+    }
+
+    let compact_file = compact_dir.join(DATA_FILE_NAME);
+    fs::File::open(&compact_file)?.sync_all()?;
+    fs::rename(&compact_file, &data_file)?;
+    // the lock file describes the old data file.
+    match fs::remove_file(path.join(LOCK_FILE_NAME)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    fs::File::open(path)?.sync_all()?;
+    fs::remove_dir_all(&compact_dir)?;
+
+    let size_after = disk_usage(&data_file)?;
+    Ok(Some(CompactSummary {
+        size_before,
+        size_after,
+    }))
+}
+
+/// Replaces the content of the given tables in `to` with their content in
+/// `from`.
+fn carry_over_tables<E: EnvironmentKind>(
+    from: &Environment<E>,
+    to: &Environment<E>,
+    tables: &[&str],
+) -> Result<(), CompactError> {
+    let from_txn = from.begin_ro_txn()?;
+    let mut throttle = Throttle::new(u64::MAX, |_| true);
+    for name in tables {
+        copy_table(&from_txn, to, name, &mut throttle)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::stream::{Sequence, StreamId};
+    use libmdbx::{DatabaseFlags, Environment, NoWriteMap, WriteFlags};
+    use tempfile::tempdir;
+
+    use crate::db::{
+        tables::{SequencerState, SequencerStateTable, StreamState, StreamStateTable},
+        MdbxEnvironmentBuilder, MdbxEnvironmentExt, MdbxGeometry, MdbxRWTransactionExt,
+        MdbxTransactionExt, Table,
+    };
+
+    use super::{
+        copy_environment_throttled, swap_compacted_environment, CompactError, MaintenanceWindow,
+        COMPACT_DIR_NAME, READY_FILE_NAME,
+    };
+
+    #[test]
+    fn test_maintenance_window() {
+        let window: MaintenanceWindow = "02:00-05:30".parse().unwrap();
+        assert!(!window.contains(60));
+        assert!(window.contains(120));
+        assert!(window.contains(329));
+        assert!(!window.contains(330));
+        assert_eq!(window.to_string(), "02:00-05:30");
+
+        let window: MaintenanceWindow = "23:00-01:00".parse().unwrap();
+        assert!(window.contains(23 * 60 + 30));
+        assert!(window.contains(30));
+        assert!(!window.contains(120));
+
+        assert!("02:00".parse::<MaintenanceWindow>().is_err());
+        assert!("25:00-01:00".parse::<MaintenanceWindow>().is_err());
+    }
+
+    fn builder() -> MdbxEnvironmentBuilder<NoWriteMap> {
+        Environment::<NoWriteMap>::builder().with_size_gib(0, 1)
+    }
+
+    fn sequencer_key() -> (StreamId, Sequence) {
+        (StreamId::from_u64(0), Sequence::from_u64(0))
+    }
+
+    fn write_stream_states(db: &Environment<NoWriteMap>, count: u64) {
+        let txn = db.begin_rw_txn().unwrap();
+        txn.ensure_table::<StreamStateTable>(None).unwrap();
+        let mut cursor = txn.open_cursor::<StreamStateTable>().unwrap();
+        for i in 0..count {
+            let state = StreamState { sequence: Some(i) };
+            cursor.put(&StreamId::from_u64(i), &state).unwrap();
+        }
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_copy_and_swap() {
+        let path = tempdir().unwrap();
+        let compact_dir = path.path().join(COMPACT_DIR_NAME);
+        {
+            let db = builder().open(path.path()).unwrap();
+            let txn = db.begin_rw_txn().unwrap();
+            let table = txn.create_db(Some("Dup"), DatabaseFlags::DUP_SORT).unwrap();
+            for value in [b"a", b"b", b"c"] {
+                txn.put(&table, b"key", value, WriteFlags::default())
+                    .unwrap();
+            }
+            txn.commit().unwrap();
+        }
+        {
+            let db = builder().open(path.path()).unwrap();
+            write_stream_states(&db, 10_000);
+
+            // delete most entries, leaving the live ones scattered.
+            let txn = db.begin_rw_txn().unwrap();
+            let mut cursor = txn.open_cursor::<StreamStateTable>().unwrap();
+            for i in (0..10_000).filter(|i| i % 100 != 0) {
+                cursor.seek_exact(&StreamId::from_u64(i)).unwrap();
+                cursor.del().unwrap();
+            }
+            txn.commit().unwrap();
+
+            std::fs::create_dir(&compact_dir).unwrap();
+            let mut reported = 0;
+            let size =
+                copy_environment_throttled(&db, builder(), &compact_dir, u64::MAX, |bytes| {
+                    reported = bytes;
+                    true
+                })
+                .unwrap();
+            assert_eq!(reported, size);
+            std::fs::File::create(compact_dir.join(READY_FILE_NAME)).unwrap();
+
+            // written after the copy, and carried over.
+            let txn = db.begin_rw_txn().unwrap();
+            txn.ensure_table::<SequencerStateTable>(None).unwrap();
+            let mut cursor = txn.open_cursor::<SequencerStateTable>().unwrap();
+            let state = SequencerState {
+                output_sequence_start: Some(42),
+                output_sequence_end: None,
+            };
+            cursor.put(&sequencer_key(), &state).unwrap();
+            txn.commit().unwrap();
+        }
+
+        let summary = swap_compacted_environment::<NoWriteMap>(
+            path.path(),
+            &MdbxGeometry::default(),
+            &[SequencerStateTable::db_name()],
+        )
+        .unwrap()
+        .unwrap();
+        assert!(summary.size_after < summary.size_before);
+        assert!(!compact_dir.exists());
+
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+        let txn = db.begin_ro_txn().unwrap();
+        let table = txn.open_table::<StreamStateTable>().unwrap();
+        for i in 0..10_000 {
+            let state = table.get(&StreamId::from_u64(i)).unwrap();
+            if i % 100 == 0 {
+                assert_eq!(state.unwrap().sequence, Some(i));
+            } else {
+                assert!(state.is_none());
+            }
+        }
+        let table = txn.open_table::<SequencerStateTable>().unwrap();
+        let state = table.get(&sequencer_key()).unwrap().unwrap();
+        assert_eq!(state.output_sequence_start, Some(42));
+        let table = txn.open_db(Some("Dup")).unwrap();
+        assert_eq!(txn.db_stat(&table).unwrap().entries(), 3);
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_interrupted_copy_is_discarded() {
+        let path = tempdir().unwrap();
+        let compact_dir = path.path().join(COMPACT_DIR_NAME);
+        let db = builder().open(path.path()).unwrap();
+        write_stream_states(&db, 10_000);
+
+        std::fs::create_dir(&compact_dir).unwrap();
+        let result = copy_environment_throttled(&db, builder(), &compact_dir, u64::MAX, |_| false);
+        assert!(matches!(result, Err(CompactError::Interrupted)));
+        drop(db);
+
+        // not marked as ready, so it's removed instead of swapped in.
+        let summary =
+            swap_compacted_environment::<NoWriteMap>(path.path(), &MdbxGeometry::default(), &[])
+                .unwrap();
+        assert!(summary.is_none());
+        assert!(!compact_dir.exists());
+    }
+}
@@ -1,11 +1,13 @@
-use std::{marker::PhantomData, ops::Range, path::Path};
+use std::{borrow::Cow, marker::PhantomData, ops::Range, path::Path};
 
 use apibara_core::stream::{MessageData, RawMessageData};
 use libmdbx::{
-    Cursor, Database, DatabaseFlags, Environment, EnvironmentBuilder, EnvironmentKind,
-    Error as MdbxError, Geometry, TableObject, Transaction, TransactionKind, WriteFlags, RW,
+    Cursor, Database, DatabaseFlags, Environment, EnvironmentBuilder, EnvironmentFlags,
+    EnvironmentKind, Error as MdbxError, Geometry, TableObject, Transaction, TransactionKind,
+    WriteFlags, RW,
 };
-use prost::Message;
+use prost::{bytes::Bytes, Message};
+use serde::Serialize;
 
 use super::{
     table::{Table, TableKey},
@@ -37,6 +39,9 @@ where
 /// Result value of any mdbx operation.
 pub type MdbxResult<T> = Result<T, MdbxError>;
 
+/// Maximum number of tables in an environment.
+const MAX_DBS: usize = 100;
+
 /// Configure and open a mdbx environment.
 pub struct MdbxEnvironmentBuilder<E: EnvironmentKind> {
     env: EnvironmentBuilder<E>,
@@ -44,6 +49,19 @@ pub struct MdbxEnvironmentBuilder<E: EnvironmentKind> {
     geometry: Geometry<Range<usize>>,
 }
 
+/// The mdbx map geometry.
+#[derive(Debug, Clone, Serialize)]
+pub struct MdbxGeometry {
+    /// Lower bound of the database size, in GiB.
+    pub min_size_gib: usize,
+    /// Upper bound of the database size, in GiB.
+    pub max_size_gib: usize,
+    /// The database grows by this amount, in GiB, when full.
+    pub growth_step_gib: usize,
+    /// The database shrinks when this amount of space, in GiB, is unused.
+    pub shrink_threshold_gib: Option<usize>,
+}
+
 /// Extension methods over mdbx environment.
 pub trait MdbxEnvironmentExt<E: EnvironmentKind> {
     /// Open a mdbx environment with the default configuration.
@@ -51,6 +69,16 @@ pub trait MdbxEnvironmentExt<E: EnvironmentKind> {
 
     /// Creates a new mdbx environment builder.
     fn builder() -> MdbxEnvironmentBuilder<E>;
+
+    /// Returns the upper bound of the database size, in bytes.
+    fn max_size(&self) -> MdbxResult<usize>;
+
+    /// Changes the upper bound of the database size to `max_size` bytes.
+    ///
+    /// Environments are opened with `MDBX_NOTLS`, so mdbx never moves the
+    /// mapping and growing only succeeds if it can be extended in place.
+    /// Must not be called from a thread with an open transaction.
+    fn set_max_size(&self, max_size: usize) -> MdbxResult<()>;
 }
 
 /// Extension methods over mdbx RO and RW transactions.
@@ -73,32 +101,40 @@ pub trait MdbxRWTransactionExt {
 impl<E: EnvironmentKind> MdbxEnvironmentExt<E> for Environment<E> {
     fn open(path: &Path) -> MdbxResult<Environment<E>> {
         let mut builder = Environment::new();
-        builder.set_max_dbs(16);
+        builder.set_max_dbs(MAX_DBS);
         builder.open(path)
     }
 
     fn builder() -> MdbxEnvironmentBuilder<E> {
         MdbxEnvironmentBuilder::new()
     }
+
+    fn max_size(&self) -> MdbxResult<usize> {
+        // mdbx maps the whole upper bound.
+        Ok(self.info()?.map_size())
+    }
+
+    fn set_max_size(&self, max_size: usize) -> MdbxResult<()> {
+        let max_size = isize::try_from(max_size).map_err(|_| MdbxError::Invalid)?;
+        // safety: the environment pointer is valid for the lifetime of
+        // `self`. negative values keep the current settings.
+        let code =
+            unsafe { mdbx_sys::mdbx_env_set_geometry(self.env(), -1, -1, max_size, -1, -1, -1) };
+        if code != mdbx_sys::MDBX_SUCCESS {
+            return Err(MdbxError::from_err_code(code));
+        }
+        Ok(())
+    }
 }
 
 impl<E: EnvironmentKind> MdbxEnvironmentBuilder<E> {
     /// Create a new environment builder.
     pub fn new() -> MdbxEnvironmentBuilder<E> {
         let env = Environment::new();
-        // set reasonable default geometry.
-        let min_size = byte_unit::n_gib_bytes!(10) as usize;
-        let max_size = byte_unit::n_gib_bytes!(100) as usize;
-        let growth_step = byte_unit::n_gib_bytes(2) as isize;
-        let geometry = Geometry {
-            size: Some(min_size..max_size),
-            growth_step: Some(growth_step),
-            shrink_threshold: None,
-            page_size: None,
-        };
+        let geometry = MdbxGeometry::default().to_geometry();
         MdbxEnvironmentBuilder {
             env,
-            max_dbs: 100,
+            max_dbs: MAX_DBS,
             geometry,
         }
     }
@@ -118,6 +154,30 @@ impl<E: EnvironmentKind> MdbxEnvironmentBuilder<E> {
         self
     }
 
+    /// Change the database shrink threshold in GiB.
+    pub fn with_shrink_threshold_gib(mut self, threshold: usize) -> Self {
+        let threshold = byte_unit::n_gib_bytes(threshold as u128) as isize;
+        self.geometry.shrink_threshold = Some(threshold);
+        self
+    }
+
+    /// Change the database geometry.
+    pub fn with_geometry(mut self, geometry: &MdbxGeometry) -> Self {
+        self.geometry = geometry.to_geometry();
+        self
+    }
+
+    /// Open the environment with exclusive access.
+    ///
+    /// Opening fails with [MdbxError::Busy] if another process uses it.
+    pub fn with_exclusive(mut self) -> Self {
+        self.env.set_flags(EnvironmentFlags {
+            exclusive: true,
+            ..Default::default()
+        });
+        self
+    }
+
     /// Open the environment.
     pub fn open(mut self, path: &Path) -> MdbxResult<Environment<E>> {
         self.env
@@ -127,6 +187,38 @@ impl<E: EnvironmentKind> MdbxEnvironmentBuilder<E> {
     }
 }
 
+impl MdbxGeometry {
+    fn to_geometry(&self) -> Geometry<Range<usize>> {
+        let min_size = byte_unit::n_gib_bytes(self.min_size_gib as u128) as usize;
+        let max_size = byte_unit::n_gib_bytes(self.max_size_gib as u128) as usize;
+        let growth_step = byte_unit::n_gib_bytes(self.growth_step_gib as u128) as isize;
+        let shrink_threshold = self
+            .shrink_threshold_gib
+            .map(|threshold| byte_unit::n_gib_bytes(threshold as u128) as isize);
+        Geometry {
+            size: Some(min_size..max_size),
+            growth_step: Some(growth_step),
+            shrink_threshold,
+            page_size: None,
+        }
+    }
+}
+
+impl Default for MdbxGeometry {
+    fn default() -> Self {
+        // mdbx only reserves address space for the upper bound, the file
+        // grows by `growth_step` as data is written. Use a large upper bound
+        // so that the database keeps growing instead of failing with a
+        // map full error.
+        MdbxGeometry {
+            min_size_gib: 10,
+            max_size_gib: 4096,
+            growth_step_gib: 2,
+            shrink_threshold_gib: None,
+        }
+    }
+}
+
 impl<E: EnvironmentKind> Default for MdbxEnvironmentBuilder<E> {
     fn default() -> Self {
         Self::new()
@@ -189,7 +281,11 @@ where
     where
         Self: Sized,
     {
-        Ok(Self(RawMessageData::from_vec(data_val.to_vec())))
+        // the value must outlive the transaction, so this is the only copy
+        // performed when reading a raw message.
+        Ok(Self(RawMessageData::from_bytes(Bytes::copy_from_slice(
+            data_val,
+        ))))
     }
 }
 
@@ -232,6 +328,16 @@ where
             .get::<TableObjectWrapper<_>>(&self.db, key.encode().as_ref())?;
         Ok(data.map(|d| d.0))
     }
+
+    /// Get the raw bytes of an item in the table by its `key`.
+    ///
+    /// The returned value borrows the memory-mapped page whenever possible,
+    /// so it's only valid for the lifetime of the transaction.
+    /// Values modified by the current (read-write) transaction are copied.
+    pub fn get_bytes(&self, key: &T::Key) -> MdbxResult<Option<Cow<'txn, [u8]>>> {
+        self.txn
+            .get::<Cow<'txn, [u8]>>(&self.db, key.encode().as_ref())
+    }
 }
 
 impl<'txn, T, K> TableCursor<'txn, T, K>
@@ -278,6 +384,18 @@ where
         raw_map_kv_result::<T>(self.cursor.set_key(key.encode().as_ref()))
     }
 
+    /// Position at the specified key and return a view over the raw value.
+    ///
+    /// The returned value borrows the memory-mapped page whenever possible,
+    /// so it's only valid for the lifetime of the transaction.
+    /// Values modified by the current (read-write) transaction are copied.
+    pub fn seek_exact_bytes(&mut self, key: &T::Key) -> MdbxResult<Option<Cow<'txn, [u8]>>> {
+        let data = self
+            .cursor
+            .set_key::<Cow<'txn, [u8]>, Cow<'txn, [u8]>>(key.encode().as_ref())?;
+        Ok(data.map(|(_, value)| value))
+    }
+
     /// Position at the first key greater than or equal to the specified key.
     pub fn seek_range(&mut self, key: &T::Key) -> MdbxResult<Option<(T::Key, T::Value)>> {
         map_kv_result::<T>(self.cursor.set_range(key.encode().as_ref()))
@@ -383,10 +501,100 @@ where
 
 pub trait MdbxErrorExt {
     fn decode_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> MdbxError;
+
+    /// Returns true if the error is caused by the database reaching its
+    /// maximum size.
+    fn is_map_full(&self) -> bool;
 }
 
 impl MdbxErrorExt for MdbxError {
     fn decode_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> MdbxError {
         MdbxError::DecodeError(Box::new(err))
     }
+
+    fn is_map_full(&self) -> bool {
+        matches!(self, MdbxError::MapFull)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use apibara_core::stream::StreamId;
+    use libmdbx::{Environment, Geometry, NoWriteMap};
+    use prost::Message;
+    use tempfile::tempdir;
+
+    use crate::db::{
+        tables::{StreamState, StreamStateTable},
+        MdbxEnvironmentExt, MdbxErrorExt, MdbxRWTransactionExt, MdbxTransactionExt,
+    };
+
+    const MIB: usize = 1024 * 1024;
+
+    #[test]
+    fn test_seek_exact_bytes_borrows_clean_pages() {
+        let path = tempdir().unwrap();
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+        let stream_id = StreamId::from_u64(1);
+        let value = StreamState { sequence: Some(42) };
+
+        let txn = db.begin_rw_txn().unwrap();
+        txn.ensure_table::<StreamStateTable>(None).unwrap();
+        let mut cursor = txn.open_cursor::<StreamStateTable>().unwrap();
+        cursor.put(&stream_id, &value).unwrap();
+        txn.commit().unwrap();
+
+        let txn = db.begin_ro_txn().unwrap();
+        let mut cursor = txn.open_cursor::<StreamStateTable>().unwrap();
+        let data = cursor.seek_exact_bytes(&stream_id).unwrap().unwrap();
+        assert!(matches!(data, Cow::Borrowed(_)));
+        assert_eq!(data.as_ref(), value.encode_to_vec().as_slice());
+        let missing = cursor.seek_exact_bytes(&StreamId::from_u64(2)).unwrap();
+        assert!(missing.is_none());
+
+        let table = txn.open_table::<StreamStateTable>().unwrap();
+        let data = table.get_bytes(&stream_id).unwrap().unwrap();
+        assert_eq!(StreamState::decode(data.as_ref()).unwrap(), value);
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_grow_full_database() {
+        let path = tempdir().unwrap();
+        let mut builder = Environment::<NoWriteMap>::new();
+        builder.set_max_dbs(16).set_geometry(Geometry {
+            size: Some(0..64 * MIB),
+            growth_step: Some(MIB as isize),
+            shrink_threshold: None,
+            page_size: None,
+        });
+        let db = builder.open(path.path()).unwrap();
+        let txn = db.begin_rw_txn().unwrap();
+        txn.ensure_table::<StreamStateTable>(None).unwrap();
+        txn.commit().unwrap();
+
+        // the mapping can only grow in place, so shrink it first to leave
+        // room after it.
+        db.set_max_size(MIB).unwrap();
+        assert_eq!(db.max_size().unwrap(), MIB);
+
+        let write = |db: &Environment<NoWriteMap>, count: u64| {
+            let txn = db.begin_rw_txn()?;
+            let mut cursor = txn.open_cursor::<StreamStateTable>()?;
+            for i in 0..count {
+                cursor.put(&StreamId::from_u64(i), &StreamState { sequence: Some(i) })?;
+            }
+            txn.commit()?;
+            Ok::<_, libmdbx::Error>(())
+        };
+
+        let err = write(&db, 100_000).unwrap_err();
+        assert!(err.is_map_full());
+
+        db.set_max_size(64 * MIB).unwrap();
+        assert_eq!(db.max_size().unwrap(), 64 * MIB);
+        write(&db, 100_000).unwrap();
+    }
 }
@@ -0,0 +1,298 @@
+//! Schema version tracking and migrations.
+//!
+//! The schema version is stored in the database together with the progress
+//! of the migration being applied. Migrations are applied in small steps,
+//! each in its own transaction, so that a migration interrupted by a crash or
+//! restart resumes from the last committed step.
+
+use libmdbx::{Environment, EnvironmentKind, Error as MdbxError, Transaction, RW};
+use prost::Message;
+use tracing::info;
+
+use super::{MdbxRWTransactionExt, MdbxTransactionExt, Table};
+
+/// Table with the database schema version.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SchemaVersionTable;
+
+/// The schema version, together with the progress of the running migration.
+///
+/// Mark version as optional to enforce serializing the `0` value.
+#[derive(Clone, PartialEq, Message)]
+pub struct SchemaVersion {
+    #[prost(fixed64, optional, tag = "1")]
+    pub version: Option<u64>,
+    /// Opaque checkpoint of the migration to `version + 1`.
+    #[prost(bytes, optional, tag = "2")]
+    pub checkpoint: Option<Vec<u8>>,
+    /// Number of steps of the migration to `version + 1` already applied.
+    #[prost(fixed64, tag = "3")]
+    pub steps: u64,
+}
+
+impl Table for SchemaVersionTable {
+    type Key = ();
+    type Value = SchemaVersion;
+
+    fn db_name() -> &'static str {
+        "SchemaVersion"
+    }
+}
+
+/// Result of applying one step of a migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationStep {
+    /// The migration has more work to do. The migration is resumed from the
+    /// given checkpoint.
+    Progress(Vec<u8>),
+    /// The migration is complete.
+    Done,
+}
+
+/// A migration from `version() - 1` to `version()`.
+pub trait Migration<E: EnvironmentKind>: Send + Sync {
+    /// The schema version after the migration is applied.
+    fn version(&self) -> u64;
+
+    /// A short description of the migration, used for logging.
+    fn description(&self) -> &'static str;
+
+    /// Apply one step of the migration.
+    ///
+    /// The step should perform a bounded amount of work, the runner commits
+    /// the transaction (and the returned checkpoint) after every step.
+    fn step(
+        &self,
+        txn: &Transaction<'_, RW, E>,
+        checkpoint: Option<&[u8]>,
+    ) -> Result<MigrationStep, MdbxError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("database operation failed")]
+    Database(#[from] MdbxError),
+    #[error("database schema version {stored} is newer than the supported version {latest}")]
+    UnsupportedVersion { stored: u64, latest: u64 },
+    #[error("migrations must be sorted by version without gaps")]
+    InvalidMigrations,
+}
+
+/// Applies migrations to bring the database to the latest schema version.
+pub struct MigrationRunner<E: EnvironmentKind> {
+    migrations: Vec<Box<dyn Migration<E>>>,
+}
+
+impl<E: EnvironmentKind> MigrationRunner<E> {
+    /// Creates a new runner without any migration.
+    pub fn new() -> Self {
+        MigrationRunner {
+            migrations: Vec::default(),
+        }
+    }
+
+    /// Adds a migration to the runner.
+    ///
+    /// Migrations must be added in order of version.
+    pub fn with_migration(mut self, migration: impl Migration<E> + 'static) -> Self {
+        self.migrations.push(Box::new(migration));
+        self
+    }
+
+    /// Returns the latest schema version.
+    pub fn latest_version(&self) -> u64 {
+        self.migrations.last().map(|m| m.version()).unwrap_or(0)
+    }
+
+    /// Returns the schema version stored in the database.
+    ///
+    /// Databases created before version tracking was introduced are at
+    /// version `0`.
+    pub fn current_version(&self, db: &Environment<E>) -> Result<u64, MigrationError> {
+        let txn = db.begin_rw_txn()?;
+        txn.ensure_table::<SchemaVersionTable>(None)?;
+        let state = txn.open_table::<SchemaVersionTable>()?.get(&())?;
+        txn.commit()?;
+        Ok(state.and_then(|s| s.version).unwrap_or(0))
+    }
+
+    /// Runs all pending migrations.
+    pub fn run(&self, db: &Environment<E>) -> Result<(), MigrationError> {
+        for (index, migration) in self.migrations.iter().enumerate() {
+            if migration.version() != index as u64 + 1 {
+                return Err(MigrationError::InvalidMigrations);
+            }
+        }
+
+        let latest = self.latest_version();
+        let stored = self.current_version(db)?;
+        if stored > latest {
+            return Err(MigrationError::UnsupportedVersion { stored, latest });
+        }
+
+        info!(version = %stored, latest = %latest, "database schema version");
+
+        for migration in self.migrations.iter().skip(stored as usize) {
+            self.run_migration(db, migration.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    fn run_migration(
+        &self,
+        db: &Environment<E>,
+        migration: &dyn Migration<E>,
+    ) -> Result<(), MigrationError> {
+        info!(
+            version = %migration.version(),
+            description = %migration.description(),
+            "start migration"
+        );
+
+        loop {
+            let txn = db.begin_rw_txn()?;
+            let mut cursor = txn.open_cursor::<SchemaVersionTable>()?;
+            let mut state = cursor.seek_exact(&())?.map(|t| t.1).unwrap_or_default();
+
+            let step = migration.step(&txn, state.checkpoint.as_deref())?;
+            state.steps += 1;
+
+            let done = match step {
+                MigrationStep::Progress(checkpoint) => {
+                    state.checkpoint = Some(checkpoint);
+                    false
+                }
+                MigrationStep::Done => {
+                    state.version = Some(migration.version());
+                    state.checkpoint = None;
+                    true
+                }
+            };
+
+            info!(
+                version = %migration.version(),
+                steps = %state.steps,
+                done = %done,
+                "migration progress"
+            );
+
+            if done {
+                // steps count the progress towards the next version.
+                state.steps = 0;
+            }
+
+            cursor.put(&(), &state)?;
+            txn.commit()?;
+
+            if done {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<E: EnvironmentKind> Default for MigrationRunner<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::stream::StreamId;
+    use libmdbx::{Environment, Error as MdbxError, NoWriteMap, Transaction, RW};
+    use tempfile::tempdir;
+
+    use crate::db::{
+        tables::{StreamState, StreamStateTable},
+        MdbxEnvironmentExt, MdbxRWTransactionExt, MdbxTransactionExt,
+    };
+
+    use super::{Migration, MigrationError, MigrationRunner, MigrationStep};
+
+    /// Adds one to the state of streams `0..4`, one stream per step.
+    struct IncrementStreams;
+
+    impl Migration<NoWriteMap> for IncrementStreams {
+        fn version(&self) -> u64 {
+            1
+        }
+
+        fn description(&self) -> &'static str {
+            "increment streams"
+        }
+
+        fn step(
+            &self,
+            txn: &Transaction<'_, RW, NoWriteMap>,
+            checkpoint: Option<&[u8]>,
+        ) -> Result<MigrationStep, MdbxError> {
+            let stream = checkpoint.map(|c| c[0] as u64).unwrap_or(0);
+            let mut cursor = txn.open_cursor::<StreamStateTable>()?;
+            let stream_id = StreamId::from_u64(stream);
+            let sequence = cursor
+                .seek_exact(&stream_id)?
+                .and_then(|t| t.1.sequence)
+                .unwrap_or(0);
+            let state = StreamState {
+                sequence: Some(sequence + 1),
+            };
+            cursor.put(&stream_id, &state)?;
+
+            if stream == 3 {
+                Ok(MigrationStep::Done)
+            } else {
+                Ok(MigrationStep::Progress(vec![stream as u8 + 1]))
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_migrations() {
+        let path = tempdir().unwrap();
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+        let txn = db.begin_rw_txn().unwrap();
+        txn.ensure_table::<StreamStateTable>(None).unwrap();
+        txn.commit().unwrap();
+
+        let runner = MigrationRunner::new().with_migration(IncrementStreams);
+        assert_eq!(runner.current_version(&db).unwrap(), 0);
+        runner.run(&db).unwrap();
+        assert_eq!(runner.current_version(&db).unwrap(), 1);
+
+        // running again is a no-op.
+        runner.run(&db).unwrap();
+
+        let txn = db.begin_ro_txn().unwrap();
+        let table = txn.open_table::<StreamStateTable>().unwrap();
+        for stream in 0..4 {
+            let state = table.get(&StreamId::from_u64(stream)).unwrap().unwrap();
+            assert_eq!(state.sequence, Some(1));
+        }
+        txn.commit().unwrap();
+    }
+
+    #[test]
+    fn test_refuse_newer_schema() {
+        let path = tempdir().unwrap();
+        let db = Environment::<NoWriteMap>::open(path.path()).unwrap();
+        let txn = db.begin_rw_txn().unwrap();
+        txn.ensure_table::<StreamStateTable>(None).unwrap();
+        txn.commit().unwrap();
+
+        MigrationRunner::new()
+            .with_migration(IncrementStreams)
+            .run(&db)
+            .unwrap();
+
+        let result = MigrationRunner::<NoWriteMap>::new().run(&db);
+        assert!(matches!(
+            result,
+            Err(MigrationError::UnsupportedVersion {
+                stored: 1,
+                latest: 0
+            })
+        ));
+    }
+}
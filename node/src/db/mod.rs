@@ -1,18 +1,32 @@
 //! # Node Database
 //!
 //! This module provides all the abstractions over storage.
+mod backup;
 mod chain_tracker;
 mod cli;
+mod compact;
+mod maintenance;
 mod mdbx;
 mod message_storage;
+mod migration;
 mod sequencer;
 mod table;
 
+pub use self::backup::{
+    backup_environment, copy_environment, BackupConfig, BackupError, BackupProgress, BackupService,
+    BackupStatus, BackupUploader, DATA_FILE_NAME,
+};
 pub use self::cli::default_data_dir;
+pub use self::compact::{compact_environment, CompactError, CompactSummary};
+pub use self::maintenance::{
+    copy_environment_throttled, swap_compacted_environment, MaintenanceConfig, MaintenanceProgress,
+    MaintenanceService, MaintenanceStatus, MaintenanceWindow, MaintenanceWindowError,
+};
 pub use self::mdbx::{
-    MdbxEnvironmentExt, MdbxErrorExt, MdbxRWTransactionExt, MdbxTable, MdbxTransactionExt,
-    TableCursor,
+    MdbxEnvironmentBuilder, MdbxEnvironmentExt, MdbxErrorExt, MdbxGeometry, MdbxRWTransactionExt,
+    MdbxTable, MdbxTransactionExt, TableCursor,
 };
+pub use self::migration::{Migration, MigrationError, MigrationRunner, MigrationStep};
 pub use self::table::{ByteVec, DupSortTable, KeyDecodeError, Table, TableKey};
 
 pub mod tables {
@@ -20,6 +34,7 @@ pub mod tables {
         Block, BlockHash, BlockTable, CanonicalBlock, CanonicalBlockTable,
     };
     pub use super::message_storage::MessageTable;
+    pub use super::migration::{SchemaVersion, SchemaVersionTable};
     pub use super::sequencer::{
         SequencerState, SequencerStateTable, StreamState, StreamStateTable,
     };
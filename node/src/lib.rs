@@ -1,3 +1,4 @@
+pub mod chain;
 pub mod core;
 pub mod db;
 pub mod message_storage;
@@ -1,7 +1,19 @@
+use std::sync::Arc;
+
 use crate::o11y::{self, Counter, KeyValue};
 use tonic::metadata::MetadataMap;
 use tracing::{debug_span, Span};
 
+/// Metadata used by clients to name their streams, for example after the
+/// indexer that opened them.
+pub const STREAM_NAME_METADATA_KEY: &str = "x-stream-name";
+
+/// Label of the stream name in metrics.
+pub(crate) const STREAM_NAME_LABEL: &str = "stream_name";
+
+/// Stream names longer than this are truncated.
+const MAX_STREAM_NAME_LEN: usize = 64;
+
 pub trait RequestObserver: Send + Sync + 'static {
     type Meter: RequestMeter;
 
@@ -23,6 +35,7 @@ pub struct SimpleRequestObserver {}
 
 /// A [RequestMeter] that adds no context.
 pub struct SimpleMeter {
+    metadata: Vec<KeyValue>,
     counter: Counter<u64>,
 }
 
@@ -41,8 +54,14 @@ pub struct MetadataKeyMeter {
 
 impl Default for SimpleMeter {
     fn default() -> Self {
+        SimpleMeter::new(Vec::default())
+    }
+}
+
+impl SimpleMeter {
+    pub fn new(metadata: Vec<KeyValue>) -> Self {
         let counter = new_data_out_counter();
-        SimpleMeter { counter }
+        SimpleMeter { metadata, counter }
     }
 }
 
@@ -62,32 +81,49 @@ impl MetadataKeyRequestObserver {
 impl RequestObserver for SimpleRequestObserver {
     type Meter = SimpleMeter;
 
-    fn stream_data_span(&self, _metadata: &MetadataMap) -> Span {
-        debug_span!("stream_data")
+    fn stream_data_span(&self, metadata: &MetadataMap) -> Span {
+        new_stream_data_span(metadata)
     }
 
-    fn stream_data_meter(&self, _metadata: &MetadataMap) -> Self::Meter {
-        SimpleMeter::default()
+    fn stream_data_meter(&self, metadata: &MetadataMap) -> Self::Meter {
+        let metadata = stream_name(metadata)
+            .map(|name| vec![KeyValue::new(STREAM_NAME_LABEL, name)])
+            .unwrap_or_default();
+        SimpleMeter::new(metadata)
+    }
+}
+
+/// Observers are shared by servers that stream the same data, like the gRPC
+/// and WebSocket servers.
+impl<O: RequestObserver> RequestObserver for Arc<O> {
+    type Meter = O::Meter;
+
+    fn stream_data_span(&self, metadata: &MetadataMap) -> Span {
+        self.as_ref().stream_data_span(metadata)
+    }
+
+    fn stream_data_meter(&self, metadata: &MetadataMap) -> Self::Meter {
+        self.as_ref().stream_data_meter(metadata)
     }
 }
 
 impl RequestMeter for SimpleMeter {
     fn increment_counter(&self, name: &'static str, amount: u64) {
         let cx = o11y::Context::current();
-        self.counter
-            .add(&cx, amount, &[KeyValue::new("datum", name)]);
+        let attributes = &[&[KeyValue::new("datum", name)], self.metadata.as_slice()].concat();
+        self.counter.add(&cx, amount, attributes);
     }
 }
 
 impl RequestObserver for MetadataKeyRequestObserver {
     type Meter = MetadataKeyMeter;
 
-    fn stream_data_span(&self, _metadata: &MetadataMap) -> Span {
-        debug_span!("stream_data")
+    fn stream_data_span(&self, metadata: &MetadataMap) -> Span {
+        new_stream_data_span(metadata)
     }
 
     fn stream_data_meter(&self, metadata: &MetadataMap) -> Self::Meter {
-        let mut result = Vec::with_capacity(self.keys.len());
+        let mut result = Vec::with_capacity(self.keys.len() + 1);
         for key in &self.keys {
             if let Some(value) = metadata.get(key) {
                 if let Ok(value) = value.to_str() {
@@ -95,6 +131,9 @@ impl RequestObserver for MetadataKeyRequestObserver {
                 }
             }
         }
+        if let Some(name) = stream_name(metadata) {
+            result.push(KeyValue::new(STREAM_NAME_LABEL, name));
+        }
         MetadataKeyMeter::new(result)
     }
 }
@@ -109,7 +148,59 @@ impl RequestMeter for MetadataKeyMeter {
     }
 }
 
-fn new_data_out_counter() -> Counter<u64> {
+/// Returns the stream name sent by the client, if any.
+///
+/// Names are truncated and stripped of non-printable characters, since
+/// they're used in metrics and logs.
+pub fn stream_name(metadata: &MetadataMap) -> Option<String> {
+    let name: String = metadata
+        .get(STREAM_NAME_METADATA_KEY)?
+        .to_str()
+        .ok()?
+        .chars()
+        .filter(char::is_ascii_graphic)
+        .take(MAX_STREAM_NAME_LEN)
+        .collect();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// Returns the span of a `stream_data` request, with the stream name if
+/// the client sent one.
+pub(crate) fn new_stream_data_span(metadata: &MetadataMap) -> Span {
+    match stream_name(metadata) {
+        None => debug_span!("stream_data"),
+        Some(name) => debug_span!("stream_data", stream_name = %name),
+    }
+}
+
+pub(crate) fn new_data_out_counter() -> Counter<u64> {
     let meter = o11y::meter("stream_data");
     meter.u64_counter("data_out").init()
 }
+
+#[cfg(test)]
+mod tests {
+    use tonic::metadata::MetadataMap;
+
+    use super::{stream_name, STREAM_NAME_METADATA_KEY};
+
+    #[test]
+    fn test_stream_name() {
+        let mut metadata = MetadataMap::new();
+        assert_eq!(stream_name(&metadata), None);
+
+        metadata.insert(STREAM_NAME_METADATA_KEY, "my indexer".parse().unwrap());
+        assert_eq!(stream_name(&metadata), Some("myindexer".to_string()));
+
+        let long = "a".repeat(100);
+        metadata.insert(STREAM_NAME_METADATA_KEY, long.parse().unwrap());
+        assert_eq!(stream_name(&metadata).unwrap().len(), 64);
+
+        metadata.insert(STREAM_NAME_METADATA_KEY, "  ".parse().unwrap());
+        assert_eq!(stream_name(&metadata), None);
+    }
+}
@@ -0,0 +1,321 @@
+//! Send request metrics to several backends.
+//!
+//! [MeteredRequestObserver] forwards the counters of each request to all
+//! configured [MeterBackend]s, together with the request metadata selected
+//! by the operator (for example the api key).
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use apibara_core::node::v1alpha2::{
+    usage_client::UsageClient, ReportUsageRequest, UsageAttribute, UsageCounter,
+};
+use prometheus::{Encoder, IntCounterVec, Opts, Registry, TextEncoder};
+use tokio_util::sync::CancellationToken;
+use tonic::{
+    metadata::MetadataMap,
+    transport::{Channel, Endpoint},
+};
+use tracing::{info, warn, Span};
+
+use crate::o11y::{self, Counter, KeyValue};
+
+use super::metadata::{
+    new_data_out_counter, new_stream_data_span, stream_name, RequestMeter, RequestObserver,
+    STREAM_NAME_LABEL,
+};
+
+/// Receives the counters of all requests.
+pub trait MeterBackend: Send + Sync + 'static {
+    /// Increments the counter `name` of a request with the given attributes.
+    fn increment_counter(&self, name: &'static str, amount: u64, attributes: &[KeyValue]);
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MeterBackendError {
+    #[error("invalid usage service url")]
+    InvalidServiceUrl(#[from] tonic::transport::Error),
+    #[error("error registering prometheus metrics")]
+    Prometheus(#[from] prometheus::Error),
+}
+
+/// A [RequestObserver] that sends counters to the configured backends.
+pub struct MeteredRequestObserver {
+    keys: Vec<String>,
+    backends: Vec<Arc<dyn MeterBackend>>,
+}
+
+/// A [RequestMeter] that sends counters to the configured backends.
+pub struct BackendMeter {
+    attributes: Vec<KeyValue>,
+    backends: Vec<Arc<dyn MeterBackend>>,
+}
+
+/// Exports counters with OpenTelemetry.
+pub struct OtlpMeterBackend {
+    counter: Counter<u64>,
+}
+
+/// Exports counters in the Prometheus text format.
+pub struct PrometheusMeterBackend {
+    registry: Registry,
+    counter: IntCounterVec,
+    keys: Vec<String>,
+}
+
+/// Writes counters as structured log events.
+#[derive(Debug, Default)]
+pub struct LogMeterBackend {}
+
+/// Reports counters to an external service implementing the `Usage` gRPC
+/// service.
+pub struct GrpcMeterBackend {
+    client: UsageClient<Channel>,
+    pending: Mutex<HashMap<CounterKey, u64>>,
+}
+
+type CounterKey = (&'static str, Vec<(String, String)>);
+
+impl MeteredRequestObserver {
+    /// Creates an observer that adds the values of the given metadata keys
+    /// to counters.
+    pub fn new(keys: Vec<String>) -> Self {
+        MeteredRequestObserver {
+            keys,
+            backends: Vec::default(),
+        }
+    }
+
+    /// Also send counters to the given backend.
+    pub fn with_backend(mut self, backend: Arc<dyn MeterBackend>) -> Self {
+        self.backends.push(backend);
+        self
+    }
+}
+
+impl RequestObserver for MeteredRequestObserver {
+    type Meter = BackendMeter;
+
+    fn stream_data_span(&self, metadata: &MetadataMap) -> Span {
+        new_stream_data_span(metadata)
+    }
+
+    fn stream_data_meter(&self, metadata: &MetadataMap) -> Self::Meter {
+        let attributes = self
+            .keys
+            .iter()
+            .filter_map(|key| {
+                let value = metadata.get(key)?.to_str().ok()?;
+                Some(KeyValue::new(key.clone(), value.to_owned()))
+            })
+            .chain(stream_name(metadata).map(|name| KeyValue::new(STREAM_NAME_LABEL, name)))
+            .collect();
+        BackendMeter {
+            attributes,
+            backends: self.backends.clone(),
+        }
+    }
+}
+
+impl RequestMeter for BackendMeter {
+    fn increment_counter(&self, name: &'static str, amount: u64) {
+        for backend in &self.backends {
+            backend.increment_counter(name, amount, &self.attributes);
+        }
+    }
+}
+
+impl Default for OtlpMeterBackend {
+    fn default() -> Self {
+        let counter = new_data_out_counter();
+        OtlpMeterBackend { counter }
+    }
+}
+
+impl MeterBackend for OtlpMeterBackend {
+    fn increment_counter(&self, name: &'static str, amount: u64, attributes: &[KeyValue]) {
+        let cx = o11y::Context::current();
+        let attributes = &[&[KeyValue::new("datum", name)], attributes].concat();
+        self.counter.add(&cx, amount, attributes);
+    }
+}
+
+impl PrometheusMeterBackend {
+    /// Creates a backend with one label for each of the given metadata keys,
+    /// plus the stream name.
+    pub fn new(keys: &[String]) -> Result<Self, MeterBackendError> {
+        let labels: Vec<_> = ["datum", STREAM_NAME_LABEL]
+            .into_iter()
+            .map(str::to_string)
+            .chain(keys.iter().map(|key| prometheus_label(key)))
+            .collect();
+        let labels: Vec<_> = labels.iter().map(String::as_str).collect();
+        let counter = IntCounterVec::new(
+            Opts::new("stream_data_out", "Data sent by stream_data requests."),
+            &labels,
+        )?;
+        let registry = Registry::new();
+        registry.register(Box::new(counter.clone()))?;
+        Ok(PrometheusMeterBackend {
+            registry,
+            counter,
+            keys: keys.to_vec(),
+        })
+    }
+
+    /// Returns the counters in the Prometheus text format.
+    pub fn encode(&self) -> Result<String, MeterBackendError> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
+
+impl MeterBackend for PrometheusMeterBackend {
+    fn increment_counter(&self, name: &'static str, amount: u64, attributes: &[KeyValue]) {
+        let mut values = Vec::with_capacity(self.keys.len() + 2);
+        values.push(name.to_string());
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.as_str().into_owned())
+                .unwrap_or_default()
+        };
+        values.push(attribute(STREAM_NAME_LABEL));
+        for key in &self.keys {
+            values.push(attribute(key));
+        }
+        let values: Vec<_> = values.iter().map(String::as_str).collect();
+        self.counter.with_label_values(&values).inc_by(amount);
+    }
+}
+
+impl MeterBackend for LogMeterBackend {
+    fn increment_counter(&self, name: &'static str, amount: u64, attributes: &[KeyValue]) {
+        let attributes = attributes
+            .iter()
+            .map(|kv| format!("{}={}", kv.key.as_str(), kv.value.as_str()))
+            .collect::<Vec<_>>()
+            .join(",");
+        info!(
+            target: "apibara_node::usage",
+            datum = %name,
+            amount = %amount,
+            attributes = %attributes,
+            "usage"
+        );
+    }
+}
+
+impl GrpcMeterBackend {
+    /// Creates a backend that reports to the service at `url`.
+    ///
+    /// The connection is established on the first report.
+    pub fn new(url: String) -> Result<Self, MeterBackendError> {
+        let channel = Endpoint::from_shared(url)?.connect_lazy();
+        Ok(GrpcMeterBackend {
+            client: UsageClient::new(channel),
+            pending: Mutex::default(),
+        })
+    }
+
+    /// Reports counters every `interval` until cancelled.
+    pub async fn run(self: Arc<Self>, interval: Duration, ct: CancellationToken) {
+        loop {
+            let cancelled = tokio::select! {
+                _ = tokio::time::sleep(interval) => false,
+                _ = ct.cancelled() => true,
+            };
+            self.report().await;
+            if cancelled {
+                return;
+            }
+        }
+    }
+
+    /// Reports the counters incremented since the previous report. Counters
+    /// are reported again with the next report if the service is unavailable.
+    async fn report(&self) {
+        let pending = std::mem::take(&mut *self.pending.lock().expect("usage counters lock"));
+        if pending.is_empty() {
+            return;
+        }
+        let counters = pending
+            .iter()
+            .map(|((name, attributes), amount)| UsageCounter {
+                name: name.to_string(),
+                amount: *amount,
+                attributes: attributes
+                    .iter()
+                    .map(|(key, value)| UsageAttribute {
+                        key: key.clone(),
+                        value: value.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let request = ReportUsageRequest {
+            counters,
+            ..ReportUsageRequest::default()
+        };
+        if let Err(err) = self.client.clone().report_usage(request).await {
+            warn!(error = ?err, "failed to report usage");
+            let mut current = self.pending.lock().expect("usage counters lock");
+            for (key, amount) in pending {
+                *current.entry(key).or_default() += amount;
+            }
+        }
+    }
+}
+
+impl MeterBackend for GrpcMeterBackend {
+    fn increment_counter(&self, name: &'static str, amount: u64, attributes: &[KeyValue]) {
+        let attributes = attributes
+            .iter()
+            .map(|kv| (kv.key.as_str().to_string(), kv.value.as_str().into_owned()))
+            .collect();
+        let mut pending = self.pending.lock().expect("usage counters lock");
+        *pending.entry((name, attributes)).or_default() += amount;
+    }
+}
+
+/// Returns a valid Prometheus label name for the metadata key.
+fn prometheus_label(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tonic::metadata::MetadataMap;
+
+    use crate::server::{RequestMeter, RequestObserver};
+
+    use super::{MeteredRequestObserver, PrometheusMeterBackend};
+
+    #[test]
+    fn test_prometheus_backend() {
+        let keys = vec!["x-api-key".to_string()];
+        let prometheus = Arc::new(PrometheusMeterBackend::new(&keys).unwrap());
+        let observer = MeteredRequestObserver::new(keys).with_backend(prometheus.clone());
+
+        let mut metadata = MetadataMap::new();
+        metadata.insert("x-api-key", "alice".parse().unwrap());
+        metadata.insert("x-stream-name", "indexer".parse().unwrap());
+        let meter = observer.stream_data_meter(&metadata);
+        meter.increment_counter("event", 3);
+        meter.increment_counter("event", 2);
+
+        let text = prometheus.encode().unwrap();
+        assert!(text.contains(
+            r#"stream_data_out{datum="event",stream_name="indexer",x_api_key="alice"} 5"#
+        ));
+    }
+}
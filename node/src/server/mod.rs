@@ -1,5 +1,11 @@
 mod metadata;
+mod meter;
 
 pub use self::metadata::{
-    MetadataKeyRequestObserver, RequestMeter, RequestObserver, SimpleMeter, SimpleRequestObserver,
+    stream_name, MetadataKeyRequestObserver, RequestMeter, RequestObserver, SimpleMeter,
+    SimpleRequestObserver, STREAM_NAME_METADATA_KEY,
+};
+pub use self::meter::{
+    BackendMeter, GrpcMeterBackend, LogMeterBackend, MeterBackend, MeterBackendError,
+    MeteredRequestObserver, OtlpMeterBackend, PrometheusMeterBackend,
 };
@@ -7,6 +7,7 @@ use apibara_core::node::v1alpha2::{DataFinality, StreamDataRequest};
 use futures::Stream;
 use pin_project::pin_project;
 use prost::Message;
+use serde::Serialize;
 use tracing::warn;
 
 use crate::core::Cursor;
@@ -17,6 +18,21 @@ const MIN_BATCH_SIZE: usize = 1;
 const MAX_BATCH_SIZE: usize = 50;
 const DEFAULT_BATCH_SIZE: usize = 20;
 
+/// Limits on the batch size requested by clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct BatchSizePolicy {
+    /// Batch size of clients that don't request one.
+    pub default_batch_size: usize,
+    pub min_batch_size: usize,
+    pub max_batch_size: usize,
+    /// Split batches into messages with at most this many data units. A
+    /// single block over the limit is still sent in one message.
+    pub max_data_units_per_message: Option<u64>,
+    /// Split batches into messages of at most this many bytes. Streams fail
+    /// on blocks over the limit.
+    pub max_message_size: Option<usize>,
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct StreamConfiguration<C, F>
 where
@@ -28,6 +44,8 @@ where
     pub finality: DataFinality,
     pub starting_cursor: Option<C>,
     pub filter: F,
+    pub max_data_units_per_message: Option<u64>,
+    pub max_message_size: Option<usize>,
 }
 
 #[derive(Default)]
@@ -37,6 +55,7 @@ where
     F: Message + Default + Clone,
 {
     current: Option<StreamConfiguration<C, F>>,
+    policy: BatchSizePolicy,
 }
 
 #[pin_project]
@@ -65,6 +84,38 @@ where
             state: Default::default(),
         }
     }
+
+    /// Clamp the batch size requested by clients with the given policy.
+    pub fn with_batch_size_policy(mut self, policy: BatchSizePolicy) -> Self {
+        self.state.policy = policy;
+        self
+    }
+}
+
+impl Default for BatchSizePolicy {
+    fn default() -> Self {
+        BatchSizePolicy {
+            default_batch_size: DEFAULT_BATCH_SIZE,
+            min_batch_size: MIN_BATCH_SIZE,
+            max_batch_size: MAX_BATCH_SIZE,
+            max_data_units_per_message: None,
+            max_message_size: None,
+        }
+    }
+}
+
+impl BatchSizePolicy {
+    /// Returns the batch size to use for the requested one.
+    pub fn batch_size(&self, requested: Option<u64>) -> usize {
+        let batch_size = match requested {
+            None => self.default_batch_size,
+            Some(requested) => usize::try_from(requested).unwrap_or(usize::MAX),
+        };
+        batch_size
+            .min(self.max_batch_size)
+            .max(self.min_batch_size)
+            .max(1)
+    }
 }
 
 impl<C, F> StreamConfigurationStreamState<C, F>
@@ -76,8 +127,7 @@ where
         &mut self,
         request: StreamDataRequest,
     ) -> Result<StreamConfiguration<C, F>, StreamError> {
-        let batch_size = request.batch_size.unwrap_or(DEFAULT_BATCH_SIZE as u64) as usize;
-        let batch_size = batch_size.clamp(MIN_BATCH_SIZE, MAX_BATCH_SIZE);
+        let batch_size = self.policy.batch_size(request.batch_size);
 
         let finality = request
             .finality
@@ -108,6 +158,8 @@ where
             stream_id,
             filter,
             starting_cursor,
+            max_data_units_per_message: self.policy.max_data_units_per_message,
+            max_message_size: self.policy.max_message_size,
         };
 
         self.current = Some(configuration.clone());
@@ -132,7 +184,13 @@ where
             Poll::Ready(None) => Poll::Ready(None),
             Poll::Ready(Some(Err(err))) => {
                 warn!(err = ?err, "configuration stream error");
-                let err = Err(StreamError::internal(err));
+                // requests rejected upstream keep their error, everything
+                // else is reported as internal.
+                let err: Box<dyn std::error::Error + Send + Sync + 'static> = Box::new(err);
+                let err = match err.downcast::<StreamError>() {
+                    Ok(err) => Err(*err),
+                    Err(err) => Err(StreamError::Internal(err)),
+                };
                 Poll::Ready(Some(err))
             }
             Poll::Ready(Some(Ok(request))) => {
@@ -142,3 +200,28 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BatchSizePolicy;
+
+    #[test]
+    fn test_batch_size_policy() {
+        let policy = BatchSizePolicy::default();
+        assert_eq!(policy.batch_size(None), 20);
+        assert_eq!(policy.batch_size(Some(0)), 1);
+        assert_eq!(policy.batch_size(Some(1_000)), 50);
+
+        let policy = BatchSizePolicy {
+            default_batch_size: 5,
+            min_batch_size: 2,
+            max_batch_size: 10,
+            max_data_units_per_message: None,
+            max_message_size: None,
+        };
+        assert_eq!(policy.batch_size(None), 5);
+        assert_eq!(policy.batch_size(Some(1)), 2);
+        assert_eq!(policy.batch_size(Some(7)), 7);
+        assert_eq!(policy.batch_size(Some(u64::MAX)), 10);
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use apibara_core::node::v1alpha2::{
     stream_data_response, Data, DataFinality, Invalidate, StreamDataResponse,
 };
@@ -12,6 +14,9 @@ use super::{
     StreamConfiguration, StreamError,
 };
 
+/// Bytes reserved in each message for the cursors and encoding overhead.
+const MESSAGE_OVERHEAD: usize = 1024;
+
 pub fn new_data_stream<C, F, B, M>(
     configuration_stream: impl Stream<Item = Result<StreamConfiguration<C, F>, StreamError>> + Unpin,
     ingestion_stream: impl Stream<Item = Result<IngestionMessage<C>, StreamError>> + Unpin,
@@ -27,10 +32,12 @@ where
 {
     let mut configuration_stream = configuration_stream.fuse();
     let mut ingestion_stream = ingestion_stream.fuse();
+    let meter = CountingMeter::new(meter);
 
     // try_stream! doesn't work with tokio::select! so we have to use stream! and helper functions.
     Box::pin(stream! {
         let mut stream_id = 0;
+        let mut limits = MessageLimits::default();
         loop {
             tokio::select! {
                 // check streams in order.
@@ -43,8 +50,9 @@ where
 
                 configuration_message = configuration_stream.select_next_some() => {
                     match handle_configuration_message(&mut cursor_producer, &mut batch_producer, configuration_message).await {
-                        Ok((new_stream_id, configure_response)) => {
+                        Ok((new_stream_id, new_limits, configure_response)) => {
                             stream_id = new_stream_id;
+                            limits = new_limits;
                             // send invalidate message if the specified cursor is no longer valid.
                             match configure_response {
                                 ReconfigureResponse::Ok => {},
@@ -100,12 +108,14 @@ where
                 batch_cursor = cursor_producer.select_next_some() => {
                     use stream_data_response::Message;
 
-                    match handle_batch_cursor(&mut cursor_producer, &mut batch_producer, batch_cursor, &meter).await {
-                        Ok(data) => {
-                            yield Ok(StreamDataResponse {
-                                stream_id,
-                                message: Some(Message::Data(data)),
-                            });
+                    match handle_batch_cursor(&mut cursor_producer, &mut batch_producer, batch_cursor, &meter, limits).await {
+                        Ok(messages) => {
+                            for data in messages {
+                                yield Ok(StreamDataResponse {
+                                    stream_id,
+                                    message: Some(Message::Data(data)),
+                                });
+                            }
                         },
                         Err(err) => {
                             yield Err(err);
@@ -118,22 +128,29 @@ where
     })
 }
 
+#[tracing::instrument(level = "debug", skip_all, fields(stream_id = tracing::field::Empty))]
 async fn handle_configuration_message<C, F, B>(
     cursor_producer: &mut impl CursorProducer<Cursor = C, Filter = F>,
     batch_producer: &mut impl BatchProducer<Cursor = C, Filter = F, Block = B>,
     configuration_message: Result<StreamConfiguration<C, F>, StreamError>,
-) -> Result<(u64, ReconfigureResponse<C>), StreamError>
+) -> Result<(u64, MessageLimits, ReconfigureResponse<C>), StreamError>
 where
     C: Cursor + Send + Sync,
     F: Message + Default + Clone,
     B: Message + Default + Clone,
 {
     let configuration_message = configuration_message?;
+    tracing::Span::current().record("stream_id", configuration_message.stream_id);
     let ingestion_response = cursor_producer.reconfigure(&configuration_message).await?;
     batch_producer.reconfigure(&configuration_message)?;
-    Ok((configuration_message.stream_id, ingestion_response))
+    let limits = MessageLimits {
+        max_data_units: configuration_message.max_data_units_per_message,
+        max_size: configuration_message.max_message_size,
+    };
+    Ok((configuration_message.stream_id, limits, ingestion_response))
 }
 
+#[tracing::instrument(level = "debug", skip_all)]
 async fn handle_ingestion_message<C, F>(
     cursor_producer: &mut impl CursorProducer<Cursor = C, Filter = F>,
     ingestion_message: Result<IngestionMessage<C>, StreamError>,
@@ -148,12 +165,18 @@ where
         .await
 }
 
+#[tracing::instrument(
+    level = "debug",
+    skip_all,
+    fields(finality = tracing::field::Empty, blocks = tracing::field::Empty)
+)]
 async fn handle_batch_cursor<C, F, B, M>(
     _cursor_producer: &mut impl CursorProducer<Cursor = C, Filter = F>,
     batch_producer: &mut impl BatchProducer<Cursor = C, Filter = F, Block = B>,
     batch_cursor: Result<BatchCursor<C>, StreamError>,
-    meter: &M,
-) -> Result<Data, StreamError>
+    meter: &CountingMeter<M>,
+    limits: MessageLimits,
+) -> Result<Vec<Data>, StreamError>
 where
     C: Cursor + Send + Sync,
     F: Message + Default + Clone,
@@ -184,17 +207,150 @@ where
             DataFinality::DataStatusPending,
         ),
     };
-    let batch = batch_producer
-        .next_batch(cursors.into_iter(), meter)
-        .await?;
-
-    Ok(Data {
-        cursor: start_cursor.map(|cursor| cursor.to_proto()),
-        end_cursor: end_cursor.map(|cursor| cursor.to_proto()),
-        finality: finality as i32,
-        data: batch
+    let span = tracing::Span::current();
+    span.record("finality", tracing::field::debug(&finality));
+    span.record("blocks", cursors.len());
+    let finality = finality as i32;
+
+    if !limits.is_enabled() {
+        let batch = batch_producer
+            .next_batch(cursors.into_iter(), meter)
+            .await?;
+        return Ok(vec![Data {
+            cursor: start_cursor.map(|cursor| cursor.to_proto()),
+            end_cursor: end_cursor.map(|cursor| cursor.to_proto()),
+            finality,
+            data: batch
+                .into_iter()
+                .map(|block| block.encode_to_vec())
+                .collect(),
+        }]);
+    }
+
+    // produce blocks one at a time, to start a new message once the
+    // current one is full.
+    let mut messages = Vec::new();
+    let mut message_start = start_cursor;
+    let mut message_end: Option<C> = None;
+    let mut data = Vec::new();
+    let mut data_units = 0;
+    let mut size = 0;
+    meter.take_data_units();
+    for cursor in cursors {
+        let batch = batch_producer
+            .next_batch(std::iter::once(cursor.clone()), meter)
+            .await?;
+        let units = meter.take_data_units();
+        let blocks: Vec<_> = batch
             .into_iter()
             .map(|block| block.encode_to_vec())
-            .collect(),
-    })
+            .collect();
+        let block_size: usize = blocks.iter().map(|block| block.len()).sum();
+        if let Some(max_size) = limits.max_data_size() {
+            if block_size > max_size {
+                return Err(StreamError::MessageTooLarge {
+                    block_number: cursor.to_proto().order_key,
+                    size: block_size,
+                    max_size: limits.max_size.unwrap_or_default(),
+                });
+            }
+        }
+        if !data.is_empty() && limits.is_exceeded(data_units + units, size + block_size) {
+            messages.push(Data {
+                cursor: message_start.map(|cursor| cursor.to_proto()),
+                end_cursor: message_end.as_ref().map(|cursor| cursor.to_proto()),
+                finality,
+                data: std::mem::take(&mut data),
+            });
+            message_start = message_end.clone();
+            data_units = 0;
+            size = 0;
+        }
+        data.extend(blocks);
+        data_units += units;
+        size += block_size;
+        message_end = Some(cursor);
+    }
+    messages.push(Data {
+        cursor: message_start.map(|cursor| cursor.to_proto()),
+        end_cursor: message_end.map(|cursor| cursor.to_proto()),
+        finality,
+        data,
+    });
+    Ok(messages)
+}
+
+/// Limits on the content of data messages.
+#[derive(Debug, Default, Clone, Copy)]
+struct MessageLimits {
+    max_data_units: Option<u64>,
+    max_size: Option<usize>,
+}
+
+impl MessageLimits {
+    fn is_enabled(&self) -> bool {
+        self.max_data_units.is_some() || self.max_size.is_some()
+    }
+
+    /// Returns the maximum size of the blocks in a message.
+    fn max_data_size(&self) -> Option<usize> {
+        self.max_size
+            .map(|max_size| max_size.saturating_sub(MESSAGE_OVERHEAD))
+    }
+
+    fn is_exceeded(&self, data_units: u64, size: usize) -> bool {
+        let units_exceeded = self
+            .max_data_units
+            .map(|max| data_units > max)
+            .unwrap_or(false);
+        let size_exceeded = self.max_data_size().map(|max| size > max).unwrap_or(false);
+        units_exceeded || size_exceeded
+    }
+}
+
+/// A [RequestMeter] that also counts the data units of the current batch.
+struct CountingMeter<M: RequestMeter> {
+    inner: M,
+    data_units: AtomicU64,
+}
+
+impl<M: RequestMeter> CountingMeter<M> {
+    fn new(inner: M) -> Self {
+        CountingMeter {
+            inner,
+            data_units: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the data units counted since the previous call.
+    fn take_data_units(&self) -> u64 {
+        self.data_units.swap(0, Ordering::Relaxed)
+    }
+}
+
+impl<M: RequestMeter> RequestMeter for CountingMeter<M> {
+    fn increment_counter(&self, name: &'static str, amount: u64) {
+        self.data_units.fetch_add(amount, Ordering::Relaxed);
+        self.inner.increment_counter(name, amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MessageLimits, MESSAGE_OVERHEAD};
+
+    #[test]
+    fn test_message_limits() {
+        assert!(!MessageLimits::default().is_enabled());
+
+        let limits = MessageLimits {
+            max_data_units: Some(10),
+            max_size: Some(MESSAGE_OVERHEAD + 100),
+        };
+        assert!(limits.is_enabled());
+        assert_eq!(limits.max_data_size(), Some(100));
+        assert!(!limits.is_exceeded(10, 100));
+        assert!(limits.is_exceeded(11, 100));
+        assert!(limits.is_exceeded(10, 101));
+    }
 }
@@ -1,11 +1,86 @@
+use std::{fmt, time::Duration};
+
+use apibara_core::node::v1alpha2::{Cursor, StreamErrorCode, StreamErrorDetails};
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    Code,
+};
 use tracing::warn;
 
+use crate::o11y::report_error;
+
+/// Error that terminates a stream.
+///
+/// Variants are grouped by [StreamErrorKind], which tells clients if and
+/// when to retry.
 #[derive(Debug, thiserror::Error)]
 pub enum StreamError {
     #[error("internal error: {0}")]
     Internal(Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error("invalid request: {message}")]
     InvalidRequest { message: String },
+    #[error("block {block_number} is {size} bytes, over the {max_size} bytes message limit")]
+    MessageTooLarge {
+        block_number: u64,
+        size: usize,
+        max_size: usize,
+    },
+    #[error(
+        "quota exceeded: used {used} of {limit} data units this {period}, resets at {reset_at}"
+    )]
+    QuotaExceeded {
+        period: QuotaPeriod,
+        limit: u64,
+        used: u64,
+        /// Unix timestamp, in seconds, at which the quota resets.
+        reset_at: u64,
+    },
+    #[error("unavailable: {message}")]
+    Unavailable {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    #[error("starting cursor {block_number} is before the earliest available block {}", .earliest.order_key)]
+    DataPruned {
+        block_number: u64,
+        earliest: Cursor,
+        latest: Option<Cursor>,
+    },
+}
+
+/// Period over which callers use their quota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaPeriod {
+    Day,
+    Month,
+}
+
+/// Category of a [StreamError].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamErrorKind {
+    /// The request can't succeed, clients should fix it instead of retrying.
+    InvalidRequest,
+    /// The caller used its quota, retrying succeeds once it resets.
+    Quota,
+    /// The node can't serve the stream now, retrying later can succeed.
+    Unavailable,
+    /// The requested data is no longer on the node, clients should start
+    /// from a more recent cursor or use another node.
+    DataPruned,
+    /// Unexpected error on the node, retrying can succeed.
+    Internal,
+}
+
+impl StreamErrorKind {
+    /// Returns true if the same request can succeed if retried later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            StreamErrorKind::InvalidRequest | StreamErrorKind::DataPruned => false,
+            StreamErrorKind::Quota | StreamErrorKind::Unavailable | StreamErrorKind::Internal => {
+                true
+            }
+        }
+    }
 }
 
 impl StreamError {
@@ -17,13 +92,230 @@ impl StreamError {
         StreamError::Internal(err.into())
     }
 
+    pub fn quota_exceeded(period: QuotaPeriod, limit: u64, used: u64, reset_at: u64) -> Self {
+        StreamError::QuotaExceeded {
+            period,
+            limit,
+            used,
+            reset_at,
+        }
+    }
+
+    pub fn unavailable(message: String, retry_after: Option<Duration>) -> Self {
+        StreamError::Unavailable {
+            message,
+            retry_after,
+        }
+    }
+
+    /// Returns the category of the error.
+    pub fn kind(&self) -> StreamErrorKind {
+        match self {
+            StreamError::Internal(_) => StreamErrorKind::Internal,
+            StreamError::InvalidRequest { .. } | StreamError::MessageTooLarge { .. } => {
+                StreamErrorKind::InvalidRequest
+            }
+            StreamError::QuotaExceeded { .. } => StreamErrorKind::Quota,
+            StreamError::Unavailable { .. } => StreamErrorKind::Unavailable,
+            StreamError::DataPruned { .. } => StreamErrorKind::DataPruned,
+        }
+    }
+
+    /// Returns true if the same request can succeed if retried later.
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    /// Returns the gRPC code sent to the client.
+    pub fn code(&self) -> Code {
+        match self {
+            StreamError::Internal(_) => Code::Internal,
+            StreamError::InvalidRequest { .. } => Code::InvalidArgument,
+            StreamError::MessageTooLarge { .. } => Code::ResourceExhausted,
+            StreamError::QuotaExceeded { .. } => Code::ResourceExhausted,
+            StreamError::Unavailable { .. } => Code::Unavailable,
+            StreamError::DataPruned { .. } => Code::OutOfRange,
+        }
+    }
+
+    /// Returns the status sent to the client, with details that tell the
+    /// client how to recover.
     pub fn into_status(self) -> tonic::Status {
+        let code = self.code();
+        let retryable = self.is_retryable();
+        let details = |error_code| StreamErrorDetails::new(error_code, retryable);
         match self {
             StreamError::Internal(err) => {
                 warn!(err = ?err, "stream error");
-                tonic::Status::internal("internal server error")
+                report_error(&*err);
+                details(StreamErrorCode::Internal).into_status(
+                    code,
+                    "internal server error",
+                    MetadataMap::new(),
+                )
+            }
+            StreamError::InvalidRequest { message } => details(StreamErrorCode::InvalidRequest)
+                .into_status(code, message, MetadataMap::new()),
+            StreamError::MessageTooLarge { .. } => {
+                let message = format!(
+                    "{self}. Narrow the filter to fewer items, or drop fields such as transactions and receipts from it"
+                );
+                details(StreamErrorCode::MessageTooLarge).into_status(
+                    code,
+                    message,
+                    MetadataMap::new(),
+                )
+            }
+            StreamError::QuotaExceeded {
+                period,
+                limit,
+                used,
+                reset_at,
+            } => {
+                // also sent as metadata, for clients that don't decode the
+                // details.
+                let mut metadata = MetadataMap::new();
+                metadata.insert(
+                    "x-apibara-quota-period",
+                    MetadataValue::from_static(period.as_str()),
+                );
+                metadata.insert("x-apibara-quota-limit", limit.into());
+                metadata.insert("x-apibara-quota-used", used.into());
+                metadata.insert("x-apibara-quota-reset", reset_at.into());
+                let message = self.to_string();
+                details(StreamErrorCode::QuotaExceeded)
+                    .with_quota_reset_at(reset_at)
+                    .into_status(code, message, metadata)
+            }
+            StreamError::Unavailable { retry_after, .. } => {
+                let message = self.to_string();
+                let mut details = details(StreamErrorCode::Overloaded);
+                if let Some(retry_after) = retry_after {
+                    details = details.with_retry_after(retry_after);
+                }
+                details.into_status(code, message, MetadataMap::new())
+            }
+            StreamError::DataPruned {
+                ref earliest,
+                ref latest,
+                ..
+            } => {
+                let message = self.to_string();
+                details(StreamErrorCode::CursorUnavailable)
+                    .with_available(Some(earliest.clone()), latest.clone())
+                    .into_status(code, message, MetadataMap::new())
             }
-            StreamError::InvalidRequest { message } => tonic::Status::invalid_argument(message),
         }
     }
 }
+
+impl From<StreamError> for tonic::Status {
+    fn from(err: StreamError) -> Self {
+        err.into_status()
+    }
+}
+
+impl QuotaPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaPeriod::Day => "day",
+            QuotaPeriod::Month => "month",
+        }
+    }
+}
+
+impl fmt::Display for QuotaPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use apibara_core::node::v1alpha2::{Cursor, StreamErrorCode, StreamErrorDetails};
+    use tonic::Code;
+
+    use super::{QuotaPeriod, StreamError, StreamErrorKind};
+
+    #[test]
+    fn test_stream_error_details() {
+        let status = StreamError::invalid_request("bad filter".to_string()).into_status();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let details = StreamErrorDetails::from_status(&status).unwrap();
+        assert_eq!(details.code(), StreamErrorCode::InvalidRequest);
+        assert!(!details.retryable);
+
+        let earliest = Cursor {
+            order_key: 100,
+            unique_key: vec![1],
+        };
+        let status = StreamError::DataPruned {
+            block_number: 10,
+            earliest: earliest.clone(),
+            latest: None,
+        }
+        .into_status();
+        assert_eq!(status.code(), Code::OutOfRange);
+        assert_eq!(
+            status.message(),
+            "starting cursor 10 is before the earliest available block 100"
+        );
+        let details = StreamErrorDetails::from_status(&status).unwrap();
+        assert_eq!(details.code(), StreamErrorCode::CursorUnavailable);
+        assert_eq!(details.earliest_available, Some(earliest));
+
+        let status = StreamError::internal("boom").into_status();
+        assert!(StreamErrorDetails::from_status(&status).unwrap().retryable);
+    }
+
+    #[test]
+    fn test_stream_error_kind() {
+        let err = StreamError::quota_exceeded(QuotaPeriod::Day, 10, 10, 1_700_000_000);
+        assert_eq!(err.kind(), StreamErrorKind::Quota);
+        assert!(err.is_retryable());
+        let status = err.into_status();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        assert_eq!(
+            status.message(),
+            "quota exceeded: used 10 of 10 data units this day, resets at 1700000000"
+        );
+        assert_eq!(
+            status.metadata().get("x-apibara-quota-period").unwrap(),
+            "day"
+        );
+        assert_eq!(
+            status.metadata().get("x-apibara-quota-reset").unwrap(),
+            "1700000000"
+        );
+        let details = StreamErrorDetails::from_status(&status).unwrap();
+        assert_eq!(details.code(), StreamErrorCode::QuotaExceeded);
+        assert_eq!(details.quota_reset_at, Some(1_700_000_000));
+
+        let err = StreamError::unavailable(
+            "node is overloaded".to_string(),
+            Some(Duration::from_secs(5)),
+        );
+        assert_eq!(err.kind(), StreamErrorKind::Unavailable);
+        let status = err.into_status();
+        assert_eq!(status.code(), Code::Unavailable);
+        let details = StreamErrorDetails::from_status(&status).unwrap();
+        assert!(details.retryable);
+        assert_eq!(details.retry_after_ms, Some(5_000));
+
+        let err = StreamError::MessageTooLarge {
+            block_number: 1,
+            size: 100,
+            max_size: 10,
+        };
+        assert_eq!(err.kind(), StreamErrorKind::InvalidRequest);
+        assert!(!err.is_retryable());
+        assert_eq!(err.code(), Code::ResourceExhausted);
+
+        assert_eq!(
+            StreamError::internal("boom").kind(),
+            StreamErrorKind::Internal
+        );
+    }
+}
@@ -5,13 +5,17 @@ mod heartbeat;
 mod ingestion;
 mod producers;
 mod response;
+mod v1alpha3;
 
-pub use self::configuration::{StreamConfiguration, StreamConfigurationStream};
+pub use self::configuration::{BatchSizePolicy, StreamConfiguration, StreamConfigurationStream};
 pub use self::data::new_data_stream;
-pub use self::error::StreamError;
+pub use self::error::{QuotaPeriod, StreamError, StreamErrorKind};
 pub use self::heartbeat::Heartbeat;
 pub use self::ingestion::IngestionMessage;
 pub use self::producers::{
     BatchCursor, BatchProducer, CursorProducer, IngestionResponse, ReconfigureResponse,
 };
 pub use self::response::ResponseStream;
+pub use self::v1alpha3::{
+    BlockDescription, BlockDescriptor, V1Alpha3RequestStream, V1Alpha3ResponseStream,
+};
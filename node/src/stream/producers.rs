@@ -83,6 +83,10 @@ impl<C: Cursor> BatchCursor<C> {
     ///
     /// Panics if `cursors` is empty.
     pub fn new_finalized(start_cursor: Option<C>, cursors: Vec<C>) -> Self {
+        debug_assert!(
+            cursors.windows(2).all(|pair| pair[0] < pair[1]),
+            "finalized cursors are not sorted"
+        );
         BatchCursor::Finalized(start_cursor, cursors)
     }
 
@@ -115,6 +119,25 @@ impl<C: Cursor> BatchCursor<C> {
         }
     }
 
+    /// Returns all cursors in the batch, in order.
+    pub fn cursors(&self) -> &[C] {
+        match self {
+            BatchCursor::Finalized(_, ref cursors) => cursors,
+            BatchCursor::Accepted(_, ref cursor) => std::slice::from_ref(cursor),
+            BatchCursor::Pending(_, ref cursor) => std::slice::from_ref(cursor),
+        }
+    }
+
+    /// Returns the timestamp of the last block in the batch, if known.
+    pub fn end_timestamp(&self) -> Option<u64> {
+        self.end_cursor().timestamp()
+    }
+
+    /// Returns the number of blocks between the end of the batch and `head`.
+    pub fn lag(&self, head: &C) -> u64 {
+        self.end_cursor().lag(head)
+    }
+
     /// Returns the finalized cursors.
     pub fn as_finalized(&self) -> Option<&[C]> {
         match self {
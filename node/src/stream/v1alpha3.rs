@@ -0,0 +1,293 @@
+//! Serve v1alpha3 clients on top of the v1alpha2 data stream.
+//!
+//! The data stream is produced once, in the v1alpha2 format. Responses are
+//! then converted for v1alpha3 clients, adding the chain id and block
+//! timestamp to cursors, per-block finality and sequence numbers.
+
+use std::{
+    pin::Pin,
+    task::{self, Poll},
+};
+
+use apibara_core::node::{v1alpha2, v1alpha3};
+use futures::Stream;
+use pin_project::pin_project;
+
+use super::error::StreamError;
+
+/// What the chain can tell about a block from its encoded data.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockDescription {
+    /// The block cursor.
+    pub cursor: Option<v1alpha2::Cursor>,
+    /// The block timestamp, in seconds since the unix epoch.
+    pub timestamp: Option<u64>,
+    /// The block finality.
+    pub finality: Option<v1alpha2::DataFinality>,
+}
+
+/// Describe blocks sent to clients.
+pub trait BlockDescriptor {
+    /// Returns the description of the encoded block.
+    ///
+    /// Blocks that can't be decoded, or that don't contain the requested
+    /// information, return an empty description.
+    fn describe(&self, data: &[u8]) -> BlockDescription;
+}
+
+/// Converts v1alpha3 requests to v1alpha2, rejecting cursors of other chains.
+#[pin_project]
+pub struct V1Alpha3RequestStream<S> {
+    #[pin]
+    inner: S,
+    chain_id: Vec<u8>,
+}
+
+impl<S> V1Alpha3RequestStream<S> {
+    pub fn new(inner: S, chain_id: Vec<u8>) -> Self {
+        V1Alpha3RequestStream { inner, chain_id }
+    }
+}
+
+impl<S, E> Stream for V1Alpha3RequestStream<S>
+where
+    S: Stream<Item = Result<v1alpha3::StreamDataRequest, E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    type Item = Result<v1alpha2::StreamDataRequest, StreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        match this.inner.poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(StreamError::internal(err)))),
+            Poll::Ready(Some(Ok(request))) => {
+                let request = request
+                    .to_v1alpha2(this.chain_id)
+                    .map_err(|err| StreamError::invalid_request(err.to_string()));
+                Poll::Ready(Some(request))
+            }
+        }
+    }
+}
+
+/// Converts v1alpha2 responses to v1alpha3.
+#[pin_project]
+pub struct V1Alpha3ResponseStream<S, D> {
+    #[pin]
+    inner: S,
+    converter: ResponseConverter<D>,
+}
+
+impl<S, D> V1Alpha3ResponseStream<S, D>
+where
+    D: BlockDescriptor,
+{
+    pub fn new(inner: S, descriptor: D, chain_id: Vec<u8>) -> Self {
+        let converter = ResponseConverter {
+            descriptor,
+            chain_id,
+            sequence: 0,
+        };
+        V1Alpha3ResponseStream { inner, converter }
+    }
+}
+
+impl<S, D> Stream for V1Alpha3ResponseStream<S, D>
+where
+    S: Stream<Item = Result<v1alpha2::StreamDataResponse, tonic::Status>>,
+    D: BlockDescriptor,
+{
+    type Item = Result<v1alpha3::StreamDataResponse, tonic::Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        match this.inner.poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Ready(Some(Err(status))) => Poll::Ready(Some(Err(status))),
+            Poll::Ready(Some(Ok(response))) => {
+                Poll::Ready(Some(Ok(this.converter.convert(response))))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+struct ResponseConverter<D> {
+    descriptor: D,
+    chain_id: Vec<u8>,
+    sequence: u64,
+}
+
+impl<D> ResponseConverter<D>
+where
+    D: BlockDescriptor,
+{
+    fn convert(&mut self, response: v1alpha2::StreamDataResponse) -> v1alpha3::StreamDataResponse {
+        use v1alpha2::stream_data_response::Message as V2Message;
+        use v1alpha3::stream_data_response::Message;
+
+        let message = response.message.map(|message| match message {
+            V2Message::Heartbeat(_) => Message::Heartbeat(v1alpha3::Heartbeat {}),
+            V2Message::Invalidate(invalidate) => Message::Invalidate(v1alpha3::Invalidate {
+                cursor: self.cursor(invalidate.cursor),
+                sequence: self.next_sequence(),
+            }),
+            V2Message::Data(data) => Message::Data(self.convert_data(data)),
+        });
+
+        v1alpha3::StreamDataResponse {
+            stream_id: response.stream_id,
+            message,
+        }
+    }
+
+    fn convert_data(&mut self, data: v1alpha2::Data) -> v1alpha3::Data {
+        let finality = v1alpha2::DataFinality::from_i32(data.finality)
+            .unwrap_or(v1alpha2::DataFinality::DataStatusUnknown);
+
+        let blocks: Vec<_> = data
+            .data
+            .into_iter()
+            .map(|data| {
+                let description = self.descriptor.describe(&data);
+                let cursor = self
+                    .cursor(description.cursor)
+                    .map(|cursor| v1alpha3::Cursor {
+                        timestamp: description.timestamp,
+                        ..cursor
+                    });
+                let finality = description.finality.unwrap_or(finality);
+                v1alpha3::Block {
+                    cursor,
+                    finality: v1alpha3::DataFinality::from(finality) as i32,
+                    data,
+                }
+            })
+            .collect();
+
+        // the end cursor is the cursor of the last block, so it shares its timestamp.
+        let mut end_cursor = self.cursor(data.end_cursor);
+        let last_cursor = blocks.last().and_then(|block| block.cursor.as_ref());
+        if let (Some(end_cursor), Some(last_cursor)) = (end_cursor.as_mut(), last_cursor) {
+            if end_cursor.order_key == last_cursor.order_key {
+                end_cursor.timestamp = last_cursor.timestamp;
+            }
+        }
+
+        v1alpha3::Data {
+            end_cursor,
+            finality: v1alpha3::DataFinality::from(finality) as i32,
+            blocks,
+            cursor: self.cursor(data.cursor),
+            sequence: self.next_sequence(),
+        }
+    }
+
+    fn cursor(&self, cursor: Option<v1alpha2::Cursor>) -> Option<v1alpha3::Cursor> {
+        cursor.map(|cursor| v1alpha3::Cursor::from_v1alpha2(cursor, &self.chain_id))
+    }
+
+    fn next_sequence(&mut self) -> u64 {
+        let sequence = self.sequence;
+        self.sequence += 1;
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::node::{v1alpha2, v1alpha3};
+    use futures::{stream, StreamExt};
+
+    use super::{BlockDescription, BlockDescriptor, V1Alpha3ResponseStream};
+
+    /// Blocks are encoded as `[order_key, timestamp, finality]`.
+    struct TestDescriptor;
+
+    impl BlockDescriptor for TestDescriptor {
+        fn describe(&self, data: &[u8]) -> BlockDescription {
+            BlockDescription {
+                cursor: Some(v1alpha2::Cursor {
+                    order_key: data[0] as u64,
+                    unique_key: vec![data[0]],
+                }),
+                timestamp: Some(data[1] as u64),
+                finality: v1alpha2::DataFinality::from_i32(data[2] as i32),
+            }
+        }
+    }
+
+    fn cursor(order_key: u64) -> Option<v1alpha2::Cursor> {
+        Some(v1alpha2::Cursor {
+            order_key,
+            unique_key: vec![order_key as u8],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_response_conversion() {
+        use v1alpha2::stream_data_response::Message as V2Message;
+        use v1alpha3::stream_data_response::Message;
+
+        let responses = vec![
+            V2Message::Data(v1alpha2::Data {
+                cursor: cursor(1),
+                end_cursor: cursor(3),
+                finality: v1alpha2::DataFinality::DataStatusAccepted as i32,
+                data: vec![vec![2, 20, 3], vec![3, 30, 2]],
+            }),
+            V2Message::Heartbeat(v1alpha2::Heartbeat {}),
+            V2Message::Invalidate(v1alpha2::Invalidate { cursor: cursor(2) }),
+        ];
+        let responses = stream::iter(
+            responses
+                .into_iter()
+                .map(|message| v1alpha2::StreamDataResponse {
+                    stream_id: 7,
+                    message: Some(message),
+                })
+                .map(Ok),
+        );
+        let mut stream = V1Alpha3ResponseStream::new(responses, TestDescriptor, vec![0xaa]);
+
+        let response = stream.next().await.unwrap().unwrap();
+        assert_eq!(response.stream_id, 7);
+        let data = match response.message {
+            Some(Message::Data(data)) => data,
+            _ => panic!("expected data"),
+        };
+        assert_eq!(data.sequence, 0);
+        assert_eq!(data.finality(), v1alpha3::DataFinality::DataStatusAccepted);
+        assert_eq!(data.blocks.len(), 2);
+        assert_eq!(
+            data.blocks[0].finality(),
+            v1alpha3::DataFinality::DataStatusFinalized
+        );
+        let block_cursor = data.blocks[0].cursor.as_ref().unwrap();
+        assert_eq!(block_cursor.order_key, 2);
+        assert_eq!(block_cursor.timestamp, Some(20));
+        assert_eq!(block_cursor.chain_id, vec![0xaa]);
+        let end_cursor = data.end_cursor.unwrap();
+        assert_eq!(end_cursor.order_key, 3);
+        assert_eq!(end_cursor.timestamp, Some(30));
+        assert_eq!(data.cursor.unwrap().timestamp, None);
+
+        let response = stream.next().await.unwrap().unwrap();
+        assert!(matches!(response.message, Some(Message::Heartbeat(_))));
+
+        let response = stream.next().await.unwrap().unwrap();
+        match response.message {
+            Some(Message::Invalidate(invalidate)) => {
+                assert_eq!(invalidate.sequence, 1);
+                assert_eq!(invalidate.cursor.unwrap().chain_id, vec![0xaa]);
+            }
+            _ => panic!("expected invalidate"),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Report errors and panics to a Sentry-compatible endpoint.
+//!
+//! Error reporting is enabled by setting the `SENTRY_DSN` env variable, and
+//! configured with the other `SENTRY_*` env variables, like
+//! `SENTRY_ENVIRONMENT`. Reports include the context set with
+//! [set_error_context] in the current [ErrorReportingScope], for example the
+//! block being ingested or the filter of a stream.
+use std::{future::Future, sync::Arc};
+
+use sentry::{ClientInitGuard, Hub, SentryFutureExt};
+
+/// Keeps error reporting enabled, and sends the pending reports when
+/// dropped.
+pub struct ErrorReportingGuard {
+    _guard: ClientInitGuard,
+}
+
+/// An isolated error context, for example of one stream or of the ingestion
+/// task.
+#[derive(Clone)]
+pub struct ErrorReportingScope {
+    hub: Arc<Hub>,
+}
+
+/// Enables error reporting, including panics, if `SENTRY_DSN` is set.
+///
+/// Reports are sent until the returned guard is dropped, so keep it alive
+/// until the program exits.
+pub fn init_error_reporting() -> ErrorReportingGuard {
+    let guard = sentry::init(sentry::ClientOptions::default());
+    ErrorReportingGuard { _guard: guard }
+}
+
+/// Reports `err` together with the context of the current scope.
+pub fn report_error(err: &(dyn std::error::Error + 'static)) {
+    if !is_error_reporting_enabled() {
+        return;
+    }
+    sentry::capture_error(err);
+}
+
+/// Adds `key` to the context of the errors reported by the current scope.
+pub fn set_error_context(key: &'static str, value: impl ToString) {
+    if !is_error_reporting_enabled() {
+        return;
+    }
+    sentry::configure_scope(|scope| scope.set_tag(key, value.to_string()));
+}
+
+fn is_error_reporting_enabled() -> bool {
+    Hub::with_active(|hub| hub.client().is_some())
+}
+
+impl ErrorReportingScope {
+    /// Creates a scope that starts with the context of the current scope.
+    pub fn new() -> Self {
+        let hub = Hub::with(|hub| Arc::new(Hub::new_from_top(hub)));
+        ErrorReportingScope { hub }
+    }
+
+    /// Runs `f` in the scope.
+    pub fn run<R>(&self, f: impl FnOnce() -> R) -> R {
+        Hub::run(self.hub.clone(), f)
+    }
+
+    /// Runs `future` in the scope, including the panics it raises.
+    pub fn bind<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        future.bind_hub(self.hub)
+    }
+}
+
+impl Default for ErrorReportingScope {
+    fn default() -> Self {
+        ErrorReportingScope::new()
+    }
+}
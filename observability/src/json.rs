@@ -0,0 +1,185 @@
+//! Write logs as JSON objects, one per line.
+//!
+//! The fields of all the spans around an event are merged with the fields
+//! of the event, so that log processors can filter on fields like
+//! `stream_id` without knowing the span they come from. Fields of inner
+//! spans and of the event replace the fields with the same name of outer
+//! spans.
+use std::{fmt, io::Write};
+
+use serde_json::{Map, Value};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{fmt::MakeWriter, layer::Context, registry::LookupSpan, Layer};
+
+/// A layer that writes events as JSON objects.
+pub struct JsonLayer<W> {
+    make_writer: W,
+}
+
+/// The fields recorded on a span.
+struct SpanFields(Map<String, Value>);
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl<W> JsonLayer<W>
+where
+    W: for<'w> MakeWriter<'w> + 'static,
+{
+    /// Writes events to the writers returned by `make_writer`, for example
+    /// `std::io::stdout`.
+    pub fn new(make_writer: W) -> Self {
+        JsonLayer { make_writer }
+    }
+}
+
+impl<S, W> Layer<S> for JsonLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + 'static,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            None => return,
+            Some(span) => span,
+        };
+        let mut fields = Map::new();
+        attrs.record(&mut JsonVisitor(&mut fields));
+        span.extensions_mut().insert(SpanFields(fields));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            None => return,
+            Some(span) => span,
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            values.record(&mut JsonVisitor(fields));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut object = Map::new();
+        object.insert(
+            "timestamp".to_string(),
+            Value::String(chrono::Utc::now().to_rfc3339()),
+        );
+        object.insert(
+            "level".to_string(),
+            Value::String(metadata.level().to_string()),
+        );
+        object.insert(
+            "target".to_string(),
+            Value::String(metadata.target().to_string()),
+        );
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                object.insert("span".to_string(), Value::String(span.name().to_string()));
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    for (name, value) in fields {
+                        object.insert(name.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        event.record(&mut JsonVisitor(&mut object));
+
+        let mut line = match serde_json::to_vec(&object) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        line.push(b'\n');
+        // there's nowhere to report errors writing logs.
+        let _ = self.make_writer.make_writer().write_all(&line);
+    }
+}
+
+impl<'a> Visit for JsonVisitor<'a> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(
+            field.name().to_string(),
+            Value::from(format!("{:?}", value)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use serde_json::Value;
+    use tracing_subscriber::{fmt::MakeWriter, prelude::*};
+
+    use super::JsonLayer;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_json_layer() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(JsonLayer::new(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("stream", stream_id = 7, key_hash = "abcd");
+            let _entered = span.enter();
+            let span = tracing::info_span!("batch", first_block = 10);
+            let _entered = span.enter();
+            tracing::warn!(last_block = 20, "slow batch");
+        });
+
+        let output = buffer.0.lock().unwrap().clone();
+        let line: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["message"], "slow batch");
+        assert_eq!(line["span"], "batch");
+        assert_eq!(line["stream_id"], 7);
+        assert_eq!(line["key_hash"], "abcd");
+        assert_eq!(line["first_block"], 10);
+        assert_eq!(line["last_block"], 20);
+    }
+}
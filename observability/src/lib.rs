@@ -1,5 +1,8 @@
 //! # OpenTelemetry helpers
 
+mod error_reporting;
+mod json;
+
 use std::env;
 
 use opentelemetry::{
@@ -19,10 +22,29 @@ pub use opentelemetry::{Context, Key, KeyValue};
 use tracing_opentelemetry::MetricsLayer;
 use tracing_subscriber::{filter, prelude::*, EnvFilter};
 
-pub use opentelemetry::metrics::{Counter, Meter};
+pub use self::{
+    error_reporting::{
+        init_error_reporting, report_error, set_error_context, ErrorReportingGuard,
+        ErrorReportingScope,
+    },
+    json::JsonLayer,
+};
+
+pub use opentelemetry::metrics::{Counter, Histogram, Meter};
 
 const OTEL_SDK_DISABLED: &str = "OTEL_SDK_DISABLED";
 
+/// Serve task data to tokio-console when set to `true`.
+///
+/// Requires the `tokio-console` feature, and building with
+/// `RUSTFLAGS="--cfg tokio_unstable"`.
+const APIBARA_TOKIO_CONSOLE: &str = "APIBARA_TOKIO_CONSOLE";
+
+/// Write logs as JSON objects, one per line, when set to `json`.
+///
+/// Logs are written with the tree format otherwise.
+const APIBARA_LOG_FORMAT: &str = "APIBARA_LOG_FORMAT";
+
 #[derive(Debug, thiserror::Error)]
 pub enum OpenTelemetryInitError {
     #[error("error setting global default subscriber")]
@@ -48,30 +70,80 @@ pub fn init_opentelemetry() -> Result<(), OpenTelemetryInitError> {
         std::env::set_var("RUST_LOG", "info");
     }
 
+    let console = env::var(APIBARA_TOKIO_CONSOLE)
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let json = env::var(APIBARA_LOG_FORMAT)
+        .map(|v| v == "json")
+        .unwrap_or(false);
+
     if sdk_disabled {
-        init_opentelemetry_no_sdk()
+        init_opentelemetry_no_sdk(console, json)?;
     } else {
-        init_opentelemetry_with_sdk()
+        init_opentelemetry_with_sdk(console, json)?;
+    }
+
+    if console && !cfg!(feature = "tokio-console") {
+        tracing::warn!(
+            "{} is set but the node was built without the tokio-console feature",
+            APIBARA_TOKIO_CONSOLE
+        );
+    }
+    Ok(())
+}
+
+/// Returns the filter configured with `RUST_LOG`.
+///
+/// The filter also enables the task instrumentation of tokio if
+/// tokio-console is enabled, since filters apply to all layers.
+fn env_filter(console: bool) -> EnvFilter {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("INFO"));
+    if !console || !cfg!(feature = "tokio-console") {
+        return filter;
     }
+    filter
+        .add_directive("tokio=trace".parse().expect("valid tokio directive"))
+        .add_directive("runtime=trace".parse().expect("valid runtime directive"))
+}
+
+/// Returns true if the span or event comes from the tokio instrumentation
+/// enabled for tokio-console, which is not exported nor logged.
+fn is_tokio_instrumentation(console: bool, metadata: &tracing::Metadata<'_>) -> bool {
+    let target = metadata.target();
+    console && (target.starts_with("tokio::") || target.starts_with("runtime::"))
+}
+
+/// Returns the layer that writes logs to stderr, as JSON objects or as a
+/// tree.
+fn log_layer<S>(json: bool) -> impl tracing_subscriber::Layer<S>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    let logtree_layer = (!json).then(|| tracing_tree::HierarchicalLayer::new(2));
+    let json_layer = json.then(|| JsonLayer::new(std::io::stderr));
+    tracing_subscriber::Layer::and_then(logtree_layer, json_layer)
 }
 
-fn init_opentelemetry_no_sdk() -> Result<(), OpenTelemetryInitError> {
-    let log_env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("INFO"));
-    let logtree_layer = tracing_tree::HierarchicalLayer::new(2).and_then(log_env_filter);
+fn init_opentelemetry_no_sdk(console: bool, json: bool) -> Result<(), OpenTelemetryInitError> {
+    let log_env_filter = env_filter(console);
+    let logtree_layer = log_layer(json)
+        .and_then(log_env_filter)
+        .with_filter(filter::filter_fn(move |metadata| {
+            !is_tokio_instrumentation(console, metadata)
+        }));
 
-    tracing_subscriber::Registry::default()
-        .with(logtree_layer)
-        .init();
+    let registry = tracing_subscriber::Registry::default().with(logtree_layer);
+    #[cfg(feature = "tokio-console")]
+    let registry = registry.with(console.then(console_subscriber::spawn));
+    registry.init();
     Ok(())
 }
 
-fn init_opentelemetry_with_sdk() -> Result<(), OpenTelemetryInitError> {
+fn init_opentelemetry_with_sdk(console: bool, json: bool) -> Result<(), OpenTelemetryInitError> {
     // filter traces by crate/level
-    let otel_env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("INFO"));
-    let log_env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("INFO"));
+    let otel_env_filter = env_filter(console);
+    let log_env_filter = env_filter(console);
 
     // Both tracer and meter are configured with environment variables.
     let meter = opentelemetry_otlp::new_pipeline()
@@ -95,19 +167,25 @@ fn init_opentelemetry_with_sdk() -> Result<(), OpenTelemetryInitError> {
     let otel_metrics_layer = MetricsLayer::new(meter);
     let otel_layer = otel_trace_layer
         .and_then(otel_metrics_layer)
-        .and_then(otel_env_filter);
+        .and_then(otel_env_filter)
+        .with_filter(filter::filter_fn(move |metadata| {
+            !is_tokio_instrumentation(console, metadata)
+        }));
 
-    // display traces on stdout
-    let logtree_layer = tracing_tree::HierarchicalLayer::new(2)
+    // display traces on stderr
+    let logtree_layer = log_layer(json)
         .and_then(log_env_filter)
-        .with_filter(filter::filter_fn(|metadata| {
+        .with_filter(filter::filter_fn(move |metadata| {
             metadata.fields().field("data.is_metrics").is_none()
+                && !is_tokio_instrumentation(console, metadata)
         }));
 
-    tracing_subscriber::Registry::default()
+    let registry = tracing_subscriber::Registry::default()
         .with(otel_layer)
-        .with(logtree_layer)
-        .init();
+        .with(logtree_layer);
+    #[cfg(feature = "tokio-console")]
+    let registry = registry.with(console.then(console_subscriber::spawn));
+    registry.init();
 
     Ok(())
 }
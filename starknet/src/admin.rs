@@ -0,0 +1,865 @@
+//! Admin HTTP API.
+//!
+//! The admin API is meant to be used by operators and should not be exposed
+//! publicly.
+use std::{net::SocketAddr, sync::Arc, time::UNIX_EPOCH};
+
+use apibara_node::{
+    db::{
+        libmdbx::{Environment, EnvironmentKind},
+        BackupConfig, BackupError, BackupService, BackupStatus, MaintenanceProgress,
+        MaintenanceService, MaintenanceStatus,
+    },
+    server::PrometheusMeterBackend,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use warp::{
+    http::StatusCode,
+    reject::{self, Reject, Rejection},
+    reply::{self, Reply},
+    Filter,
+};
+
+use crate::{
+    db::{database_info, DatabaseInfo, JournalEntry},
+    ingestion::{
+        EmitterStats, IngestionControl, IngestionControlStatus, IngestionHealth,
+        IngestionHealthStatus, IngestionJournal, IngestionProgressSnapshot, ScrubProgress,
+        ScrubStatus,
+    },
+    profiling::{Profiler, ProfilingConfig, ProfilingError},
+    runtime_config::RuntimeConfig,
+};
+
+pub struct AdminServer<E: EnvironmentKind> {
+    db: Arc<Environment<E>>,
+    token: Option<AdminToken>,
+    backup: Option<Arc<BackupService<E>>>,
+    maintenance: Option<Arc<MaintenanceService<E>>>,
+    scrub: Option<ScrubStatus>,
+    ingestion_health: Option<IngestionHealth>,
+    ingestion_control: Option<IngestionControl>,
+    ingestion_journal: Option<IngestionJournal>,
+    prometheus: Option<Arc<PrometheusMeterBackend>>,
+    profiler: Option<Profiler>,
+    emitter_stats: Option<EmitterStats>,
+    runtime_config: Option<RuntimeConfig>,
+}
+
+/// Bearer token required by the admin routes that change the state of the
+/// node, or that expose its configuration and profiles.
+#[derive(Clone)]
+pub struct AdminToken(Arc<String>);
+
+/// Rejection of requests without the admin token.
+#[derive(Debug)]
+struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+#[derive(Debug, Deserialize)]
+struct StartBackupRequest {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CpuProfileQuery {
+    seconds: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopEmittersQuery {
+    limit: Option<usize>,
+}
+
+#[cfg(feature = "reorg-injection")]
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum InjectEventRequest {
+    Reorg { depth: u64 },
+    Finalize { block_number: u64 },
+}
+
+impl<E> AdminServer<E>
+where
+    E: EnvironmentKind,
+{
+    pub fn new(db: Arc<Environment<E>>) -> Self {
+        AdminServer {
+            db,
+            token: None,
+            backup: None,
+            maintenance: None,
+            scrub: None,
+            ingestion_health: None,
+            ingestion_control: None,
+            ingestion_journal: None,
+            prometheus: None,
+            profiler: None,
+            emitter_stats: None,
+            runtime_config: None,
+        }
+    }
+
+    /// Require the given bearer token for the routes that change the state
+    /// of the node, or that expose its configuration and profiles.
+    ///
+    /// These routes reject all requests if no token is configured.
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(AdminToken::new(token));
+        self
+    }
+
+    /// Start backups of the database with the given configuration.
+    pub fn with_backup(mut self, config: BackupConfig) -> Self {
+        self.backup = Some(Arc::new(BackupService::new(self.db.clone(), config)));
+        self
+    }
+
+    /// Report the status of the given maintenance service.
+    pub fn with_maintenance(mut self, maintenance: Arc<MaintenanceService<E>>) -> Self {
+        self.maintenance = Some(maintenance);
+        self
+    }
+
+    /// Report the progress of the block scrubber.
+    pub fn with_scrub(mut self, scrub: ScrubStatus) -> Self {
+        self.scrub = Some(scrub);
+        self
+    }
+
+    /// Report the health of block ingestion.
+    pub fn with_ingestion_health(mut self, health: IngestionHealth) -> Self {
+        self.ingestion_health = Some(health);
+        self
+    }
+
+    /// Pause and resume block ingestion.
+    pub fn with_ingestion_control(mut self, control: IngestionControl) -> Self {
+        self.ingestion_control = Some(control);
+        self
+    }
+
+    /// Retry and skip blocks that repeatedly failed to ingest.
+    pub fn with_ingestion_journal(mut self, journal: IngestionJournal) -> Self {
+        self.ingestion_journal = Some(journal);
+        self
+    }
+
+    /// Serve request counters in the Prometheus text format.
+    pub fn with_prometheus(mut self, prometheus: Arc<PrometheusMeterBackend>) -> Self {
+        self.prometheus = Some(prometheus);
+        self
+    }
+
+    /// Serve CPU and heap profiles to the holders of the admin token.
+    pub fn with_profiling(mut self, config: ProfilingConfig) -> Self {
+        self.profiler = Some(Profiler::new(config));
+        self
+    }
+
+    /// Report the contracts that emit the most events.
+    pub fn with_emitter_stats(mut self, emitter_stats: EmitterStats) -> Self {
+        self.emitter_stats = Some(emitter_stats);
+        self
+    }
+
+    /// Serve the effective configuration of the node to the holders of the
+    /// admin token.
+    pub fn with_runtime_config(mut self, runtime_config: RuntimeConfig) -> Self {
+        self.runtime_config = Some(runtime_config);
+        self
+    }
+
+    pub async fn start(self, addr: SocketAddr, ct: CancellationToken) {
+        let backup_status = warp::path!("backup").and(warp::get()).map({
+            let backup = self.backup.clone();
+            move || match backup {
+                None => backup_not_configured(),
+                Some(ref backup) => reply::with_status(
+                    reply::json(&backup_status_to_json(&backup.status())),
+                    StatusCode::OK,
+                ),
+            }
+        });
+
+        let start_backup = warp::path!("backup")
+            .and(warp::post())
+            .and(authorized(self.token.clone()))
+            .and(warp::body::json())
+            .map({
+                let backup = self.backup.clone();
+                move |request: StartBackupRequest| match backup {
+                    None => backup_not_configured(),
+                    Some(ref backup) => match backup.start(request.name) {
+                        Ok(_) => reply::with_status(
+                            reply::json(&backup_status_to_json(&backup.status())),
+                            StatusCode::ACCEPTED,
+                        ),
+                        Err(err @ BackupError::AlreadyRunning) => reply::with_status(
+                            reply::json(&json!({ "error": err.to_string() })),
+                            StatusCode::CONFLICT,
+                        ),
+                        Err(err @ BackupError::InvalidName(_)) => reply::with_status(
+                            reply::json(&json!({ "error": err.to_string() })),
+                            StatusCode::BAD_REQUEST,
+                        ),
+                        Err(err) => reply::with_status(
+                            reply::json(&json!({ "error": err.to_string() })),
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                        ),
+                    },
+                }
+            });
+
+        let db_info = warp::path!("db" / "info").and(warp::get()).map({
+            let db = self.db.clone();
+            move || match database_info(db.clone()) {
+                Ok(info) => {
+                    reply::with_status(reply::json(&database_info_to_json(&info)), StatusCode::OK)
+                }
+                Err(err) => reply::with_status(
+                    reply::json(&json!({ "error": err.to_string() })),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ),
+            }
+        });
+
+        let maintenance_status = warp::path!("maintenance").and(warp::get()).map({
+            let maintenance = self.maintenance.clone();
+            move || match maintenance {
+                None => reply::with_status(
+                    reply::json(&json!({ "error": "maintenance is not configured" })),
+                    StatusCode::NOT_FOUND,
+                ),
+                Some(ref maintenance) => reply::with_status(
+                    reply::json(&maintenance_status_to_json(&maintenance.status())),
+                    StatusCode::OK,
+                ),
+            }
+        });
+
+        let scrub_status = warp::path!("scrub").and(warp::get()).map({
+            let scrub = self.scrub.clone();
+            move || match scrub {
+                None => reply::with_status(
+                    reply::json(&json!({ "error": "scrub is not configured" })),
+                    StatusCode::NOT_FOUND,
+                ),
+                Some(ref scrub) => reply::with_status(
+                    reply::json(&scrub_progress_to_json(&scrub.progress())),
+                    StatusCode::OK,
+                ),
+            }
+        });
+
+        let health_status = warp::path!("health").and(warp::get()).map({
+            let health = self.ingestion_health.clone();
+            move || match health {
+                None => reply::with_status(
+                    reply::json(&json!({ "error": "ingestion health is not configured" })),
+                    StatusCode::NOT_FOUND,
+                ),
+                Some(ref health) => {
+                    let status = health.status();
+                    let code = if status.is_healthy() {
+                        StatusCode::OK
+                    } else {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    };
+                    let mut body = health_status_to_json(&status);
+                    body["progress"] = ingestion_progress_to_json(&health.progress().snapshot());
+                    reply::with_status(reply::json(&body), code)
+                }
+            }
+        });
+
+        let ingestion_status = warp::path!("ingestion").and(warp::get()).map({
+            let control = self.ingestion_control.clone();
+            move || match control {
+                None => ingestion_control_not_configured(),
+                Some(ref control) => reply::with_status(
+                    reply::json(&ingestion_control_status_to_json(&control.status())),
+                    StatusCode::OK,
+                ),
+            }
+        });
+
+        // pausing is asynchronous, clients poll the status until ingestion
+        // is paused.
+        let pause_ingestion = warp::path!("ingestion" / "pause")
+            .and(warp::post())
+            .and(authorized(self.token.clone()))
+            .map({
+                let control = self.ingestion_control.clone();
+                move || match control {
+                    None => ingestion_control_not_configured(),
+                    Some(ref control) => {
+                        control.pause();
+                        reply::with_status(
+                            reply::json(&ingestion_control_status_to_json(&control.status())),
+                            StatusCode::ACCEPTED,
+                        )
+                    }
+                }
+            });
+
+        let resume_ingestion = warp::path!("ingestion" / "resume")
+            .and(warp::post())
+            .and(authorized(self.token.clone()))
+            .map({
+                let control = self.ingestion_control.clone();
+                move || match control {
+                    None => ingestion_control_not_configured(),
+                    Some(ref control) => {
+                        control.resume();
+                        reply::with_status(
+                            reply::json(&ingestion_control_status_to_json(&control.status())),
+                            StatusCode::OK,
+                        )
+                    }
+                }
+            });
+
+        let journal_entries = warp::path!("ingestion" / "journal").and(warp::get()).map({
+            let journal = self.ingestion_journal.clone();
+            move || match journal {
+                None => ingestion_journal_not_configured(),
+                Some(ref journal) => reply::with_status(
+                    reply::json(&journal_entries_to_json(&journal.entries())),
+                    StatusCode::OK,
+                ),
+            }
+        });
+
+        // requests are applied asynchronously, clients poll the journal to
+        // see their result.
+        let retry_journal_entry = warp::path!("ingestion" / "journal" / u64 / "retry")
+            .and(warp::post())
+            .and(authorized(self.token.clone()))
+            .map({
+                let journal = self.ingestion_journal.clone();
+                move |number: u64| match journal {
+                    None => ingestion_journal_not_configured(),
+                    Some(ref journal) => journal_request_reply(journal.retry(number)),
+                }
+            });
+
+        let skip_journal_entry = warp::path!("ingestion" / "journal" / u64 / "skip")
+            .and(warp::post())
+            .and(authorized(self.token.clone()))
+            .map({
+                let journal = self.ingestion_journal.clone();
+                move |number: u64| match journal {
+                    None => ingestion_journal_not_configured(),
+                    Some(ref journal) => journal_request_reply(journal.skip(number)),
+                }
+            });
+
+        let top_emitters = warp::path!("ingestion" / "emitters")
+            .and(warp::get())
+            .and(warp::query::<TopEmittersQuery>())
+            .map({
+                let emitter_stats = self.emitter_stats.clone();
+                move |query: TopEmittersQuery| match emitter_stats {
+                    None => reply::with_status(
+                        reply::json(&json!({ "error": "emitter stats are not configured" })),
+                        StatusCode::NOT_FOUND,
+                    ),
+                    Some(ref stats) => {
+                        let limit = query.limit.unwrap_or_else(|| stats.top_k());
+                        reply::with_status(
+                            reply::json(&top_emitters_to_json(stats, limit)),
+                            StatusCode::OK,
+                        )
+                    }
+                }
+            });
+
+        let runtime_config = warp::path!("config")
+            .and(warp::get())
+            .and(authorized(self.token.clone()))
+            .map({
+                let runtime_config = self.runtime_config.clone();
+                move || match runtime_config {
+                    None => reply::with_status(
+                        reply::json(&json!({ "error": "runtime config is not configured" })),
+                        StatusCode::NOT_FOUND,
+                    ),
+                    Some(ref config) => {
+                        reply::with_status(reply::json(config.config()), StatusCode::OK)
+                    }
+                }
+            });
+
+        let metrics = warp::path!("metrics").and(warp::get()).map({
+            let prometheus = self.prometheus.clone();
+            move || match prometheus {
+                None => reply::with_status(
+                    "prometheus metrics are not configured".to_string(),
+                    StatusCode::NOT_FOUND,
+                ),
+                Some(ref prometheus) => match prometheus.encode() {
+                    Ok(text) => reply::with_status(text, StatusCode::OK),
+                    Err(err) => {
+                        reply::with_status(err.to_string(), StatusCode::INTERNAL_SERVER_ERROR)
+                    }
+                },
+            }
+        });
+
+        // profiles follow the paths of the go pprof http handlers, so that
+        // `go tool pprof` can fetch them directly.
+        let cpu_profile = warp::path!("debug" / "pprof" / "profile")
+            .and(warp::get())
+            .and(authorized(self.token.clone()))
+            .and(warp::query::<CpuProfileQuery>())
+            .then({
+                let profiler = self.profiler.clone();
+                move |query: CpuProfileQuery| {
+                    let profiler = profiler.clone();
+                    async move {
+                        match profiler {
+                            None => profiling_not_configured(),
+                            Some(profiler) => {
+                                let duration = profiler.cpu_profile_duration(query.seconds);
+                                profile_reply(profiler.cpu_profile(duration).await)
+                            }
+                        }
+                    }
+                }
+            });
+
+        let heap_profile = warp::path!("debug" / "pprof" / "heap")
+            .and(warp::get())
+            .and(authorized(self.token.clone()))
+            .then({
+                let profiler = self.profiler.clone();
+                move || {
+                    let profiler = profiler.clone();
+                    async move {
+                        match profiler {
+                            None => profiling_not_configured(),
+                            Some(profiler) => profile_reply(profiler.heap_profile().await),
+                        }
+                    }
+                }
+            });
+
+        let routes = backup_status
+            .or(start_backup)
+            .or(db_info)
+            .or(maintenance_status)
+            .or(scrub_status)
+            .or(health_status)
+            .or(ingestion_status)
+            .or(pause_ingestion)
+            .or(resume_ingestion)
+            .or(journal_entries)
+            .or(retry_journal_entry)
+            .or(skip_journal_entry)
+            .or(top_emitters)
+            .or(runtime_config)
+            .or(metrics)
+            .or(cpu_profile)
+            .or(heap_profile);
+
+        #[cfg(feature = "reorg-injection")]
+        let routes = routes.or(inject_ingestion_event(
+            self.token.clone(),
+            self.ingestion_control.clone(),
+        ));
+
+        let routes = routes.recover(unauthorized_reply);
+
+        info!(addr = %addr, "starting admin server");
+        let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(addr, async move {
+            ct.cancelled().await;
+        });
+        server.await
+    }
+}
+
+impl AdminToken {
+    pub fn new(token: String) -> Self {
+        AdminToken(Arc::new(token))
+    }
+
+    /// Returns true if the `authorization` header contains the token.
+    ///
+    /// Tokens are compared in constant time.
+    pub fn is_authorized(&self, authorization: Option<&str>) -> bool {
+        authorization
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| constant_time_eq(token.as_bytes(), self.0.as_bytes()))
+            .unwrap_or(false)
+    }
+}
+
+/// Compares `a` and `b` in a time that only depends on their length.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Rejects the requests without the admin token.
+fn authorized(token: Option<AdminToken>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and_then(move |authorization: Option<String>| {
+            let token = token.clone();
+            async move {
+                match token {
+                    Some(token) if token.is_authorized(authorization.as_deref()) => Ok(()),
+                    _ => Err(reject::custom(Unauthorized)),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/// Replies to requests rejected by [authorized].
+async fn unauthorized_reply(rejection: Rejection) -> Result<reply::Response, Rejection> {
+    if rejection.find::<Unauthorized>().is_some() {
+        let response = reply::with_status(
+            reply::json(&json!({ "error": "unauthorized" })),
+            StatusCode::UNAUTHORIZED,
+        );
+        return Ok(response.into_response());
+    }
+    Err(rejection)
+}
+
+fn profiling_not_configured() -> reply::Response {
+    reply::with_status(
+        "profiling is not configured".to_string(),
+        StatusCode::NOT_FOUND,
+    )
+    .into_response()
+}
+
+fn profile_reply(profile: Result<Vec<u8>, ProfilingError>) -> reply::Response {
+    match profile {
+        Ok(profile) => {
+            reply::with_header(profile, "content-type", "application/octet-stream").into_response()
+        }
+        Err(err @ ProfilingError::Busy) => {
+            reply::with_status(err.to_string(), StatusCode::CONFLICT).into_response()
+        }
+        Err(err @ ProfilingError::HeapUnsupported) => {
+            reply::with_status(err.to_string(), StatusCode::NOT_IMPLEMENTED).into_response()
+        }
+        Err(err) => {
+            warn!(error = ?err, "failed to capture profile");
+            reply::with_status(err.to_string(), StatusCode::INTERNAL_SERVER_ERROR).into_response()
+        }
+    }
+}
+
+fn backup_not_configured() -> reply::WithStatus<reply::Json> {
+    reply::with_status(
+        reply::json(&json!({ "error": "backups are not configured" })),
+        StatusCode::NOT_FOUND,
+    )
+}
+
+fn backup_status_to_json(status: &BackupStatus) -> serde_json::Value {
+    match status {
+        BackupStatus::Idle => json!({ "status": "idle" }),
+        BackupStatus::Copying { target } => json!({
+            "status": "copying",
+            "target": target,
+        }),
+        BackupStatus::Uploading { target, progress } => json!({
+            "status": "uploading",
+            "target": target,
+            "bytes": progress.bytes,
+        }),
+        BackupStatus::Completed {
+            target,
+            progress,
+            elapsed,
+        } => json!({
+            "status": "completed",
+            "target": target,
+            "bytes": progress.bytes,
+            "uploaded": progress.uploaded,
+            "elapsed_ms": elapsed.as_millis() as u64,
+        }),
+        BackupStatus::Failed { target, error } => json!({
+            "status": "failed",
+            "target": target,
+            "error": error,
+        }),
+    }
+}
+
+fn maintenance_status_to_json(status: &MaintenanceStatus) -> serde_json::Value {
+    match status {
+        MaintenanceStatus::Idle => json!({ "status": "idle" }),
+        MaintenanceStatus::Copying { progress } => json!({
+            "status": "copying",
+            "progress": maintenance_progress_to_json(progress),
+        }),
+        MaintenanceStatus::Interrupted { progress } => json!({
+            "status": "interrupted",
+            "progress": maintenance_progress_to_json(progress),
+        }),
+        MaintenanceStatus::Ready { progress, elapsed } => json!({
+            "status": "ready",
+            "progress": maintenance_progress_to_json(progress),
+            "elapsed_ms": elapsed.as_millis() as u64,
+        }),
+        MaintenanceStatus::Failed { error } => json!({
+            "status": "failed",
+            "error": error,
+        }),
+    }
+}
+
+fn maintenance_progress_to_json(progress: &MaintenanceProgress) -> serde_json::Value {
+    json!({
+        "size_before": progress.size_before,
+        "bytes_copied": progress.bytes_copied,
+    })
+}
+
+fn scrub_progress_to_json(progress: &ScrubProgress) -> serde_json::Value {
+    json!({
+        "passes": progress.passes,
+        "current_block": progress.current_block,
+        "verified_blocks": progress.verified_blocks,
+        "corrupted_blocks": progress.corrupted_blocks,
+        "repaired_blocks": progress.repaired_blocks,
+        "backfilled_state_updates": progress.backfilled_state_updates,
+    })
+}
+
+fn health_status_to_json(status: &IngestionHealthStatus) -> serde_json::Value {
+    match status {
+        IngestionHealthStatus::Healthy => json!({ "status": "healthy" }),
+        IngestionHealthStatus::Retrying { attempt, error } => json!({
+            "status": "retrying",
+            "attempt": attempt,
+            "error": error,
+        }),
+        IngestionHealthStatus::Failed { error } => json!({
+            "status": "failed",
+            "error": error,
+        }),
+        IngestionHealthStatus::Lagging { lag, max_lag } => json!({
+            "status": "lagging",
+            "lag": lag,
+            "max_lag": max_lag,
+        }),
+        IngestionHealthStatus::FinalizedLagging { lag, max_lag } => json!({
+            "status": "finalized_lagging",
+            "lag": lag,
+            "max_lag": max_lag,
+        }),
+        IngestionHealthStatus::LowDiskSpace {
+            free_space,
+            min_free_space,
+        } => json!({
+            "status": "low_disk_space",
+            "free_space": free_space,
+            "min_free_space": min_free_space,
+        }),
+    }
+}
+
+fn ingestion_progress_to_json(progress: &IngestionProgressSnapshot) -> serde_json::Value {
+    let last_ingested_at = progress
+        .last_ingested_at
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|t| t.as_secs());
+    json!({
+        "provider_head": progress.provider_head,
+        "node_head": progress.node_head,
+        "lag": progress.lag(),
+        "finalized": progress.finalized,
+        "finalized_lag": progress.finalized_lag(),
+        "blocks_per_second": progress.blocks_per_second,
+        "last_ingested_at": last_ingested_at,
+    })
+}
+
+fn database_info_to_json(info: &DatabaseInfo) -> serde_json::Value {
+    let tables: Vec<_> = info
+        .tables
+        .iter()
+        .map(|table| {
+            json!({
+                "name": table.name,
+                "entries": table.entries,
+                "size": table.size,
+            })
+        })
+        .collect();
+    json!({
+        "schema_version": info.schema_version,
+        "earliest_block": info.earliest_block.map(|b| b.to_string()),
+        "finalized_block": info.finalized_block.map(|b| b.to_string()),
+        "accepted_block": info.accepted_block.map(|b| b.to_string()),
+        "tables": tables,
+    })
+}
+
+fn ingestion_control_status_to_json(status: &IngestionControlStatus) -> serde_json::Value {
+    match status {
+        IngestionControlStatus::Running => json!({ "status": "running" }),
+        IngestionControlStatus::Pausing => json!({ "status": "pausing" }),
+        IngestionControlStatus::Paused => json!({ "status": "paused" }),
+    }
+}
+
+fn ingestion_control_not_configured() -> reply::WithStatus<reply::Json> {
+    reply::with_status(
+        reply::json(&json!({ "error": "ingestion control is not configured" })),
+        StatusCode::NOT_FOUND,
+    )
+}
+
+fn journal_entries_to_json(entries: &[(u64, JournalEntry)]) -> serde_json::Value {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|(number, entry)| {
+            json!({
+                "block_number": number,
+                "error": entry.error,
+                "attempts": entry.attempts,
+                "degraded": entry.degraded,
+                "last_failed_at": entry.last_failed_at,
+            })
+        })
+        .collect();
+    json!({ "entries": entries })
+}
+
+fn top_emitters_to_json(stats: &EmitterStats, limit: usize) -> serde_json::Value {
+    let total = stats.total();
+    let emitters: Vec<_> = stats
+        .top(limit)
+        .into_iter()
+        .map(|(address, volume)| {
+            json!({
+                "address": address.to_hex(),
+                "events": volume.events,
+                "bytes": volume.bytes,
+            })
+        })
+        .collect();
+    json!({
+        "total_events": total.events,
+        "total_bytes": total.bytes,
+        "emitters": emitters,
+    })
+}
+
+fn journal_request_reply(queued: bool) -> reply::WithStatus<reply::Json> {
+    if queued {
+        reply::with_status(
+            reply::json(&json!({ "status": "queued" })),
+            StatusCode::ACCEPTED,
+        )
+    } else {
+        reply::with_status(
+            reply::json(&json!({ "error": "block is not in the ingestion journal" })),
+            StatusCode::NOT_FOUND,
+        )
+    }
+}
+
+fn ingestion_journal_not_configured() -> reply::WithStatus<reply::Json> {
+    reply::with_status(
+        reply::json(&json!({ "error": "ingestion journal is not configured" })),
+        StatusCode::NOT_FOUND,
+    )
+}
+
+/// Route to inject synthetic reorgs and finality changes into ingestion.
+#[cfg(feature = "reorg-injection")]
+fn inject_ingestion_event(
+    token: Option<AdminToken>,
+    control: Option<IngestionControl>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    use crate::ingestion::InjectedEvent;
+
+    warp::path!("ingestion" / "inject")
+        .and(warp::post())
+        .and(authorized(token))
+        .and(warp::body::json())
+        .map(move |request: InjectEventRequest| match control {
+            None => ingestion_control_not_configured(),
+            Some(ref control) => {
+                let event = match request {
+                    InjectEventRequest::Reorg { depth } => InjectedEvent::Reorg { depth },
+                    InjectEventRequest::Finalize { block_number } => {
+                        InjectedEvent::Finalize { block_number }
+                    }
+                };
+                control.inject(event);
+                reply::with_status(
+                    reply::json(&json!({ "status": "queued" })),
+                    StatusCode::ACCEPTED,
+                )
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use warp::{http::StatusCode, Filter};
+
+    use super::{authorized, unauthorized_reply, AdminToken};
+
+    #[test]
+    fn test_admin_token() {
+        let token = AdminToken::new("secret".to_string());
+        assert!(token.is_authorized(Some("Bearer secret")));
+        assert!(!token.is_authorized(Some("Bearer secre")));
+        assert!(!token.is_authorized(Some("Bearer other")));
+        assert!(!token.is_authorized(Some("secret")));
+        assert!(!token.is_authorized(None));
+    }
+
+    #[tokio::test]
+    async fn test_write_routes_require_token() {
+        let route = |token: Option<AdminToken>| {
+            warp::path!("backup")
+                .and(warp::post())
+                .and(authorized(token))
+                .map(warp::reply)
+                .recover(unauthorized_reply)
+        };
+
+        let token = Some(AdminToken::new("secret".to_string()));
+        let response = warp::test::request()
+            .method("POST")
+            .path("/backup")
+            .header("authorization", "Bearer secret")
+            .reply(&route(token.clone()))
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/backup")
+            .reply(&route(token))
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // without a token, write routes are disabled.
+        let response = warp::test::request()
+            .method("POST")
+            .path("/backup")
+            .header("authorization", "Bearer secret")
+            .reply(&route(None))
+            .await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,9 +1,23 @@
 use anyhow::Result;
-use apibara_node::o11y::init_opentelemetry;
-use apibara_starknet::{set_ctrlc_handler, start_node, StartArgs};
+use apibara_node::o11y::{init_error_reporting, init_opentelemetry};
+use apibara_starknet::{
+    cli::{run_db_command, DbCommand},
+    set_ctrlc_handler, start_node, StartArgs,
+};
 use clap::{Parser, Subcommand};
 use tokio_util::sync::CancellationToken;
 
+#[cfg(feature = "jemalloc-profiling")]
+#[global_allocator]
+static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+/// Sample allocations from the start, so that heap profiles can be dumped
+/// at any time.
+#[cfg(feature = "jemalloc-profiling")]
+#[allow(non_upper_case_globals)]
+#[export_name = "malloc_conf"]
+pub static malloc_conf: &[u8] = b"prof:true,prof_active:true,lg_prof_sample:19\0";
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
@@ -14,17 +28,22 @@ struct Cli {
 #[derive(Subcommand)]
 enum CliCommand {
     /// Start the StarkNet source node.
-    Start(StartArgs),
+    Start(Box<StartArgs>),
+    /// Inspect and manage the node database.
+    #[command(subcommand)]
+    Db(Box<DbCommand>),
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let _error_reporting = init_error_reporting();
     init_opentelemetry()?;
 
     let cts = CancellationToken::new();
     set_ctrlc_handler(cts.clone())?;
 
     match Cli::parse().command {
-        CliCommand::Start(args) => start_node(args, cts).await,
+        CliCommand::Start(args) => start_node(*args, cts).await,
+        CliCommand::Db(command) => run_db_command(*command),
     }
 }
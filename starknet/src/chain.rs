@@ -0,0 +1,78 @@
+//! Adapt StarkNet to the generic node.
+
+use std::sync::Arc;
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::{
+    chain::ChainAdapter,
+    db::{
+        libmdbx::{EnvironmentKind, Error as MdbxError, Transaction, RW},
+        MigrationRunner,
+    },
+};
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+
+use crate::{
+    core::GlobalBlockId,
+    db::{self, tables, StorageReader},
+    ingestion::{self, IngestionStreamClient},
+    server::stream::IngestionStream,
+    stream::{DbBatchProducer, SequentialCursorProducer, StarkNetBlockDescriptor},
+};
+
+/// StarkNet data, read from the node storage.
+pub struct StarkNetChain<R: StorageReader + Send + Sync + 'static> {
+    ingestion: Arc<IngestionStreamClient>,
+    storage: Arc<R>,
+}
+
+impl<R> StarkNetChain<R>
+where
+    R: StorageReader + Send + Sync + 'static,
+{
+    pub fn new(ingestion: Arc<IngestionStreamClient>, storage: Arc<R>) -> Self {
+        StarkNetChain { ingestion, storage }
+    }
+
+    pub fn storage(&self) -> &Arc<R> {
+        &self.storage
+    }
+}
+
+#[apibara_node::async_trait]
+impl<R> ChainAdapter for StarkNetChain<R>
+where
+    R: StorageReader + Send + Sync + 'static,
+{
+    type Cursor = GlobalBlockId;
+    type Filter = v1alpha2::Filter;
+    type Block = v1alpha2::Block;
+    type IngestionStream = IngestionStream<ingestion::IngestionStream, BroadcastStreamRecvError>;
+    type CursorProducer = SequentialCursorProducer<R>;
+    type BatchProducer = DbBatchProducer<R>;
+    type BlockDescriptor = StarkNetBlockDescriptor;
+
+    fn ensure_tables<E: EnvironmentKind>(txn: &Transaction<RW, E>) -> Result<(), MdbxError> {
+        tables::ensure(txn)
+    }
+
+    fn migrations<E: EnvironmentKind>() -> MigrationRunner<E> {
+        db::migrations()
+    }
+
+    async fn subscribe(&self) -> Self::IngestionStream {
+        IngestionStream::new(self.ingestion.subscribe().await)
+    }
+
+    fn cursor_producer(&self) -> Self::CursorProducer {
+        SequentialCursorProducer::new(self.storage.clone())
+    }
+
+    fn batch_producer(&self) -> Self::BatchProducer {
+        DbBatchProducer::new(self.storage.clone())
+    }
+
+    fn block_descriptor(&self) -> Self::BlockDescriptor {
+        StarkNetBlockDescriptor
+    }
+}
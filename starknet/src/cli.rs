@@ -0,0 +1,274 @@
+//! Database tools.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use apibara_core::starknet::v1alpha2;
+use apibara_node::{
+    chain::prepare_database,
+    db::{
+        compact_environment, default_data_dir,
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentBuilder, MdbxEnvironmentExt, MdbxGeometry,
+    },
+};
+use clap::{Args, Subcommand};
+
+use crate::{
+    chain::StarkNetChain,
+    core::GlobalBlockId,
+    db::{self, DatabaseInfo, DatabaseStorage},
+    export::{export_blocks, ExportFormat},
+    import::import_blocks,
+    EncryptionArgs, MdbxArgs,
+};
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum DbCommand {
+    /// Print a summary of the data stored by the node.
+    Info(DbArgs),
+    /// Export a range of blocks to a flat file.
+    Export(ExportArgs),
+    /// Import blocks from a file created by the export command.
+    Import(ImportArgs),
+    /// Move old finalized blocks to a new archive shard.
+    ///
+    /// The node must be stopped while moving blocks.
+    Shard(ShardArgs),
+    /// Roll back storage to a finalized block, removing all blocks after it.
+    ///
+    /// The node must be stopped while rolling back.
+    Rollback(RollbackArgs),
+    /// Compact the database, returning the space of deleted data to the
+    /// file system.
+    ///
+    /// The node must be stopped while compacting.
+    Compact(DbArgs),
+}
+
+/// Select the node database.
+#[derive(Clone, Debug, Default, Args)]
+pub struct DbArgs {
+    /// Data directory. Defaults to `$XDG_DATA_HOME`.
+    #[arg(long, env)]
+    pub data: Option<PathBuf>,
+    /// Indexer name. Defaults to `starknet`.
+    #[arg(long, env)]
+    pub name: Option<String>,
+    #[command(flatten)]
+    pub encryption: EncryptionArgs,
+    #[command(flatten)]
+    pub mdbx: MdbxArgs,
+}
+
+/// Export blocks from storage.
+#[derive(Clone, Debug, Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub db: DbArgs,
+    /// First block to export.
+    #[arg(long, default_value_t = 0)]
+    pub start: u64,
+    /// Export blocks up to, but excluding, this block. Defaults to the
+    /// end of the canonical chain.
+    #[arg(long)]
+    pub end: Option<u64>,
+    /// Path to the output file.
+    #[arg(long, short)]
+    pub output: PathBuf,
+    /// Format of the output file.
+    #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+    pub format: ExportFormat,
+    /// Path to a JSON file containing the filter. Exports full blocks if
+    /// not set.
+    #[arg(long)]
+    pub filter: Option<PathBuf>,
+}
+
+/// Import blocks into storage.
+#[derive(Clone, Debug, Args)]
+pub struct ImportArgs {
+    #[command(flatten)]
+    pub db: DbArgs,
+    /// Path to the input file.
+    #[arg(long, short)]
+    pub input: PathBuf,
+    /// Format of the input file.
+    #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+    pub format: ExportFormat,
+}
+
+/// Move blocks to an archive shard.
+#[derive(Clone, Debug, Args)]
+pub struct ShardArgs {
+    #[command(flatten)]
+    pub db: DbArgs,
+    /// Move all blocks before this block.
+    #[arg(long)]
+    pub end: u64,
+    /// Directory of the new archive shard.
+    #[arg(long, short)]
+    pub output: PathBuf,
+}
+
+/// Roll back storage to a block.
+#[derive(Clone, Debug, Args)]
+pub struct RollbackArgs {
+    #[command(flatten)]
+    pub db: DbArgs,
+    /// The new head of the chain. Must be finalized.
+    #[arg(long)]
+    pub block: u64,
+}
+
+impl DbArgs {
+    /// Returns the path to the node database.
+    pub fn datadir(&self) -> Result<PathBuf> {
+        if let Some(datadir) = &self.data {
+            return Ok(datadir.clone());
+        }
+        let name = self.name.as_deref().unwrap_or("starknet");
+        default_data_dir()
+            .map(|p| p.join(name))
+            .context("no default data directory. use --data instead")
+    }
+
+    /// Opens the node database, with the same settings as the node.
+    pub fn open(&self) -> Result<Arc<Environment<NoWriteMap>>> {
+        let db = self.builder().open(&self.datadir()?)?;
+        Ok(Arc::new(db))
+    }
+
+    /// Returns the builder of the node database, with the geometry of the
+    /// node.
+    fn builder(&self) -> MdbxEnvironmentBuilder<NoWriteMap> {
+        let geometry = MdbxGeometry::from(self.mdbx.clone());
+        Environment::<NoWriteMap>::builder().with_geometry(&geometry)
+    }
+
+    /// Returns the storage over the given database, with the encryption key
+    /// if any.
+    pub fn storage(&self, db: Arc<Environment<NoWriteMap>>) -> Result<DatabaseStorage<NoWriteMap>> {
+        let storage = DatabaseStorage::new(db);
+        match self.encryption.key_provider() {
+            None => Ok(storage),
+            Some(provider) => Ok(storage.with_encryption(provider.encryption_key()?)),
+        }
+    }
+}
+
+pub fn run_db_command(command: DbCommand) -> Result<()> {
+    match command {
+        DbCommand::Info(args) => {
+            let info = db::database_info(args.open()?)?;
+            print_database_info(&info);
+            Ok(())
+        }
+        DbCommand::Export(args) => export(args),
+        DbCommand::Import(args) => import(args),
+        DbCommand::Shard(args) => {
+            let storage = args.db.storage(args.db.open()?)?;
+            let moved = db::split_shard(&storage, &args.output, args.end)?;
+            println!(
+                "moved {} blocks to {}. start the node with --archive-shard {}",
+                moved,
+                args.output.display(),
+                args.output.display()
+            );
+            Ok(())
+        }
+        DbCommand::Rollback(args) => {
+            let storage = args.db.storage(args.db.open()?)?;
+            let summary = db::rollback_storage(&storage, args.block)?;
+            println!(
+                "removed {} blocks from the canonical chain, deleted {} blocks. head: {}",
+                summary.removed_from_canonical_chain, summary.removed_blocks, summary.head
+            );
+            Ok(())
+        }
+        DbCommand::Compact(args) => {
+            let summary = compact_environment(args.builder(), &args.datadir()?)?;
+            println!(
+                "compacted database from {} to {} bytes",
+                summary.size_before, summary.size_after
+            );
+            Ok(())
+        }
+    }
+}
+
+fn export(args: ExportArgs) -> Result<()> {
+    let filter = match args.filter {
+        None => None,
+        Some(path) => {
+            let file = File::open(&path)
+                .with_context(|| format!("failed to open filter {}", path.display()))?;
+            let filter: v1alpha2::Filter = serde_json::from_reader(file)?;
+            Some(filter)
+        }
+    };
+
+    let storage = Arc::new(args.db.storage(args.db.open()?)?);
+    let output = File::create(&args.output)
+        .with_context(|| format!("failed to create {}", args.output.display()))?;
+    let end = args.end.unwrap_or(u64::MAX);
+
+    let summary = export_blocks(
+        storage,
+        args.start..end,
+        filter,
+        args.format,
+        BufWriter::new(output),
+    )?;
+    println!(
+        "exported {} records from {} blocks to {}",
+        summary.records,
+        summary.blocks,
+        args.output.display()
+    );
+    Ok(())
+}
+
+fn import(args: ImportArgs) -> Result<()> {
+    let db = args.db.open()?;
+    // blocks are written in the latest format.
+    prepare_database::<StarkNetChain<DatabaseStorage<NoWriteMap>>, _>(&db)?;
+
+    let storage = args.db.storage(db)?;
+    let input = File::open(&args.input)
+        .with_context(|| format!("failed to open {}", args.input.display()))?;
+    let summary = import_blocks(&storage, args.format, BufReader::new(input))?;
+    println!(
+        "imported {} blocks, skipped {} blocks. head: {}",
+        summary.imported,
+        summary.skipped,
+        block_or_none(&summary.head)
+    );
+    Ok(())
+}
+
+fn block_or_none(block: &Option<GlobalBlockId>) -> String {
+    block
+        .map(|b| b.to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
+
+fn print_database_info(info: &DatabaseInfo) {
+    println!("schema version:  {}", info.schema_version);
+    println!("earliest block:  {}", block_or_none(&info.earliest_block));
+    println!("finalized block: {}", block_or_none(&info.finalized_block));
+    println!("accepted block:  {}", block_or_none(&info.accepted_block));
+    println!();
+    println!("{:<24} {:>12} {:>16}", "table", "entries", "size");
+    for table in &info.tables {
+        let size = byte_unit::Byte::from_bytes(table.size as u128)
+            .get_appropriate_unit(true)
+            .to_string();
+        println!("{:<24} {:>12} {:>16}", table.name, table.entries, size);
+    }
+}
@@ -3,11 +3,13 @@ use std::fmt::{Debug, Display};
 use apibara_core::{node::v1alpha2::Cursor, starknet::v1alpha2};
 use starknet::core::types::{FieldElement, FromByteArrayError};
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BlockHash([u8; 32]);
 
 /// Global identifier for blocks.
-#[derive(Copy, Clone, PartialEq)]
+///
+/// Block ids are ordered by number, then by hash.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct GlobalBlockId(u64, BlockHash);
 
 pub type IngestionMessage = apibara_node::stream::IngestionMessage<GlobalBlockId>;
@@ -166,4 +168,30 @@ impl apibara_node::core::Cursor for GlobalBlockId {
     fn to_proto(&self) -> Cursor {
         self.to_cursor()
     }
+
+    fn order_key(&self) -> u64 {
+        self.number()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_node::core::Cursor;
+
+    use super::{BlockHash, GlobalBlockId};
+
+    #[test]
+    fn test_cursor_ordering() {
+        let hash = |b: u8| BlockHash::from_slice(&[b; 32]).unwrap();
+        let a = GlobalBlockId::new(10, hash(2));
+        let b = GlobalBlockId::new(11, hash(1));
+        let c = GlobalBlockId::new(11, hash(3));
+        assert!(a < b);
+        assert!(b < c);
+        assert_eq!(a.distance(&c), 1);
+        assert_eq!(c.distance(&a), 1);
+        assert_eq!(a.lag(&c), 1);
+        assert_eq!(c.lag(&a), 0);
+        assert_eq!(a.timestamp(), None);
+    }
 }
@@ -0,0 +1,19 @@
+//! L1 data availability of blocks.
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::Table;
+
+use crate::core::GlobalBlockId;
+
+/// Store how the state diff of finalized blocks was published on L1.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DataAvailabilityTable {}
+
+impl Table for DataAvailabilityTable {
+    type Key = GlobalBlockId;
+    type Value = v1alpha2::DataAvailability;
+
+    fn db_name() -> &'static str {
+        "DataAvailability"
+    }
+}
@@ -13,6 +13,10 @@ use crate::core::{BlockHash, GlobalBlockId};
 pub struct BlockBody {
     #[prost(message, repeated, tag = "1")]
     pub transactions: prost::alloc::vec::Vec<v1alpha2::Transaction>,
+    /// Index of the pending transactions not included in the previous
+    /// snapshot of the pending block.
+    #[prost(uint32, repeated, tag = "2")]
+    pub new_pending_transactions: prost::alloc::vec::Vec<u32>,
 }
 
 #[derive(Clone, PartialEq, Message)]
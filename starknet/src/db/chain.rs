@@ -15,3 +15,16 @@ impl Table for CanonicalChainTable {
         "CanonicalChain"
     }
 }
+
+/// Store the chain id of the network the database belongs to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChainIdTable {}
+
+impl Table for ChainIdTable {
+    type Key = ();
+    type Value = v1alpha2::FieldElement;
+
+    fn db_name() -> &'static str {
+        "ChainId"
+    }
+}
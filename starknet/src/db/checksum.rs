@@ -0,0 +1,179 @@
+//! Block data checksums.
+//!
+//! A CRC32 checksum of the block header, body, receipts and state update is
+//! stored when the block is added to the canonical chain. The checksum is
+//! computed over the data as stored (compressed and, if enabled, encrypted)
+//! so that it can be verified without decoding the block.
+//!
+//! The block status is not included since it changes after the block is
+//! ingested.
+
+use apibara_node::db::{
+    libmdbx::{self, EnvironmentKind, Transaction, TransactionKind},
+    MdbxTransactionExt, Table,
+};
+use crc32fast::Hasher;
+use prost::Message;
+
+use crate::core::GlobalBlockId;
+
+use super::tables;
+
+/// Store the checksum of block data.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockChecksumTable {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BlockChecksum {
+    #[prost(fixed32, tag = "1")]
+    pub checksum: u32,
+}
+
+impl Table for BlockChecksumTable {
+    type Key = GlobalBlockId;
+    type Value = BlockChecksum;
+
+    fn db_name() -> &'static str {
+        "BlockChecksum"
+    }
+}
+
+/// Result of verifying a block checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// The stored data matches the checksum.
+    Valid,
+    /// The block was stored before checksums were introduced.
+    Missing { actual: u32 },
+    /// The stored data doesn't match the checksum.
+    Mismatch { expected: u32, actual: u32 },
+}
+
+/// Computes the checksum of the data stored for the given block.
+pub fn compute_block_checksum<K, E>(
+    txn: &Transaction<'_, K, E>,
+    id: &GlobalBlockId,
+) -> Result<u32, libmdbx::Error>
+where
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    let mut hasher = Hasher::new();
+    update_with_entry::<tables::BlockHeaderTable, K, E>(&mut hasher, txn, id)?;
+    update_with_entry::<tables::BlockBodyTable, K, E>(&mut hasher, txn, id)?;
+    update_with_entry::<tables::BlockReceiptsTable, K, E>(&mut hasher, txn, id)?;
+    update_with_entry::<tables::StateUpdateTable, K, E>(&mut hasher, txn, id)?;
+    Ok(hasher.finalize())
+}
+
+/// Compares the data stored for the given block with its checksum.
+pub fn verify_block_checksum<K, E>(
+    txn: &Transaction<'_, K, E>,
+    id: &GlobalBlockId,
+) -> Result<ChecksumStatus, libmdbx::Error>
+where
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    let actual = compute_block_checksum(txn, id)?;
+    let status = match txn.open_table::<BlockChecksumTable>()?.get(id)? {
+        None => ChecksumStatus::Missing { actual },
+        Some(stored) if stored.checksum == actual => ChecksumStatus::Valid,
+        Some(stored) => ChecksumStatus::Mismatch {
+            expected: stored.checksum,
+            actual,
+        },
+    };
+    Ok(status)
+}
+
+/// Adds the entry to the checksum, prefixed by its length so that missing and
+/// empty values are different.
+fn update_with_entry<T, K, E>(
+    hasher: &mut Hasher,
+    txn: &Transaction<'_, K, E>,
+    id: &GlobalBlockId,
+) -> Result<(), libmdbx::Error>
+where
+    T: Table<Key = GlobalBlockId>,
+    K: TransactionKind,
+    E: EnvironmentKind,
+{
+    match txn.open_table::<T>()?.get_bytes(id)? {
+        None => hasher.update(&[0]),
+        Some(data) => {
+            hasher.update(&[1]);
+            hasher.update(&(data.len() as u64).to_be_bytes());
+            hasher.update(&data);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::starknet::v1alpha2;
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        core::{BlockHash, GlobalBlockId},
+        db::{tables, BlockBody, DatabaseStorage, StorageWriter},
+    };
+
+    use super::ChecksumStatus;
+
+    #[test]
+    fn test_verify_block_checksum() {
+        let path = tempdir().unwrap();
+        let db = Arc::new(Environment::<NoWriteMap>::open(path.path()).unwrap());
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure(&txn).unwrap();
+        txn.commit().unwrap();
+        let storage = DatabaseStorage::new(db);
+
+        let id = GlobalBlockId::new(0, BlockHash::from_slice(&[1; 32]).unwrap());
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_status(&id, v1alpha2::BlockStatus::AcceptedOnL1)
+            .unwrap();
+        txn.write_header(&id, v1alpha2::BlockHeader::default())
+            .unwrap();
+        txn.write_body(&id, BlockBody::default()).unwrap();
+        txn.write_receipts(&id, Vec::default()).unwrap();
+        txn.extend_canonical_chain(&id).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(storage.verify_checksum(&id).unwrap(), ChecksumStatus::Valid);
+
+        // status is not part of the checksum.
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_status(&id, v1alpha2::BlockStatus::AcceptedOnL2)
+            .unwrap();
+        txn.commit().unwrap();
+        assert_eq!(storage.verify_checksum(&id).unwrap(), ChecksumStatus::Valid);
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.write_body(
+            &id,
+            BlockBody {
+                transactions: vec![v1alpha2::Transaction::default()],
+                ..BlockBody::default()
+            },
+        )
+        .unwrap();
+        txn.commit().unwrap();
+        assert!(matches!(
+            storage.verify_checksum(&id).unwrap(),
+            ChecksumStatus::Mismatch { .. }
+        ));
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.update_checksum(&id).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(storage.verify_checksum(&id).unwrap(), ChecksumStatus::Valid);
+    }
+}
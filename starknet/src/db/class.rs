@@ -0,0 +1,50 @@
+//! Contract classes.
+//!
+//! Class definitions are stored once, keyed by their class hash. Blocks
+//! reference classes through the declared contracts in their state update.
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{KeyDecodeError, Table, TableKey};
+
+use super::compression::CompressedData;
+
+/// The hash of a contract class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassHash([u8; 32]);
+
+/// Store class definitions by class hash.
+///
+/// Definitions are stored as compressed JSON, as returned by the RPC.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContractClassTable {}
+
+impl Table for ContractClassTable {
+    type Key = ClassHash;
+    type Value = CompressedData;
+
+    fn db_name() -> &'static str {
+        "ContractClass"
+    }
+}
+
+impl From<&v1alpha2::FieldElement> for ClassHash {
+    fn from(felt: &v1alpha2::FieldElement) -> Self {
+        ClassHash(felt.to_bytes())
+    }
+}
+
+impl TableKey for ClassHash {
+    type Encoded = [u8; 32];
+
+    fn encode(&self) -> Self::Encoded {
+        self.0
+    }
+
+    fn decode(b: &[u8]) -> Result<Self, KeyDecodeError> {
+        let hash = b.try_into().map_err(|_| KeyDecodeError::InvalidByteSize {
+            expected: 32,
+            actual: b.len(),
+        })?;
+        Ok(ClassHash(hash))
+    }
+}
@@ -0,0 +1,409 @@
+//! Compression of block data.
+//!
+//! Block bodies and receipts are compressed with zstd. Receipts are very
+//! repetitive, so data is compressed with a dictionary trained on chain data
+//! when one is available.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use apibara_node::db::{
+    libmdbx::{self, EnvironmentKind, Transaction, TransactionKind},
+    MdbxErrorExt, MdbxTransactionExt, Table,
+};
+use prost::{
+    encoding::{self, WireType},
+    DecodeError, Message,
+};
+
+use super::encryption::{EncryptionError, EncryptionKey};
+
+/// Compression level used when writing block data.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Maximum size of a trained dictionary.
+pub const MAX_DICTIONARY_SIZE: usize = 112_640;
+
+/// Compressed data, together with the dictionary used to compress it.
+#[derive(Clone, PartialEq, Message)]
+pub struct CompressedData {
+    #[prost(bytes, tag = "1")]
+    pub data: prost::alloc::vec::Vec<u8>,
+    /// Id of the compression dictionary, `0` if no dictionary was used.
+    #[prost(fixed64, tag = "2")]
+    pub dictionary: u64,
+    /// Size of the uncompressed data.
+    #[prost(fixed64, tag = "3")]
+    pub size: u64,
+    /// Nonce used to encrypt the data, empty if the data is not encrypted.
+    #[prost(bytes, tag = "4")]
+    pub nonce: prost::alloc::vec::Vec<u8>,
+}
+
+/// A zstd dictionary.
+#[derive(Clone, PartialEq, Message)]
+pub struct CompressionDictionary {
+    #[prost(bytes, tag = "1")]
+    pub data: prost::alloc::vec::Vec<u8>,
+}
+
+/// Store compression dictionaries by id.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompressionDictionaryTable {}
+
+impl Table for CompressionDictionaryTable {
+    type Key = u64;
+    type Value = CompressionDictionary;
+
+    fn db_name() -> &'static str {
+        "CompressionDictionary"
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompressionError {
+    #[error("zstd operation failed")]
+    Zstd(#[from] std::io::Error),
+    #[error("compression dictionary {0} not found")]
+    MissingDictionary(u64),
+}
+
+impl CompressedData {
+    /// Compress `data` with the given dictionary.
+    pub fn compress(
+        data: &[u8],
+        dictionary: Option<(u64, &[u8])>,
+    ) -> Result<Self, CompressionError> {
+        let size = data.len() as u64;
+        match dictionary {
+            None => {
+                let data = zstd::bulk::compress(data, COMPRESSION_LEVEL)?;
+                Ok(CompressedData {
+                    data,
+                    dictionary: 0,
+                    size,
+                    nonce: Vec::default(),
+                })
+            }
+            Some((id, dictionary)) => {
+                let mut compressor =
+                    zstd::bulk::Compressor::with_dictionary(COMPRESSION_LEVEL, dictionary)?;
+                let data = compressor.compress(data)?;
+                Ok(CompressedData {
+                    data,
+                    dictionary: id,
+                    size,
+                    nonce: Vec::default(),
+                })
+            }
+        }
+    }
+
+    /// Returns true if the data was compressed with a dictionary.
+    pub fn has_dictionary(&self) -> bool {
+        self.dictionary != 0
+    }
+
+    /// Returns true if the compressed data is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        !self.nonce.is_empty()
+    }
+
+    /// Encrypt the compressed data with the given key.
+    pub fn encrypt(mut self, key: &EncryptionKey) -> Result<Self, EncryptionError> {
+        let (data, nonce) = key.encrypt(&self.data)?;
+        self.data = data;
+        self.nonce = nonce;
+        Ok(self)
+    }
+
+    /// Returns the compressed data, decrypted if needed.
+    pub fn decrypt(
+        &self,
+        key: Option<&EncryptionKey>,
+    ) -> Result<Cow<'_, CompressedData>, EncryptionError> {
+        if !self.is_encrypted() {
+            return Ok(Cow::Borrowed(self));
+        }
+        let key = key.ok_or(EncryptionError::MissingKey)?;
+        let data = key.decrypt(&self.data, &self.nonce)?;
+        Ok(Cow::Owned(CompressedData {
+            data,
+            dictionary: self.dictionary,
+            size: self.size,
+            nonce: Vec::default(),
+        }))
+    }
+
+    /// Decompress the data with the given dictionary.
+    ///
+    /// The dictionary must be the one with id `self.dictionary`.
+    pub fn decompress(&self, dictionary: Option<&[u8]>) -> Result<Vec<u8>, CompressionError> {
+        decompress(&self.data, self.dictionary, self.size, dictionary)
+    }
+}
+
+/// A view of [CompressedData] that borrows its buffers from the slice it was
+/// decoded from.
+///
+/// Storage reads decode values straight from the database pages with this
+/// type, so the compressed payload is never copied before decompression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedDataRef<'a> {
+    pub data: Cow<'a, [u8]>,
+    pub dictionary: u64,
+    pub size: u64,
+    pub nonce: &'a [u8],
+}
+
+impl<'a> CompressedDataRef<'a> {
+    /// Decode the protobuf encoding of [CompressedData] without copying its buffers.
+    pub fn decode(mut buf: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut compressed = CompressedDataRef {
+            data: Cow::Borrowed(&[]),
+            dictionary: 0,
+            size: 0,
+            nonce: &[],
+        };
+        while !buf.is_empty() {
+            let (tag, wire_type) = encoding::decode_key(&mut buf)?;
+            match (tag, wire_type) {
+                (1 | 4, WireType::LengthDelimited) => {
+                    let len = encoding::decode_varint(&mut buf)? as usize;
+                    if len > buf.len() {
+                        return Err(DecodeError::new("buffer underflow"));
+                    }
+                    let (value, rest) = buf.split_at(len);
+                    buf = rest;
+                    if tag == 1 {
+                        compressed.data = Cow::Borrowed(value);
+                    } else {
+                        compressed.nonce = value;
+                    }
+                }
+                (2 | 3, WireType::SixtyFourBit) => {
+                    if buf.len() < 8 {
+                        return Err(DecodeError::new("buffer underflow"));
+                    }
+                    let (value, rest) = buf.split_at(8);
+                    buf = rest;
+                    let value = u64::from_le_bytes(value.try_into().expect("slice of 8 bytes"));
+                    if tag == 2 {
+                        compressed.dictionary = value;
+                    } else {
+                        compressed.size = value;
+                    }
+                }
+                _ => encoding::skip_field(
+                    wire_type,
+                    tag,
+                    &mut buf,
+                    encoding::DecodeContext::default(),
+                )?,
+            }
+        }
+        Ok(compressed)
+    }
+
+    /// Returns true if the data was compressed with a dictionary.
+    pub fn has_dictionary(&self) -> bool {
+        self.dictionary != 0
+    }
+
+    /// Returns true if the compressed data is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        !self.nonce.is_empty()
+    }
+
+    /// Returns the compressed data, decrypted if needed.
+    ///
+    /// Only encrypted data is copied.
+    pub fn decrypt(self, key: Option<&EncryptionKey>) -> Result<Self, EncryptionError> {
+        if !self.is_encrypted() {
+            return Ok(self);
+        }
+        let key = key.ok_or(EncryptionError::MissingKey)?;
+        let data = key.decrypt(&self.data, self.nonce)?;
+        Ok(CompressedDataRef {
+            data: Cow::Owned(data),
+            nonce: &[],
+            ..self
+        })
+    }
+
+    /// Decompress the data with the given dictionary.
+    ///
+    /// The dictionary must be the one with id `self.dictionary`.
+    pub fn decompress(&self, dictionary: Option<&[u8]>) -> Result<Vec<u8>, CompressionError> {
+        decompress(&self.data, self.dictionary, self.size, dictionary)
+    }
+}
+
+fn decompress(
+    data: &[u8],
+    dictionary_id: u64,
+    size: u64,
+    dictionary: Option<&[u8]>,
+) -> Result<Vec<u8>, CompressionError> {
+    let size = size as usize;
+    match dictionary {
+        None if dictionary_id != 0 => Err(CompressionError::MissingDictionary(dictionary_id)),
+        None => Ok(zstd::bulk::decompress(data, size)?),
+        Some(dictionary) => {
+            let mut decompressor = zstd::bulk::Decompressor::with_dictionary(dictionary)?;
+            Ok(decompressor.decompress(data, size)?)
+        }
+    }
+}
+
+/// A cached dictionary, with its id.
+pub type CachedValue = (u64, Arc<Vec<u8>>);
+
+/// Cache of compression dictionaries.
+///
+/// Dictionaries are never modified once written, so they can be cached
+/// for the lifetime of the process.
+#[derive(Debug, Clone, Default)]
+pub struct DictionaryCache {
+    dictionaries: Arc<RwLock<HashMap<u64, Arc<Vec<u8>>>>>,
+}
+
+impl DictionaryCache {
+    /// Returns the dictionary with the given id, loading it from the database if needed.
+    pub fn get<K: TransactionKind, E: EnvironmentKind>(
+        &self,
+        txn: &Transaction<'_, K, E>,
+        id: u64,
+    ) -> Result<Option<Arc<Vec<u8>>>, libmdbx::Error> {
+        if let Some(dictionary) = self
+            .dictionaries
+            .read()
+            .expect("dictionary cache lock")
+            .get(&id)
+        {
+            return Ok(Some(dictionary.clone()));
+        }
+
+        let mut cursor = txn.open_cursor::<CompressionDictionaryTable>()?;
+        match cursor.seek_exact(&id)? {
+            None => Ok(None),
+            Some((_, dictionary)) => Ok(Some(self.insert(id, dictionary.data))),
+        }
+    }
+
+    /// Returns the most recent dictionary, used to compress new data.
+    pub fn latest<K: TransactionKind, E: EnvironmentKind>(
+        &self,
+        txn: &Transaction<'_, K, E>,
+    ) -> Result<Option<CachedValue>, libmdbx::Error> {
+        let mut cursor = txn.open_cursor::<CompressionDictionaryTable>()?;
+        match cursor.last()? {
+            None => Ok(None),
+            Some((id, dictionary)) => Ok(Some((id, self.insert(id, dictionary.data)))),
+        }
+    }
+
+    /// Decrypt and decompress the [CompressedData] encoded in `bytes`, then
+    /// decode it as a message of type `M`.
+    ///
+    /// `bytes` is usually borrowed straight from the database page.
+    pub fn decode<M: Message + Default, K: TransactionKind, E: EnvironmentKind>(
+        &self,
+        txn: &Transaction<'_, K, E>,
+        bytes: &[u8],
+        encryption: Option<&EncryptionKey>,
+    ) -> Result<M, libmdbx::Error> {
+        let compressed = CompressedDataRef::decode(bytes)
+            .map_err(libmdbx::Error::decode_error)?
+            .decrypt(encryption)
+            .map_err(libmdbx::Error::decode_error)?;
+        let data = if compressed.has_dictionary() {
+            let dictionary = self.get(txn, compressed.dictionary)?.ok_or_else(|| {
+                libmdbx::Error::decode_error(CompressionError::MissingDictionary(
+                    compressed.dictionary,
+                ))
+            })?;
+            compressed.decompress(Some(&dictionary))
+        } else {
+            compressed.decompress(None)
+        }
+        .map_err(libmdbx::Error::decode_error)?;
+        M::decode(data.as_slice()).map_err(libmdbx::Error::decode_error)
+    }
+
+    fn insert(&self, id: u64, dictionary: Vec<u8>) -> Arc<Vec<u8>> {
+        let dictionary = Arc::new(dictionary);
+        self.dictionaries
+            .write()
+            .expect("dictionary cache lock")
+            .insert(id, dictionary.clone());
+        dictionary
+    }
+}
+
+/// Train a new dictionary from the given samples.
+pub fn train_dictionary(samples: &[Vec<u8>]) -> Result<Vec<u8>, CompressionError> {
+    Ok(zstd::dict::from_samples(samples, MAX_DICTIONARY_SIZE)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use prost::Message;
+
+    use super::{train_dictionary, CompressedData, CompressedDataRef, CompressionError};
+    use crate::db::encryption::EncryptionKey;
+
+    fn sample(i: usize) -> Vec<u8> {
+        format!(
+            "{{\"transaction_hash\":\"0x{:064x}\",\"actual_fee\":\"0x{:x}\",\"status\":\"ACCEPTED_ON_L2\",\"events\":[]}}",
+            i * 7919,
+            i
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_compress_without_dictionary() {
+        let data = sample(1).repeat(10);
+        let compressed = CompressedData::compress(&data, None).unwrap();
+        assert!(!compressed.has_dictionary());
+        assert!(compressed.data.len() < data.len());
+        assert_eq!(compressed.decompress(None).unwrap(), data);
+    }
+
+    #[test]
+    fn test_compress_with_dictionary() {
+        let samples: Vec<_> = (0..1000).map(sample).collect();
+        let dictionary = train_dictionary(&samples).unwrap();
+
+        let data = sample(12345);
+        let compressed = CompressedData::compress(&data, Some((1, &dictionary))).unwrap();
+        assert_eq!(compressed.dictionary, 1);
+        assert_eq!(compressed.decompress(Some(&dictionary)).unwrap(), data);
+        assert!(matches!(
+            compressed.decompress(None),
+            Err(CompressionError::MissingDictionary(1))
+        ));
+    }
+
+    #[test]
+    fn test_decode_borrowed_compressed_data() {
+        let data = sample(1).repeat(10);
+        let compressed = CompressedData::compress(&data, None).unwrap();
+        let bytes = compressed.encode_to_vec();
+        let borrowed = CompressedDataRef::decode(&bytes).unwrap();
+        assert_eq!(borrowed.data.as_ref(), compressed.data.as_slice());
+        assert!(!borrowed.is_encrypted());
+        assert_eq!(borrowed.decompress(None).unwrap(), data);
+
+        let key = EncryptionKey::from_bytes(&[7; 32]).unwrap();
+        let bytes = compressed.encrypt(&key).unwrap().encode_to_vec();
+        let borrowed = CompressedDataRef::decode(&bytes).unwrap();
+        assert!(borrowed.is_encrypted());
+        let decrypted = borrowed.decrypt(Some(&key)).unwrap();
+        assert_eq!(decrypted.decompress(None).unwrap(), data);
+    }
+}
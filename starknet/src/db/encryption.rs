@@ -0,0 +1,199 @@
+//! Encryption of block data at rest.
+//!
+//! Block bodies, receipts and class definitions are encrypted with
+//! AES-256-GCM after compression. Each value is encrypted with a random
+//! nonce, stored next to the ciphertext. Values written without a key are
+//! stored in clear, so encryption can be enabled on an existing database.
+//!
+//! Headers, block status, state updates and the canonical chain are not
+//! encrypted.
+//!
+//! The key is loaded by a [KeyProvider] when the node starts. Deployments
+//! that keep their keys in a KMS use envelope encryption: the key is stored
+//! encrypted by a KMS key, and [CommandKeyProvider] runs the command that
+//! decrypts it.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::Arc,
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+
+/// Size of the encryption key, in bytes.
+pub const ENCRYPTION_KEY_SIZE: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("failed to read encryption key")]
+    Io(#[from] std::io::Error),
+    #[error("encryption key must be {ENCRYPTION_KEY_SIZE} bytes, or {} hex characters", ENCRYPTION_KEY_SIZE * 2)]
+    InvalidKey,
+    #[error("data is encrypted but no encryption key was provided")]
+    MissingKey,
+    #[error("failed to encrypt data")]
+    Encrypt,
+    #[error("failed to decrypt data. is the encryption key correct?")]
+    Decrypt,
+    #[error("encryption key command failed: {0}")]
+    KeyCommand(String),
+}
+
+/// Loads the key used to encrypt block data.
+pub trait KeyProvider: Send + Sync {
+    fn encryption_key(&self) -> Result<EncryptionKey, EncryptionError>;
+}
+
+/// Reads the key from a file, see [EncryptionKey::from_file].
+#[derive(Debug, Clone)]
+pub struct FileKeyProvider {
+    path: PathBuf,
+}
+
+/// Runs a shell command that prints the key, either raw or hex encoded.
+///
+/// This is the hook used to decrypt a key protected by a KMS, for example
+/// `aws kms decrypt --ciphertext-blob fileb://key.enc --query Plaintext
+/// --output text | base64 -d`.
+#[derive(Debug, Clone)]
+pub struct CommandKeyProvider {
+    command: String,
+}
+
+/// The key used to encrypt and decrypt block data.
+#[derive(Clone)]
+pub struct EncryptionKey {
+    cipher: Arc<Aes256Gcm>,
+}
+
+impl EncryptionKey {
+    /// Creates a new key from its raw bytes.
+    pub fn from_bytes(key: &[u8]) -> Result<Self, EncryptionError> {
+        let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| EncryptionError::InvalidKey)?;
+        Ok(EncryptionKey {
+            cipher: Arc::new(cipher),
+        })
+    }
+
+    /// Reads the key from a file, either as raw bytes or hex encoded.
+    pub fn from_file(path: &Path) -> Result<Self, EncryptionError> {
+        Self::from_encoded(&fs::read(path)?)
+    }
+
+    /// Parses the key, either as raw bytes or hex encoded.
+    pub fn from_encoded(content: &[u8]) -> Result<Self, EncryptionError> {
+        if content.len() == ENCRYPTION_KEY_SIZE {
+            return Self::from_bytes(content);
+        }
+        let content = std::str::from_utf8(content).map_err(|_| EncryptionError::InvalidKey)?;
+        let content = content.trim();
+        let content = content.strip_prefix("0x").unwrap_or(content);
+        let key = hex::decode(content).map_err(|_| EncryptionError::InvalidKey)?;
+        Self::from_bytes(&key)
+    }
+
+    /// Encrypts `data`, returning the ciphertext and the nonce used.
+    pub fn encrypt(&self, data: &[u8]) -> Result<(Vec<u8>, Vec<u8>), EncryptionError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, data)
+            .map_err(|_| EncryptionError::Encrypt)?;
+        Ok((ciphertext, nonce.to_vec()))
+    }
+
+    /// Decrypts `data` encrypted with the given nonce.
+    pub fn decrypt(&self, data: &[u8], nonce: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if nonce.len() != 12 {
+            return Err(EncryptionError::Decrypt);
+        }
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), data)
+            .map_err(|_| EncryptionError::Decrypt)
+    }
+}
+
+impl FileKeyProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileKeyProvider { path: path.into() }
+    }
+}
+
+impl KeyProvider for FileKeyProvider {
+    fn encryption_key(&self) -> Result<EncryptionKey, EncryptionError> {
+        EncryptionKey::from_file(&self.path)
+    }
+}
+
+impl CommandKeyProvider {
+    pub fn new(command: impl Into<String>) -> Self {
+        CommandKeyProvider {
+            command: command.into(),
+        }
+    }
+}
+
+impl KeyProvider for CommandKeyProvider {
+    fn encryption_key(&self) -> Result<EncryptionKey, EncryptionError> {
+        // errors of the command are shown in the node logs.
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::null())
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(EncryptionError::KeyCommand(output.status.to_string()));
+        }
+        EncryptionKey::from_encoded(&output.stdout)
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(<redacted>)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandKeyProvider, EncryptionError, EncryptionKey, KeyProvider};
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let key = EncryptionKey::from_bytes(&[7; 32]).unwrap();
+        let (ciphertext, nonce) = key.encrypt(b"block data").unwrap();
+        assert_ne!(ciphertext.as_slice(), b"block data");
+        assert_eq!(key.decrypt(&ciphertext, &nonce).unwrap(), b"block data");
+
+        let other = EncryptionKey::from_bytes(&[8; 32]).unwrap();
+        assert!(matches!(
+            other.decrypt(&ciphertext, &nonce),
+            Err(EncryptionError::Decrypt)
+        ));
+        assert!(EncryptionKey::from_bytes(&[7; 16]).is_err());
+    }
+
+    #[test]
+    fn test_command_key_provider() {
+        let key = CommandKeyProvider::new(format!("echo {}", "07".repeat(32)))
+            .encryption_key()
+            .unwrap();
+        let (ciphertext, nonce) = key.encrypt(b"block data").unwrap();
+        let expected = EncryptionKey::from_bytes(&[7; 32]).unwrap();
+        assert_eq!(
+            expected.decrypt(&ciphertext, &nonce).unwrap(),
+            b"block data"
+        );
+
+        assert!(matches!(
+            CommandKeyProvider::new("exit 1").encryption_key(),
+            Err(EncryptionError::KeyCommand(_))
+        ));
+    }
+}
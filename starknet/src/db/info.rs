@@ -0,0 +1,87 @@
+//! Summary of the database content.
+
+use std::{borrow::Cow, sync::Arc};
+
+use apibara_node::db::{
+    libmdbx::{self, Environment, EnvironmentKind},
+    tables::SchemaVersionTable,
+    MdbxTransactionExt,
+};
+
+use crate::core::GlobalBlockId;
+
+use super::{DatabaseStorage, StorageReader};
+
+/// Summary of the data stored by the node.
+#[derive(Debug, Clone)]
+pub struct DatabaseInfo {
+    /// Schema version of the database.
+    pub schema_version: u64,
+    /// Earliest block available in storage.
+    pub earliest_block: Option<GlobalBlockId>,
+    /// Most recent finalized block.
+    pub finalized_block: Option<GlobalBlockId>,
+    /// Most recent accepted block. Ingestion resumes from this block.
+    pub accepted_block: Option<GlobalBlockId>,
+    /// Size of each table.
+    pub tables: Vec<TableInfo>,
+}
+
+/// Size of a table.
+#[derive(Debug, Clone)]
+pub struct TableInfo {
+    pub name: String,
+    pub entries: usize,
+    /// Size of all pages used by the table, in bytes.
+    pub size: usize,
+}
+
+/// Returns a summary of the data stored in the database.
+pub fn database_info<E: EnvironmentKind>(
+    db: Arc<Environment<E>>,
+) -> Result<DatabaseInfo, libmdbx::Error> {
+    let txn = db.begin_ro_txn()?;
+
+    // the schema version table doesn't exist before the first migration.
+    let schema_version = match txn.open_table::<SchemaVersionTable>() {
+        Err(libmdbx::Error::NotFound) => 0,
+        Err(err) => return Err(err),
+        Ok(table) => table.get(&())?.and_then(|s| s.version).unwrap_or(0),
+    };
+
+    // the main database contains the name of all other databases.
+    let main_db = txn.open_db(None)?;
+    let mut names = Vec::new();
+    let mut cursor = txn.cursor(&main_db)?;
+    let mut item = cursor.first::<Cow<'_, [u8]>, Cow<'_, [u8]>>()?;
+    while let Some((name, _)) = item {
+        names.push(String::from_utf8_lossy(&name).into_owned());
+        item = cursor.next::<Cow<'_, [u8]>, Cow<'_, [u8]>>()?;
+    }
+
+    let mut tables = Vec::with_capacity(names.len());
+    for name in names {
+        let table_db = txn.open_db(Some(&name))?;
+        let stat = txn.db_stat(&table_db)?;
+        let pages = stat.branch_pages() + stat.leaf_pages() + stat.overflow_pages();
+        tables.push(TableInfo {
+            name,
+            entries: stat.entries(),
+            size: pages * stat.page_size() as usize,
+        });
+    }
+    txn.commit()?;
+
+    let storage = DatabaseStorage::new(db);
+    let earliest_block = storage.earliest_available_block()?;
+    let finalized_block = storage.highest_finalized_block()?;
+    let accepted_block = storage.highest_accepted_block()?;
+
+    Ok(DatabaseInfo {
+        schema_version,
+        earliest_block,
+        finalized_block,
+        accepted_block,
+        tables,
+    })
+}
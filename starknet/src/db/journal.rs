@@ -0,0 +1,33 @@
+//! Blocks that repeatedly failed to ingest.
+
+use apibara_node::db::Table;
+use prost::Message;
+
+/// Store the blocks that repeatedly failed to ingest, by block number.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IngestionJournalTable {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct JournalEntry {
+    /// The last error.
+    #[prost(string, tag = "1")]
+    pub error: String,
+    /// Number of failed attempts.
+    #[prost(uint32, tag = "2")]
+    pub attempts: u32,
+    /// The block was stored without the data that failed to download.
+    #[prost(bool, tag = "3")]
+    pub degraded: bool,
+    /// Unix timestamp of the last failure.
+    #[prost(uint64, tag = "4")]
+    pub last_failed_at: u64,
+}
+
+impl Table for IngestionJournalTable {
+    type Key = u64;
+    type Value = JournalEntry;
+
+    fn db_name() -> &'static str {
+        "IngestionJournal"
+    }
+}
@@ -0,0 +1,181 @@
+//! Storage metadata.
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{
+    libmdbx::{self, EnvironmentKind, Transaction, TransactionKind, RW},
+    MdbxRWTransactionExt, MdbxTransactionExt, Table,
+};
+use prost::Message;
+
+use crate::core::GlobalBlockId;
+
+use super::tables;
+
+/// Store metadata about the stored blocks, as a single entry.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StorageMetadataTable {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct StorageMetadata {
+    /// The canonical chain up to this block was checked for gaps by the
+    /// last repair.
+    #[prost(uint64, optional, tag = "1")]
+    pub repair_checkpoint: Option<u64>,
+    /// Number of the first block in the canonical chain.
+    #[prost(uint64, optional, tag = "2")]
+    pub earliest_block_number: Option<u64>,
+    /// Hash of the first block in the canonical chain.
+    #[prost(message, optional, tag = "3")]
+    pub earliest_block_hash: Option<v1alpha2::FieldElement>,
+}
+
+impl Table for StorageMetadataTable {
+    type Key = ();
+    type Value = StorageMetadata;
+
+    fn db_name() -> &'static str {
+        "StorageMetadata"
+    }
+}
+
+impl StorageMetadata {
+    /// Returns the earliest block available in storage, if known.
+    pub fn earliest_available_block(&self) -> Option<GlobalBlockId> {
+        let number = self.earliest_block_number?;
+        let hash = self.earliest_block_hash.as_ref()?;
+        Some(GlobalBlockId::new(number, hash.into()))
+    }
+
+    fn set_earliest_available_block(&mut self, block_id: Option<GlobalBlockId>) {
+        self.earliest_block_number = block_id.map(|id| id.number());
+        self.earliest_block_hash = block_id.map(|id| id.hash().into());
+    }
+}
+
+/// Reads the storage metadata, returning the default metadata if none is
+/// stored.
+pub fn read_metadata<K: TransactionKind, E: EnvironmentKind>(
+    txn: &Transaction<'_, K, E>,
+) -> Result<StorageMetadata, libmdbx::Error> {
+    // databases written by older versions don't have the table.
+    let table = match txn.open_table::<StorageMetadataTable>() {
+        Err(libmdbx::Error::NotFound) => return Ok(StorageMetadata::default()),
+        table => table?,
+    };
+    Ok(table.get(&())?.unwrap_or_default())
+}
+
+/// Updates the storage metadata with `update`.
+pub fn update_metadata<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+    update: impl FnOnce(&mut StorageMetadata),
+) -> Result<(), libmdbx::Error> {
+    let mut metadata = read_metadata(txn)?;
+    update(&mut metadata);
+    txn.ensure_table::<StorageMetadataTable>(None)?;
+    txn.open_cursor::<StorageMetadataTable>()?
+        .put(&(), &metadata)?;
+    Ok(())
+}
+
+/// Stores the first block of the canonical chain as the earliest available
+/// block.
+///
+/// Must be called after removing blocks from the start of the canonical
+/// chain.
+pub fn update_earliest_available_block<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+) -> Result<Option<GlobalBlockId>, libmdbx::Error> {
+    let earliest = txn
+        .open_cursor::<tables::CanonicalChainTable>()?
+        .first()?
+        .map(|(number, hash)| GlobalBlockId::new(number, (&hash).into()));
+    update_metadata(txn, |metadata| {
+        metadata.set_earliest_available_block(earliest);
+    })?;
+    Ok(earliest)
+}
+
+/// Updates the earliest available block after `id` was added to the
+/// canonical chain.
+pub fn extend_earliest_available_block<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+    id: &GlobalBlockId,
+) -> Result<(), libmdbx::Error> {
+    match read_metadata(txn)?.earliest_block_number {
+        // older databases don't store the earliest block yet.
+        None => {
+            update_earliest_available_block(txn)?;
+        }
+        Some(earliest) if id.number() <= earliest => {
+            update_metadata(txn, |metadata| {
+                metadata.set_earliest_available_block(Some(*id));
+            })?;
+        }
+        Some(_) => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        core::{BlockHash, GlobalBlockId},
+        db::{tables, DatabaseStorage, StorageReader, StorageWriter},
+    };
+
+    fn new_block_id(number: u64) -> GlobalBlockId {
+        let mut hash = [0; 32];
+        hash[24..].copy_from_slice(&number.to_be_bytes());
+        GlobalBlockId::new(number, BlockHash::from_slice(&hash).unwrap())
+    }
+
+    #[test]
+    fn test_earliest_available_block() {
+        let path = tempdir().unwrap();
+        let db = Arc::new(Environment::<NoWriteMap>::open(path.path()).unwrap());
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure(&txn).unwrap();
+        txn.commit().unwrap();
+
+        let storage = DatabaseStorage::new(db);
+        assert_eq!(storage.earliest_available_block().unwrap(), None);
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 5..10 {
+            txn.extend_canonical_chain(&new_block_id(number)).unwrap();
+        }
+        txn.commit().unwrap();
+        assert_eq!(
+            storage.earliest_available_block().unwrap(),
+            Some(new_block_id(5))
+        );
+
+        // blocks written before the earliest block, for example by a
+        // backfill.
+        let mut txn = storage.begin_txn().unwrap();
+        txn.extend_canonical_chain(&new_block_id(3)).unwrap();
+        txn.commit().unwrap();
+        assert_eq!(
+            storage.earliest_available_block().unwrap(),
+            Some(new_block_id(3))
+        );
+
+        let mut txn = storage.begin_txn().unwrap();
+        txn.reject_block_from_canonical_chain(&new_block_id(3))
+            .unwrap();
+        txn.commit().unwrap();
+        assert_eq!(
+            storage.earliest_available_block().unwrap(),
+            Some(new_block_id(5))
+        );
+    }
+}
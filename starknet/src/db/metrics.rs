@@ -0,0 +1,56 @@
+//! Metrics of the storage operations.
+use std::time::Instant;
+
+use apibara_node::o11y::{self, Counter, Histogram, KeyValue};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref STORAGE_METRICS: StorageMetrics = StorageMetrics::default();
+}
+
+/// Latency and errors of each storage operation.
+pub struct StorageMetrics {
+    latency: Histogram<f64>,
+    errors: Counter<u64>,
+}
+
+impl Default for StorageMetrics {
+    fn default() -> Self {
+        let meter = o11y::meter("storage");
+        let latency = meter
+            .f64_histogram("storage_operation_latency")
+            .with_description("Latency of storage operations, in seconds")
+            .init();
+        let errors = meter
+            .u64_counter("storage_operation_errors")
+            .with_description("Number of failed storage operations")
+            .init();
+        StorageMetrics { latency, errors }
+    }
+}
+
+impl StorageMetrics {
+    /// Runs the storage operation `f`, recording its latency and whether it
+    /// failed.
+    pub fn record<T, E>(
+        &self,
+        operation: &'static str,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let started_at = Instant::now();
+        let result = f();
+        let cx = o11y::Context::current();
+        let attributes = &[KeyValue::new("operation", operation)];
+        self.latency
+            .record(&cx, started_at.elapsed().as_secs_f64(), attributes);
+        if result.is_err() {
+            self.errors.add(&cx, 1, attributes);
+        }
+        result
+    }
+}
+
+/// Returns the metrics shared by all storages.
+pub fn storage_metrics() -> &'static StorageMetrics {
+    &STORAGE_METRICS
+}
@@ -0,0 +1,199 @@
+//! Migrations of the StarkNet database schema.
+
+use apibara_node::db::{
+    libmdbx::{self, EnvironmentKind, Transaction, RW},
+    MdbxErrorExt, MdbxTransactionExt, Migration, MigrationStep, Table, TableKey,
+};
+use prost::Message;
+
+use crate::core::GlobalBlockId;
+
+use super::{
+    block::{BlockBody, BlockReceipts},
+    compression::CompressedData,
+    repair::delete_block_data,
+    tables,
+};
+
+/// Number of blocks migrated in a single step.
+const BLOCKS_PER_STEP: usize = 1_000;
+
+/// Number of block status entries scanned in a single step.
+const STATUS_ENTRIES_PER_STEP: usize = 100_000;
+
+/// Block body table, before compression.
+#[derive(Debug, Clone, Copy, Default)]
+struct UncompressedBlockBodyTable {}
+
+/// Block receipts table, before compression.
+#[derive(Debug, Clone, Copy, Default)]
+struct UncompressedBlockReceiptsTable {}
+
+impl Table for UncompressedBlockBodyTable {
+    type Key = GlobalBlockId;
+    type Value = BlockBody;
+
+    fn db_name() -> &'static str {
+        tables::BlockBodyTable::db_name()
+    }
+}
+
+impl Table for UncompressedBlockReceiptsTable {
+    type Key = GlobalBlockId;
+    type Value = BlockReceipts;
+
+    fn db_name() -> &'static str {
+        tables::BlockReceiptsTable::db_name()
+    }
+}
+
+/// Compress block bodies and receipts stored before compression was
+/// introduced.
+///
+/// Data is compressed without a dictionary, since no dictionary is available
+/// before the node ingests new blocks.
+pub struct CompressBlockDataMigration;
+
+impl<E: EnvironmentKind> Migration<E> for CompressBlockDataMigration {
+    fn version(&self) -> u64 {
+        1
+    }
+
+    fn description(&self) -> &'static str {
+        "compress block bodies and receipts"
+    }
+
+    fn step(
+        &self,
+        txn: &Transaction<'_, RW, E>,
+        checkpoint: Option<&[u8]>,
+    ) -> Result<MigrationStep, libmdbx::Error> {
+        // the checkpoint is the first table (0 = bodies, 1 = receipts) and
+        // block not migrated yet.
+        let (table, start) = match checkpoint.and_then(|c| c.split_first()) {
+            None => (0, None),
+            Some((table, start)) => {
+                let start = GlobalBlockId::decode(start).map_err(libmdbx::Error::decode_error)?;
+                (*table, Some(start))
+            }
+        };
+
+        let next = if table == 0 {
+            compress_table::<UncompressedBlockBodyTable, tables::BlockBodyTable, _>(txn, start)?
+        } else {
+            compress_table::<UncompressedBlockReceiptsTable, tables::BlockReceiptsTable, _>(
+                txn, start,
+            )?
+        };
+
+        let checkpoint = match (table, next) {
+            (_, Some(next)) => (table, next.encode()),
+            (0, None) => {
+                // start migrating receipts from the first block.
+                let mut cursor = txn.open_cursor::<UncompressedBlockReceiptsTable>()?;
+                match cursor.first()? {
+                    None => return Ok(MigrationStep::Done),
+                    Some((first, _)) => (1, first.encode()),
+                }
+            }
+            (_, None) => return Ok(MigrationStep::Done),
+        };
+
+        let mut data = vec![checkpoint.0];
+        data.extend_from_slice(checkpoint.1.as_ref());
+        Ok(MigrationStep::Progress(data))
+    }
+}
+
+/// Compress up to [BLOCKS_PER_STEP] values starting at `start`.
+///
+/// Returns the key of the next value to compress, if any.
+fn compress_table<U, T, E>(
+    txn: &Transaction<'_, RW, E>,
+    start: Option<GlobalBlockId>,
+) -> Result<Option<GlobalBlockId>, libmdbx::Error>
+where
+    U: Table<Key = GlobalBlockId>,
+    T: Table<Key = GlobalBlockId, Value = CompressedData>,
+    E: EnvironmentKind,
+{
+    let mut values = Vec::with_capacity(BLOCKS_PER_STEP);
+    let mut cursor = txn.open_cursor::<U>()?;
+    let mut item = match start {
+        None => cursor.first()?,
+        Some(start) => cursor.seek_range(&start)?,
+    };
+    while let Some((key, value)) = item {
+        if values.len() >= BLOCKS_PER_STEP {
+            item = Some((key, value));
+            break;
+        }
+        values.push((key, value));
+        item = cursor.next()?;
+    }
+
+    let mut cursor = txn.open_cursor::<T>()?;
+    for (key, value) in values {
+        let compressed = CompressedData::compress(&value.encode_to_vec(), None)
+            .map_err(libmdbx::Error::decode_error)?;
+        cursor.seek_exact(&key)?;
+        cursor.put(&key, &compressed)?;
+    }
+
+    Ok(item.map(|t| t.0))
+}
+
+/// Remove pending block data left behind by previous versions, which never
+/// removed pending data once superseded.
+pub struct RemovePendingBlocksMigration;
+
+impl<E: EnvironmentKind> Migration<E> for RemovePendingBlocksMigration {
+    fn version(&self) -> u64 {
+        2
+    }
+
+    fn description(&self) -> &'static str {
+        "remove superseded pending blocks"
+    }
+
+    fn step(
+        &self,
+        txn: &Transaction<'_, RW, E>,
+        checkpoint: Option<&[u8]>,
+    ) -> Result<MigrationStep, libmdbx::Error> {
+        // the checkpoint is the first block status not scanned yet.
+        let mut cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
+        let mut next = match checkpoint {
+            None => cursor.first()?,
+            Some(checkpoint) => {
+                let start =
+                    GlobalBlockId::decode(checkpoint).map_err(libmdbx::Error::decode_error)?;
+                cursor.seek_range(&start)?
+            }
+        }
+        .map(|t| t.0);
+
+        // pending blocks don't have a hash yet.
+        let mut pending = Vec::default();
+        let mut scanned = 0;
+        while let Some(block_id) = next {
+            if scanned >= STATUS_ENTRIES_PER_STEP {
+                break;
+            }
+            if block_id.hash().is_zero() {
+                pending.push(block_id);
+            }
+            scanned += 1;
+            next = cursor.next()?.map(|t| t.0);
+        }
+
+        for block_id in pending {
+            delete_block_data(txn, &block_id)?;
+        }
+
+        match next {
+            None => Ok(MigrationStep::Done),
+            Some(next) => Ok(MigrationStep::Progress(next.encode().to_vec())),
+        }
+    }
+}
@@ -1,21 +1,71 @@
+mod availability;
 mod block;
 mod chain;
+mod checksum;
+mod class;
+mod compression;
+mod encryption;
+mod info;
+mod journal;
+mod metadata;
+mod metrics;
+mod migrations;
+mod partial;
+mod quota;
+mod raw;
+mod repair;
+mod rollback;
+mod shard;
 mod state;
 mod storage;
+mod trace;
 mod transaction;
 
+use apibara_node::db::{libmdbx::EnvironmentKind, MigrationRunner};
+
 pub use self::block::{BlockBody, BlockReceipts, BlockStatus};
+pub use self::checksum::{compute_block_checksum, verify_block_checksum, ChecksumStatus};
+pub use self::encryption::{
+    CommandKeyProvider, EncryptionError, EncryptionKey, FileKeyProvider, KeyProvider,
+    ENCRYPTION_KEY_SIZE,
+};
+pub use self::info::{database_info, DatabaseInfo, TableInfo};
+pub use self::journal::JournalEntry;
+pub use self::partial::PartialBlock;
+pub use self::quota::{QuotaKey, QuotaUsage};
+pub use self::raw::{read_raw_block, write_raw_block};
+pub use self::repair::{repair_storage, RepairSummary, DEFAULT_REPAIR_DEPTH};
+pub use self::rollback::{rollback_storage, RollbackError, RollbackSummary};
+pub use self::shard::{split_shard, ShardError, ShardedStorage};
 pub use self::storage::{
     DatabaseStorage, DatabaseStorageWriter, MockStorageReader, StorageReader, StorageWriter,
 };
 
+/// Returns the runner with all migrations of the StarkNet database schema.
+///
+/// Add new migrations here, in order of version.
+pub fn migrations<E: EnvironmentKind>() -> MigrationRunner<E> {
+    MigrationRunner::new()
+        .with_migration(migrations::CompressBlockDataMigration)
+        .with_migration(migrations::RemovePendingBlocksMigration)
+}
+
 pub mod tables {
     use apibara_node::db::libmdbx::{EnvironmentKind, Error as MdbxError, Transaction, RW};
     use apibara_node::db::MdbxRWTransactionExt;
 
+    pub use super::availability::DataAvailabilityTable;
     pub use super::block::{BlockHeaderTable, BlockStatusTable};
-    pub use super::chain::CanonicalChainTable;
+    pub use super::chain::{CanonicalChainTable, ChainIdTable};
+    pub use super::checksum::BlockChecksumTable;
+    pub use super::class::ContractClassTable;
+    pub use super::compression::CompressionDictionaryTable;
+    pub use super::journal::IngestionJournalTable;
+    pub use super::metadata::StorageMetadataTable;
+    pub use super::partial::PartialBlockTable;
+    pub use super::quota::QuotaUsageTable;
     pub use super::state::StateUpdateTable;
+    pub use super::trace::BlockTracesTable;
     pub use super::transaction::{BlockBodyTable, BlockReceiptsTable};
 
     /// Ensures all tables exist.
@@ -26,6 +76,16 @@ pub mod tables {
         txn.ensure_table::<self::CanonicalChainTable>(None)?;
         txn.ensure_table::<self::BlockReceiptsTable>(None)?;
         txn.ensure_table::<self::StateUpdateTable>(None)?;
+        txn.ensure_table::<self::CompressionDictionaryTable>(None)?;
+        txn.ensure_table::<self::ContractClassTable>(None)?;
+        txn.ensure_table::<self::BlockChecksumTable>(None)?;
+        txn.ensure_table::<self::BlockTracesTable>(None)?;
+        txn.ensure_table::<self::ChainIdTable>(None)?;
+        txn.ensure_table::<self::DataAvailabilityTable>(None)?;
+        txn.ensure_table::<self::IngestionJournalTable>(None)?;
+        txn.ensure_table::<self::QuotaUsageTable>(None)?;
+        txn.ensure_table::<self::StorageMetadataTable>(None)?;
+        txn.ensure_table::<self::PartialBlockTable>(None)?;
         Ok(())
     }
 }
@@ -0,0 +1,47 @@
+//! Blocks stored without some of their data.
+
+use apibara_node::db::Table;
+use prost::Message;
+
+use crate::core::GlobalBlockId;
+
+/// Store the data missing from blocks.
+///
+/// Blocks backfilled from their events only contain their header, the
+/// hashes of their transactions and the events matching the backfill filter.
+/// Degraded blocks are stored without the data the provider failed to
+/// return. Complete blocks have no entry.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PartialBlockTable {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PartialBlock {
+    /// The block was backfilled from its events.
+    #[prost(bool, tag = "1")]
+    pub events_only: bool,
+    /// The state update is missing.
+    #[prost(bool, tag = "2")]
+    pub missing_state_update: bool,
+    /// The execution traces are missing.
+    #[prost(bool, tag = "3")]
+    pub missing_traces: bool,
+    /// Some of the classes declared in the block are missing.
+    #[prost(bool, tag = "4")]
+    pub missing_classes: bool,
+}
+
+impl PartialBlock {
+    /// Returns true if no data is missing.
+    pub fn is_complete(&self) -> bool {
+        *self == PartialBlock::default()
+    }
+}
+
+impl Table for PartialBlockTable {
+    type Key = GlobalBlockId;
+    type Value = PartialBlock;
+
+    fn db_name() -> &'static str {
+        "PartialBlock"
+    }
+}
@@ -0,0 +1,52 @@
+//! Data units used by each caller, for quota enforcement.
+
+use apibara_node::db::{KeyDecodeError, Table, TableKey};
+use prost::Message;
+
+/// Store the quota usage, by caller identity.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QuotaUsageTable {}
+
+/// Identity of the caller, as stored in the quota usage table.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QuotaKey(pub String);
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QuotaUsage {
+    /// Unix timestamp of the start of the current day.
+    #[prost(uint64, tag = "1")]
+    pub day_start: u64,
+    /// Data units used since the start of the day.
+    #[prost(uint64, tag = "2")]
+    pub day_units: u64,
+    /// Unix timestamp of the start of the current month.
+    #[prost(uint64, tag = "3")]
+    pub month_start: u64,
+    /// Data units used since the start of the month.
+    #[prost(uint64, tag = "4")]
+    pub month_units: u64,
+}
+
+// The identity is encoded as its utf-8 bytes.
+impl TableKey for QuotaKey {
+    type Encoded = Vec<u8>;
+
+    fn encode(&self) -> Self::Encoded {
+        self.0.as_bytes().to_vec()
+    }
+
+    fn decode(b: &[u8]) -> Result<Self, KeyDecodeError> {
+        let identity =
+            String::from_utf8(b.to_vec()).map_err(|err| KeyDecodeError::Other(Box::new(err)))?;
+        Ok(QuotaKey(identity))
+    }
+}
+
+impl Table for QuotaUsageTable {
+    type Key = QuotaKey;
+    type Value = QuotaUsage;
+
+    fn db_name() -> &'static str {
+        "QuotaUsage"
+    }
+}
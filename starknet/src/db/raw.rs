@@ -0,0 +1,63 @@
+//! Read and write all data of a block.
+
+use apibara_core::starknet::v1alpha2;
+
+use crate::core::GlobalBlockId;
+
+use super::{BlockBody, StorageReader, StorageWriter};
+
+/// Reads all data of the canonical block at the given height.
+///
+/// Returns `None` if the canonical chain is shorter than `number`.
+pub fn read_raw_block<R: StorageReader>(
+    storage: &R,
+    number: u64,
+) -> Result<Option<v1alpha2::RawBlock>, R::Error> {
+    let id = match storage.canonical_block_id(number)? {
+        None => return Ok(None),
+        Some(id) => id,
+    };
+    let status = storage
+        .read_status(&id)?
+        .unwrap_or(v1alpha2::BlockStatus::Unspecified);
+    let header = storage.read_header(&id)?;
+    let transactions = storage.read_body(&id)?;
+    let (receipts, _) = storage.read_receipts(&id)?;
+    let state_update = storage.read_state_update(&id)?;
+    let traces = storage.read_traces(&id)?;
+    Ok(Some(v1alpha2::RawBlock {
+        status: status as i32,
+        header,
+        transactions,
+        receipts,
+        state_update,
+        traces,
+    }))
+}
+
+/// Writes all data of the block and adds it to the canonical chain.
+pub fn write_raw_block<W: StorageWriter>(
+    txn: &mut W,
+    block_id: &GlobalBlockId,
+    block: v1alpha2::RawBlock,
+) -> Result<(), W::Error> {
+    txn.write_status(block_id, block.status())?;
+    if let Some(header) = block.header {
+        txn.write_header(block_id, header)?;
+    }
+    txn.write_body(
+        block_id,
+        BlockBody {
+            transactions: block.transactions,
+            ..BlockBody::default()
+        },
+    )?;
+    txn.write_receipts(block_id, block.receipts)?;
+    if let Some(state_update) = block.state_update {
+        txn.write_state_update(block_id, state_update)?;
+    }
+    if !block.traces.is_empty() {
+        txn.write_traces(block_id, block.traces)?;
+    }
+    txn.extend_canonical_chain(block_id)
+}
@@ -0,0 +1,346 @@
+//! Detect and repair partially ingested blocks.
+//!
+//! Blocks are ingested in a single transaction, but databases written by
+//! older versions (or restored from inconsistent copies) can contain
+//! canonical blocks with missing data or gaps in the canonical chain.
+//! Those blocks are removed from the canonical chain so that ingestion
+//! fetches them again.
+//!
+//! Gaps below the finalized block are kept, they are filled by ingestion
+//! when the node starts. The canonical chain up to the finalized block is
+//! only checked once, repairs resume from the checkpoint stored in the
+//! storage metadata.
+
+use apibara_node::db::{
+    libmdbx::{self, Environment, EnvironmentKind, Transaction, RW},
+    MdbxTransactionExt, Table,
+};
+use tracing::{info, warn};
+
+use crate::core::GlobalBlockId;
+
+use super::{metadata, tables};
+
+/// Number of blocks, starting from the canonical chain tip, checked for
+/// missing data.
+pub const DEFAULT_REPAIR_DEPTH: u64 = 1_000;
+
+/// Summary of the changes made by [repair_storage].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairSummary {
+    /// Number of blocks removed from the canonical chain.
+    pub removed_from_canonical_chain: u64,
+    /// Number of incomplete blocks whose data was deleted.
+    pub removed_incomplete_blocks: u64,
+}
+
+/// Removes incomplete blocks, and all blocks after them, from the canonical
+/// chain.
+///
+/// The canonical chain above the finalized block is checked for gaps, while
+/// only the most recent `depth` blocks are checked for missing data.
+pub fn repair_storage<E: EnvironmentKind>(
+    db: &Environment<E>,
+    depth: u64,
+) -> Result<RepairSummary, libmdbx::Error> {
+    let txn = db.begin_rw_txn()?;
+    let mut summary = RepairSummary::default();
+
+    let finalized = find_highest_finalized(&txn)?;
+    let first_gap = find_first_gap(&txn, finalized)?;
+    // gaps are only reported after the finalized block, and finalized blocks
+    // don't change, so there is no need to check them again.
+    if let Some(finalized) = finalized {
+        metadata::update_metadata(&txn, |metadata| {
+            metadata.repair_checkpoint = Some(finalized);
+        })?;
+    }
+
+    let truncate_from = match (first_gap, find_first_incomplete(&txn, depth)?) {
+        (None, None) => {
+            txn.commit()?;
+            return Ok(summary);
+        }
+        (Some(gap), None) => gap,
+        (None, Some(incomplete)) => incomplete,
+        (Some(gap), Some(incomplete)) => u64::min(gap, incomplete),
+    };
+
+    warn!(
+        block_number = truncate_from,
+        "canonical chain is inconsistent. repairing"
+    );
+
+    let mut canon_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+    while let Some((number, hash)) = canon_cursor.seek_range(&truncate_from)? {
+        let block_id = GlobalBlockId::new(number, (&hash).into());
+        canon_cursor.del()?;
+        summary.removed_from_canonical_chain += 1;
+
+        if !is_block_complete(&txn, &block_id)? {
+            delete_block_data(&txn, &block_id)?;
+            summary.removed_incomplete_blocks += 1;
+        }
+    }
+    metadata::update_earliest_available_block(&txn)?;
+
+    txn.commit()?;
+
+    info!(summary = ?summary, "repaired canonical chain");
+    Ok(summary)
+}
+
+/// Returns the number of the first block after a gap in the canonical chain,
+/// ignoring gaps below the finalized block.
+///
+/// The search starts from the repair checkpoint.
+fn find_first_gap<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+    finalized: Option<u64>,
+) -> Result<Option<u64>, libmdbx::Error> {
+    let checkpoint = metadata::read_metadata(txn)?.repair_checkpoint;
+    let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+    let first = match checkpoint {
+        None => cursor.first()?,
+        Some(checkpoint) => cursor.seek_range(&checkpoint)?,
+    };
+    let mut previous = match first {
+        None => return Ok(None),
+        Some((number, _)) => number,
+    };
+    while let Some((number, _)) = cursor.next()? {
+        let below_finalized = finalized.map(|f| number <= f).unwrap_or(false);
+        if number != previous + 1 && !below_finalized {
+            return Ok(Some(number));
+        }
+        previous = number;
+    }
+    Ok(None)
+}
+
+/// Returns the number of the highest finalized canonical block.
+///
+/// Unlike `StorageReader::highest_finalized_block`, blocks without status
+/// are skipped.
+fn find_highest_finalized<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+) -> Result<Option<u64>, libmdbx::Error> {
+    let mut canon_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+    let mut status_cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
+    let mut maybe_block = canon_cursor.last()?;
+    while let Some((number, hash)) = maybe_block {
+        let block_id = GlobalBlockId::new(number, (&hash).into());
+        if let Some((_, status)) = status_cursor.seek_exact(&block_id)? {
+            if status.status().is_finalized() {
+                return Ok(Some(number));
+            }
+        }
+        maybe_block = canon_cursor.prev()?;
+    }
+    Ok(None)
+}
+
+/// Returns the number of the lowest block with missing data among the most
+/// recent `depth` canonical blocks.
+fn find_first_incomplete<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+    depth: u64,
+) -> Result<Option<u64>, libmdbx::Error> {
+    let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+    let mut first_incomplete = None;
+    let mut checked = 0;
+    let mut maybe_block = cursor.last()?;
+    while let Some((number, hash)) = maybe_block {
+        if checked >= depth {
+            break;
+        }
+        let block_id = GlobalBlockId::new(number, (&hash).into());
+        if !is_block_complete(txn, &block_id)? {
+            first_incomplete = Some(number);
+        }
+        checked += 1;
+        maybe_block = cursor.prev()?;
+    }
+    Ok(first_incomplete)
+}
+
+/// Returns true if all data required to serve the block is stored.
+///
+/// State updates are not checked since pending blocks don't have one.
+fn is_block_complete<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+    id: &GlobalBlockId,
+) -> Result<bool, libmdbx::Error> {
+    Ok(has_entry::<tables::BlockStatusTable, E>(txn, id)?
+        && has_entry::<tables::BlockHeaderTable, E>(txn, id)?
+        && has_entry::<tables::BlockBodyTable, E>(txn, id)?
+        && has_entry::<tables::BlockReceiptsTable, E>(txn, id)?)
+}
+
+fn has_entry<T, E>(txn: &Transaction<'_, RW, E>, id: &GlobalBlockId) -> Result<bool, libmdbx::Error>
+where
+    T: Table<Key = GlobalBlockId>,
+    E: EnvironmentKind,
+{
+    let mut cursor = txn.open_cursor::<T>()?;
+    Ok(cursor.seek_exact_bytes(id)?.is_some())
+}
+
+/// Deletes all data of the given block.
+pub(super) fn delete_block_data<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+    id: &GlobalBlockId,
+) -> Result<(), libmdbx::Error> {
+    delete_sharded_block_data(txn, id)?;
+    delete_entry::<tables::DataAvailabilityTable, E>(txn, id)?;
+    Ok(())
+}
+
+/// Deletes the data of the given block that is moved to archive shards.
+///
+/// L1 data availability stays in the node database, since data availability
+/// ingestion resumes from the most recent block that has it.
+pub(super) fn delete_sharded_block_data<E: EnvironmentKind>(
+    txn: &Transaction<'_, RW, E>,
+    id: &GlobalBlockId,
+) -> Result<(), libmdbx::Error> {
+    delete_entry::<tables::BlockStatusTable, E>(txn, id)?;
+    delete_entry::<tables::BlockHeaderTable, E>(txn, id)?;
+    delete_entry::<tables::BlockBodyTable, E>(txn, id)?;
+    delete_entry::<tables::BlockReceiptsTable, E>(txn, id)?;
+    delete_entry::<tables::StateUpdateTable, E>(txn, id)?;
+    delete_entry::<tables::BlockTracesTable, E>(txn, id)?;
+    delete_entry::<tables::BlockChecksumTable, E>(txn, id)?;
+    delete_entry::<tables::PartialBlockTable, E>(txn, id)?;
+    Ok(())
+}
+
+fn delete_entry<T, E>(
+    txn: &Transaction<'_, RW, E>,
+    id: &GlobalBlockId,
+) -> Result<(), libmdbx::Error>
+where
+    T: Table<Key = GlobalBlockId>,
+    E: EnvironmentKind,
+{
+    let mut cursor = txn.open_cursor::<T>()?;
+    if cursor.seek_exact_bytes(id)?.is_some() {
+        cursor.del()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::starknet::v1alpha2;
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        core::{BlockHash, GlobalBlockId},
+        db::{tables, BlockBody, DatabaseStorage, StorageReader, StorageWriter},
+    };
+
+    use super::{metadata::read_metadata, repair_storage, RepairSummary};
+
+    fn new_block_id(number: u64) -> GlobalBlockId {
+        let mut hash = [0; 32];
+        hash[24..].copy_from_slice(&number.to_be_bytes());
+        GlobalBlockId::new(number, BlockHash::from_slice(&hash).unwrap())
+    }
+
+    #[test]
+    fn test_repair_incomplete_block() {
+        let path = tempdir().unwrap();
+        let db = Arc::new(Environment::<NoWriteMap>::open(path.path()).unwrap());
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure(&txn).unwrap();
+        txn.commit().unwrap();
+
+        let storage = DatabaseStorage::new(db.clone());
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..5 {
+            let id = new_block_id(number);
+            txn.write_status(&id, v1alpha2::BlockStatus::AcceptedOnL2)
+                .unwrap();
+            txn.write_header(&id, v1alpha2::BlockHeader::default())
+                .unwrap();
+            // block 3 is missing its body and receipts.
+            if number != 3 {
+                txn.write_body(&id, BlockBody::default()).unwrap();
+                txn.write_receipts(&id, Vec::default()).unwrap();
+            }
+            txn.extend_canonical_chain(&id).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let summary = repair_storage(&db, 10).unwrap();
+        assert_eq!(
+            summary,
+            RepairSummary {
+                removed_from_canonical_chain: 2,
+                removed_incomplete_blocks: 1,
+            }
+        );
+        assert_eq!(
+            storage.highest_accepted_block().unwrap(),
+            Some(new_block_id(2))
+        );
+        assert!(storage.read_header(&new_block_id(3)).unwrap().is_none());
+        assert!(storage.read_header(&new_block_id(4)).unwrap().is_some());
+
+        // repairing a consistent database is a no-op.
+        let summary = repair_storage(&db, 10).unwrap();
+        assert_eq!(summary, RepairSummary::default());
+    }
+
+    #[test]
+    fn test_keep_gaps_below_finalized() {
+        let path = tempdir().unwrap();
+        let db = Arc::new(Environment::<NoWriteMap>::open(path.path()).unwrap());
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure(&txn).unwrap();
+        txn.commit().unwrap();
+
+        let storage = DatabaseStorage::new(db.clone());
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..10 {
+            // blocks 2 and 7 are missing.
+            if number == 2 || number == 7 {
+                continue;
+            }
+            let id = new_block_id(number);
+            let status = if number < 5 {
+                v1alpha2::BlockStatus::AcceptedOnL1
+            } else {
+                v1alpha2::BlockStatus::AcceptedOnL2
+            };
+            txn.write_status(&id, status).unwrap();
+            txn.write_header(&id, v1alpha2::BlockHeader::default())
+                .unwrap();
+            txn.write_body(&id, BlockBody::default()).unwrap();
+            txn.write_receipts(&id, Vec::default()).unwrap();
+            txn.extend_canonical_chain(&id).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let summary = repair_storage(&db, 10).unwrap();
+        assert_eq!(summary.removed_from_canonical_chain, 2);
+        assert_eq!(
+            storage.highest_accepted_block().unwrap(),
+            Some(new_block_id(6))
+        );
+        assert_eq!(storage.canonical_chain_gaps(6).unwrap(), vec![2..3]);
+
+        // the next repair resumes from the finalized block.
+        let txn = db.begin_ro_txn().unwrap();
+        let checkpoint = read_metadata(&txn).unwrap().repair_checkpoint;
+        txn.commit().unwrap();
+        assert_eq!(checkpoint, Some(4));
+    }
+}
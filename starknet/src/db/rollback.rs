@@ -0,0 +1,241 @@
+//! Roll back storage to a finalized block.
+//!
+//! Rolling back removes all blocks after the target block, including pending
+//! and rejected blocks, so that ingestion resumes from the target block.
+//! Class definitions are kept since they are immutable.
+
+use apibara_node::db::{
+    libmdbx::{self, EnvironmentKind},
+    MdbxTransactionExt, Table,
+};
+use tracing::info;
+
+use crate::core::{BlockHash, GlobalBlockId};
+
+use super::{metadata, tables, DatabaseStorage, StorageReader};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RollbackError {
+    #[error("database operation failed")]
+    Database(#[from] libmdbx::Error),
+    #[error("block {0} is not in the canonical chain")]
+    NotCanonical(u64),
+    #[error("block {0} is not finalized")]
+    NotFinalized(u64),
+}
+
+/// Summary of the changes made by [rollback_storage].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollbackSummary {
+    /// The new head of the canonical chain.
+    pub head: GlobalBlockId,
+    /// Number of blocks removed from the canonical chain.
+    pub removed_from_canonical_chain: u64,
+    /// Number of blocks whose data was deleted, including non-canonical
+    /// blocks.
+    pub removed_blocks: u64,
+}
+
+/// Number of entries deleted in a single transaction.
+const ENTRIES_PER_TXN: u64 = 10_000;
+
+/// Removes all blocks after the finalized block `target`.
+///
+/// Entries are deleted in batches of small transactions. The canonical chain
+/// is truncated from its head first, so that it's valid after every
+/// transaction. An interrupted rollback is completed by running it again.
+pub fn rollback_storage<E: EnvironmentKind>(
+    storage: &DatabaseStorage<E>,
+    target: u64,
+) -> Result<RollbackSummary, RollbackError> {
+    rollback_storage_in_batches(storage, target, ENTRIES_PER_TXN)
+}
+
+fn rollback_storage_in_batches<E: EnvironmentKind>(
+    storage: &DatabaseStorage<E>,
+    target: u64,
+    batch_size: u64,
+) -> Result<RollbackSummary, RollbackError> {
+    let head = storage
+        .canonical_block_id(target)?
+        .ok_or(RollbackError::NotCanonical(target))?;
+    match storage.highest_finalized_block()? {
+        Some(finalized) if target <= finalized.number() => {}
+        _ => return Err(RollbackError::NotFinalized(target)),
+    }
+
+    let mut summary = RollbackSummary {
+        head,
+        removed_from_canonical_chain: 0,
+        removed_blocks: 0,
+    };
+
+    loop {
+        let txn = storage.environment().begin_rw_txn()?;
+        let mut removed = 0;
+        {
+            let mut canon_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+            while removed < batch_size {
+                match canon_cursor.last()? {
+                    Some((number, _)) if number > target => {
+                        canon_cursor.del()?;
+                        removed += 1;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        txn.commit()?;
+        summary.removed_from_canonical_chain += removed;
+        if removed < batch_size {
+            break;
+        }
+    }
+
+    // blocks are sorted by number, so all blocks after the target come after
+    // this key.
+    let first_removed = GlobalBlockId::new(target + 1, BlockHash::zero());
+    summary.removed_blocks =
+        delete_from::<tables::BlockStatusTable, E>(storage, &first_removed, batch_size)?;
+    delete_from::<tables::BlockHeaderTable, E>(storage, &first_removed, batch_size)?;
+    delete_from::<tables::BlockBodyTable, E>(storage, &first_removed, batch_size)?;
+    delete_from::<tables::BlockReceiptsTable, E>(storage, &first_removed, batch_size)?;
+    delete_from::<tables::StateUpdateTable, E>(storage, &first_removed, batch_size)?;
+    delete_from::<tables::BlockTracesTable, E>(storage, &first_removed, batch_size)?;
+    delete_from::<tables::DataAvailabilityTable, E>(storage, &first_removed, batch_size)?;
+    delete_from::<tables::BlockChecksumTable, E>(storage, &first_removed, batch_size)?;
+    delete_from::<tables::PartialBlockTable, E>(storage, &first_removed, batch_size)?;
+    // failures of removed blocks don't apply to the blocks ingested again.
+    delete_from::<tables::IngestionJournalTable, E>(storage, &(target + 1), batch_size)?;
+
+    let txn = storage.environment().begin_rw_txn()?;
+    metadata::update_metadata(&txn, |metadata| {
+        if let Some(checkpoint) = metadata.repair_checkpoint {
+            metadata.repair_checkpoint = Some(u64::min(checkpoint, target));
+        }
+    })?;
+    metadata::update_earliest_available_block(&txn)?;
+    txn.commit()?;
+
+    info!(summary = ?summary, "rolled back storage");
+    Ok(summary)
+}
+
+/// Deletes all entries with key greater than or equal to `start`, deleting
+/// at most `batch_size` entries per transaction.
+///
+/// Returns the number of entries deleted.
+fn delete_from<T, E>(
+    storage: &DatabaseStorage<E>,
+    start: &T::Key,
+    batch_size: u64,
+) -> Result<u64, libmdbx::Error>
+where
+    T: Table,
+    E: EnvironmentKind,
+{
+    let mut deleted = 0;
+    loop {
+        let txn = storage.environment().begin_rw_txn()?;
+        let mut batch = 0;
+        {
+            let mut cursor = txn.open_cursor::<T>()?;
+            while batch < batch_size && cursor.seek_range(start)?.is_some() {
+                cursor.del()?;
+                batch += 1;
+            }
+        }
+        txn.commit()?;
+        deleted += batch;
+        if batch < batch_size {
+            return Ok(deleted);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::starknet::v1alpha2;
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        core::{BlockHash, GlobalBlockId},
+        db::{tables, BlockBody, DatabaseStorage, JournalEntry, StorageReader, StorageWriter},
+    };
+
+    use super::{rollback_storage, rollback_storage_in_batches, RollbackError};
+
+    fn new_block_id(number: u64) -> GlobalBlockId {
+        let mut hash = [0; 32];
+        hash[24..].copy_from_slice(&number.to_be_bytes());
+        GlobalBlockId::new(number, BlockHash::from_slice(&hash).unwrap())
+    }
+
+    #[test]
+    fn test_rollback_storage() {
+        let path = tempdir().unwrap();
+        let db = Arc::new(Environment::<NoWriteMap>::open(path.path()).unwrap());
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure(&txn).unwrap();
+        txn.commit().unwrap();
+        let storage = DatabaseStorage::new(db);
+
+        let mut txn = storage.begin_txn().unwrap();
+        for number in 0..10 {
+            let id = new_block_id(number);
+            let status = if number < 8 {
+                v1alpha2::BlockStatus::AcceptedOnL1
+            } else {
+                v1alpha2::BlockStatus::AcceptedOnL2
+            };
+            txn.write_status(&id, status).unwrap();
+            txn.write_header(&id, v1alpha2::BlockHeader::default())
+                .unwrap();
+            txn.write_body(&id, BlockBody::default()).unwrap();
+            txn.write_receipts(&id, Vec::default()).unwrap();
+            txn.extend_canonical_chain(&id).unwrap();
+        }
+        txn.commit().unwrap();
+        for number in [3, 7] {
+            storage
+                .write_journal_entry(number, &JournalEntry::default())
+                .unwrap();
+        }
+
+        assert!(matches!(
+            rollback_storage(&storage, 9),
+            Err(RollbackError::NotFinalized(9))
+        ));
+        assert!(matches!(
+            rollback_storage(&storage, 12),
+            Err(RollbackError::NotCanonical(12))
+        ));
+
+        // delete in batches smaller than the number of removed blocks.
+        let summary = rollback_storage_in_batches(&storage, 5, 3).unwrap();
+        assert_eq!(summary.head, new_block_id(5));
+        assert_eq!(summary.removed_from_canonical_chain, 4);
+        assert_eq!(summary.removed_blocks, 4);
+
+        assert_eq!(
+            storage.highest_accepted_block().unwrap(),
+            Some(new_block_id(5))
+        );
+        assert!(storage.read_header(&new_block_id(5)).unwrap().is_some());
+        assert!(storage.read_header(&new_block_id(6)).unwrap().is_none());
+        assert!(storage.read_status(&new_block_id(9)).unwrap().is_none());
+        let journal: Vec<_> = storage
+            .journal_entries()
+            .unwrap()
+            .into_iter()
+            .map(|(number, _)| number)
+            .collect();
+        assert_eq!(journal, vec![3]);
+    }
+}
@@ -0,0 +1,401 @@
+//! Split storage by block range across multiple environments.
+//!
+//! Old finalized blocks are moved from the node database to archive shards,
+//! each one a separate MDBX environment containing a contiguous range of the
+//! canonical chain. Archive shards are immutable, so they can be backed up
+//! and restored independently of the node database, which only keeps the
+//! most recent blocks.
+
+use std::{fs, path::Path, sync::Arc};
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{
+    libmdbx::{self, Environment, EnvironmentKind},
+    MdbxEnvironmentExt, MdbxTransactionExt,
+};
+use tracing::info;
+
+use crate::core::GlobalBlockId;
+
+use super::{
+    metadata,
+    partial::PartialBlock,
+    raw::{read_raw_block, write_raw_block},
+    repair::delete_sharded_block_data,
+    storage::Bloom,
+    tables, DatabaseStorage, StorageReader, StorageWriter,
+};
+
+/// Number of blocks moved in a single transaction.
+const BLOCKS_PER_TXN: u64 = 1_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ShardError {
+    #[error("database operation failed")]
+    Database(#[from] libmdbx::Error),
+    #[error("failed to create shard directory")]
+    Io(#[from] std::io::Error),
+    #[error("shard target {0:?} is not empty")]
+    TargetNotEmpty(std::path::PathBuf),
+    #[error("shard {0:?} contains no blocks")]
+    EmptyShard(std::path::PathBuf),
+    #[error("shards are not contiguous: block {0} is missing")]
+    NotContiguous(u64),
+    #[error("block {0} is not finalized")]
+    NotFinalized(u64),
+}
+
+/// An immutable shard with its block range.
+#[derive(Debug)]
+struct ArchiveShard<E: EnvironmentKind> {
+    storage: DatabaseStorage<E>,
+    first: GlobalBlockId,
+    last: GlobalBlockId,
+}
+
+/// A [StorageReader] that routes reads to the shard containing the block.
+///
+/// Archive shards are sorted by block number and are followed by the node
+/// database, which contains all blocks after the last archive shard.
+#[derive(Debug)]
+pub struct ShardedStorage<E: EnvironmentKind> {
+    live: DatabaseStorage<E>,
+    archives: Vec<ArchiveShard<E>>,
+}
+
+impl<E: EnvironmentKind> Clone for ArchiveShard<E> {
+    fn clone(&self) -> Self {
+        ArchiveShard {
+            storage: self.storage.clone(),
+            first: self.first,
+            last: self.last,
+        }
+    }
+}
+
+impl<E: EnvironmentKind> Clone for ShardedStorage<E> {
+    fn clone(&self) -> Self {
+        ShardedStorage {
+            live: self.live.clone(),
+            archives: self.archives.clone(),
+        }
+    }
+}
+
+impl<E: EnvironmentKind> ShardedStorage<E> {
+    /// Creates a new sharded storage without archive shards.
+    pub fn single(live: DatabaseStorage<E>) -> Self {
+        ShardedStorage {
+            live,
+            archives: Vec::default(),
+        }
+    }
+
+    /// Creates a new sharded storage over the node database and the given
+    /// archive shards.
+    ///
+    /// Returns an error if the shards don't form a contiguous chain.
+    pub fn new(
+        live: DatabaseStorage<E>,
+        archives: Vec<(Arc<Environment<E>>, std::path::PathBuf)>,
+    ) -> Result<Self, ShardError> {
+        let mut shards = Vec::with_capacity(archives.len());
+        for (db, path) in archives {
+            // archive shards are encrypted with the same key as the node database.
+            let storage = match live.encryption() {
+                None => DatabaseStorage::new(db),
+                Some(key) => DatabaseStorage::new(db).with_encryption(key.clone()),
+            };
+            let first = storage.earliest_available_block()?;
+            let last = storage.highest_accepted_block()?;
+            match (first, last) {
+                (Some(first), Some(last)) => shards.push(ArchiveShard {
+                    storage,
+                    first,
+                    last,
+                }),
+                _ => return Err(ShardError::EmptyShard(path)),
+            }
+        }
+        shards.sort_by_key(|shard| shard.first.number());
+
+        for pair in shards.windows(2) {
+            if pair[1].first.number() != pair[0].last.number() + 1 {
+                return Err(ShardError::NotContiguous(pair[0].last.number() + 1));
+            }
+        }
+        if let Some(shard) = shards.last() {
+            if let Some(first_live) = live.earliest_available_block()? {
+                if first_live.number() != shard.last.number() + 1 {
+                    return Err(ShardError::NotContiguous(shard.last.number() + 1));
+                }
+            }
+        }
+
+        Ok(ShardedStorage {
+            live,
+            archives: shards,
+        })
+    }
+
+    /// Returns the chain id of the network stored in the node database.
+    pub fn chain_id(&self) -> Result<Option<v1alpha2::FieldElement>, libmdbx::Error> {
+        self.live.chain_id()
+    }
+
+    /// Returns the storage that contains the block with the given number.
+    fn shard_for(&self, number: u64) -> &DatabaseStorage<E> {
+        self.archives
+            .iter()
+            .find(|shard| shard.first.number() <= number && number <= shard.last.number())
+            .map(|shard| &shard.storage)
+            .unwrap_or(&self.live)
+    }
+}
+
+impl<E: EnvironmentKind> StorageReader for ShardedStorage<E> {
+    type Error = libmdbx::Error;
+
+    fn earliest_available_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+        match self.archives.first() {
+            Some(shard) => Ok(Some(shard.first)),
+            None => self.live.earliest_available_block(),
+        }
+    }
+
+    fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+        match self.live.highest_accepted_block()? {
+            Some(block_id) => Ok(Some(block_id)),
+            None => Ok(self.archives.last().map(|shard| shard.last)),
+        }
+    }
+
+    fn highest_finalized_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+        // archive shards only contain finalized blocks.
+        match self.live.highest_finalized_block()? {
+            Some(block_id) => Ok(Some(block_id)),
+            None => Ok(self.archives.last().map(|shard| shard.last)),
+        }
+    }
+
+    fn canonical_block_id(&self, number: u64) -> Result<Option<GlobalBlockId>, Self::Error> {
+        self.shard_for(number).canonical_block_id(number)
+    }
+
+    fn read_status(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::BlockStatus>, Self::Error> {
+        self.shard_for(id.number()).read_status(id)
+    }
+
+    fn read_header(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::BlockHeader>, Self::Error> {
+        self.shard_for(id.number()).read_header(id)
+    }
+
+    fn read_body(&self, id: &GlobalBlockId) -> Result<Vec<v1alpha2::Transaction>, Self::Error> {
+        self.shard_for(id.number()).read_body(id)
+    }
+
+    fn read_new_pending_transactions(&self, id: &GlobalBlockId) -> Result<Vec<u32>, Self::Error> {
+        // pending blocks are never moved to archive shards.
+        self.live.read_new_pending_transactions(id)
+    }
+
+    fn read_receipts(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<(Vec<v1alpha2::TransactionReceipt>, Option<Bloom>), Self::Error> {
+        self.shard_for(id.number()).read_receipts(id)
+    }
+
+    fn read_state_update(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::StateUpdate>, Self::Error> {
+        self.shard_for(id.number()).read_state_update(id)
+    }
+
+    fn read_traces(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Vec<v1alpha2::TransactionTrace>, Self::Error> {
+        self.shard_for(id.number()).read_traces(id)
+    }
+
+    fn read_class(
+        &self,
+        class_hash: &v1alpha2::FieldElement,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        // classes are never moved to archive shards, but shards could come
+        // from another node.
+        if let Some(definition) = self.live.read_class(class_hash)? {
+            return Ok(Some(definition));
+        }
+        for shard in self.archives.iter().rev() {
+            if let Some(definition) = shard.storage.read_class(class_hash)? {
+                return Ok(Some(definition));
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_data_availability(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::DataAvailability>, Self::Error> {
+        // data availability is not moved to archive shards.
+        self.live.read_data_availability(id)
+    }
+
+    fn read_partial_block(&self, id: &GlobalBlockId) -> Result<Option<PartialBlock>, Self::Error> {
+        self.shard_for(id.number()).read_partial_block(id)
+    }
+}
+
+/// Moves the finalized blocks before `end` from `live` to a new archive
+/// shard in `target`.
+///
+/// L1 data availability of the moved blocks stays in `live`.
+///
+/// Returns the number of blocks moved.
+pub fn split_shard<E: EnvironmentKind>(
+    live: &DatabaseStorage<E>,
+    target: &Path,
+    end: u64,
+) -> Result<u64, ShardError> {
+    fs::create_dir_all(target)?;
+    if fs::read_dir(target)?.next().is_some() {
+        return Err(ShardError::TargetNotEmpty(target.to_path_buf()));
+    }
+
+    let start = match live.earliest_available_block()? {
+        None => return Ok(0),
+        Some(block_id) => block_id.number(),
+    };
+    match live.highest_finalized_block()? {
+        Some(finalized) if end <= finalized.number() + 1 => {}
+        _ => return Err(ShardError::NotFinalized(end.saturating_sub(1))),
+    }
+
+    let shard_db = Arc::new(Environment::<E>::builder().open(target)?);
+    let txn = shard_db.begin_rw_txn()?;
+    tables::ensure(&txn)?;
+    txn.commit()?;
+    let shard = match live.encryption() {
+        None => DatabaseStorage::new(shard_db),
+        Some(key) => DatabaseStorage::new(shard_db).with_encryption(key.clone()),
+    };
+
+    let mut moved = 0;
+    let mut batch_start = start;
+    while batch_start < end {
+        let batch_end = u64::min(batch_start + BLOCKS_PER_TXN, end);
+
+        // copy blocks first, so that blocks are never missing from both
+        // environments.
+        let mut shard_txn = shard.begin_txn()?;
+        let mut block_ids = Vec::with_capacity((batch_end - batch_start) as usize);
+        for number in batch_start..batch_end {
+            let block_id = live
+                .canonical_block_id(number)?
+                .ok_or(ShardError::NotContiguous(number))?;
+            let block = read_raw_block(live, number)?.ok_or(ShardError::NotContiguous(number))?;
+            write_raw_block(&mut shard_txn, &block_id, block)?;
+            if let Some(partial) = live.read_partial_block(&block_id)? {
+                shard_txn.write_partial_block(&block_id, &partial)?;
+            }
+            block_ids.push(block_id);
+        }
+        shard_txn.commit()?;
+
+        let txn = live.environment().begin_rw_txn()?;
+        {
+            let mut canon_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+            for block_id in &block_ids {
+                if canon_cursor.seek_exact(&block_id.number())?.is_some() {
+                    canon_cursor.del()?;
+                }
+                delete_sharded_block_data(&txn, block_id)?;
+            }
+        }
+        metadata::update_earliest_available_block(&txn)?;
+        txn.commit()?;
+
+        moved += block_ids.len() as u64;
+        info!(start = %batch_start, end = %batch_end, "moved blocks to shard");
+        batch_start = batch_end;
+    }
+
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::starknet::v1alpha2;
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        core::{BlockHash, GlobalBlockId},
+        db::{tables, BlockBody, DatabaseStorage, StorageReader, StorageWriter},
+    };
+
+    use super::{split_shard, ShardedStorage};
+
+    fn new_block_id(number: u64) -> GlobalBlockId {
+        let mut hash = [0; 32];
+        hash[24..].copy_from_slice(&number.to_be_bytes());
+        GlobalBlockId::new(number, BlockHash::from_slice(&hash).unwrap())
+    }
+
+    #[test]
+    fn test_split_shard_keeps_data_availability() {
+        let path = tempdir().unwrap();
+        let db = Arc::new(Environment::<NoWriteMap>::open(path.path()).unwrap());
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure(&txn).unwrap();
+        txn.commit().unwrap();
+        let live = DatabaseStorage::new(db);
+
+        let mut txn = live.begin_txn().unwrap();
+        for number in 0..10 {
+            let id = new_block_id(number);
+            txn.write_status(&id, v1alpha2::BlockStatus::AcceptedOnL1)
+                .unwrap();
+            txn.write_header(&id, v1alpha2::BlockHeader::default())
+                .unwrap();
+            txn.write_body(&id, BlockBody::default()).unwrap();
+            txn.write_receipts(&id, Vec::default()).unwrap();
+            txn.extend_canonical_chain(&id).unwrap();
+        }
+        txn.commit().unwrap();
+        let data_availability = v1alpha2::DataAvailability {
+            l1_block_number: 42,
+            ..v1alpha2::DataAvailability::default()
+        };
+        live.write_data_availability(&[(new_block_id(2), data_availability.clone())])
+            .unwrap();
+
+        let shard_path = tempdir().unwrap();
+        let moved = split_shard(&live, shard_path.path(), 5).unwrap();
+        assert_eq!(moved, 5);
+
+        let shard_db = Arc::new(Environment::<NoWriteMap>::open(shard_path.path()).unwrap());
+        let storage =
+            ShardedStorage::new(live, vec![(shard_db, shard_path.path().to_path_buf())]).unwrap();
+        assert!(storage.read_header(&new_block_id(2)).unwrap().is_some());
+        assert_eq!(
+            storage.read_data_availability(&new_block_id(2)).unwrap(),
+            Some(data_availability)
+        );
+    }
+}
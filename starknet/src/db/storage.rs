@@ -1,21 +1,36 @@
 //! Abstraction over raw db tables.
 
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
 use apibara_core::starknet::v1alpha2;
 use apibara_node::db::{
     libmdbx::{self, Environment, EnvironmentKind, Transaction, RW},
-    MdbxErrorExt, MdbxTransactionExt, TableCursor,
+    MdbxEnvironmentExt, MdbxErrorExt, MdbxTransactionExt, Table, TableCursor,
 };
 use mockall::automock;
+use prost::Message;
 
-use crate::core::GlobalBlockId;
+use crate::core::{BlockHash, GlobalBlockId};
 
 use super::{
     block::{BlockBody, BlockReceipts, HasherKeys, RawBloom},
+    checksum::{self, BlockChecksum, ChecksumStatus},
+    compression::{
+        self, CompressedData, CompressedDataRef, CompressionDictionary, DictionaryCache,
+    },
+    encryption::EncryptionKey,
+    journal::JournalEntry,
+    metadata,
+    metrics::storage_metrics,
+    partial::PartialBlock,
+    quota::{QuotaKey, QuotaUsage},
     tables,
+    trace::BlockTraces,
 };
 
+/// Minimum number of samples needed to train a compression dictionary.
+const MIN_DICTIONARY_SAMPLES: usize = 256;
+
 /// Bloom filter over field elements.
 pub type Bloom = bloomfilter::Bloom<v1alpha2::FieldElement>;
 
@@ -28,6 +43,12 @@ pub enum MockStorageReaderError {}
 pub trait StorageReader {
     type Error: std::error::Error + Send + Sync + 'static;
 
+    /// Returns the earliest block available in storage.
+    ///
+    /// Blocks before this one were never ingested or were removed, so
+    /// they cannot be served to clients.
+    fn earliest_available_block(&self) -> Result<Option<GlobalBlockId>, Self::Error>;
+
     /// Returns the highest accepted block that was indexed.
     fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error>;
 
@@ -49,6 +70,10 @@ pub trait StorageReader {
     /// Returns all transactions in the given block.
     fn read_body(&self, id: &GlobalBlockId) -> Result<Vec<v1alpha2::Transaction>, Self::Error>;
 
+    /// Returns the index of the transactions in the pending block that are
+    /// new since the previous pending snapshot.
+    fn read_new_pending_transactions(&self, id: &GlobalBlockId) -> Result<Vec<u32>, Self::Error>;
+
     /// Returns all receipts in the given block together with its bloom filter.
     fn read_receipts(
         &self,
@@ -60,6 +85,34 @@ pub trait StorageReader {
         &self,
         id: &GlobalBlockId,
     ) -> Result<Option<v1alpha2::StateUpdate>, Self::Error>;
+
+    /// Returns the execution traces of the transactions in the given block.
+    ///
+    /// Returns an empty list if traces were not ingested.
+    fn read_traces(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Vec<v1alpha2::TransactionTrace>, Self::Error>;
+
+    /// Returns the JSON definition of the class with the given hash.
+    fn read_class(
+        &self,
+        class_hash: &v1alpha2::FieldElement,
+    ) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Returns the L1 data availability of the given block, if ingested.
+    fn read_data_availability(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::DataAvailability>, Self::Error>;
+
+    /// Returns the data missing from the block, or `None` if the block is
+    /// complete.
+    ///
+    /// Blocks backfilled from their events have no transactions, receipts
+    /// without the events that didn't match the backfill filter and no state
+    /// update. Degraded blocks miss the data the provider failed to return.
+    fn read_partial_block(&self, id: &GlobalBlockId) -> Result<Option<PartialBlock>, Self::Error>;
 }
 
 /// An object to write chain data to storage in a single transaction.
@@ -105,11 +158,47 @@ pub trait StorageWriter {
         id: &GlobalBlockId,
         state_update: v1alpha2::StateUpdate,
     ) -> Result<(), Self::Error>;
+
+    /// Writes the execution traces of the transactions in a block.
+    fn write_traces(
+        &mut self,
+        id: &GlobalBlockId,
+        traces: Vec<v1alpha2::TransactionTrace>,
+    ) -> Result<(), Self::Error>;
+
+    /// Returns true if the class with the given hash is stored.
+    fn has_class(&mut self, class_hash: &v1alpha2::FieldElement) -> Result<bool, Self::Error>;
+
+    /// Writes the JSON definition of a class.
+    ///
+    /// Classes are immutable, so existing classes are not overwritten.
+    fn write_class(
+        &mut self,
+        class_hash: &v1alpha2::FieldElement,
+        definition: &[u8],
+    ) -> Result<(), Self::Error>;
+
+    /// Records the data missing from the block.
+    ///
+    /// Complete blocks have no record, so writing a complete block clears
+    /// the data previously missing.
+    fn write_partial_block(
+        &mut self,
+        id: &GlobalBlockId,
+        partial: &PartialBlock,
+    ) -> Result<(), Self::Error>;
+
+    /// Removes the data of the pending block at the given height.
+    ///
+    /// Returns true if any data was removed.
+    fn remove_pending_block(&mut self, number: u64) -> Result<bool, Self::Error>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct DatabaseStorage<E: EnvironmentKind> {
     db: Arc<Environment<E>>,
+    dictionaries: DictionaryCache,
+    encryption: Option<EncryptionKey>,
 }
 
 pub struct DatabaseStorageWriter<'env, 'txn, E: EnvironmentKind> {
@@ -119,12 +208,59 @@ pub struct DatabaseStorageWriter<'env, 'txn, E: EnvironmentKind> {
     body_cursor: TableCursor<'txn, tables::BlockBodyTable, RW>,
     receipts_cursor: TableCursor<'txn, tables::BlockReceiptsTable, RW>,
     state_update_cursor: TableCursor<'txn, tables::StateUpdateTable, RW>,
+    traces_cursor: TableCursor<'txn, tables::BlockTracesTable, RW>,
+    class_cursor: TableCursor<'txn, tables::ContractClassTable, RW>,
     canonical_chain_cursor: TableCursor<'txn, tables::CanonicalChainTable, RW>,
+    checksum_cursor: TableCursor<'txn, tables::BlockChecksumTable, RW>,
+    dictionary: Option<(u64, Arc<Vec<u8>>)>,
+    encryption: Option<EncryptionKey>,
+}
+
+impl<E: EnvironmentKind> Clone for DatabaseStorage<E> {
+    fn clone(&self) -> Self {
+        DatabaseStorage {
+            db: self.db.clone(),
+            dictionaries: self.dictionaries.clone(),
+            encryption: self.encryption.clone(),
+        }
+    }
 }
 
 impl<E: EnvironmentKind> DatabaseStorage<E> {
     pub fn new(db: Arc<Environment<E>>) -> Self {
-        DatabaseStorage { db }
+        DatabaseStorage {
+            db,
+            dictionaries: DictionaryCache::default(),
+            encryption: None,
+        }
+    }
+
+    /// Encrypt new block data with the given key, and use it to decrypt
+    /// existing data.
+    pub fn with_encryption(mut self, key: EncryptionKey) -> Self {
+        self.encryption = Some(key);
+        self
+    }
+
+    /// Returns the key used to encrypt block data, if any.
+    pub fn encryption(&self) -> Option<&EncryptionKey> {
+        self.encryption.as_ref()
+    }
+
+    /// Returns the underlying environment.
+    pub(crate) fn environment(&self) -> &Arc<Environment<E>> {
+        &self.db
+    }
+
+    /// Doubles the maximum size of the database.
+    ///
+    /// Returns the new maximum size, in bytes. Fails if the database mapping
+    /// cannot be extended, in which case the node must be restarted with a
+    /// larger maximum size.
+    pub fn grow(&self) -> Result<usize, libmdbx::Error> {
+        let max_size = self.db.max_size()?.saturating_mul(2);
+        self.db.set_max_size(max_size)?;
+        Ok(max_size)
     }
 
     pub fn begin_txn(&self) -> Result<DatabaseStorageWriter<'_, '_, E>, libmdbx::Error> {
@@ -134,7 +270,11 @@ impl<E: EnvironmentKind> DatabaseStorage<E> {
         let body_cursor = txn.open_cursor::<tables::BlockBodyTable>()?;
         let receipts_cursor = txn.open_cursor::<tables::BlockReceiptsTable>()?;
         let state_update_cursor = txn.open_cursor::<tables::StateUpdateTable>()?;
+        let traces_cursor = txn.open_cursor::<tables::BlockTracesTable>()?;
+        let class_cursor = txn.open_cursor::<tables::ContractClassTable>()?;
         let canonical_chain_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+        let checksum_cursor = txn.open_cursor::<tables::BlockChecksumTable>()?;
+        let dictionary = self.dictionaries.latest(&txn)?;
         let writer = DatabaseStorageWriter {
             txn,
             status_cursor,
@@ -142,74 +282,312 @@ impl<E: EnvironmentKind> DatabaseStorage<E> {
             body_cursor,
             receipts_cursor,
             state_update_cursor,
+            traces_cursor,
+            class_cursor,
             canonical_chain_cursor,
+            checksum_cursor,
+            dictionary,
+            encryption: self.encryption.clone(),
         };
         Ok(writer)
     }
-}
 
-impl<E: EnvironmentKind> StorageReader for DatabaseStorage<E> {
-    type Error = libmdbx::Error;
+    /// Returns true if the state update of the given block is stored.
+    pub fn has_state_update(&self, id: &GlobalBlockId) -> Result<bool, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        let has_state_update = txn
+            .open_table::<tables::StateUpdateTable>()?
+            .get_bytes(id)?
+            .is_some();
+        txn.commit()?;
+        Ok(has_state_update)
+    }
 
-    #[tracing::instrument(level = "trace", skip(self))]
-    fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+    /// Compares the data stored for the given block with its checksum.
+    pub fn verify_checksum(&self, id: &GlobalBlockId) -> Result<ChecksumStatus, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        let status = checksum::verify_block_checksum(&txn, id)?;
+        txn.commit()?;
+        Ok(status)
+    }
+
+    /// Returns the ranges of block numbers missing from the canonical chain,
+    /// between the earliest canonical block and `end` (exclusive).
+    pub fn canonical_chain_gaps(&self, end: u64) -> Result<Vec<Range<u64>>, libmdbx::Error> {
         let txn = self.db.begin_ro_txn()?;
         let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
-        let block_id = match cursor.last()? {
-            None => None,
-            Some((number, hash)) => {
-                let hash = (&hash).try_into().map_err(libmdbx::Error::decode_error)?;
-                Some(GlobalBlockId::new(number, hash))
+        let mut gaps = Vec::new();
+        let mut previous = match cursor.first()? {
+            None => {
+                txn.commit()?;
+                return Ok(gaps);
             }
+            Some((number, _)) => number,
         };
+        while let Some((number, _)) = cursor.next()? {
+            if previous + 1 >= end {
+                break;
+            }
+            if number != previous + 1 {
+                gaps.push(previous + 1..u64::min(number, end));
+            }
+            previous = number;
+        }
         txn.commit()?;
-        Ok(block_id)
+        Ok(gaps)
     }
 
-    #[tracing::instrument(level = "trace", skip(self))]
-    fn highest_finalized_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+    /// Returns the chain id of the network stored in the database, if any.
+    pub fn chain_id(&self) -> Result<Option<v1alpha2::FieldElement>, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        let chain_id = txn.open_table::<tables::ChainIdTable>()?.get(&())?;
+        txn.commit()?;
+        Ok(chain_id)
+    }
+
+    /// Stores the chain id of the network.
+    pub fn write_chain_id(&self, chain_id: &v1alpha2::FieldElement) -> Result<(), libmdbx::Error> {
+        let txn = self.db.begin_rw_txn()?;
+        txn.open_cursor::<tables::ChainIdTable>()?
+            .put(&(), chain_id)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Returns the most recent block with L1 data availability, together
+    /// with its data availability.
+    pub fn latest_data_availability(
+        &self,
+    ) -> Result<Option<(GlobalBlockId, v1alpha2::DataAvailability)>, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        let latest = txn.open_cursor::<tables::DataAvailabilityTable>()?.last()?;
+        txn.commit()?;
+        Ok(latest)
+    }
+
+    /// Stores the L1 data availability of the given blocks.
+    pub fn write_data_availability(
+        &self,
+        blocks: &[(GlobalBlockId, v1alpha2::DataAvailability)],
+    ) -> Result<(), libmdbx::Error> {
+        let txn = self.db.begin_rw_txn()?;
+        let mut cursor = txn.open_cursor::<tables::DataAvailabilityTable>()?;
+        for (id, data_availability) in blocks {
+            cursor.seek_exact(id)?;
+            cursor.put(id, data_availability)?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Returns all entries of the ingestion journal, by block number.
+    pub fn journal_entries(&self) -> Result<Vec<(u64, JournalEntry)>, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        let mut cursor = txn.open_cursor::<tables::IngestionJournalTable>()?;
+        let mut entries = Vec::default();
+        let mut item = cursor.first()?;
+        while let Some(entry) = item {
+            entries.push(entry);
+            item = cursor.next()?;
+        }
+        txn.commit()?;
+        Ok(entries)
+    }
+
+    /// Stores the ingestion journal entry of the given block.
+    pub fn write_journal_entry(
+        &self,
+        number: u64,
+        entry: &JournalEntry,
+    ) -> Result<(), libmdbx::Error> {
+        let txn = self.db.begin_rw_txn()?;
+        let mut cursor = txn.open_cursor::<tables::IngestionJournalTable>()?;
+        cursor.seek_exact(&number)?;
+        cursor.put(&number, entry)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Removes the ingestion journal entry of the given block.
+    pub fn remove_journal_entry(&self, number: u64) -> Result<(), libmdbx::Error> {
+        let txn = self.db.begin_rw_txn()?;
+        let mut cursor = txn.open_cursor::<tables::IngestionJournalTable>()?;
+        if cursor.seek_exact(&number)?.is_some() {
+            cursor.del()?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Returns the quota usage of the given caller.
+    pub fn quota_usage(&self, identity: &str) -> Result<Option<QuotaUsage>, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        let mut cursor = txn.open_cursor::<tables::QuotaUsageTable>()?;
+        let usage = cursor
+            .seek_exact(&QuotaKey(identity.to_string()))?
+            .map(|(_, usage)| usage);
+        txn.commit()?;
+        Ok(usage)
+    }
+
+    /// Stores the quota usage of the given caller.
+    pub fn write_quota_usage(
+        &self,
+        identity: &str,
+        usage: &QuotaUsage,
+    ) -> Result<(), libmdbx::Error> {
+        let txn = self.db.begin_rw_txn()?;
+        let mut cursor = txn.open_cursor::<tables::QuotaUsageTable>()?;
+        let key = QuotaKey(identity.to_string());
+        cursor.seek_exact(&key)?;
+        cursor.put(&key, usage)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Returns true if a compression dictionary was trained.
+    pub fn has_compression_dictionary(&self) -> Result<bool, libmdbx::Error> {
+        let txn = self.db.begin_ro_txn()?;
+        let mut cursor = txn.open_cursor::<tables::CompressionDictionaryTable>()?;
+        let has_dictionary = cursor.last()?.is_some();
+        txn.commit()?;
+        Ok(has_dictionary)
+    }
+
+    /// Trains a new compression dictionary on the bodies and receipts of the
+    /// most recent `max_blocks` canonical blocks.
+    ///
+    /// New blocks are compressed with the new dictionary, existing blocks keep
+    /// using the dictionary they were compressed with.
+    /// Returns the id of the new dictionary, or `None` if there is not enough
+    /// data to train one.
+    pub fn train_compression_dictionary(
+        &self,
+        max_blocks: usize,
+    ) -> Result<Option<u64>, libmdbx::Error> {
         let txn = self.db.begin_ro_txn()?;
         let mut canon_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
-        let mut status_cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
+        let mut body_cursor = txn.open_cursor::<tables::BlockBodyTable>()?;
+        let mut receipts_cursor = txn.open_cursor::<tables::BlockReceiptsTable>()?;
+
+        let mut samples = Vec::new();
+        let mut blocks = 0;
         let mut maybe_block_id = canon_cursor.last()?;
         while let Some((block_num, block_hash)) = maybe_block_id {
-            let block_hash = (&block_hash)
-                .try_into()
-                .map_err(libmdbx::Error::decode_error)?;
-            let block_id = GlobalBlockId::new(block_num, block_hash);
-            let (_, status) = status_cursor
-                .seek_exact(&block_id)?
-                .expect("database is in inconsistent state.");
-
-            if status.status().is_finalized() {
-                txn.commit()?;
-                return Ok(Some(block_id));
+            if blocks >= max_blocks {
+                break;
             }
-
+            let block_id = GlobalBlockId::new(block_num, (&block_hash).into());
+            if let Some(body) = body_cursor.seek_exact_bytes(&block_id)? {
+                let body: BlockBody =
+                    self.dictionaries
+                        .decode(&txn, &body, self.encryption.as_ref())?;
+                samples.push(body.encode_to_vec());
+            }
+            if let Some(receipts) = receipts_cursor.seek_exact_bytes(&block_id)? {
+                let receipts: BlockReceipts =
+                    self.dictionaries
+                        .decode(&txn, &receipts, self.encryption.as_ref())?;
+                samples.push(receipts.encode_to_vec());
+            }
+            blocks += 1;
             maybe_block_id = canon_cursor.prev()?;
         }
         txn.commit()?;
-        Ok(None)
+
+        if samples.len() < MIN_DICTIONARY_SAMPLES {
+            return Ok(None);
+        }
+
+        let data = compression::train_dictionary(&samples).map_err(libmdbx::Error::decode_error)?;
+
+        let txn = self.db.begin_rw_txn()?;
+        let mut cursor = txn.open_cursor::<tables::CompressionDictionaryTable>()?;
+        let id = cursor.last()?.map(|t| t.0 + 1).unwrap_or(1);
+        cursor.put(&id, &CompressionDictionary { data })?;
+        txn.commit()?;
+        Ok(Some(id))
     }
+}
+
+impl<E: EnvironmentKind> StorageReader for DatabaseStorage<E> {
+    type Error = libmdbx::Error;
 
     #[tracing::instrument(level = "trace", skip(self))]
-    fn canonical_block_id(&self, number: u64) -> Result<Option<GlobalBlockId>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
-        match cursor.seek_exact(&number)? {
-            None => {
-                txn.commit()?;
-                Ok(None)
+    fn earliest_available_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+        storage_metrics().record("earliest_available_block", || {
+            let txn = self.db.begin_ro_txn()?;
+            let metadata = metadata::read_metadata(&txn)?;
+            let block_id = match metadata.earliest_block_number {
+                Some(_) => metadata.earliest_available_block(),
+                // databases not written since the metadata was added.
+                None => {
+                    let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+                    cursor
+                        .first()?
+                        .map(|(number, hash)| GlobalBlockId::new(number, (&hash).into()))
+                }
+            };
+            txn.commit()?;
+            Ok(block_id)
+        })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn highest_accepted_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+        storage_metrics().record("highest_accepted_block", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+            let block_id = cursor
+                .last()?
+                .map(|(number, hash)| GlobalBlockId::new(number, (&hash).into()));
+            txn.commit()?;
+            Ok(block_id)
+        })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn highest_finalized_block(&self) -> Result<Option<GlobalBlockId>, Self::Error> {
+        storage_metrics().record("highest_finalized_block", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut canon_cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+            let mut status_cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
+            let mut maybe_block_id = canon_cursor.last()?;
+            while let Some((block_num, block_hash)) = maybe_block_id {
+                let block_id = GlobalBlockId::new(block_num, (&block_hash).into());
+                let (_, status) = status_cursor
+                    .seek_exact(&block_id)?
+                    .expect("database is in inconsistent state.");
+
+                if status.status().is_finalized() {
+                    txn.commit()?;
+                    return Ok(Some(block_id));
+                }
+
+                maybe_block_id = canon_cursor.prev()?;
             }
-            Some((_, block_hash)) => {
-                let block_hash = (&block_hash)
-                    .try_into()
-                    .map_err(libmdbx::Error::decode_error)?;
-                let block_id = GlobalBlockId::new(number, block_hash);
-                txn.commit()?;
-                Ok(Some(block_id))
+            txn.commit()?;
+            Ok(None)
+        })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn canonical_block_id(&self, number: u64) -> Result<Option<GlobalBlockId>, Self::Error> {
+        storage_metrics().record("canonical_block_id", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::CanonicalChainTable>()?;
+            match cursor.seek_exact(&number)? {
+                None => {
+                    txn.commit()?;
+                    Ok(None)
+                }
+                Some((_, block_hash)) => {
+                    let block_id = GlobalBlockId::new(number, (&block_hash).into());
+                    txn.commit()?;
+                    Ok(Some(block_id))
+                }
             }
-        }
+        })
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
@@ -217,11 +595,13 @@ impl<E: EnvironmentKind> StorageReader for DatabaseStorage<E> {
         &self,
         id: &GlobalBlockId,
     ) -> Result<Option<v1alpha2::BlockStatus>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
-        let status = cursor.seek_exact(id)?.map(|t| t.1.status());
-        txn.commit()?;
-        Ok(status)
+        storage_metrics().record("read_status", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::BlockStatusTable>()?;
+            let status = cursor.seek_exact(id)?.map(|t| t.1.status());
+            txn.commit()?;
+            Ok(status)
+        })
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
@@ -229,23 +609,51 @@ impl<E: EnvironmentKind> StorageReader for DatabaseStorage<E> {
         &self,
         id: &GlobalBlockId,
     ) -> Result<Option<v1alpha2::BlockHeader>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::BlockHeaderTable>()?;
-        let header = cursor.seek_exact(id)?.map(|t| t.1);
-        txn.commit()?;
-        Ok(header)
+        storage_metrics().record("read_header", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::BlockHeaderTable>()?;
+            let header = cursor.seek_exact(id)?.map(|t| t.1);
+            txn.commit()?;
+            Ok(header)
+        })
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
     fn read_body(&self, id: &GlobalBlockId) -> Result<Vec<v1alpha2::Transaction>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::BlockBodyTable>()?;
-        let transactions = cursor
-            .seek_exact(id)?
-            .map(|t| t.1.transactions)
-            .unwrap_or_default();
-        txn.commit()?;
-        Ok(transactions)
+        storage_metrics().record("read_body", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::BlockBodyTable>()?;
+            let transactions = match cursor.seek_exact_bytes(id)? {
+                None => Vec::default(),
+                Some(body) => {
+                    let body: BlockBody =
+                        self.dictionaries
+                            .decode(&txn, &body, self.encryption.as_ref())?;
+                    body.transactions
+                }
+            };
+            txn.commit()?;
+            Ok(transactions)
+        })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_new_pending_transactions(&self, id: &GlobalBlockId) -> Result<Vec<u32>, Self::Error> {
+        storage_metrics().record("read_new_pending_transactions", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::BlockBodyTable>()?;
+            let new_transactions = match cursor.seek_exact_bytes(id)? {
+                None => Vec::default(),
+                Some(body) => {
+                    let body: BlockBody =
+                        self.dictionaries
+                            .decode(&txn, &body, self.encryption.as_ref())?;
+                    body.new_pending_transactions
+                }
+            };
+            txn.commit()?;
+            Ok(new_transactions)
+        })
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
@@ -253,13 +661,21 @@ impl<E: EnvironmentKind> StorageReader for DatabaseStorage<E> {
         &self,
         id: &GlobalBlockId,
     ) -> Result<(Vec<v1alpha2::TransactionReceipt>, Option<Bloom>), Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::BlockReceiptsTable>()?;
-        let block_receipts_data = cursor.seek_exact(id)?.map(|t| t.1).unwrap_or_default();
-        let receipts = block_receipts_data.receipts;
-        let bloom = block_receipts_data.bloom.and_then(|b| b.into());
-        txn.commit()?;
-        Ok((receipts, bloom))
+        storage_metrics().record("read_receipts", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::BlockReceiptsTable>()?;
+            let block_receipts_data: BlockReceipts = match cursor.seek_exact_bytes(id)? {
+                None => BlockReceipts::default(),
+                Some(receipts) => {
+                    self.dictionaries
+                        .decode(&txn, &receipts, self.encryption.as_ref())?
+                }
+            };
+            let receipts = block_receipts_data.receipts;
+            let bloom = block_receipts_data.bloom.and_then(|b| b.into());
+            txn.commit()?;
+            Ok((receipts, bloom))
+        })
     }
 
     #[tracing::instrument(level = "trace", skip(self))]
@@ -267,11 +683,84 @@ impl<E: EnvironmentKind> StorageReader for DatabaseStorage<E> {
         &self,
         id: &GlobalBlockId,
     ) -> Result<Option<v1alpha2::StateUpdate>, Self::Error> {
-        let txn = self.db.begin_ro_txn()?;
-        let mut cursor = txn.open_cursor::<tables::StateUpdateTable>()?;
-        let state_update = cursor.seek_exact(id)?.map(|t| t.1);
-        txn.commit()?;
-        Ok(state_update)
+        storage_metrics().record("read_state_update", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::StateUpdateTable>()?;
+            let state_update = cursor.seek_exact(id)?.map(|t| t.1);
+            txn.commit()?;
+            Ok(state_update)
+        })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_traces(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Vec<v1alpha2::TransactionTrace>, Self::Error> {
+        storage_metrics().record("read_traces", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::BlockTracesTable>()?;
+            let traces = match cursor.seek_exact_bytes(id)? {
+                None => Vec::default(),
+                Some(traces) => {
+                    let traces: BlockTraces =
+                        self.dictionaries
+                            .decode(&txn, &traces, self.encryption.as_ref())?;
+                    traces.traces
+                }
+            };
+            txn.commit()?;
+            Ok(traces)
+        })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_class(
+        &self,
+        class_hash: &v1alpha2::FieldElement,
+    ) -> Result<Option<Vec<u8>>, Self::Error> {
+        storage_metrics().record("read_class", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::ContractClassTable>()?;
+            let definition = match cursor.seek_exact_bytes(&class_hash.into())? {
+                None => None,
+                Some(bytes) => Some(
+                    CompressedDataRef::decode(&bytes)
+                        .map_err(libmdbx::Error::decode_error)?
+                        .decrypt(self.encryption.as_ref())
+                        .map_err(libmdbx::Error::decode_error)?
+                        .decompress(None)
+                        .map_err(libmdbx::Error::decode_error)?,
+                ),
+            };
+            txn.commit()?;
+            Ok(definition)
+        })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_data_availability(
+        &self,
+        id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::DataAvailability>, Self::Error> {
+        storage_metrics().record("read_data_availability", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::DataAvailabilityTable>()?;
+            let data_availability = cursor.seek_exact(id)?.map(|t| t.1);
+            txn.commit()?;
+            Ok(data_availability)
+        })
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn read_partial_block(&self, id: &GlobalBlockId) -> Result<Option<PartialBlock>, Self::Error> {
+        storage_metrics().record("read_partial_block", || {
+            let txn = self.db.begin_ro_txn()?;
+            let mut cursor = txn.open_cursor::<tables::PartialBlockTable>()?;
+            let partial = cursor.seek_exact(id)?.map(|t| t.1);
+            txn.commit()?;
+            Ok(partial)
+        })
     }
 }
 
@@ -290,6 +779,8 @@ impl<'env, 'txn, E: EnvironmentKind> StorageWriter for DatabaseStorageWriter<'en
         let hash = id.hash().into();
         self.canonical_chain_cursor.seek_exact(&number)?;
         self.canonical_chain_cursor.put(&number, &hash)?;
+        metadata::extend_earliest_available_block(&self.txn, id)?;
+        self.update_checksum(id)?;
         Ok(())
     }
 
@@ -301,6 +792,9 @@ impl<'env, 'txn, E: EnvironmentKind> StorageWriter for DatabaseStorageWriter<'en
             if current_hash == target_hash {
                 self.canonical_chain_cursor.del()?;
                 self.write_status(id, v1alpha2::BlockStatus::Rejected)?;
+                if metadata::read_metadata(&self.txn)?.earliest_block_number == Some(number) {
+                    metadata::update_earliest_available_block(&self.txn)?;
+                }
             }
         }
         Ok(())
@@ -333,6 +827,7 @@ impl<'env, 'txn, E: EnvironmentKind> StorageWriter for DatabaseStorageWriter<'en
 
     #[tracing::instrument(level = "trace", skip(self, body))]
     fn write_body(&mut self, id: &GlobalBlockId, body: BlockBody) -> Result<(), Self::Error> {
+        let body = self.compress(&body)?;
         self.body_cursor.seek_exact(id)?;
         self.body_cursor.put(id, &body)?;
         Ok(())
@@ -365,6 +860,7 @@ impl<'env, 'txn, E: EnvironmentKind> StorageWriter for DatabaseStorageWriter<'en
             receipts,
             bloom: Some(bloom.into()),
         };
+        let body = self.compress(&body)?;
         self.receipts_cursor.seek_exact(id)?;
         self.receipts_cursor.put(id, &body)?;
         Ok(())
@@ -380,6 +876,118 @@ impl<'env, 'txn, E: EnvironmentKind> StorageWriter for DatabaseStorageWriter<'en
         self.state_update_cursor.put(id, &state_update)?;
         Ok(())
     }
+
+    #[tracing::instrument(level = "trace", skip(self, traces))]
+    fn write_traces(
+        &mut self,
+        id: &GlobalBlockId,
+        traces: Vec<v1alpha2::TransactionTrace>,
+    ) -> Result<(), Self::Error> {
+        let traces = self.compress(&BlockTraces { traces })?;
+        self.traces_cursor.seek_exact(id)?;
+        self.traces_cursor.put(id, &traces)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn has_class(&mut self, class_hash: &v1alpha2::FieldElement) -> Result<bool, Self::Error> {
+        let has_class = self
+            .class_cursor
+            .seek_exact_bytes(&class_hash.into())?
+            .is_some();
+        Ok(has_class)
+    }
+
+    #[tracing::instrument(level = "trace", skip(self, definition))]
+    fn write_class(
+        &mut self,
+        class_hash: &v1alpha2::FieldElement,
+        definition: &[u8],
+    ) -> Result<(), Self::Error> {
+        if self.has_class(class_hash)? {
+            return Ok(());
+        }
+        // class definitions are large and compress well on their own.
+        let definition =
+            CompressedData::compress(definition, None).map_err(libmdbx::Error::decode_error)?;
+        let definition = self.encrypt(definition)?;
+        self.class_cursor.put(&class_hash.into(), &definition)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn write_partial_block(
+        &mut self,
+        id: &GlobalBlockId,
+        partial: &PartialBlock,
+    ) -> Result<(), Self::Error> {
+        let mut cursor = self.txn.open_cursor::<tables::PartialBlockTable>()?;
+        if partial.is_complete() {
+            delete_entry(&mut cursor, id)?;
+        } else {
+            cursor.seek_exact(id)?;
+            cursor.put(id, partial)?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "trace", skip(self))]
+    fn remove_pending_block(&mut self, number: u64) -> Result<bool, Self::Error> {
+        // pending blocks don't have a hash yet.
+        let id = GlobalBlockId::new(number, BlockHash::zero());
+        let mut removed = delete_entry(&mut self.status_cursor, &id)?;
+        removed |= delete_entry(&mut self.header_cursor, &id)?;
+        removed |= delete_entry(&mut self.body_cursor, &id)?;
+        removed |= delete_entry(&mut self.receipts_cursor, &id)?;
+        removed |= delete_entry(&mut self.state_update_cursor, &id)?;
+        removed |= delete_entry(&mut self.traces_cursor, &id)?;
+        Ok(removed)
+    }
+}
+
+fn delete_entry<T: Table>(
+    cursor: &mut TableCursor<'_, T, RW>,
+    key: &T::Key,
+) -> Result<bool, libmdbx::Error> {
+    if cursor.seek_exact_bytes(key)?.is_none() {
+        return Ok(false);
+    }
+    cursor.del()?;
+    Ok(true)
+}
+
+impl<'env, 'txn, E: EnvironmentKind> DatabaseStorageWriter<'env, 'txn, E> {
+    /// Stores the checksum of the data written for the given block.
+    ///
+    /// Called when the block is added to the canonical chain, or after its
+    /// data is written again.
+    pub fn update_checksum(&mut self, id: &GlobalBlockId) -> Result<(), libmdbx::Error> {
+        let checksum = checksum::compute_block_checksum(&self.txn, id)?;
+        self.checksum_cursor.seek_exact(id)?;
+        self.checksum_cursor.put(id, &BlockChecksum { checksum })?;
+        Ok(())
+    }
+
+    /// Compress the message with the most recent dictionary, then encrypt it
+    /// if encryption is enabled.
+    fn compress<M: Message>(&self, message: &M) -> Result<CompressedData, libmdbx::Error> {
+        let dictionary = self
+            .dictionary
+            .as_ref()
+            .map(|(id, dictionary)| (*id, dictionary.as_slice()));
+        let compressed = CompressedData::compress(&message.encode_to_vec(), dictionary)
+            .map_err(libmdbx::Error::decode_error)?;
+        self.encrypt(compressed)
+    }
+
+    fn encrypt(&self, compressed: CompressedData) -> Result<CompressedData, libmdbx::Error> {
+        match self.encryption {
+            None => Ok(compressed),
+            Some(ref key) => compressed
+                .encrypt(key)
+                .map_err(libmdbx::Error::decode_error),
+        }
+    }
 }
 
 impl From<RawBloom> for Option<Bloom> {
@@ -0,0 +1,27 @@
+//! Transaction traces.
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::Table;
+use prost::Message;
+
+use super::compression::CompressedData;
+use crate::core::GlobalBlockId;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BlockTraces {
+    #[prost(message, repeated, tag = "1")]
+    pub traces: prost::alloc::vec::Vec<v1alpha2::TransactionTrace>,
+}
+
+/// Store block transaction traces, compressed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockTracesTable {}
+
+impl Table for BlockTracesTable {
+    type Key = GlobalBlockId;
+    type Value = CompressedData;
+
+    fn db_name() -> &'static str {
+        "BlockTraces"
+    }
+}
@@ -2,20 +2,20 @@
 
 use apibara_node::db::Table;
 
-use super::block::{BlockBody, BlockReceipts};
+use super::compression::CompressedData;
 use crate::core::GlobalBlockId;
 
-/// Store block body.
+/// Store block body, compressed.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct BlockBodyTable {}
 
-/// Store block receipts.
+/// Store block receipts, compressed.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct BlockReceiptsTable {}
 
 impl Table for BlockBodyTable {
     type Key = GlobalBlockId;
-    type Value = BlockBody;
+    type Value = CompressedData;
 
     fn db_name() -> &'static str {
         "BlockBody"
@@ -24,7 +24,7 @@ impl Table for BlockBodyTable {
 
 impl Table for BlockReceiptsTable {
     type Key = GlobalBlockId;
-    type Value = BlockReceipts;
+    type Value = CompressedData;
 
     fn db_name() -> &'static str {
         "BlockReceipts"
@@ -0,0 +1,128 @@
+//! Export blocks to flat files.
+//!
+//! Blocks are read directly from storage and written one per record, either
+//! as newline-delimited JSON or as length-delimited protobuf messages.
+//! Unfiltered exports contain [v1alpha2::RawBlock] records, filtered exports
+//! contain the same [v1alpha2::Block] records sent by the stream.
+
+use std::{io::Write, ops::Range, sync::Arc};
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::server::SimpleMeter;
+use clap::ValueEnum;
+use prost::Message;
+use serde::Serialize;
+
+use crate::{
+    db::{read_raw_block, StorageReader},
+    stream::DbBatchProducer,
+};
+
+/// Format of the exported records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One JSON object per line.
+    #[default]
+    Json,
+    /// Length-delimited protobuf messages.
+    Proto,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("error reading block data")]
+    Storage(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("error writing record")]
+    Io(#[from] std::io::Error),
+    #[error("error serializing record to json")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Summary of the data written by [export_blocks].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// Number of canonical blocks read.
+    pub blocks: u64,
+    /// Number of records written.
+    pub records: u64,
+}
+
+/// Writes the canonical blocks in the given range to `writer`.
+///
+/// If `filter` is set, only blocks with data matching the filter are written.
+/// The export stops at the end of the canonical chain.
+pub fn export_blocks<R, W>(
+    storage: Arc<R>,
+    range: Range<u64>,
+    filter: Option<v1alpha2::Filter>,
+    format: ExportFormat,
+    mut writer: W,
+) -> Result<ExportSummary, ExportError>
+where
+    R: StorageReader + Send + Sync + 'static,
+    W: Write,
+{
+    let mut summary = ExportSummary::default();
+    let producer = filter.map(|filter| DbBatchProducer::with_filter(storage.clone(), filter));
+    let meter = SimpleMeter::default();
+
+    for number in range {
+        let block_id = match storage.canonical_block_id(number).map_err(storage_error)? {
+            None => break,
+            Some(block_id) => block_id,
+        };
+        summary.blocks += 1;
+
+        let written = match producer {
+            None => {
+                let block = read_raw_block(storage.as_ref(), number).map_err(storage_error)?;
+                write_record(&mut writer, format, block)?
+            }
+            Some(ref producer) => {
+                let block = producer
+                    .block_data(&block_id, &meter)
+                    .map_err(storage_error)?;
+                write_record(&mut writer, format, block)?
+            }
+        };
+
+        if written {
+            summary.records += 1;
+        }
+    }
+
+    writer.flush()?;
+    Ok(summary)
+}
+
+/// Writes the record, if any. Returns true if the record was written.
+fn write_record<W, T>(
+    writer: &mut W,
+    format: ExportFormat,
+    record: Option<T>,
+) -> Result<bool, ExportError>
+where
+    W: Write,
+    T: Message + Serialize,
+{
+    let record = match record {
+        None => return Ok(false),
+        Some(record) => record,
+    };
+
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer(&mut *writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        ExportFormat::Proto => {
+            writer.write_all(&record.encode_length_delimited_to_vec())?;
+        }
+    }
+
+    Ok(true)
+}
+
+fn storage_error<E: std::error::Error + Send + Sync + 'static>(err: E) -> ExportError {
+    ExportError::Storage(Box::new(err))
+}
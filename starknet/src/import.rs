@@ -0,0 +1,304 @@
+//! Import blocks from flat files.
+//!
+//! Imports the unfiltered files written by [crate::export]. Blocks must form
+//! a chain that extends the local canonical chain; blocks already in the
+//! canonical chain are skipped.
+
+use std::io::{self, BufRead, ErrorKind, Read};
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+use prost::Message;
+
+use crate::{
+    core::{BlockHash, GlobalBlockId, InvalidBlock},
+    db::{write_raw_block, DatabaseStorage, StorageReader, StorageWriter},
+    export::ExportFormat,
+};
+
+/// Maximum size of a protobuf record, in bytes.
+///
+/// Larger records are rejected before allocating memory for them, since
+/// the length prefix is read from an untrusted file.
+const MAX_RECORD_SIZE: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("database error")]
+    Database(#[from] libmdbx::Error),
+    #[error("error reading record")]
+    Io(#[from] io::Error),
+    #[error("error deserializing record from json")]
+    Json(#[from] serde_json::Error),
+    #[error("error decoding protobuf record")]
+    Decode(#[from] prost::DecodeError),
+    #[error("record of {0} bytes is larger than the maximum record size")]
+    RecordTooLarge(u64),
+    #[error("block is missing header")]
+    MissingBlockHeader,
+    #[error("block is missing parent hash")]
+    MissingParentHash,
+    #[error("invalid block")]
+    InvalidBlock(#[from] InvalidBlock),
+    #[error("block {block} doesn't extend block {previous}")]
+    NotLinked {
+        block: GlobalBlockId,
+        previous: GlobalBlockId,
+    },
+    #[error("block {0} conflicts with the canonical chain")]
+    Conflict(GlobalBlockId),
+}
+
+/// Summary of the data written by [import_blocks].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Number of blocks added to the canonical chain.
+    pub imported: u64,
+    /// Number of blocks skipped because already in the canonical chain.
+    pub skipped: u64,
+    /// Last block in the canonical chain.
+    pub head: Option<GlobalBlockId>,
+}
+
+/// Reads blocks from `reader` and adds them to the canonical chain.
+///
+/// Each block must be the child of the previous block. Blocks are written in
+/// their own transaction, so an import that fails midway leaves the valid
+/// prefix of the file in storage.
+pub fn import_blocks<E, R>(
+    storage: &DatabaseStorage<E>,
+    format: ExportFormat,
+    mut reader: R,
+) -> Result<ImportSummary, ImportError>
+where
+    E: EnvironmentKind,
+    R: BufRead,
+{
+    let mut summary = ImportSummary {
+        head: storage.highest_accepted_block()?,
+        ..ImportSummary::default()
+    };
+
+    while let Some(block) = read_record(&mut reader, format)? {
+        let header = block
+            .header
+            .as_ref()
+            .ok_or(ImportError::MissingBlockHeader)?;
+        let block_id = GlobalBlockId::from_block_header(header)?;
+
+        if let Some(previous) = summary.head {
+            if block_id.number() <= previous.number() {
+                match storage.canonical_block_id(block_id.number())? {
+                    Some(existing) if existing == block_id => {
+                        summary.skipped += 1;
+                        continue;
+                    }
+                    _ => return Err(ImportError::Conflict(block_id)),
+                }
+            }
+
+            let parent_hash: BlockHash = header
+                .parent_block_hash
+                .as_ref()
+                .ok_or(ImportError::MissingParentHash)?
+                .into();
+            if block_id.number() != previous.number() + 1 || parent_hash != *previous.hash() {
+                return Err(ImportError::NotLinked {
+                    block: block_id,
+                    previous,
+                });
+            }
+        }
+
+        let mut txn = storage.begin_txn()?;
+        write_raw_block(&mut txn, &block_id, block)?;
+        txn.commit()?;
+
+        summary.imported += 1;
+        summary.head = Some(block_id);
+    }
+
+    Ok(summary)
+}
+
+/// Reads the next record, returns `None` at the end of the file.
+fn read_record<R: BufRead>(
+    reader: &mut R,
+    format: ExportFormat,
+) -> Result<Option<v1alpha2::RawBlock>, ImportError> {
+    match format {
+        ExportFormat::Json => {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    return Ok(None);
+                }
+                if !line.trim().is_empty() {
+                    return Ok(Some(serde_json::from_str(&line)?));
+                }
+            }
+        }
+        ExportFormat::Proto => {
+            let length = match read_varint(reader)? {
+                None => return Ok(None),
+                Some(length) => length,
+            };
+            if length > MAX_RECORD_SIZE {
+                return Err(ImportError::RecordTooLarge(length));
+            }
+            // the buffer grows as data is read, so truncated files don't
+            // allocate the full length.
+            let mut buf = Vec::new();
+            reader.take(length).read_to_end(&mut buf)?;
+            if buf.len() as u64 != length {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+            Ok(Some(v1alpha2::RawBlock::decode(buf.as_slice())?))
+        }
+    }
+}
+
+/// Reads the varint-encoded length prefix of a record.
+///
+/// Returns `None` if the reader is at the end of the file.
+fn read_varint<R: Read>(reader: &mut R) -> Result<Option<u64>, ImportError> {
+    let mut value = 0u64;
+    for index in 0..10 {
+        let mut byte = [0u8; 1];
+        if let Err(err) = reader.read_exact(&mut byte) {
+            if index == 0 && err.kind() == ErrorKind::UnexpectedEof {
+                return Ok(None);
+            }
+            return Err(err.into());
+        }
+        value |= ((byte[0] & 0x7f) as u64) << (7 * index);
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(value));
+        }
+    }
+    Err(prost::DecodeError::new("invalid varint").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::starknet::v1alpha2;
+    use apibara_node::db::{
+        libmdbx::{Environment, NoWriteMap},
+        MdbxEnvironmentExt,
+    };
+    use tempfile::tempdir;
+
+    use crate::{
+        core::{BlockHash, GlobalBlockId},
+        db::{tables, write_raw_block, DatabaseStorage, StorageReader, StorageWriter},
+        export::{export_blocks, ExportFormat},
+    };
+
+    use super::{import_blocks, ImportError};
+
+    fn new_block_id(number: u64) -> GlobalBlockId {
+        let mut hash = [0; 32];
+        hash[24..].copy_from_slice(&number.to_be_bytes());
+        GlobalBlockId::new(number, BlockHash::from_slice(&hash).unwrap())
+    }
+
+    fn new_storage(path: &std::path::Path) -> DatabaseStorage<NoWriteMap> {
+        let db = Arc::new(Environment::<NoWriteMap>::open(path).unwrap());
+        let txn = db.begin_rw_txn().unwrap();
+        tables::ensure(&txn).unwrap();
+        txn.commit().unwrap();
+        DatabaseStorage::new(db)
+    }
+
+    fn new_block(number: u64) -> v1alpha2::RawBlock {
+        let parent_block_hash = if number == 0 {
+            None
+        } else {
+            Some(new_block_id(number - 1).hash().into())
+        };
+        v1alpha2::RawBlock {
+            status: v1alpha2::BlockStatus::AcceptedOnL1 as i32,
+            header: Some(v1alpha2::BlockHeader {
+                block_hash: Some(new_block_id(number).hash().into()),
+                parent_block_hash,
+                block_number: number,
+                ..v1alpha2::BlockHeader::default()
+            }),
+            ..v1alpha2::RawBlock::default()
+        }
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        for format in [ExportFormat::Json, ExportFormat::Proto] {
+            let source_path = tempdir().unwrap();
+            let source = new_storage(source_path.path());
+            let mut txn = source.begin_txn().unwrap();
+            for number in 0..5 {
+                write_raw_block(&mut txn, &new_block_id(number), new_block(number)).unwrap();
+            }
+            txn.commit().unwrap();
+
+            let mut file = Vec::new();
+            export_blocks(Arc::new(source), 0..3, None, format, &mut file).unwrap();
+
+            let target_path = tempdir().unwrap();
+            let target = new_storage(target_path.path());
+            let summary = import_blocks(&target, format, file.as_slice()).unwrap();
+            assert_eq!(summary.imported, 3);
+            assert_eq!(summary.head, Some(new_block_id(2)));
+            assert_eq!(
+                target.read_header(&new_block_id(2)).unwrap(),
+                new_block(2).header
+            );
+
+            // importing the same file again is a no-op.
+            let summary = import_blocks(&target, format, file.as_slice()).unwrap();
+            assert_eq!(summary.imported, 0);
+            assert_eq!(summary.skipped, 3);
+        }
+    }
+
+    #[test]
+    fn test_import_rejects_gap() {
+        let source_path = tempdir().unwrap();
+        let source = new_storage(source_path.path());
+        let mut txn = source.begin_txn().unwrap();
+        for number in 0..5 {
+            write_raw_block(&mut txn, &new_block_id(number), new_block(number)).unwrap();
+        }
+        txn.commit().unwrap();
+
+        let mut file = Vec::new();
+        export_blocks(Arc::new(source), 3..5, None, ExportFormat::Json, &mut file).unwrap();
+
+        let target_path = tempdir().unwrap();
+        let target = new_storage(target_path.path());
+        let mut txn = target.begin_txn().unwrap();
+        write_raw_block(&mut txn, &new_block_id(0), new_block(0)).unwrap();
+        txn.commit().unwrap();
+
+        let err = import_blocks(&target, ExportFormat::Json, file.as_slice()).unwrap_err();
+        assert!(matches!(err, ImportError::NotLinked { .. }));
+    }
+
+    #[test]
+    fn test_import_rejects_large_record() {
+        let target_path = tempdir().unwrap();
+        let target = new_storage(target_path.path());
+
+        // varint length prefix of u64::MAX, without data.
+        let mut file = vec![0xff; 9];
+        file.push(0x01);
+        let err = import_blocks(&target, ExportFormat::Proto, file.as_slice()).unwrap_err();
+        assert!(matches!(err, ImportError::RecordTooLarge(u64::MAX)));
+
+        // truncated record.
+        let file = [0x10, 0x00];
+        let err = import_blocks(&target, ExportFormat::Proto, &file[..]).unwrap_err();
+        assert!(matches!(err, ImportError::Io(_)));
+    }
+}
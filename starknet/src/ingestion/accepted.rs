@@ -1,39 +1,70 @@
 //! Ingest accepted block data.
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use apibara_node::db::libmdbx::EnvironmentKind;
+use apibara_node::o11y::set_error_context;
+use tokio::sync::Notify;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
 use crate::{
     core::GlobalBlockId,
-    db::{DatabaseStorage, StorageReader, StorageWriter},
-    provider::{BlockId, Provider, ProviderError},
+    db::{BlockBody, DatabaseStorage, StorageReader, StorageWriter},
+    provider::{BlockId, ProviderError},
 };
 
 use super::{
-    config::BlockIngestionConfig, downloader::Downloader, error::BlockIngestionError,
+    config::BlockIngestionConfig,
+    control::IngestionControl,
+    downloader::Downloader,
+    error::BlockIngestionError,
+    head_subscription::HeadSubscription,
+    lag::IngestionProgress,
+    quorum::QuorumVerifier,
+    reorg::recover_from_reorg,
+    source::IngestionSource,
+    stages::{stage_metrics, IngestionStage},
     subscription::IngestionStreamPublisher,
 };
 
-pub struct AcceptedBlockIngestion<G: Provider + Send, E: EnvironmentKind> {
+pub struct AcceptedBlockIngestion<G: IngestionSource, E: EnvironmentKind> {
     config: BlockIngestionConfig,
     provider: Arc<G>,
     downloader: Downloader<G>,
+    quorum: Option<QuorumVerifier>,
     storage: DatabaseStorage<E>,
     publisher: IngestionStreamPublisher,
+    progress: IngestionProgress,
+    control: IngestionControl,
 }
 
-struct AcceptedBlockIngestionImpl<G: Provider + Send, E: EnvironmentKind> {
+struct AcceptedBlockIngestionImpl<G: IngestionSource, E: EnvironmentKind> {
     finalized: Option<GlobalBlockId>,
     previous: GlobalBlockId,
     current_head: GlobalBlockId,
     pending_ingested: bool,
+    /// When the pending block was last requested.
+    last_pending_poll: Option<Instant>,
+    /// Hash of the transactions in the pending snapshots already ingested at
+    /// the current head.
+    pending_transactions: HashSet<[u8; 32]>,
+    /// Lowest block height that may still have superseded pending data.
+    pending_cleanup_from: u64,
+    /// Signalled when the provider has a new head.
+    new_heads: Option<Arc<Notify>>,
     config: BlockIngestionConfig,
     provider: Arc<G>,
     downloader: Downloader<G>,
+    quorum: Option<QuorumVerifier>,
     storage: DatabaseStorage<E>,
     publisher: IngestionStreamPublisher,
+    progress: IngestionProgress,
+    control: IngestionControl,
 }
 
 enum TickResult {
@@ -48,7 +79,7 @@ struct IngestBlockResult {
 
 impl<G, E> AcceptedBlockIngestion<G, E>
 where
-    G: Provider + Send,
+    G: IngestionSource,
     E: EnvironmentKind,
 {
     pub fn new(
@@ -56,14 +87,28 @@ where
         storage: DatabaseStorage<E>,
         config: BlockIngestionConfig,
         publisher: IngestionStreamPublisher,
+        progress: IngestionProgress,
+        control: IngestionControl,
     ) -> Self {
-        let downloader = Downloader::new(provider.clone(), config.rpc_concurrency);
+        let downloader = Downloader::new(provider.clone(), config.rpc_concurrency)
+            .with_traces(config.ingest_traces)
+            .with_classes(config.ingest_classes)
+            .with_headers_only(config.headers_only)
+            .with_missing_data_tolerance(config.tolerate_missing_data)
+            .with_journal(config.journal.clone())
+            .with_block_hash_validation(config.block_hash_validation)
+            .with_l1_message_resolver(config.l1_message_resolver.clone())
+            .with_emitter_stats(config.emitter_stats.clone());
+        let quorum = config.quorum_provider.clone().map(QuorumVerifier::new);
         AcceptedBlockIngestion {
             config,
             provider,
             storage,
             downloader,
+            quorum,
             publisher,
+            progress,
+            control,
         }
     }
 
@@ -82,41 +127,88 @@ where
             .get_head()
             .await
             .map_err(BlockIngestionError::provider)?;
+        self.progress.provider_head(current_head.number());
+        self.progress.node_head(latest_indexed.number());
 
         let finalized = self.storage.highest_finalized_block()?;
+        if let Some(finalized) = finalized {
+            self.progress.finalized(finalized.number());
+        }
+
+        // pending data below the finalized block was removed by a previous run.
+        let pending_cleanup_from = finalized.map(|b| b.number()).unwrap_or(0);
+
+        // the subscription lives as long as accepted ingestion.
+        let subscription_ct = ct.child_token();
+        let new_heads = self.config.head_subscription.clone().map(|url| {
+            let subscription = HeadSubscription::new(url);
+            let new_heads = subscription.notify();
+            tokio::spawn(subscription.start(subscription_ct.clone()));
+            new_heads
+        });
 
         let ingestion = AcceptedBlockIngestionImpl {
             current_head,
             finalized,
             previous: latest_indexed,
             pending_ingested: false,
+            last_pending_poll: None,
+            pending_transactions: HashSet::default(),
+            pending_cleanup_from,
+            new_heads,
             config: self.config,
             provider: self.provider,
             storage: self.storage,
             downloader: self.downloader,
+            quorum: self.quorum,
             publisher: self.publisher,
+            progress: self.progress,
+            control: self.control,
         };
-        ingestion.start(ct).await
+        let result = ingestion.start(ct).await;
+        subscription_ct.cancel();
+        result
     }
 }
 
 impl<G, E> AcceptedBlockIngestionImpl<G, E>
 where
-    G: Provider + Send,
+    G: IngestionSource,
     E: EnvironmentKind,
 {
     pub async fn start(mut self, ct: CancellationToken) -> Result<(), BlockIngestionError> {
         loop {
+            self.control.wait_until_resumed(&ct).await;
+            if let Some(disk_usage) = &self.config.disk_usage {
+                disk_usage.throttle(&ct).await;
+            }
             if ct.is_cancelled() {
                 return Ok(());
             }
 
+            #[cfg(feature = "reorg-injection")]
+            self.apply_injected_events()?;
+
+            self.config
+                .journal
+                .apply_requests(&self.provider, &self.storage, &self.config)
+                .await;
+
             match self.tick().await? {
                 TickResult::MoreToSync => {}
                 TickResult::FullySynced => {
-                    // no need to do anything for now
+                    // no need to do anything until the next head, or until
+                    // it's time to poll the head again.
+                    let new_head = async {
+                        match self.new_heads {
+                            Some(ref new_heads) => new_heads.notified().await,
+                            None => future::pending().await,
+                        }
+                    };
                     tokio::select! {
                         _ = tokio::time::sleep(self.config.head_refresh_interval) => {},
+                        _ = new_head => {},
+                        _ = self.config.journal.requested() => {},
                         _ = ct.cancelled() => {},
                     }
                 }
@@ -161,6 +253,7 @@ where
             .await
             .map_err(BlockIngestionError::provider)?;
 
+        self.progress.provider_head(new_head.number());
         let is_synced = new_head == self.current_head;
         debug!(
             new_head = ?new_head,
@@ -168,14 +261,18 @@ where
             "check head"
         );
 
-        // synced and pending block ingested. nothing to do until next block.
-        if is_synced && self.pending_ingested {
+        // synced and pending ingestion disabled.
+        // nothing to do until next block.
+        if is_synced && !self.config.ingest_pending {
             return Ok(TickResult::FullySynced);
         }
 
-        // synced but no pending block yet. try to ingest pending.
+        // synced. poll pending block for new transactions.
         if is_synced {
-            self.ingest_pending().await?;
+            if self.should_poll_pending() {
+                self.last_pending_poll = Some(Instant::now());
+                self.ingest_pending().await?;
+            }
             return Ok(TickResult::FullySynced);
         }
 
@@ -184,10 +281,20 @@ where
         self.advance_finalized().await?;
 
         self.pending_ingested = false;
+        self.pending_transactions.clear();
         self.current_head = new_head;
         Ok(TickResult::MoreToSync)
     }
 
+    /// Returns true if enough time passed since the pending block was last
+    /// requested.
+    fn should_poll_pending(&self) -> bool {
+        match self.last_pending_poll {
+            None => true,
+            Some(last) => last.elapsed() >= self.config.pending_poll_interval,
+        }
+    }
+
     #[tracing::instrument(skip(self))]
     async fn update_accepted(&mut self) -> Result<TickResult, BlockIngestionError> {
         // if either type 1 or type 2 chain reorganization happened, simply
@@ -209,18 +316,18 @@ where
         // if that's not the case, then a reorg happened and we need to recover
         // from that.
         let ingest_result = self
-            .ingest_block_by_number(self.previous.number() + 1)
-            .await?;
+            .ingest_block_by_number(self.previous)
+            .await
+            .map_err(|err| err.at_block(self.previous.number() + 1))?;
 
         if ingest_result.parent_id == self.previous {
-            // update canonical chain and notify subscribers
-            let mut txn = self.storage.begin_txn()?;
-            txn.extend_canonical_chain(&ingest_result.new_block_id)?;
-            txn.commit()?;
-
+            // canonical chain already updated, notify subscribers
             self.publisher
                 .publish_accepted(ingest_result.new_block_id)?;
+            self.progress
+                .block_ingested(ingest_result.new_block_id.number());
             self.previous = ingest_result.new_block_id;
+            self.remove_superseded_pending()?;
             Ok(TickResult::MoreToSync)
         } else {
             // type 2 reorg
@@ -243,6 +350,7 @@ where
         }
 
         if let Some(finalized) = self.finalized {
+            self.progress.finalized(finalized.number());
             self.publisher.publish_finalized(finalized)?;
         }
 
@@ -260,7 +368,7 @@ where
                 // an error if the pending block is not prepared yet.
                 Ok(())
             }
-            Ok((status, mut header, body)) => {
+            Ok((status, mut header, mut body)) => {
                 // pending block is not what was expected. do nothing.
                 let is_next_pending_block = if let Some(hash) = header.parent_block_hash.as_ref() {
                     *self.current_head.hash() == hash.into()
@@ -272,6 +380,13 @@ where
                     return Ok(());
                 }
 
+                // the pending block didn't change since the previous poll.
+                let new_transactions =
+                    mark_new_pending_transactions(&self.pending_transactions, &mut body);
+                if self.pending_ingested && new_transactions.is_empty() {
+                    return Ok(());
+                }
+
                 // block number is not set, so do it here.
                 header.block_number = self.current_head.number() + 1;
 
@@ -280,9 +395,13 @@ where
                 let mut txn = self.storage.begin_txn()?;
                 self.downloader
                     .finish_ingesting_block(&new_block_id, status, header, body, &mut txn)
-                    .await?;
-                txn.commit()?;
+                    .await
+                    .map_err(|err| err.at_block(new_block_id.number()))?;
+                stage_metrics().record(IngestionStage::Commit, || txn.commit())?;
 
+                // only seen once stored, so that they're marked again if
+                // storing the snapshot fails.
+                self.pending_transactions.extend(new_transactions);
                 self.pending_ingested = true;
                 self.publisher.publish_pending(new_block_id)?;
 
@@ -291,6 +410,35 @@ where
         }
     }
 
+    /// Removes pending data superseded by accepted blocks, keeping the most
+    /// recent `pending_retention` blocks.
+    #[tracing::instrument(skip(self))]
+    fn remove_superseded_pending(&mut self) -> Result<(), BlockIngestionError> {
+        // pending data at the height of `previous` is superseded too.
+        let cleanup_to = (self.previous.number() + 1).saturating_sub(self.config.pending_retention);
+        if cleanup_to <= self.pending_cleanup_from {
+            return Ok(());
+        }
+
+        let mut removed = 0;
+        let mut txn = self.storage.begin_txn()?;
+        for number in self.pending_cleanup_from..cleanup_to {
+            if txn.remove_pending_block(number)? {
+                removed += 1;
+            }
+        }
+        txn.commit()?;
+
+        debug!(
+            from = %self.pending_cleanup_from,
+            to = %cleanup_to,
+            removed = %removed,
+            "removed superseded pending data"
+        );
+        self.pending_cleanup_from = cleanup_to;
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     async fn refresh_finalized_block_status(
         &self,
@@ -317,21 +465,31 @@ where
         Ok(Some(global_id))
     }
 
+    /// Ingest the block following `previous`.
+    ///
+    /// If the block extends the chain ending at `previous`, the block is
+    /// added to the canonical chain in the same transaction that writes its
+    /// data.
     #[tracing::instrument(skip(self), err(Debug))]
     async fn ingest_block_by_number(
         &self,
-        number: u64,
+        previous: GlobalBlockId,
     ) -> Result<IngestBlockResult, BlockIngestionError> {
-        debug!(
-            block_number = %number,
-            "ingest block by number"
-        );
+        let number = previous.number() + 1;
+        debug!(block_number = number, "ingest block by number");
+        set_error_context("block_number", number);
         let block_id = BlockId::Number(number);
         let (status, header, body) = {
             loop {
                 // if the node is not fully synced it will fail to fetch the block
                 // in that case, simply wait a bit and retry.
-                match self.provider.get_block(&block_id).await {
+                let block = stage_metrics()
+                    .time(
+                        IngestionStage::FetchBlock,
+                        self.provider.get_block(&block_id),
+                    )
+                    .await;
+                match block {
                     Ok(result) => break result,
                     Err(err) if err.is_block_not_found() => {
                         warn!("node is not fully synced");
@@ -351,13 +509,24 @@ where
             .ok_or(BlockIngestionError::MissingBlockHash)?
             .into();
         let parent_id = GlobalBlockId::new(header.block_number - 1, parent_hash);
+        let extends_chain = parent_id == previous;
+
+        // blocks are accepted only if the quorum provider agrees.
+        if extends_chain {
+            if let Some(quorum) = self.quorum.as_ref() {
+                quorum.verify(&new_block_id).await?;
+            }
+        }
 
         // write block data to storage
         let mut txn = self.storage.begin_txn()?;
         self.downloader
             .finish_ingesting_block(&new_block_id, status, header, body, &mut txn)
             .await?;
-        txn.commit()?;
+        if extends_chain {
+            txn.extend_canonical_chain(&new_block_id)?;
+        }
+        stage_metrics().record(IngestionStage::Commit, || txn.commit())?;
 
         Ok(IngestBlockResult {
             new_block_id,
@@ -366,6 +535,9 @@ where
     }
 
     /// Shrink the old canonical chain until it joins with the new canonical chain.
+    ///
+    /// The old chain is walked back through the stored headers, so reorgs
+    /// of any depth above the finalized block are recovered.
     #[tracing::instrument(skip(self))]
     async fn shrink_diverging_chain(&mut self) -> Result<TickResult, BlockIngestionError> {
         info!(
@@ -374,61 +546,136 @@ where
             "shrinking canonical chain"
         );
 
-        let mut txn = self.storage.begin_txn()?;
-        let mut ingested_tip = self.previous;
+        let ingested_tip = recover_from_reorg(
+            &*self.provider,
+            &self.storage,
+            &self.publisher,
+            self.previous,
+        )
+        .await?;
 
-        loop {
-            let belongs_to_new_canonical_chain =
-                if ingested_tip.number() <= self.current_head.number() {
-                    // check status of the
-                    let block_id = BlockId::Hash(*ingested_tip.hash());
-                    let (status, _header, _body) = self
-                        .provider
-                        .get_block(&block_id)
-                        .await
-                        .map_err(BlockIngestionError::provider)?;
-                    !status.is_rejected()
-                } else {
-                    // outside of the new chain range, it doesn't belong.
-                    false
-                };
+        // `ingested_tip` is the new chain root, that is the highest common block
+        // between the old canonical chain and the new canonical chain.
+        // restart ingestion from the new canonical chain head
+        self.previous = ingested_tip;
+        self.progress.node_head(ingested_tip.number());
+        // the new chain may be shorter, pending data above the new root
+        // must be removed again once superseded.
+        self.pending_cleanup_from = u64::min(self.pending_cleanup_from, ingested_tip.number() + 1);
 
-            debug!(
-                tip = %ingested_tip,
-                belongs = %belongs_to_new_canonical_chain,
-                "check if tip belongs to chain"
-            );
+        Ok(TickResult::MoreToSync)
+    }
 
-            // finished shrinking old canonical chain
-            if belongs_to_new_canonical_chain {
-                break;
+    /// Applies the events injected through the ingestion control.
+    #[cfg(feature = "reorg-injection")]
+    fn apply_injected_events(&mut self) -> Result<(), BlockIngestionError> {
+        use super::inject::{inject_finality, inject_reorg, InjectedEvent};
+
+        for event in self.control.take_injected() {
+            match event {
+                InjectedEvent::Reorg { depth } => {
+                    let ingested_tip = inject_reorg(
+                        &self.storage,
+                        &self.publisher,
+                        self.previous,
+                        self.finalized,
+                        depth,
+                    )?;
+                    // same as a real reorg, see `shrink_diverging_chain`.
+                    self.previous = ingested_tip;
+                    self.progress.node_head(ingested_tip.number());
+                    self.pending_ingested = false;
+                    self.pending_transactions.clear();
+                    self.pending_cleanup_from =
+                        u64::min(self.pending_cleanup_from, ingested_tip.number() + 1);
+                }
+                InjectedEvent::Finalize { block_number } => {
+                    self.finalized = inject_finality(
+                        &self.storage,
+                        &self.publisher,
+                        self.previous,
+                        self.finalized,
+                        block_number,
+                    )?;
+                }
             }
+        }
 
-            txn.reject_block_from_canonical_chain(&ingested_tip)?;
-
-            // header must exist in the database
-            let header = self
-                .storage
-                .read_header(&ingested_tip)?
-                .ok_or(BlockIngestionError::InconsistentDatabase)?;
+        Ok(())
+    }
+}
 
-            let parent_hash = header
-                .parent_block_hash
-                .as_ref()
-                .ok_or(BlockIngestionError::MissingBlockHash)?
-                .into();
+/// Marks the transactions in the pending `body` that are not in `seen`, the
+/// transactions of the previous snapshots.
+///
+/// Returns the hashes of the new transactions.
+fn mark_new_pending_transactions(
+    seen: &HashSet<[u8; 32]>,
+    body: &mut BlockBody,
+) -> HashSet<[u8; 32]> {
+    let mut new_transactions = HashSet::default();
+    body.new_pending_transactions = body
+        .transactions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, tx)| {
+            let hash = tx.meta.as_ref()?.hash.as_ref()?.to_bytes();
+            let is_new = !seen.contains(&hash) && new_transactions.insert(hash);
+            is_new.then_some(index as u32)
+        })
+        .collect();
+    new_transactions
+}
 
-            ingested_tip = GlobalBlockId::new(header.block_number - 1, parent_hash);
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use apibara_core::starknet::v1alpha2;
+
+    use super::mark_new_pending_transactions;
+    use crate::db::BlockBody;
+
+    fn body(hashes: &[u64]) -> BlockBody {
+        let transactions = hashes
+            .iter()
+            .map(|hash| v1alpha2::Transaction {
+                meta: Some(v1alpha2::TransactionMeta {
+                    hash: Some(v1alpha2::FieldElement::from_u64(*hash)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .collect();
+        BlockBody {
+            transactions,
+            ..BlockBody::default()
         }
+    }
 
-        txn.commit()?;
-
-        // `ingested_tip` is the new chain root, that is the highest common block
-        // between the old canonical chain and the new canonical chain.
-        // restart ingestion from the new canonical chain head
-        self.previous = ingested_tip;
-        self.publisher.publish_invalidate(ingested_tip)?;
-
-        Ok(TickResult::MoreToSync)
+    #[test]
+    fn test_mark_new_pending_transactions() {
+        let mut seen = HashSet::default();
+
+        let mut first = body(&[1, 2]);
+        let new_transactions = mark_new_pending_transactions(&seen, &mut first);
+        assert_eq!(new_transactions.len(), 2);
+        assert_eq!(first.new_pending_transactions, vec![0, 1]);
+
+        // nothing is seen until the snapshot is stored.
+        let mut retried = body(&[1, 2]);
+        mark_new_pending_transactions(&seen, &mut retried);
+        assert_eq!(retried.new_pending_transactions, vec![0, 1]);
+        seen.extend(new_transactions);
+
+        let mut unchanged = body(&[1, 2]);
+        assert!(mark_new_pending_transactions(&seen, &mut unchanged).is_empty());
+        assert!(unchanged.new_pending_transactions.is_empty());
+
+        // the sequencer can reorder pending transactions.
+        let mut second = body(&[2, 3, 1, 4]);
+        seen.extend(mark_new_pending_transactions(&seen, &mut second));
+        assert_eq!(second.new_pending_transactions, vec![1, 3]);
+        assert_eq!(seen.len(), 4);
     }
 }
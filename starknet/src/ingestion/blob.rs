@@ -0,0 +1,210 @@
+//! Decode state diffs published in EIP-4844 blobs.
+//!
+//! Starknet publishes the state diff as the coefficients of a polynomial
+//! over the BLS12-381 scalar field. Blobs contain the evaluations of the
+//! polynomial at the roots of unity, in bit-reversed order, so decoding a
+//! blob is an inverse FFT.
+use apibara_core::starknet::v1alpha2;
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+/// Number of field elements in a blob.
+pub const FIELD_ELEMENTS_PER_BLOB: usize = 4096;
+
+/// Size of a blob, in bytes.
+pub const BLOB_SIZE: usize = FIELD_ELEMENTS_PER_BLOB * 32;
+
+/// Version byte of versioned hashes of KZG commitments.
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Modulus of the BLS12-381 scalar field.
+const BLS_MODULUS: &str =
+    "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+
+/// Primitive 4096th root of unity of the BLS12-381 scalar field.
+const ROOT_OF_UNITY: &str =
+    "39033254847818212395286706435128746857159659164139250548781411570340225835782";
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlobError {
+    #[error("blob has size {0}, expected {BLOB_SIZE}")]
+    InvalidSize(usize),
+    #[error("blob contains a value outside of the field")]
+    InvalidFieldElement,
+}
+
+struct Domain {
+    modulus: BigUint,
+    inv_two: BigUint,
+    // inverse of the evaluation points, in the same order as the blob.
+    inv_roots: Vec<BigUint>,
+}
+
+lazy_static! {
+    static ref DOMAIN: Domain = Domain::new();
+}
+
+impl Domain {
+    fn new() -> Self {
+        let modulus = parse_decimal(BLS_MODULUS);
+        let two = BigUint::from(2u32);
+        let inverse = |value: &BigUint| value.modpow(&(&modulus - &two), &modulus);
+
+        let inv_two = inverse(&two);
+        let inv_root = inverse(&parse_decimal(ROOT_OF_UNITY));
+        let mut powers = Vec::with_capacity(FIELD_ELEMENTS_PER_BLOB);
+        let mut power = BigUint::from(1u32);
+        for _ in 0..FIELD_ELEMENTS_PER_BLOB {
+            powers.push(power.clone());
+            power = power * &inv_root % &modulus;
+        }
+        let inv_roots = (0..FIELD_ELEMENTS_PER_BLOB)
+            .map(|i| powers[bit_reverse(i)].clone())
+            .collect();
+
+        Domain {
+            modulus,
+            inv_two,
+            inv_roots,
+        }
+    }
+}
+
+/// Returns the versioned hash of a KZG commitment, as included in blob
+/// transactions.
+pub fn versioned_hash(commitment: &[u8]) -> v1alpha2::FieldElement {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    v1alpha2::FieldElement::from_bytes(&hash)
+}
+
+/// Decodes the field elements published in the blob.
+///
+/// Trailing zero elements are padding and are removed.
+pub fn decode_blob(blob: &[u8]) -> Result<Vec<v1alpha2::FieldElement>, BlobError> {
+    if blob.len() != BLOB_SIZE {
+        return Err(BlobError::InvalidSize(blob.len()));
+    }
+
+    let domain = &*DOMAIN;
+    let evaluations = blob
+        .chunks_exact(32)
+        .map(|chunk| {
+            let value = BigUint::from_bytes_be(chunk);
+            if value >= domain.modulus {
+                return Err(BlobError::InvalidFieldElement);
+            }
+            Ok(value)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut coefficients = ifft(&evaluations, &domain.inv_roots, domain);
+    let zero = BigUint::default();
+    while coefficients.last() == Some(&zero) {
+        coefficients.pop();
+    }
+
+    let elements = coefficients
+        .into_iter()
+        .map(|value| {
+            let bytes = value.to_bytes_be();
+            let mut out = [0u8; 32];
+            out[32 - bytes.len()..].copy_from_slice(&bytes);
+            v1alpha2::FieldElement::from_bytes(&out)
+        })
+        .collect();
+    Ok(elements)
+}
+
+/// Returns the coefficients of the polynomial with the given evaluations.
+fn ifft(values: &[BigUint], inv_roots: &[BigUint], domain: &Domain) -> Vec<BigUint> {
+    if values.len() == 1 {
+        return values.to_vec();
+    }
+
+    let modulus = &domain.modulus;
+    let half = values.len() / 2;
+    let mut even = Vec::with_capacity(half);
+    let mut odd = Vec::with_capacity(half);
+    let mut next_inv_roots = Vec::with_capacity(half);
+    for i in 0..half {
+        let a = &values[2 * i];
+        let b = &values[2 * i + 1];
+        let inv_root = &inv_roots[2 * i];
+        even.push((a + b) * &domain.inv_two % modulus);
+        odd.push((a + modulus - b) * &domain.inv_two % modulus * inv_root % modulus);
+        next_inv_roots.push(inv_root * inv_root % modulus);
+    }
+
+    let even = ifft(&even, &next_inv_roots, domain);
+    let odd = ifft(&odd, &next_inv_roots, domain);
+    even.into_iter()
+        .zip(odd)
+        .flat_map(|(even, odd)| [even, odd])
+        .collect()
+}
+
+fn bit_reverse(index: usize) -> usize {
+    let bits = FIELD_ELEMENTS_PER_BLOB.trailing_zeros();
+    index.reverse_bits() >> (usize::BITS - bits)
+}
+
+fn parse_decimal(value: &str) -> BigUint {
+    BigUint::parse_bytes(value.as_bytes(), 10).expect("valid decimal constant")
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+    use num_bigint::BigUint;
+
+    use super::{
+        bit_reverse, decode_blob, parse_decimal, versioned_hash, BLS_MODULUS,
+        FIELD_ELEMENTS_PER_BLOB, ROOT_OF_UNITY,
+    };
+
+    fn encode(coefficients: &[u64]) -> Vec<u8> {
+        let modulus = parse_decimal(BLS_MODULUS);
+        let root = parse_decimal(ROOT_OF_UNITY);
+        let mut blob = Vec::default();
+        for i in 0..FIELD_ELEMENTS_PER_BLOB {
+            let x = root.modpow(&BigUint::from(bit_reverse(i)), &modulus);
+            let mut value = BigUint::default();
+            for coefficient in coefficients.iter().rev() {
+                value = (value * &x + BigUint::from(*coefficient)) % &modulus;
+            }
+            let bytes = value.to_bytes_be();
+            let mut out = [0u8; 32];
+            out[32 - bytes.len()..].copy_from_slice(&bytes);
+            blob.extend_from_slice(&out);
+        }
+        blob
+    }
+
+    #[test]
+    fn test_decode_blob() {
+        let decoded = decode_blob(&encode(&[7])).unwrap();
+        assert_eq!(decoded, vec![v1alpha2::FieldElement::from_u64(7)]);
+
+        let decoded = decode_blob(&encode(&[1, 2, 0, 3])).unwrap();
+        let expected: Vec<_> = [1, 2, 0, 3]
+            .into_iter()
+            .map(v1alpha2::FieldElement::from_u64)
+            .collect();
+        assert_eq!(decoded, expected);
+
+        assert!(decode_blob(&[0; 32]).is_err());
+    }
+
+    #[test]
+    fn test_versioned_hash() {
+        // commitment of the empty blob.
+        let mut commitment = [0u8; 48];
+        commitment[0] = 0xc0;
+        assert_eq!(
+            versioned_hash(&commitment).to_hex(),
+            "0x010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Validate block hashes reported by the provider.
+//!
+//! The block hash is recomputed from the header, the transactions and the
+//! events of the block, following the formula used since Starknet v0.7.
+//! Blocks produced by earlier versions use a different formula and never
+//! match.
+use apibara_core::starknet::v1alpha2;
+use clap::ValueEnum;
+use serde::Serialize;
+use starknet::core::{
+    crypto::{compute_hash_on_elements, pedersen_hash},
+    types::{FieldElement, FromByteArrayError},
+};
+use tracing::warn;
+
+use crate::{core::GlobalBlockId, db::BlockBody};
+
+use super::BlockIngestionError;
+
+/// Height of the transaction and event commitment trees.
+const COMMITMENT_TREE_HEIGHT: usize = 64;
+
+/// What to do with blocks whose hash doesn't match the computed hash.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockHashValidation {
+    /// Don't compute block hashes.
+    #[default]
+    Disabled,
+    /// Log mismatching blocks, but ingest them.
+    Warn,
+    /// Reject mismatching blocks.
+    Strict,
+}
+
+impl BlockHashValidation {
+    /// Compares the block hash with the hash computed from its content.
+    pub fn validate(
+        &self,
+        global_id: &GlobalBlockId,
+        header: &v1alpha2::BlockHeader,
+        body: &BlockBody,
+        receipts: &[v1alpha2::TransactionReceipt],
+    ) -> Result<(), BlockIngestionError> {
+        // pending blocks don't have a hash.
+        if *self == BlockHashValidation::Disabled || global_id.hash().is_zero() {
+            return Ok(());
+        }
+
+        let computed = compute_block_hash(header, body, receipts)
+            .map_err(|_| BlockIngestionError::MalformedTransaction)?;
+        let expected = header
+            .block_hash
+            .as_ref()
+            .ok_or(BlockIngestionError::MissingBlockHash)?;
+        if computed == *expected {
+            return Ok(());
+        }
+
+        if *self == BlockHashValidation::Strict {
+            return Err(BlockIngestionError::BlockHashMismatch {
+                block_id: *global_id,
+                computed,
+            });
+        }
+
+        warn!(
+            block_id = %global_id,
+            computed = %computed,
+            "block hash doesn't match block content"
+        );
+        Ok(())
+    }
+}
+
+/// Computes the hash of the block from its content.
+///
+/// Receipts can be in any order.
+pub fn compute_block_hash(
+    header: &v1alpha2::BlockHeader,
+    body: &BlockBody,
+    receipts: &[v1alpha2::TransactionReceipt],
+) -> Result<v1alpha2::FieldElement, FromByteArrayError> {
+    let mut receipts = receipts.iter().collect::<Vec<_>>();
+    receipts.sort_by_key(|receipt| receipt.transaction_index);
+
+    let transaction_leaves = body
+        .transactions
+        .iter()
+        .map(transaction_leaf)
+        .collect::<Result<Vec<_>, _>>()?;
+    let event_leaves = receipts
+        .iter()
+        .flat_map(|receipt| receipt.events.iter())
+        .map(event_hash)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let timestamp = header
+        .timestamp
+        .as_ref()
+        .map(|ts| ts.seconds as u64)
+        .unwrap_or_default();
+
+    let hash = compute_hash_on_elements(&[
+        FieldElement::from(header.block_number),
+        felt(&header.new_root)?,
+        felt(&header.sequencer_address)?,
+        FieldElement::from(timestamp),
+        FieldElement::from(transaction_leaves.len() as u64),
+        commitment(&transaction_leaves),
+        FieldElement::from(event_leaves.len() as u64),
+        commitment(&event_leaves),
+        FieldElement::ZERO,
+        FieldElement::ZERO,
+        felt(&header.parent_block_hash)?,
+    ]);
+
+    Ok(hash.into())
+}
+
+/// Leaf of the transaction commitment tree.
+///
+/// Only invoke transactions include their signature.
+fn transaction_leaf(tx: &v1alpha2::Transaction) -> Result<FieldElement, FromByteArrayError> {
+    use v1alpha2::transaction::Transaction;
+
+    let meta = tx.meta.clone().unwrap_or_default();
+    let signature = match tx.transaction {
+        Some(Transaction::InvokeV0(_)) | Some(Transaction::InvokeV1(_)) => meta
+            .signature
+            .iter()
+            .map(FieldElement::try_from)
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => Vec::default(),
+    };
+    let hash = felt(&meta.hash)?;
+    Ok(pedersen_hash(&hash, &compute_hash_on_elements(&signature)))
+}
+
+/// Leaf of the event commitment tree.
+fn event_hash(event: &v1alpha2::Event) -> Result<FieldElement, FromByteArrayError> {
+    let keys = event
+        .keys
+        .iter()
+        .map(FieldElement::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = event
+        .data
+        .iter()
+        .map(FieldElement::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(compute_hash_on_elements(&[
+        felt(&event.from_address)?,
+        compute_hash_on_elements(&keys),
+        compute_hash_on_elements(&data),
+    ]))
+}
+
+fn felt(value: &Option<v1alpha2::FieldElement>) -> Result<FieldElement, FromByteArrayError> {
+    match value {
+        None => Ok(FieldElement::ZERO),
+        Some(value) => value.try_into(),
+    }
+}
+
+/// Root of the Patricia-Merkle tree with the leaves at their index.
+fn commitment(leaves: &[FieldElement]) -> FieldElement {
+    let leaves = leaves
+        .iter()
+        .enumerate()
+        .map(|(index, leaf)| (index as u64, *leaf))
+        .collect::<Vec<_>>();
+    if leaves.is_empty() {
+        return FieldElement::ZERO;
+    }
+    subtree(&leaves, COMMITMENT_TREE_HEIGHT).hash()
+}
+
+/// A node of the tree, possibly compressed into an edge.
+struct Node {
+    bottom: FieldElement,
+    path: u64,
+    length: usize,
+}
+
+impl Node {
+    fn hash(&self) -> FieldElement {
+        if self.length == 0 {
+            return self.bottom;
+        }
+        pedersen_hash(&self.bottom, &FieldElement::from(self.path))
+            + FieldElement::from(self.length as u64)
+    }
+}
+
+/// Computes the subtree of the given height containing the sorted leaves.
+fn subtree(leaves: &[(u64, FieldElement)], height: usize) -> Node {
+    if height == 0 {
+        return Node {
+            bottom: leaves[0].1,
+            path: 0,
+            length: 0,
+        };
+    }
+
+    let bit = 1u64 << (height - 1);
+    let split = leaves.partition_point(|(index, _)| index & bit == 0);
+    let (left, right) = leaves.split_at(split);
+
+    if !left.is_empty() && !right.is_empty() {
+        let left = subtree(left, height - 1).hash();
+        let right = subtree(right, height - 1).hash();
+        return Node {
+            bottom: pedersen_hash(&left, &right),
+            path: 0,
+            length: 0,
+        };
+    }
+
+    let (child, direction) = if left.is_empty() {
+        (subtree(right, height - 1), 1)
+    } else {
+        (subtree(left, height - 1), 0)
+    };
+    Node {
+        bottom: child.bottom,
+        path: (direction << child.length) | child.path,
+        length: child.length + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet::core::{crypto::pedersen_hash, types::FieldElement};
+
+    use super::commitment;
+
+    #[test]
+    fn test_commitment() {
+        assert_eq!(commitment(&[]), FieldElement::ZERO);
+
+        let first = FieldElement::from(10u64);
+        let second = FieldElement::from(20u64);
+
+        // a single leaf at index 0 is an edge from the root.
+        let expected = pedersen_hash(&first, &FieldElement::ZERO) + FieldElement::from(64u64);
+        assert_eq!(commitment(&[first]), expected);
+
+        // leaves at index 0 and 1 share all bits but the last one.
+        let bottom = pedersen_hash(&first, &second);
+        let expected = pedersen_hash(&bottom, &FieldElement::ZERO) + FieldElement::from(63u64);
+        assert_eq!(commitment(&[first, second]), expected);
+    }
+}
@@ -0,0 +1,211 @@
+//! Bootstrap finalized blocks from another DNA node.
+//!
+//! Bootstrapping downloads the finalized blocks from the block sync service
+//! of another node, then ingestion continues from the StarkNet RPC as
+//! usual. Bootstrapping runs again every time ingestion restarts, so the
+//! RPC is only used for the blocks the other node didn't finalize yet.
+use apibara_core::starknet::v1alpha2::{
+    block_sync_client::BlockSyncClient, GetSyncStatusRequest, RawBlock, StreamBlocksRequest,
+};
+use apibara_node::db::libmdbx::EnvironmentKind;
+use tokio_util::sync::CancellationToken;
+use tonic::transport::Channel;
+use tracing::{info, warn};
+
+use crate::{
+    core::{BlockHash, GlobalBlockId},
+    db::{write_raw_block, BlockBody, DatabaseStorage, StorageReader, StorageWriter},
+};
+
+use super::{error::BlockIngestionError, BlockHashValidation};
+
+pub struct BootstrapBlockIngestion<E: EnvironmentKind> {
+    client: BlockSyncClient<Channel>,
+    storage: DatabaseStorage<E>,
+    headers_only: bool,
+    starting_block: u64,
+    block_hash_validation: BlockHashValidation,
+}
+
+impl<E> BootstrapBlockIngestion<E>
+where
+    E: EnvironmentKind,
+{
+    /// Connects to the node at the given url.
+    pub async fn connect(
+        url: String,
+        storage: DatabaseStorage<E>,
+    ) -> Result<Self, BlockIngestionError> {
+        let client = BlockSyncClient::connect(url)
+            .await
+            .map_err(BlockIngestionError::provider)?;
+        Ok(BootstrapBlockIngestion {
+            client,
+            storage,
+            headers_only: false,
+            starting_block: 0,
+            block_hash_validation: BlockHashValidation::default(),
+        })
+    }
+
+    /// Only store the status and header of bootstrapped blocks.
+    pub fn with_headers_only(mut self, headers_only: bool) -> Self {
+        self.headers_only = headers_only;
+        self
+    }
+
+    /// Validate the hash of bootstrapped blocks.
+    pub fn with_block_hash_validation(mut self, validation: BlockHashValidation) -> Self {
+        self.block_hash_validation = validation;
+        self
+    }
+
+    /// Don't bootstrap blocks before the given block, if the local node has
+    /// no blocks yet.
+    pub fn with_starting_block(mut self, starting_block: u64) -> Self {
+        self.starting_block = starting_block;
+        self
+    }
+
+    /// Ingest all blocks finalized on the remote node.
+    ///
+    /// Blocks are finalized on the remote node while bootstrapping, so the
+    /// node keeps pulling blocks until it catches up with the remote
+    /// finalized block.
+    ///
+    /// Returns the last ingested block, if any.
+    pub async fn start(
+        mut self,
+        ct: CancellationToken,
+    ) -> Result<Option<GlobalBlockId>, BlockIngestionError> {
+        let mut previous = self.storage.highest_accepted_block()?;
+
+        loop {
+            let status = self
+                .client
+                .get_sync_status(GetSyncStatusRequest::default())
+                .await
+                .map_err(BlockIngestionError::provider)?
+                .into_inner();
+
+            let remote_finalized = match status.finalized {
+                None => {
+                    info!("bootstrap node has no finalized block");
+                    return Ok(previous);
+                }
+                Some(header) => GlobalBlockId::from_block_header(&header)?,
+            };
+
+            // blocks before the remote earliest block are not available.
+            let remote_earliest = match status.earliest {
+                None => 0,
+                Some(header) => header.block_number,
+            };
+
+            let start = previous
+                .map(|id| id.number() + 1)
+                .unwrap_or(u64::max(remote_earliest, self.starting_block));
+            if start > remote_finalized.number() {
+                return Ok(previous);
+            }
+
+            info!(
+                local = ?previous,
+                remote = %remote_finalized,
+                "bootstrap finalized blocks"
+            );
+
+            // the remote node limits the number of blocks sent in a single
+            // response, the status is refreshed before asking for more.
+            let request = StreamBlocksRequest {
+                start_block_number: start,
+                end_block_number: Some(remote_finalized.number() + 1),
+            };
+            let mut blocks = self
+                .client
+                .stream_blocks(request)
+                .await
+                .map_err(BlockIngestionError::provider)?
+                .into_inner();
+
+            let mut ingested_any = false;
+            while let Some(block) = blocks
+                .message()
+                .await
+                .map_err(BlockIngestionError::provider)?
+            {
+                if ct.is_cancelled() {
+                    return Ok(previous);
+                }
+
+                match self.write_block(previous, block)? {
+                    None => {
+                        warn!(previous = ?previous, "bootstrap block doesn't extend local chain");
+                        return Ok(previous);
+                    }
+                    Some(block_id) => {
+                        previous = Some(block_id);
+                        ingested_any = true;
+                    }
+                }
+            }
+
+            if !ingested_any {
+                return Ok(previous);
+            }
+
+            info!(block_id = ?previous, "bootstrap progress");
+        }
+    }
+
+    /// Writes the block to storage and adds it to the canonical chain.
+    ///
+    /// Returns `None` if the block's parent is not `previous`.
+    fn write_block(
+        &self,
+        previous: Option<GlobalBlockId>,
+        mut block: RawBlock,
+    ) -> Result<Option<GlobalBlockId>, BlockIngestionError> {
+        let header = block
+            .header
+            .as_ref()
+            .ok_or(BlockIngestionError::MissingBlockHeader)?;
+        let block_id = GlobalBlockId::from_block_header(header)?;
+
+        if let Some(previous) = previous {
+            let parent_hash: BlockHash = header
+                .parent_block_hash
+                .as_ref()
+                .ok_or(BlockIngestionError::MissingBlockHash)?
+                .into();
+            if block_id.number() != previous.number() + 1 || parent_hash != *previous.hash() {
+                return Ok(None);
+            }
+        }
+
+        // the remote node is not trusted more than the RPC.
+        let body = BlockBody {
+            transactions: std::mem::take(&mut block.transactions),
+            ..BlockBody::default()
+        };
+        self.block_hash_validation
+            .validate(&block_id, header, &body, &block.receipts)?;
+        block.transactions = body.transactions;
+
+        let block = if self.headers_only {
+            RawBlock {
+                status: block.status,
+                header: block.header,
+                ..RawBlock::default()
+            }
+        } else {
+            block
+        };
+
+        let mut txn = self.storage.begin_txn()?;
+        write_raw_block(&mut txn, &block_id, block)?;
+        txn.commit()?;
+
+        Ok(Some(block_id))
+    }
+}
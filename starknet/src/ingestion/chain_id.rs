@@ -0,0 +1,108 @@
+//! Check that the provider serves the network the database belongs to.
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::libmdbx::EnvironmentKind;
+use tracing::info;
+
+use crate::db::DatabaseStorage;
+
+use super::{source::IngestionSource, BlockIngestionError};
+
+/// Returns the chain id of the given network (`mainnet`, `goerli` or
+/// `goerli2`), or parses the chain id.
+///
+/// Chain ids are either hex field elements, or short strings such as
+/// `KATANA` used by appchains and devnets.
+pub fn chain_id_from_network(network: &str) -> Option<v1alpha2::FieldElement> {
+    let name = match network {
+        "mainnet" => "SN_MAIN",
+        "goerli" => "SN_GOERLI",
+        "goerli2" => "SN_GOERLI2",
+        chain_id if chain_id.starts_with("0x") => {
+            return v1alpha2::FieldElement::from_hex(chain_id).ok()
+        }
+        chain_id => {
+            let is_short_string = !chain_id.is_empty()
+                && chain_id.len() < 32
+                && chain_id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !is_short_string {
+                return None;
+            }
+            chain_id
+        }
+    };
+    let mut bytes = [0u8; 32];
+    bytes[32 - name.len()..].copy_from_slice(name.as_bytes());
+    Some(v1alpha2::FieldElement::from_bytes(&bytes))
+}
+
+/// Checks that the provider chain id matches the chain id stored in the
+/// database and the `expected` chain id.
+///
+/// The chain id is stored the first time the check succeeds, so that a
+/// database is never used with a different network.
+pub async fn verify_chain_id<G, E>(
+    provider: &G,
+    storage: &DatabaseStorage<E>,
+    expected: Option<&v1alpha2::FieldElement>,
+) -> Result<v1alpha2::FieldElement, BlockIngestionError>
+where
+    G: IngestionSource,
+    E: EnvironmentKind,
+{
+    let chain_id = provider
+        .get_chain_id()
+        .await
+        .map_err(BlockIngestionError::provider)?;
+
+    if let Some(expected) = expected {
+        if *expected != chain_id {
+            return Err(BlockIngestionError::ChainIdMismatch {
+                expected: expected.clone(),
+                found: chain_id,
+            });
+        }
+    }
+
+    match storage.chain_id()? {
+        Some(stored) if stored != chain_id => {
+            return Err(BlockIngestionError::ChainIdMismatch {
+                expected: stored,
+                found: chain_id,
+            });
+        }
+        Some(_) => {}
+        None => {
+            info!(chain_id = %chain_id, "storing chain id");
+            storage.write_chain_id(&chain_id)?;
+        }
+    }
+
+    Ok(chain_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+
+    use super::chain_id_from_network;
+
+    #[test]
+    fn test_chain_id_from_network() {
+        let mainnet = chain_id_from_network("mainnet").unwrap();
+        assert_eq!(
+            mainnet,
+            v1alpha2::FieldElement::from_hex("0x534e5f4d41494e").unwrap()
+        );
+        let custom = chain_id_from_network("0x534e5f4d41494e").unwrap();
+        assert_eq!(mainnet, custom);
+        assert!(chain_id_from_network("not a network").is_none());
+
+        let katana = chain_id_from_network("KATANA").unwrap();
+        assert_eq!(
+            katana,
+            v1alpha2::FieldElement::from_hex("0x4b4154414e41").unwrap()
+        );
+    }
+}
@@ -1,20 +1,114 @@
 //! Block ingestion configuration.
 use std::time::Duration;
 
+use apibara_core::starknet::v1alpha2;
+use serde::Serialize;
+use url::Url;
+
+use super::{
+    block_hash::BlockHashValidation, data_availability::DataAvailabilityConfig,
+    disk::DiskUsageConfig, emitters::EmitterStats, events_backfill::EventsBackfillConfig,
+    journal::IngestionJournal, l1_message::L1MessageResolver, retry::RetryPolicy,
+};
+
 /// Block ingestion configuration.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct BlockIngestionConfig {
     /// Concurrency for RPC requests.
     pub rpc_concurrency: usize,
+    /// First block ingested into an empty database. Earlier blocks are not
+    /// available.
+    pub starting_block: u64,
     /// How often to refresh head block.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
     pub head_refresh_interval: Duration,
+    /// Ingest the pending block.
+    pub ingest_pending: bool,
+    /// Minimum interval between requests for the pending block.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub pending_poll_interval: Duration,
+    /// How block hashes are checked against the block content.
+    pub block_hash_validation: BlockHashValidation,
+    /// Only ingest block headers and the canonical chain.
+    pub headers_only: bool,
+    /// Ingest the execution traces of transactions.
+    pub ingest_traces: bool,
+    /// Ingest the definition of declared classes.
+    pub ingest_classes: bool,
+    /// Ingest blocks without state update or traces if the provider doesn't
+    /// return them.
+    pub tolerate_missing_data: bool,
+    /// Number of failed attempts at the same block after which it's stored
+    /// without the data the provider fails to return. Never if `None`.
+    pub journal_max_attempts: Option<u32>,
+    /// Blocks that repeatedly failed to ingest.
+    #[serde(skip)]
+    pub journal: IngestionJournal,
+    /// Number of blocks for which superseded pending data is kept.
+    pub pending_retention: u64,
+    /// Url of a DNA node used to bootstrap finalized blocks.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_url_str")]
+    pub bootstrap_node: Option<String>,
+    /// Backfill finalized blocks with only their events.
+    pub events_backfill: Option<EventsBackfillConfig>,
+    /// Url of a second RPC provider that must agree on accepted blocks.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_url")]
+    pub quorum_provider: Option<Url>,
+    /// WebSocket url of the RPC provider, used to be notified of new heads.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_url")]
+    pub head_subscription: Option<Url>,
+    /// Chain id the provider must have. The chain id stored in the database
+    /// is always checked.
+    pub expected_chain_id: Option<v1alpha2::FieldElement>,
+    /// How ingestion is restarted after an error.
+    pub retry_policy: RetryPolicy,
+    /// Number of blocks ingestion can be behind the provider head before it's
+    /// reported as unhealthy.
+    pub max_lag: Option<u64>,
+    /// Number of blocks the finalized block can be behind the node head
+    /// before ingestion is reported as unhealthy.
+    pub max_finalized_lag: Option<u64>,
+    /// Looks up the L1 transactions that sent the messages consumed by L1
+    /// handler transactions.
+    #[serde(serialize_with = "crate::runtime_config::serialize_is_some")]
+    pub l1_message_resolver: Option<L1MessageResolver>,
+    /// Ingest the L1 data availability of finalized blocks.
+    pub data_availability: Option<DataAvailabilityConfig>,
+    /// Slow down and pause ingestion when the database volume is almost full.
+    pub disk_usage: Option<DiskUsageConfig>,
+    /// Count the events of ingested blocks by emitter.
+    #[serde(serialize_with = "crate::runtime_config::serialize_is_some")]
+    pub emitter_stats: Option<EmitterStats>,
 }
 
 impl Default for BlockIngestionConfig {
     fn default() -> Self {
         BlockIngestionConfig {
             rpc_concurrency: 16,
+            starting_block: 0,
             head_refresh_interval: Duration::from_secs(3),
+            ingest_pending: true,
+            pending_poll_interval: Duration::from_secs(3),
+            block_hash_validation: BlockHashValidation::default(),
+            headers_only: false,
+            ingest_traces: false,
+            ingest_classes: false,
+            tolerate_missing_data: false,
+            journal_max_attempts: None,
+            journal: IngestionJournal::default(),
+            pending_retention: 8,
+            bootstrap_node: None,
+            events_backfill: None,
+            quorum_provider: None,
+            head_subscription: None,
+            expected_chain_id: None,
+            retry_policy: RetryPolicy::default(),
+            max_lag: None,
+            max_finalized_lag: None,
+            l1_message_resolver: None,
+            data_availability: None,
+            disk_usage: None,
+            emitter_stats: None,
         }
     }
 }
@@ -0,0 +1,161 @@
+//! Pause and resume block ingestion.
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+#[cfg(feature = "reorg-injection")]
+use std::{collections::VecDeque, sync::Mutex};
+
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+#[cfg(feature = "reorg-injection")]
+use super::inject::InjectedEvent;
+
+/// Shared handle to pause and resume ingestion.
+///
+/// Ingestion checks the handle between blocks, so a block being ingested
+/// when ingestion is paused is always completed.
+#[derive(Debug, Clone)]
+pub struct IngestionControl {
+    paused_tx: Arc<watch::Sender<bool>>,
+    paused_rx: watch::Receiver<bool>,
+    // true while ingestion waits to be resumed.
+    idle: Arc<AtomicBool>,
+    #[cfg(feature = "reorg-injection")]
+    injected: Arc<Mutex<VecDeque<InjectedEvent>>>,
+}
+
+/// State of ingestion, as requested through [IngestionControl].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestionControlStatus {
+    /// Ingestion is running.
+    Running,
+    /// Ingestion was paused, but is still completing the current block.
+    Pausing,
+    /// Ingestion is paused and doesn't write to the database.
+    Paused,
+}
+
+impl Default for IngestionControl {
+    fn default() -> Self {
+        let (paused_tx, paused_rx) = watch::channel(false);
+        IngestionControl {
+            paused_tx: Arc::new(paused_tx),
+            paused_rx,
+            idle: Arc::new(AtomicBool::new(false)),
+            #[cfg(feature = "reorg-injection")]
+            injected: Arc::default(),
+        }
+    }
+}
+
+impl IngestionControl {
+    /// Pauses ingestion after the current block.
+    ///
+    /// Returns false if ingestion was already paused.
+    pub fn pause(&self) -> bool {
+        let was_paused = self.paused_tx.send_replace(true);
+        if !was_paused {
+            info!("pausing block ingestion");
+        }
+        !was_paused
+    }
+
+    /// Resumes ingestion.
+    ///
+    /// Returns false if ingestion was not paused.
+    pub fn resume(&self) -> bool {
+        let was_paused = self.paused_tx.send_replace(false);
+        if was_paused {
+            info!("resuming block ingestion");
+        }
+        was_paused
+    }
+
+    pub fn status(&self) -> IngestionControlStatus {
+        if !*self.paused_rx.borrow() {
+            IngestionControlStatus::Running
+        } else if self.idle.load(Ordering::SeqCst) {
+            IngestionControlStatus::Paused
+        } else {
+            IngestionControlStatus::Pausing
+        }
+    }
+
+    /// Waits until ingestion is not paused, or until `ct` is cancelled.
+    pub async fn wait_until_resumed(&self, ct: &CancellationToken) {
+        let mut paused_rx = self.paused_rx.clone();
+        if !*paused_rx.borrow_and_update() {
+            return;
+        }
+
+        info!("block ingestion paused");
+        self.idle.store(true, Ordering::SeqCst);
+        while *paused_rx.borrow_and_update() {
+            tokio::select! {
+                changed = paused_rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+                _ = ct.cancelled() => break,
+            }
+        }
+        self.idle.store(false, Ordering::SeqCst);
+    }
+
+    /// Queues an event, applied by ingestion before the next block.
+    #[cfg(feature = "reorg-injection")]
+    pub fn inject(&self, event: InjectedEvent) {
+        info!(event = ?event, "queue injected event");
+        self.injected
+            .lock()
+            .expect("ingestion control lock")
+            .push_back(event);
+    }
+
+    /// Removes and returns the queued events.
+    #[cfg(feature = "reorg-injection")]
+    pub fn take_injected(&self) -> Vec<InjectedEvent> {
+        self.injected
+            .lock()
+            .expect("ingestion control lock")
+            .drain(..)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_util::sync::CancellationToken;
+
+    use super::{IngestionControl, IngestionControlStatus};
+
+    #[tokio::test]
+    async fn test_pause_and_resume() {
+        let control = IngestionControl::default();
+        let ct = CancellationToken::new();
+        assert_eq!(control.status(), IngestionControlStatus::Running);
+        control.wait_until_resumed(&ct).await;
+
+        assert!(control.pause());
+        assert!(!control.pause());
+        assert_eq!(control.status(), IngestionControlStatus::Pausing);
+
+        let waiter = tokio::spawn({
+            let control = control.clone();
+            let ct = ct.clone();
+            async move { control.wait_until_resumed(&ct).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(control.status(), IngestionControlStatus::Paused);
+
+        assert!(control.resume());
+        waiter.await.unwrap();
+        assert_eq!(control.status(), IngestionControlStatus::Running);
+    }
+}
@@ -0,0 +1,521 @@
+//! Ingest the L1 data availability of finalized blocks.
+//!
+//! The Starknet core contract emits `LogStateUpdate` when the state of a
+//! block is accepted on L1. Networks using blob data availability publish
+//! the state diff of all blocks settled by the update in the blobs of the
+//! same transaction. Blobs are fetched from the beacon chain, decoded and
+//! reconciled with the state updates ingested from L2.
+use std::{collections::HashSet, time::Duration};
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha3::{Digest, Keccak256};
+use tokio::sync::OnceCell;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use url::Url;
+
+use crate::db::{DatabaseStorage, StorageReader};
+
+use super::blob::{decode_blob, versioned_hash, BlobError};
+
+/// Signature of the event emitted by the core contract for each state
+/// update.
+const LOG_STATE_UPDATE: &str = "LogStateUpdate(uint256,int256,uint256)";
+
+/// Duration of a beacon chain slot.
+const SECONDS_PER_SLOT: u64 = 12;
+
+/// Data availability ingestion configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct DataAvailabilityConfig {
+    /// Url of the Ethereum RPC.
+    #[serde(serialize_with = "crate::runtime_config::serialize_url")]
+    pub ethereum_rpc: Url,
+    /// Url of the Ethereum beacon node api, used to fetch blobs.
+    #[serde(serialize_with = "crate::runtime_config::serialize_url")]
+    pub beacon_api: Url,
+    /// Address of the Starknet core contract.
+    pub core_contract: String,
+    /// First L1 block searched for state updates, if no block has data
+    /// availability yet.
+    pub l1_starting_block: u64,
+    /// Number of L1 blocks whose state updates are requested together.
+    pub l1_blocks_per_request: u64,
+    /// How often to check for new finalized blocks.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub poll_interval: Duration,
+}
+
+impl DataAvailabilityConfig {
+    pub fn new(ethereum_rpc: Url, beacon_api: Url, core_contract: String) -> Self {
+        DataAvailabilityConfig {
+            ethereum_rpc,
+            beacon_api,
+            core_contract,
+            l1_starting_block: 0,
+            l1_blocks_per_request: 1000,
+            poll_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DataAvailabilityError {
+    #[error("ethereum request failed")]
+    Request(#[from] reqwest::Error),
+    #[error("ethereum rpc returned an error: {0}")]
+    Rpc(String),
+    #[error("failed to parse ethereum response")]
+    InvalidResponse,
+    #[error("blob {0} not found on the beacon chain")]
+    MissingBlob(v1alpha2::FieldElement),
+    #[error("failed to decode blob")]
+    Blob(#[from] BlobError),
+    #[error("database operation failed")]
+    Database(#[from] libmdbx::Error),
+}
+
+/// A state update accepted by the core contract.
+#[derive(Debug, Clone)]
+struct StateUpdateLog {
+    l1_block_number: u64,
+    transaction_hash: v1alpha2::FieldElement,
+    /// Last Starknet block settled by the update.
+    block_number: u64,
+}
+
+/// Reads state updates and blobs from Ethereum.
+struct L1DataClient {
+    client: reqwest::Client,
+    config: DataAvailabilityConfig,
+    genesis_time: OnceCell<u64>,
+}
+
+pub struct DataAvailabilityIngestion<E: EnvironmentKind> {
+    client: L1DataClient,
+    storage: DatabaseStorage<E>,
+}
+
+impl<E> DataAvailabilityIngestion<E>
+where
+    E: EnvironmentKind,
+{
+    pub fn new(storage: DatabaseStorage<E>, config: DataAvailabilityConfig) -> Self {
+        let client = L1DataClient {
+            client: reqwest::Client::new(),
+            config,
+            genesis_time: OnceCell::new(),
+        };
+        DataAvailabilityIngestion { client, storage }
+    }
+
+    /// Follows the state updates accepted on L1 until `ct` is cancelled.
+    pub async fn start(self, ct: CancellationToken) -> Result<(), DataAvailabilityError> {
+        let config = &self.client.config;
+        // blocks settled before the first state update found are skipped.
+        let (mut l1_cursor, mut last_settled) = match self.storage.latest_data_availability()? {
+            None => (config.l1_starting_block, None),
+            Some((block_id, data_availability)) => {
+                (data_availability.l1_block_number, Some(block_id.number()))
+            }
+        };
+        info!(
+            l1_block = %l1_cursor,
+            last_settled = ?last_settled,
+            "start data availability ingestion"
+        );
+
+        while !ct.is_cancelled() {
+            let l1_head = self.client.finalized_block_number().await?;
+            if l1_cursor > l1_head {
+                wait(config.poll_interval, &ct).await;
+                continue;
+            }
+
+            let l1_end = u64::min(
+                l1_cursor + u64::max(config.l1_blocks_per_request, 1) - 1,
+                l1_head,
+            );
+            for update in self.client.state_updates(l1_cursor, l1_end).await? {
+                if matches!(last_settled, Some(last) if update.block_number <= last) {
+                    continue;
+                }
+                // the provider can report blocks as finalized after L1 does.
+                while !self.is_finalized(update.block_number)? {
+                    if ct.is_cancelled() {
+                        return Ok(());
+                    }
+                    wait(config.poll_interval, &ct).await;
+                }
+                let first = last_settled
+                    .map(|number| number + 1)
+                    .unwrap_or(update.block_number);
+                self.ingest_state_update(&update, first).await?;
+                last_settled = Some(update.block_number);
+            }
+
+            l1_cursor = l1_end + 1;
+        }
+
+        Ok(())
+    }
+
+    fn is_finalized(&self, block_number: u64) -> Result<bool, DataAvailabilityError> {
+        let finalized = self.storage.highest_finalized_block()?;
+        Ok(matches!(finalized, Some(block_id) if block_id.number() >= block_number))
+    }
+
+    /// Stores the data availability of the blocks between `first` and the
+    /// last block settled by the update.
+    async fn ingest_state_update(
+        &self,
+        update: &StateUpdateLog,
+        first: u64,
+    ) -> Result<(), DataAvailabilityError> {
+        let blob_versioned_hashes = self
+            .client
+            .blob_versioned_hashes(&update.transaction_hash)
+            .await?;
+        let state_diff = if blob_versioned_hashes.is_empty() {
+            Vec::default()
+        } else {
+            self.client
+                .blobs(update.l1_block_number, &blob_versioned_hashes)
+                .await?
+        };
+        let published: HashSet<[u8; 32]> = state_diff.iter().map(|e| e.to_bytes()).collect();
+
+        let mut blocks = Vec::default();
+        for number in first..=update.block_number {
+            let block_id = match self.storage.canonical_block_id(number)? {
+                None => continue,
+                Some(block_id) => block_id,
+            };
+            let reconciled = !blob_versioned_hashes.is_empty()
+                && self
+                    .storage
+                    .read_state_update(&block_id)?
+                    .map(|state_update| is_published(&published, &state_update))
+                    .unwrap_or(false);
+            if !blob_versioned_hashes.is_empty() && !reconciled {
+                warn!(block_id = %block_id, "state diff doesn't match l1 blobs");
+            }
+            let data_availability = v1alpha2::DataAvailability {
+                l1_block_number: update.l1_block_number,
+                l1_transaction_hash: Some(update.transaction_hash.clone()),
+                blob_versioned_hashes: blob_versioned_hashes.clone(),
+                state_diff_length: state_diff.len() as u64,
+                reconciled,
+            };
+            blocks.push((block_id, data_availability));
+        }
+
+        self.storage.write_data_availability(&blocks)?;
+        info!(
+            first = %first,
+            last = %update.block_number,
+            l1_block = %update.l1_block_number,
+            blobs = %blob_versioned_hashes.len(),
+            "ingested data availability"
+        );
+        Ok(())
+    }
+}
+
+impl L1DataClient {
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, DataAvailabilityError> {
+        #[derive(Deserialize)]
+        struct RpcResponse<T> {
+            result: Option<T>,
+            error: Option<Value>,
+        }
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response: RpcResponse<T> = self
+            .client
+            .post(self.config.ethereum_rpc.clone())
+            .json(&request)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = response.error {
+            return Err(DataAvailabilityError::Rpc(error.to_string()));
+        }
+        response
+            .result
+            .ok_or(DataAvailabilityError::InvalidResponse)
+    }
+
+    async fn beacon_request<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> Result<T, DataAvailabilityError> {
+        #[derive(Deserialize)]
+        struct BeaconResponse<T> {
+            data: T,
+        }
+
+        let url = self
+            .config
+            .beacon_api
+            .join(path)
+            .map_err(|_| DataAvailabilityError::InvalidResponse)?;
+        let response: BeaconResponse<T> = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.data)
+    }
+
+    async fn block(&self, block: Value) -> Result<L1Block, DataAvailabilityError> {
+        self.request("eth_getBlockByNumber", json!([block, false]))
+            .await
+    }
+
+    /// Returns the number of the last finalized L1 block.
+    async fn finalized_block_number(&self) -> Result<u64, DataAvailabilityError> {
+        let block = self.block(json!("finalized")).await?;
+        parse_quantity(&block.number)
+    }
+
+    /// Returns the state updates accepted between the two L1 blocks,
+    /// inclusive.
+    async fn state_updates(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<StateUpdateLog>, DataAvailabilityError> {
+        let topic = v1alpha2::FieldElement::from_bytes(&Keccak256::digest(LOG_STATE_UPDATE).into());
+        let logs: Vec<Log> = self
+            .request(
+                "eth_getLogs",
+                json!([{
+                    "address": self.config.core_contract,
+                    "fromBlock": format!("{from_block:#x}"),
+                    "toBlock": format!("{to_block:#x}"),
+                    "topics": [topic.to_hex()],
+                }]),
+            )
+            .await?;
+
+        let mut updates = Vec::with_capacity(logs.len());
+        for log in logs {
+            let data = log
+                .data
+                .strip_prefix("0x")
+                .and_then(|data| hex::decode(data).ok())
+                .ok_or(DataAvailabilityError::InvalidResponse)?;
+            // data is the global root, followed by the block number.
+            let block_number = match data.get(32..64) {
+                Some(number) if number[..24].iter().all(|b| *b == 0) => {
+                    let mut bytes = [0u8; 8];
+                    bytes.copy_from_slice(&number[24..]);
+                    u64::from_be_bytes(bytes)
+                }
+                _ => return Err(DataAvailabilityError::InvalidResponse),
+            };
+            let transaction_hash = v1alpha2::FieldElement::from_hex(&log.transaction_hash)
+                .map_err(|_| DataAvailabilityError::InvalidResponse)?;
+            updates.push(StateUpdateLog {
+                l1_block_number: parse_quantity(&log.block_number)?,
+                transaction_hash,
+                block_number,
+            });
+        }
+        Ok(updates)
+    }
+
+    /// Returns the versioned hashes of the blobs of the transaction.
+    async fn blob_versioned_hashes(
+        &self,
+        transaction_hash: &v1alpha2::FieldElement,
+    ) -> Result<Vec<v1alpha2::FieldElement>, DataAvailabilityError> {
+        let transaction: L1Transaction = self
+            .request(
+                "eth_getTransactionByHash",
+                json!([transaction_hash.to_hex()]),
+            )
+            .await?;
+        transaction
+            .blob_versioned_hashes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|hash| {
+                v1alpha2::FieldElement::from_hex(&hash)
+                    .map_err(|_| DataAvailabilityError::InvalidResponse)
+            })
+            .collect()
+    }
+
+    /// Returns the decoded content of the blobs included in the L1 block, in
+    /// the order of `versioned_hashes`.
+    async fn blobs(
+        &self,
+        l1_block_number: u64,
+        versioned_hashes: &[v1alpha2::FieldElement],
+    ) -> Result<Vec<v1alpha2::FieldElement>, DataAvailabilityError> {
+        let block = self.block(json!(format!("{l1_block_number:#x}"))).await?;
+        let timestamp = parse_quantity(&block.timestamp)?;
+        let genesis_time = self
+            .genesis_time
+            .get_or_try_init(|| async {
+                let genesis: Genesis = self.beacon_request("eth/v1/beacon/genesis").await?;
+                genesis
+                    .genesis_time
+                    .parse::<u64>()
+                    .map_err(|_| DataAvailabilityError::InvalidResponse)
+            })
+            .await?;
+        let slot = timestamp.saturating_sub(*genesis_time) / SECONDS_PER_SLOT;
+        let sidecars: Vec<BlobSidecar> = self
+            .beacon_request(&format!("eth/v1/beacon/blob_sidecars/{slot}"))
+            .await?;
+
+        let mut elements = Vec::default();
+        for versioned in versioned_hashes {
+            let blob = sidecars
+                .iter()
+                .find(|sidecar| {
+                    decode_hex(&sidecar.kzg_commitment)
+                        .map(|commitment| versioned_hash(&commitment) == *versioned)
+                        .unwrap_or(false)
+                })
+                .ok_or_else(|| DataAvailabilityError::MissingBlob(versioned.clone()))?;
+            let blob = decode_hex(&blob.blob).ok_or(DataAvailabilityError::InvalidResponse)?;
+            elements.extend(decode_blob(&blob)?);
+        }
+        Ok(elements)
+    }
+}
+
+#[derive(Deserialize)]
+struct L1Block {
+    number: String,
+    timestamp: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Log {
+    data: String,
+    transaction_hash: String,
+    block_number: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct L1Transaction {
+    blob_versioned_hashes: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct Genesis {
+    genesis_time: String,
+}
+
+#[derive(Deserialize)]
+struct BlobSidecar {
+    blob: String,
+    kzg_commitment: String,
+}
+
+/// Returns true if the contracts and storage keys changed by the state
+/// update are published in the state diff.
+fn is_published(published: &HashSet<[u8; 32]>, state_update: &v1alpha2::StateUpdate) -> bool {
+    let state_diff = match state_update.state_diff {
+        None => return true,
+        Some(ref state_diff) => state_diff,
+    };
+    let contains = |element: &Option<v1alpha2::FieldElement>| {
+        element
+            .as_ref()
+            .map(|element| published.contains(&element.to_bytes()))
+            .unwrap_or(false)
+    };
+
+    state_diff.storage_diffs.iter().all(|diff| {
+        contains(&diff.contract_address)
+            && diff
+                .storage_entries
+                .iter()
+                .all(|entry| contains(&entry.key))
+    }) && state_diff
+        .deployed_contracts
+        .iter()
+        .all(|deployed| contains(&deployed.contract_address))
+        && state_diff
+            .nonces
+            .iter()
+            .all(|nonce| contains(&nonce.contract_address))
+}
+
+fn parse_quantity(value: &str) -> Result<u64, DataAvailabilityError> {
+    let digits = value
+        .strip_prefix("0x")
+        .ok_or(DataAvailabilityError::InvalidResponse)?;
+    u64::from_str_radix(digits, 16).map_err(|_| DataAvailabilityError::InvalidResponse)
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    value
+        .strip_prefix("0x")
+        .and_then(|value| hex::decode(value).ok())
+}
+
+async fn wait(duration: Duration, ct: &CancellationToken) {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => {},
+        _ = ct.cancelled() => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use apibara_core::starknet::v1alpha2;
+
+    use super::is_published;
+
+    #[test]
+    fn test_is_published() {
+        let element = v1alpha2::FieldElement::from_u64;
+        let state_update = v1alpha2::StateUpdate {
+            state_diff: Some(v1alpha2::StateDiff {
+                storage_diffs: vec![v1alpha2::StorageDiff {
+                    contract_address: Some(element(1)),
+                    storage_entries: vec![v1alpha2::StorageEntry {
+                        key: Some(element(2)),
+                        value: Some(element(3)),
+                    }],
+                }],
+                ..v1alpha2::StateDiff::default()
+            }),
+            ..v1alpha2::StateUpdate::default()
+        };
+
+        let published: HashSet<_> = [1, 2, 3].map(|e| element(e).to_bytes()).into();
+        assert!(is_published(&published, &state_update));
+
+        let published: HashSet<_> = [1, 3].map(|e| element(e).to_bytes()).into();
+        assert!(!is_published(&published, &state_update));
+    }
+}
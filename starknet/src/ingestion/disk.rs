@@ -0,0 +1,156 @@
+//! Throttle ingestion when the database volume is running out of space.
+//!
+//! Writing a block when the volume or the database map is full fails with
+//! an error that stops ingestion. Instead, ingestion slows down when free
+//! space falls below a first threshold, and pauses below a second one until
+//! space is freed.
+use std::{fs, io, path::PathBuf, time::Duration};
+
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+/// Name of the mdbx data file in the database directory.
+const DATA_FILE: &str = "mdbx.dat";
+
+/// How often free space is checked while ingestion is paused.
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Delay between blocks while ingestion is slowed down.
+const DEFAULT_THROTTLE_DELAY: Duration = Duration::from_secs(1);
+
+/// Disk usage thresholds.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsageConfig {
+    /// The database directory.
+    pub path: PathBuf,
+    /// Maximum size of the database. Space available to the database is
+    /// also bounded by this size minus the size of the data file.
+    pub max_database_size: Option<u64>,
+    /// Pause ingestion when the free space falls below this many bytes.
+    pub min_free_space: u64,
+    /// Slow down ingestion when the free space falls below this many bytes.
+    pub throttle_free_space: u64,
+    /// Delay between blocks while ingestion is slowed down.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub throttle_delay: Duration,
+    /// How often free space is checked while ingestion is paused.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub check_interval: Duration,
+}
+
+impl DiskUsageConfig {
+    /// Creates a configuration that pauses ingestion when the space available
+    /// to the database at `path` falls below `min_free_space` bytes.
+    pub fn new(path: PathBuf, min_free_space: u64) -> Self {
+        DiskUsageConfig {
+            path,
+            max_database_size: None,
+            min_free_space,
+            throttle_free_space: min_free_space,
+            throttle_delay: DEFAULT_THROTTLE_DELAY,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+        }
+    }
+
+    /// Returns the number of bytes the database can still grow by.
+    pub fn free_space(&self) -> io::Result<u64> {
+        let available = fs2::available_space(&self.path)?;
+        let max_database_size = match self.max_database_size {
+            None => return Ok(available),
+            Some(max_database_size) => max_database_size,
+        };
+        let used = match fs::metadata(self.path.join(DATA_FILE)) {
+            Ok(metadata) => metadata.len(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(err),
+        };
+        Ok(u64::min(available, max_database_size.saturating_sub(used)))
+    }
+
+    /// Returns true if free space is below the minimum.
+    pub fn is_low(&self, free_space: u64) -> bool {
+        free_space < self.min_free_space
+    }
+
+    /// Waits until there's enough free space to ingest the next block, or
+    /// until `ct` is cancelled.
+    ///
+    /// Ingestion continues if free space cannot be checked.
+    pub async fn throttle(&self, ct: &CancellationToken) {
+        loop {
+            let free_space = match self.free_space() {
+                Ok(free_space) => free_space,
+                Err(err) => {
+                    warn!(error = ?err, "failed to check free disk space");
+                    return;
+                }
+            };
+
+            let wait = match self.action(free_space) {
+                DiskAction::Continue => return,
+                DiskAction::Throttle => {
+                    debug!(free_space = %free_space, "low disk space. slowing down ingestion");
+                    self.throttle_delay
+                }
+                DiskAction::Pause => {
+                    warn!(
+                        free_space = %free_space,
+                        min_free_space = %self.min_free_space,
+                        "disk almost full. ingestion paused"
+                    );
+                    self.check_interval
+                }
+            };
+
+            tokio::select! {
+                _ = ct.cancelled() => return,
+                _ = tokio::time::sleep(wait) => {},
+            }
+
+            if !self.is_low(free_space) {
+                return;
+            }
+        }
+    }
+
+    fn action(&self, free_space: u64) -> DiskAction {
+        if self.is_low(free_space) {
+            DiskAction::Pause
+        } else if free_space < self.throttle_free_space {
+            DiskAction::Throttle
+        } else {
+            DiskAction::Continue
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DiskAction {
+    Continue,
+    Throttle,
+    Pause,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiskAction, DiskUsageConfig};
+
+    #[test]
+    fn test_disk_action() {
+        let mut config = DiskUsageConfig::new("/tmp".into(), 100);
+        config.throttle_free_space = 1_000;
+        assert_eq!(config.action(2_000), DiskAction::Continue);
+        assert_eq!(config.action(500), DiskAction::Throttle);
+        assert_eq!(config.action(50), DiskAction::Pause);
+    }
+
+    #[test]
+    fn test_free_space_bounded_by_max_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("mdbx.dat"), vec![0u8; 1_000]).unwrap();
+        let mut config = DiskUsageConfig::new(dir.path().to_path_buf(), 0);
+        config.max_database_size = Some(1_500);
+        assert_eq!(config.free_space().unwrap(), 500);
+    }
+}
@@ -4,31 +4,135 @@ use std::sync::Arc;
 
 use apibara_core::starknet::v1alpha2;
 use futures::{stream, StreamExt};
+use tracing::warn;
 
 use crate::{
     core::GlobalBlockId,
-    db::{BlockBody, StorageWriter},
-    provider::{BlockId, Provider},
+    db::{BlockBody, PartialBlock, StorageWriter},
+    provider::BlockId,
 };
 
-use super::BlockIngestionError;
+use super::{
+    block_hash::BlockHashValidation,
+    emitters::EmitterStats,
+    journal::IngestionJournal,
+    l1_message::{l1_message_hash, L1MessageResolver},
+    source::IngestionSource,
+    stages::{stage_metrics, IngestionStage},
+    BlockIngestionError,
+};
+
+/// Block data downloaded but not written to storage yet.
+#[derive(Debug)]
+pub struct DownloadedBlock {
+    pub global_id: GlobalBlockId,
+    status: v1alpha2::BlockStatus,
+    header: v1alpha2::BlockHeader,
+    body: BlockBody,
+    receipts: Vec<v1alpha2::TransactionReceipt>,
+    state_update: Option<v1alpha2::StateUpdate>,
+    classes: Vec<DeclaredClass>,
+    traces: Option<Vec<v1alpha2::TransactionTrace>>,
+    partial: PartialBlock,
+}
 
-pub struct Downloader<G: Provider + Send> {
+/// JSON definition of a class declared in a block.
+#[derive(Debug)]
+struct DeclaredClass {
+    class_hash: v1alpha2::FieldElement,
+    definition: Vec<u8>,
+}
+
+pub struct Downloader<G: IngestionSource> {
     provider: Arc<G>,
-    receipt_concurrency: usize,
+    rpc_concurrency: usize,
+    ingest_traces: bool,
+    ingest_classes: bool,
+    headers_only: bool,
+    tolerate_missing_data: bool,
+    journal: IngestionJournal,
+    block_hash_validation: BlockHashValidation,
+    l1_message_resolver: Option<L1MessageResolver>,
+    emitter_stats: Option<EmitterStats>,
 }
 
 impl<G> Downloader<G>
 where
-    G: Provider + Send,
+    G: IngestionSource,
 {
-    pub fn new(provider: Arc<G>, receipt_concurrency: usize) -> Self {
+    pub fn new(provider: Arc<G>, rpc_concurrency: usize) -> Self {
         Downloader {
             provider,
-            receipt_concurrency,
+            rpc_concurrency,
+            ingest_traces: false,
+            ingest_classes: false,
+            headers_only: false,
+            tolerate_missing_data: false,
+            journal: IngestionJournal::default(),
+            block_hash_validation: BlockHashValidation::default(),
+            l1_message_resolver: None,
+            emitter_stats: None,
         }
     }
 
+    /// Also download the execution traces of the transactions.
+    pub fn with_traces(mut self, ingest_traces: bool) -> Self {
+        self.ingest_traces = ingest_traces;
+        self
+    }
+
+    /// Also download the definition of the classes declared in the block.
+    pub fn with_classes(mut self, ingest_classes: bool) -> Self {
+        self.ingest_classes = ingest_classes;
+        self
+    }
+
+    /// Only store the block status and header.
+    ///
+    /// Bodies and receipts are stored empty so that blocks are still
+    /// complete, state updates and traces are not downloaded.
+    pub fn with_headers_only(mut self, headers_only: bool) -> Self {
+        self.headers_only = headers_only;
+        self
+    }
+
+    /// Store blocks without state update or traces if the provider fails
+    /// to return them, instead of failing.
+    pub fn with_missing_data_tolerance(mut self, tolerate_missing_data: bool) -> Self {
+        self.tolerate_missing_data = tolerate_missing_data;
+        self
+    }
+
+    /// Store blocks marked as degraded in the journal without the data the
+    /// provider fails to return.
+    pub fn with_journal(mut self, journal: IngestionJournal) -> Self {
+        self.journal = journal;
+        self
+    }
+
+    /// Check the block hash against the block content.
+    ///
+    /// Not applied to headers only ingestion, since it requires the
+    /// transactions and receipts.
+    pub fn with_block_hash_validation(mut self, validation: BlockHashValidation) -> Self {
+        self.block_hash_validation = validation;
+        self
+    }
+
+    /// Look up the L1 transactions that sent the messages consumed by L1
+    /// handler transactions.
+    pub fn with_l1_message_resolver(mut self, resolver: Option<L1MessageResolver>) -> Self {
+        self.l1_message_resolver = resolver;
+        self
+    }
+
+    /// Count the events of the written blocks by emitter.
+    pub fn with_emitter_stats(mut self, emitter_stats: Option<EmitterStats>) -> Self {
+        self.emitter_stats = emitter_stats;
+        self
+    }
+
+    /// Download the block data and write it to storage.
     pub async fn finish_ingesting_block<W: StorageWriter>(
         &self,
         global_id: &GlobalBlockId,
@@ -40,7 +144,143 @@ where
     where
         BlockIngestionError: From<W::Error>,
     {
-        // download state update, receipts
+        let block = self
+            .download_block(*global_id, status, header, body)
+            .await?;
+        self.write_block(block, writer)
+    }
+
+    /// Download the receipts, state update, declared classes and traces of
+    /// the block.
+    ///
+    /// Nothing is written to storage, so the next block can be downloaded
+    /// while the previous one is written.
+    pub async fn download_block(
+        &self,
+        global_id: GlobalBlockId,
+        status: v1alpha2::BlockStatus,
+        header: v1alpha2::BlockHeader,
+        mut body: BlockBody,
+    ) -> Result<DownloadedBlock, BlockIngestionError> {
+        if self.headers_only {
+            return Ok(DownloadedBlock {
+                global_id,
+                status,
+                header,
+                body: BlockBody::default(),
+                receipts: Vec::default(),
+                state_update: None,
+                classes: Vec::default(),
+                traces: None,
+                partial: PartialBlock::default(),
+            });
+        }
+
+        self.link_l1_messages(&mut body).await;
+
+        // receipts, state update and traces are independent, so they're
+        // downloaded at the same time. classes are listed in the state update.
+        let metrics = stage_metrics();
+        let (receipts, (state_update, classes), traces) = futures::try_join!(
+            metrics.time(IngestionStage::FetchReceipts, self.download_receipts(&body)),
+            async {
+                let state_update = metrics
+                    .time(
+                        IngestionStage::FetchStateUpdate,
+                        self.download_state_update(&global_id),
+                    )
+                    .await?;
+                let classes = match state_update {
+                    None => (Vec::default(), true),
+                    // classes can only be declared once, so the classes of a new
+                    // block are not stored yet.
+                    Some(ref state_update) => {
+                        metrics
+                            .time(
+                                IngestionStage::FetchClasses,
+                                self.download_declared_classes(&global_id, state_update, |_| {
+                                    Ok(false)
+                                }),
+                            )
+                            .await?
+                    }
+                };
+                Ok::<_, BlockIngestionError>((state_update, classes))
+            },
+            metrics.time(
+                IngestionStage::FetchTraces,
+                self.download_traces(&global_id)
+            ),
+        )?;
+        let (classes, has_classes) = classes;
+
+        metrics.record(IngestionStage::Validate, || {
+            self.block_hash_validation
+                .validate(&global_id, &header, &body, &receipts)
+        })?;
+
+        // pending blocks have no state update and traces.
+        let is_pending = global_id.hash().is_zero();
+        let partial = PartialBlock {
+            missing_state_update: !is_pending && state_update.is_none(),
+            missing_traces: self.ingest_traces && !is_pending && traces.is_none(),
+            missing_classes: !has_classes,
+            ..PartialBlock::default()
+        };
+
+        Ok(DownloadedBlock {
+            global_id,
+            status,
+            header,
+            body,
+            receipts,
+            state_update,
+            classes,
+            traces,
+            partial,
+        })
+    }
+
+    /// Write the downloaded block to storage, together with the classes it
+    /// declares.
+    ///
+    /// The data missing from the block is recorded, so that readers don't
+    /// mistake a degraded block for a complete one.
+    pub fn write_block<W: StorageWriter>(
+        &self,
+        block: DownloadedBlock,
+        writer: &mut W,
+    ) -> Result<(), BlockIngestionError>
+    where
+        BlockIngestionError: From<W::Error>,
+    {
+        let global_id = &block.global_id;
+        if let Some(emitter_stats) = &self.emitter_stats {
+            emitter_stats.record_receipts(&block.receipts);
+        }
+        stage_metrics().record(IngestionStage::Write, || {
+            writer.write_status(global_id, block.status)?;
+            writer.write_header(global_id, block.header)?;
+            writer.write_body(global_id, block.body)?;
+            writer.write_receipts(global_id, block.receipts)?;
+            if let Some(traces) = block.traces {
+                writer.write_traces(global_id, traces)?;
+            }
+            write_classes(&block.classes, writer)?;
+            if let Some(state_update) = block.state_update {
+                writer.write_state_update(global_id, state_update)?;
+            }
+            writer.write_partial_block(global_id, &block.partial)?;
+            Ok::<_, BlockIngestionError>(())
+        })
+    }
+
+    /// Download the receipts of the transactions in the block, at most
+    /// `rpc_concurrency` at the time.
+    async fn download_receipts(
+        &self,
+        body: &BlockBody,
+    ) -> Result<Vec<v1alpha2::TransactionReceipt>, BlockIngestionError> {
         let hashes = body
             .transactions
             .iter()
@@ -73,37 +313,197 @@ where
                         .map_err(BlockIngestionError::provider)
                 }
             })
-            .buffer_unordered(self.receipt_concurrency);
+            .buffer_unordered(self.rpc_concurrency);
 
-        let receipts = receipts
+        receipts
             .collect::<Vec<_>>()
             .await
             .into_iter()
-            .collect::<Result<Vec<_>, BlockIngestionError>>()?;
+            .collect::<Result<Vec<_>, BlockIngestionError>>()
+    }
 
+    async fn download_state_update(
+        &self,
+        global_id: &GlobalBlockId,
+    ) -> Result<Option<v1alpha2::StateUpdate>, BlockIngestionError> {
         // pathfinder doesn't support state update for pending data.
-        let state_update = if !global_id.hash().is_zero() {
-            let block_id = BlockId::Hash(*global_id.hash());
-            let state_update = self
-                .provider
-                .get_state_update(&block_id)
-                .await
-                .map_err(BlockIngestionError::provider)?;
-            Some(state_update)
-        } else {
-            None
-        };
+        if global_id.hash().is_zero() {
+            return Ok(None);
+        }
+        let block_id = BlockId::Hash(*global_id.hash());
+        match self.provider.get_state_update(&block_id).await {
+            Ok(state_update) => Ok(Some(state_update)),
+            Err(err) if self.tolerates_missing_data(global_id) => {
+                warn!(block_id = %global_id, error = ?err, "missing state update");
+                Ok(None)
+            }
+            Err(err) => Err(BlockIngestionError::provider(err)),
+        }
+    }
+
+    async fn download_traces(
+        &self,
+        global_id: &GlobalBlockId,
+    ) -> Result<Option<Vec<v1alpha2::TransactionTrace>>, BlockIngestionError> {
+        // traces are not available for pending data either.
+        if !self.ingest_traces || global_id.hash().is_zero() {
+            return Ok(None);
+        }
+        let block_id = BlockId::Hash(*global_id.hash());
+        match self.provider.get_block_traces(&block_id).await {
+            Ok(traces) => Ok(Some(traces)),
+            Err(err) if self.tolerates_missing_data(global_id) => {
+                warn!(block_id = %global_id, error = ?err, "missing traces");
+                Ok(None)
+            }
+            Err(err) => Err(BlockIngestionError::provider(err)),
+        }
+    }
+
+    /// Adds the L1 message they consume to L1 handler transactions.
+    ///
+    /// The L1 transaction is best effort: ingestion continues without it if
+    /// the Ethereum RPC fails.
+    async fn link_l1_messages(&self, body: &mut BlockBody) {
+        for tx in body.transactions.iter_mut() {
+            let nonce = match tx.meta.as_ref().and_then(|meta| meta.nonce.clone()) {
+                None => continue,
+                Some(nonce) => nonce,
+            };
+            let l1_handler = match tx.transaction.as_mut() {
+                Some(v1alpha2::transaction::Transaction::L1Handler(l1_handler)) => l1_handler,
+                _ => continue,
+            };
+            let message_hash = match l1_message_hash(l1_handler, &nonce) {
+                None => continue,
+                Some(message_hash) => message_hash,
+            };
+
+            let mut l1_transaction_hash = None;
+            if let Some(resolver) = &self.l1_message_resolver {
+                match resolver.find_l1_transaction(l1_handler, &nonce).await {
+                    Ok(hash) => l1_transaction_hash = hash,
+                    Err(err) => {
+                        warn!(
+                            error = ?err,
+                            message_hash = %message_hash,
+                            "failed to find l1 transaction"
+                        );
+                    }
+                }
+            }
+
+            l1_handler.message = Some(v1alpha2::L1ToL2Message {
+                message_hash: Some(message_hash),
+                l1_transaction_hash,
+            });
+        }
+    }
+
+    /// Fetch and store the state update of an ingested block, together with
+    /// the classes it declares.
+    ///
+    /// Returns false if some of the declared classes are missing.
+    pub async fn ingest_state_update<W: StorageWriter>(
+        &self,
+        global_id: &GlobalBlockId,
+        writer: &mut W,
+    ) -> Result<bool, BlockIngestionError>
+    where
+        BlockIngestionError: From<W::Error>,
+    {
+        let block_id = BlockId::Hash(*global_id.hash());
+        let state_update = self
+            .provider
+            .get_state_update(&block_id)
+            .await
+            .map_err(BlockIngestionError::provider)?;
+        let (classes, has_classes) = self
+            .download_declared_classes(global_id, &state_update, |class_hash| {
+                Ok(writer.has_class(class_hash)?)
+            })
+            .await?;
+        write_classes(&classes, writer)?;
+        writer.write_state_update(global_id, state_update)?;
+        Ok(has_classes)
+    }
+
+    /// Download the classes declared in the block, at most `rpc_concurrency`
+    /// at the time, skipping the classes for which `is_stored` returns true.
+    ///
+    /// Also returns false if the provider failed to return some classes and
+    /// missing data is tolerated.
+    async fn download_declared_classes(
+        &self,
+        global_id: &GlobalBlockId,
+        state_update: &v1alpha2::StateUpdate,
+        mut is_stored: impl FnMut(&v1alpha2::FieldElement) -> Result<bool, BlockIngestionError>,
+    ) -> Result<(Vec<DeclaredClass>, bool), BlockIngestionError> {
+        if !self.ingest_classes {
+            return Ok((Vec::default(), true));
+        }
+
+        let mut class_hashes = Vec::new();
+        let declared_contracts = state_update
+            .state_diff
+            .iter()
+            .flat_map(|diff| diff.declared_contracts.iter());
+        for declared in declared_contracts {
+            if let Some(class_hash) = declared.class_hash.as_ref() {
+                if !is_stored(class_hash)? {
+                    class_hashes.push(class_hash.clone());
+                }
+            }
+        }
 
-        // write block status, header, body, receipts and state update to storage
-        writer.write_status(global_id, status)?;
-        writer.write_header(global_id, header)?;
-        writer.write_body(global_id, body)?;
-        writer.write_receipts(global_id, receipts)?;
+        let block_id = BlockId::Hash(*global_id.hash());
+        let classes = stream::iter(class_hashes)
+            .map(|class_hash| {
+                let provider = &self.provider;
+                let block_id = &block_id;
+                async move {
+                    let definition = provider.get_class(block_id, &class_hash).await;
+                    (class_hash, definition)
+                }
+            })
+            .buffer_unordered(self.rpc_concurrency)
+            .collect::<Vec<_>>()
+            .await;
 
-        if let Some(state_update) = state_update {
-            writer.write_state_update(global_id, state_update)?;
+        let mut has_classes = true;
+        let mut downloaded = Vec::with_capacity(classes.len());
+        for (class_hash, definition) in classes {
+            match definition {
+                Ok(definition) => downloaded.push(DeclaredClass {
+                    class_hash,
+                    definition,
+                }),
+                Err(err) if self.tolerates_missing_data(global_id) => {
+                    warn!(block_id = %global_id, class_hash = %class_hash, error = ?err, "missing class");
+                    has_classes = false;
+                }
+                Err(err) => return Err(BlockIngestionError::provider(err)),
+            }
         }
 
-        Ok(())
+        Ok((downloaded, has_classes))
+    }
+
+    fn tolerates_missing_data(&self, global_id: &GlobalBlockId) -> bool {
+        self.tolerate_missing_data || self.journal.is_degraded(global_id.number())
+    }
+}
+
+/// Write the downloaded classes. Classes already stored are kept.
+fn write_classes<W: StorageWriter>(
+    classes: &[DeclaredClass],
+    writer: &mut W,
+) -> Result<(), BlockIngestionError>
+where
+    BlockIngestionError: From<W::Error>,
+{
+    for class in classes {
+        writer.write_class(&class.class_hash, &class.definition)?;
     }
+    Ok(())
 }
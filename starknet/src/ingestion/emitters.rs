@@ -0,0 +1,251 @@
+//! Track the contracts that emit the most events.
+//!
+//! Events are counted by emitter address over a rolling window, made of
+//! the current and the previous period, so that operators can find the
+//! contracts that dominate storage and bandwidth.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::o11y::{self, KeyValue};
+use prost::Message;
+use tracing::warn;
+
+/// Emitter statistics configuration.
+#[derive(Debug, Clone)]
+pub struct EmitterStatsConfig {
+    /// Number of emitters reported.
+    pub top_k: usize,
+    /// Length of each period of the rolling window.
+    pub period: Duration,
+    /// Maximum number of emitters tracked in each period. The events of
+    /// other emitters are only counted in the total.
+    pub max_tracked: usize,
+}
+
+/// Events emitted by one address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmitterVolume {
+    /// Number of events.
+    pub events: u64,
+    /// Size of the events, in bytes.
+    pub bytes: u64,
+}
+
+/// Shared handle to the emitter statistics.
+#[derive(Debug, Clone)]
+pub struct EmitterStats {
+    config: EmitterStatsConfig,
+    state: Arc<Mutex<EmittersState>>,
+}
+
+#[derive(Debug)]
+struct EmittersState {
+    current: HashMap<[u8; 32], EmitterVolume>,
+    previous: HashMap<[u8; 32], EmitterVolume>,
+    /// Events of all emitters in the current and previous periods.
+    current_total: EmitterVolume,
+    previous_total: EmitterVolume,
+    period_start: Instant,
+}
+
+impl Default for EmitterStatsConfig {
+    fn default() -> Self {
+        EmitterStatsConfig {
+            top_k: 20,
+            period: Duration::from_secs(3600),
+            max_tracked: 100_000,
+        }
+    }
+}
+
+impl EmitterVolume {
+    fn add(&mut self, other: EmitterVolume) {
+        self.events += other.events;
+        self.bytes += other.bytes;
+    }
+}
+
+impl EmitterStats {
+    pub fn new(config: EmitterStatsConfig) -> Self {
+        let state = EmittersState {
+            current: HashMap::default(),
+            previous: HashMap::default(),
+            current_total: EmitterVolume::default(),
+            previous_total: EmitterVolume::default(),
+            period_start: Instant::now(),
+        };
+        EmitterStats {
+            config,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Counts the events in the receipts of an ingested block.
+    pub(crate) fn record_receipts(&self, receipts: &[v1alpha2::TransactionReceipt]) {
+        let mut state = self.state.lock().expect("emitter stats lock");
+        state.rotate(self.config.period);
+        for event in receipts.iter().flat_map(|receipt| &receipt.events) {
+            let volume = EmitterVolume {
+                events: 1,
+                bytes: event.encoded_len() as u64,
+            };
+            state.current_total.add(volume);
+            let address = match &event.from_address {
+                None => continue,
+                Some(address) => address.to_bytes(),
+            };
+            let tracked = state.current.len();
+            match state.current.get_mut(&address) {
+                Some(emitter) => emitter.add(volume),
+                None if tracked < self.config.max_tracked => {
+                    state.current.insert(address, volume);
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Returns the `limit` emitters with the largest volume of events over
+    /// the rolling window, largest first.
+    pub fn top(&self, limit: usize) -> Vec<(v1alpha2::FieldElement, EmitterVolume)> {
+        let mut state = self.state.lock().expect("emitter stats lock");
+        state.rotate(self.config.period);
+        let mut volumes = state.previous.clone();
+        for (address, volume) in &state.current {
+            volumes.entry(*address).or_default().add(*volume);
+        }
+        let mut volumes: Vec<_> = volumes.into_iter().collect();
+        volumes.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then(a.0.cmp(&b.0)));
+        volumes.truncate(limit);
+        volumes
+            .into_iter()
+            .map(|(address, volume)| (v1alpha2::FieldElement::from_bytes(&address), volume))
+            .collect()
+    }
+
+    /// Returns the volume of events of all emitters over the rolling window.
+    pub fn total(&self) -> EmitterVolume {
+        let mut state = self.state.lock().expect("emitter stats lock");
+        state.rotate(self.config.period);
+        let mut total = state.previous_total;
+        total.add(state.current_total);
+        total
+    }
+
+    /// Returns the number of emitters reported by default.
+    pub fn top_k(&self) -> usize {
+        self.config.top_k
+    }
+
+    /// Exports the volume of the top emitters as metrics, labeled by
+    /// address.
+    pub fn register_metrics(&self) {
+        let meter = o11y::meter("ingestion");
+        let events = meter
+            .u64_observable_gauge("top_emitter_events")
+            .with_description("Number of events of the top emitters over the rolling window")
+            .init();
+        let bytes = meter
+            .u64_observable_gauge("top_emitter_bytes")
+            .with_description("Size of the events of the top emitters over the rolling window")
+            .init();
+
+        let stats = self.clone();
+        let result = meter.register_callback(move |cx| {
+            for (address, volume) in stats.top(stats.config.top_k) {
+                let attributes = &[KeyValue::new("address", address.to_hex())];
+                events.observe(cx, volume.events, attributes);
+                bytes.observe(cx, volume.bytes, attributes);
+            }
+        });
+
+        if let Err(err) = result {
+            warn!(error = ?err, "failed to register emitter metrics");
+        }
+    }
+}
+
+impl EmittersState {
+    /// Starts a new period if the current one is over.
+    fn rotate(&mut self, period: Duration) {
+        let elapsed = self.period_start.elapsed();
+        if elapsed < period {
+            return;
+        }
+        if elapsed >= 2 * period {
+            // no events in the previous period either.
+            self.previous.clear();
+            self.previous_total = EmitterVolume::default();
+        } else {
+            self.previous = std::mem::take(&mut self.current);
+            self.previous_total = self.current_total;
+        }
+        self.current.clear();
+        self.current_total = EmitterVolume::default();
+        self.period_start = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use apibara_core::starknet::v1alpha2::{Event, FieldElement, TransactionReceipt};
+
+    use super::{EmitterStats, EmitterStatsConfig};
+
+    fn receipt(addresses: &[u64]) -> TransactionReceipt {
+        let events = addresses
+            .iter()
+            .map(|address| Event {
+                from_address: Some(FieldElement::from_u64(*address)),
+                ..Event::default()
+            })
+            .collect();
+        TransactionReceipt {
+            events,
+            ..TransactionReceipt::default()
+        }
+    }
+
+    #[test]
+    fn test_top_emitters() {
+        let stats = EmitterStats::new(EmitterStatsConfig {
+            max_tracked: 2,
+            ..EmitterStatsConfig::default()
+        });
+        stats.record_receipts(&[receipt(&[1, 2, 2]), receipt(&[2, 3])]);
+
+        let top = stats.top(10);
+        // address 3 is over the tracking limit.
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, FieldElement::from_u64(2));
+        assert_eq!(top[0].1.events, 3);
+        assert_eq!(top[1].0, FieldElement::from_u64(1));
+        assert_eq!(top[1].1.events, 1);
+        assert_eq!(stats.total().events, 5);
+
+        assert_eq!(stats.top(1).len(), 1);
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let stats = EmitterStats::new(EmitterStatsConfig {
+            period: Duration::from_millis(50),
+            ..EmitterStatsConfig::default()
+        });
+        stats.record_receipts(&[receipt(&[1])]);
+        std::thread::sleep(Duration::from_millis(60));
+        // the previous period is still in the window.
+        stats.record_receipts(&[receipt(&[1])]);
+        assert_eq!(stats.top(10)[0].1.events, 2);
+
+        std::thread::sleep(Duration::from_millis(110));
+        assert!(stats.top(10).is_empty());
+        assert_eq!(stats.total().events, 0);
+    }
+}
@@ -1,8 +1,11 @@
 //! Ingestion error.
-use apibara_node::db::libmdbx;
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::{libmdbx, MdbxErrorExt};
 use std::error::Error;
 
-use crate::core::{InvalidBlock, InvalidBlockHashSize};
+use crate::core::{GlobalBlockId, InvalidBlock, InvalidBlockHashSize};
+
+use super::retry::ErrorClass;
 
 #[derive(Debug, thiserror::Error)]
 pub enum BlockIngestionError {
@@ -26,9 +29,92 @@ pub enum BlockIngestionError {
     InvalidBlock(#[from] InvalidBlock),
     #[error("failed to publish an ingestion stream message")]
     IngestionStreamPublish,
+    #[error("quorum provider has block {found}, expected {expected}")]
+    QuorumMismatch {
+        expected: GlobalBlockId,
+        found: GlobalBlockId,
+    },
+    #[error("finalized block {0} is not part of the canonical chain")]
+    FinalizedBlockReorganized(GlobalBlockId),
+    #[error("provider chain id {found} doesn't match chain id {expected}")]
+    ChainIdMismatch {
+        expected: v1alpha2::FieldElement,
+        found: v1alpha2::FieldElement,
+    },
+    #[error("block {block_id} hash doesn't match its content, computed {computed}")]
+    BlockHashMismatch {
+        block_id: GlobalBlockId,
+        computed: v1alpha2::FieldElement,
+    },
+    #[error("failed to ingest block {block_number}")]
+    Block {
+        block_number: u64,
+        #[source]
+        source: Box<BlockIngestionError>,
+    },
 }
 
 impl BlockIngestionError {
+    /// Returns true if the error is caused by the database being full.
+    pub fn is_database_full(&self) -> bool {
+        matches!(self.cause(), BlockIngestionError::Database(err) if err.is_map_full())
+    }
+
+    /// Returns true if ingestion must not be retried after the error,
+    /// whatever the retry policy.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self.cause(), BlockIngestionError::ChainIdMismatch { .. })
+    }
+
+    /// Returns the number of the block that failed to ingest, if known.
+    pub fn block_number(&self) -> Option<u64> {
+        match self {
+            BlockIngestionError::Block { block_number, .. } => Some(*block_number),
+            _ => None,
+        }
+    }
+
+    /// Attributes the error to the given block, unless it's already
+    /// attributed to a block.
+    pub(crate) fn at_block(self, block_number: u64) -> Self {
+        match self {
+            BlockIngestionError::Block { .. } => self,
+            err => BlockIngestionError::Block {
+                block_number,
+                source: Box::new(err),
+            },
+        }
+    }
+
+    /// Returns the error without the block it's attributed to.
+    fn cause(&self) -> &BlockIngestionError {
+        match self {
+            BlockIngestionError::Block { source, .. } => source,
+            err => err,
+        }
+    }
+
+    /// Returns the class of the error, used by the retry policy.
+    pub fn class(&self) -> ErrorClass {
+        match self.cause() {
+            BlockIngestionError::Provider(_) => ErrorClass::Provider,
+            BlockIngestionError::Database(_)
+            | BlockIngestionError::InconsistentDatabase
+            | BlockIngestionError::BlockNotCanonical => ErrorClass::Database,
+            BlockIngestionError::MissingBlockHeader
+            | BlockIngestionError::MissingBlockHash
+            | BlockIngestionError::MalformedTransaction
+            | BlockIngestionError::InvalidBlockHash(_)
+            | BlockIngestionError::InvalidBlock(_)
+            | BlockIngestionError::FinalizedBlockReorganized(_)
+            | BlockIngestionError::BlockHashMismatch { .. }
+            | BlockIngestionError::ChainIdMismatch { .. } => ErrorClass::Data,
+            BlockIngestionError::QuorumMismatch { .. } => ErrorClass::Quorum,
+            BlockIngestionError::IngestionStreamPublish => ErrorClass::Internal,
+            BlockIngestionError::Block { source, .. } => source.class(),
+        }
+    }
+
     pub(crate) fn provider<E>(err: E) -> Self
     where
         E: Error + Send + Sync + 'static,
@@ -0,0 +1,341 @@
+//! Backfill finalized blocks from the events emitted in them.
+//!
+//! Events are fetched with `starknet_getEvents`, many blocks at the time,
+//! instead of requesting the receipt of each transaction. Blocks are stored
+//! with their header, the hashes of their transactions and receipts that
+//! only contain the matching events. They are marked as events only, so
+//! that streams with filters that need the missing data are rejected
+//! instead of silently skipping it. Full ingestion continues from the last
+//! backfilled block.
+use std::{collections::HashMap, sync::Arc};
+
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::libmdbx::EnvironmentKind;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{
+    core::{BlockHash, GlobalBlockId},
+    db::{write_raw_block, DatabaseStorage, PartialBlock, StorageReader, StorageWriter},
+    provider::{BlockId, EventFilter, Provider},
+};
+
+use super::{error::BlockIngestionError, lag::IngestionProgress};
+
+/// Events backfill configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventsBackfillConfig {
+    /// Events stored in backfilled blocks.
+    #[serde(skip)]
+    pub filter: EventFilter,
+    /// Number of events requested in each page.
+    pub chunk_size: u64,
+    /// Number of blocks whose events are requested together.
+    pub blocks_per_request: u64,
+}
+
+impl Default for EventsBackfillConfig {
+    fn default() -> Self {
+        EventsBackfillConfig {
+            filter: EventFilter::default(),
+            chunk_size: 1000,
+            blocks_per_request: 1000,
+        }
+    }
+}
+
+pub struct EventsBackfill<G: Provider + Send, E: EnvironmentKind> {
+    provider: Arc<G>,
+    storage: DatabaseStorage<E>,
+    config: EventsBackfillConfig,
+    progress: IngestionProgress,
+    rpc_concurrency: usize,
+    starting_block: u64,
+}
+
+/// Events emitted in a block, grouped by transaction hash.
+#[derive(Debug)]
+struct BlockEvents {
+    block_hash: BlockHash,
+    transactions: HashMap<[u8; 32], Vec<v1alpha2::Event>>,
+}
+
+impl<G, E> EventsBackfill<G, E>
+where
+    G: Provider + Send,
+    E: EnvironmentKind,
+{
+    pub fn new(
+        provider: Arc<G>,
+        storage: DatabaseStorage<E>,
+        config: EventsBackfillConfig,
+        progress: IngestionProgress,
+    ) -> Self {
+        EventsBackfill {
+            provider,
+            storage,
+            config,
+            progress,
+            rpc_concurrency: 16,
+            starting_block: 0,
+        }
+    }
+
+    /// Maximum number of blocks requested at the same time.
+    pub fn with_rpc_concurrency(mut self, rpc_concurrency: usize) -> Self {
+        self.rpc_concurrency = usize::max(rpc_concurrency, 1);
+        self
+    }
+
+    /// Don't backfill blocks before the given block, if the node has no
+    /// blocks yet.
+    pub fn with_starting_block(mut self, starting_block: u64) -> Self {
+        self.starting_block = starting_block;
+        self
+    }
+
+    /// Backfill blocks until the first block that is not finalized.
+    ///
+    /// Returns the last backfilled block, if any.
+    pub async fn start(
+        self,
+        ct: CancellationToken,
+    ) -> Result<Option<GlobalBlockId>, BlockIngestionError> {
+        let head = self
+            .provider
+            .get_head()
+            .await
+            .map_err(BlockIngestionError::provider)?;
+        self.progress.provider_head(head.number());
+
+        let mut previous = self.storage.highest_accepted_block()?;
+        info!(
+            local = ?previous,
+            head = %head,
+            "backfill finalized blocks from events"
+        );
+
+        loop {
+            if ct.is_cancelled() {
+                return Ok(previous);
+            }
+
+            let from_block = previous
+                .map(|id| id.number() + 1)
+                .unwrap_or(self.starting_block);
+            if from_block > head.number() {
+                break;
+            }
+            let to_block = u64::min(
+                from_block + u64::max(self.config.blocks_per_request, 1) - 1,
+                head.number(),
+            );
+
+            let mut events = self.fetch_events(from_block, to_block).await?;
+            let mut blocks = stream::iter(from_block..=to_block)
+                .map(|number| self.fetch_block(number))
+                .buffered(self.rpc_concurrency);
+
+            while let Some(block) = blocks.try_next().await? {
+                let block = match block {
+                    None => {
+                        info!(block_id = ?previous, "backfill reached non finalized blocks");
+                        return Ok(previous);
+                    }
+                    Some(block) => block,
+                };
+                let header = block
+                    .header
+                    .as_ref()
+                    .ok_or(BlockIngestionError::MissingBlockHeader)?;
+                let block_id = GlobalBlockId::from_block_header(header)?;
+
+                if let Some(previous) = previous {
+                    let parent_hash: BlockHash = header
+                        .parent_block_hash
+                        .as_ref()
+                        .ok_or(BlockIngestionError::MissingBlockHash)?
+                        .into();
+                    if parent_hash != *previous.hash() {
+                        warn!(block_id = %block_id, "backfilled block doesn't extend local chain");
+                        return Ok(Some(previous));
+                    }
+                }
+
+                let block_events = events.remove(&block_id.number());
+                if let Some(ref block_events) = block_events {
+                    // the block changed between requests, the provider is
+                    // not consistent.
+                    if block_events.block_hash != *block_id.hash() {
+                        warn!(block_id = %block_id, "events belong to a different block");
+                        return Ok(previous);
+                    }
+                }
+                let block = with_events(block, block_events);
+
+                let mut txn = self.storage.begin_txn()?;
+                write_raw_block(&mut txn, &block_id, block)?;
+                let partial = PartialBlock {
+                    events_only: true,
+                    ..PartialBlock::default()
+                };
+                txn.write_partial_block(&block_id, &partial)?;
+                txn.commit()?;
+
+                self.progress.block_ingested(block_id.number());
+                previous = Some(block_id);
+            }
+
+            info!(block_id = ?previous, "events backfill progress");
+        }
+
+        Ok(previous)
+    }
+
+    /// Returns the events emitted between the two blocks, by block number.
+    async fn fetch_events(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<HashMap<u64, BlockEvents>, BlockIngestionError> {
+        let mut events: HashMap<u64, BlockEvents> = HashMap::default();
+        let mut continuation_token = None;
+        loop {
+            let page = self
+                .provider
+                .get_events(
+                    &self.config.filter,
+                    from_block,
+                    to_block,
+                    continuation_token,
+                    self.config.chunk_size,
+                )
+                .await
+                .map_err(BlockIngestionError::provider)?;
+
+            for emitted in page.events {
+                let block_events =
+                    events
+                        .entry(emitted.block_id.number())
+                        .or_insert_with(|| BlockEvents {
+                            block_hash: *emitted.block_id.hash(),
+                            transactions: HashMap::default(),
+                        });
+                block_events
+                    .transactions
+                    .entry(emitted.transaction_hash.to_bytes())
+                    .or_default()
+                    .push(emitted.event);
+            }
+
+            continuation_token = page.continuation_token;
+            if continuation_token.is_none() {
+                return Ok(events);
+            }
+        }
+    }
+
+    /// Returns the block without events, or `None` if it's not finalized.
+    async fn fetch_block(
+        &self,
+        number: u64,
+    ) -> Result<Option<v1alpha2::RawBlock>, BlockIngestionError> {
+        let (status, header, hashes) = self
+            .provider
+            .get_block_with_tx_hashes(&BlockId::Number(number))
+            .await
+            .map_err(BlockIngestionError::provider)?;
+
+        if !status.is_finalized() {
+            return Ok(None);
+        }
+
+        let transactions = hashes
+            .into_iter()
+            .map(|hash| v1alpha2::Transaction {
+                meta: Some(v1alpha2::TransactionMeta {
+                    hash: Some(hash),
+                    ..v1alpha2::TransactionMeta::default()
+                }),
+                transaction: None,
+            })
+            .collect();
+
+        Ok(Some(v1alpha2::RawBlock {
+            status: status as i32,
+            header: Some(header),
+            transactions,
+            ..v1alpha2::RawBlock::default()
+        }))
+    }
+}
+
+/// Adds one receipt with the transaction's events for each transaction.
+fn with_events(mut block: v1alpha2::RawBlock, events: Option<BlockEvents>) -> v1alpha2::RawBlock {
+    let mut events = events.map(|events| events.transactions).unwrap_or_default();
+    block.receipts = block
+        .transactions
+        .iter()
+        .enumerate()
+        .map(|(index, tx)| {
+            let transaction_hash = tx.meta.as_ref().and_then(|meta| meta.hash.clone());
+            let events = transaction_hash
+                .as_ref()
+                .and_then(|hash| events.remove(&hash.to_bytes()))
+                .unwrap_or_default();
+            v1alpha2::TransactionReceipt {
+                transaction_hash,
+                transaction_index: index as u64,
+                events,
+                ..v1alpha2::TransactionReceipt::default()
+            }
+        })
+        .collect();
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use apibara_core::starknet::v1alpha2;
+
+    use crate::core::BlockHash;
+
+    use super::{with_events, BlockEvents};
+
+    #[test]
+    fn test_with_events() {
+        let transaction = |hash: u64| v1alpha2::Transaction {
+            meta: Some(v1alpha2::TransactionMeta {
+                hash: Some(v1alpha2::FieldElement::from_u64(hash)),
+                ..v1alpha2::TransactionMeta::default()
+            }),
+            transaction: None,
+        };
+        let event = v1alpha2::Event {
+            from_address: Some(v1alpha2::FieldElement::from_u64(42)),
+            ..v1alpha2::Event::default()
+        };
+        let block = v1alpha2::RawBlock {
+            transactions: vec![transaction(1), transaction(2)],
+            ..v1alpha2::RawBlock::default()
+        };
+        let events = BlockEvents {
+            block_hash: BlockHash::zero(),
+            transactions: HashMap::from([(
+                v1alpha2::FieldElement::from_u64(2).to_bytes(),
+                vec![event.clone()],
+            )]),
+        };
+
+        let block = with_events(block, Some(events));
+        // every transaction has a receipt, even without events.
+        assert_eq!(block.receipts.len(), 2);
+        assert!(block.receipts[0].events.is_empty());
+        assert_eq!(block.receipts[1].transaction_index, 1);
+        assert_eq!(block.receipts[1].events, vec![event]);
+    }
+}
@@ -3,6 +3,7 @@ use std::{sync::Arc, time::Duration};
 
 use apibara_core::starknet::v1alpha2;
 use apibara_node::db::libmdbx::EnvironmentKind;
+use apibara_node::o11y::set_error_context;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
@@ -10,32 +11,40 @@ use crate::{
     core::GlobalBlockId,
     db::{DatabaseStorage, StorageWriter},
     ingestion::accepted::AcceptedBlockIngestion,
-    provider::{BlockId, Provider, ProviderError},
+    provider::{BlockId, ProviderError},
 };
 
 use super::{
-    config::BlockIngestionConfig, downloader::Downloader, error::BlockIngestionError,
+    config::BlockIngestionConfig,
+    control::IngestionControl,
+    downloader::{DownloadedBlock, Downloader},
+    error::BlockIngestionError,
+    lag::IngestionProgress,
+    source::IngestionSource,
+    stages::{stage_metrics, IngestionStage},
     subscription::IngestionStreamPublisher,
 };
 
-pub struct FinalizedBlockIngestion<G: Provider + Send, E: EnvironmentKind> {
+pub struct FinalizedBlockIngestion<G: IngestionSource, E: EnvironmentKind> {
     config: BlockIngestionConfig,
     provider: Arc<G>,
-    downloader: Downloader<G>,
+    downloader: Arc<Downloader<G>>,
     storage: DatabaseStorage<E>,
     publisher: IngestionStreamPublisher,
+    progress: IngestionProgress,
+    control: IngestionControl,
 }
 
 #[derive(Debug)]
-enum IngestResult {
-    Ingested(GlobalBlockId),
+enum FetchResult {
+    Downloaded(Box<DownloadedBlock>),
     TransitionToAccepted(GlobalBlockId),
     RetryWithDelay(Duration),
 }
 
 impl<G, E> FinalizedBlockIngestion<G, E>
 where
-    G: Provider + Send,
+    G: IngestionSource,
     E: EnvironmentKind,
 {
     pub fn new(
@@ -43,14 +52,27 @@ where
         storage: DatabaseStorage<E>,
         config: BlockIngestionConfig,
         publisher: IngestionStreamPublisher,
+        progress: IngestionProgress,
+        control: IngestionControl,
     ) -> Self {
-        let downloader = Downloader::new(provider.clone(), config.rpc_concurrency);
+        let downloader = Downloader::new(provider.clone(), config.rpc_concurrency)
+            .with_traces(config.ingest_traces)
+            .with_classes(config.ingest_classes)
+            .with_headers_only(config.headers_only)
+            .with_missing_data_tolerance(config.tolerate_missing_data)
+            .with_journal(config.journal.clone())
+            .with_block_hash_validation(config.block_hash_validation)
+            .with_l1_message_resolver(config.l1_message_resolver.clone())
+            .with_emitter_stats(config.emitter_stats.clone());
+        let downloader = Arc::new(downloader);
         FinalizedBlockIngestion {
             config,
             provider,
             storage,
             downloader,
             publisher,
+            progress,
+            control,
         }
     }
 
@@ -72,22 +94,57 @@ where
         txn.commit()?;
 
         let mut current_block = latest_indexed;
+        self.progress.node_head(current_block.number());
+        // the head is only needed to report the lag, don't fail if it's
+        // not available.
+        if let Ok(head) = self.provider.get_head().await {
+            self.progress.provider_head(head.number());
+        }
 
+        let mut next = self
+            .fetch_block_by_number(current_block.number() + 1)
+            .await?;
         let latest_indexed = loop {
+            // the next block is downloaded, but not written while paused.
+            self.control.wait_until_resumed(&ct).await;
+            if let Some(disk_usage) = &self.config.disk_usage {
+                disk_usage.throttle(&ct).await;
+            }
             if ct.is_cancelled() {
                 return Ok(());
             }
 
-            let next_block_number = current_block.number() + 1;
-            match self.ingest_block_by_number(next_block_number).await? {
-                IngestResult::Ingested(global_id) => {
+            match next {
+                FetchResult::Downloaded(block) => {
+                    let global_id = block.global_id;
+                    // download the next block in a separate task, so that
+                    // it makes progress while this one is written.
+                    let fetch = tokio::spawn(fetch_block_by_number(
+                        self.provider.clone(),
+                        self.downloader.clone(),
+                        global_id.number() + 1,
+                    ));
+                    if let Err(err) = self.write_block(*block) {
+                        fetch.abort();
+                        return Err(err);
+                    }
                     self.publisher.publish_finalized(global_id)?;
+                    self.progress.block_ingested(global_id.number());
                     current_block = global_id;
+                    next = match fetch.await {
+                        Ok(fetched) => fetched?,
+                        // the task is only aborted above, so it can only
+                        // fail by panicking.
+                        Err(err) => std::panic::resume_unwind(err.into_panic()),
+                    };
                 }
-                IngestResult::RetryWithDelay(delay) => {
+                FetchResult::RetryWithDelay(delay) => {
                     tokio::time::sleep(delay).await;
+                    next = self
+                        .fetch_block_by_number(current_block.number() + 1)
+                        .await?;
                 }
-                IngestResult::TransitionToAccepted(global_id) => {
+                FetchResult::TransitionToAccepted(global_id) => {
                     info!(
                         block_id = %global_id,
                         "transition to ingest accepted"
@@ -97,47 +154,70 @@ where
             }
         };
 
-        AcceptedBlockIngestion::new(self.provider, self.storage, self.config, self.publisher)
-            .start(latest_indexed, ct)
-            .await
+        AcceptedBlockIngestion::new(
+            self.provider,
+            self.storage,
+            self.config,
+            self.publisher,
+            self.progress,
+            self.control,
+        )
+        .start(latest_indexed, ct)
+        .await
     }
 
-    #[tracing::instrument(skip(self), err(Debug))]
-    async fn ingest_block_by_number(
-        &self,
-        number: u64,
-    ) -> Result<IngestResult, BlockIngestionError> {
-        debug!(
-            block_number = %number,
-            "ingest block by number"
-        );
-        let block_id = BlockId::Number(number);
-        let (status, header, body) = match self.provider.get_block(&block_id).await {
-            Ok(result) => result,
-            Err(err) if err.is_block_not_found() => {
-                return Ok(IngestResult::RetryWithDelay(Duration::from_secs(60)))
-            }
-            Err(err) => return Err(BlockIngestionError::provider(err)),
-        };
-
-        let global_id = GlobalBlockId::from_block_header(&header)?;
-
-        if !status.is_finalized() {
-            return Ok(IngestResult::TransitionToAccepted(global_id));
-        }
+    async fn fetch_block_by_number(&self, number: u64) -> Result<FetchResult, BlockIngestionError> {
+        fetch_block_by_number(self.provider.clone(), self.downloader.clone(), number).await
+    }
 
+    fn write_block(&self, block: DownloadedBlock) -> Result<(), BlockIngestionError> {
+        let global_id = block.global_id;
         let mut txn = self.storage.begin_txn()?;
         self.downloader
-            .finish_ingesting_block(&global_id, status, header, body, &mut txn)
-            .await?;
+            .write_block(block, &mut txn)
+            .map_err(|err| err.at_block(global_id.number()))?;
         txn.extend_canonical_chain(&global_id)?;
-        txn.commit()?;
+        stage_metrics().record(IngestionStage::Commit, || txn.commit())?;
 
         info!(
             block_id = %global_id,
             "ingested finalized block"
         );
 
-        Ok(IngestResult::Ingested(global_id))
+        Ok(())
+    }
+}
+
+#[tracing::instrument(skip(provider, downloader), err(Debug))]
+async fn fetch_block_by_number<G: IngestionSource>(
+    provider: Arc<G>,
+    downloader: Arc<Downloader<G>>,
+    number: u64,
+) -> Result<FetchResult, BlockIngestionError> {
+    debug!(block_number = number, "fetch block by number");
+    set_error_context("block_number", number);
+    let block_id = BlockId::Number(number);
+    let block = stage_metrics()
+        .time(IngestionStage::FetchBlock, provider.get_block(&block_id))
+        .await;
+    let (status, header, body) = match block {
+        Ok(result) => result,
+        Err(err) if err.is_block_not_found() => {
+            return Ok(FetchResult::RetryWithDelay(Duration::from_secs(60)))
+        }
+        Err(err) => return Err(BlockIngestionError::provider(err).at_block(number)),
+    };
+
+    let global_id = GlobalBlockId::from_block_header(&header)
+        .map_err(|err| BlockIngestionError::from(err).at_block(number))?;
+
+    if !status.is_finalized() {
+        return Ok(FetchResult::TransitionToAccepted(global_id));
     }
+
+    let block = downloader
+        .download_block(global_id, status, header, body)
+        .await
+        .map_err(|err| err.at_block(number))?;
+    Ok(FetchResult::Downloaded(Box::new(block)))
 }
@@ -0,0 +1,123 @@
+//! Subscribe to new heads over the provider WebSocket endpoint.
+//!
+//! Notifications are only used to wake up ingestion, block data is still
+//! fetched with the RPC provider. If the subscription fails, ingestion falls
+//! back to polling the head.
+use std::{sync::Arc, time::Duration};
+
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::sync::Notify;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// JSON-RPC method used to subscribe to new heads.
+const SUBSCRIBE_METHOD: &str = "starknet_subscribeNewHeads";
+
+/// Delay before the first reconnection attempt.
+const MIN_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximum delay between reconnection attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum HeadSubscriptionError {
+    #[error("websocket error")]
+    WebSocket(#[source] Box<tokio_tungstenite::tungstenite::Error>),
+    #[error("failed to parse message")]
+    Json(#[from] serde_json::Error),
+    #[error("subscription failed: {0}")]
+    Rpc(String),
+    #[error("connection closed")]
+    Closed,
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for HeadSubscriptionError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        HeadSubscriptionError::WebSocket(Box::new(err))
+    }
+}
+
+/// A subscription that signals new heads.
+pub struct HeadSubscription {
+    url: Url,
+    notify: Arc<Notify>,
+}
+
+impl HeadSubscription {
+    pub fn new(url: Url) -> Self {
+        HeadSubscription {
+            url,
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Returns the notifier signalled on each new head.
+    pub fn notify(&self) -> Arc<Notify> {
+        self.notify.clone()
+    }
+
+    /// Keeps the subscription alive, reconnecting on errors, until the token
+    /// is cancelled.
+    pub async fn start(self, ct: CancellationToken) {
+        let mut retry_delay = MIN_RETRY_DELAY;
+        loop {
+            match self.subscribe(ct.clone()).await {
+                Ok(()) => return,
+                Err(err) => {
+                    warn!(error = ?err, "head subscription failed. polling head instead");
+                }
+            }
+
+            tokio::select! {
+                _ = ct.cancelled() => return,
+                _ = tokio::time::sleep(retry_delay) => {},
+            }
+            retry_delay = Duration::min(retry_delay * 2, MAX_RETRY_DELAY);
+        }
+    }
+
+    async fn subscribe(&self, ct: CancellationToken) -> Result<(), HeadSubscriptionError> {
+        let (mut ws, _) = connect_async(self.url.as_str()).await?;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": SUBSCRIBE_METHOD,
+            "params": [],
+        });
+        ws.send(Message::Text(request.to_string())).await?;
+        info!(url = %self.url, "subscribed to new heads");
+
+        loop {
+            tokio::select! {
+                _ = ct.cancelled() => return Ok(()),
+                message = ws.next() => {
+                    match message {
+                        None | Some(Ok(Message::Close(_))) => {
+                            return Err(HeadSubscriptionError::Closed)
+                        }
+                        Some(Ok(Message::Text(text))) => self.handle_message(&text)?,
+                        Some(Ok(Message::Ping(data))) => ws.send(Message::Pong(data)).await?,
+                        Some(Ok(_)) => {}
+                        Some(Err(err)) => return Err(err.into()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_message(&self, text: &str) -> Result<(), HeadSubscriptionError> {
+        let message: serde_json::Value = serde_json::from_str(text)?;
+        if let Some(error) = message.get("error") {
+            return Err(HeadSubscriptionError::Rpc(error.to_string()));
+        }
+        // the subscription response has an id, notifications have a method.
+        if message.get("method").is_some() {
+            debug!("received new head notification");
+            self.notify.notify_one();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Fill gaps in the canonical chain.
+//!
+//! Crashes and older versions can leave gaps in the canonical chain below
+//! the finalized block. Finalized streams stop at the first gap, so the
+//! missing blocks are fetched from the provider before the node starts
+//! serving streams.
+
+use std::sync::Arc;
+
+use apibara_node::db::libmdbx::EnvironmentKind;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{
+    core::{BlockHash, GlobalBlockId},
+    db::{DatabaseStorage, StorageReader, StorageWriter},
+    provider::BlockId,
+};
+
+use super::{
+    config::BlockIngestionConfig,
+    downloader::Downloader,
+    source::IngestionSource,
+    stages::{stage_metrics, IngestionStage},
+    BlockIngestionError,
+};
+
+/// Ingests the blocks missing from the canonical chain below the finalized
+/// block.
+pub struct GapHealer<G: IngestionSource, E: EnvironmentKind> {
+    provider: Arc<G>,
+    downloader: Downloader<G>,
+    storage: DatabaseStorage<E>,
+}
+
+impl<G, E> GapHealer<G, E>
+where
+    G: IngestionSource,
+    E: EnvironmentKind,
+{
+    pub fn new(
+        provider: Arc<G>,
+        storage: DatabaseStorage<E>,
+        config: &BlockIngestionConfig,
+    ) -> Self {
+        let downloader = Downloader::new(provider.clone(), config.rpc_concurrency)
+            .with_traces(config.ingest_traces)
+            .with_classes(config.ingest_classes)
+            .with_headers_only(config.headers_only)
+            .with_missing_data_tolerance(config.tolerate_missing_data)
+            .with_journal(config.journal.clone())
+            .with_block_hash_validation(config.block_hash_validation)
+            .with_l1_message_resolver(config.l1_message_resolver.clone())
+            .with_emitter_stats(config.emitter_stats.clone());
+        GapHealer {
+            provider,
+            downloader,
+            storage,
+        }
+    }
+
+    /// Ingests all missing blocks below the finalized block.
+    ///
+    /// Returns the number of blocks ingested.
+    pub async fn heal(&self, ct: CancellationToken) -> Result<u64, BlockIngestionError> {
+        let finalized = match self.storage.highest_finalized_block()? {
+            None => return Ok(0),
+            Some(finalized) => finalized,
+        };
+
+        let gaps = self.storage.canonical_chain_gaps(finalized.number())?;
+        if gaps.is_empty() {
+            return Ok(0);
+        }
+
+        warn!(gaps = ?gaps, "canonical chain has gaps below finalized block");
+
+        let mut healed = 0;
+        for gap in gaps {
+            let mut previous = None;
+            for number in gap.clone() {
+                if ct.is_cancelled() {
+                    return Ok(healed);
+                }
+                previous = Some(self.ingest_missing_block(number).await?);
+                healed += 1;
+            }
+
+            // the block after the gap must extend the ingested blocks.
+            if let Some(previous) = previous {
+                self.check_extends(gap.end, &previous)?;
+            }
+            info!(from = %gap.start, to = %gap.end, "filled canonical chain gap");
+        }
+
+        Ok(healed)
+    }
+
+    /// Ingests the block and adds it to the canonical chain.
+    async fn ingest_missing_block(
+        &self,
+        number: u64,
+    ) -> Result<GlobalBlockId, BlockIngestionError> {
+        let (status, header, body) = stage_metrics()
+            .time(
+                IngestionStage::FetchBlock,
+                self.provider.get_block(&BlockId::Number(number)),
+            )
+            .await
+            .map_err(BlockIngestionError::provider)?;
+
+        let block_id = GlobalBlockId::from_block_header(&header)?;
+        let parent_hash: BlockHash = header
+            .parent_block_hash
+            .as_ref()
+            .ok_or(BlockIngestionError::MissingBlockHash)?
+            .into();
+
+        // gaps always follow a canonical block.
+        let parent = self
+            .storage
+            .canonical_block_id(number - 1)?
+            .ok_or(BlockIngestionError::InconsistentDatabase)?;
+        if *parent.hash() != parent_hash {
+            return Err(BlockIngestionError::InconsistentDatabase);
+        }
+
+        let mut txn = self.storage.begin_txn()?;
+        self.downloader
+            .finish_ingesting_block(&block_id, status, header, body, &mut txn)
+            .await?;
+        txn.extend_canonical_chain(&block_id)?;
+        stage_metrics().record(IngestionStage::Commit, || txn.commit())?;
+
+        Ok(block_id)
+    }
+
+    /// Checks that the canonical block `number` has `parent` as parent.
+    fn check_extends(
+        &self,
+        number: u64,
+        parent: &GlobalBlockId,
+    ) -> Result<(), BlockIngestionError> {
+        let block_id = self
+            .storage
+            .canonical_block_id(number)?
+            .ok_or(BlockIngestionError::InconsistentDatabase)?;
+        let header = self
+            .storage
+            .read_header(&block_id)?
+            .ok_or(BlockIngestionError::InconsistentDatabase)?;
+        let parent_hash: BlockHash = header
+            .parent_block_hash
+            .as_ref()
+            .ok_or(BlockIngestionError::MissingBlockHash)?
+            .into();
+        if *parent.hash() != parent_hash {
+            return Err(BlockIngestionError::InconsistentDatabase);
+        }
+        Ok(())
+    }
+}
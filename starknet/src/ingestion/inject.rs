@@ -0,0 +1,108 @@
+//! Inject synthetic chain reorganizations and finality changes.
+//!
+//! Only used to test how streams and clients react to invalidated and
+//! finalized data, without waiting for the chain to reorganize. Events are
+//! applied by accepted ingestion between blocks.
+use apibara_core::starknet::v1alpha2;
+use apibara_node::db::libmdbx::EnvironmentKind;
+use tracing::info;
+
+use crate::{
+    core::GlobalBlockId,
+    db::{DatabaseStorage, StorageReader, StorageWriter},
+};
+
+use super::{error::BlockIngestionError, subscription::IngestionStreamPublisher};
+
+/// An event injected into ingestion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectedEvent {
+    /// Remove the `depth` most recent blocks from the canonical chain.
+    ///
+    /// The blocks are still canonical for the provider, so they're ingested
+    /// again.
+    Reorg { depth: u64 },
+    /// Mark the canonical blocks up to `block_number` as finalized.
+    Finalize { block_number: u64 },
+}
+
+/// Removes up to `depth` blocks from the canonical chain ending at `tip`,
+/// without removing finalized blocks.
+///
+/// Returns the new tip of the canonical chain.
+pub(super) fn inject_reorg<E>(
+    storage: &DatabaseStorage<E>,
+    publisher: &IngestionStreamPublisher,
+    tip: GlobalBlockId,
+    finalized: Option<GlobalBlockId>,
+    depth: u64,
+) -> Result<GlobalBlockId, BlockIngestionError>
+where
+    E: EnvironmentKind,
+{
+    let lowest = finalized.map(|b| b.number()).unwrap_or(0);
+    let new_tip_number = u64::max(tip.number().saturating_sub(depth), lowest);
+    if new_tip_number == tip.number() {
+        return Ok(tip);
+    }
+
+    let new_tip = storage
+        .canonical_block_id(new_tip_number)?
+        .ok_or(BlockIngestionError::InconsistentDatabase)?;
+
+    let mut txn = storage.begin_txn()?;
+    for number in (new_tip_number + 1..=tip.number()).rev() {
+        let block_id = storage
+            .canonical_block_id(number)?
+            .ok_or(BlockIngestionError::InconsistentDatabase)?;
+        txn.reject_block_from_canonical_chain(&block_id)?;
+    }
+    txn.commit()?;
+
+    info!(
+        new_tip = %new_tip,
+        removed = %(tip.number() - new_tip_number),
+        "injected chain reorganization"
+    );
+
+    publisher.publish_invalidate(new_tip)?;
+    Ok(new_tip)
+}
+
+/// Marks the canonical blocks after `finalized` and up to `block_number`
+/// as finalized, without going past `tip`.
+///
+/// Returns the new finalized block.
+pub(super) fn inject_finality<E>(
+    storage: &DatabaseStorage<E>,
+    publisher: &IngestionStreamPublisher,
+    tip: GlobalBlockId,
+    finalized: Option<GlobalBlockId>,
+    block_number: u64,
+) -> Result<Option<GlobalBlockId>, BlockIngestionError>
+where
+    E: EnvironmentKind,
+{
+    let first = finalized.map(|b| b.number() + 1).unwrap_or(0);
+    let last = u64::min(block_number, tip.number());
+    if first > last {
+        return Ok(finalized);
+    }
+
+    let mut new_finalized = finalized;
+    let mut txn = storage.begin_txn()?;
+    for number in first..=last {
+        let block_id = storage
+            .canonical_block_id(number)?
+            .ok_or(BlockIngestionError::InconsistentDatabase)?;
+        txn.write_status(&block_id, v1alpha2::BlockStatus::AcceptedOnL1)?;
+        new_finalized = Some(block_id);
+    }
+    txn.commit()?;
+
+    if let Some(new_finalized) = new_finalized {
+        info!(finalized = %new_finalized, "injected finalized block");
+        publisher.publish_finalized(new_finalized)?;
+    }
+    Ok(new_finalized)
+}
@@ -0,0 +1,379 @@
+//! Journal of blocks that repeatedly fail to ingest.
+//!
+//! Ingestion errors are retried according to the retry policy. The block
+//! ingestion was working on when an error happened is recorded in the
+//! journal, together with the error and the number of attempts.
+//!
+//! Once a block failed `journal_max_attempts` times because of the provider,
+//! it's marked as degraded: it's stored without the data that fails to
+//! download (state update, traces and classes) and ingestion continues with
+//! the following blocks. Operators retry or skip journaled blocks through
+//! the admin API.
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::{
+    db::{DatabaseStorage, JournalEntry, StorageReader, StorageWriter},
+    provider::BlockId,
+};
+
+use super::{
+    config::BlockIngestionConfig, downloader::Downloader, retry::ErrorClass,
+    source::IngestionSource, BlockIngestionError,
+};
+
+/// Shared handle to the ingestion journal.
+///
+/// The journal is stored in the database, the handle keeps a copy of it for
+/// the admin API.
+#[derive(Debug, Clone, Default)]
+pub struct IngestionJournal {
+    entries: Arc<Mutex<BTreeMap<u64, JournalEntry>>>,
+    requests: Arc<Mutex<VecDeque<JournalRequest>>>,
+    requested: Arc<Notify>,
+}
+
+/// An operator request about a journaled block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalRequest {
+    /// Ingest the block again. Degraded blocks are ingested with all their
+    /// data.
+    Retry(u64),
+    /// Stop retrying the block. Failing blocks are stored degraded, degraded
+    /// blocks are kept as they are.
+    Skip(u64),
+}
+
+impl IngestionJournal {
+    /// Returns the journaled blocks, by block number.
+    pub fn entries(&self) -> Vec<(u64, JournalEntry)> {
+        self.lock()
+            .iter()
+            .map(|(number, entry)| (*number, entry.clone()))
+            .collect()
+    }
+
+    /// Queues a request to retry the given block.
+    ///
+    /// Returns false if the block is not in the journal.
+    pub fn retry(&self, number: u64) -> bool {
+        self.request(JournalRequest::Retry(number))
+    }
+
+    /// Queues a request to skip the given block.
+    ///
+    /// Returns false if the block is not in the journal.
+    pub fn skip(&self, number: u64) -> bool {
+        self.request(JournalRequest::Skip(number))
+    }
+
+    /// Returns true if the block must be stored without the data that fails
+    /// to download.
+    pub(crate) fn is_degraded(&self, number: u64) -> bool {
+        matches!(self.lock().get(&number), Some(entry) if entry.degraded)
+    }
+
+    /// Loads the journal from storage.
+    pub(crate) fn load<E: EnvironmentKind>(
+        &self,
+        storage: &DatabaseStorage<E>,
+    ) -> Result<(), libmdbx::Error> {
+        let entries = storage.journal_entries()?;
+        *self.lock() = entries.into_iter().collect();
+        Ok(())
+    }
+
+    /// Records that ingesting the given block failed with `err`.
+    ///
+    /// Entries of blocks before `number` that are not degraded were ingested
+    /// since they failed, so they're removed.
+    pub(crate) fn record_failure<E: EnvironmentKind>(
+        &self,
+        storage: &DatabaseStorage<E>,
+        number: u64,
+        err: &BlockIngestionError,
+        max_attempts: Option<u32>,
+    ) -> Result<JournalEntry, libmdbx::Error> {
+        self.clear_recovered(storage, number)?;
+
+        let previous = self.lock().get(&number).cloned();
+        let entry = next_entry(previous, err, max_attempts, unix_now());
+        if entry.degraded {
+            warn!(
+                block_number = number,
+                attempts = %entry.attempts,
+                "ingesting block without the data that fails to download"
+            );
+        }
+        self.write(storage, number, Some(entry.clone()))?;
+        Ok(entry)
+    }
+
+    /// Removes the entries of blocks before `end` that are not degraded.
+    pub(crate) fn clear_recovered<E: EnvironmentKind>(
+        &self,
+        storage: &DatabaseStorage<E>,
+        end: u64,
+    ) -> Result<(), libmdbx::Error> {
+        let recovered: Vec<_> = self
+            .lock()
+            .range(..end)
+            .filter(|(_, entry)| !entry.degraded)
+            .map(|(number, _)| *number)
+            .collect();
+        for number in recovered {
+            self.write(storage, number, None)?;
+        }
+        Ok(())
+    }
+
+    /// Stores the entry of the given block, or removes it if `None`.
+    pub(crate) fn write<E: EnvironmentKind>(
+        &self,
+        storage: &DatabaseStorage<E>,
+        number: u64,
+        entry: Option<JournalEntry>,
+    ) -> Result<(), libmdbx::Error> {
+        match entry {
+            None => {
+                storage.remove_journal_entry(number)?;
+                self.lock().remove(&number);
+            }
+            Some(entry) => {
+                storage.write_journal_entry(number, &entry)?;
+                self.lock().insert(number, entry);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the queued requests.
+    ///
+    /// Returns true if a failing block was retried or skipped, in which case
+    /// ingestion should be retried without waiting.
+    pub(crate) async fn apply_requests<G, E>(
+        &self,
+        provider: &Arc<G>,
+        storage: &DatabaseStorage<E>,
+        config: &BlockIngestionConfig,
+    ) -> bool
+    where
+        G: IngestionSource,
+        E: EnvironmentKind,
+    {
+        let mut retry_now = false;
+        for request in self.take_requests() {
+            match self.apply_request(request, provider, storage, config).await {
+                Ok(retry) => retry_now |= retry,
+                Err(err) => {
+                    warn!(request = ?request, error = ?err, "failed to apply ingestion journal request")
+                }
+            }
+        }
+        retry_now
+    }
+
+    async fn apply_request<G, E>(
+        &self,
+        request: JournalRequest,
+        provider: &Arc<G>,
+        storage: &DatabaseStorage<E>,
+        config: &BlockIngestionConfig,
+    ) -> Result<bool, BlockIngestionError>
+    where
+        G: IngestionSource,
+        E: EnvironmentKind,
+    {
+        let number = match request {
+            JournalRequest::Retry(number) | JournalRequest::Skip(number) => number,
+        };
+        let mut entry = match self.lock().get(&number).cloned() {
+            None => return Ok(false),
+            Some(entry) => entry,
+        };
+
+        match request {
+            // failing blocks are retried by ingestion, with a fresh count of
+            // attempts.
+            JournalRequest::Retry(_) if !entry.degraded => {
+                self.write(storage, number, None)?;
+                Ok(true)
+            }
+            JournalRequest::Retry(_) => {
+                match reingest_block(number, provider, storage, config).await {
+                    Ok(()) => {
+                        info!(
+                            block_number = number,
+                            "ingested degraded block with all its data"
+                        );
+                        self.write(storage, number, None)?;
+                    }
+                    Err(err) => {
+                        self.record_failure(storage, number, &err, config.journal_max_attempts)?;
+                    }
+                }
+                Ok(false)
+            }
+            JournalRequest::Skip(_) if !entry.degraded => {
+                entry.degraded = true;
+                self.write(storage, number, Some(entry))?;
+                Ok(true)
+            }
+            JournalRequest::Skip(_) => {
+                self.write(storage, number, None)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Removes and returns the queued requests.
+    fn take_requests(&self) -> Vec<JournalRequest> {
+        self.requests
+            .lock()
+            .expect("ingestion journal lock")
+            .drain(..)
+            .collect()
+    }
+
+    /// Waits until a request is queued.
+    pub(crate) async fn requested(&self) {
+        self.requested.notified().await
+    }
+
+    fn request(&self, request: JournalRequest) -> bool {
+        let number = match request {
+            JournalRequest::Retry(number) | JournalRequest::Skip(number) => number,
+        };
+        if !self.lock().contains_key(&number) {
+            return false;
+        }
+        info!(request = ?request, "queue ingestion journal request");
+        self.requests
+            .lock()
+            .expect("ingestion journal lock")
+            .push_back(request);
+        self.requested.notify_one();
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<u64, JournalEntry>> {
+        self.entries.lock().expect("ingestion journal lock")
+    }
+}
+
+/// Fetches the canonical block with the given number from the provider and
+/// overwrites its data, failing if any data is missing.
+async fn reingest_block<G, E>(
+    number: u64,
+    provider: &Arc<G>,
+    storage: &DatabaseStorage<E>,
+    config: &BlockIngestionConfig,
+) -> Result<(), BlockIngestionError>
+where
+    G: IngestionSource,
+    E: EnvironmentKind,
+{
+    let block_id = storage
+        .canonical_block_id(number)?
+        .ok_or(BlockIngestionError::BlockNotCanonical)?;
+    let (status, header, body) = provider
+        .get_block(&BlockId::Hash(*block_id.hash()))
+        .await
+        .map_err(BlockIngestionError::provider)?;
+
+    // the downloader doesn't use the journal, so that the block is not
+    // stored degraded again.
+    let downloader = Downloader::new(provider.clone(), config.rpc_concurrency)
+        .with_traces(config.ingest_traces)
+        .with_classes(config.ingest_classes)
+        .with_headers_only(config.headers_only)
+        .with_missing_data_tolerance(config.tolerate_missing_data)
+        .with_block_hash_validation(config.block_hash_validation)
+        .with_l1_message_resolver(config.l1_message_resolver.clone());
+    let block = downloader
+        .download_block(block_id, status, header, body)
+        .await?;
+
+    let mut txn = storage.begin_txn()?;
+    downloader.write_block(block, &mut txn)?;
+    txn.update_checksum(&block_id)?;
+    txn.commit()?;
+    Ok(())
+}
+
+/// Returns the entry of a block after it failed with `err`.
+///
+/// Only provider errors degrade the block, skipping data the provider doesn't
+/// return is safe while other errors signal invalid data.
+fn next_entry(
+    previous: Option<JournalEntry>,
+    err: &BlockIngestionError,
+    max_attempts: Option<u32>,
+    now: u64,
+) -> JournalEntry {
+    let mut entry = previous.unwrap_or_default();
+    entry.attempts += 1;
+    entry.error = format!("{:?}", err);
+    entry.last_failed_at = now;
+    if let Some(max_attempts) = max_attempts {
+        entry.degraded |= err.class() == ErrorClass::Provider && entry.attempts >= max_attempts;
+    }
+    entry
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{next_entry, BlockIngestionError, IngestionJournal};
+
+    fn provider_error() -> BlockIngestionError {
+        BlockIngestionError::provider(std::io::Error::other("trace not found"))
+    }
+
+    #[test]
+    fn test_degrade_after_max_attempts() {
+        let entry = next_entry(None, &provider_error(), Some(2), 10);
+        assert_eq!(entry.attempts, 1);
+        assert!(!entry.degraded);
+
+        let entry = next_entry(Some(entry), &provider_error(), Some(2), 20);
+        assert_eq!(entry.attempts, 2);
+        assert_eq!(entry.last_failed_at, 20);
+        assert!(entry.degraded);
+
+        // invalid data is never skipped.
+        let entry = next_entry(
+            None,
+            &BlockIngestionError::MalformedTransaction,
+            Some(1),
+            10,
+        );
+        assert!(!entry.degraded);
+
+        // disabled.
+        let entry = next_entry(None, &provider_error(), None, 10);
+        assert!(!entry.degraded);
+    }
+
+    #[test]
+    fn test_request_unknown_block() {
+        let journal = IngestionJournal::default();
+        assert!(!journal.retry(1));
+        assert!(!journal.skip(1));
+        assert!(journal.take_requests().is_empty());
+        assert!(!journal.is_degraded(1));
+    }
+}
@@ -0,0 +1,200 @@
+//! Link L1 handler transactions to the L1 message they consume.
+//!
+//! The message hash is computed from the transaction, the same way the
+//! Starknet core contract does. The L1 transaction that sent the message is
+//! looked up on an Ethereum RPC, if one is configured.
+use apibara_core::starknet::v1alpha2;
+use serde::Deserialize;
+use serde_json::json;
+use sha3::{Digest, Keccak256};
+use url::Url;
+
+/// Address of the Starknet core contract on Ethereum mainnet.
+pub const MAINNET_CORE_CONTRACT: &str = "0xc662c410C0ECf747543f5bA90660f6ABeBD9C8c4";
+
+/// Signature of the event emitted by the core contract for each message.
+const LOG_MESSAGE_TO_L2: &str = "LogMessageToL2(address,uint256,uint256,uint256[],uint256,uint256)";
+
+#[derive(Debug, thiserror::Error)]
+pub enum L1MessageError {
+    #[error("ethereum rpc request failed")]
+    Request(#[from] reqwest::Error),
+    #[error("ethereum rpc returned an error: {0}")]
+    Rpc(String),
+    #[error("failed to parse ethereum rpc response")]
+    InvalidResponse,
+}
+
+/// Looks up the L1 transactions that sent messages to L2.
+#[derive(Debug, Clone)]
+pub struct L1MessageResolver {
+    client: reqwest::Client,
+    url: Url,
+    core_contract: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Vec<Log>>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Log {
+    data: String,
+    transaction_hash: String,
+}
+
+impl L1MessageResolver {
+    /// Creates a resolver that queries the Ethereum RPC at `url` for events
+    /// of the core contract at `core_contract`.
+    pub fn new(url: Url, core_contract: String) -> Self {
+        L1MessageResolver {
+            client: reqwest::Client::new(),
+            url,
+            core_contract,
+        }
+    }
+
+    /// Returns the hash of the L1 transaction that sent the message consumed
+    /// by `tx`, or `None` if it's not found.
+    pub async fn find_l1_transaction(
+        &self,
+        tx: &v1alpha2::L1HandlerTransaction,
+        nonce: &v1alpha2::FieldElement,
+    ) -> Result<Option<v1alpha2::FieldElement>, L1MessageError> {
+        let (from_address, _) = match tx.calldata.split_first() {
+            None => return Ok(None),
+            Some(from_address) => from_address,
+        };
+        let to_address = tx.contract_address.clone().unwrap_or_default();
+        let selector = tx.entry_point_selector.clone().unwrap_or_default();
+        let topic =
+            v1alpha2::FieldElement::from_bytes(&Keccak256::digest(LOG_MESSAGE_TO_L2).into());
+
+        // the nonce is not indexed, so it's matched on the returned events.
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_getLogs",
+            "params": [{
+                "address": self.core_contract,
+                "fromBlock": "earliest",
+                "toBlock": "latest",
+                "topics": [
+                    topic.to_hex(),
+                    from_address.to_hex(),
+                    to_address.to_hex(),
+                    selector.to_hex(),
+                ],
+            }],
+        });
+
+        let response: RpcResponse = self
+            .client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = response.error {
+            return Err(L1MessageError::Rpc(error.to_string()));
+        }
+
+        let nonce = nonce.to_bytes();
+        for log in response.result.unwrap_or_default() {
+            let data = log
+                .data
+                .strip_prefix("0x")
+                .and_then(|data| hex::decode(data).ok())
+                .ok_or(L1MessageError::InvalidResponse)?;
+            // data starts with the payload offset, followed by the nonce.
+            if data.get(32..64) != Some(&nonce[..]) {
+                continue;
+            }
+            let hash = v1alpha2::FieldElement::from_hex(&log.transaction_hash)
+                .map_err(|_| L1MessageError::InvalidResponse)?;
+            return Ok(Some(hash));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Returns the hash of the L1 message consumed by the transaction.
+///
+/// The first element of the calldata is the L1 sender, the rest is the
+/// message payload.
+pub fn l1_message_hash(
+    tx: &v1alpha2::L1HandlerTransaction,
+    nonce: &v1alpha2::FieldElement,
+) -> Option<v1alpha2::FieldElement> {
+    let (from_address, payload) = tx.calldata.split_first()?;
+    let to_address = tx.contract_address.as_ref()?;
+    let selector = tx.entry_point_selector.as_ref()?;
+    let payload_len = v1alpha2::FieldElement::from_u64(payload.len() as u64);
+
+    let mut hasher = Keccak256::new();
+    hasher.update(from_address.to_bytes());
+    hasher.update(to_address.to_bytes());
+    hasher.update(nonce.to_bytes());
+    hasher.update(selector.to_bytes());
+    hasher.update(payload_len.to_bytes());
+    for item in payload {
+        hasher.update(item.to_bytes());
+    }
+
+    Some(v1alpha2::FieldElement::from_bytes(
+        &hasher.finalize().into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+
+    use super::l1_message_hash;
+
+    #[test]
+    fn test_l1_message_hash() {
+        let tx = v1alpha2::L1HandlerTransaction {
+            contract_address: Some(
+                v1alpha2::FieldElement::from_hex(
+                    "0x073314940630fd6dcda0d772d4c972c4e0a9946bef9dabf4ef84eda8ef542b82",
+                )
+                .unwrap(),
+            ),
+            entry_point_selector: Some(
+                v1alpha2::FieldElement::from_hex(
+                    "0x02d757788a8d8d6f21d1cd40bce38a8222d70654214e96ff95d8086e684fbee5",
+                )
+                .unwrap(),
+            ),
+            calldata: vec![
+                v1alpha2::FieldElement::from_hex("0xae0ee0a63a2ce6baeeffe56e7714fb4efe48d419")
+                    .unwrap(),
+                v1alpha2::FieldElement::from_hex("0x0455ef3a7e6ba8a00d3b6c1b3b8b66e3c42e7f77")
+                    .unwrap(),
+                v1alpha2::FieldElement::from_u64(1_000_000_000_000_000),
+                v1alpha2::FieldElement::from_u64(0),
+            ],
+            message: None,
+        };
+        let nonce = v1alpha2::FieldElement::from_u64(1);
+
+        let hash = l1_message_hash(&tx, &nonce).unwrap();
+        assert_eq!(
+            hash.to_hex(),
+            "0x2202ecdf2339694b873948689a5a962bffd98635c70b28b81b9b19aea86a5c45"
+        );
+
+        let empty = v1alpha2::L1HandlerTransaction {
+            calldata: Vec::default(),
+            ..tx
+        };
+        assert!(l1_message_hash(&empty, &nonce).is_none());
+    }
+}
@@ -0,0 +1,219 @@
+//! Track how far block ingestion is behind the provider.
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use apibara_node::o11y;
+use tracing::warn;
+
+/// Window over which the ingestion rate is computed.
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Shared handle to the ingestion progress.
+#[derive(Debug, Clone)]
+pub struct IngestionProgress {
+    state: Arc<Mutex<ProgressState>>,
+}
+
+/// Ingestion progress at a point in time.
+#[derive(Debug, Clone, Default)]
+pub struct IngestionProgressSnapshot {
+    /// Most recent head block number reported by the provider.
+    pub provider_head: Option<u64>,
+    /// Most recent block number ingested by the node.
+    pub node_head: Option<u64>,
+    /// Most recent finalized block number.
+    pub finalized: Option<u64>,
+    /// Blocks ingested per second, over the last few seconds.
+    pub blocks_per_second: f64,
+    /// When the last block was ingested.
+    pub last_ingested_at: Option<SystemTime>,
+    /// True if the node reached the provider head at least once.
+    pub reached_head: bool,
+}
+
+#[derive(Debug)]
+struct ProgressState {
+    snapshot: IngestionProgressSnapshot,
+    window_start: Instant,
+    window_blocks: u64,
+}
+
+impl Default for IngestionProgress {
+    fn default() -> Self {
+        let state = ProgressState {
+            snapshot: IngestionProgressSnapshot::default(),
+            window_start: Instant::now(),
+            window_blocks: 0,
+        };
+        IngestionProgress {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+}
+
+impl IngestionProgress {
+    /// Returns the current ingestion progress.
+    pub fn snapshot(&self) -> IngestionProgressSnapshot {
+        let state = self.state.lock().expect("ingestion progress lock");
+        let mut snapshot = state.snapshot.clone();
+        // without new blocks the rate must decrease, even if the window
+        // is not complete.
+        let elapsed = state.window_start.elapsed();
+        if elapsed >= RATE_WINDOW {
+            snapshot.blocks_per_second = state.window_blocks as f64 / elapsed.as_secs_f64();
+        }
+        snapshot
+    }
+
+    /// Exports the ingestion progress as metrics.
+    pub fn register_metrics(&self) {
+        let meter = o11y::meter("ingestion");
+        let provider_head = meter
+            .u64_observable_gauge("provider_head")
+            .with_description("Head block number of the provider")
+            .init();
+        let node_head = meter
+            .u64_observable_gauge("node_head")
+            .with_description("Most recent block number ingested")
+            .init();
+        let lag = meter
+            .u64_observable_gauge("lag")
+            .with_description("Number of blocks between the node head and the provider head")
+            .init();
+        let finalized_lag = meter
+            .u64_observable_gauge("finalized_lag")
+            .with_description("Number of blocks between the finalized block and the node head")
+            .init();
+        let blocks_per_second = meter
+            .f64_observable_gauge("blocks_per_second")
+            .with_description("Number of blocks ingested per second")
+            .init();
+        let last_ingested = meter
+            .u64_observable_gauge("last_ingested_timestamp")
+            .with_description("Unix timestamp, in seconds, of the last ingested block")
+            .init();
+
+        let progress = self.clone();
+        let result = meter.register_callback(move |cx| {
+            let snapshot = progress.snapshot();
+            if let Some(head) = snapshot.provider_head {
+                provider_head.observe(cx, head, &[]);
+            }
+            if let Some(head) = snapshot.node_head {
+                node_head.observe(cx, head, &[]);
+            }
+            if let Some(value) = snapshot.lag() {
+                lag.observe(cx, value, &[]);
+            }
+            if let Some(value) = snapshot.finalized_lag() {
+                finalized_lag.observe(cx, value, &[]);
+            }
+            blocks_per_second.observe(cx, snapshot.blocks_per_second, &[]);
+            if let Some(timestamp) = snapshot.last_ingested_at {
+                let timestamp = timestamp
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                last_ingested.observe(cx, timestamp, &[]);
+            }
+        });
+
+        if let Err(err) = result {
+            warn!(error = ?err, "failed to register ingestion metrics");
+        }
+    }
+
+    /// Records the head block number reported by the provider.
+    pub(crate) fn provider_head(&self, number: u64) {
+        let mut state = self.state.lock().expect("ingestion progress lock");
+        state.snapshot.provider_head = Some(number);
+        state.update_reached_head();
+    }
+
+    /// Records that the block was ingested and is the new node head.
+    pub(crate) fn block_ingested(&self, number: u64) {
+        let mut state = self.state.lock().expect("ingestion progress lock");
+        state.snapshot.node_head = Some(number);
+        state.snapshot.last_ingested_at = Some(SystemTime::now());
+        state.window_blocks += 1;
+
+        let elapsed = state.window_start.elapsed();
+        if elapsed >= RATE_WINDOW {
+            state.snapshot.blocks_per_second = state.window_blocks as f64 / elapsed.as_secs_f64();
+            state.window_start = Instant::now();
+            state.window_blocks = 0;
+        }
+        state.update_reached_head();
+    }
+
+    /// Records the node head, for example after a chain reorganization,
+    /// without counting it as ingested.
+    pub(crate) fn node_head(&self, number: u64) {
+        let mut state = self.state.lock().expect("ingestion progress lock");
+        state.snapshot.node_head = Some(number);
+    }
+
+    /// Records the most recent finalized block.
+    pub(crate) fn finalized(&self, number: u64) {
+        let mut state = self.state.lock().expect("ingestion progress lock");
+        state.snapshot.finalized = Some(number);
+    }
+}
+
+impl IngestionProgressSnapshot {
+    /// Returns the number of blocks between the node and the provider head.
+    pub fn lag(&self) -> Option<u64> {
+        match (self.provider_head, self.node_head) {
+            (Some(provider_head), Some(node_head)) => Some(provider_head.saturating_sub(node_head)),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of blocks between the finalized block and the
+    /// node head.
+    pub fn finalized_lag(&self) -> Option<u64> {
+        match (self.node_head, self.finalized) {
+            (Some(node_head), Some(finalized)) => Some(node_head.saturating_sub(finalized)),
+            _ => None,
+        }
+    }
+}
+
+impl ProgressState {
+    fn update_reached_head(&mut self) {
+        if self.snapshot.lag() == Some(0) {
+            self.snapshot.reached_head = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IngestionProgress;
+
+    #[test]
+    fn test_lag() {
+        let progress = IngestionProgress::default();
+        assert_eq!(progress.snapshot().lag(), None);
+
+        progress.provider_head(10);
+        progress.block_ingested(4);
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.lag(), Some(6));
+        assert!(!snapshot.reached_head);
+        assert!(snapshot.last_ingested_at.is_some());
+
+        progress.block_ingested(10);
+        assert!(progress.snapshot().reached_head);
+
+        // the provider can be behind after a reorg.
+        progress.provider_head(8);
+        assert_eq!(progress.snapshot().lag(), Some(0));
+
+        assert_eq!(progress.snapshot().finalized_lag(), None);
+        progress.finalized(3);
+        assert_eq!(progress.snapshot().finalized_lag(), Some(7));
+    }
+}
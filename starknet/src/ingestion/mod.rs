@@ -1,84 +1,344 @@
 mod accepted;
+mod blob;
+mod block_hash;
+mod bootstrap;
+mod chain_id;
 mod config;
+mod control;
+mod data_availability;
+mod disk;
 mod downloader;
+mod emitters;
 mod error;
+mod events_backfill;
 mod finalized;
+mod head_subscription;
+mod heal;
+#[cfg(feature = "reorg-injection")]
+mod inject;
+mod journal;
+mod l1_message;
+mod lag;
+mod quorum;
+mod reorg;
+mod retry;
+mod scrub;
+mod source;
+mod stages;
 mod started;
 mod subscription;
 
 use std::sync::Arc;
 
-use apibara_node::db::libmdbx::{Environment, EnvironmentKind};
-use tokio::time::Duration;
+use apibara_node::db::libmdbx::EnvironmentKind;
+use backoff::backoff::Backoff;
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
-use tracing::error;
+use tracing::{error, info, warn};
 
 use crate::{db::DatabaseStorage, provider::Provider};
 
-use self::{started::StartedBlockIngestion, subscription::IngestionStreamPublisher};
+use self::{bootstrap::BootstrapBlockIngestion, started::StartedBlockIngestion};
 
 pub use self::{
+    block_hash::{compute_block_hash, BlockHashValidation},
+    chain_id::{chain_id_from_network, verify_chain_id},
     config::BlockIngestionConfig,
+    control::{IngestionControl, IngestionControlStatus},
+    data_availability::{DataAvailabilityConfig, DataAvailabilityError, DataAvailabilityIngestion},
+    disk::DiskUsageConfig,
+    emitters::{EmitterStats, EmitterStatsConfig, EmitterVolume},
     error::BlockIngestionError,
+    events_backfill::{EventsBackfill, EventsBackfillConfig},
+    heal::GapHealer,
+    journal::{IngestionJournal, JournalRequest},
+    l1_message::{l1_message_hash, L1MessageError, L1MessageResolver, MAINNET_CORE_CONTRACT},
+    lag::{IngestionProgress, IngestionProgressSnapshot},
+    retry::{ErrorClass, IngestionHealth, IngestionHealthStatus, RetryPolicy},
+    scrub::{BlockScrubber, ScrubConfig, ScrubError, ScrubProgress, ScrubStatus},
+    source::IngestionSource,
+    stages::IngestionStage,
     subscription::{IngestionStream, IngestionStreamClient},
 };
 
+#[cfg(feature = "reorg-injection")]
+pub use self::inject::InjectedEvent;
+
+pub(crate) use self::subscription::IngestionStreamPublisher;
+
 /// Block ingestion service.
-pub struct BlockIngestion<G: Provider + Send, E: EnvironmentKind> {
+pub struct BlockIngestion<G: Provider + Send + Sync + 'static, E: EnvironmentKind> {
     config: BlockIngestionConfig,
     provider: Arc<G>,
-    db: Arc<Environment<E>>,
+    storage: DatabaseStorage<E>,
     publisher: IngestionStreamPublisher,
+    health: IngestionHealth,
+    control: IngestionControl,
 }
 
 impl<G, E> BlockIngestion<G, E>
 where
-    G: Provider + Send,
+    G: Provider + Send + Sync + 'static,
     E: EnvironmentKind,
 {
     pub fn new(
         provider: Arc<G>,
-        db: Arc<Environment<E>>,
+        storage: DatabaseStorage<E>,
         config: BlockIngestionConfig,
     ) -> (IngestionStreamClient, Self) {
         let (sub_client, publisher) = IngestionStreamPublisher::new();
+        let health = IngestionHealth::default()
+            .with_max_lag(config.max_lag)
+            .with_max_finalized_lag(config.max_finalized_lag)
+            .with_disk_usage(config.disk_usage.clone());
+        health.progress().register_metrics();
+        if let Some(emitter_stats) = &config.emitter_stats {
+            emitter_stats.register_metrics();
+        }
 
         let ingestion = BlockIngestion {
             provider,
-            db,
+            storage,
             config,
             publisher,
+            health,
+            control: IngestionControl::default(),
         };
         (sub_client, ingestion)
     }
 
+    /// Returns a handle to the ingestion health.
+    pub fn health(&self) -> IngestionHealth {
+        self.health.clone()
+    }
+
+    /// Returns a handle to pause and resume ingestion.
+    pub fn control(&self) -> IngestionControl {
+        self.control.clone()
+    }
+
+    /// Returns a handle to the blocks that repeatedly failed to ingest.
+    pub fn journal(&self) -> IngestionJournal {
+        self.config.journal.clone()
+    }
+
+    /// Returns a handle to the event emitter statistics, if enabled.
+    pub fn emitter_stats(&self) -> Option<EmitterStats> {
+        self.config.emitter_stats.clone()
+    }
+
     /// Start ingesting blocks.
     pub async fn start(self, ct: CancellationToken) -> Result<(), BlockIngestionError> {
-        loop {
-            let storage = DatabaseStorage::new(self.db.clone());
-            let result = StartedBlockIngestion::new(
+        self.config.journal.load(&self.storage)?;
+
+        self.bootstrap(ct.clone()).await;
+
+        if let Some(config) = self.config.events_backfill.clone() {
+            // same as bootstrap, full ingestion continues where it stopped.
+            let backfill = EventsBackfill::new(
                 self.provider.clone(),
-                storage,
-                self.config.clone(),
-                self.publisher.clone(),
+                self.storage.clone(),
+                config,
+                self.health.progress().clone(),
             )
-            .start(ct.clone())
-            .await;
+            .with_rpc_concurrency(self.config.rpc_concurrency)
+            .with_starting_block(self.config.starting_block);
+            match backfill.start(ct.clone()).await {
+                Ok(latest) => info!(latest = ?latest, "events backfill completed"),
+                Err(err) => warn!(error = ?err, "events backfill failed"),
+            }
+        }
+
+        let policy = &self.config.retry_policy;
+        let mut backoff = policy.backoff();
+        let mut attempt = 0;
+        let mut restarted = false;
+        loop {
+            // the bootstrap node is the primary source of finalized blocks,
+            // catch up with it before falling back to the RPC again.
+            if restarted {
+                self.bootstrap(ct.clone()).await;
+            }
+            restarted = true;
+
+            let started_at = Instant::now();
+            let storage = self.storage.clone();
+            let ingestion = async {
+                // the provider can change between attempts, for example
+                // behind a load balancer.
+                verify_chain_id(
+                    &*self.provider,
+                    &storage,
+                    self.config.expected_chain_id.as_ref(),
+                )
+                .await?;
+                StartedBlockIngestion::new(
+                    self.provider.clone(),
+                    storage.clone(),
+                    self.config.clone(),
+                    self.publisher.clone(),
+                    self.health.progress().clone(),
+                    self.control.clone(),
+                )
+                .start(ct.clone())
+                .await
+            };
+            tokio::pin!(ingestion);
+
+            // ingestion that runs for a while made progress, so it's healthy
+            // again.
+            let result = tokio::select! {
+                result = &mut ingestion => result,
+                _ = tokio::time::sleep(policy.max_delay) => {
+                    self.health.set(IngestionHealthStatus::Healthy);
+                    ingestion.await
+                }
+            };
 
-            match result {
+            let err = match result {
                 Ok(_) => {
                     if !ct.is_cancelled() {
                         error!("block ingestion stopped without error");
                     }
                     return Ok(());
                 }
-                Err(err) => {
-                    error!(error = ?err, "block ingestion terminated with error");
+                Err(err) => err,
+            };
+
+            if err.is_database_full() {
+                // the failed transaction was aborted, so it can be retried
+                // as soon as the database is larger.
+                match self.storage.grow() {
+                    Ok(max_size) => {
+                        warn!(max_size = %max_size, "database was full. increased its maximum size");
+                        attempt = 0;
+                        backoff.reset();
+                        continue;
+                    }
+                    Err(grow_err) => error!(
+                        error = ?err,
+                        grow_error = ?grow_err,
+                        "database reached its maximum size. increase --mdbx-max-size-gib"
+                    ),
+                }
+            } else {
+                error!(error = ?err, "block ingestion terminated with error");
+            }
+
+            if started_at.elapsed() >= policy.max_delay {
+                attempt = 0;
+                backoff.reset();
+            }
+            attempt += 1;
+
+            // the block is now stored without the data that fails, so
+            // ingestion continues with the following blocks.
+            if self.record_failure(&err) {
+                attempt = 0;
+                backoff.reset();
+                continue;
+            }
+
+            if !policy.should_retry(&err, attempt) {
+                error!(
+                    attempt = %attempt,
+                    class = ?err.class(),
+                    "block ingestion failed. not retrying"
+                );
+                self.health.set(IngestionHealthStatus::Failed {
+                    error: format!("{:?}", err),
+                });
+                // keep serving stored data, the health status signals the
+                // failure to operators.
+                ct.cancelled().await;
+                return Err(err);
+            }
+
+            self.health.set(IngestionHealthStatus::Retrying {
+                attempt,
+                error: format!("{:?}", err),
+            });
+
+            let delay = backoff.next_backoff().unwrap_or(policy.max_delay);
+            warn!(attempt = %attempt, delay = ?delay, "retrying block ingestion");
+            let sleep = tokio::time::sleep(delay);
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    _ = ct.cancelled() => return Ok(()),
+                    _ = &mut sleep => break,
+                    _ = self.config.journal.requested() => {
+                        let retry_now = self
+                            .config
+                            .journal
+                            .apply_requests(&self.provider, &self.storage, &self.config)
+                            .await;
+                        if retry_now {
+                            attempt = 0;
+                            backoff.reset();
+                            break;
+                        }
+                    }
                 }
             }
+        }
+    }
+
+    /// Records the error in the journal, against the block that failed to
+    /// ingest.
+    ///
+    /// Errors not attributed to a block are not recorded. Returns true if the
+    /// block became degraded.
+    fn record_failure(&self, err: &BlockIngestionError) -> bool {
+        if !matches!(
+            err.class(),
+            ErrorClass::Provider | ErrorClass::Data | ErrorClass::Quorum
+        ) {
+            return false;
+        }
+        let number = match err.block_number() {
+            None => return false,
+            Some(number) => number,
+        };
+
+        let journal = &self.config.journal;
+        let was_degraded = journal.is_degraded(number);
+        let result = journal
+            .record_failure(&self.storage, number, err, self.config.journal_max_attempts)
+            .map(|entry| entry.degraded && !was_degraded);
+        match result {
+            Ok(degraded) => degraded,
+            Err(err) => {
+                warn!(error = ?err, "failed to record ingestion failure");
+                false
+            }
+        }
+    }
+
+    /// Ingests the blocks finalized on the bootstrap node, if any.
+    async fn bootstrap(&self, ct: CancellationToken) {
+        let url = match self.config.bootstrap_node.clone() {
+            None => return,
+            Some(url) => url,
+        };
+
+        info!(url = %url, "bootstrap from node");
+        let storage = self.storage.clone();
+        let result = async {
+            BootstrapBlockIngestion::connect(url, storage)
+                .await?
+                .with_headers_only(self.config.headers_only)
+                .with_starting_block(self.config.starting_block)
+                .with_block_hash_validation(self.config.block_hash_validation)
+                .start(ct)
+                .await
+        }
+        .await;
 
-            // TODO: would be better if we exponentially backed off.
-            tokio::time::sleep(Duration::from_secs(10)).await;
+        // bootstrap is an optimization, fall back to the RPC if it fails.
+        match result {
+            Ok(latest) => info!(latest = ?latest, "bootstrap completed"),
+            Err(err) => warn!(error = ?err, "bootstrap failed"),
         }
     }
 }
@@ -0,0 +1,67 @@
+//! Cross-check accepted blocks against a second provider.
+use std::time::Duration;
+
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    core::GlobalBlockId,
+    provider::{BlockId, HttpProvider, Provider, ProviderError},
+};
+
+use super::error::BlockIngestionError;
+
+/// Number of times the block is requested from a lagging provider.
+const MAX_ATTEMPTS: usize = 5;
+
+/// Delay between requests to a lagging provider.
+const RETRY_DELAY: Duration = Duration::from_secs(3);
+
+/// Verifies that a second provider agrees on the hash of new blocks.
+pub struct QuorumVerifier {
+    provider: HttpProvider,
+}
+
+impl QuorumVerifier {
+    pub fn new(url: Url) -> Self {
+        QuorumVerifier {
+            provider: HttpProvider::new(url),
+        }
+    }
+
+    /// Returns an error if the quorum provider has a different block at the
+    /// height of `block_id`.
+    ///
+    /// If the quorum provider doesn't have the block yet, the request is
+    /// retried a few times before giving up.
+    pub async fn verify(&self, block_id: &GlobalBlockId) -> Result<(), BlockIngestionError> {
+        let number = BlockId::Number(block_id.number());
+        let mut attempt = 1;
+        let header = loop {
+            match self.provider.get_block(&number).await {
+                Ok((_, header, _)) => break header,
+                Err(err) if err.is_block_not_found() && attempt < MAX_ATTEMPTS => {
+                    debug!(block_id = %block_id, "quorum provider is not synced");
+                    attempt += 1;
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+                Err(err) => return Err(BlockIngestionError::provider(err)),
+            }
+        };
+
+        let quorum_id = GlobalBlockId::from_block_header(&header)?;
+        if quorum_id != *block_id {
+            warn!(
+                block_id = %block_id,
+                quorum_block_id = %quorum_id,
+                "providers disagree on block"
+            );
+            return Err(BlockIngestionError::QuorumMismatch {
+                expected: *block_id,
+                found: quorum_id,
+            });
+        }
+
+        Ok(())
+    }
+}
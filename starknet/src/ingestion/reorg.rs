@@ -0,0 +1,109 @@
+//! Recover from chain reorganizations of any depth.
+//!
+//! The stored canonical chain is walked back, using the parent hash in the
+//! stored headers, until a block that is also in the provider's canonical
+//! chain is found.
+use apibara_node::db::libmdbx::EnvironmentKind;
+use tracing::{debug, info};
+
+use crate::{
+    core::{BlockHash, GlobalBlockId},
+    db::{DatabaseStorage, StorageReader, StorageWriter},
+    provider::{BlockId, ProviderError},
+};
+
+use super::{
+    error::BlockIngestionError, source::IngestionSource, subscription::IngestionStreamPublisher,
+};
+
+/// Removes the blocks after the common ancestor of `tip` and the provider's
+/// canonical chain from the canonical chain, then invalidates them.
+///
+/// Returns the common ancestor.
+pub async fn recover_from_reorg<G, E>(
+    provider: &G,
+    storage: &DatabaseStorage<E>,
+    publisher: &IngestionStreamPublisher,
+    tip: GlobalBlockId,
+) -> Result<GlobalBlockId, BlockIngestionError>
+where
+    G: IngestionSource,
+    E: EnvironmentKind,
+{
+    let finalized = storage.highest_finalized_block()?;
+    let mut rejected = Vec::new();
+    let mut ingested_tip = tip;
+
+    while !is_canonical(provider, &ingested_tip).await? {
+        if let Some(finalized) = finalized {
+            if ingested_tip.number() <= finalized.number() {
+                return Err(BlockIngestionError::FinalizedBlockReorganized(ingested_tip));
+            }
+        }
+
+        debug!(tip = %ingested_tip, "block doesn't belong to canonical chain");
+        rejected.push(ingested_tip);
+
+        // header must exist in the database
+        let header = storage
+            .read_header(&ingested_tip)?
+            .ok_or(BlockIngestionError::InconsistentDatabase)?;
+        let parent_hash = header
+            .parent_block_hash
+            .as_ref()
+            .ok_or(BlockIngestionError::MissingBlockHash)?
+            .into();
+        let parent_number = header
+            .block_number
+            .checked_sub(1)
+            .ok_or(BlockIngestionError::InconsistentDatabase)?;
+        ingested_tip = GlobalBlockId::new(parent_number, parent_hash);
+    }
+
+    if rejected.is_empty() {
+        return Ok(ingested_tip);
+    }
+
+    let mut txn = storage.begin_txn()?;
+    for block_id in &rejected {
+        txn.reject_block_from_canonical_chain(block_id)?;
+    }
+    txn.commit()?;
+
+    info!(
+        common_ancestor = %ingested_tip,
+        removed = %rejected.len(),
+        "recovered from chain reorganization"
+    );
+
+    publisher.publish_invalidate(ingested_tip)?;
+    Ok(ingested_tip)
+}
+
+/// Returns true if the provider's canonical chain contains the block.
+///
+/// Blocks are fetched by number since providers can prune reorganized
+/// blocks, or not mark them as rejected.
+async fn is_canonical<G>(
+    provider: &G,
+    block_id: &GlobalBlockId,
+) -> Result<bool, BlockIngestionError>
+where
+    G: IngestionSource,
+{
+    let header = match provider
+        .get_block(&BlockId::Number(block_id.number()))
+        .await
+    {
+        Ok((_, header, _)) => header,
+        // the provider chain is shorter than the stored chain.
+        Err(err) if err.is_block_not_found() => return Ok(false),
+        Err(err) => return Err(BlockIngestionError::provider(err)),
+    };
+    let hash: BlockHash = header
+        .block_hash
+        .as_ref()
+        .ok_or(BlockIngestionError::MissingBlockHash)?
+        .into();
+    Ok(hash == *block_id.hash())
+}
@@ -0,0 +1,279 @@
+//! Retry policy and health of block ingestion.
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use backoff::ExponentialBackoff;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::{disk::DiskUsageConfig, lag::IngestionProgress, BlockIngestionError};
+
+/// Class of ingestion errors, used to decide if ingestion is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Requests to the RPC provider failed.
+    Provider,
+    /// Database operations failed, or the database is inconsistent.
+    Database,
+    /// The provider returned malformed data.
+    Data,
+    /// The quorum provider disagrees on a block.
+    Quorum,
+    /// Internal errors, for example if the ingestion stream is closed.
+    Internal,
+}
+
+/// How ingestion is restarted after an error.
+#[derive(Debug, Clone, Serialize)]
+pub struct RetryPolicy {
+    /// Maximum number of consecutive failed attempts. Retry forever if `None`.
+    pub max_attempts: Option<u32>,
+    /// Delay before the first retry.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub initial_delay: Duration,
+    /// Maximum delay between retries.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub max_delay: Duration,
+    /// Factor applied to the delay after each failed attempt.
+    pub multiplier: f64,
+    /// Randomize delays by up to this fraction of their value.
+    pub jitter: f64,
+    /// Errors that are retried. Ingestion stops on any other error.
+    pub retryable: Vec<ErrorClass>,
+}
+
+/// Health of block ingestion.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum IngestionHealthStatus {
+    /// Ingestion is running.
+    #[default]
+    Healthy,
+    /// Ingestion failed and will be retried.
+    Retrying { attempt: u32, error: String },
+    /// Ingestion failed and was stopped.
+    Failed { error: String },
+    /// Ingestion is running, but too far behind the provider head.
+    Lagging { lag: u64, max_lag: u64 },
+    /// Ingestion is running, but the finalized block is too far behind the
+    /// node head.
+    FinalizedLagging { lag: u64, max_lag: u64 },
+    /// Ingestion is paused until disk space is freed.
+    LowDiskSpace {
+        free_space: u64,
+        min_free_space: u64,
+    },
+}
+
+/// Shared handle to the ingestion health.
+#[derive(Debug, Clone, Default)]
+pub struct IngestionHealth {
+    status: Arc<Mutex<IngestionHealthStatus>>,
+    progress: IngestionProgress,
+    max_lag: Option<u64>,
+    max_finalized_lag: Option<u64>,
+    disk_usage: Option<DiskUsageConfig>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: None,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.1,
+            retryable: vec![
+                ErrorClass::Provider,
+                ErrorClass::Database,
+                ErrorClass::Data,
+                ErrorClass::Quorum,
+                ErrorClass::Internal,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns true if ingestion should be retried after `attempt` consecutive
+    /// failures, the last one caused by `err`.
+    pub fn should_retry(&self, err: &BlockIngestionError, attempt: u32) -> bool {
+        if err.is_fatal() || !self.retryable.contains(&err.class()) {
+            return false;
+        }
+        match self.max_attempts {
+            None => true,
+            Some(max_attempts) => attempt < max_attempts,
+        }
+    }
+
+    /// Returns the backoff used to compute delays between retries.
+    pub fn backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoff {
+            current_interval: self.initial_delay,
+            initial_interval: self.initial_delay,
+            randomization_factor: self.jitter.clamp(0.0, 1.0),
+            multiplier: f64::max(self.multiplier, 1.0),
+            max_interval: self.max_delay,
+            max_elapsed_time: None,
+            ..ExponentialBackoff::default()
+        }
+    }
+}
+
+impl IngestionHealthStatus {
+    /// Returns true if ingestion is running or will be retried, and is not
+    /// lagging.
+    pub fn is_healthy(&self) -> bool {
+        !matches!(
+            self,
+            IngestionHealthStatus::Failed { .. }
+                | IngestionHealthStatus::Lagging { .. }
+                | IngestionHealthStatus::FinalizedLagging { .. }
+                | IngestionHealthStatus::LowDiskSpace { .. }
+        )
+    }
+}
+
+impl IngestionHealth {
+    /// Report ingestion as lagging if it's more than `max_lag` blocks behind
+    /// the provider head.
+    ///
+    /// The threshold only applies once the node reached the provider head,
+    /// so that nodes syncing from scratch are healthy.
+    pub fn with_max_lag(mut self, max_lag: Option<u64>) -> Self {
+        self.max_lag = max_lag;
+        self
+    }
+
+    /// Report ingestion as lagging if the finalized block is more than
+    /// `max_lag` blocks behind the node head.
+    pub fn with_max_finalized_lag(mut self, max_lag: Option<u64>) -> Self {
+        self.max_finalized_lag = max_lag;
+        self
+    }
+
+    /// Report ingestion as unhealthy while it's paused because the disk is
+    /// almost full.
+    pub fn with_disk_usage(mut self, disk_usage: Option<DiskUsageConfig>) -> Self {
+        self.disk_usage = disk_usage;
+        self
+    }
+
+    /// Returns the current ingestion health.
+    pub fn status(&self) -> IngestionHealthStatus {
+        let status = self.status.lock().expect("ingestion health lock").clone();
+        if status != IngestionHealthStatus::Healthy {
+            return status;
+        }
+
+        if let Some(disk_usage) = &self.disk_usage {
+            if let Ok(free_space) = disk_usage.free_space() {
+                if disk_usage.is_low(free_space) {
+                    return IngestionHealthStatus::LowDiskSpace {
+                        free_space,
+                        min_free_space: disk_usage.min_free_space,
+                    };
+                }
+            }
+        }
+
+        let progress = self.progress.snapshot();
+        if let (Some(max_lag), Some(lag)) = (self.max_lag, progress.lag()) {
+            if progress.reached_head && lag > max_lag {
+                return IngestionHealthStatus::Lagging { lag, max_lag };
+            }
+        }
+
+        if let (Some(max_lag), Some(lag)) = (self.max_finalized_lag, progress.finalized_lag()) {
+            if lag > max_lag {
+                return IngestionHealthStatus::FinalizedLagging { lag, max_lag };
+            }
+        }
+
+        status
+    }
+
+    /// Returns a handle to the ingestion progress.
+    pub fn progress(&self) -> &IngestionProgress {
+        &self.progress
+    }
+
+    pub(crate) fn set(&self, status: IngestionHealthStatus) {
+        *self.status.lock().expect("ingestion health lock") = status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ingestion::BlockIngestionError;
+
+    use super::{ErrorClass, IngestionHealth, IngestionHealthStatus, RetryPolicy};
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy {
+            max_attempts: Some(3),
+            retryable: vec![ErrorClass::Provider],
+            ..RetryPolicy::default()
+        };
+
+        let provider_error = BlockIngestionError::Provider("connection refused".into());
+        assert!(policy.should_retry(&provider_error, 1));
+        assert!(policy.should_retry(&provider_error, 2));
+        assert!(!policy.should_retry(&provider_error, 3));
+
+        let data_error = BlockIngestionError::MalformedTransaction;
+        assert!(!policy.should_retry(&data_error, 1));
+
+        // errors keep their class and first block once attributed to a block.
+        let block_error = BlockIngestionError::Provider("connection refused".into())
+            .at_block(10)
+            .at_block(11);
+        assert_eq!(block_error.block_number(), Some(10));
+        assert!(policy.should_retry(&block_error, 1));
+    }
+
+    #[test]
+    fn test_lagging() {
+        let health = IngestionHealth::default().with_max_lag(Some(5));
+        health.progress().provider_head(100);
+        health.progress().block_ingested(10);
+        // still syncing.
+        assert_eq!(health.status(), IngestionHealthStatus::Healthy);
+
+        health.progress().block_ingested(100);
+        health.progress().provider_head(110);
+        assert_eq!(
+            health.status(),
+            IngestionHealthStatus::Lagging {
+                lag: 10,
+                max_lag: 5
+            }
+        );
+        assert!(!health.status().is_healthy());
+    }
+
+    #[test]
+    fn test_finalized_lagging() {
+        let health = IngestionHealth::default().with_max_finalized_lag(Some(50));
+        health.progress().block_ingested(100);
+        // no finalized block yet.
+        assert_eq!(health.status(), IngestionHealthStatus::Healthy);
+
+        health.progress().finalized(60);
+        assert_eq!(health.status(), IngestionHealthStatus::Healthy);
+
+        health.progress().block_ingested(120);
+        assert_eq!(
+            health.status(),
+            IngestionHealthStatus::FinalizedLagging {
+                lag: 60,
+                max_lag: 50
+            }
+        );
+        assert!(!health.status().is_healthy());
+    }
+}
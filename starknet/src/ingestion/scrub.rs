@@ -0,0 +1,324 @@
+//! Validate stored blocks against their checksum.
+//!
+//! The scrubber walks the finalized blocks in the canonical chain, comparing
+//! their data with the checksum stored at ingestion time. Corrupted blocks
+//! are fetched again from the provider and overwritten.
+//!
+//! Blocks stored before checksums were introduced don't have one, the
+//! scrubber stores the checksum of their current data.
+//!
+//! Blocks stored without a state update, for example blocks imported from a
+//! file, are completed with the state update from the provider so that state
+//! update filters have data to serve.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::{
+    core::GlobalBlockId,
+    db::{ChecksumStatus, DatabaseStorage, StorageReader, StorageWriter},
+    provider::{BlockId, Provider},
+};
+
+use super::{downloader::Downloader, BlockIngestionError};
+
+/// How long to wait before starting a new pass over the chain.
+const PASS_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Concurrency for RPC requests when repairing blocks.
+const REPAIR_RPC_CONCURRENCY: usize = 4;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScrubError {
+    #[error("database operation failed")]
+    Database(#[from] libmdbx::Error),
+    #[error("verification task failed")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+/// Scrubber configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScrubConfig {
+    /// Maximum number of blocks verified per second.
+    pub blocks_per_second: u64,
+}
+
+/// Scrubber counters, since the node started.
+#[derive(Debug, Clone, Default)]
+pub struct ScrubProgress {
+    /// Number of completed passes over the chain.
+    pub passes: u64,
+    /// The next block to verify.
+    pub current_block: u64,
+    /// Number of blocks verified.
+    pub verified_blocks: u64,
+    /// Number of blocks that didn't match their checksum.
+    pub corrupted_blocks: u64,
+    /// Number of corrupted blocks that were ingested again.
+    pub repaired_blocks: u64,
+    /// Number of blocks whose missing state update was ingested.
+    pub backfilled_state_updates: u64,
+}
+
+/// Shared view over the scrubber progress.
+#[derive(Debug, Clone, Default)]
+pub struct ScrubStatus {
+    progress: Arc<Mutex<ScrubProgress>>,
+}
+
+/// Result of verifying a range of blocks.
+#[derive(Debug, Default)]
+struct VerifiedBatch {
+    verified: u64,
+    corrupted: Vec<GlobalBlockId>,
+    missing_state_update: Vec<GlobalBlockId>,
+    /// The next block to verify, or `None` if the pass is complete.
+    next: Option<u64>,
+}
+
+/// A service that validates block checksums and re-ingests corrupted blocks.
+pub struct BlockScrubber<G: Provider + Send + Sync + 'static, E: EnvironmentKind> {
+    provider: Arc<G>,
+    downloader: Downloader<G>,
+    storage: DatabaseStorage<E>,
+    config: ScrubConfig,
+    status: ScrubStatus,
+    headers_only: bool,
+}
+
+impl ScrubStatus {
+    /// Returns the current scrubber progress.
+    pub fn progress(&self) -> ScrubProgress {
+        self.progress.lock().expect("scrub status lock").clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut ScrubProgress)) {
+        f(&mut self.progress.lock().expect("scrub status lock"))
+    }
+}
+
+impl<G, E> BlockScrubber<G, E>
+where
+    G: Provider + Send + Sync + 'static,
+    E: EnvironmentKind,
+{
+    pub fn new(provider: Arc<G>, storage: DatabaseStorage<E>, config: ScrubConfig) -> Self {
+        let downloader = Downloader::new(provider.clone(), REPAIR_RPC_CONCURRENCY);
+        BlockScrubber {
+            provider,
+            downloader,
+            storage,
+            config,
+            status: ScrubStatus::default(),
+            headers_only: false,
+        }
+    }
+
+    /// Don't backfill state updates, and only repair headers.
+    pub fn with_headers_only(mut self, headers_only: bool) -> Self {
+        self.downloader = self.downloader.with_headers_only(headers_only);
+        self.headers_only = headers_only;
+        self
+    }
+
+    /// Returns a handle to the scrubber status.
+    pub fn status(&self) -> ScrubStatus {
+        self.status.clone()
+    }
+
+    /// Verifies blocks until the token is cancelled.
+    pub async fn start(self, ct: CancellationToken) {
+        let blocks_per_second = u64::max(self.config.blocks_per_second, 1);
+        let mut next_block = None;
+        loop {
+            let started_at = Instant::now();
+            let result = self.verify_batch(next_block, blocks_per_second).await;
+
+            let wait = match result {
+                Ok(Some(next)) => {
+                    next_block = Some(next);
+                    Duration::from_secs(1).saturating_sub(started_at.elapsed())
+                }
+                Ok(None) => {
+                    info!("completed block scrub pass");
+                    self.status.update(|progress| progress.passes += 1);
+                    next_block = None;
+                    PASS_INTERVAL
+                }
+                Err(err) => {
+                    error!(error = ?err, "failed to verify block checksums");
+                    PASS_INTERVAL
+                }
+            };
+
+            tokio::select! {
+                _ = ct.cancelled() => return,
+                _ = tokio::time::sleep(wait) => {},
+            }
+        }
+    }
+
+    /// Verifies up to `count` finalized blocks, starting at `start` or at the
+    /// earliest block.
+    ///
+    /// Returns the next block to verify, or `None` if the pass is complete.
+    async fn verify_batch(
+        &self,
+        start: Option<u64>,
+        count: u64,
+    ) -> Result<Option<u64>, ScrubError> {
+        let storage = self.storage.clone();
+        let batch = tokio::task::spawn_blocking(move || {
+            let start = match start {
+                Some(start) => start,
+                None => match storage.earliest_available_block()? {
+                    None => return Ok(VerifiedBatch::default()),
+                    Some(block_id) => block_id.number(),
+                },
+            };
+            let end = match storage.highest_finalized_block()? {
+                None => return Ok(VerifiedBatch::default()),
+                Some(block_id) => u64::min(block_id.number() + 1, start + count),
+            };
+            verify_range(&storage, start, end)
+        })
+        .await??;
+
+        self.status.update(|progress| {
+            progress.verified_blocks += batch.verified;
+            progress.corrupted_blocks += batch.corrupted.len() as u64;
+            if let Some(next) = batch.next {
+                progress.current_block = next;
+            }
+        });
+
+        for block_id in batch.corrupted {
+            match self.repair_block(&block_id).await {
+                Ok(()) => {
+                    info!(block_id = %block_id, "repaired corrupted block");
+                    self.status.update(|progress| progress.repaired_blocks += 1);
+                }
+                Err(err) => {
+                    error!(block_id = %block_id, error = ?err, "failed to repair corrupted block")
+                }
+            }
+        }
+
+        // headers-only nodes never store state updates.
+        let missing_state_update = if self.headers_only {
+            Vec::default()
+        } else {
+            batch.missing_state_update
+        };
+        for block_id in missing_state_update {
+            match self.backfill_state_update(&block_id).await {
+                Ok(()) => {
+                    info!(block_id = %block_id, "ingested missing state update");
+                    self.status
+                        .update(|progress| progress.backfilled_state_updates += 1);
+                }
+                Err(err) => {
+                    error!(block_id = %block_id, error = ?err, "failed to ingest missing state update")
+                }
+            }
+        }
+
+        Ok(batch.next)
+    }
+
+    /// Fetches the state update of the block from the provider and stores it.
+    async fn backfill_state_update(
+        &self,
+        block_id: &GlobalBlockId,
+    ) -> Result<(), BlockIngestionError> {
+        let mut partial = self
+            .storage
+            .read_partial_block(block_id)?
+            .unwrap_or_default();
+        let mut txn = self.storage.begin_txn()?;
+        let has_classes = self
+            .downloader
+            .ingest_state_update(block_id, &mut txn)
+            .await?;
+        partial.missing_state_update = false;
+        partial.missing_classes = !has_classes;
+        txn.write_partial_block(block_id, &partial)?;
+        txn.update_checksum(block_id)?;
+        txn.commit()?;
+        Ok(())
+    }
+
+    /// Fetches the block from the provider and overwrites its data.
+    async fn repair_block(&self, block_id: &GlobalBlockId) -> Result<(), BlockIngestionError> {
+        let (status, header, body) = self
+            .provider
+            .get_block(&BlockId::Hash(*block_id.hash()))
+            .await
+            .map_err(BlockIngestionError::provider)?;
+        if GlobalBlockId::from_block_header(&header)? != *block_id {
+            return Err(BlockIngestionError::InconsistentDatabase);
+        }
+
+        let mut txn = self.storage.begin_txn()?;
+        self.downloader
+            .finish_ingesting_block(block_id, status, header, body, &mut txn)
+            .await?;
+        txn.update_checksum(block_id)?;
+        txn.commit()?;
+        Ok(())
+    }
+}
+
+/// Verifies the blocks in the given range.
+fn verify_range<E: EnvironmentKind>(
+    storage: &DatabaseStorage<E>,
+    start: u64,
+    end: u64,
+) -> Result<VerifiedBatch, libmdbx::Error> {
+    let mut batch = VerifiedBatch::default();
+    if start >= end {
+        return Ok(batch);
+    }
+
+    for number in start..end {
+        let block_id = match storage.canonical_block_id(number)? {
+            // blocks were moved to an archive shard or removed.
+            None => return Ok(batch),
+            Some(block_id) => block_id,
+        };
+        let is_corrupted = match storage.verify_checksum(&block_id)? {
+            ChecksumStatus::Valid => false,
+            ChecksumStatus::Missing { .. } => {
+                let mut txn = storage.begin_txn()?;
+                txn.update_checksum(&block_id)?;
+                txn.commit()?;
+                false
+            }
+            ChecksumStatus::Mismatch { expected, actual } => {
+                warn!(
+                    block_id = %block_id,
+                    expected = %expected,
+                    actual = %actual,
+                    "block data doesn't match checksum"
+                );
+                true
+            }
+        };
+        // repairing a corrupted block also ingests its state update.
+        if is_corrupted {
+            batch.corrupted.push(block_id);
+        } else if !storage.has_state_update(&block_id)? {
+            batch.missing_state_update.push(block_id);
+        }
+        batch.verified += 1;
+    }
+    batch.next = Some(end);
+    Ok(batch)
+}
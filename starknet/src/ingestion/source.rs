@@ -0,0 +1,105 @@
+//! Sources of chain data for block ingestion.
+//!
+//! The ingestion state machine and the storage writer only fetch data
+//! through [IngestionSource], so sources other than the RPC (another DNA
+//! node, a file replay) are added by implementing it. Every [Provider] is an
+//! ingestion source, this includes the feeder gateway.
+use apibara_core::starknet::v1alpha2;
+
+use crate::{
+    core::GlobalBlockId,
+    db::BlockBody,
+    provider::{BlockId, Provider, ProviderError},
+};
+
+/// Chain data needed to ingest blocks.
+#[apibara_node::async_trait]
+pub trait IngestionSource: Send + Sync + 'static {
+    type Error: ProviderError;
+
+    /// Get the most recent accepted block number and hash.
+    async fn get_head(&self) -> Result<GlobalBlockId, Self::Error>;
+
+    /// Get the chain id of the network.
+    async fn get_chain_id(&self) -> Result<v1alpha2::FieldElement, Self::Error>;
+
+    /// Get a specific block.
+    ///
+    /// Returns an error for which [ProviderError::is_block_not_found] is true
+    /// if the source doesn't have the block.
+    async fn get_block(
+        &self,
+        id: &BlockId,
+    ) -> Result<(v1alpha2::BlockStatus, v1alpha2::BlockHeader, BlockBody), Self::Error>;
+
+    /// Get state update for a specific block.
+    async fn get_state_update(&self, id: &BlockId) -> Result<v1alpha2::StateUpdate, Self::Error>;
+
+    /// Get receipt for a specific transaction.
+    async fn get_transaction_receipt(
+        &self,
+        hash: &v1alpha2::FieldElement,
+    ) -> Result<v1alpha2::TransactionReceipt, Self::Error>;
+
+    /// Get the JSON definition of the class with the given hash.
+    async fn get_class(
+        &self,
+        id: &BlockId,
+        class_hash: &v1alpha2::FieldElement,
+    ) -> Result<Vec<u8>, Self::Error>;
+
+    /// Get the execution traces of the transactions in a block.
+    async fn get_block_traces(
+        &self,
+        id: &BlockId,
+    ) -> Result<Vec<v1alpha2::TransactionTrace>, Self::Error>;
+}
+
+#[apibara_node::async_trait]
+impl<P> IngestionSource for P
+where
+    P: Provider + Send + Sync + 'static,
+{
+    type Error = <P as Provider>::Error;
+
+    async fn get_head(&self) -> Result<GlobalBlockId, Self::Error> {
+        Provider::get_head(self).await
+    }
+
+    async fn get_chain_id(&self) -> Result<v1alpha2::FieldElement, Self::Error> {
+        Provider::get_chain_id(self).await
+    }
+
+    async fn get_block(
+        &self,
+        id: &BlockId,
+    ) -> Result<(v1alpha2::BlockStatus, v1alpha2::BlockHeader, BlockBody), Self::Error> {
+        Provider::get_block(self, id).await
+    }
+
+    async fn get_state_update(&self, id: &BlockId) -> Result<v1alpha2::StateUpdate, Self::Error> {
+        Provider::get_state_update(self, id).await
+    }
+
+    async fn get_transaction_receipt(
+        &self,
+        hash: &v1alpha2::FieldElement,
+    ) -> Result<v1alpha2::TransactionReceipt, Self::Error> {
+        Provider::get_transaction_receipt(self, hash).await
+    }
+
+    async fn get_class(
+        &self,
+        id: &BlockId,
+        class_hash: &v1alpha2::FieldElement,
+    ) -> Result<Vec<u8>, Self::Error> {
+        Provider::get_class(self, id, class_hash).await
+    }
+
+    async fn get_block_traces(
+        &self,
+        id: &BlockId,
+    ) -> Result<Vec<v1alpha2::TransactionTrace>, Self::Error> {
+        Provider::get_block_traces(self, id).await
+    }
+}
@@ -0,0 +1,112 @@
+//! Metrics of the stages of block ingestion.
+//!
+//! The duration of each stage tells whether ingestion is slowed down by the
+//! provider, the fetch stages, or by local storage, the write and commit
+//! stages.
+use std::{future::Future, time::Instant};
+
+use apibara_node::o11y::{self, Counter, Histogram, KeyValue};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref STAGE_METRICS: StageMetrics = StageMetrics::default();
+}
+
+/// A stage of block ingestion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestionStage {
+    /// Fetch the block header and transactions from the provider.
+    FetchBlock,
+    /// Fetch the transaction receipts from the provider.
+    FetchReceipts,
+    /// Fetch the state update from the provider.
+    FetchStateUpdate,
+    /// Fetch the transaction traces from the provider.
+    FetchTraces,
+    /// Fetch and write the classes declared in the block.
+    FetchClasses,
+    /// Check the block hash against the block content.
+    Validate,
+    /// Write the block data to the storage transaction.
+    Write,
+    /// Commit the storage transaction.
+    Commit,
+}
+
+/// Duration and errors of each ingestion stage.
+pub struct StageMetrics {
+    duration: Histogram<f64>,
+    errors: Counter<u64>,
+}
+
+impl IngestionStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IngestionStage::FetchBlock => "fetch_block",
+            IngestionStage::FetchReceipts => "fetch_receipts",
+            IngestionStage::FetchStateUpdate => "fetch_state_update",
+            IngestionStage::FetchTraces => "fetch_traces",
+            IngestionStage::FetchClasses => "fetch_classes",
+            IngestionStage::Validate => "validate",
+            IngestionStage::Write => "write",
+            IngestionStage::Commit => "commit",
+        }
+    }
+}
+
+impl Default for StageMetrics {
+    fn default() -> Self {
+        let meter = o11y::meter("ingestion");
+        let duration = meter
+            .f64_histogram("ingestion_stage_duration")
+            .with_description("Duration of block ingestion stages, in seconds")
+            .init();
+        let errors = meter
+            .u64_counter("ingestion_stage_errors")
+            .with_description("Number of failed block ingestion stages")
+            .init();
+        StageMetrics { duration, errors }
+    }
+}
+
+impl StageMetrics {
+    /// Runs the ingestion stage `f`, recording its duration and whether it
+    /// failed.
+    pub fn record<T, E>(
+        &self,
+        stage: IngestionStage,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let started_at = Instant::now();
+        let result = f();
+        self.observe(stage, started_at, result.is_ok());
+        result
+    }
+
+    /// Same as [StageMetrics::record], for stages that run asynchronously.
+    pub async fn time<T, E>(
+        &self,
+        stage: IngestionStage,
+        f: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let started_at = Instant::now();
+        let result = f.await;
+        self.observe(stage, started_at, result.is_ok());
+        result
+    }
+
+    fn observe(&self, stage: IngestionStage, started_at: Instant, success: bool) {
+        let cx = o11y::Context::current();
+        let attributes = &[KeyValue::new("stage", stage.name())];
+        self.duration
+            .record(&cx, started_at.elapsed().as_secs_f64(), attributes);
+        if !success {
+            self.errors.add(&cx, 1, attributes);
+        }
+    }
+}
+
+/// Returns the metrics shared by all ingestion tasks.
+pub fn stage_metrics() -> &'static StageMetrics {
+    &STAGE_METRICS
+}
@@ -10,25 +10,28 @@ use crate::{
     core::{BlockHash, GlobalBlockId},
     db::{DatabaseStorage, StorageReader, StorageWriter},
     ingestion::finalized::FinalizedBlockIngestion,
-    provider::{BlockId, Provider, ProviderError},
+    provider::{BlockId, ProviderError},
 };
 
 use super::{
-    accepted::AcceptedBlockIngestion, config::BlockIngestionConfig, downloader::Downloader,
-    error::BlockIngestionError, subscription::IngestionStreamPublisher,
+    accepted::AcceptedBlockIngestion, config::BlockIngestionConfig, control::IngestionControl,
+    downloader::Downloader, error::BlockIngestionError, lag::IngestionProgress,
+    reorg::recover_from_reorg, source::IngestionSource, subscription::IngestionStreamPublisher,
 };
 
-pub struct StartedBlockIngestion<G: Provider + Send, E: EnvironmentKind> {
+pub struct StartedBlockIngestion<G: IngestionSource, E: EnvironmentKind> {
     config: BlockIngestionConfig,
     provider: Arc<G>,
     downloader: Downloader<G>,
     storage: DatabaseStorage<E>,
     publisher: IngestionStreamPublisher,
+    progress: IngestionProgress,
+    control: IngestionControl,
 }
 
 impl<G, E> StartedBlockIngestion<G, E>
 where
-    G: Provider + Send,
+    G: IngestionSource,
     E: EnvironmentKind,
 {
     pub fn new(
@@ -36,14 +39,26 @@ where
         storage: DatabaseStorage<E>,
         config: BlockIngestionConfig,
         publisher: IngestionStreamPublisher,
+        progress: IngestionProgress,
+        control: IngestionControl,
     ) -> Self {
-        let downloader = Downloader::new(provider.clone(), config.rpc_concurrency);
+        let downloader = Downloader::new(provider.clone(), config.rpc_concurrency)
+            .with_traces(config.ingest_traces)
+            .with_classes(config.ingest_classes)
+            .with_headers_only(config.headers_only)
+            .with_missing_data_tolerance(config.tolerate_missing_data)
+            .with_journal(config.journal.clone())
+            .with_block_hash_validation(config.block_hash_validation)
+            .with_l1_message_resolver(config.l1_message_resolver.clone())
+            .with_emitter_stats(config.emitter_stats.clone());
         StartedBlockIngestion {
             config,
             provider,
             storage,
             downloader,
             publisher,
+            progress,
+            control,
         }
     }
 
@@ -51,7 +66,7 @@ where
         loop {
             let latest_indexed = match self.storage.highest_accepted_block()? {
                 Some(block) => block,
-                None => self.ingest_genesis_block().await?,
+                None => self.ingest_starting_block().await?,
             };
 
             info!(
@@ -63,15 +78,19 @@ where
             // on the status of the latest indexed block.
             let status = self.block_status(&latest_indexed).await?;
             if status.is_rejected() {
-                // remove block from canonical chain (but not storage) and
-                // try again.
+                // remove blocks from canonical chain (but not storage) up
+                // to the common ancestor and try again.
                 debug!(
                     id = %latest_indexed,
                     "block was rejected while offline"
                 );
-                let mut txn = self.storage.begin_txn()?;
-                txn.reject_block_from_canonical_chain(&latest_indexed)?;
-                txn.commit()?;
+                recover_from_reorg(
+                    &*self.provider,
+                    &self.storage,
+                    &self.publisher,
+                    latest_indexed,
+                )
+                .await?;
             } else if status.is_accepted() {
                 return self
                     .into_accepted_block_ingestion()
@@ -87,11 +106,25 @@ where
     }
 
     fn into_accepted_block_ingestion(self) -> AcceptedBlockIngestion<G, E> {
-        AcceptedBlockIngestion::new(self.provider, self.storage, self.config, self.publisher)
+        AcceptedBlockIngestion::new(
+            self.provider,
+            self.storage,
+            self.config,
+            self.publisher,
+            self.progress,
+            self.control,
+        )
     }
 
     fn into_finalized_block_ingestion(self) -> FinalizedBlockIngestion<G, E> {
-        FinalizedBlockIngestion::new(self.provider, self.storage, self.config, self.publisher)
+        FinalizedBlockIngestion::new(
+            self.provider,
+            self.storage,
+            self.config,
+            self.publisher,
+            self.progress,
+            self.control,
+        )
     }
 
     async fn block_status(
@@ -120,7 +153,7 @@ where
                 let block_hash: BlockHash = header.block_hash.unwrap_or_default().into();
                 info!(
                     block_hash = ?block_hash,
-                    block_number = %global_id.number(),
+                    block_number = global_id.number(),
                     "block hash for block by number"
                 );
 
@@ -134,10 +167,14 @@ where
         }
     }
 
+    /// Ingests the first block into an empty database.
+    ///
+    /// The block is the genesis block, unless a starting block is configured.
     #[tracing::instrument(skip(self))]
-    async fn ingest_genesis_block(&self) -> Result<GlobalBlockId, BlockIngestionError> {
-        info!("ingest genesis block");
-        let block_id = BlockId::Number(0);
+    async fn ingest_starting_block(&self) -> Result<GlobalBlockId, BlockIngestionError> {
+        let starting_block = self.config.starting_block;
+        info!(block_number = starting_block, "ingest starting block");
+        let block_id = BlockId::Number(starting_block);
         let (status, header, body) = self
             .provider
             .get_block(&block_id)
@@ -145,7 +182,7 @@ where
             .map_err(BlockIngestionError::provider)?;
 
         let global_id = GlobalBlockId::from_block_header(&header)?;
-        info!(id = %global_id, "genesis block");
+        info!(id = %global_id, "starting block");
 
         let mut txn = self.storage.begin_txn()?;
         self.downloader
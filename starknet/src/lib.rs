@@ -1,31 +1,69 @@
+pub mod admin;
+pub mod backup;
+pub mod chain;
+pub mod cli;
 pub mod core;
 pub mod db;
+pub mod export;
 pub mod healer;
+pub mod import;
 pub mod ingestion;
+pub mod limiter;
 pub mod node;
+pub mod profiling;
 pub mod provider;
+pub mod runtime_config;
 pub mod server;
+pub mod status;
 pub mod stream;
 pub mod websocket;
 
+pub use crate::db::EncryptionKey;
+pub use crate::ingestion::{BlockHashValidation, ErrorClass, RetryPolicy, ScrubConfig};
+pub use crate::limiter::RpcLimits;
 pub use crate::node::StarkNetNode;
 pub use crate::provider::HttpProvider;
+pub use crate::server::{ServerExtension, TlsConfig};
 
 pub use apibara_node::{
     db::libmdbx::NoWriteMap,
-    server::{MetadataKeyRequestObserver, SimpleRequestObserver},
+    server::{MetadataKeyRequestObserver, MeteredRequestObserver, SimpleRequestObserver},
 };
 
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
-use anyhow::Result;
-use apibara_node::db::default_data_dir;
-use clap::Args;
+use anyhow::{Context, Result};
+use apibara_node::{
+    db::{default_data_dir, BackupConfig, BackupUploader, MaintenanceWindow, MdbxGeometry},
+    server::{GrpcMeterBackend, LogMeterBackend, OtlpMeterBackend, PrometheusMeterBackend},
+    stream::BatchSizePolicy,
+};
+use clap::{Args, ValueEnum};
+use ipnet::IpNet;
 use tempdir::TempDir;
 use tokio_util::sync::CancellationToken;
+use tonic::codegen::http::HeaderValue;
 use tracing::info;
+use url::Url;
 
-#[derive(Clone, Debug, Args)]
+use crate::backup::HttpBackupUploader;
+use crate::db::{CommandKeyProvider, FileKeyProvider, KeyProvider};
+use crate::ingestion::EmitterStatsConfig;
+use crate::profiling::ProfilingConfig;
+use crate::server::{
+    auth::{GrpcValidator, JwksValidator, StaticKeyValidator},
+    parse_ip_net,
+    quota::{GrpcQuotaBackend, RedisQuotaBackend},
+    AuditRotation, BillingBackend, BillingConfig, ConnectionConfig, FileAuditSink,
+    GrpcBillingBackend, GrpcWebConfig, HttpBillingBackend, IpFilterConfig, LogAuditSink,
+    MessageSizeConfig, QuotaConfig, QuotaLimits, RateLimit, RateLimitConfig, ReloadConfig,
+    ReloadableKeys, StreamLimits, StreamMetricsConfig, SyslogAuditSink, SyslogTarget,
+};
+use crate::stream::{
+    FilterRestrictions, LoadSheddingConfig, PriorityConfig, PriorityTier, SlowBatchConfig,
+};
+
+#[derive(Clone, Debug, Default, Args)]
 pub struct StartArgs {
     /// StarkNet RPC address.
     #[arg(long, env)]
@@ -45,9 +83,544 @@ pub struct StartArgs {
     /// Use the specified metadata key for tracing and metering.
     #[arg(long, env)]
     pub use_metadata: Vec<String>,
-    // Websocket address
+    /// Send request counters to these backends, comma separated.
+    ///
+    /// Defaults to OpenTelemetry.
+    #[arg(long, env, value_enum, value_delimiter = ',')]
+    pub meter: Vec<MeterKind>,
+    /// Url of the `Usage` gRPC service used by the `grpc` meter.
+    #[arg(long, env)]
+    pub meter_service_url: Option<String>,
+    /// Serve the data stream over WebSocket at this address. Data is
+    /// sent as JSON, or as protobuf with `/ws?format=binary`.
     #[arg(long, env)]
     pub websocket_address: Option<String>,
+    /// Admin API address. The admin API is disabled if not set.
+    #[arg(long, env)]
+    pub admin_address: Option<String>,
+    /// Serve CPU and heap profiles at `/debug/pprof/profile` and
+    /// `/debug/pprof/heap` of the admin API to requests with the admin token.
+    #[arg(long, env, requires = "admin_token")]
+    pub admin_profiling: bool,
+    /// Maximum duration of CPU profiles, in seconds. Defaults to 120.
+    #[arg(long, env, requires = "admin_profiling")]
+    pub admin_profiling_max_secs: Option<u64>,
+    /// Require this bearer token for the admin API routes that change the
+    /// state of the node, like backups and ingestion control, or that
+    /// expose it, like the effective configuration at `/config` and
+    /// profiles. These routes are disabled if not set.
+    #[arg(long, env, requires = "admin_address")]
+    pub admin_token: Option<String>,
+    /// Write the backups started from the admin API to a new directory
+    /// inside this directory.
+    #[arg(long, env, requires = "admin_token")]
+    pub backup_dir: Option<PathBuf>,
+    /// Upload completed backups with a HTTP PUT request to
+    /// `<url>/<name>/mdbx.dat`.
+    #[arg(long, env, requires = "backup_dir")]
+    pub backup_upload_url: Option<String>,
+    /// Serve the node status as JSON at `/status` on this address, for load
+    /// balancers and uptime checks. Unlike the admin API, it can be exposed
+    /// publicly.
+    #[arg(long, env)]
+    pub status_address: Option<String>,
+    /// Bootstrap finalized blocks from the DNA node at this url, then
+    /// continue ingesting from the RPC. The node must serve block sync with
+    /// `--block-sync`.
+    #[arg(long, env)]
+    pub bootstrap_node: Option<String>,
+    /// Start ingesting from this block instead of genesis, if the database is
+    /// empty. Earlier blocks are not available to streams.
+    #[arg(long, env)]
+    pub starting_block: Option<u64>,
+    /// Don't ingest the pending block.
+    ///
+    /// Saves RPC requests on nodes that only serve accepted and finalized data.
+    #[arg(long, env)]
+    pub disable_pending: bool,
+    /// Minimum interval between requests for the pending block, in
+    /// milliseconds. Defaults to 3 seconds.
+    #[arg(long, env)]
+    pub pending_poll_interval_ms: Option<u64>,
+    /// Only ingest block headers, for nodes serving header streams.
+    #[arg(long, env, conflicts_with = "ingest_traces")]
+    pub headers_only: bool,
+    /// Ingest transaction traces, including internal calls.
+    ///
+    /// Requires an RPC provider that supports the trace api.
+    #[arg(long, env)]
+    pub ingest_traces: bool,
+    /// Check block hashes against the block content: `disabled`, `warn`
+    /// logs mismatching blocks and `strict` rejects them.
+    ///
+    /// Blocks produced before Starknet v0.7 never match.
+    #[arg(long, env, value_enum, default_value_t = BlockHashValidation::Disabled)]
+    pub block_hash_validation: BlockHashValidation,
+    /// Ingest the definition of declared classes, streamed as declared
+    /// classes data.
+    #[arg(long, env)]
+    pub ingest_classes: bool,
+    /// Ingest from a Starknet appchain or local devnet (Madara, Katana).
+    ///
+    /// Implies `--instant-finality`, tolerates blocks without state update
+    /// or traces and disables block hash validation.
+    #[arg(long, env)]
+    pub appchain: bool,
+    /// Consider blocks finalized as soon as they're accepted, for chains
+    /// that don't settle on L1.
+    #[arg(long, env)]
+    pub instant_finality: bool,
+    /// Maximum number of requests per second sent to the RPC.
+    #[arg(long, env)]
+    pub rpc_requests_per_second: Option<u32>,
+    /// Maximum number of concurrent requests sent to the RPC.
+    #[arg(long, env)]
+    pub rpc_max_in_flight: Option<usize>,
+    /// Adapt the number of concurrent requests sent to the RPC, up to
+    /// `--rpc-max-in-flight`, to keep latency below this target (in ms).
+    #[arg(long, env)]
+    pub rpc_target_latency_ms: Option<u64>,
+    /// StarkNet RPC WebSocket address. If set, the node subscribes to new
+    /// heads instead of waiting for the next poll.
+    #[arg(long, env)]
+    pub rpc_ws: Option<String>,
+    /// Ingest blocks, state updates and receipts from the feeder gateway of
+    /// this network (`mainnet`, `goerli` or `goerli2`), or at this url.
+    ///
+    /// Traces are still fetched from the RPC.
+    #[arg(long, env)]
+    pub feeder_gateway: Option<String>,
+    /// Refuse to ingest data if the RPC doesn't serve this network
+    /// (`mainnet`, `goerli` or `goerli2`), or the network with this chain id,
+    /// either hex or a short string such as `KATANA`.
+    ///
+    /// The chain id of the first RPC is stored in the database and always
+    /// checked.
+    #[arg(long, env)]
+    pub network: Option<String>,
+    /// Cross-check accepted blocks with the RPC at this address. Blocks are
+    /// not accepted until both providers agree on their hash.
+    #[arg(long, env)]
+    pub quorum_rpc: Option<String>,
+    /// Look up the L1 transaction that sent the message consumed by L1
+    /// handler transactions on the Ethereum RPC at this address.
+    #[arg(long, env)]
+    pub ethereum_rpc: Option<String>,
+    /// Address of the Starknet core contract on Ethereum, used together with
+    /// `--ethereum-rpc` and `--l1-finality-rpc`. Defaults to the mainnet
+    /// contract.
+    #[arg(long, env)]
+    pub starknet_core_contract: Option<String>,
+    /// Only consider blocks finalized once the Starknet core contract, read
+    /// on the Ethereum RPC at this address, accepted their state update.
+    ///
+    /// The status reported by the Starknet RPC is ignored.
+    #[arg(long, env)]
+    pub l1_finality_rpc: Option<String>,
+    /// Ingest the L1 data availability of finalized blocks, fetching blobs
+    /// from the Ethereum beacon node api at this address.
+    ///
+    /// State updates are read on `--ethereum-rpc`.
+    #[arg(long, env, requires = "ethereum_rpc")]
+    pub beacon_api: Option<String>,
+    /// First L1 block searched for state updates by data availability
+    /// ingestion, used until a block has data availability.
+    #[arg(long, env)]
+    pub data_availability_l1_starting_block: Option<u64>,
+    /// Serve the stream over TLS with the PEM encoded certificate chain in
+    /// this file.
+    #[arg(long, env, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM encoded private key of the TLS certificate.
+    #[arg(long, env, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+    /// Check the TLS certificate and key for changes every this many
+    /// seconds, reloading them without restarting the node.
+    #[arg(long, env, requires = "tls_cert")]
+    pub tls_reload_interval_secs: Option<u64>,
+    /// Require clients to authenticate with one of the keys in this file.
+    ///
+    /// Each line contains the caller identity and its key, separated by
+    /// whitespace.
+    #[arg(long, env, conflicts_with_all = ["auth_jwks_url", "auth_validator_url"])]
+    pub auth_keys_file: Option<PathBuf>,
+    /// Check the keys file, the filter restrictions file and the quota file
+    /// for changes every this many seconds. Streams no longer allowed after a
+    /// change are terminated, the others continue.
+    #[arg(long, env)]
+    pub reload_interval_secs: Option<u64>,
+    /// Require clients to authenticate with a JWT signed by one of the keys
+    /// published at this url. The caller identity is the `sub` claim.
+    #[arg(long, env, conflicts_with = "auth_validator_url")]
+    pub auth_jwks_url: Option<Url>,
+    /// Only accept JWTs issued by this issuer.
+    #[arg(long, env, requires = "auth_jwks_url")]
+    pub auth_jwt_issuer: Option<String>,
+    /// Only accept JWTs for this audience.
+    #[arg(long, env, requires = "auth_jwks_url")]
+    pub auth_jwt_audience: Option<String>,
+    /// Require clients to authenticate with a token validated by the `Auth`
+    /// gRPC service at this url.
+    #[arg(long, env)]
+    pub auth_validator_url: Option<String>,
+    /// Limit the data units streamed by each authenticated caller per day.
+    #[arg(long, env)]
+    pub quota_daily_data_units: Option<u64>,
+    /// Limit the data units streamed by each authenticated caller per
+    /// month.
+    #[arg(long, env)]
+    pub quota_monthly_data_units: Option<u64>,
+    /// Load the quota of specific callers from this file.
+    ///
+    /// Each line contains the caller identity, its daily limit and its
+    /// monthly limit. Use `-` for no limit.
+    #[arg(long, env)]
+    pub quota_file: Option<PathBuf>,
+    /// Share quota usage with other nodes through the Redis server at this
+    /// url, instead of storing it in the node database.
+    #[arg(long, env, conflicts_with = "quota_service_url")]
+    pub quota_redis_url: Option<String>,
+    /// Share quota usage with other nodes through the `Quota` gRPC service
+    /// at this url, instead of storing it in the node database.
+    #[arg(long, env)]
+    pub quota_service_url: Option<String>,
+    /// Limit new connections from each client IP to this many per second.
+    #[arg(long, env)]
+    pub rate_limit_connections_per_ip: Option<f64>,
+    /// Limit new streams from each client IP to this many per second.
+    #[arg(long, env)]
+    pub rate_limit_streams_per_ip: Option<f64>,
+    /// Limit new streams of each authenticated caller to this many per
+    /// second.
+    #[arg(long, env)]
+    pub rate_limit_streams_per_key: Option<f64>,
+    /// Allow bursts of up to this many new connections or streams. Defaults
+    /// to one second worth of them.
+    #[arg(long, env)]
+    pub rate_limit_burst: Option<u32>,
+    /// Limit the number of streams open at the same time on the node.
+    #[arg(long, env)]
+    pub max_streams: Option<usize>,
+    /// Limit the number of streams open at the same time by each
+    /// authenticated caller.
+    #[arg(long, env)]
+    pub max_streams_per_key: Option<usize>,
+    /// Close streams whose client didn't send a configuration or read data
+    /// for this many seconds.
+    #[arg(long, env)]
+    pub stream_idle_timeout_secs: Option<u64>,
+    /// End streams after they sent this many data units. Clients resume
+    /// from the cursor returned in the error details.
+    #[arg(long, env)]
+    pub max_data_units_per_session: Option<u64>,
+    /// Send HTTP/2 keepalive pings to clients every this many seconds.
+    /// Keeps long-lived streams open through load balancers that drop idle
+    /// connections.
+    #[arg(long, env)]
+    pub keepalive_interval_secs: Option<u64>,
+    /// Close connections whose client doesn't acknowledge a keepalive ping
+    /// within this many seconds.
+    #[arg(long, env, requires = "keepalive_interval_secs")]
+    pub keepalive_timeout_secs: Option<u64>,
+    /// Close connections older than this many seconds, so that clients
+    /// reconnect to another node.
+    #[arg(long, env)]
+    pub max_connection_age_secs: Option<u64>,
+    /// Only accept clients from this network, in CIDR notation. Can be
+    /// repeated. Accepts clients from any network if not set.
+    #[arg(long, env, value_parser = parse_ip_net)]
+    pub ip_allow: Vec<IpNet>,
+    /// Reject clients from this network, in CIDR notation. Can be repeated.
+    #[arg(long, env, value_parser = parse_ip_net)]
+    pub ip_deny: Vec<IpNet>,
+    /// Trust the `x-forwarded-for` header of requests sent by the proxy in
+    /// this network. Can be repeated.
+    #[arg(long, env, value_parser = parse_ip_net)]
+    pub trusted_proxy: Vec<IpNet>,
+    /// Expect a PROXY protocol (v1 or v2) header at the start of each
+    /// connection from a `--trusted-proxy`, and use the client address it
+    /// contains. Connections from the proxies without the header are closed.
+    #[arg(long, env)]
+    pub proxy_protocol: bool,
+    /// Serve finalized blocks to other nodes bootstrapping with
+    /// `--bootstrap-node`. Block sync is not metered, so enable it only on
+    /// nodes reachable by trusted peers.
+    #[arg(long, env)]
+    pub block_sync: bool,
+    /// Read the priority tier of callers from this file. Batches of streams
+    /// with a higher tier are produced first when the node is busy.
+    ///
+    /// Each line contains the caller identity and its tier: `low`, `normal`
+    /// or `high`.
+    #[arg(long, env)]
+    pub priority_tiers_file: Option<PathBuf>,
+    /// Tier of callers not in the priority tiers file. Defaults to `normal`.
+    #[arg(long, env, requires = "priority_tiers_file")]
+    pub default_priority_tier: Option<PriorityTier>,
+    /// Produce at most this many batches at the same time when priority
+    /// tiers are enabled. Defaults to the number of CPUs.
+    #[arg(long, env, requires = "priority_tiers_file")]
+    pub max_concurrent_batches: Option<usize>,
+    /// Batch size of clients that don't request one. Defaults to 20.
+    #[arg(long, env)]
+    pub default_batch_size: Option<usize>,
+    /// Raise the batch size requested by clients to at least this value.
+    /// Defaults to 1.
+    #[arg(long, env)]
+    pub min_batch_size: Option<usize>,
+    /// Lower the batch size requested by clients to at most this value.
+    /// Defaults to 50.
+    #[arg(long, env)]
+    pub max_batch_size: Option<usize>,
+    /// Split batches into messages with at most this many data units.
+    #[arg(long, env)]
+    pub max_data_units_per_message: Option<u64>,
+    /// Split the data sent to clients into messages of at most this many
+    /// bytes. Streams fail with an error naming the block if a single block
+    /// is larger.
+    #[arg(long, env)]
+    pub max_response_size: Option<usize>,
+    /// Reject requests larger than this many bytes. Defaults to 4 MiB.
+    #[arg(long, env)]
+    pub max_request_size: Option<usize>,
+    /// Restrict the filters of callers with the restrictions in this JSON
+    /// file, for example to the data of some contracts only.
+    #[arg(long, env)]
+    pub filter_restrictions_file: Option<PathBuf>,
+    /// Shed load when the node uses more than this percentage of the
+    /// available CPU time.
+    #[arg(long, env)]
+    pub shed_load_cpu_percent: Option<f64>,
+    /// Shed load when the node uses more than this many megabytes of memory.
+    #[arg(long, env)]
+    pub shed_load_memory_mb: Option<u64>,
+    /// Shed load when reading a block from storage takes longer than this
+    /// many milliseconds on average.
+    #[arg(long, env)]
+    pub shed_load_read_latency_ms: Option<u64>,
+    /// While shedding load, slow down the backfills of streams with this
+    /// priority tier or lower. Defaults to `normal`.
+    #[arg(long, env)]
+    pub shed_load_max_slowed_tier: Option<PriorityTier>,
+    /// Report the usage of each caller to the `Usage` gRPC service at this
+    /// url.
+    #[arg(long, env, conflicts_with = "billing_http_url")]
+    pub billing_grpc_url: Option<String>,
+    /// Post the usage of each caller as JSON to this url.
+    #[arg(long, env)]
+    pub billing_http_url: Option<Url>,
+    /// Report usage to the billing service every this many seconds.
+    /// Defaults to 60 seconds.
+    #[arg(long, env)]
+    pub billing_interval_secs: Option<u64>,
+    /// Record the blocks, bytes, lag and batch latency of each stream,
+    /// labeled by caller, stream name and finality.
+    #[arg(long, env)]
+    pub stream_metrics: bool,
+    /// Don't label stream metrics with the caller identity.
+    #[arg(long, env, requires = "stream_metrics")]
+    pub stream_metrics_no_caller_label: bool,
+    /// Report streams beyond this many distinct caller and stream name
+    /// pairs under the `other` label. Defaults to 100.
+    #[arg(long, env, requires = "stream_metrics")]
+    pub stream_metrics_max_label_sets: Option<usize>,
+    /// Log a warning with the filter hash, block range and timing breakdown
+    /// of batches that take longer than this many milliseconds to produce.
+    #[arg(long, env)]
+    pub slow_batch_threshold_ms: Option<u64>,
+    /// Log a warning for batches that take longer than this many
+    /// milliseconds to send to the client.
+    #[arg(long, env)]
+    pub slow_send_threshold_ms: Option<u64>,
+    /// Log when streams are opened, configured and closed, together with
+    /// the data they sent.
+    #[arg(long, env, conflicts_with_all = ["audit_log_file", "audit_syslog"])]
+    pub audit_log: bool,
+    /// Append stream audit events to this file, one JSON object per line.
+    #[arg(long, env, conflicts_with = "audit_syslog")]
+    pub audit_log_file: Option<PathBuf>,
+    /// Rotate the audit file once it reaches this size, in MiB.
+    #[arg(long, env, requires = "audit_log_file")]
+    pub audit_log_max_size_mb: Option<u64>,
+    /// Number of rotated audit files kept. Defaults to 10.
+    #[arg(long, env, requires = "audit_log_max_size_mb")]
+    pub audit_log_max_files: Option<usize>,
+    /// Send stream audit events to syslog, either to a local socket like
+    /// `/dev/log` or to a server like `udp://syslog:514`.
+    #[arg(long, env)]
+    pub audit_syslog: Option<SyslogTarget>,
+    /// Syslog facility code of audit events. Defaults to 16, `local0`.
+    #[arg(long, env, requires = "audit_syslog")]
+    pub audit_syslog_facility: Option<u8>,
+    /// Accept grpc-web requests, so that browsers can stream data without a
+    /// proxy.
+    #[arg(long, env)]
+    pub grpc_web: bool,
+    /// Only accept grpc-web requests from this origin. Can be repeated.
+    /// Accepts requests from any origin if not set.
+    #[arg(long, env, requires = "grpc_web")]
+    pub grpc_web_allowed_origin: Vec<String>,
+    /// Let the authenticated caller with this identity list, terminate, and
+    /// reposition streams through the admin gRPC service. Can be repeated.
+    #[arg(long, env)]
+    pub admin_operator: Vec<String>,
+    /// Verify the checksum of up to this many finalized blocks per second,
+    /// ingesting corrupted blocks again.
+    ///
+    /// Block verification is disabled if not set.
+    #[arg(long, env)]
+    pub scrub_blocks_per_second: Option<u64>,
+    /// Report ingestion as unhealthy if it falls more than this many blocks
+    /// behind the RPC head, after it reached the head once.
+    #[arg(long, env)]
+    pub ingestion_max_lag_blocks: Option<u64>,
+    /// Report ingestion as unhealthy if the finalized block falls more than
+    /// this many blocks behind the node head.
+    #[arg(long, env)]
+    pub ingestion_max_finalized_lag_blocks: Option<u64>,
+    /// Track the contracts that emit the most events, and report this many
+    /// of them as metrics and from the admin API.
+    #[arg(long, env)]
+    pub top_emitters: Option<usize>,
+    /// Length, in seconds, of each of the two periods of the rolling window
+    /// over which emitters are ranked.
+    #[arg(long, env, requires = "top_emitters")]
+    pub top_emitters_period_secs: Option<u64>,
+    /// Store a block without its state update, traces and classes after
+    /// this many failed attempts at ingesting it, and continue with the
+    /// following blocks.
+    ///
+    /// Such blocks are listed by the admin API, which can retry them.
+    #[arg(long, env)]
+    pub ingestion_journal_max_attempts: Option<u32>,
+    /// Pause ingestion while less than this many GiB are available to the
+    /// database, instead of failing when the disk is full.
+    #[arg(long, env)]
+    pub min_free_disk_space_gib: Option<u64>,
+    /// Slow down ingestion while less than this many GiB are available to
+    /// the database.
+    #[arg(long, env, requires = "min_free_disk_space_gib")]
+    pub throttle_free_disk_space_gib: Option<u64>,
+    /// Serve old blocks from the archive shard at this path. Can be repeated.
+    #[arg(long, env)]
+    pub archive_shard: Vec<PathBuf>,
+    #[command(flatten)]
+    pub mdbx: MdbxArgs,
+    #[command(flatten)]
+    pub maintenance: MaintenanceArgs,
+    #[command(flatten)]
+    pub encryption: EncryptionArgs,
+    #[command(flatten)]
+    pub retry: RetryArgs,
+    #[command(flatten)]
+    pub events_backfill: EventsBackfillArgs,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct EventsBackfillArgs {
+    /// Backfill finalized blocks from `starknet_getEvents` before ingesting
+    /// full blocks.
+    ///
+    /// Backfilled blocks only contain the header, the transaction hashes and
+    /// the events matching the backfill filter. Streams that filter on other
+    /// data are rejected when they reach these blocks.
+    #[arg(long, env)]
+    pub events_backfill: bool,
+    /// Only backfill events emitted by this contract.
+    #[arg(long, env)]
+    pub events_backfill_address: Option<String>,
+    /// Only backfill events with these keys, comma separated.
+    #[arg(long, env, value_delimiter = ',')]
+    pub events_backfill_keys: Vec<String>,
+    /// Number of events requested in each `starknet_getEvents` page.
+    #[arg(long, env, default_value_t = 1_000)]
+    pub events_backfill_chunk_size: u64,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct MaintenanceArgs {
+    /// Write a compacted copy of the database every day in this UTC window,
+    /// e.g. `02:00-05:00`. The copy is swapped in when the node restarts.
+    ///
+    /// Maintenance is disabled if not set.
+    #[arg(long, env)]
+    pub maintenance_window: Option<MaintenanceWindow>,
+    /// Maximum number of bytes copied per second by maintenance.
+    #[arg(long, env, default_value_t = 64 * 1024 * 1024)]
+    pub maintenance_max_bytes_per_second: u64,
+}
+
+/// Encrypt block data at rest.
+#[derive(Clone, Debug, Default, Args)]
+pub struct EncryptionArgs {
+    /// Encrypt block data with the key in this file.
+    ///
+    /// The file contains the 32 bytes key, either raw or hex encoded.
+    #[arg(long, env)]
+    pub encryption_key_file: Option<PathBuf>,
+    /// Encrypt block data with the key printed by this shell command, either
+    /// raw or hex encoded.
+    ///
+    /// Used to decrypt a key protected by a KMS when the node starts.
+    #[arg(long, env, conflicts_with = "encryption_key_file")]
+    pub encryption_key_command: Option<String>,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct RetryArgs {
+    /// Stop ingestion after this many consecutive failures, and report the node
+    /// as unhealthy. Retry forever if not set.
+    #[arg(long, env)]
+    pub ingestion_max_attempts: Option<u32>,
+    /// Delay before restarting ingestion after the first failure, in milliseconds.
+    #[arg(long, env, default_value_t = 1_000)]
+    pub ingestion_retry_initial_delay_ms: u64,
+    /// Maximum delay between ingestion restarts, in milliseconds.
+    #[arg(long, env, default_value_t = 60_000)]
+    pub ingestion_retry_max_delay_ms: u64,
+    /// Factor applied to the delay after each failure.
+    #[arg(long, env, default_value_t = 2.0)]
+    pub ingestion_retry_multiplier: f64,
+    /// Randomize delays by up to this fraction of their value.
+    #[arg(long, env, default_value_t = 0.1)]
+    pub ingestion_retry_jitter: f64,
+    /// Errors after which ingestion is restarted, comma separated.
+    ///
+    /// Defaults to all errors.
+    #[arg(long, env, value_enum, value_delimiter = ',')]
+    pub ingestion_retry_on: Vec<ErrorClass>,
+}
+
+/// Backend receiving request counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MeterKind {
+    /// Export with OpenTelemetry.
+    Otlp,
+    /// Serve in the Prometheus format at the `/metrics` route of the admin
+    /// API.
+    Prometheus,
+    /// Write structured log events.
+    Log,
+    /// Report to the `Usage` gRPC service at `--meter-service-url`.
+    Grpc,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct MdbxArgs {
+    /// Lower bound of the database size, in GiB.
+    #[arg(long, env, default_value_t = 10)]
+    pub mdbx_min_size_gib: usize,
+    /// Upper bound of the database size, in GiB.
+    ///
+    /// The database file grows automatically up to this size.
+    #[arg(long, env, default_value_t = 4096)]
+    pub mdbx_max_size_gib: usize,
+    /// Grow the database by this amount, in GiB.
+    #[arg(long, env, default_value_t = 2)]
+    pub mdbx_growth_step_gib: usize,
+    /// Shrink the database when this amount of space, in GiB, is unused.
+    #[arg(long, env)]
+    pub mdbx_shrink_threshold_gib: Option<usize>,
 }
 
 /// Connect the cancellation token to the ctrl-c handler.
@@ -61,10 +634,141 @@ pub fn set_ctrlc_handler(ct: CancellationToken) -> Result<()> {
     Ok(())
 }
 
+impl Default for MdbxArgs {
+    fn default() -> Self {
+        MdbxGeometry::default().into()
+    }
+}
+
+impl EncryptionArgs {
+    /// Returns the provider of the encryption key, if block data is
+    /// encrypted.
+    pub fn key_provider(&self) -> Option<Box<dyn KeyProvider>> {
+        if let Some(path) = &self.encryption_key_file {
+            return Some(Box::new(FileKeyProvider::new(path)));
+        }
+        self.encryption_key_command
+            .as_ref()
+            .map(|command| Box::new(CommandKeyProvider::new(command)) as Box<dyn KeyProvider>)
+    }
+}
+
+impl Default for MaintenanceArgs {
+    fn default() -> Self {
+        MaintenanceArgs {
+            maintenance_window: None,
+            maintenance_max_bytes_per_second: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for EventsBackfillArgs {
+    fn default() -> Self {
+        EventsBackfillArgs {
+            events_backfill: false,
+            events_backfill_address: None,
+            events_backfill_keys: Vec::default(),
+            events_backfill_chunk_size: 1_000,
+        }
+    }
+}
+
+impl Default for RetryArgs {
+    fn default() -> Self {
+        RetryPolicy::default().into()
+    }
+}
+
+impl From<RetryPolicy> for RetryArgs {
+    fn from(policy: RetryPolicy) -> Self {
+        RetryArgs {
+            ingestion_max_attempts: policy.max_attempts,
+            ingestion_retry_initial_delay_ms: policy.initial_delay.as_millis() as u64,
+            ingestion_retry_max_delay_ms: policy.max_delay.as_millis() as u64,
+            ingestion_retry_multiplier: policy.multiplier,
+            ingestion_retry_jitter: policy.jitter,
+            ingestion_retry_on: policy.retryable,
+        }
+    }
+}
+
+impl From<RetryArgs> for RetryPolicy {
+    fn from(args: RetryArgs) -> Self {
+        let retryable = if args.ingestion_retry_on.is_empty() {
+            RetryPolicy::default().retryable
+        } else {
+            args.ingestion_retry_on
+        };
+        RetryPolicy {
+            max_attempts: args.ingestion_max_attempts,
+            initial_delay: Duration::from_millis(args.ingestion_retry_initial_delay_ms),
+            max_delay: Duration::from_millis(args.ingestion_retry_max_delay_ms),
+            multiplier: args.ingestion_retry_multiplier,
+            jitter: args.ingestion_retry_jitter,
+            retryable,
+        }
+    }
+}
+
+impl From<MdbxGeometry> for MdbxArgs {
+    fn from(geometry: MdbxGeometry) -> Self {
+        MdbxArgs {
+            mdbx_min_size_gib: geometry.min_size_gib,
+            mdbx_max_size_gib: geometry.max_size_gib,
+            mdbx_growth_step_gib: geometry.growth_step_gib,
+            mdbx_shrink_threshold_gib: geometry.shrink_threshold_gib,
+        }
+    }
+}
+
+impl From<MdbxArgs> for MdbxGeometry {
+    fn from(args: MdbxArgs) -> Self {
+        MdbxGeometry {
+            min_size_gib: args.mdbx_min_size_gib,
+            max_size_gib: args.mdbx_max_size_gib,
+            growth_step_gib: args.mdbx_growth_step_gib,
+            shrink_threshold_gib: args.mdbx_shrink_threshold_gib,
+        }
+    }
+}
+
+/// How often the `grpc` meter reports usage.
+const USAGE_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
 pub async fn start_node(args: StartArgs, cts: CancellationToken) -> Result<()> {
+    let meters = if args.meter.is_empty() {
+        vec![MeterKind::Otlp]
+    } else {
+        args.meter.clone()
+    };
+    let mut observer = MeteredRequestObserver::new(args.use_metadata.clone());
+    let mut prometheus_meter = None;
+    for meter in meters {
+        observer = match meter {
+            MeterKind::Otlp => observer.with_backend(Arc::new(OtlpMeterBackend::default())),
+            MeterKind::Log => observer.with_backend(Arc::new(LogMeterBackend::default())),
+            MeterKind::Prometheus => {
+                let prometheus = Arc::new(PrometheusMeterBackend::new(&args.use_metadata)?);
+                prometheus_meter = Some(prometheus.clone());
+                observer.with_backend(prometheus)
+            }
+            MeterKind::Grpc => {
+                let url = args.meter_service_url.clone().ok_or_else(|| {
+                    anyhow::anyhow!("the grpc meter requires --meter-service-url")
+                })?;
+                let grpc = Arc::new(GrpcMeterBackend::new(url)?);
+                tokio::spawn(grpc.clone().run(USAGE_REPORT_INTERVAL, cts.clone()));
+                observer.with_backend(grpc)
+            }
+        };
+    }
+
     let mut node =
         StarkNetNode::<HttpProvider, SimpleRequestObserver, NoWriteMap>::builder(&args.rpc)?
-            .with_request_observer(MetadataKeyRequestObserver::new(args.use_metadata));
+            .with_request_observer(observer);
+    if let Some(prometheus) = prometheus_meter {
+        node.with_prometheus_meter(prometheus);
+    }
 
     if args.devnet {
         let tempdir = TempDir::new("apibara")?;
@@ -76,7 +780,7 @@ pub async fn start_node(args: StartArgs, cts: CancellationToken) -> Result<()> {
     } else if let Some(name) = args.name {
         let datadir = default_data_dir()
             .map(|p| p.join(name))
-            .expect("no datadir");
+            .context("no default data directory. use --data instead")?;
         node.with_datadir(datadir);
     }
 
@@ -84,6 +788,440 @@ pub async fn start_node(args: StartArgs, cts: CancellationToken) -> Result<()> {
         node.with_websocket_address(websocket_address);
     }
 
+    node.with_geometry(args.mdbx.into());
+
+    if let Some(admin_address) = args.admin_address {
+        node.with_admin_address(admin_address);
+    }
+
+    if args.admin_profiling {
+        let mut profiling = ProfilingConfig::default();
+        if let Some(max_secs) = args.admin_profiling_max_secs {
+            profiling.max_duration = Duration::from_secs(max_secs);
+        }
+        node.with_profiling(profiling);
+    }
+
+    if let Some(token) = args.admin_token {
+        node.with_admin_token(token);
+    }
+
+    if let Some(dir) = args.backup_dir {
+        let uploader = match args.backup_upload_url {
+            None => None,
+            Some(url) => {
+                let uploader: Arc<dyn BackupUploader> =
+                    Arc::new(HttpBackupUploader::new(url.parse()?));
+                Some(uploader)
+            }
+        };
+        node.with_backup(BackupConfig { dir, uploader });
+    }
+
+    if let Some(status_address) = args.status_address {
+        node.with_status_address(status_address);
+    }
+
+    if let Some(window) = args.maintenance.maintenance_window {
+        node.with_maintenance(window, args.maintenance.maintenance_max_bytes_per_second);
+    }
+
+    if let Some(blocks_per_second) = args.scrub_blocks_per_second {
+        node.with_scrub(ScrubConfig { blocks_per_second });
+    }
+
+    if let Some(provider) = args.encryption.key_provider() {
+        node.with_encryption_key(provider.encryption_key()?);
+    }
+
+    if let (Some(cert), Some(key)) = (args.tls_cert, args.tls_key) {
+        let mut tls = TlsConfig::new(cert, key);
+        if let Some(interval) = args.tls_reload_interval_secs {
+            tls = tls.with_reload_interval(Duration::from_secs(interval));
+        }
+        node.with_tls(tls);
+    }
+
+    let has_auth = args.auth_keys_file.is_some()
+        || args.auth_jwks_url.is_some()
+        || args.auth_validator_url.is_some();
+    let mut reloadable_keys = None;
+    if let Some(path) = args.auth_keys_file {
+        if args.reload_interval_secs.is_some() {
+            let keys = ReloadableKeys::load(&path)?;
+            node.with_token_validator(Arc::new(keys.clone()));
+            reloadable_keys = Some(keys);
+        } else {
+            node.with_token_validator(Arc::new(StaticKeyValidator::from_file(&path)?));
+        }
+    }
+
+    if let Some(url) = args.auth_jwks_url {
+        let validator = JwksValidator::new(url)
+            .with_issuer(args.auth_jwt_issuer)
+            .with_audience(args.auth_jwt_audience);
+        node.with_token_validator(Arc::new(validator));
+    }
+
+    if let Some(url) = args.auth_validator_url {
+        node.with_token_validator(Arc::new(GrpcValidator::new(url)?));
+    }
+
+    let has_quota = args.quota_daily_data_units.is_some()
+        || args.quota_monthly_data_units.is_some()
+        || args.quota_file.is_some();
+    if has_quota {
+        if !has_auth {
+            anyhow::bail!("quotas require authentication");
+        }
+        let limits = QuotaLimits::new(args.quota_daily_data_units, args.quota_monthly_data_units);
+        let mut quota = QuotaConfig::new(limits);
+        if let Some(path) = &args.quota_file {
+            quota = quota.with_caller_limits_file(path)?;
+        }
+        if let Some(url) = args.quota_redis_url {
+            quota = quota.with_backend(Arc::new(RedisQuotaBackend::connect(&url).await?));
+        }
+        if let Some(url) = args.quota_service_url {
+            quota = quota.with_backend(Arc::new(GrpcQuotaBackend::new(url)?));
+        }
+        node.with_quota(quota);
+    }
+
+    // the caller identity is only known with authentication.
+    if !has_auth
+        && (args.rate_limit_streams_per_key.is_some() || args.max_streams_per_key.is_some())
+    {
+        anyhow::bail!("per key stream limits require authentication");
+    }
+
+    let rate_limit = |per_second: Option<f64>| {
+        per_second.map(|per_second| {
+            let limit = RateLimit::new(per_second);
+            match args.rate_limit_burst {
+                None => limit,
+                Some(burst) => limit.with_burst(burst),
+            }
+        })
+    };
+    let rate_limit_config = RateLimitConfig {
+        connections_per_ip: rate_limit(args.rate_limit_connections_per_ip),
+        streams_per_ip: rate_limit(args.rate_limit_streams_per_ip),
+        streams_per_key: rate_limit(args.rate_limit_streams_per_key),
+    };
+    if rate_limit_config.is_enabled() {
+        node.with_rate_limit(rate_limit_config);
+    }
+
+    let stream_limits = StreamLimits {
+        max_streams: args.max_streams,
+        max_streams_per_key: args.max_streams_per_key,
+        idle_timeout: args.stream_idle_timeout_secs.map(Duration::from_secs),
+        max_data_units_per_session: args.max_data_units_per_session,
+    };
+    if stream_limits.is_enabled() {
+        node.with_stream_limits(stream_limits);
+    }
+
+    if args.proxy_protocol && args.trusted_proxy.is_empty() {
+        anyhow::bail!("the proxy protocol requires trusted proxies");
+    }
+    let ip_filter = IpFilterConfig {
+        allow: args.ip_allow,
+        deny: args.ip_deny,
+        trusted_proxies: args.trusted_proxy,
+    };
+    if ip_filter.is_enabled() {
+        node.with_ip_filter(ip_filter);
+    }
+    node.with_proxy_protocol(args.proxy_protocol);
+    node.with_block_sync(args.block_sync);
+
+    let slow_batch = SlowBatchConfig {
+        produce_threshold: args.slow_batch_threshold_ms.map(Duration::from_millis),
+        send_threshold: args.slow_send_threshold_ms.map(Duration::from_millis),
+    };
+    if slow_batch.is_enabled() {
+        node.with_slow_batch(slow_batch);
+    }
+
+    if args.stream_metrics {
+        let mut config = StreamMetricsConfig {
+            label_callers: !args.stream_metrics_no_caller_label,
+            ..StreamMetricsConfig::default()
+        };
+        if let Some(max_label_sets) = args.stream_metrics_max_label_sets {
+            config.max_label_sets = max_label_sets;
+        }
+        node.with_stream_metrics(config);
+    }
+
+    if let Some(path) = args.priority_tiers_file {
+        if !has_auth {
+            anyhow::bail!("priority tiers require authentication");
+        }
+        let max_concurrent_batches = match args.max_concurrent_batches {
+            Some(max) => max,
+            None => std::thread::available_parallelism()?.get(),
+        };
+        let mut priority =
+            PriorityConfig::new(max_concurrent_batches).with_caller_tiers_file(&path)?;
+        if let Some(tier) = args.default_priority_tier {
+            priority = priority.with_default_tier(tier);
+        }
+        node.with_priority(priority);
+    }
+
+    if args.default_batch_size.is_some()
+        || args.min_batch_size.is_some()
+        || args.max_batch_size.is_some()
+        || args.max_data_units_per_message.is_some()
+    {
+        let default_policy = BatchSizePolicy::default();
+        let policy = BatchSizePolicy {
+            default_batch_size: args
+                .default_batch_size
+                .unwrap_or(default_policy.default_batch_size),
+            min_batch_size: args.min_batch_size.unwrap_or(default_policy.min_batch_size),
+            max_batch_size: args.max_batch_size.unwrap_or(default_policy.max_batch_size),
+            max_data_units_per_message: args.max_data_units_per_message,
+            ..default_policy
+        };
+        if policy.min_batch_size == 0 || policy.min_batch_size > policy.max_batch_size {
+            anyhow::bail!("min batch size must be between 1 and the max batch size");
+        }
+        if policy.default_batch_size < policy.min_batch_size
+            || policy.default_batch_size > policy.max_batch_size
+        {
+            anyhow::bail!("default batch size must be between the min and max batch size");
+        }
+        node.with_batch_size_policy(policy);
+    }
+
+    if args.max_response_size.is_some() || args.max_request_size.is_some() {
+        node.with_message_size_config(MessageSizeConfig {
+            max_response_size: args.max_response_size,
+            max_request_size: args.max_request_size,
+        });
+    }
+
+    if let Some(path) = &args.filter_restrictions_file {
+        if !has_auth {
+            anyhow::bail!("filter restrictions require authentication");
+        }
+        node.with_filter_restrictions(FilterRestrictions::from_file(path)?);
+    }
+
+    if let Some(interval) = args.reload_interval_secs {
+        if reloadable_keys.is_none()
+            && args.filter_restrictions_file.is_none()
+            && args.quota_file.is_none()
+        {
+            anyhow::bail!(
+                "reloading requires a keys file, a filter restrictions file or a quota file"
+            );
+        }
+        node.with_reload_config(ReloadConfig {
+            interval: Duration::from_secs(interval),
+            keys: reloadable_keys,
+            restrictions_file: args.filter_restrictions_file,
+            quota_file: args.quota_file,
+        });
+    }
+
+    let mut load_shedding = LoadSheddingConfig {
+        max_cpu_usage: args.shed_load_cpu_percent.map(|percent| percent / 100.0),
+        max_memory: args.shed_load_memory_mb.map(|mb| mb * 1024 * 1024),
+        max_read_latency: args.shed_load_read_latency_ms.map(Duration::from_millis),
+        ..LoadSheddingConfig::default()
+    };
+    if let Some(tier) = args.shed_load_max_slowed_tier {
+        load_shedding.max_slowed_tier = tier;
+    }
+    if load_shedding.is_enabled() {
+        node.with_load_shedding(load_shedding);
+    }
+
+    let billing_backend: Option<Arc<dyn BillingBackend>> =
+        match (args.billing_grpc_url, args.billing_http_url) {
+            (Some(url), _) => Some(Arc::new(GrpcBillingBackend::new(url)?)),
+            (None, Some(url)) => Some(Arc::new(HttpBillingBackend::new(url))),
+            (None, None) => None,
+        };
+    if let Some(backend) = billing_backend {
+        if !has_auth {
+            anyhow::bail!("usage reports require authentication");
+        }
+        let mut billing = BillingConfig::new(backend);
+        if let Some(interval) = args.billing_interval_secs {
+            billing = billing.with_interval(Duration::from_secs(interval));
+        }
+        node.with_billing(billing);
+    }
+
+    let connection_config = ConnectionConfig {
+        keepalive_interval: args.keepalive_interval_secs.map(Duration::from_secs),
+        keepalive_timeout: args.keepalive_timeout_secs.map(Duration::from_secs),
+        max_connection_age: args.max_connection_age_secs.map(Duration::from_secs),
+    };
+    if connection_config.is_enabled() {
+        node.with_connection_config(connection_config);
+    }
+
+    if let Some(path) = args.audit_log_file {
+        let mut sink = FileAuditSink::new(&path)?;
+        if let Some(max_size_mb) = args.audit_log_max_size_mb {
+            sink = sink.with_rotation(AuditRotation {
+                max_size: max_size_mb * 1024 * 1024,
+                max_files: args.audit_log_max_files.unwrap_or(10),
+            });
+        }
+        node.with_audit_sink(Arc::new(sink));
+    } else if let Some(target) = args.audit_syslog {
+        let mut sink = SyslogAuditSink::new(&target)?;
+        if let Some(facility) = args.audit_syslog_facility {
+            sink = sink.with_facility(facility);
+        }
+        node.with_audit_sink(Arc::new(sink));
+    } else if args.audit_log {
+        node.with_audit_sink(Arc::new(LogAuditSink::default()));
+    }
+
+    if args.grpc_web {
+        let mut grpc_web = GrpcWebConfig::default();
+        for origin in args.grpc_web_allowed_origin {
+            grpc_web = grpc_web.with_allowed_origin(HeaderValue::from_str(&origin)?);
+        }
+        node.with_grpc_web(grpc_web);
+    }
+
+    if !args.admin_operator.is_empty() {
+        if !has_auth {
+            anyhow::bail!("the admin service requires authentication");
+        }
+        for identity in args.admin_operator {
+            node.with_admin_operator(identity);
+        }
+    }
+
+    for path in args.archive_shard {
+        node.with_archive_shard(path);
+    }
+
+    if let Some(starting_block) = args.starting_block {
+        node.with_starting_block(starting_block);
+    }
+
+    if args.disable_pending {
+        node.with_pending_ingestion(false);
+    }
+
+    if let Some(interval) = args.pending_poll_interval_ms {
+        node.with_pending_poll_interval(Duration::from_millis(interval));
+    }
+
+    if args.headers_only {
+        node.with_headers_only(true);
+    }
+
+    if args.ingest_traces {
+        node.with_trace_ingestion(true);
+    }
+
+    node.with_block_hash_validation(args.block_hash_validation);
+
+    if args.ingest_classes {
+        node.with_class_ingestion(true);
+    }
+
+    if args.instant_finality {
+        node.with_instant_finality(true);
+    }
+
+    if args.appchain {
+        node.with_appchain_compatibility();
+    }
+
+    node.with_retry_policy(args.retry.into());
+
+    if let Some(max_lag) = args.ingestion_max_lag_blocks {
+        node.with_max_ingestion_lag(max_lag);
+    }
+
+    if let Some(max_lag) = args.ingestion_max_finalized_lag_blocks {
+        node.with_max_finalized_lag(max_lag);
+    }
+
+    if let Some(top_k) = args.top_emitters {
+        let mut config = EmitterStatsConfig {
+            top_k,
+            ..EmitterStatsConfig::default()
+        };
+        if let Some(period) = args.top_emitters_period_secs {
+            config.period = Duration::from_secs(period);
+        }
+        node.with_emitter_stats(config);
+    }
+
+    if let Some(max_attempts) = args.ingestion_journal_max_attempts {
+        node.with_journal_max_attempts(max_attempts);
+    }
+
+    if let Some(min_free_space) = args.min_free_disk_space_gib {
+        node.with_disk_usage_thresholds(min_free_space, args.throttle_free_disk_space_gib);
+    }
+
+    node.with_rpc_limits(RpcLimits {
+        requests_per_second: args.rpc_requests_per_second,
+        max_in_flight: args.rpc_max_in_flight,
+        target_latency: args.rpc_target_latency_ms.map(Duration::from_millis),
+    });
+
+    if let Some(rpc_ws) = args.rpc_ws {
+        node.with_head_subscription(&rpc_ws)?;
+    }
+
+    if let Some(feeder_gateway) = args.feeder_gateway {
+        node.with_feeder_gateway(&feeder_gateway)?;
+    }
+
+    if let Some(network) = args.network {
+        node.with_network(&network)?;
+    }
+
+    if let Some(quorum_rpc) = args.quorum_rpc {
+        node.with_quorum_provider(&quorum_rpc)?;
+    }
+
+    if let Some(l1_finality_rpc) = args.l1_finality_rpc {
+        node.with_l1_finality(&l1_finality_rpc, args.starknet_core_contract.clone())?;
+    }
+
+    if let (Some(ethereum_rpc), Some(beacon_api)) = (&args.ethereum_rpc, &args.beacon_api) {
+        node.with_data_availability(
+            ethereum_rpc,
+            beacon_api,
+            args.starknet_core_contract.clone(),
+            args.data_availability_l1_starting_block,
+        )?;
+    }
+
+    if let Some(ethereum_rpc) = args.ethereum_rpc {
+        node.with_ethereum_rpc(&ethereum_rpc, args.starknet_core_contract)?;
+    }
+
+    if let Some(bootstrap_node) = args.bootstrap_node {
+        node.with_bootstrap_node(bootstrap_node);
+    }
+
+    if args.events_backfill.events_backfill {
+        node.with_events_backfill(
+            args.events_backfill.events_backfill_address.as_deref(),
+            &args.events_backfill.events_backfill_keys,
+            args.events_backfill.events_backfill_chunk_size,
+        )?;
+    }
+
     node.build()?.start(cts.clone(), args.wait_for_rpc).await?;
 
     Ok(())
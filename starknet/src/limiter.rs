@@ -0,0 +1,335 @@
+//! Limit the rate and concurrency of requests to the RPC provider.
+//!
+//! The number of requests in flight can adapt to the provider: it grows by
+//! one request for every window of fast requests, and halves when a request
+//! fails or is slower than the target latency.
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde::Serialize;
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::Instant,
+};
+use tracing::debug;
+
+/// Limits on the requests sent to the RPC provider.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RpcLimits {
+    /// Maximum number of requests started per second.
+    pub requests_per_second: Option<u32>,
+    /// Maximum number of requests in flight at the same time.
+    pub max_in_flight: Option<usize>,
+    /// Adapt the number of requests in flight, up to `max_in_flight`, to
+    /// keep request latency below this target.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub target_latency: Option<Duration>,
+}
+
+/// Upper bound of the adaptive limit when `max_in_flight` is not set.
+const DEFAULT_ADAPTIVE_MAX_IN_FLIGHT: usize = 64;
+
+/// Enforces [RpcLimits] on all requests made through it.
+///
+/// Clones share the same limits, so that all ingestion tasks together stay
+/// below them.
+#[derive(Debug, Clone, Default)]
+pub struct RpcLimiter {
+    inner: Arc<LimiterInner>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterInner {
+    in_flight: Option<Semaphore>,
+    rate: Option<RateLimit>,
+    adaptive: Option<AdaptiveLimit>,
+}
+
+/// Adjusts the permits of the in flight semaphore (AIMD).
+#[derive(Debug)]
+struct AdaptiveLimit {
+    target_latency: Duration,
+    max: usize,
+    state: Mutex<AdaptiveState>,
+}
+
+#[derive(Debug)]
+struct AdaptiveState {
+    limit: usize,
+    // fast requests since the last change of the limit.
+    successes: usize,
+    // permits to forget before the semaphore matches the limit.
+    excess: usize,
+    last_decrease: Instant,
+}
+
+/// Spaces requests evenly, without bursts.
+#[derive(Debug)]
+struct RateLimit {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+/// A permit to send one request, released when dropped.
+#[derive(Debug)]
+pub struct RpcPermit<'a> {
+    in_flight: Option<SemaphorePermit<'a>>,
+    limiter: &'a LimiterInner,
+    started_at: Instant,
+    failed: bool,
+}
+
+impl RpcLimiter {
+    pub fn new(limits: RpcLimits) -> Self {
+        let adaptive = limits.target_latency.map(|target_latency| {
+            let max = limits
+                .max_in_flight
+                .unwrap_or(DEFAULT_ADAPTIVE_MAX_IN_FLIGHT);
+            AdaptiveLimit::new(target_latency, usize::max(max, 1))
+        });
+        let in_flight = match adaptive {
+            Some(ref adaptive) => Some(Semaphore::new(adaptive.current_limit())),
+            None => limits
+                .max_in_flight
+                .map(|max| Semaphore::new(usize::max(max, 1))),
+        };
+        let rate = limits.requests_per_second.map(|rps| RateLimit {
+            interval: Duration::from_secs(1) / u32::max(rps, 1),
+            next_slot: Mutex::new(Instant::now()),
+        });
+        RpcLimiter {
+            inner: Arc::new(LimiterInner {
+                in_flight,
+                rate,
+                adaptive,
+            }),
+        }
+    }
+
+    /// Returns the current adaptive limit of requests in flight, if enabled.
+    pub fn adaptive_limit(&self) -> Option<usize> {
+        self.inner
+            .adaptive
+            .as_ref()
+            .map(|adaptive| adaptive.current_limit())
+    }
+
+    /// Waits until a request can be sent without exceeding the limits.
+    pub async fn acquire(&self) -> RpcPermit<'_> {
+        let in_flight = match self.inner.in_flight {
+            None => None,
+            Some(ref semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .expect("rpc limiter semaphore is never closed"),
+            ),
+        };
+
+        if let Some(ref rate) = self.inner.rate {
+            let slot = rate.reserve_slot();
+            tokio::time::sleep_until(slot).await;
+        }
+
+        RpcPermit {
+            in_flight,
+            limiter: &self.inner,
+            started_at: Instant::now(),
+            failed: false,
+        }
+    }
+}
+
+impl RpcPermit<'_> {
+    /// Marks the request as failed.
+    pub fn failed(&mut self) {
+        self.failed = true;
+    }
+}
+
+impl Drop for RpcPermit<'_> {
+    fn drop(&mut self) {
+        let (adaptive, semaphore) = match (&self.limiter.adaptive, &self.limiter.in_flight) {
+            (Some(adaptive), Some(semaphore)) => (adaptive, semaphore),
+            _ => return,
+        };
+        let latency = self.started_at.elapsed();
+        let release = adaptive.record(semaphore, latency, self.failed);
+        if !release {
+            if let Some(permit) = self.in_flight.take() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+impl AdaptiveLimit {
+    fn new(target_latency: Duration, max: usize) -> Self {
+        // start low and grow, rather than overloading the provider.
+        let limit = usize::max(max / 4, 1);
+        AdaptiveLimit {
+            target_latency,
+            max,
+            state: Mutex::new(AdaptiveState {
+                limit,
+                successes: 0,
+                excess: 0,
+                last_decrease: Instant::now(),
+            }),
+        }
+    }
+
+    fn current_limit(&self) -> usize {
+        self.state.lock().expect("rpc limiter lock").limit
+    }
+
+    /// Updates the limit after a request completed.
+    ///
+    /// Returns whether the permit of the request should be released, or
+    /// forgotten to shrink the semaphore.
+    fn record(&self, semaphore: &Semaphore, latency: Duration, failed: bool) -> bool {
+        let mut state = self.state.lock().expect("rpc limiter lock");
+
+        if failed || latency > self.target_latency {
+            // requests in flight during a decrease are likely slow too, only
+            // decrease once per target latency.
+            if state.last_decrease.elapsed() > self.target_latency && state.limit > 1 {
+                let limit = usize::max(state.limit / 2, 1);
+                state.excess += state.limit - limit;
+                state.limit = limit;
+                state.successes = 0;
+                state.last_decrease = Instant::now();
+                // shrink the semaphore now, rather than as requests complete.
+                while state.excess > 0 {
+                    match semaphore.try_acquire() {
+                        Ok(permit) => permit.forget(),
+                        Err(_) => break,
+                    }
+                    state.excess -= 1;
+                }
+                debug!(limit = %limit, latency = ?latency, failed = %failed, "decrease rpc in flight limit");
+            }
+        } else {
+            state.successes += 1;
+            if state.successes >= state.limit && state.limit < self.max {
+                state.successes = 0;
+                state.limit += 1;
+                if state.excess > 0 {
+                    state.excess -= 1;
+                } else {
+                    semaphore.add_permits(1);
+                }
+            }
+        }
+
+        if state.excess > 0 {
+            state.excess -= 1;
+            return false;
+        }
+        true
+    }
+}
+
+impl RateLimit {
+    /// Returns the instant at which the next request can start.
+    fn reserve_slot(&self) -> Instant {
+        let mut next_slot = self.next_slot.lock().expect("rpc limiter lock");
+        let slot = Instant::max(*next_slot, Instant::now());
+        *next_slot = slot + self.interval;
+        slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant},
+    };
+
+    use super::{RpcLimiter, RpcLimits};
+
+    #[tokio::test]
+    async fn test_requests_per_second() {
+        let limiter = RpcLimiter::new(RpcLimits {
+            requests_per_second: Some(50),
+            max_in_flight: None,
+            target_latency: None,
+        });
+
+        let started_at = Instant::now();
+        for _ in 0..6 {
+            let _permit = limiter.acquire().await;
+        }
+        // the first request starts immediately, the others every 20ms.
+        assert!(started_at.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight() {
+        let limiter = RpcLimiter::new(RpcLimits {
+            requests_per_second: None,
+            max_in_flight: Some(2),
+            target_latency: None,
+        });
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let limiter = limiter.clone();
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                tokio::spawn(async move {
+                    let _permit = limiter.acquire().await;
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_in_flight() {
+        let limiter = RpcLimiter::new(RpcLimits {
+            requests_per_second: None,
+            max_in_flight: Some(8),
+            target_latency: Some(Duration::from_millis(20)),
+        });
+        assert_eq!(limiter.adaptive_limit(), Some(2));
+
+        // fast requests increase the limit up to the maximum.
+        for _ in 0..64 {
+            let _permit = limiter.acquire().await;
+        }
+        assert_eq!(limiter.adaptive_limit(), Some(8));
+
+        // a failed request halves the limit.
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        let mut permit = limiter.acquire().await;
+        permit.failed();
+        drop(permit);
+        assert_eq!(limiter.adaptive_limit(), Some(4));
+
+        // the semaphore shrinks with the limit.
+        let permits: Vec<_> = futures::future::join_all((0..4).map(|_| limiter.acquire())).await;
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), limiter.acquire())
+                .await
+                .is_err()
+        );
+        drop(permits);
+    }
+}
@@ -7,22 +7,46 @@ use std::{
     time::Duration,
 };
 
+use apibara_core::starknet::v1alpha2;
 use apibara_node::{
+    chain::prepare_database,
     db::{
         default_data_dir,
         libmdbx::{self, Environment, EnvironmentKind},
-        MdbxEnvironmentExt,
+        swap_compacted_environment, BackupConfig, CompactError, MaintenanceConfig,
+        MaintenanceService, MaintenanceWindow, MdbxEnvironmentExt, MdbxGeometry, MigrationError,
+        Table,
     },
-    server::{RequestObserver, SimpleRequestObserver},
+    o11y::{report_error, ErrorReportingScope},
+    server::{PrometheusMeterBackend, RequestObserver, SimpleRequestObserver},
+    stream::BatchSizePolicy,
 };
+use serde_json::json;
 use tokio_util::sync::CancellationToken;
-use tracing::{info, warn};
+use tracing::{error, info, warn};
 
 use crate::{
-    db::{tables, DatabaseStorage},
-    ingestion::{BlockIngestion, BlockIngestionConfig, BlockIngestionError},
-    provider::{HttpProviderError, Provider},
-    server::{Server, ServerError},
+    admin::AdminServer,
+    chain::StarkNetChain,
+    db::{self, tables, DatabaseStorage, EncryptionKey, ShardError, ShardedStorage},
+    ingestion::{
+        chain_id_from_network, verify_chain_id, BlockHashValidation, BlockIngestion,
+        BlockIngestionConfig, BlockIngestionError, BlockScrubber, DataAvailabilityConfig,
+        DataAvailabilityIngestion, DiskUsageConfig, EmitterStats, EmitterStatsConfig,
+        EventsBackfillConfig, GapHealer, L1MessageResolver, RetryPolicy, ScrubConfig,
+        MAINNET_CORE_CONTRACT,
+    },
+    limiter::RpcLimits,
+    profiling::ProfilingConfig,
+    provider::{EventFilter, FeederGateway, HttpProviderError, L1Finality, Provider},
+    runtime_config::{redact_url, RuntimeConfig},
+    server::{
+        AuditSink, BillingConfig, ConnectionConfig, GrpcWebConfig, IpFilterConfig,
+        MessageSizeConfig, OpenStreams, QuotaConfig, RateLimitConfig, ReloadConfig, Server,
+        ServerError, ServerExtension, StreamLimits, StreamMetricsConfig, TlsConfig, TokenValidator,
+    },
+    status::StatusServer,
+    stream::{FilterRestrictions, LoadSheddingConfig, PriorityConfig, SlowBatchConfig},
     websocket::WebsocketStreamServer,
     HttpProvider,
 };
@@ -36,7 +60,47 @@ where
     db: Arc<Environment<E>>,
     sequencer_provider: Arc<G>,
     request_span: O,
+    config: NodeConfig,
+    runtime_config: RuntimeConfig,
+}
+
+/// Configuration of the node services, set by [StarkNetNodeBuilder].
+#[derive(Default)]
+pub(crate) struct NodeConfig {
     websocket_address: Option<String>,
+    admin_address: Option<String>,
+    status_address: Option<String>,
+    ingestion_config: BlockIngestionConfig,
+    maintenance_config: Option<MaintenanceConfig>,
+    scrub_config: Option<ScrubConfig>,
+    archive_shards: Vec<PathBuf>,
+    encryption_key: Option<EncryptionKey>,
+    tls_config: Option<TlsConfig>,
+    token_validator: Option<Arc<dyn TokenValidator>>,
+    quota_config: Option<QuotaConfig>,
+    rate_limit_config: Option<RateLimitConfig>,
+    grpc_web_config: Option<GrpcWebConfig>,
+    admin_operators: Vec<String>,
+    stream_limits: Option<StreamLimits>,
+    prometheus_meter: Option<Arc<PrometheusMeterBackend>>,
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    connection_config: Option<ConnectionConfig>,
+    ip_filter_config: Option<IpFilterConfig>,
+    billing_config: Option<BillingConfig>,
+    priority_config: Option<PriorityConfig>,
+    batch_size_policy: Option<BatchSizePolicy>,
+    message_size_config: Option<MessageSizeConfig>,
+    filter_restrictions: Option<FilterRestrictions>,
+    reload_config: Option<ReloadConfig>,
+    load_shedding_config: Option<LoadSheddingConfig>,
+    extensions: Vec<Arc<dyn ServerExtension>>,
+    proxy_protocol: bool,
+    block_sync: bool,
+    stream_metrics_config: Option<StreamMetricsConfig>,
+    slow_batch_config: Option<SlowBatchConfig>,
+    profiling_config: Option<ProfilingConfig>,
+    admin_token: Option<String>,
+    backup_config: Option<BackupConfig>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -45,10 +109,14 @@ pub enum StarkNetNodeError {
     BlockIngestion(BlockIngestionError),
     #[error("database operation failed")]
     Database(#[from] libmdbx::Error),
+    #[error("database migration failed")]
+    Migration(#[from] MigrationError),
     #[error("server error")]
     Server(#[from] ServerError),
     #[error("error parsing server address")]
     AddressParseError(#[from] AddrParseError),
+    #[error("invalid archive shards")]
+    Shard(#[from] ShardError),
 }
 
 impl<G, O, E> StarkNetNode<G, O, E>
@@ -68,7 +136,8 @@ where
         db: Environment<E>,
         sequencer_provider: G,
         request_span: O,
-        websocket_address: Option<String>,
+        config: NodeConfig,
+        runtime_config: RuntimeConfig,
     ) -> Self {
         let db = Arc::new(db);
         let sequencer_provider = Arc::new(sequencer_provider);
@@ -76,7 +145,8 @@ where
             db,
             sequencer_provider,
             request_span,
-            websocket_address,
+            config,
+            runtime_config,
         }
     }
 
@@ -87,33 +157,106 @@ where
         wait_for_rpc: bool,
     ) -> Result<(), StarkNetNodeError> {
         info!("starting starknet node");
-        self.ensure_tables()?;
+        prepare_database::<StarkNetChain<DatabaseStorage<E>>, E>(&self.db)?;
+        db::repair_storage(&self.db, db::DEFAULT_REPAIR_DEPTH)?;
+        let storage = self.storage();
+        let sharded_storage = self.open_sharded_storage()?;
 
         if wait_for_rpc {
             self.wait_for_rpc(ct.clone()).await?;
         }
 
-        // TODO: config from command line
+        // never write data from a different network to the database.
+        match verify_chain_id(
+            &*self.sequencer_provider,
+            &storage,
+            self.config.ingestion_config.expected_chain_id.as_ref(),
+        )
+        .await
+        {
+            Ok(chain_id) => info!(chain_id = %chain_id, "provider chain id"),
+            Err(err) if err.is_fatal() => return Err(StarkNetNodeError::BlockIngestion(err)),
+            Err(err) => warn!(error = ?err, "failed to verify chain id"),
+        }
+
+        // detect the spec version early, so that it's logged at startup.
+        if let Err(err) = self.sequencer_provider.spec_version().await {
+            warn!(error = ?err, "failed to detect provider rpc spec version");
+        }
+
+        // fill gaps before serving streams, since streams stop at the first gap.
+        let healer = GapHealer::new(
+            self.sequencer_provider.clone(),
+            storage.clone(),
+            &self.config.ingestion_config,
+        );
+        match healer.heal(ct.clone()).await {
+            Ok(0) => {}
+            Ok(healed) => info!(healed = %healed, "filled gaps in canonical chain"),
+            Err(err) => error!(error = ?err, "failed to fill gaps in canonical chain"),
+        }
+
+        let headers_only = self.config.ingestion_config.headers_only;
+        let data_availability = self.config.ingestion_config.data_availability.clone();
         let (block_ingestion_client, block_ingestion) = BlockIngestion::new(
             self.sequencer_provider.clone(),
-            self.db.clone(),
-            BlockIngestionConfig::default(),
+            storage.clone(),
+            self.config.ingestion_config,
         );
+        let ingestion_health = block_ingestion.health();
+        let ingestion_control = block_ingestion.control();
+        let ingestion_journal = block_ingestion.journal();
+        let emitter_stats = block_ingestion.emitter_stats();
 
+        // ingestion panics are reported with the context of the ingestion
+        // scope, like the block being ingested.
         let mut block_ingestion_handle = tokio::spawn({
             let ct = ct.clone();
-            async move {
-                block_ingestion
-                    .start(ct)
-                    .await
-                    .map_err(StarkNetNodeError::BlockIngestion)
-            }
+            ErrorReportingScope::new().bind(async move {
+                let result = block_ingestion.start(ct).await;
+                if let Err(err) = &result {
+                    report_error(err);
+                }
+                result.map_err(StarkNetNodeError::BlockIngestion)
+            })
         });
 
         // TODO: configure from command line
         let server_addr: SocketAddr = "0.0.0.0:7171".parse()?;
+        let open_streams = OpenStreams::default();
+        let audit_sink = self.config.audit_sink.clone();
+        let request_observer = Arc::new(self.request_span);
         let server = Server::<E, O>::new(self.db.clone(), block_ingestion_client.clone())
-            .with_request_observer(self.request_span);
+            .with_request_observer(request_observer.clone())
+            .with_ingestion_health(ingestion_health.clone())
+            .with_tls(self.config.tls_config)
+            .with_auth(self.config.token_validator.clone())
+            .with_quota(self.config.quota_config)
+            .with_rate_limit(self.config.rate_limit_config)
+            .with_grpc_web(self.config.grpc_web_config)
+            .with_admin_operators(self.config.admin_operators)
+            .with_stream_limits(self.config.stream_limits)
+            .with_audit(self.config.audit_sink)
+            .with_connection(self.config.connection_config)
+            .with_ip_filter(self.config.ip_filter_config)
+            .with_billing(self.config.billing_config)
+            .with_priority(self.config.priority_config)
+            .with_batch_size_policy(self.config.batch_size_policy)
+            .with_message_size(self.config.message_size_config)
+            .with_filter_restrictions(self.config.filter_restrictions.clone())
+            .with_reload(self.config.reload_config)
+            .with_load_shedding(self.config.load_shedding_config)
+            .with_extensions(self.config.extensions)
+            .with_proxy_protocol(self.config.proxy_protocol)
+            .with_block_sync(self.config.block_sync)
+            .with_stream_metrics(self.config.stream_metrics_config)
+            .with_slow_batch(self.config.slow_batch_config)
+            .with_open_streams(open_streams.clone())
+            .with_storage(sharded_storage.clone());
+        // websocket clients share the quota and limits of grpc clients.
+        let quota = server.quota_tracker();
+        let rate_limiter = server.rate_limiter();
+        let ip_filter = server.ip_filter();
         let mut server_handle = tokio::spawn({
             let ct = ct.clone();
             async move {
@@ -124,21 +267,102 @@ where
             }
         });
 
-        let storage = Arc::new(DatabaseStorage::new(self.db.clone()));
+        tokio::spawn(train_compression_dictionary(
+            Arc::new(storage.clone()),
+            ct.clone(),
+        ));
 
         info!("Starting websocket server");
-        let mut websocket_handle = match self.websocket_address {
+        let mut websocket_handle = match self.config.websocket_address {
             Some(websocket_address) => {
                 let websocket_server = WebsocketStreamServer::new(
                     websocket_address,
-                    storage,
+                    Arc::new(sharded_storage.clone()),
                     block_ingestion_client.clone(),
-                );
+                )
+                .with_request_observer(request_observer)
+                .with_token_validator(self.config.token_validator)
+                .with_batch_size_policy(self.config.batch_size_policy.unwrap_or_default())
+                .with_restrictions(self.config.filter_restrictions)
+                .with_quota(quota)
+                .with_rate_limiter(rate_limiter)
+                .with_ip_filter(ip_filter);
                 tokio::spawn(Arc::new(websocket_server).start())
             }
             None => tokio::spawn(future::pending()),
         };
 
+        if let Some(config) = data_availability {
+            let data_availability = DataAvailabilityIngestion::new(storage.clone(), config);
+            tokio::spawn({
+                let ct = ct.clone();
+                async move {
+                    if let Err(err) = data_availability.start(ct).await {
+                        error!(error = ?err, "data availability ingestion failed");
+                    }
+                }
+            });
+        }
+
+        let maintenance = self.config.maintenance_config.map(|config| {
+            let maintenance = Arc::new(MaintenanceService::new(self.db.clone(), config));
+            tokio::spawn(maintenance.clone().start(ct.clone()));
+            maintenance
+        });
+
+        let scrub = self.config.scrub_config.map(|config| {
+            let scrubber = BlockScrubber::new(self.sequencer_provider.clone(), storage, config)
+                .with_headers_only(headers_only);
+            let status = scrubber.status();
+            tokio::spawn(scrubber.start(ct.clone()));
+            status
+        });
+
+        let mut status_handle = match self.config.status_address {
+            Some(status_address) => {
+                let status_addr: SocketAddr = status_address.parse()?;
+                let status_server =
+                    StatusServer::new(sharded_storage.clone(), ingestion_health.clone())
+                        .with_open_streams(open_streams);
+                tokio::spawn(status_server.start(status_addr, ct.clone()))
+            }
+            None => tokio::spawn(future::pending()),
+        };
+
+        let mut admin_handle = match self.config.admin_address {
+            Some(admin_address) => {
+                let admin_addr: SocketAddr = admin_address.parse()?;
+                let mut admin_server = AdminServer::new(self.db.clone())
+                    .with_runtime_config(self.runtime_config)
+                    .with_ingestion_health(ingestion_health)
+                    .with_ingestion_control(ingestion_control)
+                    .with_ingestion_journal(ingestion_journal);
+                if let Some(maintenance) = maintenance {
+                    admin_server = admin_server.with_maintenance(maintenance);
+                }
+                if let Some(scrub) = scrub {
+                    admin_server = admin_server.with_scrub(scrub);
+                }
+                if let Some(prometheus) = self.config.prometheus_meter {
+                    admin_server = admin_server.with_prometheus(prometheus);
+                }
+                if let Some(profiling) = self.config.profiling_config {
+                    admin_server = admin_server.with_profiling(profiling);
+                }
+                if let Some(emitter_stats) = emitter_stats {
+                    admin_server = admin_server.with_emitter_stats(emitter_stats);
+                }
+                if let Some(token) = self.config.admin_token {
+                    admin_server = admin_server.with_token(token);
+                }
+                if let Some(backup_config) = self.config.backup_config {
+                    admin_server = admin_server.with_backup(backup_config);
+                }
+                tokio::spawn(admin_server.start(admin_addr, ct.clone()))
+            }
+            None => tokio::spawn(future::pending()),
+        };
+
         // TODO: based on which handles terminates first, it needs to wait
         // for the other handle to terminate too.
         tokio::select! {
@@ -151,17 +375,42 @@ where
             ret = &mut websocket_handle => {
                 warn!(resul = ?ret, "websocket server terminated");
             }
+            ret = &mut admin_handle => {
+                warn!(result = ?ret, "admin server terminated");
+            }
+            ret = &mut status_handle => {
+                warn!(result = ?ret, "status server terminated");
+            }
+        }
+
+        // events of the streams closed after this point are written when the
+        // sink is dropped.
+        if let Some(audit_sink) = audit_sink {
+            audit_sink.flush();
         }
 
         info!("terminated. bye");
         Ok(())
     }
 
-    fn ensure_tables(&self) -> Result<(), StarkNetNodeError> {
-        let txn = self.db.begin_rw_txn()?;
-        tables::ensure(&txn)?;
-        txn.commit()?;
-        Ok(())
+    /// Opens the archive shards and returns a storage that reads from them.
+    fn open_sharded_storage(&self) -> Result<ShardedStorage<E>, StarkNetNodeError> {
+        let mut archives = Vec::with_capacity(self.config.archive_shards.len());
+        for path in &self.config.archive_shards {
+            info!(path = ?path, "opening archive shard");
+            let db = Environment::<E>::builder().open(path)?;
+            archives.push((Arc::new(db), path.clone()));
+        }
+        Ok(ShardedStorage::new(self.storage(), archives)?)
+    }
+
+    /// Returns the storage over the node database.
+    fn storage(&self) -> DatabaseStorage<E> {
+        let storage = DatabaseStorage::new(self.db.clone());
+        match self.config.encryption_key {
+            None => storage,
+            Some(ref key) => storage.with_encryption(key.clone()),
+        }
     }
 
     async fn wait_for_rpc(&self, ct: CancellationToken) -> Result<(), StarkNetNodeError> {
@@ -187,25 +436,79 @@ where
     }
 }
 
+/// Number of blocks used to train the compression dictionary.
+const DICTIONARY_TRAINING_BLOCKS: usize = 1_000;
+
+/// Trains the block compression dictionary once enough blocks are ingested.
+///
+/// Nodes that already have a dictionary keep using it.
+async fn train_compression_dictionary<E: EnvironmentKind>(
+    storage: Arc<DatabaseStorage<E>>,
+    ct: CancellationToken,
+) {
+    loop {
+        let result = tokio::task::spawn_blocking({
+            let storage = storage.clone();
+            move || {
+                if storage.has_compression_dictionary()? {
+                    return Ok(true);
+                }
+                let dictionary =
+                    storage.train_compression_dictionary(DICTIONARY_TRAINING_BLOCKS)?;
+                if let Some(id) = dictionary {
+                    info!(id = %id, "trained compression dictionary");
+                }
+                Ok::<_, libmdbx::Error>(dictionary.is_some())
+            }
+        })
+        .await;
+
+        match result {
+            Ok(Ok(true)) => return,
+            Ok(Ok(false)) => {}
+            Ok(Err(err)) => error!(error = ?err, "failed to train compression dictionary"),
+            Err(err) => error!(error = ?err, "compression dictionary task panicked"),
+        }
+
+        tokio::select! {
+            _ = ct.cancelled() => return,
+            _ = tokio::time::sleep(Duration::from_secs(600)) => {},
+        }
+    }
+}
+
 pub struct StarkNetNodeBuilder<O: RequestObserver, E: EnvironmentKind> {
-    datadir: PathBuf,
+    datadir: Option<PathBuf>,
     provider: HttpProvider,
+    feeder_gateway: Option<FeederGateway>,
+    rpc_limits: RpcLimits,
+    instant_finality: bool,
+    l1_finality: Option<L1Finality>,
     poll_interval: Duration,
     request_observer: O,
-    websocket_address: Option<String>,
+    geometry: MdbxGeometry,
+    config: NodeConfig,
     _phantom: PhantomData<E>,
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum StarkNetNodeBuilderError {
+    #[error("no datadir, the platform has no default data directory")]
+    NoDatadir,
     #[error("failed to create datadir")]
     CreateDatadir(std::io::Error),
     #[error("failed to open mdbx database")]
     DatabaseOpen(libmdbx::Error),
+    #[error("failed to swap in the compacted database")]
+    SwapCompacted(CompactError),
     #[error("failed to parse provider url")]
     ProviderUrl(#[from] url::ParseError),
     #[error("failed to create sequencer")]
     Provider(#[from] HttpProviderError),
+    #[error("invalid network {0}")]
+    InvalidNetwork(String),
+    #[error("invalid field element {0}")]
+    InvalidFieldElement(String),
 }
 
 impl<O, E> StarkNetNodeBuilder<O, E>
@@ -216,9 +519,7 @@ where
     pub(crate) fn new(
         url: &str,
     ) -> Result<StarkNetNodeBuilder<SimpleRequestObserver, E>, StarkNetNodeBuilderError> {
-        let datadir = default_data_dir()
-            .map(|d| d.join("starknet"))
-            .expect("no datadir");
+        let datadir = default_data_dir().map(|d| d.join("starknet"));
         let url = url.parse()?;
         let sequencer = HttpProvider::new(url);
         let poll_interval = Duration::from_millis(5_000);
@@ -226,16 +527,25 @@ where
         let builder = StarkNetNodeBuilder {
             datadir,
             provider: sequencer,
+            feeder_gateway: None,
+            rpc_limits: RpcLimits::default(),
+            instant_finality: false,
+            l1_finality: None,
             poll_interval,
             request_observer,
-            websocket_address: None,
+            geometry: MdbxGeometry::default(),
+            config: NodeConfig::default(),
             _phantom: Default::default(),
         };
         Ok(builder)
     }
 
     pub fn with_datadir(&mut self, datadir: PathBuf) {
-        self.datadir = datadir;
+        self.datadir = Some(datadir);
+    }
+
+    pub fn with_geometry(&mut self, geometry: MdbxGeometry) {
+        self.geometry = geometry;
     }
 
     pub fn with_poll_interval(&mut self, poll_interval: Duration) {
@@ -249,31 +559,551 @@ where
         StarkNetNodeBuilder {
             datadir: self.datadir,
             provider: self.provider,
+            feeder_gateway: self.feeder_gateway,
+            rpc_limits: self.rpc_limits,
+            instant_finality: self.instant_finality,
+            l1_finality: self.l1_finality,
             poll_interval: self.poll_interval,
             request_observer,
-            websocket_address: self.websocket_address,
+            geometry: self.geometry,
+            config: self.config,
             _phantom: self._phantom,
         }
     }
 
-    pub fn build(self) -> Result<StarkNetNode<HttpProvider, O, E>, StarkNetNodeBuilderError> {
-        fs::create_dir_all(&self.datadir).map_err(StarkNetNodeBuilderError::CreateDatadir)?;
+    pub fn build(mut self) -> Result<StarkNetNode<HttpProvider, O, E>, StarkNetNodeBuilderError> {
+        let datadir = self
+            .datadir
+            .clone()
+            .ok_or(StarkNetNodeBuilderError::NoDatadir)?;
+        fs::create_dir_all(&datadir).map_err(StarkNetNodeBuilderError::CreateDatadir)?;
+
+        // the database can't grow past its maximum size, whatever the free
+        // space on the volume.
+        if let Some(disk_usage) = self.config.ingestion_config.disk_usage.as_mut() {
+            disk_usage.path = datadir.clone();
+            disk_usage.max_database_size = Some(gib_to_bytes(self.geometry.max_size_gib as u64));
+        }
+        if let Some(maintenance) = self.config.maintenance_config.as_mut() {
+            maintenance.path = datadir.clone();
+            maintenance.geometry = self.geometry.clone();
+        }
+
+        // snapshot the configuration once all values are final.
+        let runtime_config = RuntimeConfig::new(self.runtime_config());
+
+        // quota usage and the ingestion journal can't be rebuilt by
+        // ingesting blocks again, so they're kept from the replaced data file.
+        let carry_over = [
+            tables::QuotaUsageTable::db_name(),
+            tables::IngestionJournalTable::db_name(),
+        ];
+        let swapped = swap_compacted_environment::<E>(&datadir, &self.geometry, &carry_over)
+            .map_err(StarkNetNodeBuilderError::SwapCompacted)?;
+        if let Some(summary) = swapped {
+            info!(
+                size_before = %summary.size_before,
+                size_after = %summary.size_after,
+                "swapped in compacted database"
+            );
+        }
 
         let db = Environment::<E>::builder()
-            .with_size_gib(10, 100)
-            .with_growth_step_gib(2)
-            .open(&self.datadir)
+            .with_geometry(&self.geometry)
+            .open(&datadir)
             .map_err(StarkNetNodeBuilderError::DatabaseOpen)?;
 
+        let mut provider = self
+            .provider
+            .with_limits(self.rpc_limits)
+            .with_instant_finality(self.instant_finality);
+        if let Some(gateway) = self.feeder_gateway {
+            provider = provider.with_feeder_gateway(gateway);
+        }
+        if let Some(l1_finality) = self.l1_finality {
+            provider = provider.with_l1_finality(l1_finality);
+        }
+
         Ok(StarkNetNode::new(
             db,
-            self.provider,
+            provider,
             self.request_observer,
-            self.websocket_address,
+            self.config,
+            runtime_config,
         ))
     }
 
+    /// Returns the effective configuration of the node, with secrets
+    /// redacted.
+    fn runtime_config(&self) -> serde_json::Value {
+        json!({
+            "datadir": self.datadir,
+            "geometry": self.geometry,
+            "provider": {
+                "rpc": redact_url(self.provider.rpc_url()),
+                "feeder_gateway": self.feeder_gateway.is_some(),
+                "l1_finality": self.l1_finality.is_some(),
+                "instant_finality": self.instant_finality,
+                "poll_interval": self.poll_interval.as_millis() as u64,
+                "limits": self.rpc_limits,
+            },
+            "ingestion": self.config.ingestion_config,
+            "maintenance": self.config.maintenance_config,
+            "scrub": self.config.scrub_config,
+            "archive_shards": self.config.archive_shards,
+            "encryption": self.config.encryption_key.is_some(),
+            "addresses": {
+                "websocket": self.config.websocket_address,
+                "admin": self.config.admin_address,
+                "status": self.config.status_address,
+            },
+            "server": {
+                "tls": self.config.tls_config,
+                "auth": self.config.token_validator.is_some(),
+                "admin_operators": self.config.admin_operators.len(),
+                "quota": self.config.quota_config,
+                "rate_limit": self.config.rate_limit_config,
+                "grpc_web": self.config.grpc_web_config,
+                "stream_limits": self.config.stream_limits,
+                "connection": self.config.connection_config,
+                "ip_filter": self.config.ip_filter_config,
+                "billing_interval": self
+                    .config
+                    .billing_config
+                    .as_ref()
+                    .map(|billing| billing.interval.as_millis() as u64),
+                "priority": self.config.priority_config,
+                "batch_size": self.config.batch_size_policy.unwrap_or_default(),
+                "message_size": self.config.message_size_config,
+                "filter_restrictions": self.config.filter_restrictions.is_some(),
+                "reload": self.config.reload_config,
+                "load_shedding": self.config.load_shedding_config,
+                "extensions": self.config.extensions.len(),
+                "proxy_protocol": self.config.proxy_protocol,
+                "block_sync": self.config.block_sync,
+                "stream_metrics": self.config.stream_metrics_config,
+                "slow_batch": self.config.slow_batch_config,
+                "prometheus": self.config.prometheus_meter.is_some(),
+                "audit": self.config.audit_sink.is_some(),
+            },
+            "profiling": self.config.profiling_config,
+            "admin_token": self.config.admin_token.is_some(),
+            "backup": self.config.backup_config,
+        })
+    }
+
     pub(crate) fn with_websocket_address(&mut self, websocket_address: String) {
-        self.websocket_address = Some(websocket_address)
+        self.config.websocket_address = Some(websocket_address)
+    }
+
+    pub(crate) fn with_admin_address(&mut self, admin_address: String) {
+        self.config.admin_address = Some(admin_address)
+    }
+
+    pub(crate) fn with_status_address(&mut self, status_address: String) {
+        self.config.status_address = Some(status_address)
+    }
+
+    /// Restart ingestion after errors according to the given policy.
+    pub fn with_retry_policy(&mut self, policy: RetryPolicy) {
+        self.config.ingestion_config.retry_policy = policy;
+    }
+
+    /// Report ingestion as unhealthy if it's more than `max_lag` blocks
+    /// behind the provider head.
+    pub fn with_max_ingestion_lag(&mut self, max_lag: u64) {
+        self.config.ingestion_config.max_lag = Some(max_lag);
+    }
+
+    /// Report ingestion as unhealthy if the finalized block is more than
+    /// `max_lag` blocks behind the node head.
+    pub fn with_max_finalized_lag(&mut self, max_lag: u64) {
+        self.config.ingestion_config.max_finalized_lag = Some(max_lag);
+    }
+
+    /// Track the contracts that emit the most events, and report them as
+    /// metrics and from the admin API.
+    pub fn with_emitter_stats(&mut self, config: EmitterStatsConfig) {
+        self.config.ingestion_config.emitter_stats = Some(EmitterStats::new(config));
+    }
+
+    /// Store blocks that failed `max_attempts` times because of the provider
+    /// without the failing data, and continue with the following blocks.
+    ///
+    /// Skipped data is listed in the ingestion journal of the admin API.
+    pub fn with_journal_max_attempts(&mut self, max_attempts: u32) {
+        self.config.ingestion_config.journal_max_attempts = Some(max_attempts);
+    }
+
+    /// Pause ingestion when less than `min_free_space_gib` GiB are available
+    /// to the database, and slow it down below `throttle_free_space_gib`.
+    pub fn with_disk_usage_thresholds(
+        &mut self,
+        min_free_space_gib: u64,
+        throttle_free_space_gib: Option<u64>,
+    ) {
+        let mut disk_usage =
+            // the path is set to the datadir when building the node.
+            DiskUsageConfig::new(PathBuf::default(), gib_to_bytes(min_free_space_gib));
+        if let Some(throttle_free_space_gib) = throttle_free_space_gib {
+            disk_usage.throttle_free_space = gib_to_bytes(throttle_free_space_gib);
+        }
+        self.config.ingestion_config.disk_usage = Some(disk_usage);
+    }
+
+    /// Limit the rate and concurrency of requests to the RPC provider.
+    ///
+    /// Limits are shared by all ingestion tasks.
+    pub fn with_rpc_limits(&mut self, limits: RpcLimits) {
+        self.rpc_limits = limits;
+    }
+
+    /// Write a compacted copy of the database in the configured window,
+    /// swapped in the next time the node starts.
+    pub fn with_maintenance(&mut self, window: MaintenanceWindow, max_bytes_per_second: u64) {
+        self.config.maintenance_config = Some(MaintenanceConfig {
+            // the path and geometry are set when building the node.
+            path: PathBuf::default(),
+            geometry: MdbxGeometry::default(),
+            window,
+            max_bytes_per_second,
+        });
+    }
+
+    /// Verify block checksums in the background, re-ingesting corrupted
+    /// blocks.
+    pub fn with_scrub(&mut self, config: ScrubConfig) {
+        self.config.scrub_config = Some(config);
+    }
+
+    /// Encrypt block data with the given key.
+    pub fn with_encryption_key(&mut self, key: EncryptionKey) {
+        self.config.encryption_key = Some(key);
+    }
+
+    /// Serve the gRPC stream over TLS with the given certificate.
+    pub fn with_tls(&mut self, config: TlsConfig) {
+        self.config.tls_config = Some(config);
+    }
+
+    /// Require clients to authenticate with a bearer token, checked by the
+    /// given validator.
+    pub fn with_token_validator(&mut self, validator: Arc<dyn TokenValidator>) {
+        self.config.token_validator = Some(validator);
     }
+
+    /// Limit the data units streamed by each authenticated caller.
+    pub fn with_quota(&mut self, config: QuotaConfig) {
+        self.config.quota_config = Some(config);
+    }
+
+    /// Rate limit new connections and streams.
+    pub fn with_rate_limit(&mut self, config: RateLimitConfig) {
+        self.config.rate_limit_config = Some(config);
+    }
+
+    /// Accept grpc-web requests from browsers.
+    pub fn with_grpc_web(&mut self, config: GrpcWebConfig) {
+        self.config.grpc_web_config = Some(config);
+    }
+
+    /// Serve the request counters of the given backend at the `/metrics`
+    /// route of the admin API.
+    pub fn with_prometheus_meter(&mut self, prometheus: Arc<PrometheusMeterBackend>) {
+        self.config.prometheus_meter = Some(prometheus);
+    }
+
+    /// Limit the number of streams open at the same time.
+    pub fn with_stream_limits(&mut self, limits: StreamLimits) {
+        self.config.stream_limits = Some(limits);
+    }
+
+    /// Configure keepalive pings and the maximum age of client connections.
+    pub fn with_connection_config(&mut self, config: ConnectionConfig) {
+        self.config.connection_config = Some(config);
+    }
+
+    /// Read the address of clients from the PROXY protocol header sent by
+    /// the load balancer in front of the node.
+    pub fn with_proxy_protocol(&mut self, enabled: bool) {
+        self.config.proxy_protocol = enabled;
+    }
+
+    /// Serve the block sync service, used by other nodes to bootstrap from
+    /// this node.
+    pub fn with_block_sync(&mut self, enabled: bool) {
+        self.config.block_sync = enabled;
+    }
+
+    /// Record the data sent to each stream, labeled by caller and stream
+    /// name.
+    pub fn with_stream_metrics(&mut self, config: StreamMetricsConfig) {
+        self.config.stream_metrics_config = Some(config);
+    }
+
+    /// Log the batches that take longer than the thresholds to produce or
+    /// send.
+    pub fn with_slow_batch(&mut self, config: SlowBatchConfig) {
+        self.config.slow_batch_config = Some(config);
+    }
+
+    /// Serve CPU and heap profiles from the admin API.
+    pub fn with_profiling(&mut self, config: ProfilingConfig) {
+        self.config.profiling_config = Some(config);
+    }
+
+    /// Require the given bearer token for the admin routes that change the
+    /// state of the node.
+    pub fn with_admin_token(&mut self, token: String) {
+        self.config.admin_token = Some(token);
+    }
+
+    /// Start backups from the admin API with the given configuration.
+    pub fn with_backup(&mut self, config: BackupConfig) {
+        self.config.backup_config = Some(config);
+    }
+
+    /// Only accept clients from the networks allowed by the filter.
+    pub fn with_ip_filter(&mut self, config: IpFilterConfig) {
+        self.config.ip_filter_config = Some(config);
+    }
+
+    /// Clamp the batch size requested by clients with the given policy.
+    pub fn with_batch_size_policy(&mut self, policy: BatchSizePolicy) {
+        self.config.batch_size_policy = Some(policy);
+    }
+
+    /// Limit the size of the messages exchanged with clients.
+    pub fn with_message_size_config(&mut self, config: MessageSizeConfig) {
+        self.config.message_size_config = Some(config);
+    }
+
+    /// Restrict the filters of callers to a subset of the chain.
+    pub fn with_filter_restrictions(&mut self, restrictions: FilterRestrictions) {
+        self.config.filter_restrictions = Some(restrictions);
+    }
+
+    /// Reload the keys and filter restrictions while the node is running.
+    pub fn with_reload_config(&mut self, config: ReloadConfig) {
+        self.config.reload_config = Some(config);
+    }
+
+    /// Refuse new streams and slow down backfills when the node is under
+    /// resource pressure.
+    pub fn with_load_shedding(&mut self, config: LoadSheddingConfig) {
+        self.config.load_shedding_config = Some(config);
+    }
+
+    /// Favor the streams of callers with a higher priority tier.
+    pub fn with_priority(&mut self, config: PriorityConfig) {
+        self.config.priority_config = Some(config);
+    }
+
+    /// Periodically report the usage of callers to a billing service.
+    pub fn with_billing(&mut self, config: BillingConfig) {
+        self.config.billing_config = Some(config);
+    }
+
+    /// Send the lifecycle of streams to the given audit sink.
+    pub fn with_audit_sink(&mut self, sink: Arc<dyn AuditSink>) {
+        self.config.audit_sink = Some(sink);
+    }
+
+    /// Intercept requests and observe the lifecycle of streams with the
+    /// given extension. Extensions run in the order they are registered.
+    pub fn with_extension(&mut self, extension: Arc<dyn ServerExtension>) {
+        self.config.extensions.push(extension);
+    }
+
+    /// Let the caller with the given identity inspect and control streams
+    /// through the admin service.
+    pub fn with_admin_operator(&mut self, identity: String) {
+        self.config.admin_operators.push(identity);
+    }
+
+    /// Serve old blocks from the archive shard at the given path.
+    pub fn with_archive_shard(&mut self, path: PathBuf) {
+        self.config.archive_shards.push(path);
+    }
+
+    /// Enable or disable ingestion of the pending block.
+    pub fn with_pending_ingestion(&mut self, enabled: bool) {
+        self.config.ingestion_config.ingest_pending = enabled;
+    }
+
+    /// Request the pending block at most once per `interval`.
+    pub fn with_pending_poll_interval(&mut self, interval: Duration) {
+        self.config.ingestion_config.pending_poll_interval = interval;
+    }
+
+    /// Only ingest block headers and the canonical chain.
+    ///
+    /// Streams only contain headers, but storage is a fraction of a full node.
+    pub fn with_headers_only(&mut self, headers_only: bool) {
+        self.config.ingestion_config.headers_only = headers_only;
+    }
+
+    /// Enable or disable ingestion of transaction traces.
+    ///
+    /// The RPC provider must support the trace api.
+    pub fn with_trace_ingestion(&mut self, enabled: bool) {
+        self.config.ingestion_config.ingest_traces = enabled;
+    }
+
+    /// Check block hashes against the content of ingested blocks.
+    pub fn with_block_hash_validation(&mut self, validation: BlockHashValidation) {
+        self.config.ingestion_config.block_hash_validation = validation;
+    }
+
+    /// Enable or disable ingestion of the definition of declared classes.
+    pub fn with_class_ingestion(&mut self, enabled: bool) {
+        self.config.ingestion_config.ingest_classes = enabled;
+    }
+
+    /// Only accept blocks if the RPC provider at the given url agrees on
+    /// their hash.
+    pub fn with_quorum_provider(&mut self, url: &str) -> Result<(), StarkNetNodeBuilderError> {
+        self.config.ingestion_config.quorum_provider = Some(url.parse()?);
+        Ok(())
+    }
+
+    /// Ingest chain data from the feeder gateway of the given network
+    /// (`mainnet`, `goerli` or `goerli2`), or at the given url.
+    pub fn with_feeder_gateway(
+        &mut self,
+        network_or_url: &str,
+    ) -> Result<(), StarkNetNodeBuilderError> {
+        self.feeder_gateway = Some(FeederGateway::from_network_or_url(network_or_url)?);
+        Ok(())
+    }
+
+    /// Look up the L1 transaction that sent the message consumed by each L1
+    /// handler transaction on the Ethereum RPC at the given url.
+    ///
+    /// Events are read from the Starknet core contract at `core_contract`,
+    /// defaults to the mainnet contract.
+    pub fn with_ethereum_rpc(
+        &mut self,
+        url: &str,
+        core_contract: Option<String>,
+    ) -> Result<(), StarkNetNodeBuilderError> {
+        let core_contract = core_contract.unwrap_or_else(|| MAINNET_CORE_CONTRACT.to_string());
+        let resolver = L1MessageResolver::new(url.parse()?, core_contract);
+        self.config.ingestion_config.l1_message_resolver = Some(resolver);
+        Ok(())
+    }
+
+    /// Only consider blocks finalized once their state update is accepted by
+    /// the Starknet core contract, read on the Ethereum RPC at the given url.
+    ///
+    /// The core contract address defaults to the mainnet contract.
+    pub fn with_l1_finality(
+        &mut self,
+        url: &str,
+        core_contract: Option<String>,
+    ) -> Result<(), StarkNetNodeBuilderError> {
+        let core_contract = core_contract.unwrap_or_else(|| MAINNET_CORE_CONTRACT.to_string());
+        self.l1_finality = Some(L1Finality::new(url.parse()?, core_contract));
+        Ok(())
+    }
+
+    /// Ingest the L1 data availability of finalized blocks.
+    ///
+    /// State updates are read from the Starknet core contract on the
+    /// Ethereum RPC at `ethereum_rpc`, and blobs are fetched from the beacon
+    /// node api at `beacon_api`.
+    pub fn with_data_availability(
+        &mut self,
+        ethereum_rpc: &str,
+        beacon_api: &str,
+        core_contract: Option<String>,
+        l1_starting_block: Option<u64>,
+    ) -> Result<(), StarkNetNodeBuilderError> {
+        let core_contract = core_contract.unwrap_or_else(|| MAINNET_CORE_CONTRACT.to_string());
+        let mut config =
+            DataAvailabilityConfig::new(ethereum_rpc.parse()?, beacon_api.parse()?, core_contract);
+        if let Some(l1_starting_block) = l1_starting_block {
+            config.l1_starting_block = l1_starting_block;
+        }
+        self.config.ingestion_config.data_availability = Some(config);
+        Ok(())
+    }
+
+    /// Backfill finalized blocks with only the events emitted by `address`
+    /// with the given `keys`, before ingesting full blocks.
+    pub fn with_events_backfill(
+        &mut self,
+        address: Option<&str>,
+        keys: &[String],
+        chunk_size: u64,
+    ) -> Result<(), StarkNetNodeBuilderError> {
+        let parse = |value: &str| {
+            v1alpha2::FieldElement::from_hex(value)
+                .map_err(|_| StarkNetNodeBuilderError::InvalidFieldElement(value.to_string()))
+        };
+        let filter = EventFilter {
+            address: address.map(parse).transpose()?,
+            keys: keys
+                .iter()
+                .map(|key| parse(key))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        self.config.ingestion_config.events_backfill = Some(EventsBackfillConfig {
+            filter,
+            chunk_size,
+            ..EventsBackfillConfig::default()
+        });
+        Ok(())
+    }
+
+    /// Consider blocks finalized as soon as they're accepted.
+    ///
+    /// For appchains and devnets that don't settle on L1.
+    pub fn with_instant_finality(&mut self, instant_finality: bool) {
+        self.instant_finality = instant_finality;
+    }
+
+    /// Ingest from Starknet appchains and local devnets, such as Madara and
+    /// Katana.
+    ///
+    /// Blocks are finalized as soon as they're accepted, missing state
+    /// updates and traces are tolerated and block hashes are not validated.
+    pub fn with_appchain_compatibility(&mut self) {
+        self.instant_finality = true;
+        self.config.ingestion_config.tolerate_missing_data = true;
+        self.config.ingestion_config.block_hash_validation = BlockHashValidation::Disabled;
+    }
+
+    /// Refuse to ingest data if the provider doesn't serve the given network
+    /// (`mainnet`, `goerli` or `goerli2`), or the network with the given
+    /// chain id.
+    pub fn with_network(&mut self, network: &str) -> Result<(), StarkNetNodeBuilderError> {
+        let chain_id = chain_id_from_network(network)
+            .ok_or_else(|| StarkNetNodeBuilderError::InvalidNetwork(network.to_string()))?;
+        self.config.ingestion_config.expected_chain_id = Some(chain_id);
+        Ok(())
+    }
+
+    /// Subscribe to new heads from the RPC WebSocket endpoint at the given
+    /// url, instead of only polling the head.
+    pub fn with_head_subscription(&mut self, url: &str) -> Result<(), StarkNetNodeBuilderError> {
+        self.config.ingestion_config.head_subscription = Some(url.parse()?);
+        Ok(())
+    }
+
+    /// Start ingesting from the given block, instead of genesis.
+    ///
+    /// Only applies to an empty database, earlier blocks are never ingested.
+    pub fn with_starting_block(&mut self, starting_block: u64) {
+        self.config.ingestion_config.starting_block = starting_block;
+    }
+
+    /// Bootstrap finalized blocks from the DNA node at the given url.
+    pub fn with_bootstrap_node(&mut self, url: String) {
+        self.config.ingestion_config.bootstrap_node = Some(url);
+    }
+}
+
+fn gib_to_bytes(gib: u64) -> u64 {
+    gib.saturating_mul(1024 * 1024 * 1024)
 }
@@ -0,0 +1,168 @@
+//! Capture CPU and heap profiles of the running node.
+//!
+//! Profiles are served by the admin API in the pprof format, so that they
+//! can be inspected with `go tool pprof` or compatible tools. Heap profiles
+//! require building the node with the `jemalloc-profiling` feature, and are
+//! in the jemalloc format.
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use pprof::protos::Message;
+use serde::Serialize;
+
+/// Duration of CPU profiles that don't specify one.
+const DEFAULT_CPU_PROFILE_DURATION: Duration = Duration::from_secs(30);
+
+/// Default maximum duration of CPU profiles.
+const DEFAULT_MAX_DURATION: Duration = Duration::from_secs(120);
+
+/// CPU samples per second.
+const CPU_SAMPLING_FREQUENCY: i32 = 100;
+
+/// Profiling configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfilingConfig {
+    /// Maximum duration of CPU profiles.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub max_duration: Duration,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProfilingError {
+    #[error("a profile is already being captured")]
+    Busy,
+    #[error("heap profiling requires the jemalloc-profiling feature")]
+    HeapUnsupported,
+    #[error("error capturing cpu profile")]
+    Cpu(#[from] pprof::Error),
+    #[error("error dumping heap profile: {0}")]
+    Heap(String),
+    #[error("error reading heap profile")]
+    Io(#[from] std::io::Error),
+}
+
+/// Captures one profile at a time.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    config: ProfilingConfig,
+    busy: Arc<AtomicBool>,
+}
+
+/// Marks the profiler as busy until dropped.
+struct BusyGuard(Arc<AtomicBool>);
+
+impl Default for ProfilingConfig {
+    fn default() -> Self {
+        ProfilingConfig {
+            max_duration: DEFAULT_MAX_DURATION,
+        }
+    }
+}
+
+impl Profiler {
+    pub fn new(config: ProfilingConfig) -> Self {
+        Profiler {
+            config,
+            busy: Arc::default(),
+        }
+    }
+
+    /// Returns the duration of a CPU profile, clamped to the maximum.
+    pub fn cpu_profile_duration(&self, seconds: Option<u64>) -> Duration {
+        seconds
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_CPU_PROFILE_DURATION)
+            .min(self.config.max_duration)
+    }
+
+    /// Samples the CPU usage of the node for `duration`, and returns the
+    /// profile encoded in the pprof format.
+    pub async fn cpu_profile(&self, duration: Duration) -> Result<Vec<u8>, ProfilingError> {
+        let _busy = self.acquire()?;
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(CPU_SAMPLING_FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()?;
+        tokio::time::sleep(duration).await;
+        let profile = guard.report().build()?.pprof()?;
+        Ok(profile.encode_to_vec())
+    }
+
+    /// Returns a profile of the memory currently allocated by the node.
+    pub async fn heap_profile(&self) -> Result<Vec<u8>, ProfilingError> {
+        let _busy = self.acquire()?;
+        tokio::task::spawn_blocking(dump_heap_profile)
+            .await
+            .map_err(|err| ProfilingError::Heap(err.to_string()))?
+    }
+
+    fn acquire(&self) -> Result<BusyGuard, ProfilingError> {
+        if self.busy.swap(true, Ordering::SeqCst) {
+            return Err(ProfilingError::Busy);
+        }
+        Ok(BusyGuard(self.busy.clone()))
+    }
+}
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(feature = "jemalloc-profiling")]
+fn dump_heap_profile() -> Result<Vec<u8>, ProfilingError> {
+    use std::ffi::CString;
+
+    let path = std::env::temp_dir().join(format!("apibara-heap-{}.prof", std::process::id()));
+    let c_path = CString::new(path.to_string_lossy().as_bytes())
+        .map_err(|err| ProfilingError::Heap(err.to_string()))?;
+    // safety: prof.dump takes the path of the file to write, as a C string.
+    unsafe { tikv_jemalloc_ctl::raw::write(b"prof.dump\0", c_path.as_ptr()) }
+        .map_err(|err| ProfilingError::Heap(err.to_string()))?;
+    let profile = std::fs::read(&path)?;
+    std::fs::remove_file(&path)?;
+    Ok(profile)
+}
+
+#[cfg(not(feature = "jemalloc-profiling"))]
+fn dump_heap_profile() -> Result<Vec<u8>, ProfilingError> {
+    Err(ProfilingError::HeapUnsupported)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Profiler, ProfilingConfig, ProfilingError};
+
+    #[test]
+    fn test_cpu_profile_duration() {
+        let profiler = Profiler::new(ProfilingConfig {
+            max_duration: Duration::from_secs(60),
+        });
+        assert_eq!(profiler.cpu_profile_duration(None), Duration::from_secs(30));
+        assert_eq!(
+            profiler.cpu_profile_duration(Some(10)),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            profiler.cpu_profile_duration(Some(600)),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_one_profile_at_a_time() {
+        let profiler = Profiler::new(ProfilingConfig::default());
+        let busy = profiler.acquire().unwrap();
+        assert!(matches!(profiler.acquire(), Err(ProfilingError::Busy)));
+        drop(busy);
+        assert!(profiler.acquire().is_ok());
+    }
+}
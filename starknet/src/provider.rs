@@ -1,16 +1,40 @@
 //! Connect to the sequencer gateway.
+use std::{future::Future, time::Instant};
+
 use apibara_core::starknet::v1alpha2;
+use serde::Deserialize;
+use serde_json::json;
 use starknet::{
     core::types::{FieldElement, FromByteArrayError},
     providers::jsonrpc::{self, models::ErrorCode, JsonRpcClientError, RpcError},
 };
+use tokio::sync::OnceCell;
+use tracing::info;
 use url::Url;
 
 use crate::{
     core::{BlockHash, GlobalBlockId, InvalidBlockHashSize},
     db::BlockBody,
+    limiter::{RpcLimiter, RpcLimits},
 };
 
+mod gateway;
+mod l1_finality;
+mod metrics;
+mod spec;
+
+use self::metrics::RpcMetrics;
+
+pub use self::gateway::FeederGateway;
+pub use self::l1_finality::{L1Finality, L1FinalityError};
+pub use self::spec::RpcSpecVersion;
+
+/// JSON-RPC method used to fetch the execution traces of a block.
+const TRACE_BLOCK_METHOD: &str = "starknet_traceBlockTransactions";
+
+/// JSON-RPC error code returned when the block is not found.
+const BLOCK_NOT_FOUND_CODE: i64 = 24;
+
 #[derive(Debug, Clone)]
 pub enum BlockId {
     Latest,
@@ -19,6 +43,31 @@ pub enum BlockId {
     Number(u64),
 }
 
+/// Events returned by [Provider::get_events].
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    /// Only events emitted by this contract.
+    pub address: Option<v1alpha2::FieldElement>,
+    /// Only events with these keys.
+    pub keys: Vec<v1alpha2::FieldElement>,
+}
+
+/// An event, together with the block and transaction that emitted it.
+#[derive(Debug, Clone)]
+pub struct EmittedEvent {
+    pub block_id: GlobalBlockId,
+    pub transaction_hash: v1alpha2::FieldElement,
+    pub event: v1alpha2::Event,
+}
+
+/// A page of events, in the order they were emitted.
+#[derive(Debug, Clone, Default)]
+pub struct EventsPage {
+    pub events: Vec<EmittedEvent>,
+    /// Token to request the next page, if any.
+    pub continuation_token: Option<String>,
+}
+
 pub trait ProviderError: std::error::Error + Send + Sync + 'static {
     fn is_block_not_found(&self) -> bool;
 }
@@ -30,12 +79,46 @@ pub trait Provider {
     /// Get the most recent accepted block number and hash.
     async fn get_head(&self) -> Result<GlobalBlockId, Self::Error>;
 
+    /// Get the JSON-RPC spec version supported by the provider, used to
+    /// build trace requests.
+    async fn spec_version(&self) -> Result<RpcSpecVersion, Self::Error>;
+
+    /// Get the chain id of the network.
+    async fn get_chain_id(&self) -> Result<v1alpha2::FieldElement, Self::Error>;
+
     /// Get a specific block.
     async fn get_block(
         &self,
         id: &BlockId,
     ) -> Result<(v1alpha2::BlockStatus, v1alpha2::BlockHeader, BlockBody), Self::Error>;
 
+    /// Get a specific block, with the hashes of its transactions instead of
+    /// the transactions.
+    ///
+    /// Only accepted blocks are supported.
+    async fn get_block_with_tx_hashes(
+        &self,
+        id: &BlockId,
+    ) -> Result<
+        (
+            v1alpha2::BlockStatus,
+            v1alpha2::BlockHeader,
+            Vec<v1alpha2::FieldElement>,
+        ),
+        Self::Error,
+    >;
+
+    /// Get a page of the events emitted between `from_block` and `to_block`,
+    /// inclusive.
+    async fn get_events(
+        &self,
+        filter: &EventFilter,
+        from_block: u64,
+        to_block: u64,
+        continuation_token: Option<String>,
+        chunk_size: u64,
+    ) -> Result<EventsPage, Self::Error>;
+
     /// Get state update for a specific block.
     async fn get_state_update(&self, id: &BlockId) -> Result<v1alpha2::StateUpdate, Self::Error>;
 
@@ -44,11 +127,38 @@ pub trait Provider {
         &self,
         hash: &v1alpha2::FieldElement,
     ) -> Result<v1alpha2::TransactionReceipt, Self::Error>;
+
+    /// Get the JSON definition of the class with the given hash.
+    async fn get_class(
+        &self,
+        id: &BlockId,
+        class_hash: &v1alpha2::FieldElement,
+    ) -> Result<Vec<u8>, Self::Error>;
+
+    /// Get the execution traces of the transactions in a block.
+    ///
+    /// Traces are only available for blocks identified by their hash.
+    async fn get_block_traces(
+        &self,
+        id: &BlockId,
+    ) -> Result<Vec<v1alpha2::TransactionTrace>, Self::Error>;
 }
 
 /// StarkNet RPC provider over HTTP.
 pub struct HttpProvider {
     provider: jsonrpc::JsonRpcClient<jsonrpc::HttpTransport>,
+    // the trace api is not supported by the jsonrpc client.
+    client: reqwest::Client,
+    rpc_url: Url,
+    limiter: RpcLimiter,
+    // fetch chain data from the feeder gateway instead of the rpc.
+    gateway: Option<FeederGateway>,
+    // detected on the first request that depends on it.
+    spec_version: OnceCell<RpcSpecVersion>,
+    // appchains and devnets don't settle on L1.
+    instant_finality: bool,
+    l1_finality: Option<L1Finality>,
+    metrics: RpcMetrics,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -69,13 +179,97 @@ pub enum HttpProviderError {
     InvalidBlockId(#[from] FromByteArrayError),
     #[error("failed to parse block hash")]
     InvalidBlockHash(#[from] InvalidBlockHashSize),
+    #[error("request only supports blocks identified by hash")]
+    UnsupportedBlockId,
+    #[error("failed to read finality from l1")]
+    L1Finality(#[from] L1FinalityError),
 }
 
 impl HttpProvider {
     pub fn new(rpc_url: Url) -> Self {
-        let http = jsonrpc::HttpTransport::new(rpc_url);
+        let http = jsonrpc::HttpTransport::new(rpc_url.clone());
         let provider = jsonrpc::JsonRpcClient::new(http);
-        HttpProvider { provider }
+        HttpProvider {
+            provider,
+            client: reqwest::Client::new(),
+            rpc_url,
+            limiter: RpcLimiter::default(),
+            gateway: None,
+            spec_version: OnceCell::new(),
+            instant_finality: false,
+            l1_finality: None,
+            metrics: RpcMetrics::default(),
+        }
+    }
+
+    /// Returns the url of the RPC provider.
+    pub fn rpc_url(&self) -> &Url {
+        &self.rpc_url
+    }
+
+    /// Fetch blocks, state updates, receipts and classes from the feeder
+    /// gateway. Traces are still fetched from the RPC.
+    pub fn with_feeder_gateway(mut self, gateway: FeederGateway) -> Self {
+        self.gateway = Some(gateway);
+        self
+    }
+
+    /// Limit the rate and concurrency of requests to the provider.
+    pub fn with_limits(mut self, limits: RpcLimits) -> Self {
+        self.limiter = RpcLimiter::new(limits);
+        self
+    }
+
+    /// Report accepted blocks as finalized, for chains that don't settle
+    /// on L1.
+    pub fn with_instant_finality(mut self, instant_finality: bool) -> Self {
+        self.instant_finality = instant_finality;
+        self
+    }
+
+    /// Decide finality from the Starknet core contract on Ethereum, instead
+    /// of the status reported by the provider.
+    pub fn with_l1_finality(mut self, l1_finality: L1Finality) -> Self {
+        self.l1_finality = Some(l1_finality);
+        self
+    }
+
+    async fn block_status(
+        &self,
+        status: v1alpha2::BlockStatus,
+        header: &v1alpha2::BlockHeader,
+    ) -> Result<v1alpha2::BlockStatus, HttpProviderError> {
+        let status = match self.l1_finality {
+            None => status,
+            Some(ref l1_finality) => {
+                l1_finality
+                    .block_status(status, header.block_number)
+                    .await?
+            }
+        };
+        if self.instant_finality && status == v1alpha2::BlockStatus::AcceptedOnL2 {
+            return Ok(v1alpha2::BlockStatus::AcceptedOnL1);
+        }
+        Ok(status)
+    }
+
+    /// Sends the request to `method` once the limiter allows it.
+    ///
+    /// Errors other than missing blocks are reported to the limiter, so that
+    /// it can reduce concurrency when the provider is struggling.
+    async fn limited<T, F>(&self, method: &'static str, request: F) -> Result<T, HttpProviderError>
+    where
+        F: Future<Output = Result<T, HttpProviderError>>,
+    {
+        let mut permit = self.limiter.acquire().await;
+        let started_at = Instant::now();
+        let result = request.await;
+        let failed = matches!(result, Err(ref err) if !err.is_block_not_found());
+        if failed {
+            permit.failed();
+        }
+        self.metrics.record(method, started_at.elapsed(), failed);
+        result
     }
 }
 
@@ -117,16 +311,61 @@ impl Provider for HttpProvider {
 
     #[tracing::instrument(skip(self), err(Debug))]
     async fn get_head(&self) -> Result<GlobalBlockId, Self::Error> {
-        let hash_and_number = self
-            .provider
-            .block_hash_and_number()
-            .await
-            .map_err(HttpProviderError::from_provider_error)?;
-        let hash: v1alpha2::FieldElement = hash_and_number.block_hash.into();
-        Ok(GlobalBlockId::new(
-            hash_and_number.block_number,
-            hash.into(),
-        ))
+        self.limited("starknet_blockHashAndNumber", async {
+            if let Some(ref gateway) = self.gateway {
+                return gateway.get_head().await;
+            }
+            let hash_and_number = self
+                .provider
+                .block_hash_and_number()
+                .await
+                .map_err(HttpProviderError::from_provider_error)?;
+            let hash: v1alpha2::FieldElement = hash_and_number.block_hash.into();
+            Ok(GlobalBlockId::new(
+                hash_and_number.block_number,
+                hash.into(),
+            ))
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip(self), err(Debug))]
+    async fn spec_version(&self) -> Result<RpcSpecVersion, Self::Error> {
+        let version = self
+            .spec_version
+            .get_or_try_init(|| {
+                self.limited("starknet_specVersion", async {
+                    let response = self
+                        .client
+                        .post(self.rpc_url.clone())
+                        .json(&RpcSpecVersion::request())
+                        .send()
+                        .await
+                        .map_err(|err| HttpProviderError::Provider(Box::new(err)))?
+                        .json()
+                        .await
+                        .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
+                    let version = RpcSpecVersion::from_response(response)
+                        .map_err(|err| HttpProviderError::Provider(err.into()))?;
+                    info!(spec_version = %version, "detected provider rpc spec version");
+                    Ok(version)
+                })
+            })
+            .await?;
+        Ok(*version)
+    }
+
+    #[tracing::instrument(skip(self), err(Debug))]
+    async fn get_chain_id(&self) -> Result<v1alpha2::FieldElement, Self::Error> {
+        self.limited("starknet_chainId", async {
+            let chain_id = self
+                .provider
+                .chain_id()
+                .await
+                .map_err(HttpProviderError::from_provider_error)?;
+            Ok(chain_id.into())
+        })
+        .await
     }
 
     #[tracing::instrument(skip(self), err(Debug))]
@@ -134,45 +373,172 @@ impl Provider for HttpProvider {
         &self,
         id: &BlockId,
     ) -> Result<(v1alpha2::BlockStatus, v1alpha2::BlockHeader, BlockBody), Self::Error> {
-        let block_id = id.try_into()?;
-        let block = self
-            .provider
-            .get_block_with_txs(&block_id)
-            .await
-            .map_err(HttpProviderError::from_provider_error)?;
-
-        match block {
-            jsonrpc::models::MaybePendingBlockWithTxs::Block(ref block) => {
-                if id.is_pending() {
-                    return Err(HttpProviderError::UnexpectedPendingBlock);
+        let (status, header, body) = self
+            .limited("starknet_getBlockWithTxs", async {
+                if let Some(ref gateway) = self.gateway {
+                    return gateway.get_block(id).await;
                 }
-                let status = block.to_proto();
-                let header = block.to_proto();
-                let body = block.to_proto();
-                Ok((status, header, body))
-            }
-            jsonrpc::models::MaybePendingBlockWithTxs::PendingBlock(ref block) => {
-                if !id.is_pending() {
-                    return Err(HttpProviderError::ExpectedPendingBlock);
+                let block_id = id.try_into()?;
+                let block = self
+                    .provider
+                    .get_block_with_txs(&block_id)
+                    .await
+                    .map_err(HttpProviderError::from_provider_error)?;
+
+                match block {
+                    jsonrpc::models::MaybePendingBlockWithTxs::Block(ref block) => {
+                        if id.is_pending() {
+                            return Err(HttpProviderError::UnexpectedPendingBlock);
+                        }
+                        let status = block.to_proto();
+                        let header = block.to_proto();
+                        let body = block.to_proto();
+                        Ok((status, header, body))
+                    }
+                    jsonrpc::models::MaybePendingBlockWithTxs::PendingBlock(ref block) => {
+                        if !id.is_pending() {
+                            return Err(HttpProviderError::ExpectedPendingBlock);
+                        }
+                        let status = block.to_proto();
+                        let header = block.to_proto();
+                        let body = block.to_proto();
+                        Ok((status, header, body))
+                    }
                 }
-                let status = block.to_proto();
-                let header = block.to_proto();
-                let body = block.to_proto();
-                Ok((status, header, body))
-            }
+            })
+            .await?;
+        let status = self.block_status(status, &header).await?;
+        Ok((status, header, body))
+    }
+
+    #[tracing::instrument(skip(self), err(Debug))]
+    async fn get_block_with_tx_hashes(
+        &self,
+        id: &BlockId,
+    ) -> Result<
+        (
+            v1alpha2::BlockStatus,
+            v1alpha2::BlockHeader,
+            Vec<v1alpha2::FieldElement>,
+        ),
+        Self::Error,
+    > {
+        if id.is_pending() {
+            return Err(HttpProviderError::UnexpectedPendingBlock);
         }
+        let (status, header, hashes) = self
+            .limited("starknet_getBlockWithTxHashes", async {
+                if let Some(ref gateway) = self.gateway {
+                    let (status, header, body) = gateway.get_block(id).await?;
+                    let hashes = body
+                        .transactions
+                        .into_iter()
+                        .map(|tx| tx.meta.and_then(|meta| meta.hash).unwrap_or_default())
+                        .collect();
+                    return Ok((status, header, hashes));
+                }
+                let block_id = id.try_into()?;
+                let block = self
+                    .provider
+                    .get_block_with_tx_hashes(&block_id)
+                    .await
+                    .map_err(HttpProviderError::from_provider_error)?;
+
+                match block {
+                    jsonrpc::models::MaybePendingBlockWithTxHashes::Block(ref block) => {
+                        let status = block.status.to_proto();
+                        let header = block.to_proto();
+                        let hashes = block
+                            .transactions
+                            .iter()
+                            .map(|hash| (*hash).into())
+                            .collect();
+                        Ok((status, header, hashes))
+                    }
+                    jsonrpc::models::MaybePendingBlockWithTxHashes::PendingBlock(_) => {
+                        Err(HttpProviderError::UnexpectedPendingBlock)
+                    }
+                }
+            })
+            .await?;
+        let status = self.block_status(status, &header).await?;
+        Ok((status, header, hashes))
+    }
+
+    #[tracing::instrument(skip(self), err(Debug))]
+    async fn get_events(
+        &self,
+        filter: &EventFilter,
+        from_block: u64,
+        to_block: u64,
+        continuation_token: Option<String>,
+        chunk_size: u64,
+    ) -> Result<EventsPage, Self::Error> {
+        use jsonrpc::models::{BlockId as SNBlockId, EventFilter as SNEventFilter};
+
+        self.limited("starknet_getEvents", async {
+            let address = filter
+                .address
+                .as_ref()
+                .map(FieldElement::try_from)
+                .transpose()?;
+            let keys = filter
+                .keys
+                .iter()
+                .map(FieldElement::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            let filter = SNEventFilter {
+                from_block: Some(SNBlockId::Number(from_block)),
+                to_block: Some(SNBlockId::Number(to_block)),
+                address,
+                keys: if keys.is_empty() { None } else { Some(keys) },
+            };
+            let page = self
+                .provider
+                .get_events(filter, continuation_token, chunk_size)
+                .await
+                .map_err(HttpProviderError::from_provider_error)?;
+
+            let events = page
+                .events
+                .iter()
+                .map(|event| {
+                    let block_hash: v1alpha2::FieldElement = event.block_hash.into();
+                    EmittedEvent {
+                        block_id: GlobalBlockId::new(event.block_number, block_hash.into()),
+                        transaction_hash: event.transaction_hash.into(),
+                        event: v1alpha2::Event {
+                            from_address: Some(event.from_address.into()),
+                            keys: event.keys.iter().map(|key| (*key).into()).collect(),
+                            data: event.data.iter().map(|data| (*data).into()).collect(),
+                        },
+                    }
+                })
+                .collect();
+            Ok(EventsPage {
+                events,
+                continuation_token: page.continuation_token,
+            })
+        })
+        .await
     }
 
     #[tracing::instrument(skip(self), err(Debug))]
     async fn get_state_update(&self, id: &BlockId) -> Result<v1alpha2::StateUpdate, Self::Error> {
-        let block_id = id.try_into()?;
-        let state_update = self
-            .provider
-            .get_state_update(&block_id)
-            .await
-            .map_err(HttpProviderError::from_provider_error)?
-            .to_proto();
-        Ok(state_update)
+        self.limited("starknet_getStateUpdate", async {
+            if let Some(ref gateway) = self.gateway {
+                return gateway.get_state_update(id).await;
+            }
+            let block_id = id.try_into()?;
+            let state_update = self
+                .provider
+                .get_state_update(&block_id)
+                .await
+                .map_err(HttpProviderError::from_provider_error)?
+                .to_proto();
+            Ok(state_update)
+        })
+        .await
     }
 
     #[tracing::instrument(skip(self), fields(hash = %hash), err(Debug))]
@@ -180,16 +546,99 @@ impl Provider for HttpProvider {
         &self,
         hash: &v1alpha2::FieldElement,
     ) -> Result<v1alpha2::TransactionReceipt, Self::Error> {
-        let hash: FieldElement = hash
-            .try_into()
-            .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
-        let receipt = self
-            .provider
-            .get_transaction_receipt(hash)
-            .await
-            .map_err(HttpProviderError::from_provider_error)?
-            .to_proto();
-        Ok(receipt)
+        self.limited("starknet_getTransactionReceipt", async {
+            let hash: FieldElement = hash
+                .try_into()
+                .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
+            if let Some(ref gateway) = self.gateway {
+                return gateway.get_transaction_receipt(&hash).await;
+            }
+            let receipt = self
+                .provider
+                .get_transaction_receipt(hash)
+                .await
+                .map_err(HttpProviderError::from_provider_error)?
+                .to_proto();
+            Ok(receipt)
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip(self), fields(class_hash = %class_hash), err(Debug))]
+    async fn get_class(
+        &self,
+        id: &BlockId,
+        class_hash: &v1alpha2::FieldElement,
+    ) -> Result<Vec<u8>, Self::Error> {
+        self.limited("starknet_getClass", async {
+            let block_id = id.try_into()?;
+            let class_hash: FieldElement = class_hash
+                .try_into()
+                .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
+            if let Some(ref gateway) = self.gateway {
+                return gateway.get_class(id, &class_hash).await;
+            }
+            let class = self
+                .provider
+                .get_class(&block_id, class_hash)
+                .await
+                .map_err(HttpProviderError::from_provider_error)?;
+            let definition = serde_json::to_vec(&class)
+                .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
+            Ok(definition)
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip(self), err(Debug))]
+    async fn get_block_traces(
+        &self,
+        id: &BlockId,
+    ) -> Result<Vec<v1alpha2::TransactionTrace>, Self::Error> {
+        let block_hash: FieldElement = match id {
+            BlockId::Hash(hash) => hash.try_into()?,
+            _ => return Err(HttpProviderError::UnsupportedBlockId),
+        };
+        let spec_version = self.spec_version().await?;
+        self.limited(TRACE_BLOCK_METHOD, async {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": TRACE_BLOCK_METHOD,
+                "params": spec_version.trace_block_params(&format!("{:#x}", block_hash)),
+            });
+            let response: TraceResponse = self
+                .client
+                .post(self.rpc_url.clone())
+                .json(&request)
+                .send()
+                .await
+                .map_err(|err| HttpProviderError::Provider(Box::new(err)))?
+                .json()
+                .await
+                .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
+
+            if let Some(error) = response.error {
+                if error.code == BLOCK_NOT_FOUND_CODE {
+                    return Err(HttpProviderError::BlockNotFound);
+                }
+                return Err(HttpProviderError::Provider(error.message.into()));
+            }
+
+            let traces = response
+                .result
+                .unwrap_or_default()
+                .iter()
+                .enumerate()
+                .map(|(tx_idx, trace)| {
+                    let mut trace: v1alpha2::TransactionTrace = trace.to_proto();
+                    trace.transaction_index = tx_idx as u64;
+                    trace
+                })
+                .collect();
+            Ok(traces)
+        })
+        .await
     }
 }
 
@@ -252,6 +701,29 @@ impl ToProto<v1alpha2::BlockHeader> for jsonrpc::models::BlockWithTxs {
     }
 }
 
+impl ToProto<v1alpha2::BlockHeader> for jsonrpc::models::BlockWithTxHashes {
+    fn to_proto(&self) -> v1alpha2::BlockHeader {
+        let block_hash = self.block_hash.into();
+        let parent_block_hash = self.parent_hash.into();
+        let block_number = self.block_number;
+        let sequencer_address = self.sequencer_address.into();
+        let new_root = self.new_root.into();
+        let timestamp = pbjson_types::Timestamp {
+            nanos: 0,
+            seconds: self.timestamp as i64,
+        };
+
+        v1alpha2::BlockHeader {
+            block_hash: Some(block_hash),
+            parent_block_hash: Some(parent_block_hash),
+            block_number,
+            sequencer_address: Some(sequencer_address),
+            new_root: Some(new_root),
+            timestamp: Some(timestamp),
+        }
+    }
+}
+
 impl ToProto<v1alpha2::BlockHeader> for jsonrpc::models::PendingBlockWithTxs {
     fn to_proto(&self) -> v1alpha2::BlockHeader {
         let block_hash = FieldElement::ZERO.into();
@@ -276,14 +748,20 @@ impl ToProto<v1alpha2::BlockHeader> for jsonrpc::models::PendingBlockWithTxs {
 impl ToProto<BlockBody> for jsonrpc::models::BlockWithTxs {
     fn to_proto(&self) -> BlockBody {
         let transactions = self.transactions.iter().map(|tx| tx.to_proto()).collect();
-        BlockBody { transactions }
+        BlockBody {
+            transactions,
+            ..BlockBody::default()
+        }
     }
 }
 
 impl ToProto<BlockBody> for jsonrpc::models::PendingBlockWithTxs {
     fn to_proto(&self) -> BlockBody {
         let transactions = self.transactions.iter().map(|tx| tx.to_proto()).collect();
-        BlockBody { transactions }
+        BlockBody {
+            transactions,
+            ..BlockBody::default()
+        }
     }
 }
 
@@ -461,10 +939,12 @@ impl ToProto<v1alpha2::Transaction> for jsonrpc::models::L1HandlerTransaction {
         use v1alpha2::transaction::Transaction;
 
         let hash = self.transaction_hash.into();
+        let nonce = v1alpha2::FieldElement::from_u64(self.nonce);
         let version = self.version;
 
         let meta = v1alpha2::TransactionMeta {
             hash: Some(hash),
+            nonce: Some(nonce),
             version,
             ..v1alpha2::TransactionMeta::default()
         };
@@ -477,6 +957,7 @@ impl ToProto<v1alpha2::Transaction> for jsonrpc::models::L1HandlerTransaction {
             contract_address: Some(contract_address),
             entry_point_selector: Some(entry_point_selector),
             calldata,
+            message: None,
         };
 
         v1alpha2::Transaction {
@@ -917,3 +1398,107 @@ impl ToProto<v1alpha2::NonceUpdate> for jsonrpc::models::NonceUpdate {
         }
     }
 }
+
+#[derive(Debug, Deserialize)]
+struct TraceResponse {
+    result: Option<Vec<BlockTransactionTrace>>,
+    error: Option<TraceResponseError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TraceResponseError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockTransactionTrace {
+    transaction_hash: FieldElement,
+    trace_root: TransactionTraceRoot,
+}
+
+/// Trace of any transaction type.
+///
+/// L1 handler and deploy account transactions name their main invocation
+/// differently, but it's stored as the execute invocation.
+#[derive(Debug, Deserialize)]
+struct TransactionTraceRoot {
+    validate_invocation: Option<FunctionInvocation>,
+    #[serde(alias = "function_invocation", alias = "constructor_invocation")]
+    execute_invocation: Option<ExecuteInvocation>,
+    fee_transfer_invocation: Option<FunctionInvocation>,
+}
+
+/// The execute invocation of reverted transactions only contains the revert
+/// reason.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ExecuteInvocation {
+    Success(Box<FunctionInvocation>),
+    Reverted {},
+}
+
+#[derive(Debug, Deserialize)]
+struct FunctionInvocation {
+    contract_address: FieldElement,
+    entry_point_selector: FieldElement,
+    #[serde(default)]
+    calldata: Vec<FieldElement>,
+    caller_address: FieldElement,
+    #[serde(alias = "code_address")]
+    class_hash: Option<FieldElement>,
+    #[serde(default)]
+    result: Vec<FieldElement>,
+    #[serde(default)]
+    calls: Vec<FunctionInvocation>,
+}
+
+impl ToProto<v1alpha2::TransactionTrace> for BlockTransactionTrace {
+    fn to_proto(&self) -> v1alpha2::TransactionTrace {
+        let transaction_hash = self.transaction_hash.into();
+        let validate_invocation = self
+            .trace_root
+            .validate_invocation
+            .as_ref()
+            .map(|inv| inv.to_proto());
+        let execute_invocation = match self.trace_root.execute_invocation {
+            Some(ExecuteInvocation::Success(ref inv)) => Some(inv.to_proto()),
+            _ => None,
+        };
+        let fee_transfer_invocation = self
+            .trace_root
+            .fee_transfer_invocation
+            .as_ref()
+            .map(|inv| inv.to_proto());
+
+        v1alpha2::TransactionTrace {
+            transaction_hash: Some(transaction_hash),
+            transaction_index: 0,
+            validate_invocation,
+            execute_invocation,
+            fee_transfer_invocation,
+        }
+    }
+}
+
+impl ToProto<v1alpha2::FunctionInvocation> for FunctionInvocation {
+    fn to_proto(&self) -> v1alpha2::FunctionInvocation {
+        let contract_address = self.contract_address.into();
+        let entry_point_selector = self.entry_point_selector.into();
+        let calldata = self.calldata.iter().map(|fe| fe.into()).collect();
+        let caller_address = self.caller_address.into();
+        let class_hash = self.class_hash.map(|fe| fe.into());
+        let result = self.result.iter().map(|fe| fe.into()).collect();
+        let calls = self.calls.iter().map(|call| call.to_proto()).collect();
+
+        v1alpha2::FunctionInvocation {
+            contract_address: Some(contract_address),
+            entry_point_selector: Some(entry_point_selector),
+            calldata,
+            caller_address: Some(caller_address),
+            class_hash,
+            result,
+            calls,
+        }
+    }
+}
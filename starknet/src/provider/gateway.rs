@@ -0,0 +1,599 @@
+//! Fetch chain data from the sequencer feeder gateway.
+//!
+//! The feeder gateway is an alternative to the RPC for networks that run
+//! one. Blocks include their receipts and full pending data.
+use std::collections::BTreeMap;
+
+use apibara_core::starknet::v1alpha2;
+use serde::{de::DeserializeOwned, Deserialize};
+use starknet::core::types::FieldElement;
+use url::Url;
+
+use crate::{core::GlobalBlockId, db::BlockBody};
+
+use super::{BlockId, HttpProviderError, ToProto, TryToProto};
+
+/// Error code returned by the feeder gateway when the block is not found.
+const BLOCK_NOT_FOUND_CODE: &str = "StarknetErrorCode.BLOCK_NOT_FOUND";
+
+/// Feeder gateway client.
+#[derive(Debug, Clone)]
+pub struct FeederGateway {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl FeederGateway {
+    pub fn new(url: Url) -> Self {
+        FeederGateway {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+
+    /// Creates a client for the feeder gateway of the given network
+    /// (`mainnet`, `goerli` or `goerli2`), or at the given url.
+    pub fn from_network_or_url(network_or_url: &str) -> Result<Self, url::ParseError> {
+        let url = match network_or_url {
+            "mainnet" => "https://alpha-mainnet.starknet.io/feeder_gateway",
+            "goerli" => "https://alpha4.starknet.io/feeder_gateway",
+            "goerli2" => "https://alpha4-2.starknet.io/feeder_gateway",
+            url => url,
+        };
+        Ok(FeederGateway::new(url.parse()?))
+    }
+
+    pub async fn get_head(&self) -> Result<GlobalBlockId, HttpProviderError> {
+        let block: Block = self
+            .request("get_block", &block_query(&BlockId::Latest)?)
+            .await?;
+        let number = block
+            .block_number
+            .ok_or(HttpProviderError::UnexpectedPendingBlock)?;
+        let hash: v1alpha2::FieldElement = block
+            .block_hash
+            .ok_or(HttpProviderError::UnexpectedPendingBlock)?
+            .into();
+        Ok(GlobalBlockId::new(number, hash.into()))
+    }
+
+    pub async fn get_block(
+        &self,
+        id: &BlockId,
+    ) -> Result<(v1alpha2::BlockStatus, v1alpha2::BlockHeader, BlockBody), HttpProviderError> {
+        let block: Block = self.request("get_block", &block_query(id)?).await?;
+        let is_pending = block.block_hash.is_none();
+        if id.is_pending() && !is_pending {
+            return Err(HttpProviderError::ExpectedPendingBlock);
+        }
+        if !id.is_pending() && is_pending {
+            return Err(HttpProviderError::UnexpectedPendingBlock);
+        }
+
+        let status = block.status.to_proto();
+        let header = block.to_proto();
+        let body = block.try_to_proto()?;
+        Ok((status, header, body))
+    }
+
+    pub async fn get_state_update(
+        &self,
+        id: &BlockId,
+    ) -> Result<v1alpha2::StateUpdate, HttpProviderError> {
+        let state_update: StateUpdate = self.request("get_state_update", &block_query(id)?).await?;
+        state_update.try_to_proto()
+    }
+
+    /// Returns the transaction receipt.
+    ///
+    /// The feeder gateway doesn't return the address of deployed contracts.
+    pub async fn get_transaction_receipt(
+        &self,
+        hash: &FieldElement,
+    ) -> Result<v1alpha2::TransactionReceipt, HttpProviderError> {
+        let query = [("transactionHash", format!("{:#x}", hash))];
+        let receipt: TransactionReceipt = self.request("get_transaction_receipt", &query).await?;
+        Ok(receipt.to_proto())
+    }
+
+    pub async fn get_class(
+        &self,
+        id: &BlockId,
+        class_hash: &FieldElement,
+    ) -> Result<Vec<u8>, HttpProviderError> {
+        let mut query = block_query(id)?;
+        query.push(("classHash", format!("{:#x}", class_hash)));
+        let response = self.send("get_class_by_hash", &query).await?;
+        let definition = response
+            .bytes()
+            .await
+            .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
+        Ok(definition.to_vec())
+    }
+
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, HttpProviderError> {
+        self.send(method, query)
+            .await?
+            .json()
+            .await
+            .map_err(|err| HttpProviderError::Provider(Box::new(err)))
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        query: &[(&str, String)],
+    ) -> Result<reqwest::Response, HttpProviderError> {
+        let url = format!("{}/{}", self.url.as_str().trim_end_matches('/'), method);
+        let response = self
+            .client
+            .get(url)
+            .query(query)
+            .send()
+            .await
+            .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
+
+        if response.status().is_success() {
+            return Ok(response);
+        }
+
+        let error: GatewayError = response
+            .json()
+            .await
+            .map_err(|err| HttpProviderError::Provider(Box::new(err)))?;
+        if error.code == BLOCK_NOT_FOUND_CODE {
+            return Err(HttpProviderError::BlockNotFound);
+        }
+        Err(HttpProviderError::Provider(error.message.into()))
+    }
+}
+
+fn block_query(id: &BlockId) -> Result<Vec<(&'static str, String)>, HttpProviderError> {
+    let query = match id {
+        BlockId::Latest => ("blockNumber", "latest".to_string()),
+        BlockId::Pending => ("blockNumber", "pending".to_string()),
+        BlockId::Hash(hash) => {
+            let hash: FieldElement = hash.try_into()?;
+            ("blockHash", format!("{:#x}", hash))
+        }
+        BlockId::Number(number) => ("blockNumber", number.to_string()),
+    };
+    Ok(vec![query])
+}
+
+/// Parses a hex or decimal string, used by the gateway for versions.
+fn parse_version(version: &Option<String>) -> Result<u64, HttpProviderError> {
+    let version = match version {
+        None => return Ok(0),
+        Some(version) => version,
+    };
+    let parsed = match version.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => version.parse(),
+    };
+    parsed.map_err(|err| HttpProviderError::Provider(Box::new(err)))
+}
+
+fn parse_field_element(value: &str) -> Result<FieldElement, HttpProviderError> {
+    FieldElement::from_hex_be(value).map_err(|err| HttpProviderError::Provider(Box::new(err)))
+}
+
+#[derive(Debug, Deserialize)]
+struct GatewayError {
+    code: String,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum BlockStatus {
+    Pending,
+    AcceptedOnL2,
+    AcceptedOnL1,
+    Rejected,
+    Aborted,
+}
+
+#[derive(Debug, Deserialize)]
+struct Block {
+    block_hash: Option<FieldElement>,
+    block_number: Option<u64>,
+    parent_block_hash: FieldElement,
+    state_root: Option<FieldElement>,
+    status: BlockStatus,
+    timestamp: u64,
+    sequencer_address: Option<FieldElement>,
+    #[serde(default)]
+    transactions: Vec<Transaction>,
+}
+
+/// Transaction of any type, fields depend on the type and version.
+#[derive(Debug, Deserialize)]
+struct Transaction {
+    #[serde(rename = "type")]
+    transaction_type: String,
+    transaction_hash: FieldElement,
+    version: Option<String>,
+    max_fee: Option<FieldElement>,
+    #[serde(default)]
+    signature: Vec<FieldElement>,
+    nonce: Option<FieldElement>,
+    contract_address: Option<FieldElement>,
+    sender_address: Option<FieldElement>,
+    entry_point_selector: Option<FieldElement>,
+    #[serde(default)]
+    calldata: Vec<FieldElement>,
+    class_hash: Option<FieldElement>,
+    contract_address_salt: Option<FieldElement>,
+    #[serde(default)]
+    constructor_calldata: Vec<FieldElement>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransactionReceipt {
+    transaction_hash: FieldElement,
+    #[serde(default)]
+    transaction_index: u64,
+    actual_fee: Option<FieldElement>,
+    #[serde(default)]
+    l2_to_l1_messages: Vec<L2ToL1Message>,
+    #[serde(default)]
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+struct L2ToL1Message {
+    to_address: FieldElement,
+    #[serde(default)]
+    payload: Vec<FieldElement>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    from_address: FieldElement,
+    #[serde(default)]
+    keys: Vec<FieldElement>,
+    #[serde(default)]
+    data: Vec<FieldElement>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StateUpdate {
+    new_root: Option<FieldElement>,
+    old_root: FieldElement,
+    state_diff: StateDiff,
+}
+
+/// Maps are sorted by address, so that the stored data doesn't depend on
+/// the response order.
+#[derive(Debug, Deserialize)]
+struct StateDiff {
+    #[serde(default)]
+    storage_diffs: BTreeMap<String, Vec<StorageEntry>>,
+    #[serde(default)]
+    deployed_contracts: Vec<DeployedContract>,
+    #[serde(default)]
+    old_declared_contracts: Vec<FieldElement>,
+    #[serde(default)]
+    declared_classes: Vec<DeclaredClass>,
+    #[serde(default)]
+    nonces: BTreeMap<String, FieldElement>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StorageEntry {
+    key: FieldElement,
+    value: FieldElement,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeployedContract {
+    address: FieldElement,
+    class_hash: FieldElement,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeclaredClass {
+    class_hash: FieldElement,
+}
+
+impl ToProto<v1alpha2::BlockStatus> for BlockStatus {
+    fn to_proto(&self) -> v1alpha2::BlockStatus {
+        match self {
+            BlockStatus::Pending => v1alpha2::BlockStatus::Pending,
+            BlockStatus::AcceptedOnL2 => v1alpha2::BlockStatus::AcceptedOnL2,
+            BlockStatus::AcceptedOnL1 => v1alpha2::BlockStatus::AcceptedOnL1,
+            BlockStatus::Rejected | BlockStatus::Aborted => v1alpha2::BlockStatus::Rejected,
+        }
+    }
+}
+
+impl ToProto<v1alpha2::BlockHeader> for Block {
+    fn to_proto(&self) -> v1alpha2::BlockHeader {
+        // pending blocks have no hash and number.
+        let block_hash = self.block_hash.unwrap_or(FieldElement::ZERO).into();
+        let block_number = self.block_number.unwrap_or(u64::MAX);
+        let parent_block_hash = self.parent_block_hash.into();
+        let sequencer_address = self.sequencer_address.unwrap_or(FieldElement::ZERO).into();
+        let new_root = self.state_root.map(|root| root.into());
+        let timestamp = pbjson_types::Timestamp {
+            nanos: 0,
+            seconds: self.timestamp as i64,
+        };
+
+        v1alpha2::BlockHeader {
+            block_hash: Some(block_hash),
+            parent_block_hash: Some(parent_block_hash),
+            block_number,
+            sequencer_address: Some(sequencer_address),
+            new_root,
+            timestamp: Some(timestamp),
+        }
+    }
+}
+
+impl TryToProto<BlockBody> for Block {
+    type Error = HttpProviderError;
+
+    fn try_to_proto(&self) -> Result<BlockBody, Self::Error> {
+        let transactions = self
+            .transactions
+            .iter()
+            .map(|tx| tx.try_to_proto())
+            .collect::<Result<_, _>>()?;
+        Ok(BlockBody {
+            transactions,
+            ..BlockBody::default()
+        })
+    }
+}
+
+impl TryToProto<v1alpha2::Transaction> for Transaction {
+    type Error = HttpProviderError;
+
+    fn try_to_proto(&self) -> Result<v1alpha2::Transaction, Self::Error> {
+        use v1alpha2::transaction::Transaction;
+
+        let version = parse_version(&self.version)?;
+        let meta = v1alpha2::TransactionMeta {
+            hash: Some(self.transaction_hash.into()),
+            max_fee: self.max_fee.map(|fe| fe.into()),
+            signature: self.signature.iter().map(|fe| fe.into()).collect(),
+            nonce: self.nonce.map(|fe| fe.into()),
+            version,
+        };
+
+        let calldata = self.calldata.iter().map(|fe| fe.into()).collect();
+        let constructor_calldata = self
+            .constructor_calldata
+            .iter()
+            .map(|fe| fe.into())
+            .collect();
+
+        let transaction = match self.transaction_type.as_str() {
+            "INVOKE_FUNCTION" if version == 0 => {
+                Transaction::InvokeV0(v1alpha2::InvokeTransactionV0 {
+                    contract_address: self.contract_address.map(|fe| fe.into()),
+                    entry_point_selector: self.entry_point_selector.map(|fe| fe.into()),
+                    calldata,
+                })
+            }
+            "INVOKE_FUNCTION" => Transaction::InvokeV1(v1alpha2::InvokeTransactionV1 {
+                sender_address: self
+                    .sender_address
+                    .or(self.contract_address)
+                    .map(|fe| fe.into()),
+                calldata,
+            }),
+            "DEPLOY" => Transaction::Deploy(v1alpha2::DeployTransaction {
+                class_hash: self.class_hash.map(|fe| fe.into()),
+                contract_address_salt: self.contract_address_salt.map(|fe| fe.into()),
+                constructor_calldata,
+            }),
+            "DECLARE" => Transaction::Declare(v1alpha2::DeclareTransaction {
+                class_hash: self.class_hash.map(|fe| fe.into()),
+                sender_address: self.sender_address.map(|fe| fe.into()),
+            }),
+            "L1_HANDLER" => Transaction::L1Handler(v1alpha2::L1HandlerTransaction {
+                contract_address: self.contract_address.map(|fe| fe.into()),
+                entry_point_selector: self.entry_point_selector.map(|fe| fe.into()),
+                calldata,
+                message: None,
+            }),
+            "DEPLOY_ACCOUNT" => Transaction::DeployAccount(v1alpha2::DeployAccountTransaction {
+                contract_address_salt: self.contract_address_salt.map(|fe| fe.into()),
+                class_hash: self.class_hash.map(|fe| fe.into()),
+                constructor_calldata,
+            }),
+            other => {
+                return Err(HttpProviderError::Provider(
+                    format!("unknown transaction type {}", other).into(),
+                ))
+            }
+        };
+
+        Ok(v1alpha2::Transaction {
+            meta: Some(meta),
+            transaction: Some(transaction),
+        })
+    }
+}
+
+impl ToProto<v1alpha2::TransactionReceipt> for TransactionReceipt {
+    fn to_proto(&self) -> v1alpha2::TransactionReceipt {
+        let transaction_hash = self.transaction_hash.into();
+        let actual_fee = self.actual_fee.map(|fe| fe.into());
+        let l2_to_l1_messages = self
+            .l2_to_l1_messages
+            .iter()
+            .map(|msg| msg.to_proto())
+            .collect();
+        let events = self.events.iter().map(|ev| ev.to_proto()).collect();
+
+        v1alpha2::TransactionReceipt {
+            transaction_index: self.transaction_index,
+            transaction_hash: Some(transaction_hash),
+            actual_fee,
+            l2_to_l1_messages,
+            events,
+            contract_address: None,
+        }
+    }
+}
+
+impl ToProto<v1alpha2::L2ToL1Message> for L2ToL1Message {
+    fn to_proto(&self) -> v1alpha2::L2ToL1Message {
+        let to_address = self.to_address.into();
+        let payload = self.payload.iter().map(|p| p.into()).collect();
+
+        v1alpha2::L2ToL1Message {
+            to_address: Some(to_address),
+            payload,
+        }
+    }
+}
+
+impl ToProto<v1alpha2::Event> for Event {
+    fn to_proto(&self) -> v1alpha2::Event {
+        let from_address = self.from_address.into();
+        let keys = self.keys.iter().map(|k| k.into()).collect();
+        let data = self.data.iter().map(|d| d.into()).collect();
+
+        v1alpha2::Event {
+            from_address: Some(from_address),
+            keys,
+            data,
+        }
+    }
+}
+
+impl TryToProto<v1alpha2::StateUpdate> for StateUpdate {
+    type Error = HttpProviderError;
+
+    fn try_to_proto(&self) -> Result<v1alpha2::StateUpdate, Self::Error> {
+        let new_root = self.new_root.map(|root| root.into());
+        let old_root = self.old_root.into();
+        let state_diff = self.state_diff.try_to_proto()?;
+        Ok(v1alpha2::StateUpdate {
+            new_root,
+            old_root: Some(old_root),
+            state_diff: Some(state_diff),
+        })
+    }
+}
+
+impl TryToProto<v1alpha2::StateDiff> for StateDiff {
+    type Error = HttpProviderError;
+
+    fn try_to_proto(&self) -> Result<v1alpha2::StateDiff, Self::Error> {
+        let storage_diffs = self
+            .storage_diffs
+            .iter()
+            .map(|(address, entries)| {
+                let contract_address = parse_field_element(address)?.into();
+                let storage_entries = entries
+                    .iter()
+                    .map(|entry| v1alpha2::StorageEntry {
+                        key: Some(entry.key.into()),
+                        value: Some(entry.value.into()),
+                    })
+                    .collect();
+                Ok(v1alpha2::StorageDiff {
+                    contract_address: Some(contract_address),
+                    storage_entries,
+                })
+            })
+            .collect::<Result<_, HttpProviderError>>()?;
+        let declared_contracts = self
+            .old_declared_contracts
+            .iter()
+            .chain(self.declared_classes.iter().map(|class| &class.class_hash))
+            .map(|class_hash| class_hash.to_proto())
+            .collect();
+        let deployed_contracts = self
+            .deployed_contracts
+            .iter()
+            .map(|contract| v1alpha2::DeployedContract {
+                contract_address: Some(contract.address.into()),
+                class_hash: Some(contract.class_hash.into()),
+            })
+            .collect();
+        let nonces = self
+            .nonces
+            .iter()
+            .map(|(address, nonce)| {
+                let contract_address = parse_field_element(address)?.into();
+                Ok(v1alpha2::NonceUpdate {
+                    contract_address: Some(contract_address),
+                    nonce: Some(nonce.into()),
+                })
+            })
+            .collect::<Result<_, HttpProviderError>>()?;
+
+        Ok(v1alpha2::StateDiff {
+            storage_diffs,
+            declared_contracts,
+            deployed_contracts,
+            nonces,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::starknet::v1alpha2;
+
+    use super::{Block, TryToProto};
+
+    #[test]
+    fn test_parse_block() {
+        let block: Block = serde_json::from_value(serde_json::json!({
+            "block_hash": "0x1",
+            "parent_block_hash": "0x2",
+            "block_number": 3,
+            "state_root": "0x4",
+            "status": "ACCEPTED_ON_L1",
+            "timestamp": 5,
+            "sequencer_address": "0x6",
+            "transactions": [
+                {
+                    "type": "INVOKE_FUNCTION",
+                    "transaction_hash": "0x7",
+                    "version": "0x1",
+                    "max_fee": "0x8",
+                    "signature": ["0x9"],
+                    "nonce": "0xa",
+                    "sender_address": "0xb",
+                    "calldata": ["0xc", "0xd"]
+                },
+                {
+                    "type": "L1_HANDLER",
+                    "transaction_hash": "0xe",
+                    "version": "0x0",
+                    "contract_address": "0xf",
+                    "entry_point_selector": "0x10",
+                    "calldata": []
+                }
+            ],
+            "transaction_receipts": []
+        }))
+        .unwrap();
+
+        let body = block.try_to_proto().unwrap();
+        assert_eq!(body.transactions.len(), 2);
+        let meta = body.transactions[0].meta.as_ref().unwrap();
+        assert_eq!(meta.version, 1);
+        assert!(matches!(
+            body.transactions[0].transaction,
+            Some(v1alpha2::transaction::Transaction::InvokeV1(_))
+        ));
+        assert!(matches!(
+            body.transactions[1].transaction,
+            Some(v1alpha2::transaction::Transaction::L1Handler(_))
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+//! Decide block finality from the Starknet core contract on Ethereum.
+//!
+//! A block is finalized once the state update of the block, or of a later
+//! block, is accepted by the core contract. The contract state is read at
+//! the `finalized` Ethereum block, so finalized Starknet blocks are never
+//! reverted by an Ethereum reorganization.
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use apibara_core::starknet::v1alpha2;
+use serde::Deserialize;
+use serde_json::json;
+use sha3::{Digest, Keccak256};
+use url::Url;
+
+/// Signature of the core contract function returning the last block whose
+/// state update was accepted.
+const STATE_BLOCK_NUMBER: &str = "stateBlockNumber()";
+
+/// Ethereum produces a block every 12 seconds, the contract state doesn't
+/// change more often.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(12);
+
+#[derive(Debug, thiserror::Error)]
+pub enum L1FinalityError {
+    #[error("ethereum rpc request failed")]
+    Request(#[from] reqwest::Error),
+    #[error("ethereum rpc returned an error: {0}")]
+    Rpc(String),
+    #[error("failed to parse ethereum rpc response")]
+    InvalidResponse,
+}
+
+/// Reads the last block settled on L1 from the Starknet core contract.
+#[derive(Debug)]
+pub struct L1Finality {
+    client: reqwest::Client,
+    url: Url,
+    core_contract: String,
+    refresh_interval: Duration,
+    // last state block number and when it was read.
+    state: Mutex<Option<(Option<u64>, Instant)>>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<String>,
+    error: Option<serde_json::Value>,
+}
+
+impl L1Finality {
+    /// Creates a reader for the core contract at `core_contract`, on the
+    /// Ethereum RPC at `url`.
+    pub fn new(url: Url, core_contract: String) -> Self {
+        L1Finality {
+            client: reqwest::Client::new(),
+            url,
+            core_contract,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Returns the status of the block with the given number, replacing the
+    /// status reported by the L2 provider.
+    ///
+    /// Pending and rejected blocks keep their status.
+    pub async fn block_status(
+        &self,
+        status: v1alpha2::BlockStatus,
+        block_number: u64,
+    ) -> Result<v1alpha2::BlockStatus, L1FinalityError> {
+        if !status.is_accepted() && !status.is_finalized() {
+            return Ok(status);
+        }
+        match self.state_block_number(block_number).await? {
+            Some(settled) if block_number <= settled => Ok(v1alpha2::BlockStatus::AcceptedOnL1),
+            _ => Ok(v1alpha2::BlockStatus::AcceptedOnL2),
+        }
+    }
+
+    /// Returns the last block settled on L1.
+    ///
+    /// The cached value is used if it already covers `block_number`, or if
+    /// it was read recently.
+    async fn state_block_number(&self, block_number: u64) -> Result<Option<u64>, L1FinalityError> {
+        if let Some((settled, read_at)) = *self.state.lock().expect("l1 finality lock") {
+            let covered = matches!(settled, Some(settled) if block_number <= settled);
+            if covered || read_at.elapsed() < self.refresh_interval {
+                return Ok(settled);
+            }
+        }
+
+        let settled = self.fetch_state_block_number().await?;
+        *self.state.lock().expect("l1 finality lock") = Some((settled, Instant::now()));
+        Ok(settled)
+    }
+
+    async fn fetch_state_block_number(&self) -> Result<Option<u64>, L1FinalityError> {
+        let selector = &Keccak256::digest(STATE_BLOCK_NUMBER)[..4];
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_call",
+            "params": [
+                {
+                    "to": self.core_contract,
+                    "data": format!("0x{}", hex::encode(selector)),
+                },
+                "finalized",
+            ],
+        });
+
+        let response: RpcResponse = self
+            .client
+            .post(self.url.clone())
+            .json(&request)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if let Some(error) = response.error {
+            return Err(L1FinalityError::Rpc(error.to_string()));
+        }
+
+        let result = response.result.ok_or(L1FinalityError::InvalidResponse)?;
+        parse_state_block_number(&result)
+    }
+}
+
+/// Parses the `int256` returned by `stateBlockNumber()`.
+///
+/// The contract returns -1 before the first state update.
+fn parse_state_block_number(result: &str) -> Result<Option<u64>, L1FinalityError> {
+    let data = result
+        .strip_prefix("0x")
+        .and_then(|data| hex::decode(data).ok())
+        .filter(|data| data.len() == 32)
+        .ok_or(L1FinalityError::InvalidResponse)?;
+    if data[0] & 0x80 != 0 {
+        return Ok(None);
+    }
+    if data[..24].iter().any(|b| *b != 0) {
+        return Err(L1FinalityError::InvalidResponse);
+    }
+    let mut number = [0u8; 8];
+    number.copy_from_slice(&data[24..]);
+    Ok(Some(u64::from_be_bytes(number)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_state_block_number;
+
+    #[test]
+    fn test_parse_state_block_number() {
+        let settled = format!("0x{:064x}", 600_000);
+        assert_eq!(parse_state_block_number(&settled).unwrap(), Some(600_000));
+
+        let initial = format!("0x{}", "ff".repeat(32));
+        assert_eq!(parse_state_block_number(&initial).unwrap(), None);
+
+        assert!(parse_state_block_number("0x1234").is_err());
+    }
+}
@@ -0,0 +1,50 @@
+//! Metrics of the requests sent to the provider.
+use std::time::Duration;
+
+use apibara_node::o11y::{self, Counter, Histogram, KeyValue};
+
+/// Request count, errors and latency of each RPC method.
+///
+/// Requests served by the feeder gateway are reported under the equivalent
+/// RPC method.
+pub struct RpcMetrics {
+    requests: Counter<u64>,
+    errors: Counter<u64>,
+    latency: Histogram<f64>,
+}
+
+impl Default for RpcMetrics {
+    fn default() -> Self {
+        let meter = o11y::meter("provider");
+        let requests = meter
+            .u64_counter("rpc_requests")
+            .with_description("Number of requests sent to the provider")
+            .init();
+        let errors = meter
+            .u64_counter("rpc_errors")
+            .with_description("Number of failed requests sent to the provider")
+            .init();
+        let latency = meter
+            .f64_histogram("rpc_latency")
+            .with_description("Latency of requests sent to the provider, in seconds")
+            .init();
+        RpcMetrics {
+            requests,
+            errors,
+            latency,
+        }
+    }
+}
+
+impl RpcMetrics {
+    /// Records a completed request to `method`.
+    pub fn record(&self, method: &'static str, latency: Duration, failed: bool) {
+        let cx = o11y::Context::current();
+        let attributes = &[KeyValue::new("method", method)];
+        self.requests.add(&cx, 1, attributes);
+        if failed {
+            self.errors.add(&cx, 1, attributes);
+        }
+        self.latency.record(&cx, latency.as_secs_f64(), attributes);
+    }
+}
@@ -0,0 +1,148 @@
+//! Detect the JSON-RPC spec version of the provider.
+//!
+//! Providers upgrade their spec version independently of the node, so the
+//! version is detected once and the params of the trace request are adapted
+//! to it. The trace request is the only one built by the node: the other
+//! requests and their responses follow the spec of the jsonrpc client and
+//! are not adapted.
+use std::fmt;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// JSON-RPC method returning the spec version of the provider.
+const SPEC_VERSION_METHOD: &str = "starknet_specVersion";
+
+/// JSON-RPC error code returned when the method doesn't exist.
+const METHOD_NOT_FOUND_CODE: i64 = -32601;
+
+/// JSON-RPC spec version supported by the provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RpcSpecVersion {
+    /// Versions before 0.4, which don't report their version.
+    Legacy,
+    V0_4,
+    V0_5,
+    V0_6,
+    V0_7,
+    V0_8,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct SpecVersionResponse {
+    result: Option<String>,
+    error: Option<SpecVersionError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpecVersionError {
+    code: i64,
+    message: String,
+}
+
+impl RpcSpecVersion {
+    /// Parses a `major.minor.patch` version.
+    ///
+    /// Versions newer than the latest known version are treated as the
+    /// latest known version.
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim_start_matches('v').split('.');
+        let major: u64 = parts.next()?.parse().ok()?;
+        let minor: u64 = parts.next()?.parse().ok()?;
+        let version = match (major, minor) {
+            (0, 0..=3) => RpcSpecVersion::Legacy,
+            (0, 4) => RpcSpecVersion::V0_4,
+            (0, 5) => RpcSpecVersion::V0_5,
+            (0, 6) => RpcSpecVersion::V0_6,
+            (0, 7) => RpcSpecVersion::V0_7,
+            _ => RpcSpecVersion::V0_8,
+        };
+        Some(version)
+    }
+
+    /// Returns the JSON-RPC request for the spec version.
+    pub(super) fn request() -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": SPEC_VERSION_METHOD,
+            "params": [],
+        })
+    }
+
+    /// Returns the version in the response to [RpcSpecVersion::request].
+    pub(super) fn from_response(response: SpecVersionResponse) -> Result<Self, String> {
+        if let Some(error) = response.error {
+            // the method was introduced in 0.4.
+            if error.code == METHOD_NOT_FOUND_CODE {
+                return Ok(RpcSpecVersion::Legacy);
+            }
+            return Err(error.message);
+        }
+        let version = response.result.unwrap_or_default();
+        RpcSpecVersion::parse(&version).ok_or_else(|| format!("invalid spec version {}", version))
+    }
+
+    /// Returns the params of the request for the traces of the block with
+    /// the given hash.
+    ///
+    /// Since 0.6 the block is identified by a block id.
+    pub(super) fn trace_block_params(&self, block_hash: &str) -> Value {
+        if *self >= RpcSpecVersion::V0_6 {
+            json!({ "block_id": { "block_hash": block_hash } })
+        } else {
+            json!({ "block_hash": block_hash })
+        }
+    }
+}
+
+impl fmt::Display for RpcSpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = match self {
+            RpcSpecVersion::Legacy => "legacy",
+            RpcSpecVersion::V0_4 => "0.4",
+            RpcSpecVersion::V0_5 => "0.5",
+            RpcSpecVersion::V0_6 => "0.6",
+            RpcSpecVersion::V0_7 => "0.7",
+            RpcSpecVersion::V0_8 => "0.8",
+        };
+        f.write_str(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{RpcSpecVersion, SpecVersionResponse};
+
+    #[test]
+    fn test_parse_spec_version() {
+        assert_eq!(RpcSpecVersion::parse("0.6.0"), Some(RpcSpecVersion::V0_6));
+        assert_eq!(RpcSpecVersion::parse("0.7.1"), Some(RpcSpecVersion::V0_7));
+        assert_eq!(RpcSpecVersion::parse("0.9.0"), Some(RpcSpecVersion::V0_8));
+        assert_eq!(RpcSpecVersion::parse("0.3.0"), Some(RpcSpecVersion::Legacy));
+        assert_eq!(RpcSpecVersion::parse("latest"), None);
+
+        let response: SpecVersionResponse = serde_json::from_value(json!({
+            "error": { "code": -32601, "message": "Method not found" }
+        }))
+        .unwrap();
+        assert_eq!(
+            RpcSpecVersion::from_response(response),
+            Ok(RpcSpecVersion::Legacy)
+        );
+    }
+
+    #[test]
+    fn test_trace_block_params() {
+        assert_eq!(
+            RpcSpecVersion::V0_5.trace_block_params("0x1"),
+            json!({ "block_hash": "0x1" })
+        );
+        assert_eq!(
+            RpcSpecVersion::V0_7.trace_block_params("0x1"),
+            json!({ "block_id": { "block_hash": "0x1" } })
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Implements the admin service, used by operators to control streams.
+
+use std::collections::HashSet;
+
+use apibara_core::node::v1alpha2::{
+    admin_server, ListStreamsRequest, ListStreamsResponse, RepositionStreamRequest,
+    RepositionStreamResponse, TerminateStreamRequest, TerminateStreamResponse,
+};
+use tonic::{metadata::MetadataMap, Request, Response, Status};
+use tracing::info;
+
+use super::{
+    auth::CALLER_METADATA_KEY,
+    registry::{StreamControlError, StreamRegistry},
+};
+
+/// Error returned to callers that are not operators.
+#[derive(Debug, thiserror::Error)]
+enum OperatorError {
+    #[error("missing caller identity")]
+    MissingIdentity,
+    #[error("caller is not an operator")]
+    NotOperator,
+}
+
+pub struct AdminService {
+    registry: StreamRegistry,
+    operators: HashSet<String>,
+}
+
+impl AdminService {
+    /// Creates a new admin service, only accessible to the callers with the
+    /// given identities.
+    pub fn new(registry: StreamRegistry, operators: HashSet<String>) -> Self {
+        AdminService {
+            registry,
+            operators,
+        }
+    }
+
+    pub fn into_service(self) -> admin_server::AdminServer<Self> {
+        admin_server::AdminServer::new(self)
+    }
+
+    /// Returns the identity of the caller, failing if it's not an operator.
+    fn authorize<'a>(&self, metadata: &'a MetadataMap) -> Result<&'a str, OperatorError> {
+        let identity = metadata
+            .get(CALLER_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(OperatorError::MissingIdentity)?;
+        if !self.operators.contains(identity) {
+            return Err(OperatorError::NotOperator);
+        }
+        Ok(identity)
+    }
+}
+
+#[tonic::async_trait]
+impl admin_server::Admin for AdminService {
+    async fn list_streams(
+        &self,
+        request: Request<ListStreamsRequest>,
+    ) -> Result<Response<ListStreamsResponse>, Status> {
+        self.authorize(request.metadata()).map_err(operator_error)?;
+        let streams = self.registry.list();
+        Ok(Response::new(ListStreamsResponse { streams }))
+    }
+
+    async fn terminate_stream(
+        &self,
+        request: Request<TerminateStreamRequest>,
+    ) -> Result<Response<TerminateStreamResponse>, Status> {
+        let operator = self.authorize(request.metadata()).map_err(operator_error)?;
+        let id = request.get_ref().id;
+        info!(operator = %operator, stream_id = %id, "terminate stream");
+        self.registry.terminate(id).map_err(control_error)?;
+        Ok(Response::new(TerminateStreamResponse {}))
+    }
+
+    async fn reposition_stream(
+        &self,
+        request: Request<RepositionStreamRequest>,
+    ) -> Result<Response<RepositionStreamResponse>, Status> {
+        let operator = self.authorize(request.metadata()).map_err(operator_error)?;
+        let id = request.get_ref().id;
+        let cursor = request
+            .get_ref()
+            .cursor
+            .clone()
+            .ok_or_else(|| Status::invalid_argument("missing cursor"))?;
+        info!(operator = %operator, stream_id = %id, cursor = %cursor.order_key, "reposition stream");
+        self.registry
+            .reposition(id, cursor)
+            .map_err(control_error)?;
+        Ok(Response::new(RepositionStreamResponse {}))
+    }
+}
+
+fn operator_error(err: OperatorError) -> Status {
+    match err {
+        OperatorError::MissingIdentity => Status::unauthenticated(err.to_string()),
+        OperatorError::NotOperator => Status::permission_denied(err.to_string()),
+    }
+}
+
+fn control_error(err: StreamControlError) -> Status {
+    match err {
+        StreamControlError::NotFound(_) => Status::not_found(err.to_string()),
+        StreamControlError::NotConfigured(_) => Status::failed_precondition(err.to_string()),
+    }
+}
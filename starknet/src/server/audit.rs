@@ -0,0 +1,440 @@
+//! Audit the lifecycle of streams.
+//!
+//! The registry records an event when a stream is opened, each time it's
+//! configured, and when it's closed. Events are meant to investigate abuse
+//! and to bill callers after the fact.
+//!
+//! Events are written to the node logs, to a file that can be rotated by
+//! size, or to syslog, independently of the application logs.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    net::UdpSocket,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+/// Receives the audit events of all streams.
+pub trait AuditSink: std::fmt::Debug + Send + Sync + 'static {
+    fn record(&self, event: &AuditEvent);
+
+    /// Writes the buffered events, if any. Called when the node shuts down.
+    fn flush(&self) {}
+}
+
+/// An event in the lifecycle of a stream.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// The client opened the stream.
+    Opened {
+        stream_id: u64,
+        caller: Option<String>,
+        timestamp: u64,
+        /// Address of the client, as reported by the proxy if any.
+        remote_addr: Option<String>,
+    },
+    /// The client, or an operator, configured the stream.
+    Configured {
+        stream_id: u64,
+        caller: Option<String>,
+        timestamp: u64,
+        /// Hex encoded sha256 hash of the encoded filter.
+        filter_hash: String,
+        starting_cursor: Option<u64>,
+        finality: Option<i32>,
+        batch_size: Option<u64>,
+    },
+    /// The stream was closed.
+    Closed {
+        stream_id: u64,
+        caller: Option<String>,
+        timestamp: u64,
+        reason: CloseReason,
+        /// Cursor of the last data sent to the client.
+        cursor: Option<u64>,
+        messages: u64,
+        bytes: u64,
+        data_units: u64,
+        duration_secs: f64,
+    },
+}
+
+/// Why a stream was closed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CloseReason {
+    /// The client closed the stream.
+    Disconnected,
+    /// All the requested data was sent.
+    Completed,
+    /// The stream failed, for example because the quota was exceeded.
+    ///
+    /// The code is the error code sent to the client, like
+    /// `quota_exceeded`, or `unknown`.
+    Error { code: String, message: String },
+    /// An operator terminated the stream.
+    Terminated,
+    /// The client was idle for too long.
+    Idle,
+    /// The stream sent the maximum number of data units of a session.
+    SessionLimit,
+    /// The node shut down.
+    Shutdown,
+}
+
+impl CloseReason {
+    /// Returns the reason as a metric label. Errors are labeled by their
+    /// code.
+    pub fn label(&self) -> &str {
+        match self {
+            CloseReason::Disconnected => "disconnected",
+            CloseReason::Completed => "completed",
+            CloseReason::Error { code, .. } => code,
+            CloseReason::Terminated => "terminated",
+            CloseReason::Idle => "idle",
+            CloseReason::SessionLimit => "session_limit",
+            CloseReason::Shutdown => "shutdown",
+        }
+    }
+}
+
+/// Writes audit events to the node logs.
+#[derive(Debug, Default)]
+pub struct LogAuditSink {}
+
+/// Appends audit events to a file, one JSON object per line.
+///
+/// Events are buffered, and written when the sink is flushed or dropped.
+#[derive(Debug)]
+pub struct FileAuditSink {
+    path: PathBuf,
+    rotation: Option<AuditRotation>,
+    file: Mutex<AuditFile>,
+}
+
+/// Rotate the audit file once it reaches `max_size` bytes, keeping the
+/// `max_files` most recent rotated files as `<path>.1`, `<path>.2`, ...
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRotation {
+    pub max_size: u64,
+    pub max_files: usize,
+}
+
+#[derive(Debug)]
+struct AuditFile {
+    writer: BufWriter<File>,
+    size: u64,
+}
+
+/// Sends audit events to syslog, one RFC 5424 message per event.
+#[derive(Debug)]
+pub struct SyslogAuditSink {
+    socket: SyslogSocket,
+    hostname: String,
+    facility: u8,
+}
+
+/// Where syslog messages are sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyslogTarget {
+    /// A local unix socket, like `/dev/log`.
+    Unix(PathBuf),
+    /// A remote syslog server, as `host:port`.
+    Udp(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyslogTargetError {
+    #[error("syslog target must be a path or udp://host:port, got {0}")]
+    InvalidTarget(String),
+}
+
+#[derive(Debug)]
+enum SyslogSocket {
+    Unix(UnixDatagram),
+    Udp(UdpSocket),
+}
+
+/// Syslog facility of audit events, `local0`.
+const DEFAULT_SYSLOG_FACILITY: u8 = 16;
+
+/// Syslog severity of audit events, `informational`.
+const SYSLOG_SEVERITY: u8 = 6;
+
+impl AuditSink for LogAuditSink {
+    fn record(&self, event: &AuditEvent) {
+        match serde_json::to_string(event) {
+            Ok(event) => info!(target: "apibara::audit", event = %event, "stream audit"),
+            Err(err) => warn!(error = ?err, "failed to serialize audit event"),
+        }
+    }
+}
+
+impl FileAuditSink {
+    /// Appends events to the file at `path`, creating it if needed.
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let file = AuditFile::open(path)?;
+        Ok(FileAuditSink {
+            path: path.to_path_buf(),
+            rotation: None,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Rotate the file by size.
+    pub fn with_rotation(mut self, rotation: AuditRotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Moves the current file to `<path>.1`, shifting the older files, and
+    /// starts a new file.
+    fn rotate(&self, file: &mut AuditFile, rotation: &AuditRotation) -> io::Result<()> {
+        file.writer.flush()?;
+        let max_files = rotation.max_files.max(1);
+        let _ = fs::remove_file(rotated_path(&self.path, max_files));
+        for index in (1..max_files).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        *file = AuditFile::open(&self.path)?;
+        Ok(())
+    }
+}
+
+impl AuditFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(AuditFile {
+            writer: BufWriter::new(file),
+            size,
+        })
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, event: &AuditEvent) {
+        let mut line = match serde_json::to_vec(event) {
+            Ok(line) => line,
+            Err(err) => {
+                warn!(error = ?err, "failed to serialize audit event");
+                return;
+            }
+        };
+        line.push(b'\n');
+        let mut file = self.file.lock().expect("audit file lock");
+        if let Err(err) = file.writer.write_all(&line) {
+            warn!(error = ?err, "failed to write audit event");
+            return;
+        }
+        file.size += line.len() as u64;
+        match self.rotation {
+            Some(ref rotation) if file.size >= rotation.max_size => {
+                if let Err(err) = self.rotate(&mut file, rotation) {
+                    warn!(error = ?err, "failed to rotate audit file");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn flush(&self) {
+        let mut file = self.file.lock().expect("audit file lock");
+        if let Err(err) = file.writer.flush() {
+            warn!(error = ?err, "failed to flush audit file");
+        }
+    }
+}
+
+impl Drop for FileAuditSink {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(format!(".{}", index));
+    PathBuf::from(path)
+}
+
+impl SyslogAuditSink {
+    /// Sends events to the given target.
+    pub fn new(target: &SyslogTarget) -> io::Result<Self> {
+        let socket = match target {
+            SyslogTarget::Unix(path) => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(path)?;
+                SyslogSocket::Unix(socket)
+            }
+            SyslogTarget::Udp(addr) => {
+                let socket = UdpSocket::bind("0.0.0.0:0")?;
+                socket.connect(addr.as_str())?;
+                SyslogSocket::Udp(socket)
+            }
+        };
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "-".to_string());
+        Ok(SyslogAuditSink {
+            socket,
+            hostname,
+            facility: DEFAULT_SYSLOG_FACILITY,
+        })
+    }
+
+    /// Send events with the given facility code, like 4 for `auth` or 16
+    /// for `local0`.
+    pub fn with_facility(mut self, facility: u8) -> Self {
+        self.facility = facility;
+        self
+    }
+
+    fn message(&self, event: &str) -> String {
+        format!(
+            "<{}>1 {} {} apibara {} audit - {}",
+            self.facility * 8 + SYSLOG_SEVERITY,
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            self.hostname,
+            std::process::id(),
+            event
+        )
+    }
+}
+
+impl AuditSink for SyslogAuditSink {
+    fn record(&self, event: &AuditEvent) {
+        let event = match serde_json::to_string(event) {
+            Ok(event) => event,
+            Err(err) => {
+                warn!(error = ?err, "failed to serialize audit event");
+                return;
+            }
+        };
+        let message = self.message(&event);
+        let result = match self.socket {
+            SyslogSocket::Unix(ref socket) => socket.send(message.as_bytes()),
+            SyslogSocket::Udp(ref socket) => socket.send(message.as_bytes()),
+        };
+        if let Err(err) = result {
+            warn!(error = ?err, "failed to send audit event to syslog");
+        }
+    }
+}
+
+impl FromStr for SyslogTarget {
+    type Err = SyslogTargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix("udp://") {
+            if addr.is_empty() {
+                return Err(SyslogTargetError::InvalidTarget(s.to_string()));
+            }
+            return Ok(SyslogTarget::Udp(addr.to_string()));
+        }
+        if s.contains("://") || s.is_empty() {
+            return Err(SyslogTargetError::InvalidTarget(s.to_string()));
+        }
+        Ok(SyslogTarget::Unix(PathBuf::from(s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use tempdir::TempDir;
+
+    use super::{AuditEvent, AuditRotation, AuditSink, CloseReason, FileAuditSink, SyslogTarget};
+
+    fn opened(stream_id: u64) -> AuditEvent {
+        AuditEvent::Opened {
+            stream_id,
+            caller: None,
+            timestamp: 1_688_000_000,
+            remote_addr: None,
+        }
+    }
+
+    #[test]
+    fn test_audit_event_json() {
+        let event = AuditEvent::Closed {
+            stream_id: 1,
+            caller: Some("alice".to_string()),
+            timestamp: 1_688_000_000,
+            reason: CloseReason::Error {
+                code: "quota_exceeded".to_string(),
+                message: "quota exceeded".to_string(),
+            },
+            cursor: Some(42),
+            messages: 3,
+            bytes: 100,
+            data_units: 7,
+            duration_secs: 1.5,
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "closed");
+        assert_eq!(json["reason"]["type"], "error");
+        assert_eq!(json["reason"]["code"], "quota_exceeded");
+        assert_eq!(json["reason"]["message"], "quota exceeded");
+        assert_eq!(json["data_units"], 7);
+    }
+
+    #[test]
+    fn test_file_rotation() {
+        let dir = TempDir::new("audit").unwrap();
+        let path = dir.path().join("audit.log");
+        let sink = FileAuditSink::new(&path)
+            .unwrap()
+            .with_rotation(AuditRotation {
+                max_size: 1,
+                max_files: 2,
+            });
+        for stream_id in 0..4 {
+            sink.record(&opened(stream_id));
+        }
+        sink.flush();
+
+        // each event fills a file, only the two most recent are kept.
+        let rotated = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+        assert!(rotated("audit.log.1").contains(r#""stream_id":3"#));
+        assert!(rotated("audit.log.2").contains(r#""stream_id":2"#));
+        assert!(!dir.path().join("audit.log.3").exists());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+    }
+
+    #[test]
+    fn test_file_flush_on_drop() {
+        let dir = TempDir::new("audit").unwrap();
+        let path = dir.path().join("audit.log");
+        let sink = FileAuditSink::new(&path).unwrap();
+        sink.record(&opened(1));
+        drop(sink);
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains(r#""event":"opened""#));
+    }
+
+    #[test]
+    fn test_syslog_target() {
+        assert_eq!(
+            "/dev/log".parse::<SyslogTarget>().unwrap(),
+            SyslogTarget::Unix(PathBuf::from("/dev/log"))
+        );
+        assert_eq!(
+            "udp://syslog:514".parse::<SyslogTarget>().unwrap(),
+            SyslogTarget::Udp("syslog:514".to_string())
+        );
+        assert!("tcp://syslog:514".parse::<SyslogTarget>().is_err());
+        assert!("udp://".parse::<SyslogTarget>().is_err());
+    }
+}
@@ -0,0 +1,571 @@
+//! Authenticate clients with bearer tokens.
+//!
+//! Clients send their token in the `authorization` metadata. Tokens are
+//! checked by a [TokenValidator] before the request reaches the stream
+//! service, and the identity of the caller is attached to the request, both
+//! as an extension and as the `x-apibara-caller` metadata. Use the metadata
+//! key with the request observer to meter data by caller.
+//!
+//! Clients can't set the `x-apibara-caller` metadata themselves, it's
+//! removed from all requests even when authentication is disabled.
+//!
+//! Health and reflection services don't require a token.
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use apibara_core::node::v1alpha2::{auth_client::AuthClient, ValidateTokenRequest};
+use futures::future::BoxFuture;
+use jsonwebtoken::{
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use tonic::{
+    body::BoxBody,
+    codegen::http::{self, HeaderMap, HeaderValue},
+    metadata::MetadataMap,
+    transport::{Channel, Endpoint},
+    Status,
+};
+use tower::{Layer, Service};
+use tracing::warn;
+use url::Url;
+
+/// Metadata key with the identity of the authenticated caller.
+pub const CALLER_METADATA_KEY: &str = "x-apibara-caller";
+
+/// Only requests to these services need a token.
+const PROTECTED_PATH_PREFIX: &str = "/apibara.";
+
+/// How long the JWKS is cached.
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Minimum interval between JWKS requests, when a token is signed by an
+/// unknown key.
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Timeout of JWKS requests.
+const JWKS_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("failed to read keys file")]
+    KeysFile(#[from] std::io::Error),
+    #[error("invalid line {0} in keys file")]
+    InvalidKeysFile(usize),
+    #[error("failed to fetch jwks")]
+    Jwks(#[from] reqwest::Error),
+    #[error("invalid jwk")]
+    InvalidJwk(#[source] jsonwebtoken::errors::Error),
+    #[error("invalid validator url")]
+    InvalidValidatorUrl(#[from] tonic::transport::Error),
+    #[error("token validator failed: {0}")]
+    Validator(Box<Status>),
+}
+
+/// Identity of an authenticated caller.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallerIdentity(String);
+
+/// Checks the tokens sent by clients.
+#[apibara_node::async_trait]
+pub trait TokenValidator: Send + Sync + 'static {
+    /// Returns the identity of the caller, or `None` if the token is not
+    /// valid.
+    async fn validate(&self, token: &str) -> Result<Option<CallerIdentity>, AuthError>;
+}
+
+/// Validates tokens against a fixed list of keys.
+#[derive(Debug, Default)]
+pub struct StaticKeyValidator {
+    keys: HashMap<String, CallerIdentity>,
+}
+
+/// Validates JWTs signed by one of the keys of a JWKS.
+///
+/// The caller identity is the `sub` claim.
+pub struct JwksValidator {
+    client: reqwest::Client,
+    url: Url,
+    audience: Option<String>,
+    issuer: Option<String>,
+    // keys and when they were fetched.
+    keys: Mutex<Option<(JwkSet, Instant)>>,
+    // held while fetching the keys, so that only one request is sent.
+    fetching: Mutex<()>,
+}
+
+/// Validates tokens with an external service implementing the `Auth` gRPC
+/// service.
+pub struct GrpcValidator {
+    client: AuthClient<Channel>,
+}
+
+/// Layer that authenticates requests to the node services.
+#[derive(Clone)]
+pub struct AuthLayer {
+    validator: Option<Arc<dyn TokenValidator>>,
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    validator: Option<Arc<dyn TokenValidator>>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+impl CallerIdentity {
+    pub fn new(identity: impl Into<String>) -> Self {
+        CallerIdentity(identity.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl StaticKeyValidator {
+    /// Adds a key, used by the caller with the given identity.
+    pub fn with_key(mut self, key: impl Into<String>, identity: CallerIdentity) -> Self {
+        self.keys.insert(key.into(), identity);
+        self
+    }
+
+    /// Returns true if the key with the given hash is valid, see
+    /// [key_hash].
+    pub fn has_key_hash(&self, key_hash: &str) -> bool {
+        self.keys.keys().any(|key| hash_key(key) == key_hash)
+    }
+
+    /// Loads keys from a file.
+    ///
+    /// Each line contains the caller identity and its key, separated by
+    /// whitespace. Empty lines and lines starting with `#` are ignored.
+    pub fn from_file(path: &Path) -> Result<Self, AuthError> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Result<Self, AuthError> {
+        let mut validator = StaticKeyValidator::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(identity), Some(key), None) => {
+                    validator = validator.with_key(key, CallerIdentity::new(identity));
+                }
+                _ => return Err(AuthError::InvalidKeysFile(index + 1)),
+            }
+        }
+        Ok(validator)
+    }
+}
+
+#[apibara_node::async_trait]
+impl TokenValidator for StaticKeyValidator {
+    async fn validate(&self, token: &str) -> Result<Option<CallerIdentity>, AuthError> {
+        Ok(self.keys.get(token).cloned())
+    }
+}
+
+impl JwksValidator {
+    /// Creates a validator with the keys published at `url`.
+    pub fn new(url: Url) -> Self {
+        JwksValidator {
+            client: reqwest::Client::new(),
+            url,
+            audience: None,
+            issuer: None,
+            keys: Mutex::new(None),
+            fetching: Mutex::new(()),
+        }
+    }
+
+    /// Only accept tokens with the given `aud` claim.
+    pub fn with_audience(mut self, audience: Option<String>) -> Self {
+        self.audience = audience;
+        self
+    }
+
+    /// Only accept tokens with the given `iss` claim.
+    pub fn with_issuer(mut self, issuer: Option<String>) -> Self {
+        self.issuer = issuer;
+        self
+    }
+
+    /// Returns the key with the given id.
+    ///
+    /// Keys are fetched again when they're old, or if the key is unknown,
+    /// since the issuer may have rotated its keys. Keys are fetched without
+    /// holding the cached keys, so that a slow issuer doesn't block tokens
+    /// signed by known keys.
+    async fn find_key(&self, kid: &str) -> Result<Option<Jwk>, AuthError> {
+        if let Some(jwk) = self.cached_key(kid).await {
+            return Ok(jwk);
+        }
+
+        // another request may have fetched the keys while waiting.
+        let _fetching = self.fetching.lock().await;
+        if let Some(jwk) = self.cached_key(kid).await {
+            return Ok(jwk);
+        }
+
+        let set: JwkSet = self
+            .client
+            .get(self.url.clone())
+            .timeout(JWKS_REQUEST_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let jwk = set.find(kid).cloned();
+        *self.keys.lock().await = Some((set, Instant::now()));
+        Ok(jwk)
+    }
+
+    /// Returns the cached key with the given id, or `None` if the keys must
+    /// be fetched first.
+    async fn cached_key(&self, kid: &str) -> Option<Option<Jwk>> {
+        let keys = self.keys.lock().await;
+        let (set, fetched_at) = keys.as_ref()?;
+        let jwk = set.find(kid);
+        let max_age = if jwk.is_some() {
+            JWKS_REFRESH_INTERVAL
+        } else {
+            JWKS_MIN_REFRESH_INTERVAL
+        };
+        if fetched_at.elapsed() >= max_age {
+            return None;
+        }
+        Some(jwk.cloned())
+    }
+}
+
+/// Returns the algorithm used to sign tokens with the given key.
+///
+/// The algorithm comes from the key and never from the token, so that tokens
+/// can't pick a weaker algorithm. Symmetric keys are never accepted, since
+/// the JWKS is public.
+fn key_algorithm(jwk: &Jwk) -> Option<Algorithm> {
+    let algorithm = match (&jwk.algorithm, jwk.common.algorithm) {
+        (AlgorithmParameters::OctetKey(_), _) => return None,
+        (_, Some(algorithm)) => algorithm,
+        (AlgorithmParameters::RSA(_), None) => Algorithm::RS256,
+        (AlgorithmParameters::EllipticCurve(params), None) => match params.curve {
+            EllipticCurve::P256 => Algorithm::ES256,
+            EllipticCurve::P384 => Algorithm::ES384,
+            _ => return None,
+        },
+        (AlgorithmParameters::OctetKeyPair(_), None) => Algorithm::EdDSA,
+    };
+    match algorithm {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => None,
+        algorithm => Some(algorithm),
+    }
+}
+
+#[apibara_node::async_trait]
+impl TokenValidator for JwksValidator {
+    async fn validate(&self, token: &str) -> Result<Option<CallerIdentity>, AuthError> {
+        let header = match jsonwebtoken::decode_header(token) {
+            Ok(header) => header,
+            Err(_) => return Ok(None),
+        };
+        let kid = match header.kid {
+            None => return Ok(None),
+            Some(kid) => kid,
+        };
+        let jwk = match self.find_key(&kid).await? {
+            None => return Ok(None),
+            Some(jwk) => jwk,
+        };
+        let algorithm = match key_algorithm(&jwk) {
+            None => return Ok(None),
+            Some(algorithm) => algorithm,
+        };
+        let key = DecodingKey::from_jwk(&jwk).map_err(AuthError::InvalidJwk)?;
+
+        let mut validation = Validation::new(algorithm);
+        if let Some(audience) = &self.audience {
+            validation.set_audience(&[audience]);
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match jsonwebtoken::decode::<Claims>(token, &key, &validation) {
+            Ok(data) => Ok(Some(CallerIdentity::new(data.claims.sub))),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+impl GrpcValidator {
+    /// Creates a validator that calls the service at `url`.
+    ///
+    /// The connection is established on the first request.
+    pub fn new(url: String) -> Result<Self, AuthError> {
+        let channel = Endpoint::from_shared(url)?.connect_lazy();
+        Ok(GrpcValidator {
+            client: AuthClient::new(channel),
+        })
+    }
+}
+
+#[apibara_node::async_trait]
+impl TokenValidator for GrpcValidator {
+    async fn validate(&self, token: &str) -> Result<Option<CallerIdentity>, AuthError> {
+        let request = ValidateTokenRequest {
+            token: token.to_string(),
+        };
+        let response = self
+            .client
+            .clone()
+            .validate_token(request)
+            .await
+            .map_err(|status| AuthError::Validator(Box::new(status)))?
+            .into_inner();
+        if response.valid {
+            Ok(Some(CallerIdentity::new(response.identity)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl AuthLayer {
+    pub fn new(validator: Arc<dyn TokenValidator>) -> Self {
+        AuthLayer {
+            validator: Some(validator),
+        }
+    }
+
+    /// Returns a layer that doesn't authenticate requests, but still removes
+    /// the caller identity sent by clients.
+    pub fn disabled() -> Self {
+        AuthLayer { validator: None }
+    }
+
+    /// Returns true if requests are authenticated.
+    pub fn is_enabled(&self) -> bool {
+        self.validator.is_some()
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for AuthService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // the service that was polled ready handles the request.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let validator = self.validator.clone();
+
+        Box::pin(async move {
+            // the identity is only set by the node.
+            request.headers_mut().remove(CALLER_METADATA_KEY);
+
+            let validator = match validator {
+                Some(validator) if request.uri().path().starts_with(PROTECTED_PATH_PREFIX) => {
+                    validator
+                }
+                _ => return inner.call(request).await,
+            };
+
+            match authenticate(&*validator, request.headers()).await {
+                Err(status) => Ok(status.to_http()),
+                Ok(identity) => {
+                    if let Ok(value) = HeaderValue::from_str(identity.as_str()) {
+                        request.headers_mut().insert(CALLER_METADATA_KEY, value);
+                    }
+                    request.extensions_mut().insert(identity);
+                    inner.call(request).await
+                }
+            }
+        })
+    }
+}
+
+/// Returns the identity of the caller sending the given headers.
+async fn authenticate(
+    validator: &dyn TokenValidator,
+    headers: &HeaderMap,
+) -> Result<CallerIdentity, Status> {
+    let token = headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| Status::unauthenticated("missing bearer token"))?;
+    validate_token(validator, token.trim()).await
+}
+
+/// Returns a short hash of the bearer token in `metadata`, used to tell the
+/// streams of different keys apart in logs without logging the keys.
+pub fn key_hash(metadata: &MetadataMap) -> Option<String> {
+    let token = metadata
+        .get(http::header::AUTHORIZATION.as_str())
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))?;
+    Some(hash_key(token.trim()))
+}
+
+pub(crate) fn hash_key(key: &str) -> String {
+    let hash = Sha256::digest(key.as_bytes());
+    hex::encode(&hash[..8])
+}
+
+/// Returns the identity of the caller with the given token.
+pub(crate) async fn validate_token(
+    validator: &dyn TokenValidator,
+    token: &str,
+) -> Result<CallerIdentity, Status> {
+    match validator.validate(token).await {
+        Ok(Some(identity)) => Ok(identity),
+        Ok(None) => Err(Status::unauthenticated("invalid token")),
+        Err(err) => {
+            warn!(error = ?err, "failed to validate token");
+            Err(Status::unavailable("failed to validate token"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use jsonwebtoken::{jwk::Jwk, Algorithm};
+    use tonic::{
+        body::empty_body,
+        codegen::http::{self, header::AUTHORIZATION, HeaderMap, HeaderValue},
+        metadata::MetadataMap,
+    };
+    use tower::{service_fn, Layer, ServiceExt};
+
+    use super::{
+        authenticate, key_algorithm, key_hash, AuthLayer, CallerIdentity, StaticKeyValidator,
+        TokenValidator, CALLER_METADATA_KEY,
+    };
+
+    #[tokio::test]
+    async fn test_static_keys() {
+        let validator = StaticKeyValidator::parse(
+            "# keys\n\
+             alice dna_aaaa\n\
+             \n\
+             bob   dna_bbbb\n",
+        )
+        .unwrap();
+        assert_eq!(
+            validator.validate("dna_bbbb").await.unwrap(),
+            Some(CallerIdentity::new("bob"))
+        );
+        assert_eq!(validator.validate("dna_cccc").await.unwrap(), None);
+
+        assert!(StaticKeyValidator::parse("alice").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_authenticate() {
+        let validator =
+            StaticKeyValidator::default().with_key("dna_aaaa", CallerIdentity::new("alice"));
+
+        let mut headers = HeaderMap::new();
+        assert!(authenticate(&validator, &headers).await.is_err());
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer dna_bbbb"));
+        assert!(authenticate(&validator, &headers).await.is_err());
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer dna_aaaa"));
+        assert_eq!(
+            authenticate(&validator, &headers).await.unwrap(),
+            CallerIdentity::new("alice")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_caller_without_auth() {
+        let service = AuthLayer::disabled().layer(service_fn(|request: http::Request<()>| {
+            let has_caller = request.headers().contains_key(CALLER_METADATA_KEY);
+            async move {
+                assert!(!has_caller);
+                Ok::<_, Infallible>(http::Response::new(empty_body()))
+            }
+        }));
+        let request = http::Request::builder()
+            .uri("/apibara.node.v1alpha2.Stream/StreamData")
+            .header(CALLER_METADATA_KEY, "alice")
+            .body(())
+            .unwrap();
+        service.oneshot(request).await.unwrap();
+    }
+
+    #[test]
+    fn test_key_hash() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(key_hash(&MetadataMap::from_headers(headers.clone())), None);
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer dna_aaaa"));
+        let hash = key_hash(&MetadataMap::from_headers(headers.clone())).unwrap();
+        assert_eq!(hash.len(), 16);
+        assert!(!hash.contains("dna_aaaa"));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer dna_bbbb"));
+        assert_ne!(key_hash(&MetadataMap::from_headers(headers)).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_key_algorithm() {
+        let jwk = |json: &str| serde_json::from_str::<Jwk>(json).unwrap();
+
+        let rsa = jwk(r#"{"kty": "RSA", "kid": "a", "n": "AQAB", "e": "AQAB"}"#);
+        assert_eq!(key_algorithm(&rsa), Some(Algorithm::RS256));
+
+        let rsa = jwk(r#"{"kty": "RSA", "kid": "a", "alg": "PS384", "n": "AQAB", "e": "AQAB"}"#);
+        assert_eq!(key_algorithm(&rsa), Some(Algorithm::PS384));
+
+        let ec = jwk(r#"{"kty": "EC", "kid": "b", "crv": "P-384", "x": "AA", "y": "AA"}"#);
+        assert_eq!(key_algorithm(&ec), Some(Algorithm::ES384));
+
+        // symmetric keys would let anyone reading the jwks sign tokens.
+        let oct = jwk(r#"{"kty": "oct", "kid": "c", "alg": "HS256", "k": "AA"}"#);
+        assert_eq!(key_algorithm(&oct), None);
+    }
+}
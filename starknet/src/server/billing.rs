@@ -0,0 +1,333 @@
+//! Report the usage of callers to an external billing service.
+//!
+//! The stream registry accumulates the data units, bytes, and stream time
+//! of each authenticated caller. Every interval, the usage since the
+//! previous report is sent to the billing service. If the service is
+//! unavailable, usage is kept and added to the next report.
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use apibara_core::node::v1alpha2::{
+    usage_client::UsageClient, ReportUsageRequest, UsageAttribute, UsageCounter,
+};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tonic::{
+    transport::{Channel, Endpoint},
+    Status,
+};
+use tracing::{debug, warn};
+use url::Url;
+
+use super::registry::StreamRegistry;
+
+/// Default interval between usage reports.
+pub const DEFAULT_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum BillingError {
+    #[error("invalid billing service url")]
+    InvalidServiceUrl(#[from] tonic::transport::Error),
+    #[error("billing service error: {0}")]
+    Grpc(Box<Status>),
+    #[error("billing service http error")]
+    Http(#[from] reqwest::Error),
+}
+
+/// Usage of a caller.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CallerUsage {
+    pub data_units: u64,
+    pub bytes: u64,
+    /// Time spent streaming, summed over all streams of the caller.
+    pub stream_seconds: f64,
+}
+
+/// Usage of all callers over a period.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageReport {
+    /// Start of the period, in seconds since the unix epoch.
+    pub period_start: u64,
+    /// End of the period, in seconds since the unix epoch.
+    pub period_end: u64,
+    pub usage: HashMap<String, CallerUsage>,
+}
+
+/// Receives the usage reports.
+#[apibara_node::async_trait]
+pub trait BillingBackend: Send + Sync + 'static {
+    async fn report(&self, report: &UsageReport) -> Result<(), BillingError>;
+}
+
+/// Billing reports configuration.
+#[derive(Clone)]
+pub struct BillingConfig {
+    pub backend: Arc<dyn BillingBackend>,
+    pub interval: Duration,
+}
+
+/// Reports usage to an external service implementing the `Usage` gRPC
+/// service.
+pub struct GrpcBillingBackend {
+    client: UsageClient<Channel>,
+}
+
+/// Posts usage reports as JSON to an HTTP endpoint.
+pub struct HttpBillingBackend {
+    client: reqwest::Client,
+    url: Url,
+}
+
+/// Periodically reports the usage accumulated by the registry.
+pub struct BillingReporter {
+    registry: StreamRegistry,
+    config: BillingConfig,
+    pending: Option<UsageReport>,
+}
+
+impl CallerUsage {
+    pub fn is_empty(&self) -> bool {
+        self.data_units == 0 && self.bytes == 0 && self.stream_seconds == 0.0
+    }
+
+    pub fn merge(&mut self, other: &CallerUsage) {
+        self.data_units += other.data_units;
+        self.bytes += other.bytes;
+        self.stream_seconds += other.stream_seconds;
+    }
+}
+
+impl UsageReport {
+    /// Adds a more recent report to this one.
+    fn merge(&mut self, other: UsageReport) {
+        self.period_end = other.period_end;
+        for (caller, usage) in other.usage {
+            self.usage.entry(caller).or_default().merge(&usage);
+        }
+    }
+}
+
+impl BillingConfig {
+    pub fn new(backend: Arc<dyn BillingBackend>) -> Self {
+        BillingConfig {
+            backend,
+            interval: DEFAULT_REPORT_INTERVAL,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl GrpcBillingBackend {
+    /// Creates a backend that reports to the service at `url`.
+    ///
+    /// The connection is established on the first report.
+    pub fn new(url: String) -> Result<Self, BillingError> {
+        let channel = Endpoint::from_shared(url)?.connect_lazy();
+        Ok(GrpcBillingBackend {
+            client: UsageClient::new(channel),
+        })
+    }
+}
+
+#[apibara_node::async_trait]
+impl BillingBackend for GrpcBillingBackend {
+    async fn report(&self, report: &UsageReport) -> Result<(), BillingError> {
+        let mut counters = Vec::with_capacity(report.usage.len() * 3);
+        for (caller, usage) in &report.usage {
+            let attributes = vec![UsageAttribute {
+                key: "caller".to_string(),
+                value: caller.clone(),
+            }];
+            let amounts = [
+                ("data_units", usage.data_units),
+                ("bytes", usage.bytes),
+                ("stream_seconds", usage.stream_seconds.round() as u64),
+            ];
+            for (name, amount) in amounts {
+                counters.push(UsageCounter {
+                    name: name.to_string(),
+                    amount,
+                    attributes: attributes.clone(),
+                });
+            }
+        }
+        let request = ReportUsageRequest {
+            counters,
+            period_start: report.period_start,
+            period_end: report.period_end,
+        };
+        self.client
+            .clone()
+            .report_usage(request)
+            .await
+            .map_err(|status| BillingError::Grpc(Box::new(status)))?;
+        Ok(())
+    }
+}
+
+impl HttpBillingBackend {
+    pub fn new(url: Url) -> Self {
+        HttpBillingBackend {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+}
+
+#[apibara_node::async_trait]
+impl BillingBackend for HttpBillingBackend {
+    async fn report(&self, report: &UsageReport) -> Result<(), BillingError> {
+        self.client
+            .post(self.url.clone())
+            .json(report)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+impl BillingReporter {
+    pub fn new(registry: StreamRegistry, config: BillingConfig) -> Self {
+        BillingReporter {
+            registry,
+            config,
+            pending: None,
+        }
+    }
+
+    /// Reports usage every interval until cancelled, then reports the
+    /// remaining usage.
+    pub async fn run(mut self, ct: CancellationToken) {
+        let mut period_start = unix_timestamp(SystemTime::now());
+        loop {
+            let cancelled = tokio::select! {
+                _ = tokio::time::sleep(self.config.interval) => false,
+                _ = ct.cancelled() => true,
+            };
+            let period_end = unix_timestamp(SystemTime::now());
+            let usage = self.registry.take_usage(Instant::now());
+            self.add_usage(UsageReport {
+                period_start,
+                period_end,
+                usage,
+            });
+            period_start = period_end;
+            self.report().await;
+            if cancelled {
+                return;
+            }
+        }
+    }
+
+    /// Adds usage to the report waiting to be sent.
+    fn add_usage(&mut self, mut report: UsageReport) {
+        report.usage.retain(|_, usage| !usage.is_empty());
+        match &mut self.pending {
+            None => self.pending = Some(report),
+            Some(pending) => pending.merge(report),
+        }
+    }
+
+    /// Sends the pending report, keeping it if the service is unavailable.
+    async fn report(&mut self) {
+        let report = match self.pending.take() {
+            None => return,
+            Some(report) if report.usage.is_empty() => return,
+            Some(report) => report,
+        };
+        match self.config.backend.report(&report).await {
+            Ok(_) => debug!(callers = %report.usage.len(), "usage reported"),
+            Err(err) => {
+                warn!(error = ?err, "failed to report usage, retrying with the next report");
+                self.pending = Some(report);
+            }
+        }
+    }
+}
+
+fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use tonic::Status;
+
+    use crate::server::registry::StreamRegistry;
+
+    use super::{
+        BillingBackend, BillingConfig, BillingError, BillingReporter, CallerUsage, UsageReport,
+    };
+
+    #[derive(Default)]
+    struct MemoryBillingBackend {
+        available: AtomicBool,
+        reports: Mutex<Vec<UsageReport>>,
+    }
+
+    #[apibara_node::async_trait]
+    impl BillingBackend for MemoryBillingBackend {
+        async fn report(&self, report: &UsageReport) -> Result<(), BillingError> {
+            if !self.available.load(Ordering::SeqCst) {
+                return Err(BillingError::Grpc(Box::new(Status::unavailable("down"))));
+            }
+            self.reports.lock().unwrap().push(report.clone());
+            Ok(())
+        }
+    }
+
+    fn report(period_start: u64, period_end: u64, data_units: u64) -> UsageReport {
+        let usage = CallerUsage {
+            data_units,
+            bytes: 10 * data_units,
+            stream_seconds: 1.0,
+        };
+        UsageReport {
+            period_start,
+            period_end,
+            usage: HashMap::from([("alice".to_string(), usage)]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_buffer_usage_during_outage() {
+        let backend = Arc::new(MemoryBillingBackend::default());
+        let config = BillingConfig::new(backend.clone());
+        let mut reporter = BillingReporter::new(StreamRegistry::default(), config);
+
+        reporter.add_usage(report(0, 60, 3));
+        reporter.report().await;
+        reporter.add_usage(report(60, 120, 2));
+
+        backend.available.store(true, Ordering::SeqCst);
+        reporter.report().await;
+        reporter.report().await;
+
+        let reports = backend.reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].period_start, 0);
+        assert_eq!(reports[0].period_end, 120);
+        let usage = &reports[0].usage["alice"];
+        assert_eq!(usage.data_units, 5);
+        assert_eq!(usage.bytes, 50);
+        assert_eq!(usage.stream_seconds, 2.0);
+    }
+}
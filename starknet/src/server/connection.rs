@@ -0,0 +1,164 @@
+//! Tune the lifetime of client connections.
+//!
+//! Some load balancers drop connections that are idle for a while, without
+//! notifying either side. HTTP/2 keepalive pings keep the connection active
+//! and detect dead connections, so that clients reconnect.
+//!
+//! Connections older than the maximum age are closed, so that clients
+//! reconnect and are spread again between the nodes behind a load balancer.
+//! Clients see the streams of the connection fail with `UNAVAILABLE`.
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{self, Poll},
+    time::Duration,
+};
+
+use futures::{Stream, StreamExt};
+use pin_project::pin_project;
+use serde::Serialize;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{sleep, Sleep},
+};
+use tonic::transport::{server::Connected, Server as TonicServer};
+
+/// HTTP/2 keepalive and connection lifetime configuration.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConnectionConfig {
+    /// Send a keepalive ping to the client at this interval.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub keepalive_interval: Option<Duration>,
+    /// Close the connection if the client doesn't acknowledge a keepalive
+    /// ping within this timeout. Defaults to 20 seconds.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub keepalive_timeout: Option<Duration>,
+    /// Close connections older than this.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub max_connection_age: Option<Duration>,
+}
+
+/// A connection that ends once it reaches its maximum age.
+#[pin_project]
+pub struct AgedConnection<IO> {
+    #[pin]
+    inner: IO,
+    expired: Option<Pin<Box<Sleep>>>,
+}
+
+impl ConnectionConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.keepalive_interval.is_some()
+            || self.keepalive_timeout.is_some()
+            || self.max_connection_age.is_some()
+    }
+
+    /// Configures keepalive pings on the server.
+    pub fn configure(&self, server: TonicServer) -> TonicServer {
+        server
+            .http2_keepalive_interval(self.keepalive_interval)
+            .http2_keepalive_timeout(self.keepalive_timeout)
+    }
+}
+
+/// Closes the connections once they are older than `max_age`, if any.
+pub fn limit_connection_age<S, IO, E>(
+    incoming: S,
+    max_age: Option<Duration>,
+) -> impl Stream<Item = Result<AgedConnection<IO>, E>>
+where
+    S: Stream<Item = Result<IO, E>>,
+{
+    incoming.map(move |connection| {
+        connection.map(|inner| AgedConnection {
+            inner,
+            expired: max_age.map(|max_age| Box::pin(sleep(max_age))),
+        })
+    })
+}
+
+impl<IO: Connected> Connected for AgedConnection<IO> {
+    type ConnectInfo = IO::ConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.inner.connect_info()
+    }
+}
+
+impl<IO: AsyncRead> AsyncRead for AgedConnection<IO> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        if let Some(expired) = this.expired {
+            // reading nothing signals the end of the connection.
+            if expired.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Ok(()));
+            }
+        }
+        this.inner.poll_read(cx, buf)
+    }
+}
+
+impl<IO: AsyncWrite> AsyncWrite for AgedConnection<IO> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{stream, StreamExt};
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+    use super::limit_connection_age;
+
+    #[tokio::test]
+    async fn test_connection_max_age() {
+        let (mut client, server) = duplex(64);
+        let incoming = stream::iter(vec![Ok::<_, std::io::Error>(server)]);
+        let mut incoming = Box::pin(limit_connection_age(
+            incoming,
+            Some(Duration::from_millis(50)),
+        ));
+        let mut server = incoming.next().await.unwrap().unwrap();
+
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0; 4];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let read = server.read(&mut buf).await.unwrap();
+        assert_eq!(read, 0);
+    }
+}
@@ -0,0 +1,122 @@
+//! Extend the server without changing the node.
+//!
+//! Embedders register extensions to intercept the requests of clients, for
+//! example to authenticate them with a custom scheme or to add metadata,
+//! and to observe the lifecycle of streams, for example for custom
+//! metering. Extensions run in the order they are registered.
+use std::sync::Arc;
+
+use tonic::{
+    service::{interceptor::InterceptorLayer, Interceptor},
+    Request, Status,
+};
+
+use super::audit::AuditEvent;
+
+/// An extension of the server.
+///
+/// All methods have a default implementation that does nothing, so
+/// extensions only implement the hooks they need.
+pub trait ServerExtension: std::fmt::Debug + Send + Sync + 'static {
+    /// Called with the metadata of each request, after authentication.
+    ///
+    /// The identity of the caller, if any, is in the request extensions as
+    /// a [CallerIdentity](super::CallerIdentity). Return an error to reject
+    /// the request with that status.
+    fn intercept(&self, request: Request<()>) -> Result<Request<()>, Box<Status>> {
+        Ok(request)
+    }
+
+    /// Called when a stream is opened, configured, or closed.
+    fn on_stream_event(&self, _event: &AuditEvent) {}
+}
+
+/// Runs the interceptors of all extensions.
+#[derive(Debug, Clone)]
+pub struct ExtensionInterceptor {
+    extensions: Arc<[Arc<dyn ServerExtension>]>,
+}
+
+impl ExtensionInterceptor {
+    /// Returns a layer that intercepts requests with the given extensions,
+    /// or `None` if there are no extensions.
+    pub fn layer(
+        extensions: &[Arc<dyn ServerExtension>],
+    ) -> Option<InterceptorLayer<ExtensionInterceptor>> {
+        if extensions.is_empty() {
+            return None;
+        }
+        let interceptor = ExtensionInterceptor {
+            extensions: extensions.into(),
+        };
+        Some(tonic::service::interceptor(interceptor))
+    }
+}
+
+impl Interceptor for ExtensionInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        for extension in self.extensions.iter() {
+            request = extension.intercept(request).map_err(|status| *status)?;
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tonic::{service::Interceptor, Code, Request, Status};
+
+    use super::{ExtensionInterceptor, ServerExtension};
+
+    #[derive(Debug)]
+    struct TagExtension;
+
+    #[derive(Debug)]
+    struct RequireTagExtension;
+
+    impl ServerExtension for TagExtension {
+        fn intercept(&self, mut request: Request<()>) -> Result<Request<()>, Box<Status>> {
+            request
+                .metadata_mut()
+                .insert("x-tag", "tagged".parse().unwrap());
+            Ok(request)
+        }
+    }
+
+    impl ServerExtension for RequireTagExtension {
+        fn intercept(&self, request: Request<()>) -> Result<Request<()>, Box<Status>> {
+            if request.metadata().contains_key("x-tag") {
+                Ok(request)
+            } else {
+                Err(Box::new(Status::permission_denied("missing tag")))
+            }
+        }
+    }
+
+    #[test]
+    fn test_extensions_run_in_order() {
+        assert!(ExtensionInterceptor::layer(&[]).is_none());
+
+        let mut interceptor = ExtensionInterceptor {
+            extensions: vec![
+                Arc::new(TagExtension) as Arc<dyn ServerExtension>,
+                Arc::new(RequireTagExtension),
+            ]
+            .into(),
+        };
+        let request = interceptor.call(Request::new(())).unwrap();
+        assert_eq!(request.metadata().get("x-tag").unwrap(), "tagged");
+
+        let mut interceptor = ExtensionInterceptor {
+            extensions: vec![
+                Arc::new(RequireTagExtension) as Arc<dyn ServerExtension>,
+                Arc::new(TagExtension),
+            ]
+            .into(),
+        };
+        let err = interceptor.call(Request::new(())).unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+    }
+}
@@ -1,4 +1,11 @@
 //! Check health of the node.
+//!
+//! The node implements the standard `grpc.health.v1.Health` service. The
+//! node and its services are `SERVING` only while the database is readable
+//! and block ingestion is healthy, that is not failed or lagging behind the
+//! provider head. Set `--ingestion-max-lag-blocks` and
+//! `--ingestion-max-finalized-lag-blocks` so that load balancers steer
+//! clients away from stale nodes.
 
 use std::{sync::Arc, time::Duration};
 
@@ -7,26 +14,46 @@ use apibara_node::db::{
     MdbxTransactionExt,
 };
 use tokio_util::sync::CancellationToken;
-use tonic_health::pb::health_server::{Health, HealthServer};
-use tracing::warn;
+use tonic_health::{
+    pb::health_server::{Health, HealthServer},
+    ServingStatus,
+};
+use tracing::{info, warn};
+
+use crate::{
+    db::tables,
+    ingestion::{IngestionHealth, IngestionHealthStatus},
+};
 
-use crate::db::tables;
+/// Services whose status is reported, the empty name is the whole node.
+const SERVICE_NAMES: [&str; 3] = [
+    "",
+    "apibara.node.v1alpha2.Stream",
+    "apibara.starknet.v1alpha2.BlockSync",
+];
 
 pub struct HealthReporter<E: EnvironmentKind> {
     db: Arc<Environment<E>>,
-    _reporter: tonic_health::server::HealthReporter,
+    ingestion: Option<IngestionHealth>,
+    reporter: tonic_health::server::HealthReporter,
+    status: ServingStatus,
 }
 
 impl<E> HealthReporter<E>
 where
     E: EnvironmentKind,
 {
-    pub fn new(db: Arc<Environment<E>>) -> (Self, HealthServer<impl Health>) {
+    pub fn new(
+        db: Arc<Environment<E>>,
+        ingestion: Option<IngestionHealth>,
+    ) -> (Self, HealthServer<impl Health>) {
         let (reporter, service) = tonic_health::server::health_reporter();
         (
             HealthReporter {
                 db,
-                _reporter: reporter,
+                ingestion,
+                reporter,
+                status: ServingStatus::Unknown,
             },
             service,
         )
@@ -36,16 +63,28 @@ where
         let interval = Duration::from_secs(1);
         loop {
             if ct.is_cancelled() {
+                // stop routing traffic to the node while it shuts down.
+                self.set_status(ServingStatus::NotServing).await;
                 return;
             }
 
-            if self.check_db().is_ok() {
-                self.set_serving().await;
-            } else {
-                self.set_not_serving().await;
+            let db_error = self.check_db().err();
+            let ingestion = self.ingestion.as_ref().map(IngestionHealth::status);
+            let status = serving_status(db_error.is_none(), ingestion.as_ref());
+            if status != self.status {
+                match status {
+                    ServingStatus::Serving => info!("server is serving"),
+                    _ => {
+                        warn!(db_error = ?db_error, ingestion = ?ingestion, "server is not serving")
+                    }
+                }
             }
+            self.set_status(status).await;
 
-            tokio::time::sleep(interval).await;
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = ct.cancelled() => {}
+            }
         }
     }
 
@@ -58,20 +97,68 @@ where
         Ok(())
     }
 
-    async fn set_serving(&mut self) {
-        /*
-        self.reporter
-            .set_serving::<pb::node_server::NodeServer<NodeServer<E>>>()
-            .await
-        */
+    async fn set_status(&mut self, status: ServingStatus) {
+        if status == self.status {
+            return;
+        }
+        for service_name in SERVICE_NAMES {
+            self.reporter.set_service_status(service_name, status).await;
+        }
+        self.status = status;
+    }
+}
+
+/// Returns the status of the node given the database and ingestion health.
+fn serving_status(db_ok: bool, ingestion: Option<&IngestionHealthStatus>) -> ServingStatus {
+    if !db_ok {
+        return ServingStatus::NotServing;
     }
+    match ingestion {
+        Some(status) if !status.is_healthy() => ServingStatus::NotServing,
+        _ => ServingStatus::Serving,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic_health::ServingStatus;
+
+    use crate::ingestion::IngestionHealthStatus;
+
+    use super::serving_status;
 
-    async fn set_not_serving(&mut self) {
-        warn!("server is not serving");
-        /*
-        self.reporter
-            .set_not_serving::<pb::node_server::NodeServer<NodeServer<E>>>()
-            .await
-        */
+    #[test]
+    fn test_serving_status() {
+        let healthy = IngestionHealthStatus::Healthy;
+        let retrying = IngestionHealthStatus::Retrying {
+            attempt: 1,
+            error: "timeout".to_string(),
+        };
+        let lagging = IngestionHealthStatus::Lagging {
+            lag: 20,
+            max_lag: 10,
+        };
+        assert_eq!(serving_status(true, None), ServingStatus::Serving);
+        assert_eq!(serving_status(true, Some(&healthy)), ServingStatus::Serving);
+        assert_eq!(
+            serving_status(true, Some(&retrying)),
+            ServingStatus::Serving
+        );
+        assert_eq!(
+            serving_status(true, Some(&lagging)),
+            ServingStatus::NotServing
+        );
+        let finalized_lagging = IngestionHealthStatus::FinalizedLagging {
+            lag: 2000,
+            max_lag: 1000,
+        };
+        assert_eq!(
+            serving_status(true, Some(&finalized_lagging)),
+            ServingStatus::NotServing
+        );
+        assert_eq!(
+            serving_status(false, Some(&healthy)),
+            ServingStatus::NotServing
+        );
     }
 }
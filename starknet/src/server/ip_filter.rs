@@ -0,0 +1,315 @@
+//! Allow or deny clients by IP address.
+//!
+//! Connections from denied addresses are closed as soon as they are
+//! accepted. When the node runs behind trusted proxies, connections from a
+//! proxy are accepted and each request is checked against the client
+//! address in the `x-forwarded-for` header instead, since the proxy is
+//! shared by all clients.
+use std::{
+    net::{AddrParseError, IpAddr},
+    str::FromStr,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures::{future::BoxFuture, Stream, StreamExt};
+use ipnet::IpNet;
+use serde::Serialize;
+use tonic::{body::BoxBody, codegen::http, transport::server::Connected, Status};
+use tower::{Layer, Service};
+use tracing::debug;
+
+use super::rate_limit::{remote_ip, RemoteAddr};
+
+/// Header with the client addresses, added by proxies.
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Networks allowed and denied access to the node.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IpFilterConfig {
+    /// Only accept clients from these networks. Any client if empty.
+    pub allow: Vec<IpNet>,
+    /// Reject clients from these networks, even if allowed.
+    pub deny: Vec<IpNet>,
+    /// Proxies trusted to provide the client address.
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+/// Shared IP filter.
+#[derive(Debug, Clone)]
+pub struct IpFilter {
+    config: Arc<IpFilterConfig>,
+}
+
+/// Layer that checks the client address of requests sent through a
+/// trusted proxy.
+#[derive(Clone)]
+pub struct IpFilterLayer {
+    filter: IpFilter,
+}
+
+#[derive(Clone)]
+pub struct IpFilterService<S> {
+    inner: S,
+    filter: IpFilter,
+}
+
+impl IpFilterConfig {
+    /// Returns true if any network is allowed, denied or trusted.
+    pub fn is_enabled(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty() || !self.trusted_proxies.is_empty()
+    }
+}
+
+impl IpFilter {
+    pub fn new(config: IpFilterConfig) -> Self {
+        IpFilter {
+            config: Arc::new(config),
+        }
+    }
+
+    /// Returns true if the client with the given address can access the
+    /// node.
+    pub fn is_allowed(&self, ip: IpAddr) -> bool {
+        let ip = canonical_ip(ip);
+        if self.config.deny.iter().any(|net| net.contains(&ip)) {
+            return false;
+        }
+        self.config.allow.is_empty() || self.config.allow.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Returns true if the given address is a trusted proxy.
+    pub fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        let ip = canonical_ip(ip);
+        self.config
+            .trusted_proxies
+            .iter()
+            .any(|net| net.contains(&ip))
+    }
+
+    /// Returns true if the connection from `peer` can be accepted.
+    ///
+    /// Connections from trusted proxies are always accepted, their requests
+    /// are checked by [IpFilterLayer].
+    fn allow_connection(&self, peer: IpAddr) -> bool {
+        self.is_trusted_proxy(peer) || self.is_allowed(peer)
+    }
+
+    /// Returns true if the request received from `peer` can be served.
+    ///
+    /// Requests sent through a trusted proxy are checked against the client
+    /// address in the `x-forwarded-for` header.
+    pub fn allow_request(&self, peer: IpAddr, headers: &http::HeaderMap) -> bool {
+        if !self.is_trusted_proxy(peer) {
+            return self.is_allowed(peer);
+        }
+        let forwarded_for = headers
+            .get(FORWARDED_FOR_HEADER)
+            .and_then(|value| value.to_str().ok());
+        let client = self.forwarded_client(peer, forwarded_for);
+        let allowed = client
+            .map(|client| self.is_allowed(client))
+            .unwrap_or(false);
+        if !allowed {
+            debug!(peer = %peer, client = ?client, "request denied by ip filter");
+        }
+        allowed
+    }
+
+    /// Returns the address of the client that sent a request through the
+    /// proxy at `peer`.
+    ///
+    /// Proxies append the address they received the request from, so the
+    /// client is the last address that is not a trusted proxy. Requests
+    /// without the header, like health checks sent by the proxy itself, are
+    /// from `peer`.
+    fn forwarded_client(&self, peer: IpAddr, forwarded_for: Option<&str>) -> Option<IpAddr> {
+        let forwarded_for = match forwarded_for.map(str::trim) {
+            None | Some("") => return Some(peer),
+            Some(forwarded_for) => forwarded_for,
+        };
+        let forwarded = forwarded_for
+            .split(',')
+            .map(|addr| addr.trim().parse::<IpAddr>().ok());
+        let mut client = peer;
+        for addr in forwarded.rev() {
+            if !self.is_trusted_proxy(client) {
+                break;
+            }
+            client = addr?;
+        }
+        Some(client)
+    }
+}
+
+/// Drops new connections from clients that are not allowed.
+pub fn filter_connections<S, IO, E>(
+    incoming: S,
+    filter: Option<IpFilter>,
+) -> impl Stream<Item = Result<IO, E>>
+where
+    S: Stream<Item = Result<IO, E>>,
+    IO: Connected,
+    IO::ConnectInfo: RemoteAddr,
+{
+    incoming.filter(move |connection| {
+        let allowed = match (connection, &filter) {
+            (Err(_), _) | (_, None) => true,
+            (Ok(io), Some(filter)) => match io.connect_info().remote_addr() {
+                None => true,
+                Some(addr) => {
+                    let allowed = filter.allow_connection(addr.ip());
+                    if !allowed {
+                        debug!(addr = %addr, "connection denied by ip filter");
+                    }
+                    allowed
+                }
+            },
+        };
+        futures::future::ready(allowed)
+    })
+}
+
+/// Parses a network in CIDR notation, or a single address.
+pub fn parse_ip_net(s: &str) -> Result<IpNet, AddrParseError> {
+    match IpNet::from_str(s) {
+        Ok(net) => Ok(net),
+        Err(_) => Ok(IpAddr::from_str(s)?.into()),
+    }
+}
+
+impl IpFilterLayer {
+    pub fn new(filter: IpFilter) -> Self {
+        IpFilterLayer { filter }
+    }
+}
+
+impl<S> Layer<S> for IpFilterLayer {
+    type Service = IpFilterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IpFilterService {
+            inner,
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for IpFilterService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // the service that was polled ready handles the request.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        // connections from other clients were checked when accepted.
+        if let Some(peer) = remote_ip(request.extensions()) {
+            if self.filter.is_trusted_proxy(peer)
+                && !self.filter.allow_request(peer, request.headers())
+            {
+                let response = Status::permission_denied("client address not allowed").to_http();
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+
+        Box::pin(inner.call(request))
+    }
+}
+
+/// Returns the IPv4 address of IPv4-mapped IPv6 addresses, so that they
+/// match IPv4 networks.
+fn canonical_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use tonic::codegen::http::HeaderMap;
+
+    use super::{parse_ip_net, IpFilter, IpFilterConfig};
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ip_filter() {
+        let filter = IpFilter::new(IpFilterConfig {
+            allow: vec![parse_ip_net("10.0.0.0/8").unwrap()],
+            deny: vec![parse_ip_net("10.1.2.3").unwrap()],
+            trusted_proxies: vec![parse_ip_net("192.168.0.0/16").unwrap()],
+        });
+        assert!(filter.is_allowed(ip("10.2.0.1")));
+        assert!(filter.is_allowed(ip("::ffff:10.2.0.1")));
+        assert!(!filter.is_allowed(ip("10.1.2.3")));
+        assert!(!filter.is_allowed(ip("8.8.8.8")));
+
+        // proxies are accepted, their requests are checked instead.
+        assert!(filter.allow_connection(ip("192.168.1.1")));
+        assert!(!filter.allow_connection(ip("8.8.8.8")));
+    }
+
+    #[test]
+    fn test_forwarded_client() {
+        let filter = IpFilter::new(IpFilterConfig {
+            trusted_proxies: vec![parse_ip_net("192.168.0.0/16").unwrap()],
+            ..IpFilterConfig::default()
+        });
+        let proxy = ip("192.168.1.1");
+        assert_eq!(
+            filter.forwarded_client(proxy, Some("1.1.1.1, 10.0.0.1, 192.168.2.2")),
+            Some(ip("10.0.0.1"))
+        );
+        // clients can't spoof addresses before the last untrusted one.
+        assert_eq!(
+            filter.forwarded_client(proxy, Some("10.0.0.1, 8.8.8.8")),
+            Some(ip("8.8.8.8"))
+        );
+        assert_eq!(
+            filter.forwarded_client(proxy, Some("10.0.0.1, garbage")),
+            None
+        );
+        // requests sent by the proxy itself.
+        assert_eq!(filter.forwarded_client(proxy, None), Some(proxy));
+        assert_eq!(filter.forwarded_client(proxy, Some(" ")), Some(proxy));
+        assert_eq!(
+            filter.forwarded_client(ip("8.8.8.8"), None),
+            Some(ip("8.8.8.8"))
+        );
+    }
+
+    #[test]
+    fn test_allow_request_without_forwarded_for() {
+        let mut headers = HeaderMap::new();
+        let filter = IpFilter::new(IpFilterConfig {
+            allow: vec![parse_ip_net("192.168.1.0/24").unwrap()],
+            trusted_proxies: vec![parse_ip_net("192.168.0.0/16").unwrap()],
+            ..IpFilterConfig::default()
+        });
+        // the proxy address is checked instead.
+        assert!(filter.allow_request(ip("192.168.1.1"), &headers));
+        assert!(!filter.allow_request(ip("192.168.2.1"), &headers));
+
+        headers.insert("x-forwarded-for", "192.168.1.2".parse().unwrap());
+        assert!(filter.allow_request(ip("192.168.2.1"), &headers));
+        headers.insert("x-forwarded-for", "8.8.8.8".parse().unwrap());
+        assert!(!filter.allow_request(ip("192.168.1.1"), &headers));
+    }
+}
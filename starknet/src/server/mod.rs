@@ -1,26 +1,118 @@
+mod admin;
+pub mod audit;
+pub mod auth;
+mod billing;
+mod connection;
+pub mod extension;
 mod health;
+mod ip_filter;
+mod proxy_protocol;
+pub mod quota;
+mod rate_limit;
+mod registry;
+mod reload;
 pub mod stream;
+mod stream_metrics;
+pub mod sync;
+mod tls;
+mod web;
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{collections::HashSet, net::SocketAddr, sync::Arc};
 
 use apibara_core::node as node_pb;
 use apibara_node::{
-    db::libmdbx::{Environment, EnvironmentKind},
+    db::libmdbx::{Environment, EnvironmentKind, Error as MdbxError},
     server::{RequestObserver, SimpleRequestObserver},
+    stream::BatchSizePolicy,
 };
 use tokio::task::JoinError;
 use tokio_util::sync::CancellationToken;
-use tonic::transport::Server as TonicServer;
-use tracing::{debug_span, error, info};
+use tonic::transport::{server::TcpIncoming, Server as TonicServer};
+use tonic_web::GrpcWebLayer;
+use tower::util::option_layer;
+use tracing::{debug_span, info, warn};
 
-use crate::{db::DatabaseStorage, ingestion::IngestionStreamClient, server::stream::StreamService};
+use crate::{
+    db::{DatabaseStorage, ShardedStorage},
+    ingestion::{IngestionHealth, IngestionStreamClient},
+    server::{stream::StreamService, sync::BlockSyncService},
+    stream::{
+        BatchScheduler, FilterRestrictions, LoadMonitor, LoadSheddingConfig, PriorityConfig,
+        SlowBatchConfig,
+    },
+};
+
+use self::{
+    admin::AdminService,
+    auth::AuthLayer,
+    billing::BillingReporter,
+    connection::limit_connection_age,
+    extension::ExtensionInterceptor,
+    health::HealthReporter,
+    ip_filter::{filter_connections, IpFilterLayer},
+    proxy_protocol::proxy_incoming,
+    quota::DatabaseQuotaBackend,
+    rate_limit::{limit_connections, RateLimitLayer},
+    registry::StreamRegistry,
+    reload::ConfigReloader,
+    stream_metrics::StreamMetrics,
+    tls::tls_incoming,
+};
+
+pub use self::{
+    audit::{
+        AuditEvent, AuditRotation, AuditSink, CloseReason, FileAuditSink, LogAuditSink,
+        SyslogAuditSink, SyslogTarget, SyslogTargetError,
+    },
+    auth::{CallerIdentity, TokenValidator},
+    billing::{
+        BillingBackend, BillingConfig, BillingError, CallerUsage, GrpcBillingBackend,
+        HttpBillingBackend, UsageReport,
+    },
+    connection::ConnectionConfig,
+    extension::ServerExtension,
+    ip_filter::{parse_ip_net, IpFilter, IpFilterConfig},
+    quota::{QuotaBackend, QuotaConfig, QuotaLimits, QuotaTracker},
+    rate_limit::{RateLimit, RateLimitConfig, RateLimiter},
+    registry::{OpenStreams, StreamLimits},
+    reload::{ReloadConfig, ReloadableKeys},
+    stream::MessageSizeConfig,
+    stream_metrics::StreamMetricsConfig,
+    tls::{TlsConfig, TlsError},
+    web::GrpcWebConfig,
+};
 
-use self::health::HealthReporter;
+pub(crate) use self::rate_limit::retry_after_seconds;
 
 pub struct Server<E: EnvironmentKind, O: RequestObserver> {
     db: Arc<Environment<E>>,
+    storage: ShardedStorage<E>,
     ingestion: Arc<IngestionStreamClient>,
     request_observer: O,
+    ingestion_health: Option<IngestionHealth>,
+    tls: Option<TlsConfig>,
+    auth: AuthLayer,
+    quota: Option<QuotaTracker>,
+    rate_limiter: Option<RateLimiter>,
+    grpc_web: Option<GrpcWebConfig>,
+    admin_operators: HashSet<String>,
+    stream_limits: Option<StreamLimits>,
+    audit: Option<Arc<dyn AuditSink>>,
+    connection: ConnectionConfig,
+    ip_filter: Option<IpFilter>,
+    billing: Option<BillingConfig>,
+    priority: Option<PriorityConfig>,
+    batch_size_policy: BatchSizePolicy,
+    message_size: MessageSizeConfig,
+    restrictions: Option<FilterRestrictions>,
+    reload: Option<ReloadConfig>,
+    load_shedding: Option<LoadSheddingConfig>,
+    extensions: Vec<Arc<dyn ServerExtension>>,
+    proxy_protocol: bool,
+    block_sync: bool,
+    open_streams: OpenStreams,
+    stream_metrics: Option<StreamMetricsConfig>,
+    slow_batch: Option<SlowBatchConfig>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -31,6 +123,12 @@ pub enum ServerError {
     Task(#[from] JoinError),
     #[error("error starting reflection server")]
     ReflectionServer(#[from] tonic_reflection::server::Error),
+    #[error("error configuring tls")]
+    Tls(#[from] TlsError),
+    #[error("error binding server address")]
+    Bind(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("error reading chain id")]
+    ChainId(#[source] MdbxError),
 }
 
 impl<E, O> Server<E, O>
@@ -44,10 +142,36 @@ where
     ) -> Server<E, SimpleRequestObserver> {
         let ingestion = Arc::new(ingestion);
         let request_observer = SimpleRequestObserver::default();
+        let storage = ShardedStorage::single(DatabaseStorage::new(db.clone()));
         Server {
             db,
+            storage,
             ingestion,
             request_observer,
+            ingestion_health: None,
+            tls: None,
+            auth: AuthLayer::disabled(),
+            quota: None,
+            rate_limiter: None,
+            grpc_web: None,
+            admin_operators: HashSet::default(),
+            stream_limits: None,
+            audit: None,
+            connection: ConnectionConfig::default(),
+            ip_filter: None,
+            billing: None,
+            priority: None,
+            batch_size_policy: BatchSizePolicy::default(),
+            message_size: MessageSizeConfig::default(),
+            restrictions: None,
+            reload: None,
+            load_shedding: None,
+            extensions: Vec::default(),
+            proxy_protocol: false,
+            block_sync: false,
+            open_streams: OpenStreams::default(),
+            stream_metrics: None,
+            slow_batch: None,
         }
     }
 
@@ -55,13 +179,232 @@ where
     pub fn with_request_observer<S: RequestObserver>(self, request_observer: S) -> Server<E, S> {
         Server {
             db: self.db,
+            storage: self.storage,
             ingestion: self.ingestion,
             request_observer,
+            ingestion_health: self.ingestion_health,
+            tls: self.tls,
+            auth: self.auth,
+            quota: self.quota,
+            rate_limiter: self.rate_limiter,
+            grpc_web: self.grpc_web,
+            admin_operators: self.admin_operators,
+            stream_limits: self.stream_limits,
+            audit: self.audit,
+            connection: self.connection,
+            ip_filter: self.ip_filter,
+            billing: self.billing,
+            priority: self.priority,
+            batch_size_policy: self.batch_size_policy,
+            message_size: self.message_size,
+            restrictions: self.restrictions,
+            reload: self.reload,
+            load_shedding: self.load_shedding,
+            extensions: self.extensions,
+            proxy_protocol: self.proxy_protocol,
+            block_sync: self.block_sync,
+            open_streams: self.open_streams,
+            stream_metrics: self.stream_metrics,
+            slow_batch: self.slow_batch,
         }
     }
 
+    /// Serve data from the given storage, which can include archive shards.
+    pub fn with_storage(mut self, storage: ShardedStorage<E>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Report the node as not serving while block ingestion is unhealthy.
+    pub fn with_ingestion_health(mut self, health: IngestionHealth) -> Self {
+        self.ingestion_health = Some(health);
+        self
+    }
+
+    /// Terminate TLS with the given certificate, instead of serving
+    /// plaintext connections.
+    pub fn with_tls(mut self, tls: Option<TlsConfig>) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Require clients to authenticate with a token checked by the given
+    /// validator.
+    pub fn with_auth(mut self, validator: Option<Arc<dyn TokenValidator>>) -> Self {
+        self.auth = validator.map_or_else(AuthLayer::disabled, AuthLayer::new);
+        self
+    }
+
+    /// Limit the data units streamed by each authenticated caller.
+    ///
+    /// Usage is stored in the node database, unless the configuration has
+    /// its own backend.
+    pub fn with_quota(mut self, quota: Option<QuotaConfig>) -> Self {
+        self.quota = quota.map(|config| {
+            let backend = Arc::new(DatabaseQuotaBackend::new(self.db.clone()));
+            QuotaTracker::new(config, backend)
+        });
+        self
+    }
+
+    /// Rate limit new connections and streams.
+    pub fn with_rate_limit(mut self, rate_limit: Option<RateLimitConfig>) -> Self {
+        self.rate_limiter = rate_limit
+            .filter(RateLimitConfig::is_enabled)
+            .map(RateLimiter::new);
+        self
+    }
+
+    /// Accept grpc-web requests from browsers, with the given CORS
+    /// configuration.
+    pub fn with_grpc_web(mut self, grpc_web: Option<GrpcWebConfig>) -> Self {
+        self.grpc_web = grpc_web;
+        self
+    }
+
+    /// Limit the number of streams open at the same time.
+    pub fn with_stream_limits(mut self, stream_limits: Option<StreamLimits>) -> Self {
+        self.stream_limits = stream_limits.filter(StreamLimits::is_enabled);
+        self
+    }
+
+    /// Configure keepalive pings and the maximum age of connections.
+    pub fn with_connection(mut self, connection: Option<ConnectionConfig>) -> Self {
+        self.connection = connection.unwrap_or_default();
+        self
+    }
+
+    /// Only accept clients allowed by the given IP filter.
+    pub fn with_ip_filter(mut self, ip_filter: Option<IpFilterConfig>) -> Self {
+        self.ip_filter = ip_filter
+            .filter(IpFilterConfig::is_enabled)
+            .map(IpFilter::new);
+        self
+    }
+
+    /// Read the address of clients from the PROXY protocol header sent by
+    /// the load balancer at the start of each connection.
+    ///
+    /// Headers are only read from the trusted proxies of the IP filter.
+    pub fn with_proxy_protocol(mut self, enabled: bool) -> Self {
+        self.proxy_protocol = enabled;
+        self
+    }
+
+    /// Serve the block sync service, used by other nodes to bootstrap from
+    /// this node.
+    pub fn with_block_sync(mut self, enabled: bool) -> Self {
+        self.block_sync = enabled;
+        self
+    }
+
+    /// Favor the streams of callers with a higher priority tier when
+    /// producing batches.
+    pub fn with_priority(mut self, priority: Option<PriorityConfig>) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Clamp the batch size requested by clients with the given policy.
+    pub fn with_batch_size_policy(mut self, policy: Option<BatchSizePolicy>) -> Self {
+        self.batch_size_policy = policy.unwrap_or_default();
+        self
+    }
+
+    /// Restrict the filters of callers.
+    pub fn with_filter_restrictions(mut self, restrictions: Option<FilterRestrictions>) -> Self {
+        self.restrictions = restrictions;
+        self
+    }
+
+    /// Reload the configuration while the server is running, terminating
+    /// the streams no longer allowed.
+    pub fn with_reload(mut self, config: Option<ReloadConfig>) -> Self {
+        self.reload = config;
+        self
+    }
+
+    /// Shed load when the node is under resource pressure.
+    pub fn with_load_shedding(mut self, config: Option<LoadSheddingConfig>) -> Self {
+        self.load_shedding = config;
+        self
+    }
+
+    /// Limit the size of the messages exchanged with clients.
+    pub fn with_message_size(mut self, config: Option<MessageSizeConfig>) -> Self {
+        self.message_size = config.unwrap_or_default();
+        self
+    }
+
+    /// Periodically report the usage of callers to a billing service.
+    pub fn with_billing(mut self, billing: Option<BillingConfig>) -> Self {
+        self.billing = billing;
+        self
+    }
+
+    /// Returns the quota of callers, shared with the other servers that
+    /// stream data.
+    pub fn quota_tracker(&self) -> Option<QuotaTracker> {
+        self.quota.clone()
+    }
+
+    /// Returns the rate limits, shared with the other servers that stream
+    /// data.
+    pub fn rate_limiter(&self) -> Option<RateLimiter> {
+        self.rate_limiter.clone()
+    }
+
+    /// Returns the IP filter, shared with the other servers that stream
+    /// data.
+    pub fn ip_filter(&self) -> Option<IpFilter> {
+        self.ip_filter.clone()
+    }
+
+    /// Keep the given counter up to date with the number of open streams.
+    pub fn with_open_streams(mut self, open_streams: OpenStreams) -> Self {
+        self.open_streams = open_streams;
+        self
+    }
+
+    /// Record the data sent to each stream, labeled by caller and stream name.
+    pub fn with_stream_metrics(mut self, config: Option<StreamMetricsConfig>) -> Self {
+        self.stream_metrics = config;
+        self
+    }
+
+    /// Log the batches that are slow to produce or send.
+    pub fn with_slow_batch(mut self, config: Option<SlowBatchConfig>) -> Self {
+        self.slow_batch = config;
+        self
+    }
+
+    /// Send the lifecycle of streams to the given audit sink.
+    pub fn with_audit(mut self, audit: Option<Arc<dyn AuditSink>>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Intercept requests and observe the lifecycle of streams with the
+    /// given extensions, in order.
+    pub fn with_extensions(
+        mut self,
+        extensions: impl IntoIterator<Item = Arc<dyn ServerExtension>>,
+    ) -> Self {
+        self.extensions.extend(extensions);
+        self
+    }
+
+    /// Serve the admin service to the callers with the given identities.
+    ///
+    /// The admin service requires authentication, to know the callers.
+    pub fn with_admin_operators(mut self, operators: impl IntoIterator<Item = String>) -> Self {
+        self.admin_operators.extend(operators);
+        self
+    }
+
     pub async fn start(self, addr: SocketAddr, ct: CancellationToken) -> Result<(), ServerError> {
-        let (mut health_reporter, health_service) = HealthReporter::new(self.db.clone());
+        let (mut health_reporter, health_service) =
+            HealthReporter::new(self.db.clone(), self.ingestion_health.clone());
 
         let reporter_handle = tokio::spawn({
             let ct = ct.clone();
@@ -72,26 +415,175 @@ where
             .register_encoded_file_descriptor_set(node_pb::v1alpha2::node_file_descriptor_set())
             .build()?;
 
-        let storage = DatabaseStorage::new(self.db);
-        let stream_service =
-            StreamService::new(self.ingestion, storage, self.request_observer).into_service();
+        let quota = self.quota;
+        let quota_handle = quota
+            .clone()
+            .map(|tracker| tokio::spawn(tracker.run(ct.clone())));
+
+        let registry = StreamRegistry::new(self.stream_limits.unwrap_or_default())
+            .with_audit(self.audit)
+            .with_extensions(self.extensions.clone())
+            .with_open_streams(self.open_streams)
+            .with_shutdown(ct.clone());
+        let registry_handle = tokio::spawn(registry.clone().run(ct.clone()));
+        let billing_handle = self.billing.map(|config| {
+            let reporter = BillingReporter::new(registry.clone(), config);
+            tokio::spawn(reporter.run(ct.clone()))
+        });
+        let load_monitor = self.load_shedding.map(LoadMonitor::new);
+        let load_monitor_handle = load_monitor
+            .clone()
+            .map(|monitor| tokio::spawn(monitor.run(ct.clone())));
+        let reload_handle = self.reload.map(|config| {
+            let reloader = ConfigReloader::new(
+                config,
+                registry.clone(),
+                self.restrictions.clone(),
+                quota.clone(),
+            );
+            tokio::spawn(reloader.run(ct.clone()))
+        });
+        let admin_service = match (self.auth.is_enabled(), self.admin_operators.is_empty()) {
+            (_, true) => None,
+            (false, false) => {
+                warn!("admin service disabled because authentication is not configured");
+                None
+            }
+            (true, false) => {
+                Some(AdminService::new(registry.clone(), self.admin_operators).into_service())
+            }
+        };
+
+        let storage = self.storage;
+        // cursors sent to v1alpha3 clients carry the chain id.
+        let chain_id = storage
+            .chain_id()
+            .map_err(ServerError::ChainId)?
+            .map(|chain_id| chain_id.to_bytes().to_vec())
+            .unwrap_or_default();
+        let sync_service = self
+            .block_sync
+            .then(|| BlockSyncService::new(storage.clone()).into_service());
+        let (stream_service, stream_v1alpha3_service) =
+            StreamService::new(self.ingestion, storage, self.request_observer)
+                .with_quota(quota)
+                .with_ingestion_health(self.ingestion_health.clone())
+                .with_registry(registry)
+                .with_scheduler(self.priority.map(BatchScheduler::new))
+                .with_batch_size_policy(self.batch_size_policy)
+                .with_message_size(self.message_size)
+                .with_restrictions(self.restrictions.clone())
+                .with_load_monitor(load_monitor)
+                .with_stream_metrics(self.stream_metrics.map(StreamMetrics::new))
+                .with_slow_batch(self.slow_batch)
+                .with_chain_id(chain_id)
+                .into_services();
 
-        info!(addr = %addr, "starting server");
+        info!(
+            addr = %addr,
+            tls = self.tls.is_some(),
+            proxy_protocol = self.proxy_protocol,
+            auth = self.auth.is_enabled(),
+            rate_limit = self.rate_limiter.is_some(),
+            ip_filter = self.ip_filter.is_some(),
+            grpc_web = self.grpc_web.is_some(),
+            block_sync = self.block_sync,
+            admin = admin_service.is_some(),
+            extensions = self.extensions.len(),
+            "starting server"
+        );
 
-        TonicServer::builder()
+        let rate_limiter = self.rate_limiter;
+        let ip_filter = self.ip_filter;
+        let trusted_proxies = ip_filter.clone().filter(|_| self.proxy_protocol);
+        if self.proxy_protocol && trusted_proxies.is_none() {
+            warn!("proxy protocol enabled without trusted proxies, ignoring headers");
+        }
+        let accept_http1 = self.grpc_web.is_some();
+        // grpc-web requests are translated before authentication, so that
+        // errors are returned in the grpc-web format. Streams are rate
+        // limited after authentication, to know the caller. Extensions see
+        // the requests that passed all other checks.
+        let router = self
+            .connection
+            .configure(TonicServer::builder())
             .trace_fn(|_| debug_span!("node_server"))
+            .accept_http1(accept_http1)
+            .layer(option_layer(
+                self.grpc_web.as_ref().map(GrpcWebConfig::cors_layer),
+            ))
+            .layer(option_layer(self.grpc_web.map(|_| GrpcWebLayer::new())))
+            .layer(option_layer(ip_filter.clone().map(IpFilterLayer::new)))
+            .layer(self.auth)
+            .layer(option_layer(rate_limiter.clone().map(RateLimitLayer::new)))
+            .layer(option_layer(ExtensionInterceptor::layer(&self.extensions)))
             .add_service(health_service)
             .add_service(stream_service)
-            .add_service(reflection_service)
-            .serve_with_shutdown(addr, {
-                let ct = ct.clone();
-                async move { ct.cancelled().await }
-            })
-            .await?;
+            .add_service(stream_v1alpha3_service)
+            .add_optional_service(sync_service)
+            .add_optional_service(admin_service)
+            .add_service(reflection_service);
+        let shutdown = {
+            let ct = ct.clone();
+            async move { ct.cancelled().await }
+        };
+        let max_connection_age = self.connection.max_connection_age;
+        match (self.tls, trusted_proxies) {
+            (None, None)
+                if rate_limiter.is_none()
+                    && ip_filter.is_none()
+                    && max_connection_age.is_none() =>
+            {
+                router.serve_with_shutdown(addr, shutdown).await?
+            }
+            (None, Some(trusted_proxies)) => {
+                let incoming = proxy_incoming(addr, trusted_proxies, ct.clone())
+                    .await
+                    .map_err(|err| ServerError::Bind(Box::new(err)))?;
+                let incoming = filter_connections(incoming, ip_filter);
+                let incoming = limit_connections(incoming, rate_limiter);
+                let incoming = limit_connection_age(incoming, max_connection_age);
+                router
+                    .serve_with_incoming_shutdown(incoming, shutdown)
+                    .await?
+            }
+            (None, None) => {
+                let incoming = TcpIncoming::new(addr, true, None).map_err(ServerError::Bind)?;
+                let incoming = filter_connections(incoming, ip_filter);
+                let incoming = limit_connections(incoming, rate_limiter);
+                let incoming = limit_connection_age(incoming, max_connection_age);
+                router
+                    .serve_with_incoming_shutdown(incoming, shutdown)
+                    .await?
+            }
+            (Some(tls), trusted_proxies) => {
+                let incoming =
+                    tls_incoming(addr, tls, trusted_proxies, accept_http1, ct.clone()).await?;
+                let incoming = filter_connections(incoming, ip_filter);
+                let incoming = limit_connections(incoming, rate_limiter);
+                let incoming = limit_connection_age(incoming, max_connection_age);
+                router
+                    .serve_with_incoming_shutdown(incoming, shutdown)
+                    .await?
+            }
+        }
 
         // signal health reporter to stop and wait for it
         ct.cancel();
         reporter_handle.await?;
+        registry_handle.await?;
+        if let Some(billing_handle) = billing_handle {
+            billing_handle.await?;
+        }
+        if let Some(reload_handle) = reload_handle {
+            reload_handle.await?;
+        }
+        if let Some(load_monitor_handle) = load_monitor_handle {
+            load_monitor_handle.await?;
+        }
+        if let Some(quota_handle) = quota_handle {
+            quota_handle.await?;
+        }
 
         Ok(())
     }
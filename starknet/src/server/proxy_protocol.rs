@@ -0,0 +1,464 @@
+//! Accept connections through proxies that speak the PROXY protocol.
+//!
+//! L4 load balancers hide the address of clients, since the node sees the
+//! connections opened by the load balancer. With the PROXY protocol, the
+//! load balancer sends the address of the client in a header at the start
+//! of each connection. The node reads the header and uses the address of
+//! the client for rate limiting, IP filtering, and audit logs.
+//!
+//! Both the text (v1) and binary (v2) versions of the protocol are
+//! supported. Headers are only read from trusted proxies, other peers could
+//! use them to spoof their address. Their connections are treated as
+//! direct connections. Connections from trusted proxies without a valid
+//! header are closed.
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    task::{self, Poll},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tonic::transport::server::{Connected, TlsConnectInfo};
+use tracing::{debug, warn};
+
+use super::ip_filter::IpFilter;
+
+/// Maximum number of established connections waiting to be served.
+const CONNECTION_BUFFER_SIZE: usize = 128;
+
+/// Close connections that don't send the header within this time.
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Signature of v1 headers.
+const V1_PREFIX: &[u8] = b"PROXY ";
+
+/// Maximum length of v1 headers, including the final CRLF.
+const V1_MAX_LENGTH: usize = 107;
+
+/// Signature of v2 headers.
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Maximum length of v2 headers accepted, including extensions.
+const V2_MAX_LENGTH: usize = 4096;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProxyProtocolError {
+    #[error("connection doesn't start with a PROXY protocol header")]
+    MissingHeader,
+    #[error("invalid PROXY protocol header")]
+    InvalidHeader,
+    #[error("timeout reading the PROXY protocol header")]
+    Timeout,
+    #[error("failed to read the PROXY protocol header")]
+    Io(#[from] io::Error),
+}
+
+/// Connection information of a connection accepted through a proxy.
+///
+/// The remote address is the address of the client, not of the proxy.
+#[derive(Debug, Clone)]
+pub struct ProxiedConnectInfo {
+    remote_addr: SocketAddr,
+}
+
+/// A connection with the address of the client that opened it.
+///
+/// Bytes read after the PROXY protocol header are returned before reading
+/// from the connection again.
+pub struct ProxiedStream {
+    inner: TcpStream,
+    client: SocketAddr,
+    buffered: Vec<u8>,
+    position: usize,
+}
+
+impl ProxiedStream {
+    /// Returns a connection opened directly by the client at `peer`.
+    pub fn direct(inner: TcpStream, peer: SocketAddr) -> Self {
+        ProxiedStream {
+            inner,
+            client: peer,
+            buffered: Vec::default(),
+            position: 0,
+        }
+    }
+
+    /// Reads the PROXY protocol header of a connection opened by the proxy
+    /// at `peer`.
+    ///
+    /// Health checks of the proxy don't have a client address, their
+    /// connections keep the address of the proxy.
+    pub async fn accept(
+        mut inner: TcpStream,
+        peer: SocketAddr,
+    ) -> Result<Self, ProxyProtocolError> {
+        let (client, buffered) = tokio::time::timeout(HEADER_TIMEOUT, read_header(&mut inner))
+            .await
+            .map_err(|_| ProxyProtocolError::Timeout)??;
+        Ok(ProxiedStream {
+            inner,
+            client: client.unwrap_or(peer),
+            buffered,
+            position: 0,
+        })
+    }
+
+    /// Reads the PROXY protocol header if `peer` is one of the trusted
+    /// proxies, otherwise returns a direct connection.
+    pub async fn accept_from(
+        inner: TcpStream,
+        peer: SocketAddr,
+        trusted_proxies: &IpFilter,
+    ) -> Result<Self, ProxyProtocolError> {
+        if trusted_proxies.is_trusted_proxy(peer.ip()) {
+            Self::accept(inner, peer).await
+        } else {
+            Ok(Self::direct(inner, peer))
+        }
+    }
+}
+
+/// Accepts connections on `addr`, until `ct` is cancelled.
+///
+/// Headers are read in their own task, so that slow proxies don't delay
+/// other connections. Only connections from `trusted_proxies` start with a
+/// header.
+pub async fn proxy_incoming(
+    addr: SocketAddr,
+    trusted_proxies: IpFilter,
+    ct: CancellationToken,
+) -> io::Result<ReceiverStream<Result<ProxiedStream, io::Error>>> {
+    let listener = TcpListener::bind(addr).await?;
+    let (tx, rx) = mpsc::channel(CONNECTION_BUFFER_SIZE);
+    tokio::spawn(async move {
+        loop {
+            let accepted = tokio::select! {
+                _ = ct.cancelled() => return,
+                accepted = listener.accept() => accepted,
+            };
+            let (stream, peer) = match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!(error = ?err, "failed to accept connection");
+                    continue;
+                }
+            };
+            let _ = stream.set_nodelay(true);
+            let tx = tx.clone();
+            let trusted_proxies = trusted_proxies.clone();
+            tokio::spawn(async move {
+                match ProxiedStream::accept_from(stream, peer, &trusted_proxies).await {
+                    Ok(stream) => {
+                        let _ = tx.send(Ok(stream)).await;
+                    }
+                    Err(err) => debug!(peer = %peer, error = ?err, "proxy protocol failed"),
+                }
+            });
+        }
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
+
+/// Returns the address of the client that sent the request.
+///
+/// Unlike [tonic::Request::remote_addr], this includes the client address
+/// of connections accepted through a proxy.
+pub fn client_addr<T>(request: &tonic::Request<T>) -> Option<SocketAddr> {
+    request.remote_addr().or_else(|| {
+        let extensions = request.extensions();
+        extensions
+            .get::<ProxiedConnectInfo>()
+            .map(ProxiedConnectInfo::remote_addr)
+            .or_else(|| {
+                extensions
+                    .get::<TlsConnectInfo<ProxiedConnectInfo>>()
+                    .map(|info| info.get_ref().remote_addr())
+            })
+    })
+}
+
+/// Reads the header from `io`, returning the client address in the header
+/// and the bytes read after it.
+async fn read_header<IO: AsyncRead + Unpin>(
+    io: &mut IO,
+) -> Result<(Option<SocketAddr>, Vec<u8>), ProxyProtocolError> {
+    let mut buf = Vec::with_capacity(V1_MAX_LENGTH);
+    loop {
+        if let Some((client, length)) = parse_header(&buf)? {
+            return Ok((client, buf.split_off(length)));
+        }
+        let read = io.read_buf(&mut buf).await?;
+        if read == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+    }
+}
+
+/// Parses the header at the start of `buf`, returning the client address
+/// and the length of the header.
+///
+/// Returns `None` if more bytes are needed.
+fn parse_header(buf: &[u8]) -> Result<Option<(Option<SocketAddr>, usize)>, ProxyProtocolError> {
+    if buf.starts_with(V2_SIGNATURE) {
+        return parse_v2(buf);
+    }
+    if buf.starts_with(V1_PREFIX) {
+        return parse_v1(buf);
+    }
+    if V2_SIGNATURE.starts_with(buf) || V1_PREFIX.starts_with(buf) {
+        return Ok(None);
+    }
+    Err(ProxyProtocolError::MissingHeader)
+}
+
+/// Parses a text header, for example
+/// `PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n`.
+fn parse_v1(buf: &[u8]) -> Result<Option<(Option<SocketAddr>, usize)>, ProxyProtocolError> {
+    let end = match buf.windows(2).position(|window| window == b"\r\n") {
+        Some(end) if end + 2 <= V1_MAX_LENGTH => end,
+        None if buf.len() < V1_MAX_LENGTH => return Ok(None),
+        _ => return Err(ProxyProtocolError::InvalidHeader),
+    };
+    let line = std::str::from_utf8(&buf[V1_PREFIX.len()..end])
+        .map_err(|_| ProxyProtocolError::InvalidHeader)?;
+    let parts: Vec<_> = line.split(' ').collect();
+    let client = match parts.as_slice() {
+        ["UNKNOWN", ..] => None,
+        [protocol, source, _destination, source_port, _destination_port] => {
+            let ip: IpAddr = source
+                .parse()
+                .map_err(|_| ProxyProtocolError::InvalidHeader)?;
+            let port: u16 = source_port
+                .parse()
+                .map_err(|_| ProxyProtocolError::InvalidHeader)?;
+            match (*protocol, ip) {
+                ("TCP4", IpAddr::V4(_)) | ("TCP6", IpAddr::V6(_)) => {}
+                _ => return Err(ProxyProtocolError::InvalidHeader),
+            }
+            Some(SocketAddr::new(ip, port))
+        }
+        _ => return Err(ProxyProtocolError::InvalidHeader),
+    };
+    Ok(Some((client, end + 2)))
+}
+
+/// Parses a binary header.
+fn parse_v2(buf: &[u8]) -> Result<Option<(Option<SocketAddr>, usize)>, ProxyProtocolError> {
+    if buf.len() < 16 {
+        return Ok(None);
+    }
+    let version_command = buf[12];
+    if version_command >> 4 != 2 {
+        return Err(ProxyProtocolError::InvalidHeader);
+    }
+    let length = 16 + u16::from_be_bytes([buf[14], buf[15]]) as usize;
+    if length > V2_MAX_LENGTH {
+        return Err(ProxyProtocolError::InvalidHeader);
+    }
+    if buf.len() < length {
+        return Ok(None);
+    }
+    let addresses = &buf[16..length];
+    let port = |offset: usize| u16::from_be_bytes([addresses[offset], addresses[offset + 1]]);
+    let client = match (version_command & 0x0f, buf[13] >> 4) {
+        // the proxy opened the connection itself, for example a health
+        // check.
+        (0x0, _) => None,
+        (0x1, 0x1) if addresses.len() >= 12 => {
+            let ip: [u8; 4] = addresses[..4].try_into().expect("ipv4 address");
+            Some(SocketAddr::new(Ipv4Addr::from(ip).into(), port(8)))
+        }
+        (0x1, 0x2) if addresses.len() >= 36 => {
+            let ip: [u8; 16] = addresses[..16].try_into().expect("ipv6 address");
+            Some(SocketAddr::new(Ipv6Addr::from(ip).into(), port(32)))
+        }
+        // unspecified or unix socket address.
+        (0x1, 0x0) | (0x1, 0x3) => None,
+        _ => return Err(ProxyProtocolError::InvalidHeader),
+    };
+    Ok(Some((client, length)))
+}
+
+impl ProxiedConnectInfo {
+    /// Returns the address of the client.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+}
+
+impl Connected for ProxiedStream {
+    type ConnectInfo = ProxiedConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        ProxiedConnectInfo {
+            remote_addr: self.client,
+        }
+    }
+}
+
+impl AsyncRead for ProxiedStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.position < this.buffered.len() {
+            let buffered = &this.buffered[this.position..];
+            let length = buffered.len().min(buf.remaining());
+            buf.put_slice(&buffered[..length]);
+            this.position += length;
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxiedStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tokio::{
+        io::{duplex, AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use crate::server::ip_filter::{parse_ip_net, IpFilter, IpFilterConfig};
+
+    use super::{parse_header, read_header, ProxiedStream, ProxyProtocolError};
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_v1_header() {
+        let header = b"PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n";
+        assert_eq!(
+            parse_header(header).unwrap(),
+            Some((Some(addr("192.168.0.1:56324")), header.len()))
+        );
+        let header = b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 443\r\n";
+        assert_eq!(
+            parse_header(header).unwrap(),
+            Some((Some(addr("[2001:db8::1]:56324")), header.len()))
+        );
+        assert_eq!(
+            parse_header(b"PROXY UNKNOWN\r\n").unwrap(),
+            Some((None, 15))
+        );
+        assert_eq!(parse_header(b"PRO").unwrap(), None);
+        assert_eq!(parse_header(b"PROXY TCP4 192.168").unwrap(), None);
+        assert!(matches!(
+            parse_header(b"PROXY TCP6 192.168.0.1 192.168.0.11 56324 443\r\n"),
+            Err(ProxyProtocolError::InvalidHeader)
+        ));
+        assert!(matches!(
+            parse_header(b"PRI * HTTP/2.0\r\n"),
+            Err(ProxyProtocolError::MissingHeader)
+        ));
+    }
+
+    #[test]
+    fn test_parse_v2_header() {
+        let mut header = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+        header.extend([0x21, 0x11, 0x00, 0x0c]);
+        header.extend([10, 0, 0, 1, 10, 0, 0, 2]);
+        header.extend(1234u16.to_be_bytes());
+        header.extend(443u16.to_be_bytes());
+        assert_eq!(parse_header(&header[..20]).unwrap(), None);
+        assert_eq!(
+            parse_header(&header).unwrap(),
+            Some((Some(addr("10.0.0.1:1234")), 28))
+        );
+
+        // health checks of the proxy.
+        let mut local = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+        local.extend([0x20, 0x00, 0x00, 0x00]);
+        assert_eq!(parse_header(&local).unwrap(), Some((None, 16)));
+
+        let mut invalid = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+        invalid.extend([0x11, 0x11, 0x00, 0x00]);
+        assert!(matches!(
+            parse_header(&invalid),
+            Err(ProxyProtocolError::InvalidHeader)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_header_keeps_data() {
+        let (mut client, mut server) = duplex(256);
+        client
+            .write_all(b"PROXY TCP4 1.1.1.1 2.2.2.2 1000 443\r\nPRI * HTTP/2.0")
+            .await
+            .unwrap();
+        let (client, buffered) = read_header(&mut server).await.unwrap();
+        assert_eq!(client, Some(addr("1.1.1.1:1000")));
+        assert_eq!(buffered, b"PRI * HTTP/2.0");
+    }
+
+    #[tokio::test]
+    async fn test_only_trusted_proxies_send_headers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        client
+            .write_all(b"PROXY TCP4 1.1.1.1 2.2.2.2 1000 443\r\n")
+            .await
+            .unwrap();
+        let (stream, peer) = listener.accept().await.unwrap();
+
+        // the header of untrusted peers is not parsed.
+        let trusted_proxies = IpFilter::new(IpFilterConfig {
+            trusted_proxies: vec![parse_ip_net("10.0.0.0/8").unwrap()],
+            ..IpFilterConfig::default()
+        });
+        let mut stream = ProxiedStream::accept_from(stream, peer, &trusted_proxies)
+            .await
+            .unwrap();
+        assert_eq!(stream.client, peer);
+        let mut buf = [0; 6];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"PROXY ");
+    }
+}
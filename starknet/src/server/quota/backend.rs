@@ -0,0 +1,100 @@
+//! Storage of the quota usage.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use apibara_node::db::libmdbx::{self, Environment, EnvironmentKind};
+use tonic::Status;
+
+use crate::db::{DatabaseStorage, QuotaUsage};
+
+use super::roll_usage;
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaBackendError {
+    #[error("database error")]
+    Database(#[from] libmdbx::Error),
+    #[error("redis error")]
+    Redis(#[from] redis::RedisError),
+    #[error("invalid quota service url")]
+    InvalidServiceUrl(#[from] tonic::transport::Error),
+    #[error("quota service error: {0}")]
+    Service(Box<Status>),
+}
+
+/// Stores the usage of callers.
+///
+/// Nodes sharing a backend share the quota of callers.
+#[apibara_node::async_trait]
+pub trait QuotaBackend: Send + Sync + 'static {
+    /// Adds `units` to the usage of the caller at time `now`, returning its
+    /// usage in the current day and month.
+    ///
+    /// Use zero units to read the usage.
+    async fn add_usage(
+        &self,
+        identity: &str,
+        units: u64,
+        now: u64,
+    ) -> Result<QuotaUsage, QuotaBackendError>;
+}
+
+/// Keeps usage in memory. Usage is lost on restart.
+#[derive(Debug, Default)]
+pub struct MemoryQuotaBackend {
+    usage: Mutex<HashMap<String, QuotaUsage>>,
+}
+
+/// Keeps usage in the node database.
+pub struct DatabaseQuotaBackend<E: EnvironmentKind> {
+    storage: DatabaseStorage<E>,
+    // serializes updates, which read and write usage in two transactions.
+    lock: tokio::sync::Mutex<()>,
+}
+
+#[apibara_node::async_trait]
+impl QuotaBackend for MemoryQuotaBackend {
+    async fn add_usage(
+        &self,
+        identity: &str,
+        units: u64,
+        now: u64,
+    ) -> Result<QuotaUsage, QuotaBackendError> {
+        let mut usage = self.usage.lock().expect("quota usage lock");
+        let usage = usage.entry(identity.to_string()).or_default();
+        roll_usage(usage, now);
+        usage.day_units += units;
+        usage.month_units += units;
+        Ok(usage.clone())
+    }
+}
+
+impl<E: EnvironmentKind> DatabaseQuotaBackend<E> {
+    pub fn new(db: Arc<Environment<E>>) -> Self {
+        DatabaseQuotaBackend {
+            storage: DatabaseStorage::new(db),
+            lock: Default::default(),
+        }
+    }
+}
+
+#[apibara_node::async_trait]
+impl<E: EnvironmentKind> QuotaBackend for DatabaseQuotaBackend<E> {
+    async fn add_usage(
+        &self,
+        identity: &str,
+        units: u64,
+        now: u64,
+    ) -> Result<QuotaUsage, QuotaBackendError> {
+        let _guard = self.lock.lock().await;
+        let mut usage = self.storage.quota_usage(identity)?.unwrap_or_default();
+        roll_usage(&mut usage, now);
+        if units > 0 {
+            usage.day_units += units;
+            usage.month_units += units;
+            self.storage.write_quota_usage(identity, &usage)?;
+        }
+        Ok(usage)
+    }
+}
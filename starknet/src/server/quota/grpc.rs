@@ -0,0 +1,53 @@
+//! Quota usage stored by an external service.
+use apibara_core::node::v1alpha2::{quota_client::QuotaClient, AddUsageRequest};
+use tonic::transport::{Channel, Endpoint};
+
+use crate::db::QuotaUsage;
+
+use super::backend::{QuotaBackend, QuotaBackendError};
+
+/// Keeps usage in an external service implementing the `Quota` gRPC service.
+pub struct GrpcQuotaBackend {
+    client: QuotaClient<Channel>,
+}
+
+impl GrpcQuotaBackend {
+    /// Creates a backend that calls the service at `url`.
+    ///
+    /// The connection is established on the first request.
+    pub fn new(url: String) -> Result<Self, QuotaBackendError> {
+        let channel = Endpoint::from_shared(url)?.connect_lazy();
+        Ok(GrpcQuotaBackend {
+            client: QuotaClient::new(channel),
+        })
+    }
+}
+
+#[apibara_node::async_trait]
+impl QuotaBackend for GrpcQuotaBackend {
+    async fn add_usage(
+        &self,
+        identity: &str,
+        units: u64,
+        now: u64,
+    ) -> Result<QuotaUsage, QuotaBackendError> {
+        let request = AddUsageRequest {
+            identity: identity.to_string(),
+            units,
+            timestamp: now,
+        };
+        let response = self
+            .client
+            .clone()
+            .add_usage(request)
+            .await
+            .map_err(|status| QuotaBackendError::Service(Box::new(status)))?
+            .into_inner();
+        Ok(QuotaUsage {
+            day_start: response.day_start,
+            day_units: response.day_units,
+            month_start: response.month_start,
+            month_units: response.month_units,
+        })
+    }
+}
@@ -0,0 +1,591 @@
+//! Enforce quotas on the data units streamed by each caller.
+//!
+//! Every datum sent to a client (header, transaction, event, ...) is one
+//! data unit. Callers are identified by the `x-apibara-caller` metadata set
+//! by the authentication layer, and can use up to a given number of data
+//! units per day and per month. Streams are refused once the quota is
+//! exhausted, and running streams end with a `RESOURCE_EXHAUSTED` error.
+//!
+//! Usage is stored by a [QuotaBackend], by default the node database.
+//! Deployments with several nodes use a shared backend (Redis or an external
+//! service) so that callers have the same quota on all nodes. Each node
+//! counts usage locally and periodically adds it to the backend, reading
+//! back the usage of all nodes.
+mod backend;
+mod grpc;
+mod redis;
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, RwLock},
+    task::{self, Poll},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use apibara_node::{server::RequestMeter, stream::StreamError};
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use futures::Stream;
+use pin_project::pin_project;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+use crate::db::QuotaUsage;
+
+pub use self::backend::{
+    DatabaseQuotaBackend, MemoryQuotaBackend, QuotaBackend, QuotaBackendError,
+};
+pub use self::grpc::GrpcQuotaBackend;
+pub use self::redis::RedisQuotaBackend;
+pub use apibara_node::stream::QuotaPeriod;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// How often usage is synchronized with the backend.
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum QuotaError {
+    #[error("failed to read quota file")]
+    Io(#[from] std::io::Error),
+    #[error("invalid line {0} in quota file")]
+    InvalidLine(usize),
+}
+
+/// Maximum number of data units, unlimited if `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct QuotaLimits {
+    pub daily: Option<u64>,
+    pub monthly: Option<u64>,
+}
+
+/// Quota configuration.
+#[derive(Clone, Serialize)]
+pub struct QuotaConfig {
+    /// Limits of callers without specific limits.
+    pub default_limits: QuotaLimits,
+    /// Limits by caller identity.
+    #[serde(serialize_with = "crate::runtime_config::serialize_len")]
+    pub caller_limits: HashMap<String, QuotaLimits>,
+    /// How often usage is synchronized with the backend.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub flush_interval: Duration,
+    /// Where usage is stored. The node database if `None`.
+    #[serde(serialize_with = "crate::runtime_config::serialize_is_some")]
+    pub backend: Option<Arc<dyn QuotaBackend>>,
+}
+
+/// Returned when a caller used all the data units of a period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    pub period: QuotaPeriod,
+    pub limit: u64,
+    pub used: u64,
+    /// Unix timestamp of when the quota resets.
+    pub reset_at: u64,
+}
+
+/// Usage of all callers.
+#[derive(Clone)]
+pub struct QuotaTracker {
+    config: Arc<QuotaConfig>,
+    // limits by caller, replaced when the limits file is reloaded.
+    caller_limits: Arc<RwLock<HashMap<String, QuotaLimits>>>,
+    backend: Arc<dyn QuotaBackend>,
+    usage: Arc<Mutex<HashMap<String, CallerUsage>>>,
+}
+
+#[derive(Default)]
+struct CallerUsage {
+    // usage of all nodes, including pending units.
+    usage: QuotaUsage,
+    // units not added to the backend yet.
+    pending: u64,
+}
+
+/// Quota of the caller of a stream.
+#[derive(Clone)]
+pub struct CallerQuota {
+    tracker: QuotaTracker,
+    identity: String,
+}
+
+/// A [RequestMeter] that also counts data units against the caller quota.
+pub struct QuotaMeter<M: RequestMeter> {
+    inner: M,
+    quota: Option<CallerQuota>,
+}
+
+/// A stream of responses that ends with an error once the caller quota is
+/// exceeded.
+#[pin_project]
+pub struct QuotaLimitedStream<S> {
+    #[pin]
+    inner: S,
+    quota: Option<CallerQuota>,
+    exceeded: bool,
+}
+
+impl QuotaLimits {
+    pub fn new(daily: Option<u64>, monthly: Option<u64>) -> Self {
+        QuotaLimits { daily, monthly }
+    }
+}
+
+impl QuotaConfig {
+    /// Creates a configuration where all callers have the given limits.
+    pub fn new(default_limits: QuotaLimits) -> Self {
+        QuotaConfig {
+            default_limits,
+            caller_limits: HashMap::default(),
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            backend: None,
+        }
+    }
+
+    /// Store usage in the given backend.
+    pub fn with_backend(mut self, backend: Arc<dyn QuotaBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Use the given limits for the caller, instead of the default ones.
+    pub fn with_caller_limits(mut self, identity: impl Into<String>, limits: QuotaLimits) -> Self {
+        self.caller_limits.insert(identity.into(), limits);
+        self
+    }
+
+    /// Loads the limits of callers from a file.
+    ///
+    /// Each line contains the caller identity, its daily limit and its
+    /// monthly limit, separated by whitespace. Use `-` for no limit. Empty
+    /// lines and lines starting with `#` are ignored.
+    pub fn with_caller_limits_file(self, path: &Path) -> Result<Self, QuotaError> {
+        let content = fs::read_to_string(path)?;
+        self.with_caller_limits_from_str(&content)
+    }
+
+    fn with_caller_limits_from_str(mut self, content: &str) -> Result<Self, QuotaError> {
+        self.caller_limits.extend(parse_caller_limits(content)?);
+        Ok(self)
+    }
+}
+
+impl From<QuotaExceeded> for StreamError {
+    fn from(exceeded: QuotaExceeded) -> Self {
+        StreamError::quota_exceeded(
+            exceeded.period,
+            exceeded.limit,
+            exceeded.used,
+            exceeded.reset_at,
+        )
+    }
+}
+
+impl QuotaTracker {
+    /// Creates a tracker storing usage in the configured backend, or in
+    /// `default_backend` if none is configured.
+    pub fn new(config: QuotaConfig, default_backend: Arc<dyn QuotaBackend>) -> Self {
+        let backend = config.backend.clone().unwrap_or(default_backend);
+        let caller_limits = Arc::new(RwLock::new(config.caller_limits.clone()));
+        QuotaTracker {
+            config: Arc::new(config),
+            caller_limits,
+            backend,
+            usage: Arc::default(),
+        }
+    }
+
+    /// Replaces the limits of callers with the ones in the file, see
+    /// [QuotaConfig::with_caller_limits_file].
+    ///
+    /// The previous limits are kept if the file is invalid.
+    pub fn reload_caller_limits_file(&self, path: &Path) -> Result<(), QuotaError> {
+        let content = fs::read_to_string(path)?;
+        self.set_caller_limits(parse_caller_limits(&content)?);
+        Ok(())
+    }
+
+    fn set_caller_limits(&self, caller_limits: HashMap<String, QuotaLimits>) {
+        *self.caller_limits.write().expect("quota limits lock") = caller_limits;
+    }
+
+    fn limits(&self, identity: &str) -> QuotaLimits {
+        self.caller_limits
+            .read()
+            .expect("quota limits lock")
+            .get(identity)
+            .copied()
+            .unwrap_or(self.config.default_limits)
+    }
+
+    /// Returns the quota of the given caller.
+    pub fn caller(&self, identity: impl Into<String>) -> CallerQuota {
+        CallerQuota {
+            tracker: self.clone(),
+            identity: identity.into(),
+        }
+    }
+
+    /// Adds the pending units of all callers to the backend, and reads back
+    /// their usage.
+    pub async fn flush(&self) {
+        let now = unix_now();
+        let pending: Vec<_> = self
+            .lock()
+            .iter_mut()
+            .map(|(identity, usage)| (identity.clone(), std::mem::take(&mut usage.pending)))
+            .collect();
+        for (identity, units) in pending {
+            match self.backend.add_usage(&identity, units, now).await {
+                Ok(usage) => self.update(&identity, usage, now),
+                Err(err) => {
+                    warn!(identity = %identity, error = ?err, "failed to update quota usage");
+                    if let Some(usage) = self.lock().get_mut(&identity) {
+                        usage.pending += units;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Periodically synchronizes usage with the backend, until `ct` is
+    /// cancelled.
+    pub async fn run(self, ct: CancellationToken) {
+        loop {
+            let cancelled = tokio::select! {
+                _ = ct.cancelled() => true,
+                _ = tokio::time::sleep(self.config.flush_interval) => false,
+            };
+            self.flush().await;
+            if cancelled {
+                return;
+            }
+        }
+    }
+
+    /// Reads the usage of the caller from the backend.
+    async fn refresh(&self, identity: &str) {
+        let now = unix_now();
+        match self.backend.add_usage(identity, 0, now).await {
+            Ok(usage) => self.update(identity, usage, now),
+            Err(err) => warn!(identity = %identity, error = ?err, "failed to read quota usage"),
+        }
+    }
+
+    /// Replaces the usage of the caller with the usage in the backend, plus
+    /// the units that are still pending.
+    fn update(&self, identity: &str, mut shared: QuotaUsage, now: u64) {
+        roll_usage(&mut shared, now);
+        let mut usage = self.lock();
+        let usage = usage.entry(identity.to_string()).or_default();
+        shared.day_units += usage.pending;
+        shared.month_units += usage.pending;
+        usage.usage = shared;
+    }
+
+    fn check_at(&self, identity: &str, now: u64) -> Result<(), QuotaExceeded> {
+        let limits = self.limits(identity);
+        let mut usage = self.lock();
+        let usage = match usage.get_mut(identity) {
+            None => return Ok(()),
+            Some(usage) => usage,
+        };
+        roll_usage(&mut usage.usage, now);
+        check_limits(&usage.usage, &limits)
+    }
+
+    fn add_at(&self, identity: &str, units: u64, now: u64) {
+        let mut usage = self.lock();
+        let usage = usage.entry(identity.to_string()).or_default();
+        roll_usage(&mut usage.usage, now);
+        usage.usage.day_units += units;
+        usage.usage.month_units += units;
+        usage.pending += units;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, CallerUsage>> {
+        self.usage.lock().expect("quota usage lock")
+    }
+}
+
+impl CallerQuota {
+    /// Reads the latest usage of the caller, then returns an error if it used
+    /// all its data units.
+    pub async fn refresh_and_check(&self) -> Result<(), QuotaExceeded> {
+        self.tracker.refresh(&self.identity).await;
+        self.check()
+    }
+
+    /// Returns an error if the caller used all its data units.
+    pub fn check(&self) -> Result<(), QuotaExceeded> {
+        self.tracker.check_at(&self.identity, unix_now())
+    }
+
+    /// Counts the given data units against the caller quota.
+    pub fn add(&self, units: u64) {
+        self.tracker.add_at(&self.identity, units, unix_now())
+    }
+}
+
+impl<M: RequestMeter> QuotaMeter<M> {
+    pub fn new(inner: M, quota: Option<CallerQuota>) -> Self {
+        QuotaMeter { inner, quota }
+    }
+}
+
+impl<M: RequestMeter> RequestMeter for QuotaMeter<M> {
+    fn increment_counter(&self, name: &'static str, amount: u64) {
+        self.inner.increment_counter(name, amount);
+        if let Some(quota) = &self.quota {
+            if amount > 0 {
+                quota.add(amount);
+            }
+        }
+    }
+}
+
+impl<S> QuotaLimitedStream<S> {
+    pub fn new(inner: S, quota: Option<CallerQuota>) -> Self {
+        QuotaLimitedStream {
+            inner,
+            quota,
+            exceeded: false,
+        }
+    }
+}
+
+impl<S, T, E> Stream for QuotaLimitedStream<S>
+where
+    S: Stream<Item = Result<T, E>>,
+    E: From<StreamError>,
+{
+    type Item = Result<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if *this.exceeded {
+            return Poll::Ready(None);
+        }
+        if let Some(quota) = this.quota {
+            if let Err(exceeded) = quota.check() {
+                *this.exceeded = true;
+                return Poll::Ready(Some(Err(StreamError::from(exceeded).into())));
+            }
+        }
+        this.inner.poll_next(cx)
+    }
+}
+
+/// Parses the limits of callers, one caller per line.
+fn parse_caller_limits(content: &str) -> Result<HashMap<String, QuotaLimits>, QuotaError> {
+    let mut caller_limits = HashMap::default();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || QuotaError::InvalidLine(index + 1);
+        let parse_limit = |limit: &str| match limit {
+            "-" => Ok(None),
+            limit => limit.parse().map(Some).map_err(|_| invalid()),
+        };
+        let parts: Vec<_> = line.split_whitespace().collect();
+        match parts.as_slice() {
+            [identity, daily, monthly] => {
+                let limits = QuotaLimits::new(parse_limit(daily)?, parse_limit(monthly)?);
+                caller_limits.insert(identity.to_string(), limits);
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(caller_limits)
+}
+
+/// Resets the counters of periods that ended.
+fn roll_usage(usage: &mut QuotaUsage, now: u64) {
+    let day_start = day_start(now);
+    if usage.day_start != day_start {
+        usage.day_start = day_start;
+        usage.day_units = 0;
+    }
+    let (month_start, _) = month_bounds(now);
+    if usage.month_start != month_start {
+        usage.month_start = month_start;
+        usage.month_units = 0;
+    }
+}
+
+fn check_limits(usage: &QuotaUsage, limits: &QuotaLimits) -> Result<(), QuotaExceeded> {
+    if let Some(limit) = limits.daily {
+        if usage.day_units >= limit {
+            return Err(QuotaExceeded {
+                period: QuotaPeriod::Day,
+                limit,
+                used: usage.day_units,
+                reset_at: usage.day_start + SECONDS_PER_DAY,
+            });
+        }
+    }
+    if let Some(limit) = limits.monthly {
+        if usage.month_units >= limit {
+            let (_, month_end) = month_bounds(usage.month_start);
+            return Err(QuotaExceeded {
+                period: QuotaPeriod::Month,
+                limit,
+                used: usage.month_units,
+                reset_at: month_end,
+            });
+        }
+    }
+    Ok(())
+}
+
+fn day_start(now: u64) -> u64 {
+    now - now % SECONDS_PER_DAY
+}
+
+/// Returns the unix timestamps of the start and end of the UTC month of
+/// `now`.
+fn month_bounds(now: u64) -> (u64, u64) {
+    let date = match NaiveDateTime::from_timestamp_opt(now as i64, 0) {
+        None => return (0, u64::MAX),
+        Some(datetime) => datetime.date(),
+    };
+    let (next_year, next_month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    let timestamp = |year, month| {
+        NaiveDate::from_ymd_opt(year, month, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|date| date.timestamp() as u64)
+            .unwrap_or_default()
+    };
+    (
+        timestamp(date.year(), date.month()),
+        timestamp(next_year, next_month),
+    )
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{
+        month_bounds, parse_caller_limits, MemoryQuotaBackend, QuotaConfig, QuotaExceeded,
+        QuotaLimits, QuotaPeriod, QuotaTracker,
+    };
+
+    // 2023-05-31T23:00:00Z
+    const NOW: u64 = 1_685_574_000;
+
+    #[test]
+    fn test_month_bounds() {
+        // 2023-05-01 and 2023-06-01
+        assert_eq!(month_bounds(NOW), (1_682_899_200, 1_685_577_600));
+        // 2022-12-01 and 2023-01-01
+        assert_eq!(month_bounds(1_671_000_000), (1_669_852_800, 1_672_531_200));
+    }
+
+    #[test]
+    fn test_quota_exceeded() {
+        let config = QuotaConfig::new(QuotaLimits::new(Some(100), Some(120)))
+            .with_caller_limits("unlimited", QuotaLimits::default());
+        let tracker = QuotaTracker::new(config, Arc::new(MemoryQuotaBackend::default()));
+
+        assert!(tracker.check_at("alice", NOW).is_ok());
+        tracker.add_at("alice", 100, NOW);
+        assert_eq!(
+            tracker.check_at("alice", NOW),
+            Err(QuotaExceeded {
+                period: QuotaPeriod::Day,
+                limit: 100,
+                used: 100,
+                reset_at: 1_685_577_600,
+            })
+        );
+
+        // next day, in the next month.
+        let tomorrow = NOW + 3_600;
+        assert!(tracker.check_at("alice", tomorrow).is_ok());
+        tracker.add_at("alice", 50, tomorrow);
+        assert!(tracker.check_at("alice", tomorrow).is_ok());
+
+        // same month.
+        let later = tomorrow + 86_400;
+        tracker.add_at("alice", 99, later);
+        assert_eq!(
+            tracker.check_at("alice", later),
+            Err(QuotaExceeded {
+                period: QuotaPeriod::Month,
+                limit: 120,
+                used: 149,
+                reset_at: 1_688_169_600,
+            })
+        );
+
+        tracker.add_at("unlimited", 1_000, NOW);
+        assert!(tracker.check_at("unlimited", NOW).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_shared_backend() {
+        let backend = Arc::new(MemoryQuotaBackend::default());
+        let config = QuotaConfig::new(QuotaLimits::new(Some(100), None));
+        let first = QuotaTracker::new(config.clone(), backend.clone());
+        let second = QuotaTracker::new(config, backend);
+
+        let first_quota = first.caller("alice");
+        let second_quota = second.caller("alice");
+        first_quota.add(60);
+        second_quota.add(30);
+        assert!(first_quota.check().is_ok());
+        assert!(second_quota.check().is_ok());
+
+        first.flush().await;
+        second.flush().await;
+        // units used before the first node reads back usage.
+        first_quota.add(10);
+        assert!(second_quota.check().is_ok());
+        assert!(first_quota.refresh_and_check().await.is_err());
+    }
+
+    #[test]
+    fn test_caller_limits_file() {
+        let config = QuotaConfig::new(QuotaLimits::default())
+            .with_caller_limits_from_str("# limits\nalice 10 -\n\nbob - 20\n")
+            .unwrap();
+        let tracker = QuotaTracker::new(config, Arc::new(MemoryQuotaBackend::default()));
+        assert_eq!(tracker.limits("alice"), QuotaLimits::new(Some(10), None));
+        assert_eq!(tracker.limits("bob"), QuotaLimits::new(None, Some(20)));
+        assert_eq!(tracker.limits("carol"), QuotaLimits::default());
+
+        assert!(QuotaConfig::new(QuotaLimits::default())
+            .with_caller_limits_from_str("alice 10")
+            .is_err());
+    }
+
+    #[test]
+    fn test_reload_caller_limits() {
+        let config = QuotaConfig::new(QuotaLimits::default())
+            .with_caller_limits("alice", QuotaLimits::new(Some(100), None));
+        let tracker = QuotaTracker::new(config, Arc::new(MemoryQuotaBackend::default()));
+        tracker.add_at("alice", 50, NOW);
+        assert!(tracker.check_at("alice", NOW).is_ok());
+
+        tracker.set_caller_limits(parse_caller_limits("alice 50 -").unwrap());
+        assert!(tracker.check_at("alice", NOW).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+//! Quota usage stored in Redis.
+use redis::{aio::ConnectionManager, Client};
+
+use crate::db::QuotaUsage;
+
+use super::{
+    backend::{QuotaBackend, QuotaBackendError},
+    day_start, month_bounds, SECONDS_PER_DAY,
+};
+
+const DEFAULT_KEY_PREFIX: &str = "apibara:quota";
+
+/// Keeps usage in Redis, shared by all nodes using the same server.
+///
+/// Usage is stored in one counter per caller and period, which expires
+/// after the period ends.
+pub struct RedisQuotaBackend {
+    connection: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisQuotaBackend {
+    /// Connects to the Redis server at `url`.
+    pub async fn connect(url: &str) -> Result<Self, QuotaBackendError> {
+        let client = Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(RedisQuotaBackend {
+            connection,
+            prefix: DEFAULT_KEY_PREFIX.to_string(),
+        })
+    }
+
+    /// Prefix the keys with `prefix`, to share a server with other
+    /// applications.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+#[apibara_node::async_trait]
+impl QuotaBackend for RedisQuotaBackend {
+    async fn add_usage(
+        &self,
+        identity: &str,
+        units: u64,
+        now: u64,
+    ) -> Result<QuotaUsage, QuotaBackendError> {
+        let day_start = day_start(now);
+        let (month_start, month_end) = month_bounds(now);
+        let day_key = format!("{}:{}:day:{}", self.prefix, identity, day_start);
+        let month_key = format!("{}:{}:month:{}", self.prefix, identity, month_start);
+        // keep counters for a day after the period ends, in case of clock
+        // drift between nodes.
+        let day_ttl = 2 * SECONDS_PER_DAY;
+        let month_ttl = month_end.saturating_sub(now) + SECONDS_PER_DAY;
+
+        let (day_units, month_units): (u64, u64) = redis::pipe()
+            .atomic()
+            .incr(&day_key, units)
+            .expire(&day_key, day_ttl as usize)
+            .ignore()
+            .incr(&month_key, units)
+            .expire(&month_key, month_ttl as usize)
+            .ignore()
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        Ok(QuotaUsage {
+            day_start,
+            day_units,
+            month_start,
+            month_units,
+        })
+    }
+}
@@ -0,0 +1,405 @@
+//! Rate limit connections and streams.
+//!
+//! Limits are enforced with token buckets: a client can open up to `burst`
+//! connections or streams at once, then one every `1 / per_second` seconds.
+//! New connections over the limit are closed immediately. Streams over the
+//! limit fail with `RESOURCE_EXHAUSTED` and a `retry-after` metadata with
+//! the number of seconds to wait before trying again.
+//!
+//! Streams are limited both by client IP and by caller identity, so that
+//! callers can't work around their limit by connecting from several
+//! addresses.
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use apibara_core::node::v1alpha2::{StreamErrorCode, StreamErrorDetails};
+use futures::{future::BoxFuture, Stream, StreamExt};
+use serde::Serialize;
+use tonic::{
+    body::BoxBody,
+    codegen::http::{self, Extensions},
+    metadata::MetadataMap,
+    transport::server::{Connected, TcpConnectInfo, TlsConnectInfo},
+    Code, Status,
+};
+use tower::{Layer, Service};
+use tracing::debug;
+
+use super::{auth::CALLER_METADATA_KEY, proxy_protocol::ProxiedConnectInfo};
+
+/// Only requests to these services are rate limited.
+const LIMITED_PATH_PREFIX: &str = "/apibara.";
+
+/// Remove full buckets once there are more than this many.
+const MAX_BUCKETS: usize = 10_000;
+
+/// Allow `per_second` events per second on average, with bursts of up to
+/// `burst` events.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RateLimit {
+    pub per_second: f64,
+    pub burst: u32,
+}
+
+/// Rate limits, disabled if `None`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RateLimitConfig {
+    /// New connections by client IP.
+    pub connections_per_ip: Option<RateLimit>,
+    /// New streams by client IP.
+    pub streams_per_ip: Option<RateLimit>,
+    /// New streams by caller identity.
+    pub streams_per_key: Option<RateLimit>,
+}
+
+/// Shared state of the rate limits.
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    buckets: Arc<Mutex<HashMap<BucketKey, TokenBucket>>>,
+}
+
+/// Layer that rate limits new streams.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: RateLimiter,
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: RateLimiter,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Connection(IpAddr),
+    StreamByIp(IpAddr),
+    StreamByKey(String),
+}
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimit {
+    /// Creates a limit of `per_second` events per second, with bursts of
+    /// one second worth of events.
+    pub fn new(per_second: f64) -> Self {
+        RateLimit {
+            per_second,
+            burst: per_second.ceil().max(1.0) as u32,
+        }
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+impl RateLimitConfig {
+    /// Returns true if any limit is set.
+    pub fn is_enabled(&self) -> bool {
+        self.connections_per_ip.is_some()
+            || self.streams_per_ip.is_some()
+            || self.streams_per_key.is_some()
+    }
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimit, now: Instant) -> Self {
+        TokenBucket {
+            tokens: limit.burst as f64,
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.tokens = f64::min(limit.burst as f64, self.tokens + elapsed * limit.per_second);
+        self.updated_at = now;
+    }
+
+    /// Takes a token, or returns how long until a token is available.
+    fn try_take(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        let wait = (1.0 - self.tokens) / limit.per_second;
+        Err(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX))
+    }
+
+    fn is_full(&self, limit: &RateLimit) -> bool {
+        self.tokens >= limit.burst as f64
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        RateLimiter {
+            config: Arc::new(config),
+            buckets: Arc::default(),
+        }
+    }
+
+    /// Returns true if the client can open a new connection.
+    pub fn allow_connection(&self, ip: IpAddr) -> bool {
+        match self.config.connections_per_ip {
+            None => true,
+            Some(limit) => self
+                .take(BucketKey::Connection(ip), &limit, Instant::now())
+                .is_ok(),
+        }
+    }
+
+    /// Checks if the client can open a new stream, returning how long it
+    /// should wait if not.
+    pub fn check_stream(&self, ip: Option<IpAddr>, key: Option<&str>) -> Result<(), Duration> {
+        let now = Instant::now();
+        if let (Some(limit), Some(key)) = (self.config.streams_per_key, key) {
+            self.take(BucketKey::StreamByKey(key.to_string()), &limit, now)?;
+        }
+        if let (Some(limit), Some(ip)) = (self.config.streams_per_ip, ip) {
+            self.take(BucketKey::StreamByIp(ip), &limit, now)?;
+        }
+        Ok(())
+    }
+
+    fn take(&self, key: BucketKey, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().expect("rate limit buckets lock");
+        if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&key) {
+            self.prune(&mut buckets, now);
+        }
+        buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .try_take(limit, now)
+    }
+
+    /// Removes buckets that are full, they're the same as new buckets.
+    fn prune(&self, buckets: &mut HashMap<BucketKey, TokenBucket>, now: Instant) {
+        buckets.retain(|key, bucket| {
+            let limit = match key {
+                BucketKey::Connection(_) => self.config.connections_per_ip,
+                BucketKey::StreamByIp(_) => self.config.streams_per_ip,
+                BucketKey::StreamByKey(_) => self.config.streams_per_key,
+            };
+            match limit {
+                None => false,
+                Some(limit) => {
+                    bucket.refill(&limit, now);
+                    !bucket.is_full(&limit)
+                }
+            }
+        });
+    }
+}
+
+/// Drops new connections from clients over the connection limit, if any.
+pub fn limit_connections<S, IO, E>(
+    incoming: S,
+    limiter: Option<RateLimiter>,
+) -> impl Stream<Item = Result<IO, E>>
+where
+    S: Stream<Item = Result<IO, E>>,
+    IO: Connected,
+    IO::ConnectInfo: RemoteAddr,
+{
+    incoming.filter(move |connection| {
+        let allowed = match (connection, &limiter) {
+            (Err(_), _) | (_, None) => true,
+            (Ok(io), Some(limiter)) => match io.connect_info().remote_addr() {
+                None => true,
+                Some(addr) => {
+                    let allowed = limiter.allow_connection(addr.ip());
+                    if !allowed {
+                        debug!(addr = %addr, "connection rate limited");
+                    }
+                    allowed
+                }
+            },
+        };
+        futures::future::ready(allowed)
+    })
+}
+
+/// Connection information that includes the client address.
+pub trait RemoteAddr {
+    fn remote_addr(&self) -> Option<SocketAddr>;
+}
+
+impl RemoteAddr for TcpConnectInfo {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        TcpConnectInfo::remote_addr(self)
+    }
+}
+
+impl RemoteAddr for TlsConnectInfo<TcpConnectInfo> {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.get_ref().remote_addr()
+    }
+}
+
+impl RemoteAddr for ProxiedConnectInfo {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(ProxiedConnectInfo::remote_addr(self))
+    }
+}
+
+impl RemoteAddr for TlsConnectInfo<ProxiedConnectInfo> {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.get_ref().remote_addr())
+    }
+}
+
+impl RateLimitLayer {
+    pub fn new(limiter: RateLimiter) -> Self {
+        RateLimitLayer { limiter }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+impl<S, B> Service<http::Request<B>> for RateLimitService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // the service that was polled ready handles the request.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        if request.uri().path().starts_with(LIMITED_PATH_PREFIX) {
+            let ip = remote_ip(request.extensions());
+            let key = request
+                .headers()
+                .get(CALLER_METADATA_KEY)
+                .and_then(|value| value.to_str().ok());
+            if let Err(retry_after) = self.limiter.check_stream(ip, key) {
+                debug!(ip = ?ip, key = ?key, "stream rate limited");
+                let response = rate_limited_status(retry_after).to_http();
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+
+        Box::pin(inner.call(request))
+    }
+}
+
+/// Returns the `RESOURCE_EXHAUSTED` status sent to rate limited clients.
+fn rate_limited_status(retry_after: Duration) -> Status {
+    let seconds = retry_after_seconds(retry_after);
+    let mut metadata = MetadataMap::new();
+    metadata.insert("retry-after", seconds.into());
+    let message = format!("rate limit exceeded, retry after {}s", seconds);
+    StreamErrorDetails::new(StreamErrorCode::RateLimited, true)
+        .with_retry_after(Duration::from_secs(seconds))
+        .into_status(Code::ResourceExhausted, message, metadata)
+}
+
+/// Returns the value of the `retry-after` sent to rate limited clients.
+pub(crate) fn retry_after_seconds(retry_after: Duration) -> u64 {
+    // round up, so that clients waiting for `retry-after` get a token.
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
+/// Returns the client IP from the connection information added by tonic.
+pub(super) fn remote_ip(extensions: &Extensions) -> Option<IpAddr> {
+    remote_addr(extensions).map(|addr| addr.ip())
+}
+
+/// Returns the client address from the connection information added by
+/// tonic.
+fn remote_addr(extensions: &Extensions) -> Option<SocketAddr> {
+    fn get<T: RemoteAddr + Send + Sync + 'static>(extensions: &Extensions) -> Option<SocketAddr> {
+        extensions.get::<T>().and_then(RemoteAddr::remote_addr)
+    }
+    get::<TcpConnectInfo>(extensions)
+        .or_else(|| get::<TlsConnectInfo<TcpConnectInfo>>(extensions))
+        .or_else(|| get::<ProxiedConnectInfo>(extensions))
+        .or_else(|| get::<TlsConnectInfo<ProxiedConnectInfo>>(extensions))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::{Duration, Instant},
+    };
+
+    use super::{RateLimit, RateLimitConfig, RateLimiter, TokenBucket};
+
+    #[test]
+    fn test_token_bucket() {
+        let limit = RateLimit::new(2.0).with_burst(3);
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(&limit, now);
+        for _ in 0..3 {
+            assert!(bucket.try_take(&limit, now).is_ok());
+        }
+        assert_eq!(
+            bucket.try_take(&limit, now),
+            Err(Duration::from_millis(500))
+        );
+
+        let later = now + Duration::from_millis(500);
+        assert!(bucket.try_take(&limit, later).is_ok());
+        assert!(bucket.try_take(&limit, later).is_err());
+
+        // never more than the burst.
+        let much_later = later + Duration::from_secs(60);
+        bucket.refill(&limit, much_later);
+        assert!(bucket.is_full(&limit));
+        assert_eq!(bucket.tokens, 3.0);
+    }
+
+    #[test]
+    fn test_stream_limits() {
+        let config = RateLimitConfig {
+            streams_per_ip: Some(RateLimit::new(0.1).with_burst(2)),
+            streams_per_key: Some(RateLimit::new(0.1).with_burst(1)),
+            ..RateLimitConfig::default()
+        };
+        let limiter = RateLimiter::new(config);
+        let ip = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        assert!(limiter.check_stream(ip, Some("alice")).is_ok());
+        assert!(limiter.check_stream(ip, Some("alice")).is_err());
+        assert!(limiter.check_stream(ip, Some("bob")).is_ok());
+        // the ip used all its tokens.
+        assert!(limiter.check_stream(ip, Some("carol")).is_err());
+        assert!(limiter.check_stream(None, Some("dave")).is_ok());
+
+        // connections are not limited.
+        assert!(limiter.allow_connection(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    }
+}
@@ -0,0 +1,1283 @@
+//! Track the data streams open on the node.
+//!
+//! Streams register themselves when they start and unregister when they are
+//! dropped. Operators use the registry to inspect streams and to terminate
+//! or reposition them. Streams are also terminated when the configuration of
+//! the node is reloaded and they are no longer allowed.
+//!
+//! The registry also limits the number of streams open at the same time, on
+//! the whole node and by each caller. Streams over the limit fail with
+//! `RESOURCE_EXHAUSTED` and the `x-apibara-stream-limit-scope` and
+//! `x-apibara-stream-limit` metadata.
+//!
+//! Streams are closed when they are idle for too long: the client didn't
+//! send a configuration, or didn't read the data sent by the node. Since the
+//! server stops polling streams whose client doesn't read, the data stream is
+//! dropped by the registry directly, freeing the resources it holds.
+//!
+//! When configured, the registry sends the lifecycle of each stream to an
+//! [AuditSink]. It also accumulates the usage of callers, reported to the
+//! billing service.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{self, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use apibara_core::{
+    node::v1alpha2::{
+        stream_data_response, Cursor, StreamDataRequest, StreamDataResponse, StreamErrorCode,
+        StreamErrorDetails, StreamInfo,
+    },
+    starknet::v1alpha2::Filter,
+};
+use apibara_node::{
+    o11y::{self, set_error_context, Counter, KeyValue},
+    server::RequestMeter,
+};
+use futures::Stream;
+use pin_project::pin_project;
+use prost::Message;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    Code, Status,
+};
+use tracing::info;
+
+use super::{
+    audit::{AuditEvent, AuditSink, CloseReason},
+    billing::CallerUsage,
+    extension::ServerExtension,
+};
+
+/// How often idle streams are closed.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shared registry of open streams.
+#[derive(Debug, Clone, Default)]
+pub struct StreamRegistry {
+    limits: StreamLimits,
+    audit: Option<Arc<dyn AuditSink>>,
+    extensions: Vec<Arc<dyn ServerExtension>>,
+    open_streams: OpenStreams,
+    shutdown: CancellationToken,
+    close_metrics: CloseMetrics,
+    state: Arc<Mutex<RegistryState>>,
+}
+
+/// Counts the closed streams by reason.
+#[derive(Clone)]
+struct CloseMetrics {
+    closed: Counter<u64>,
+}
+
+/// Number of open streams, shared with other parts of the node.
+#[derive(Debug, Clone, Default)]
+pub struct OpenStreams(Arc<AtomicUsize>);
+
+/// Limits on the number of streams open at the same time.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StreamLimits {
+    /// Streams open on the node.
+    pub max_streams: Option<usize>,
+    /// Streams open by each authenticated caller.
+    pub max_streams_per_key: Option<usize>,
+    /// Close streams idle for longer than this. Should be longer than the
+    /// interval between heartbeats.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub idle_timeout: Option<Duration>,
+    /// End streams after they sent this many data units. Clients resume
+    /// from the cursor in the error details.
+    pub max_data_units_per_session: Option<u64>,
+}
+
+/// Which limit a new stream exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamLimitScope {
+    Node,
+    Caller,
+}
+
+/// Returned when opening a stream would exceed a limit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamLimitExceeded {
+    pub scope: StreamLimitScope,
+    pub limit: usize,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StreamControlError {
+    #[error("stream {0} not found")]
+    NotFound(u64),
+    #[error("stream {0} is not configured yet")]
+    NotConfigured(u64),
+}
+
+/// Receives the configurations sent by operators to reposition a stream.
+#[derive(Debug)]
+pub struct ConfigurationControl {
+    handle: Arc<StreamHandle>,
+    reposition: mpsc::UnboundedReceiver<StreamDataRequest>,
+}
+
+/// Receives the request to terminate a stream, with the status sent to the
+/// client.
+#[derive(Debug)]
+pub struct ResponseControl {
+    handle: Arc<StreamHandle>,
+    terminate: oneshot::Receiver<Status>,
+}
+
+/// Forwards the client configuration, together with the configurations sent
+/// to reposition the stream.
+#[pin_project]
+pub struct ControlledConfiguration<S> {
+    #[pin]
+    inner: S,
+    control: ConfigurationControl,
+}
+
+/// Counts the data units sent by a stream.
+pub struct RegisteredMeter<M: RequestMeter> {
+    inner: M,
+    handle: Arc<StreamHandle>,
+}
+
+/// Records the data sent to the client and ends the stream when it's
+/// terminated.
+pub struct ControlledStream<S> {
+    inner: Arc<Mutex<Option<Pin<Box<S>>>>>,
+    control: ResponseControl,
+    terminated: bool,
+}
+
+/// Unregisters the stream when dropped.
+#[derive(Debug)]
+struct StreamHandle {
+    id: u64,
+    registry: StreamRegistry,
+}
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    next_id: u64,
+    streams: HashMap<u64, StreamEntry>,
+    /// Usage of the closed streams, not reported yet.
+    closed_usage: HashMap<String, CallerUsage>,
+}
+
+#[derive(Debug)]
+struct StreamEntry {
+    caller: Option<String>,
+    key_hash: Option<String>,
+    started_at: SystemTime,
+    opened_at: Instant,
+    last_sent_at: Instant,
+    configuration: Option<StreamDataRequest>,
+    cursor: Option<Cursor>,
+    messages: u64,
+    bytes: u64,
+    data_units: u64,
+    unbilled: CallerUsage,
+    billed_until: Instant,
+    close_reason: Option<CloseReason>,
+    terminate: Option<oneshot::Sender<Status>>,
+    reposition: mpsc::UnboundedSender<StreamDataRequest>,
+    release: Option<ReleaseStream>,
+}
+
+/// An open stream, as seen by [StreamRegistry::terminate_matching].
+#[derive(Debug)]
+pub struct OpenStream<'a> {
+    /// Identity of the caller, if authenticated.
+    pub caller: Option<&'a str>,
+    /// Hash of the key that opened the stream, see [super::auth::key_hash].
+    pub key_hash: Option<&'a str>,
+    /// Last configuration sent by the client.
+    pub configuration: Option<&'a StreamDataRequest>,
+}
+
+/// Drops the data stream.
+struct ReleaseStream(Box<dyn FnOnce() + Send>);
+
+impl std::fmt::Debug for ReleaseStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReleaseStream").finish_non_exhaustive()
+    }
+}
+
+impl StreamLimits {
+    pub fn is_enabled(&self) -> bool {
+        self.max_streams.is_some()
+            || self.max_streams_per_key.is_some()
+            || self.idle_timeout.is_some()
+            || self.max_data_units_per_session.is_some()
+    }
+}
+
+impl OpenStreams {
+    /// Returns the number of open streams.
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn set(&self, count: usize) {
+        self.0.store(count, Ordering::Relaxed);
+    }
+}
+
+impl StreamLimitExceeded {
+    /// Returns the `RESOURCE_EXHAUSTED` status sent to the client.
+    pub fn to_status(&self) -> Status {
+        let scope = match self.scope {
+            StreamLimitScope::Node => "node",
+            StreamLimitScope::Caller => "caller",
+        };
+        let mut metadata = MetadataMap::new();
+        metadata.insert(
+            "x-apibara-stream-limit-scope",
+            MetadataValue::from_static(scope),
+        );
+        metadata.insert("x-apibara-stream-limit", (self.limit as u64).into());
+        let message = format!(
+            "too many open streams: the {} limit is {} streams",
+            scope, self.limit
+        );
+        StreamErrorDetails::new(StreamErrorCode::StreamLimit, true).into_status(
+            Code::ResourceExhausted,
+            message,
+            metadata,
+        )
+    }
+}
+
+impl StreamRegistry {
+    /// Creates a registry that limits the number of open streams.
+    pub fn new(limits: StreamLimits) -> Self {
+        StreamRegistry {
+            limits,
+            audit: None,
+            extensions: Vec::default(),
+            open_streams: OpenStreams::default(),
+            shutdown: CancellationToken::default(),
+            close_metrics: CloseMetrics::default(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Streams closed after `shutdown` is cancelled are closed because the
+    /// node shut down.
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Send the lifecycle of streams to the given sink.
+    pub fn with_audit(mut self, audit: Option<Arc<dyn AuditSink>>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Keep the given counter up to date with the number of open streams.
+    pub fn with_open_streams(mut self, open_streams: OpenStreams) -> Self {
+        self.open_streams = open_streams;
+        self
+    }
+
+    /// Send the lifecycle of streams to the given extensions.
+    pub fn with_extensions(mut self, extensions: Vec<Arc<dyn ServerExtension>>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Registers a new stream opened by the given caller, failing if the
+    /// node or the caller has too many open streams.
+    pub fn register(
+        &self,
+        caller: Option<String>,
+    ) -> Result<(ConfigurationControl, ResponseControl), StreamLimitExceeded> {
+        self.register_from(caller, None, None)
+    }
+
+    /// Registers a new stream opened by the given caller, with the key whose
+    /// hash is `key_hash`, from the client at `remote_addr`.
+    pub fn register_from(
+        &self,
+        caller: Option<String>,
+        key_hash: Option<String>,
+        remote_addr: Option<SocketAddr>,
+    ) -> Result<(ConfigurationControl, ResponseControl), StreamLimitExceeded> {
+        let mut state = self.state.lock().expect("stream registry lock");
+        if let Some(limit) = self.limits.max_streams {
+            if state.streams.len() >= limit {
+                return Err(StreamLimitExceeded {
+                    scope: StreamLimitScope::Node,
+                    limit,
+                });
+            }
+        }
+        if let (Some(limit), Some(caller)) = (self.limits.max_streams_per_key, &caller) {
+            let open = state
+                .streams
+                .values()
+                .filter(|entry| entry.caller.as_ref() == Some(caller))
+                .count();
+            if open >= limit {
+                return Err(StreamLimitExceeded {
+                    scope: StreamLimitScope::Caller,
+                    limit,
+                });
+            }
+        }
+
+        let (terminate_tx, terminate_rx) = oneshot::channel();
+        let (reposition_tx, reposition_rx) = mpsc::unbounded_channel();
+        let now = Instant::now();
+        let entry = StreamEntry {
+            caller: caller.clone(),
+            key_hash,
+            started_at: SystemTime::now(),
+            opened_at: now,
+            last_sent_at: now,
+            configuration: None,
+            cursor: None,
+            messages: 0,
+            bytes: 0,
+            data_units: 0,
+            unbilled: CallerUsage::default(),
+            billed_until: now,
+            close_reason: None,
+            terminate: Some(terminate_tx),
+            reposition: reposition_tx,
+            release: None,
+        };
+
+        let id = state.next_id;
+        state.next_id += 1;
+        state.streams.insert(id, entry);
+        self.open_streams.set(state.streams.len());
+        drop(state);
+
+        self.record(|| AuditEvent::Opened {
+            stream_id: id,
+            caller,
+            timestamp: unix_timestamp(SystemTime::now()),
+            remote_addr: remote_addr.map(|addr| addr.to_string()),
+        });
+
+        let handle = Arc::new(StreamHandle {
+            id,
+            registry: self.clone(),
+        });
+        let configuration = ConfigurationControl {
+            handle: handle.clone(),
+            reposition: reposition_rx,
+        };
+        let response = ResponseControl {
+            handle,
+            terminate: terminate_rx,
+        };
+        Ok((configuration, response))
+    }
+
+    /// Returns the streams currently open, sorted by id.
+    pub fn list(&self) -> Vec<StreamInfo> {
+        let now = SystemTime::now();
+        let state = self.state.lock().expect("stream registry lock");
+        let mut streams: Vec<_> = state
+            .streams
+            .iter()
+            .map(|(id, entry)| entry.info(*id, now))
+            .collect();
+        streams.sort_by_key(|stream| stream.id);
+        streams
+    }
+
+    /// Terminates the stream with the given id.
+    pub fn terminate(&self, id: u64) -> Result<(), StreamControlError> {
+        let mut state = self.state.lock().expect("stream registry lock");
+        let entry = state
+            .streams
+            .get_mut(&id)
+            .ok_or(StreamControlError::NotFound(id))?;
+        if let Some(terminate) = entry.terminate.take() {
+            let status = StreamErrorDetails::new(StreamErrorCode::Terminated, false).into_status(
+                Code::Aborted,
+                "stream terminated by the node operator",
+                MetadataMap::new(),
+            );
+            let _ = terminate.send(status);
+        }
+        Ok(())
+    }
+
+    /// Terminates the streams for which `reason` returns a status. The
+    /// status is sent to the client.
+    ///
+    /// Returns how many streams were terminated.
+    pub fn terminate_matching(
+        &self,
+        mut reason: impl FnMut(&OpenStream<'_>) -> Option<Status>,
+    ) -> usize {
+        let mut state = self.state.lock().expect("stream registry lock");
+        let mut terminated = 0;
+        for entry in state.streams.values_mut() {
+            if entry.terminate.is_none() {
+                continue;
+            }
+            let stream = OpenStream {
+                caller: entry.caller.as_deref(),
+                key_hash: entry.key_hash.as_deref(),
+                configuration: entry.configuration.as_ref(),
+            };
+            if let Some(status) = reason(&stream) {
+                if let Some(terminate) = entry.terminate.take() {
+                    let _ = terminate.send(status);
+                    terminated += 1;
+                }
+            }
+        }
+        terminated
+    }
+
+    /// Restarts the stream with the given id from the given cursor, keeping
+    /// the rest of its configuration.
+    pub fn reposition(&self, id: u64, cursor: Cursor) -> Result<(), StreamControlError> {
+        let state = self.state.lock().expect("stream registry lock");
+        let entry = state
+            .streams
+            .get(&id)
+            .ok_or(StreamControlError::NotFound(id))?;
+        let mut configuration = entry
+            .configuration
+            .clone()
+            .ok_or(StreamControlError::NotConfigured(id))?;
+        configuration.starting_cursor = Some(cursor);
+        entry
+            .reposition
+            .send(configuration)
+            .map_err(|_| StreamControlError::NotFound(id))
+    }
+
+    /// Closes idle streams until cancelled.
+    pub async fn run(self, ct: CancellationToken) {
+        let idle_timeout = match self.limits.idle_timeout {
+            None => return,
+            Some(idle_timeout) => idle_timeout,
+        };
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(IDLE_CHECK_INTERVAL) => {}
+                _ = ct.cancelled() => return,
+            }
+            let closed = self.close_idle_streams(Instant::now(), idle_timeout);
+            if closed > 0 {
+                info!(closed = %closed, "closed idle streams");
+            }
+        }
+    }
+
+    /// Closes the streams idle for longer than `idle_timeout`, returning how
+    /// many were closed.
+    fn close_idle_streams(&self, now: Instant, idle_timeout: Duration) -> usize {
+        let mut state = self.state.lock().expect("stream registry lock");
+        let idle: Vec<_> = state
+            .streams
+            .iter()
+            .filter(|(_, entry)| entry.is_idle(now, idle_timeout))
+            .map(|(id, _)| *id)
+            .collect();
+        let removed: Vec<_> = idle
+            .iter()
+            .filter_map(|id| Some((*id, state.remove(*id, now)?)))
+            .collect();
+        drop(state);
+
+        // dropping the data stream can access the registry.
+        for (id, mut entry) in removed {
+            if let Some(release) = entry.release.take() {
+                (release.0)();
+            }
+            self.close_metrics.closed(&CloseReason::Idle);
+            self.record(|| entry.closed_event(id, CloseReason::Idle));
+        }
+        idle.len()
+    }
+
+    /// Returns the usage of each caller since the previous call.
+    pub fn take_usage(&self, now: Instant) -> HashMap<String, CallerUsage> {
+        let mut state = self.state.lock().expect("stream registry lock");
+        let mut usage = std::mem::take(&mut state.closed_usage);
+        for entry in state.streams.values_mut() {
+            if let Some(caller) = entry.caller.clone() {
+                let unbilled = entry.take_unbilled(now);
+                usage.entry(caller).or_default().merge(&unbilled);
+            }
+        }
+        usage
+    }
+
+    fn update<T>(&self, id: u64, f: impl FnOnce(&mut StreamEntry) -> T) -> Option<T> {
+        let mut state = self.state.lock().expect("stream registry lock");
+        state.streams.get_mut(&id).map(f)
+    }
+
+    /// Sends the event to the audit sink and the extensions, if any.
+    fn record(&self, event: impl FnOnce() -> AuditEvent) {
+        if self.audit.is_none() && self.extensions.is_empty() {
+            return;
+        }
+        let event = event();
+        if let Some(audit) = &self.audit {
+            audit.record(&event);
+        }
+        for extension in &self.extensions {
+            extension.on_stream_event(&event);
+        }
+    }
+}
+
+impl Default for CloseMetrics {
+    fn default() -> Self {
+        let meter = o11y::meter("stream");
+        let closed = meter
+            .u64_counter("stream_closed")
+            .with_description("Number of closed streams, by reason")
+            .init();
+        CloseMetrics { closed }
+    }
+}
+
+impl CloseMetrics {
+    fn closed(&self, reason: &CloseReason) {
+        let cx = o11y::Context::current();
+        self.closed.add(
+            &cx,
+            1,
+            &[KeyValue::new("reason", reason.label().to_string())],
+        );
+    }
+}
+
+impl std::fmt::Debug for CloseMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CloseMetrics").finish_non_exhaustive()
+    }
+}
+
+impl StreamHandle {
+    fn set_configuration(&self, configuration: &StreamDataRequest) {
+        let filter_hash = filter_hash(&configuration.filter);
+        set_error_context("filter_hash", &filter_hash);
+        let caller = self.registry.update(self.id, |entry| {
+            entry.configuration = Some(configuration.clone());
+            entry.caller.clone()
+        });
+        if let Some(caller) = caller {
+            self.registry.record(|| AuditEvent::Configured {
+                stream_id: self.id,
+                caller,
+                timestamp: unix_timestamp(SystemTime::now()),
+                filter_hash,
+                starting_cursor: configuration
+                    .starting_cursor
+                    .as_ref()
+                    .map(|cursor| cursor.order_key),
+                finality: configuration.finality,
+                batch_size: configuration.batch_size,
+            });
+        }
+    }
+
+    /// Records why the stream is closing, keeping the first reason.
+    fn set_close_reason(&self, reason: CloseReason) {
+        self.registry.update(self.id, |entry| {
+            entry.close_reason.get_or_insert(reason);
+        });
+    }
+
+    fn add_data_units(&self, amount: u64) {
+        self.registry.update(self.id, |entry| {
+            entry.data_units += amount;
+            entry.unbilled.data_units += amount;
+        });
+    }
+
+    /// Returns the cursor to resume from if the stream sent the maximum
+    /// number of data units of a session.
+    fn session_limit_reached(&self, limit: u64) -> Option<Option<Cursor>> {
+        self.registry
+            .update(self.id, |entry| {
+                (entry.data_units >= limit).then(|| entry.cursor.clone())
+            })
+            .flatten()
+    }
+
+    fn set_release(&self, release: ReleaseStream) {
+        self.registry.update(self.id, |entry| {
+            entry.release = Some(release);
+        });
+    }
+
+    fn record_response(&self, response: &StreamDataResponse) {
+        let data = match &response.message {
+            Some(stream_data_response::Message::Data(data)) => Some((
+                data.end_cursor.clone(),
+                data.data.iter().map(|d| d.len() as u64).sum(),
+            )),
+            Some(stream_data_response::Message::Invalidate(invalidate)) => {
+                Some((invalidate.cursor.clone(), 0))
+            }
+            _ => None,
+        };
+        self.registry.update(self.id, |entry| {
+            // heartbeats show that the client is still reading.
+            entry.last_sent_at = Instant::now();
+            if let Some((cursor, bytes)) = data {
+                entry.cursor = cursor;
+                entry.messages += 1;
+                entry.bytes += bytes;
+                entry.unbilled.bytes += bytes;
+            }
+        });
+    }
+}
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        let mut state = self.registry.state.lock().expect("stream registry lock");
+        let entry = state.remove(self.id, Instant::now());
+        self.registry.open_streams.set(state.streams.len());
+        drop(state);
+
+        if let Some(mut entry) = entry {
+            let reason = entry.close_reason.take().unwrap_or_else(|| {
+                if self.registry.shutdown.is_cancelled() {
+                    CloseReason::Shutdown
+                } else {
+                    CloseReason::Disconnected
+                }
+            });
+            self.registry.close_metrics.closed(&reason);
+            self.registry.record(|| entry.closed_event(self.id, reason));
+        }
+    }
+}
+
+impl RegistryState {
+    /// Removes the stream, keeping its usage until it's reported.
+    fn remove(&mut self, id: u64, now: Instant) -> Option<StreamEntry> {
+        let mut entry = self.streams.remove(&id)?;
+        if let Some(caller) = entry.caller.clone() {
+            let unbilled = entry.take_unbilled(now);
+            self.closed_usage
+                .entry(caller)
+                .or_default()
+                .merge(&unbilled);
+        }
+        Some(entry)
+    }
+}
+
+impl StreamEntry {
+    /// Returns the usage since the previous call.
+    fn take_unbilled(&mut self, now: Instant) -> CallerUsage {
+        let mut usage = std::mem::take(&mut self.unbilled);
+        usage.stream_seconds = now
+            .saturating_duration_since(self.billed_until)
+            .as_secs_f64();
+        self.billed_until = now;
+        usage
+    }
+
+    /// Returns true if the client didn't configure the stream, or didn't
+    /// read any message, for `idle_timeout`.
+    fn is_idle(&self, now: Instant, idle_timeout: Duration) -> bool {
+        let not_configured =
+            self.configuration.is_none() && now.duration_since(self.opened_at) >= idle_timeout;
+        not_configured || now.duration_since(self.last_sent_at) >= idle_timeout
+    }
+
+    fn closed_event(&self, id: u64, reason: CloseReason) -> AuditEvent {
+        AuditEvent::Closed {
+            stream_id: id,
+            caller: self.caller.clone(),
+            timestamp: unix_timestamp(SystemTime::now()),
+            reason,
+            cursor: self.cursor.as_ref().map(|cursor| cursor.order_key),
+            messages: self.messages,
+            bytes: self.bytes,
+            data_units: self.data_units,
+            duration_secs: self.opened_at.elapsed().as_secs_f64(),
+        }
+    }
+
+    fn info(&self, id: u64, now: SystemTime) -> StreamInfo {
+        let started_at = unix_timestamp(self.started_at);
+        let elapsed = now
+            .duration_since(self.started_at)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let bytes_per_second = if elapsed > 0.0 {
+            self.bytes as f64 / elapsed
+        } else {
+            0.0
+        };
+        StreamInfo {
+            id,
+            caller: self.caller.clone().unwrap_or_default(),
+            filter_summary: self
+                .configuration
+                .as_ref()
+                .map(|configuration| filter_summary(&configuration.filter))
+                .unwrap_or_default(),
+            cursor: self.cursor.clone(),
+            started_at,
+            messages: self.messages,
+            bytes: self.bytes,
+            bytes_per_second,
+        }
+    }
+}
+
+impl<S> ControlledConfiguration<S> {
+    pub fn new(inner: S, control: ConfigurationControl) -> Self {
+        ControlledConfiguration { inner, control }
+    }
+}
+
+impl<S, E> Stream for ControlledConfiguration<S>
+where
+    S: Stream<Item = Result<StreamDataRequest, E>>,
+{
+    type Item = Result<StreamDataRequest, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let configuration = match this.inner.poll_next(cx) {
+            Poll::Ready(Some(Ok(configuration))) => configuration,
+            Poll::Ready(item) => return Poll::Ready(item),
+            // the channel is never closed while the stream is registered.
+            Poll::Pending => match this.control.reposition.poll_recv(cx) {
+                Poll::Ready(Some(configuration)) => configuration,
+                _ => return Poll::Pending,
+            },
+        };
+        this.control.handle.set_configuration(&configuration);
+        Poll::Ready(Some(Ok(configuration)))
+    }
+}
+
+impl ResponseControl {
+    /// Returns the id of the stream in the registry.
+    pub fn stream_id(&self) -> u64 {
+        self.handle.id
+    }
+
+    /// Wraps the meter of the stream to count the data units it sends.
+    pub fn meter<M: RequestMeter>(&self, inner: M) -> RegisteredMeter<M> {
+        RegisteredMeter {
+            inner,
+            handle: self.handle.clone(),
+        }
+    }
+}
+
+impl<M: RequestMeter> RequestMeter for RegisteredMeter<M> {
+    fn increment_counter(&self, name: &'static str, amount: u64) {
+        self.inner.increment_counter(name, amount);
+        if amount > 0 {
+            self.handle.add_data_units(amount);
+        }
+    }
+}
+
+impl<S> ControlledStream<S>
+where
+    S: Send + 'static,
+{
+    pub fn new(inner: S, control: ResponseControl) -> Self {
+        let inner = Arc::new(Mutex::new(Some(Box::pin(inner))));
+        control.handle.set_release(ReleaseStream(Box::new({
+            let inner = inner.clone();
+            move || {
+                inner.lock().expect("controlled stream lock").take();
+            }
+        })));
+        ControlledStream {
+            inner,
+            control,
+            terminated: false,
+        }
+    }
+}
+
+impl<S> Stream for ControlledStream<S>
+where
+    S: Stream<Item = Result<StreamDataResponse, Status>>,
+{
+    type Item = Result<StreamDataResponse, Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.terminated {
+            return Poll::Ready(None);
+        }
+        if let Poll::Ready(Ok(status)) = Pin::new(&mut this.control.terminate).poll(cx) {
+            this.terminated = true;
+            this.control
+                .handle
+                .set_close_reason(CloseReason::Terminated);
+            return Poll::Ready(Some(Err(status)));
+        }
+        let item = match this.inner.lock().expect("controlled stream lock").as_mut() {
+            Some(inner) => inner.as_mut().poll_next(cx),
+            None => {
+                this.terminated = true;
+                return Poll::Ready(Some(Err(Status::deadline_exceeded(
+                    "stream closed after being idle",
+                ))));
+            }
+        };
+        if let Some(limit) = this
+            .control
+            .handle
+            .registry
+            .limits
+            .max_data_units_per_session
+        {
+            if let Some(cursor) = this.control.handle.session_limit_reached(limit) {
+                this.terminated = true;
+                this.control
+                    .handle
+                    .set_close_reason(CloseReason::SessionLimit);
+                return Poll::Ready(Some(Err(session_limit_status(limit, cursor))));
+            }
+        }
+        match &item {
+            Poll::Ready(Some(Ok(response))) => this.control.handle.record_response(response),
+            Poll::Ready(Some(Err(status))) => {
+                this.control.handle.set_close_reason(CloseReason::Error {
+                    code: error_code(status),
+                    message: status.message().to_string(),
+                })
+            }
+            Poll::Ready(None) => this.control.handle.set_close_reason(CloseReason::Completed),
+            Poll::Pending => {}
+        }
+        item
+    }
+}
+
+/// Returns the code of the error sent to the client, like `quota_exceeded`.
+fn error_code(status: &Status) -> String {
+    StreamErrorDetails::from_status(status)
+        .map(|details| details.code())
+        .filter(|code| *code != StreamErrorCode::Unspecified)
+        .map(|code| {
+            code.as_str_name()
+                .trim_start_matches("STREAM_ERROR_CODE_")
+                .to_lowercase()
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Returns the status of streams that sent the maximum number of data units
+/// of a session.
+fn session_limit_status(limit: u64, cursor: Option<Cursor>) -> Status {
+    let message = match &cursor {
+        Some(cursor) => format!(
+            "stream sent the maximum of {} data units per session, resume from block {}",
+            limit, cursor.order_key
+        ),
+        None => format!(
+            "stream sent the maximum of {} data units per session",
+            limit
+        ),
+    };
+    StreamErrorDetails::new(StreamErrorCode::SessionLimit, true)
+        .with_resume_cursor(cursor)
+        .into_status(Code::ResourceExhausted, message, MetadataMap::new())
+}
+
+fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Returns the hash of the encoded filter.
+///
+/// Equivalent filters have the same hash. Filters that can't be decoded are
+/// hashed as they are.
+fn filter_hash(filter: &[u8]) -> String {
+    match Filter::decode(filter) {
+        Ok(filter) => hex::encode(filter.canonical_hash()),
+        Err(_) => hex::encode(Sha256::digest(filter)),
+    }
+}
+
+/// Returns a short description of the encoded filter.
+fn filter_summary(filter: &[u8]) -> String {
+    let filter = match Filter::decode(filter) {
+        Ok(filter) => filter,
+        Err(_) => return "invalid filter".to_string(),
+    };
+
+    let mut parts = Vec::new();
+    match &filter.header {
+        Some(header) if header.weak => parts.push("weak header".to_string()),
+        Some(_) => parts.push("header".to_string()),
+        None => {}
+    }
+    let counts = [
+        (filter.transactions.len(), "transactions"),
+        (filter.events.len(), "events"),
+        (filter.messages.len(), "messages"),
+        (filter.invocations.len(), "invocations"),
+        (filter.declared_classes.len(), "declared classes"),
+    ];
+    for (count, name) in counts {
+        if count > 0 {
+            parts.push(format!("{count} {name}"));
+        }
+    }
+    if filter.state_update.is_some() {
+        parts.push("state update".to_string());
+    }
+    if filter.data_availability {
+        parts.push("data availability".to_string());
+    }
+
+    if parts.is_empty() {
+        "empty".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::{
+        node::v1alpha2::{
+            stream_data_response, Cursor, Data, StreamDataRequest, StreamDataResponse,
+            StreamErrorCode, StreamErrorDetails,
+        },
+        starknet::v1alpha2::{EventFilter, Filter, HeaderFilter},
+    };
+    use apibara_node::server::{RequestMeter, SimpleMeter};
+    use futures::{stream, FutureExt, StreamExt};
+    use prost::Message;
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use tokio_util::sync::CancellationToken;
+    use tonic::{metadata::MetadataMap, Code, Status};
+
+    use crate::server::audit::{AuditEvent, AuditSink, CloseReason};
+
+    use super::{
+        filter_summary, ControlledConfiguration, ControlledStream, OpenStreams, StreamControlError,
+        StreamLimitScope, StreamLimits, StreamRegistry,
+    };
+
+    #[test]
+    fn test_filter_summary() {
+        let filter = Filter {
+            header: Some(HeaderFilter { weak: true }),
+            events: vec![EventFilter::default(), EventFilter::default()],
+            ..Filter::default()
+        };
+        assert_eq!(
+            filter_summary(&filter.encode_to_vec()),
+            "weak header, 2 events"
+        );
+        assert_eq!(filter_summary(&[]), "empty");
+    }
+
+    #[tokio::test]
+    async fn test_reposition_stream() {
+        let registry = StreamRegistry::default();
+        let (configuration_control, response_control) =
+            registry.register(Some("alice".to_string())).unwrap();
+        let id = registry.list()[0].id;
+
+        assert!(matches!(
+            registry.reposition(id, Cursor::default()),
+            Err(StreamControlError::NotConfigured(_))
+        ));
+
+        let request = StreamDataRequest {
+            batch_size: Some(10),
+            ..StreamDataRequest::default()
+        };
+        let client = stream::iter(vec![Ok::<_, std::io::Error>(request)]).chain(stream::pending());
+        let mut configuration =
+            ControlledConfiguration::new(Box::pin(client), configuration_control);
+        let first = configuration.next().await.unwrap().unwrap();
+        assert_eq!(first.batch_size, Some(10));
+
+        let cursor = Cursor {
+            order_key: 42,
+            unique_key: Vec::default(),
+        };
+        registry.reposition(id, cursor.clone()).unwrap();
+        let second = configuration.next().await.unwrap().unwrap();
+        assert_eq!(second.batch_size, Some(10));
+        assert_eq!(second.starting_cursor, Some(cursor));
+
+        drop(configuration);
+        drop(response_control);
+        assert!(registry.list().is_empty());
+        assert!(matches!(
+            registry.terminate(id),
+            Err(StreamControlError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_stream_limits() {
+        let open_streams = OpenStreams::default();
+        let registry = StreamRegistry::new(StreamLimits {
+            max_streams: Some(3),
+            max_streams_per_key: Some(2),
+            idle_timeout: None,
+            max_data_units_per_session: None,
+        })
+        .with_open_streams(open_streams.clone());
+        let alice = || Some("alice".to_string());
+        let _first = registry.register(alice()).unwrap();
+        let second = registry.register(alice()).unwrap();
+        let err = registry.register(alice()).unwrap_err();
+        assert_eq!(err.scope, StreamLimitScope::Caller);
+        assert_eq!(err.limit, 2);
+
+        let _third = registry.register(None).unwrap();
+        let err = registry.register(Some("bob".to_string())).unwrap_err();
+        assert_eq!(err.scope, StreamLimitScope::Node);
+        assert_eq!(open_streams.count(), 3);
+
+        // closing a stream frees a slot.
+        drop(second);
+        assert_eq!(open_streams.count(), 2);
+        assert!(registry.register(alice()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_close_idle_streams() {
+        let registry = StreamRegistry::default();
+        let timeout = Duration::from_secs(60);
+        let (_configuration_control, response_control) = registry.register(None).unwrap();
+        let data = stream::pending::<Result<StreamDataResponse, Status>>();
+        let mut response = ControlledStream::new(data, response_control);
+
+        let now = Instant::now();
+        assert_eq!(registry.close_idle_streams(now, timeout), 0);
+        // the client never configured the stream.
+        assert_eq!(registry.close_idle_streams(now + timeout, timeout), 1);
+        assert!(registry.list().is_empty());
+
+        let err = response.next().await.unwrap().unwrap_err();
+        assert_eq!(err.code(), Code::DeadlineExceeded);
+        assert!(response.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_limit() {
+        let registry = StreamRegistry::new(StreamLimits {
+            max_data_units_per_session: Some(5),
+            ..StreamLimits::default()
+        });
+        let (_configuration_control, response_control) = registry.register(None).unwrap();
+        let meter = response_control.meter(SimpleMeter::default());
+        let cursor = Cursor {
+            order_key: 42,
+            unique_key: vec![1],
+        };
+        let data = StreamDataResponse {
+            message: Some(stream_data_response::Message::Data(Data {
+                end_cursor: Some(cursor.clone()),
+                ..Data::default()
+            })),
+            ..StreamDataResponse::default()
+        };
+        let data = stream::iter(vec![Ok(data)]).chain(stream::pending());
+        let mut response = ControlledStream::new(data, response_control);
+
+        assert!(response.next().await.unwrap().is_ok());
+        meter.increment_counter("event", 5);
+        let err = response.next().await.unwrap().unwrap_err();
+        assert_eq!(err.code(), Code::ResourceExhausted);
+        let details = StreamErrorDetails::from_status(&err).unwrap();
+        assert_eq!(details.code(), StreamErrorCode::SessionLimit);
+        assert_eq!(details.resume_cursor, Some(cursor));
+        assert!(response.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_terminate_matching() {
+        let registry = StreamRegistry::default();
+        let (_alice_configuration, alice) = registry.register(Some("alice".to_string())).unwrap();
+        let (_bob_configuration, bob) = registry.register(Some("bob".to_string())).unwrap();
+        let mut alice = ControlledStream::new(
+            stream::pending::<Result<StreamDataResponse, Status>>(),
+            alice,
+        );
+        let mut bob =
+            ControlledStream::new(stream::pending::<Result<StreamDataResponse, Status>>(), bob);
+
+        let terminated = registry.terminate_matching(|stream| match stream.caller {
+            Some("alice") => Some(Status::unauthenticated("revoked")),
+            _ => None,
+        });
+        assert_eq!(terminated, 1);
+        let err = alice.next().await.unwrap().unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        assert_eq!(err.message(), "revoked");
+        assert!(bob.next().now_or_never().is_none());
+    }
+
+    #[derive(Debug, Default)]
+    struct MemoryAuditSink {
+        events: Mutex<Vec<AuditEvent>>,
+    }
+
+    impl AuditSink for MemoryAuditSink {
+        fn record(&self, event: &AuditEvent) {
+            self.events.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_stream_lifecycle() {
+        let audit = Arc::new(MemoryAuditSink::default());
+        let registry = StreamRegistry::default().with_audit(Some(audit.clone()));
+        let (configuration_control, response_control) =
+            registry.register(Some("alice".to_string())).unwrap();
+        let id = registry.list()[0].id;
+
+        let request = StreamDataRequest {
+            batch_size: Some(10),
+            filter: Filter::default().encode_to_vec(),
+            ..StreamDataRequest::default()
+        };
+        let client = stream::iter(vec![Ok::<_, std::io::Error>(request)]).chain(stream::pending());
+        let mut configuration =
+            ControlledConfiguration::new(Box::pin(client), configuration_control);
+        configuration.next().await.unwrap().unwrap();
+
+        let meter = response_control.meter(SimpleMeter::default());
+        meter.increment_counter("event", 5);
+        let data = stream::pending::<Result<StreamDataResponse, Status>>();
+        let mut response = ControlledStream::new(data, response_control);
+        registry.terminate(id).unwrap();
+        let err = response.next().await.unwrap().unwrap_err();
+        assert_eq!(err.code(), Code::Aborted);
+
+        drop(configuration);
+        drop(meter);
+        drop(response);
+
+        let events = audit.events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[0], AuditEvent::Opened { stream_id, .. } if *stream_id == id));
+        match &events[1] {
+            AuditEvent::Configured {
+                filter_hash,
+                batch_size,
+                ..
+            } => {
+                assert_eq!(filter_hash.len(), 64);
+                assert_eq!(*batch_size, Some(10));
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+        match &events[2] {
+            AuditEvent::Closed {
+                reason, data_units, ..
+            } => {
+                assert_eq!(*reason, CloseReason::Terminated);
+                assert_eq!(*data_units, 5);
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_close_reasons() {
+        let audit = Arc::new(MemoryAuditSink::default());
+        let shutdown = CancellationToken::new();
+        let registry = StreamRegistry::default()
+            .with_audit(Some(audit.clone()))
+            .with_shutdown(shutdown.clone());
+
+        let (configuration, response_control) = registry.register(None).unwrap();
+        let status = StreamErrorDetails::new(StreamErrorCode::QuotaExceeded, true).into_status(
+            Code::ResourceExhausted,
+            "quota exceeded",
+            MetadataMap::new(),
+        );
+        let data = stream::iter(vec![Err::<StreamDataResponse, _>(status)]);
+        let mut response = ControlledStream::new(data, response_control);
+        response.next().await.unwrap().unwrap_err();
+        drop(configuration);
+        drop(response);
+
+        let (configuration, response_control) = registry.register(None).unwrap();
+        shutdown.cancel();
+        drop(configuration);
+        drop(response_control);
+
+        let events = audit.events.lock().unwrap();
+        let reasons: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                AuditEvent::Closed { reason, .. } => Some(reason.label()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reasons, vec!["quota_exceeded", "shutdown"]);
+    }
+
+    #[test]
+    fn test_take_usage() {
+        let registry = StreamRegistry::default();
+        let (alice_configuration, alice) = registry.register(Some("alice".to_string())).unwrap();
+        let (_anonymous_configuration, anonymous) = registry.register(None).unwrap();
+        alice
+            .meter(SimpleMeter::default())
+            .increment_counter("event", 3);
+        anonymous
+            .meter(SimpleMeter::default())
+            .increment_counter("event", 4);
+
+        let now = Instant::now() + Duration::from_secs(10);
+        let usage = registry.take_usage(now);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage["alice"].data_units, 3);
+        assert!(usage["alice"].stream_seconds >= 10.0);
+
+        // usage of closed streams is kept until reported.
+        alice
+            .meter(SimpleMeter::default())
+            .increment_counter("event", 2);
+        drop(alice_configuration);
+        drop(alice);
+        let usage = registry.take_usage(Instant::now());
+        assert_eq!(usage["alice"].data_units, 2);
+        assert!(registry.take_usage(Instant::now()).is_empty());
+    }
+}
@@ -0,0 +1,379 @@
+//! Reload the configuration of the server while it's running.
+//!
+//! The keys file, the filter restrictions file and the quota limits file are
+//! checked for changes every interval. After a reload, open streams are
+//! evaluated again: streams opened with a key that was revoked, whose filter
+//! is no longer allowed, or whose caller is over its new quota, are
+//! terminated with a status that explains why. Other streams continue
+//! undisturbed.
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use apibara_core::{
+    node::v1alpha2::{DataFinality, StreamDataRequest, StreamErrorCode, StreamErrorDetails},
+    starknet::v1alpha2::Filter,
+};
+use apibara_node::stream::StreamError;
+use prost::Message;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tonic::{metadata::MetadataMap, Code, Status};
+use tracing::{info, warn};
+
+use crate::stream::FilterRestrictions;
+
+use super::{
+    auth::{AuthError, CallerIdentity, StaticKeyValidator, TokenValidator},
+    quota::QuotaTracker,
+    registry::StreamRegistry,
+};
+
+/// Configuration reload.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadConfig {
+    /// How often files are checked for changes.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub interval: Duration,
+    /// Keys reloaded from their file.
+    #[serde(serialize_with = "crate::runtime_config::serialize_is_some")]
+    pub keys: Option<ReloadableKeys>,
+    /// Reload the filter restrictions from this file.
+    pub restrictions_file: Option<PathBuf>,
+    /// Reload the quota limits of callers from this file.
+    pub quota_file: Option<PathBuf>,
+}
+
+/// A [TokenValidator] with keys that are reloaded from a file.
+#[derive(Debug, Clone)]
+pub struct ReloadableKeys {
+    path: PathBuf,
+    current: Arc<RwLock<(Arc<StaticKeyValidator>, Option<SystemTime>)>>,
+}
+
+/// Reloads the configuration and terminates the streams no longer allowed.
+pub struct ConfigReloader {
+    config: ReloadConfig,
+    registry: StreamRegistry,
+    restrictions: Option<FilterRestrictions>,
+    restrictions_modified: Option<SystemTime>,
+    quota: Option<QuotaTracker>,
+    quota_modified: Option<SystemTime>,
+}
+
+impl ReloadableKeys {
+    /// Loads the keys from the file at `path`.
+    pub fn load(path: &Path) -> Result<Self, AuthError> {
+        let validator = StaticKeyValidator::from_file(path)?;
+        let modified = last_modified(path);
+        Ok(ReloadableKeys {
+            path: path.to_path_buf(),
+            current: Arc::new(RwLock::new((Arc::new(validator), modified))),
+        })
+    }
+
+    /// Loads the keys again if the file changed since the last load,
+    /// returning the new keys.
+    ///
+    /// The previous keys are kept if the file is invalid.
+    fn reload_if_changed(&self) -> Option<Arc<StaticKeyValidator>> {
+        let modified = last_modified(&self.path);
+        if modified == self.current.read().expect("keys lock").1 {
+            return None;
+        }
+        match StaticKeyValidator::from_file(&self.path) {
+            Ok(validator) => {
+                info!(path = ?self.path, "reloaded keys");
+                let validator = Arc::new(validator);
+                *self.current.write().expect("keys lock") = (validator.clone(), modified);
+                Some(validator)
+            }
+            Err(err) => {
+                warn!(error = ?err, path = ?self.path, "failed to reload keys");
+                None
+            }
+        }
+    }
+}
+
+#[apibara_node::async_trait]
+impl TokenValidator for ReloadableKeys {
+    async fn validate(&self, token: &str) -> Result<Option<CallerIdentity>, AuthError> {
+        let validator = self.current.read().expect("keys lock").0.clone();
+        validator.validate(token).await
+    }
+}
+
+impl ConfigReloader {
+    pub fn new(
+        config: ReloadConfig,
+        registry: StreamRegistry,
+        restrictions: Option<FilterRestrictions>,
+        quota: Option<QuotaTracker>,
+    ) -> Self {
+        let restrictions_modified = config.restrictions_file.as_deref().and_then(last_modified);
+        let quota_modified = config.quota_file.as_deref().and_then(last_modified);
+        ConfigReloader {
+            config,
+            registry,
+            restrictions,
+            restrictions_modified,
+            quota,
+            quota_modified,
+        }
+    }
+
+    /// Checks for changes every interval until cancelled.
+    pub async fn run(mut self, ct: CancellationToken) {
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(self.config.interval) => {}
+                _ = ct.cancelled() => return,
+            }
+            self.reload();
+        }
+    }
+
+    fn reload(&mut self) {
+        self.reload_keys();
+        self.reload_restrictions();
+        self.reload_quota();
+    }
+
+    fn reload_keys(&self) {
+        if let Some(validator) = self
+            .config
+            .keys
+            .as_ref()
+            .and_then(ReloadableKeys::reload_if_changed)
+        {
+            let terminated = terminate_revoked(&self.registry, &validator);
+            if terminated > 0 {
+                info!(terminated = %terminated, "terminated streams with revoked keys");
+            }
+        }
+    }
+
+    fn reload_restrictions(&mut self) {
+        let (path, restrictions) = match (&self.config.restrictions_file, &self.restrictions) {
+            (Some(path), Some(restrictions)) => (path, restrictions),
+            _ => return,
+        };
+        let modified = last_modified(path);
+        if modified == self.restrictions_modified {
+            return;
+        }
+        match restrictions.reload_from_file(path) {
+            Ok(changed) => {
+                info!(path = ?path, changed = %changed.len(), "reloaded filter restrictions");
+                self.restrictions_modified = modified;
+                let changed = changed.into_iter().collect();
+                let terminated = terminate_restricted(&self.registry, restrictions, &changed);
+                if terminated > 0 {
+                    info!(terminated = %terminated, "terminated streams with restricted filters");
+                }
+            }
+            Err(err) => {
+                warn!(error = ?err, path = ?path, "failed to reload filter restrictions");
+            }
+        }
+    }
+
+    fn reload_quota(&mut self) {
+        let (path, quota) = match (&self.config.quota_file, &self.quota) {
+            (Some(path), Some(quota)) => (path, quota),
+            _ => return,
+        };
+        let modified = last_modified(path);
+        if modified == self.quota_modified {
+            return;
+        }
+        match quota.reload_caller_limits_file(path) {
+            Ok(()) => {
+                info!(path = ?path, "reloaded quota limits");
+                self.quota_modified = modified;
+                let terminated = terminate_over_quota(&self.registry, quota);
+                if terminated > 0 {
+                    info!(terminated = %terminated, "terminated streams over quota");
+                }
+            }
+            Err(err) => {
+                warn!(error = ?err, path = ?path, "failed to reload quota limits");
+            }
+        }
+    }
+}
+
+/// Terminates the streams opened with a key that is no longer valid.
+fn terminate_revoked(registry: &StreamRegistry, validator: &StaticKeyValidator) -> usize {
+    registry.terminate_matching(|stream| {
+        let key_hash = stream.key_hash?;
+        if validator.has_key_hash(key_hash) {
+            return None;
+        }
+        Some(denied(
+            Code::Unauthenticated,
+            "stream terminated because its key was revoked".to_string(),
+        ))
+    })
+}
+
+/// Terminates the streams of callers that used all the data units of their
+/// quota.
+fn terminate_over_quota(registry: &StreamRegistry, quota: &QuotaTracker) -> usize {
+    registry.terminate_matching(|stream| {
+        let exceeded = quota.caller(stream.caller?).check().err()?;
+        Some(StreamError::from(exceeded).into_status())
+    })
+}
+
+/// Terminates the streams of the `changed` callers with a filter that is no
+/// longer allowed.
+fn terminate_restricted(
+    registry: &StreamRegistry,
+    restrictions: &FilterRestrictions,
+    changed: &HashSet<String>,
+) -> usize {
+    registry.terminate_matching(|stream| {
+        let caller = stream.caller.filter(|caller| changed.contains(*caller))?;
+        let restriction = restrictions.caller(Some(caller))?;
+        let (filter, finality) = decode_configuration(stream.configuration?)?;
+        let err = restriction.check_filter(&filter, finality).err()?;
+        Some(denied(
+            Code::PermissionDenied,
+            format!(
+                "stream terminated after the filter restrictions changed: {}",
+                err.into_status().message()
+            ),
+        ))
+    })
+}
+
+/// Returns the status of streams no longer allowed.
+fn denied(code: Code, message: String) -> Status {
+    StreamErrorDetails::new(StreamErrorCode::PermissionDenied, false).into_status(
+        code,
+        message,
+        MetadataMap::new(),
+    )
+}
+
+fn decode_configuration(configuration: &StreamDataRequest) -> Option<(Filter, DataFinality)> {
+    let filter = Filter::decode(configuration.filter.as_slice()).ok()?;
+    let finality = configuration
+        .finality
+        .and_then(DataFinality::from_i32)
+        .unwrap_or(DataFinality::DataStatusAccepted);
+    Some((filter, finality))
+}
+
+fn last_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use apibara_core::{
+        node::v1alpha2::{StreamDataRequest, StreamDataResponse},
+        starknet::v1alpha2::{EventFilter, FieldElement, Filter},
+    };
+    use futures::{stream, FutureExt, StreamExt};
+    use prost::Message;
+    use tonic::{Code, Status};
+
+    use crate::{
+        server::{
+            auth::{hash_key, CallerIdentity, StaticKeyValidator},
+            quota::{MemoryQuotaBackend, QuotaConfig, QuotaLimits, QuotaTracker},
+            registry::{ControlledConfiguration, ControlledStream, StreamRegistry},
+        },
+        stream::{FilterRestriction, FilterRestrictions},
+    };
+
+    use super::{terminate_over_quota, terminate_restricted, terminate_revoked};
+
+    #[tokio::test]
+    async fn test_terminate_streams_after_reload() {
+        let registry = StreamRegistry::default();
+        let (alice_configuration, alice) = registry
+            .register_from(Some("alice".to_string()), Some(hash_key("alice_key")), None)
+            .unwrap();
+        let (_bob_configuration, bob) = registry
+            .register_from(Some("bob".to_string()), Some(hash_key("bob_key")), None)
+            .unwrap();
+        let mut alice = ControlledStream::new(
+            stream::pending::<Result<StreamDataResponse, Status>>(),
+            alice,
+        );
+        let mut bob =
+            ControlledStream::new(stream::pending::<Result<StreamDataResponse, Status>>(), bob);
+
+        let request = StreamDataRequest {
+            filter: Filter {
+                events: vec![EventFilter {
+                    from_address: Some(FieldElement::from_u64(1)),
+                    ..EventFilter::default()
+                }],
+                ..Filter::default()
+            }
+            .encode_to_vec(),
+            ..StreamDataRequest::default()
+        };
+        let client = stream::iter(vec![Ok::<_, std::io::Error>(request)]).chain(stream::pending());
+        let mut alice_configuration =
+            ControlledConfiguration::new(Box::pin(client), alice_configuration);
+        alice_configuration.next().await.unwrap().unwrap();
+
+        // alice can only stream another contract now.
+        let restrictions = FilterRestrictions::default().with_caller(
+            "alice",
+            FilterRestriction {
+                contracts: Some(vec![FieldElement::from_u64(2)]),
+                ..FilterRestriction::default()
+            },
+        );
+        let changed = HashSet::from(["alice".to_string()]);
+        assert_eq!(terminate_restricted(&registry, &restrictions, &changed), 1);
+        let err = alice.next().await.unwrap().unwrap_err();
+        assert_eq!(err.code(), Code::PermissionDenied);
+
+        // bob's key was revoked, even though bob has another key.
+        let validator = StaticKeyValidator::default()
+            .with_key("alice_key", CallerIdentity::new("alice"))
+            .with_key("bob_other_key", CallerIdentity::new("bob"));
+        assert_eq!(terminate_revoked(&registry, &validator), 1);
+        let err = bob.next().await.unwrap().unwrap_err();
+        assert_eq!(err.code(), Code::Unauthenticated);
+        assert!(alice.next().now_or_never().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_terminate_streams_over_quota() {
+        let registry = StreamRegistry::default();
+        let (_alice_configuration, alice) = registry.register(Some("alice".to_string())).unwrap();
+        let (_bob_configuration, bob) = registry.register(Some("bob".to_string())).unwrap();
+        let mut alice = ControlledStream::new(
+            stream::pending::<Result<StreamDataResponse, Status>>(),
+            alice,
+        );
+        let mut bob =
+            ControlledStream::new(stream::pending::<Result<StreamDataResponse, Status>>(), bob);
+
+        let config = QuotaConfig::new(QuotaLimits::new(Some(100), None))
+            .with_caller_limits("alice", QuotaLimits::new(Some(10), None));
+        let quota = QuotaTracker::new(config, Arc::new(MemoryQuotaBackend::default()));
+        quota.caller("alice").add(20);
+        quota.caller("bob").add(20);
+
+        assert_eq!(terminate_over_quota(&registry, &quota), 1);
+        let err = alice.next().await.unwrap().unwrap_err();
+        assert_eq!(err.code(), Code::ResourceExhausted);
+        assert!(bob.next().now_or_never().is_none());
+    }
+}
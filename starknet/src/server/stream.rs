@@ -1,32 +1,76 @@
 //! Implements the node stream service.
 
 use std::{
+    net::SocketAddr,
     pin::Pin,
     sync::Arc,
     task::{self, Poll},
 };
 
-use apibara_core::node::v1alpha2::{stream_server, StreamDataRequest, StreamDataResponse};
+use apibara_core::node::{
+    v1alpha2::{
+        stream_server, StatusRequest, StatusResponse, StreamDataRequest, StreamDataResponse,
+    },
+    v1alpha3,
+};
 use apibara_node::{
+    chain::ChainAdapter,
+    o11y::{set_error_context, ErrorReportingScope},
     server::RequestObserver,
-    stream::{new_data_stream, ResponseStream, StreamConfigurationStream, StreamError},
+    stream::{
+        new_data_stream, BatchSizePolicy, ResponseStream, StreamConfigurationStream, StreamError,
+        V1Alpha3RequestStream, V1Alpha3ResponseStream,
+    },
 };
 use futures::Stream;
 use pin_project::pin_project;
+use serde::Serialize;
 use tonic::{metadata::MetadataMap, Request, Response, Streaming};
+use tracing::info_span;
 use tracing_futures::Instrument;
 
 use crate::{
+    chain::StarkNetChain,
     core::IngestionMessage,
     db::StorageReader,
-    ingestion::IngestionStreamClient,
-    stream::{DbBatchProducer, SequentialCursorProducer},
+    ingestion::{IngestionHealth, IngestionStreamClient},
+    stream::{
+        BatchScheduler, FilterRestrictions, LoadMonitor, SlowBatchConfig, SlowBatchLog,
+        SlowSendStream,
+    },
+};
+
+use super::{
+    auth::{self, CALLER_METADATA_KEY},
+    proxy_protocol::client_addr,
+    quota::{CallerQuota, QuotaLimitedStream, QuotaMeter, QuotaTracker},
+    registry::{ControlledConfiguration, ControlledStream, StreamRegistry},
+    stream_metrics::{MeteredStream, StreamMetrics},
 };
 
-pub struct StreamService<R: StorageReader, O: RequestObserver> {
-    ingestion: Arc<IngestionStreamClient>,
-    storage: Arc<R>,
+/// Size limits of the messages exchanged with clients.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct MessageSizeConfig {
+    /// Split data into messages of at most this many bytes.
+    pub max_response_size: Option<usize>,
+    /// Reject requests larger than this many bytes. Defaults to 4 MiB.
+    pub max_request_size: Option<usize>,
+}
+
+pub struct StreamService<R: StorageReader + Send + Sync + 'static, O: RequestObserver> {
+    chain: StarkNetChain<R>,
     request_observer: O,
+    quota: Option<QuotaTracker>,
+    ingestion_health: Option<IngestionHealth>,
+    registry: StreamRegistry,
+    scheduler: Option<BatchScheduler>,
+    batch_size_policy: BatchSizePolicy,
+    message_size: MessageSizeConfig,
+    restrictions: Option<FilterRestrictions>,
+    load_monitor: Option<LoadMonitor>,
+    stream_metrics: Option<StreamMetrics>,
+    slow_batch: Option<SlowBatchConfig>,
+    chain_id: Vec<u8>,
 }
 
 impl<R, O> StreamService<R, O>
@@ -35,35 +79,247 @@ where
     O: RequestObserver,
 {
     pub fn new(ingestion: Arc<IngestionStreamClient>, storage: R, request_observer: O) -> Self {
-        let storage = Arc::new(storage);
+        let chain = StarkNetChain::new(ingestion, Arc::new(storage));
         StreamService {
-            ingestion,
-            storage,
+            chain,
             request_observer,
+            quota: None,
+            ingestion_health: None,
+            registry: StreamRegistry::default(),
+            scheduler: None,
+            batch_size_policy: BatchSizePolicy::default(),
+            message_size: MessageSizeConfig::default(),
+            restrictions: None,
+            load_monitor: None,
+            stream_metrics: None,
+            slow_batch: None,
+            chain_id: Vec::new(),
+        }
+    }
+
+    /// Enforce the quota of callers.
+    pub fn with_quota(mut self, quota: Option<QuotaTracker>) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    /// Report the ingestion progress in the node status.
+    pub fn with_ingestion_health(mut self, health: Option<IngestionHealth>) -> Self {
+        self.ingestion_health = health;
+        self
+    }
+
+    /// Register streams in the given registry, shared with the admin
+    /// service.
+    pub fn with_registry(mut self, registry: StreamRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Schedule the production of batches by the priority tier of callers.
+    pub fn with_scheduler(mut self, scheduler: Option<BatchScheduler>) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// Clamp the batch size requested by clients with the given policy.
+    pub fn with_batch_size_policy(mut self, policy: BatchSizePolicy) -> Self {
+        self.batch_size_policy = policy;
+        self
+    }
+
+    /// Restrict the filters of callers.
+    pub fn with_restrictions(mut self, restrictions: Option<FilterRestrictions>) -> Self {
+        self.restrictions = restrictions;
+        self
+    }
+
+    /// Refuse new streams and slow down backfills when the node is
+    /// overloaded.
+    pub fn with_load_monitor(mut self, monitor: Option<LoadMonitor>) -> Self {
+        self.load_monitor = monitor;
+        self
+    }
+
+    /// Record the data sent to each stream.
+    pub fn with_stream_metrics(mut self, metrics: Option<StreamMetrics>) -> Self {
+        self.stream_metrics = metrics;
+        self
+    }
+
+    /// Log the batches that are slow to produce or send.
+    pub fn with_slow_batch(mut self, config: Option<SlowBatchConfig>) -> Self {
+        self.slow_batch = config;
+        self
+    }
+
+    /// Limit the size of requests and responses.
+    pub fn with_message_size(mut self, config: MessageSizeConfig) -> Self {
+        self.message_size = config;
+        self
+    }
+
+    /// Set the id of the chain, sent to v1alpha3 clients.
+    pub fn with_chain_id(mut self, chain_id: Vec<u8>) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Returns the v1alpha2 and v1alpha3 services, sharing the same streams.
+    pub fn into_services(
+        self,
+    ) -> (
+        stream_server::StreamServer<Self>,
+        v1alpha3::stream_server::StreamServer<Self>,
+    ) {
+        let message_size = self.message_size;
+        let service = Arc::new(self);
+        let mut v1alpha2_service = stream_server::StreamServer::from_arc(service.clone());
+        let mut v1alpha3_service = v1alpha3::stream_server::StreamServer::from_arc(service);
+        if let Some(max_size) = message_size.max_response_size {
+            v1alpha2_service = v1alpha2_service.max_encoding_message_size(max_size);
+            v1alpha3_service = v1alpha3_service.max_encoding_message_size(max_size);
+        }
+        if let Some(max_size) = message_size.max_request_size {
+            v1alpha2_service = v1alpha2_service.max_decoding_message_size(max_size);
+            v1alpha3_service = v1alpha3_service.max_decoding_message_size(max_size);
         }
+        (v1alpha2_service, v1alpha3_service)
     }
 
-    pub fn into_service(self) -> stream_server::StreamServer<Self> {
-        stream_server::StreamServer::new(self)
+    /// Returns the status of the node, shared by all protocol versions.
+    fn node_status(&self) -> Result<StatusResponse, R::Error> {
+        let current_head = self.chain.storage().highest_accepted_block()?;
+        let last_finalized = self.chain.storage().highest_finalized_block()?;
+        let earliest_available = self.chain.storage().earliest_available_block()?;
+        let progress = self
+            .ingestion_health
+            .as_ref()
+            .map(|health| health.progress().snapshot())
+            .unwrap_or_default();
+
+        Ok(StatusResponse {
+            current_head: current_head.map(|id| id.to_cursor()),
+            last_finalized: last_finalized.map(|id| id.to_cursor()),
+            earliest_available: earliest_available.map(|id| id.to_cursor()),
+            provider_head: progress.provider_head,
+            ingestion_lag: progress.lag(),
+        })
+    }
+
+    /// Returns the quota of the caller, failing if it's already exceeded.
+    ///
+    /// Callers without identity are not subject to quotas.
+    async fn caller_quota(
+        &self,
+        metadata: &MetadataMap,
+    ) -> Result<Option<CallerQuota>, tonic::Status> {
+        let tracker = match &self.quota {
+            None => return Ok(None),
+            Some(tracker) => tracker,
+        };
+        let identity = match metadata
+            .get(CALLER_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+        {
+            None => return Ok(None),
+            Some(identity) => identity,
+        };
+        let quota = tracker.caller(identity);
+        quota
+            .refresh_and_check()
+            .await
+            .map_err(|exceeded| StreamError::from(exceeded).into_status())?;
+        Ok(Some(quota))
     }
 
     async fn stream_data_with_configuration<S, E>(
         &self,
         metadata: MetadataMap,
+        remote_addr: Option<SocketAddr>,
+        quota: Option<CallerQuota>,
         configuration: S,
-    ) -> impl Stream<Item = Result<StreamDataResponse, tonic::Status>>
+    ) -> Result<impl Stream<Item = Result<StreamDataResponse, tonic::Status>>, tonic::Status>
     where
-        S: Stream<Item = Result<StreamDataRequest, E>> + Unpin,
+        S: Stream<Item = Result<StreamDataRequest, E>> + Unpin + Send + 'static,
         E: std::error::Error + Send + Sync + 'static,
     {
+        if let Some(monitor) = &self.load_monitor {
+            if monitor.is_overloaded() {
+                return Err(StreamError::unavailable(
+                    "node is overloaded, retry later or connect to another node".to_string(),
+                    None,
+                )
+                .into_status());
+            }
+        }
+
+        let caller = metadata
+            .get(CALLER_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let scheduler = self
+            .scheduler
+            .as_ref()
+            .map(|scheduler| (scheduler.clone(), scheduler.tier(caller.as_deref())));
+        let restriction = self
+            .restrictions
+            .as_ref()
+            .and_then(|restrictions| restrictions.caller(caller.as_deref()));
+        let (configuration_control, response_control) = self
+            .registry
+            .register_from(caller, auth::key_hash(&metadata), remote_addr)
+            .map_err(|exceeded| exceeded.to_status())?;
+
+        let stream_id = response_control.stream_id();
+        let error_scope = ErrorReportingScope::new();
+        error_scope.run(|| set_error_context("stream_id", stream_id));
         let stream_span = self.request_observer.stream_data_span(&metadata);
+        // fields shared by all logs of the stream, see the json log format.
+        let stream_fields_span = info_span!(
+            parent: &stream_span,
+            "stream",
+            stream_id,
+            key_hash = auth::key_hash(&metadata).as_deref()
+        );
+        let stream_metrics = self.stream_metrics.as_ref().map(|metrics| {
+            let progress = self
+                .ingestion_health
+                .as_ref()
+                .map(|health| health.progress().clone());
+            metrics.stream(&metadata, progress)
+        });
         let stream_meter = self.request_observer.stream_data_meter(&metadata);
+        let stream_meter = QuotaMeter::new(stream_meter, quota.clone());
+        let stream_meter = response_control.meter(stream_meter);
 
-        let configuration_stream = StreamConfigurationStream::new(configuration);
-        let ingestion_stream = self.ingestion.subscribe().await;
-        let ingestion_stream = IngestionStream::new(ingestion_stream);
-        let batch_producer = DbBatchProducer::new(self.storage.clone());
-        let cursor_producer = SequentialCursorProducer::new(self.storage.clone());
+        let configuration = ControlledConfiguration::new(configuration, configuration_control);
+        let batch_size_policy = BatchSizePolicy {
+            max_message_size: self.message_size.max_response_size,
+            ..self.batch_size_policy
+        };
+        let configuration_stream =
+            StreamConfigurationStream::new(configuration).with_batch_size_policy(batch_size_policy);
+        let ingestion_stream = self.chain.subscribe().await;
+        let mut batch_producer = self.chain.batch_producer();
+        let tier = scheduler
+            .as_ref()
+            .map(|(_, tier)| *tier)
+            .unwrap_or_default();
+        if let Some((scheduler, tier)) = scheduler {
+            batch_producer = batch_producer.with_scheduler(scheduler, tier);
+        }
+        if let Some(monitor) = &self.load_monitor {
+            batch_producer = batch_producer.with_load_monitor(monitor.clone(), tier);
+        }
+        if let Some(restriction) = restriction {
+            batch_producer = batch_producer.with_restriction(restriction);
+        }
+        let slow_batch_log = self.slow_batch.clone().map(SlowBatchLog::new);
+        if let Some(log) = &slow_batch_log {
+            batch_producer = batch_producer.with_slow_batch_log(log.clone());
+        }
+        let cursor_producer = self.chain.cursor_producer();
 
         let data_stream = new_data_stream(
             configuration_stream,
@@ -73,7 +329,13 @@ where
             stream_meter,
         );
 
-        ResponseStream::new(data_stream).instrument(stream_span)
+        let response = QuotaLimitedStream::new(ResponseStream::new(data_stream), quota);
+        let response = ControlledStream::new(response, response_control);
+        let response = SlowSendStream::new(response, slow_batch_log);
+        let response = MeteredStream::new(response, stream_metrics);
+        Ok(ErrorScopedStream::new(response, error_scope)
+            .instrument(stream_fields_span)
+            .instrument(stream_span))
     }
 }
 
@@ -94,9 +356,11 @@ where
         request: Request<Streaming<StreamDataRequest>>,
     ) -> Result<Response<Self::StreamDataStream>, tonic::Status> {
         let metadata = request.metadata().clone();
+        let remote_addr = client_addr(&request);
+        let quota = self.caller_quota(&metadata).await?;
         let response = self
-            .stream_data_with_configuration(metadata, request.into_inner())
-            .await;
+            .stream_data_with_configuration(metadata, remote_addr, quota, request.into_inner())
+            .await?;
         Ok(Response::new(Box::pin(response)))
     }
 
@@ -105,14 +369,101 @@ where
         request: Request<StreamDataRequest>,
     ) -> Result<Response<Self::StreamDataImmutableStream>, tonic::Status> {
         let metadata = request.metadata().clone();
+        let remote_addr = client_addr(&request);
+        let quota = self.caller_quota(&metadata).await?;
         let configuration_stream = ImmutableRequestStream {
             request: Some(request.into_inner()),
         };
         let response = self
-            .stream_data_with_configuration(metadata, configuration_stream)
-            .await;
+            .stream_data_with_configuration(metadata, remote_addr, quota, configuration_stream)
+            .await?;
+        Ok(Response::new(Box::pin(response)))
+    }
+
+    async fn status(
+        &self,
+        _request: Request<StatusRequest>,
+    ) -> Result<Response<StatusResponse>, tonic::Status> {
+        let response = self.node_status().map_err(internal_error)?;
+        Ok(Response::new(response))
+    }
+}
+
+#[tonic::async_trait]
+impl<R, O> v1alpha3::stream_server::Stream for StreamService<R, O>
+where
+    R: StorageReader + Send + Sync + 'static,
+    O: RequestObserver,
+{
+    type StreamDataStream = Pin<
+        Box<
+            dyn Stream<Item = Result<v1alpha3::StreamDataResponse, tonic::Status>> + Send + 'static,
+        >,
+    >;
+
+    type StreamDataImmutableStream = Pin<
+        Box<
+            dyn Stream<Item = Result<v1alpha3::StreamDataResponse, tonic::Status>> + Send + 'static,
+        >,
+    >;
+
+    async fn stream_data(
+        &self,
+        request: Request<Streaming<v1alpha3::StreamDataRequest>>,
+    ) -> Result<Response<Self::StreamDataStream>, tonic::Status> {
+        let metadata = request.metadata().clone();
+        let remote_addr = client_addr(&request);
+        let quota = self.caller_quota(&metadata).await?;
+        let configuration_stream =
+            V1Alpha3RequestStream::new(request.into_inner(), self.chain_id.clone());
+        let response = self
+            .stream_data_with_configuration(metadata, remote_addr, quota, configuration_stream)
+            .await?;
+        let response = V1Alpha3ResponseStream::new(
+            response,
+            self.chain.block_descriptor(),
+            self.chain_id.clone(),
+        );
+        Ok(Response::new(Box::pin(response)))
+    }
+
+    async fn stream_data_immutable(
+        &self,
+        request: Request<v1alpha3::StreamDataRequest>,
+    ) -> Result<Response<Self::StreamDataImmutableStream>, tonic::Status> {
+        let metadata = request.metadata().clone();
+        let remote_addr = client_addr(&request);
+        let request = request
+            .into_inner()
+            .to_v1alpha2(&self.chain_id)
+            .map_err(|err| StreamError::invalid_request(err.to_string()).into_status())?;
+        let quota = self.caller_quota(&metadata).await?;
+        let configuration_stream = ImmutableRequestStream {
+            request: Some(request),
+        };
+        let response = self
+            .stream_data_with_configuration(metadata, remote_addr, quota, configuration_stream)
+            .await?;
+        let response = V1Alpha3ResponseStream::new(
+            response,
+            self.chain.block_descriptor(),
+            self.chain_id.clone(),
+        );
         Ok(Response::new(Box::pin(response)))
     }
+
+    async fn status(
+        &self,
+        _request: Request<v1alpha3::StatusRequest>,
+    ) -> Result<Response<v1alpha3::StatusResponse>, tonic::Status> {
+        let status = self.node_status().map_err(internal_error)?;
+        let response = v1alpha3::StatusResponse::from_v1alpha2(status, &self.chain_id);
+        Ok(Response::new(response))
+    }
+}
+
+fn internal_error<E: std::error::Error>(err: E) -> tonic::Status {
+    tonic::Status::internal(err.to_string())
 }
 
 /// A stream that yields the configuration once, and is pending forever after that.
@@ -178,3 +529,32 @@ where
         self.inner.size_hint()
     }
 }
+
+/// A stream polled in its own error reporting scope, so that errors are
+/// reported with the context of the stream.
+#[pin_project]
+pub struct ErrorScopedStream<S> {
+    #[pin]
+    inner: S,
+    scope: ErrorReportingScope,
+}
+
+impl<S> ErrorScopedStream<S> {
+    pub fn new(inner: S, scope: ErrorReportingScope) -> Self {
+        ErrorScopedStream { inner, scope }
+    }
+}
+
+impl<S: Stream> Stream for ErrorScopedStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let inner = this.inner;
+        this.scope.run(|| inner.poll_next(cx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,267 @@
+//! Metrics of the data sent to each stream.
+//!
+//! Metrics are labeled with the caller, the stream name, and the finality
+//! of the data so that dashboards can show each consumer. The number of
+//! distinct label sets is capped, and streams over the cap are reported
+//! under the `other` label.
+use std::{
+    collections::HashSet,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{self, Poll},
+    time::Instant,
+};
+
+use apibara_core::node::v1alpha2::{stream_data_response, DataFinality, StreamDataResponse};
+use apibara_node::{
+    o11y::{self, Counter, Histogram, KeyValue},
+    server::stream_name,
+};
+use futures::Stream;
+use pin_project::pin_project;
+use serde::Serialize;
+use tonic::metadata::MetadataMap;
+
+use crate::ingestion::IngestionProgress;
+
+use super::auth::CALLER_METADATA_KEY;
+
+/// Label value of the streams over the label sets cap.
+const OTHER_LABEL: &str = "other";
+
+/// Configuration of the per-stream metrics.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamMetricsConfig {
+    /// Label metrics with the identity of the caller.
+    pub label_callers: bool,
+    /// Maximum number of distinct caller and stream name pairs.
+    pub max_label_sets: usize,
+}
+
+/// Records the data sent to streams.
+#[derive(Clone)]
+pub struct StreamMetrics {
+    inner: Arc<StreamMetricsInner>,
+}
+
+struct StreamMetricsInner {
+    config: StreamMetricsConfig,
+    label_sets: Mutex<HashSet<(Option<String>, Option<String>)>>,
+    blocks: Counter<u64>,
+    bytes: Counter<u64>,
+    lag: Histogram<u64>,
+    batch_latency: Histogram<f64>,
+}
+
+/// Records the data sent to one stream.
+pub struct StreamMeter {
+    metrics: StreamMetrics,
+    attributes: Vec<KeyValue>,
+    progress: Option<IngestionProgress>,
+}
+
+/// A stream that records the data messages it yields.
+#[pin_project]
+pub struct MeteredStream<S> {
+    #[pin]
+    inner: S,
+    meter: Option<StreamMeter>,
+    batch_started_at: Option<Instant>,
+}
+
+impl Default for StreamMetricsConfig {
+    fn default() -> Self {
+        StreamMetricsConfig {
+            label_callers: true,
+            max_label_sets: 100,
+        }
+    }
+}
+
+impl StreamMetrics {
+    pub fn new(config: StreamMetricsConfig) -> Self {
+        let meter = o11y::meter("stream");
+        let blocks = meter
+            .u64_counter("stream_blocks_sent")
+            .with_description("Number of blocks sent to streams")
+            .init();
+        let bytes = meter
+            .u64_counter("stream_bytes_sent")
+            .with_description("Size of the data sent to streams, in bytes")
+            .init();
+        let lag = meter
+            .u64_histogram("stream_lag")
+            .with_description("Number of blocks between the node head and the data sent")
+            .init();
+        let batch_latency = meter
+            .f64_histogram("stream_batch_latency")
+            .with_description("Time to produce a data message, in seconds")
+            .init();
+        let inner = StreamMetricsInner {
+            config,
+            label_sets: Mutex::default(),
+            blocks,
+            bytes,
+            lag,
+            batch_latency,
+        };
+        StreamMetrics {
+            inner: Arc::new(inner),
+        }
+    }
+
+    /// Returns a meter for the stream with the given request metadata.
+    ///
+    /// The lag is recorded only if `progress` is given.
+    pub fn stream(
+        &self,
+        metadata: &MetadataMap,
+        progress: Option<IngestionProgress>,
+    ) -> StreamMeter {
+        let caller = if self.inner.config.label_callers {
+            metadata
+                .get(CALLER_METADATA_KEY)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        } else {
+            None
+        };
+        let (caller, name) = self.label_set(caller, stream_name(metadata));
+        let mut attributes = Vec::with_capacity(2);
+        if let Some(caller) = caller {
+            attributes.push(KeyValue::new("caller", caller));
+        }
+        if let Some(name) = name {
+            attributes.push(KeyValue::new("stream_name", name));
+        }
+        StreamMeter {
+            metrics: self.clone(),
+            attributes,
+            progress,
+        }
+    }
+
+    /// Returns the labels of the stream, or the `other` labels if the label
+    /// sets cap is reached.
+    fn label_set(
+        &self,
+        caller: Option<String>,
+        name: Option<String>,
+    ) -> (Option<String>, Option<String>) {
+        if caller.is_none() && name.is_none() {
+            return (None, None);
+        }
+        let key = (caller, name);
+        let mut label_sets = self.inner.label_sets.lock().expect("label sets lock");
+        if label_sets.contains(&key) {
+            return key;
+        }
+        if label_sets.len() >= self.inner.config.max_label_sets {
+            let other = || Some(OTHER_LABEL.to_string());
+            return (key.0.and(other()), key.1.and(other()));
+        }
+        label_sets.insert(key.clone());
+        key
+    }
+}
+
+impl StreamMeter {
+    /// Records a message sent to the stream, that took `latency` to produce.
+    pub fn record(&self, response: &StreamDataResponse, latency: std::time::Duration) {
+        let data = match &response.message {
+            Some(stream_data_response::Message::Data(data)) => data,
+            _ => return,
+        };
+        let finality = match DataFinality::from_i32(data.finality) {
+            Some(DataFinality::DataStatusFinalized) => "finalized",
+            Some(DataFinality::DataStatusAccepted) => "accepted",
+            Some(DataFinality::DataStatusPending) => "pending",
+            _ => "unknown",
+        };
+        let attributes = &[
+            self.attributes.as_slice(),
+            &[KeyValue::new("finality", finality)],
+        ]
+        .concat();
+
+        let cx = o11y::Context::current();
+        let metrics = &self.metrics.inner;
+        let bytes: usize = data.data.iter().map(Vec::len).sum();
+        metrics.blocks.add(&cx, data.data.len() as u64, attributes);
+        metrics.bytes.add(&cx, bytes as u64, attributes);
+        metrics
+            .batch_latency
+            .record(&cx, latency.as_secs_f64(), attributes);
+
+        let head = self
+            .progress
+            .as_ref()
+            .and_then(|progress| progress.snapshot().node_head);
+        let end = data.end_cursor.as_ref().map(|cursor| cursor.order_key);
+        if let (Some(head), Some(end)) = (head, end) {
+            metrics
+                .lag
+                .record(&cx, head.saturating_sub(end), attributes);
+        }
+    }
+}
+
+impl<S> MeteredStream<S> {
+    /// Records the messages of `inner` with `meter`, if any.
+    pub fn new(inner: S, meter: Option<StreamMeter>) -> Self {
+        MeteredStream {
+            inner,
+            meter,
+            batch_started_at: None,
+        }
+    }
+}
+
+impl<S, E> Stream for MeteredStream<S>
+where
+    S: Stream<Item = Result<StreamDataResponse, E>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let meter = match this.meter {
+            None => return this.inner.poll_next(cx),
+            Some(meter) => meter,
+        };
+        // the batch starts when the client asks for the next message, so that
+        // the time the client takes to receive messages is not counted.
+        let started_at = *this.batch_started_at.get_or_insert_with(Instant::now);
+        match this.inner.poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(item) => {
+                *this.batch_started_at = None;
+                if let Some(Ok(response)) = &item {
+                    meter.record(response, started_at.elapsed());
+                }
+                Poll::Ready(item)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StreamMetrics, StreamMetricsConfig};
+
+    #[test]
+    fn test_label_sets_cap() {
+        let metrics = StreamMetrics::new(StreamMetricsConfig {
+            label_callers: true,
+            max_label_sets: 1,
+        });
+        let alice = (Some("alice".to_string()), Some("indexer".to_string()));
+        let bob = (Some("bob".to_string()), None);
+        let other = (Some("other".to_string()), None);
+
+        assert_eq!(metrics.label_set(alice.0.clone(), alice.1.clone()), alice);
+        assert_eq!(metrics.label_set(bob.0.clone(), bob.1.clone()), other);
+        assert_eq!(metrics.label_set(alice.0.clone(), alice.1.clone()), alice);
+        assert_eq!(metrics.label_set(None, None), (None, None));
+    }
+}
@@ -0,0 +1,112 @@
+//! Implements the block sync service, used by other nodes to sync from this node.
+
+use std::{pin::Pin, sync::Arc};
+
+use apibara_core::starknet::v1alpha2::{
+    block_sync_server, GetSyncStatusRequest, GetSyncStatusResponse, RawBlock, StreamBlocksRequest,
+};
+use futures::{stream, Stream};
+use prost::Message;
+use tonic::{Request, Response, Status};
+use tracing::debug;
+
+use crate::db::{read_raw_block, StorageReader};
+
+/// Maximum number of blocks streamed in a single request.
+const MAX_BLOCKS_PER_REQUEST: u64 = 1_000;
+
+/// Stop streaming blocks once a request sent this many bytes.
+///
+/// Clients send a new request to continue from the last block.
+const MAX_BYTES_PER_REQUEST: usize = 256 * 1024 * 1024;
+
+pub struct BlockSyncService<R: StorageReader> {
+    storage: Arc<R>,
+}
+
+impl<R> BlockSyncService<R>
+where
+    R: StorageReader + Send + Sync + 'static,
+{
+    pub fn new(storage: R) -> Self {
+        let storage = Arc::new(storage);
+        BlockSyncService { storage }
+    }
+
+    pub fn into_service(self) -> block_sync_server::BlockSyncServer<Self> {
+        block_sync_server::BlockSyncServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl<R> block_sync_server::BlockSync for BlockSyncService<R>
+where
+    R: StorageReader + Send + Sync + 'static,
+{
+    type StreamBlocksStream =
+        Pin<Box<dyn Stream<Item = Result<RawBlock, Status>> + Send + 'static>>;
+
+    async fn get_sync_status(
+        &self,
+        _request: Request<GetSyncStatusRequest>,
+    ) -> Result<Response<GetSyncStatusResponse>, Status> {
+        let finalized = match self.storage.highest_finalized_block().map_err(internal)? {
+            None => None,
+            Some(id) => self.storage.read_header(&id).map_err(internal)?,
+        };
+        let accepted = match self.storage.highest_accepted_block().map_err(internal)? {
+            None => None,
+            Some(id) => self.storage.read_header(&id).map_err(internal)?,
+        };
+        let earliest = match self.storage.earliest_available_block().map_err(internal)? {
+            None => None,
+            Some(id) => self.storage.read_header(&id).map_err(internal)?,
+        };
+        Ok(Response::new(GetSyncStatusResponse {
+            finalized,
+            accepted,
+            earliest,
+        }))
+    }
+
+    async fn stream_blocks(
+        &self,
+        request: Request<StreamBlocksRequest>,
+    ) -> Result<Response<Self::StreamBlocksStream>, Status> {
+        let request = request.into_inner();
+        let start = request.start_block_number;
+        let max_end = start.saturating_add(MAX_BLOCKS_PER_REQUEST);
+        let end = request
+            .end_block_number
+            .map(|end| u64::min(end, max_end))
+            .unwrap_or(max_end);
+
+        debug!(start = %start, end = %end, "stream blocks");
+
+        // stop at the first block not in the canonical chain.
+        let storage = self.storage.clone();
+        let blocks = stream::unfold(Some((start, 0)), move |state| {
+            let storage = storage.clone();
+            async move {
+                let (number, sent) = state?;
+                if number >= end || sent >= MAX_BYTES_PER_REQUEST {
+                    return None;
+                }
+                match read_raw_block(storage.as_ref(), number).map_err(internal) {
+                    Ok(None) => None,
+                    Ok(Some(block)) => {
+                        let sent = sent + block.encoded_len();
+                        Some((Ok(block), Some((number + 1, sent))))
+                    }
+                    Err(err) => Some((Err(err), None)),
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(blocks)))
+    }
+}
+
+fn internal<E: std::error::Error>(err: E) -> Status {
+    Status::internal(err.to_string())
+}
@@ -0,0 +1,247 @@
+//! TLS termination for the gRPC server.
+//!
+//! Certificates are read from PEM files. When reloading is enabled, the
+//! files are checked periodically and new connections use the most recent
+//! certificate, so certificates are renewed without restarting the node.
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::{self, CertifiedKey},
+    Certificate, PrivateKey, ServerConfig,
+};
+use serde::Serialize;
+use tokio::{net::TcpListener, sync::mpsc};
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+use super::{ip_filter::IpFilter, proxy_protocol::ProxiedStream};
+
+/// Maximum number of established connections waiting to be served.
+const CONNECTION_BUFFER_SIZE: usize = 128;
+
+/// Close connections that don't complete the handshake within this time.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("failed to bind {addr}")]
+    Bind { addr: SocketAddr, source: io::Error },
+    #[error("failed to read {path:?}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("no certificate in {0:?}")]
+    MissingCertificate(PathBuf),
+    #[error("no private key in {0:?}")]
+    MissingPrivateKey(PathBuf),
+    #[error("unsupported private key in {0:?}")]
+    UnsupportedPrivateKey(PathBuf),
+}
+
+/// TLS configuration of the gRPC server.
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsConfig {
+    /// Path to the PEM encoded certificate chain.
+    pub cert_path: PathBuf,
+    /// Path to the PEM encoded private key.
+    pub key_path: PathBuf,
+    /// Check the files for changes at this interval. Never if `None`.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub reload_interval: Option<Duration>,
+}
+
+/// Resolves the server certificate to the last one loaded from disk.
+struct ReloadableCertificate {
+    config: TlsConfig,
+    current: RwLock<(Arc<CertifiedKey>, Option<SystemTime>)>,
+}
+
+impl TlsConfig {
+    pub fn new(cert_path: PathBuf, key_path: PathBuf) -> Self {
+        TlsConfig {
+            cert_path,
+            key_path,
+            reload_interval: None,
+        }
+    }
+
+    /// Reload the certificate when the files change, checking at the given
+    /// interval.
+    pub fn with_reload_interval(mut self, interval: Duration) -> Self {
+        self.reload_interval = Some(interval);
+        self
+    }
+}
+
+impl ReloadableCertificate {
+    fn load(config: TlsConfig) -> Result<Self, TlsError> {
+        let key = load_certified_key(&config)?;
+        let modified = last_modified(&config);
+        Ok(ReloadableCertificate {
+            config,
+            current: RwLock::new((Arc::new(key), modified)),
+        })
+    }
+
+    /// Loads the certificate again if the files changed since the last load.
+    ///
+    /// The previous certificate is kept if the new one is invalid, for
+    /// example if only one of the two files was written yet.
+    fn reload_if_changed(&self) {
+        let modified = last_modified(&self.config);
+        if modified == self.current.read().expect("tls certificate lock").1 {
+            return;
+        }
+        match load_certified_key(&self.config) {
+            Ok(key) => {
+                info!(cert_path = ?self.config.cert_path, "reloaded tls certificate");
+                *self.current.write().expect("tls certificate lock") = (Arc::new(key), modified);
+            }
+            Err(err) => warn!(error = ?err, "failed to reload tls certificate"),
+        }
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertificate {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().expect("tls certificate lock").0.clone())
+    }
+}
+
+/// Accepts TLS connections on `addr`, until `ct` is cancelled.
+///
+/// Handshakes happen in their own task, so that slow clients don't delay
+/// other connections, and are aborted after [HANDSHAKE_TIMEOUT]. With
+/// `trusted_proxies`, connections from these proxies start with a PROXY
+/// protocol header before the handshake. With `accept_http1`, HTTP/1.1 is negotiated for clients
+/// that don't support HTTP/2, such as grpc-web clients.
+pub async fn tls_incoming(
+    addr: SocketAddr,
+    config: TlsConfig,
+    trusted_proxies: Option<IpFilter>,
+    accept_http1: bool,
+    ct: CancellationToken,
+) -> Result<ReceiverStream<Result<TlsStream<ProxiedStream>, io::Error>>, TlsError> {
+    let reload_interval = config.reload_interval;
+    let certificate = Arc::new(ReloadableCertificate::load(config)?);
+
+    let mut server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(certificate.clone());
+    server_config.alpn_protocols = vec![b"h2".to_vec()];
+    if accept_http1 {
+        server_config.alpn_protocols.push(b"http/1.1".to_vec());
+    }
+    let acceptor = TlsAcceptor::from(Arc::new(server_config));
+
+    if let Some(interval) = reload_interval {
+        tokio::spawn({
+            let ct = ct.clone();
+            async move {
+                loop {
+                    tokio::select! {
+                        _ = ct.cancelled() => return,
+                        _ = tokio::time::sleep(interval) => {},
+                    }
+                    certificate.reload_if_changed();
+                }
+            }
+        });
+    }
+
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|source| TlsError::Bind { addr, source })?;
+    let (tx, rx) = mpsc::channel(CONNECTION_BUFFER_SIZE);
+    tokio::spawn(async move {
+        loop {
+            let accepted = tokio::select! {
+                _ = ct.cancelled() => return,
+                accepted = listener.accept() => accepted,
+            };
+            let (stream, peer) = match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!(error = ?err, "failed to accept connection");
+                    continue;
+                }
+            };
+            let acceptor = acceptor.clone();
+            let tx = tx.clone();
+            let trusted_proxies = trusted_proxies.clone();
+            tokio::spawn(async move {
+                let stream = if let Some(trusted_proxies) = trusted_proxies {
+                    match ProxiedStream::accept_from(stream, peer, &trusted_proxies).await {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            debug!(peer = %peer, error = ?err, "proxy protocol failed");
+                            return;
+                        }
+                    }
+                } else {
+                    ProxiedStream::direct(stream, peer)
+                };
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = tx.send(Ok(stream)).await;
+                    }
+                    Ok(Err(err)) => debug!(peer = %peer, error = ?err, "tls handshake failed"),
+                    Err(_) => debug!(peer = %peer, "tls handshake timed out"),
+                }
+            });
+        }
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
+
+fn load_certified_key(config: &TlsConfig) -> Result<CertifiedKey, TlsError> {
+    let certs: Vec<_> = read_pem(&config.cert_path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            rustls_pemfile::Item::X509Certificate(cert) => Some(Certificate(cert)),
+            _ => None,
+        })
+        .collect();
+    if certs.is_empty() {
+        return Err(TlsError::MissingCertificate(config.cert_path.clone()));
+    }
+
+    let key = read_pem(&config.key_path)?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::MissingPrivateKey(config.key_path.clone()))?;
+    let key = sign::any_supported_type(&key)
+        .map_err(|_| TlsError::UnsupportedPrivateKey(config.key_path.clone()))?;
+
+    Ok(CertifiedKey::new(certs, key))
+}
+
+fn read_pem(path: &Path) -> Result<Vec<rustls_pemfile::Item>, TlsError> {
+    let io_error = |source| TlsError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let file = File::open(path).map_err(io_error)?;
+    rustls_pemfile::read_all(&mut BufReader::new(file)).map_err(io_error)
+}
+
+/// Returns the most recent modification time of the certificate and key.
+fn last_modified(config: &TlsConfig) -> Option<SystemTime> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    Option::max(modified(&config.cert_path), modified(&config.key_path))
+}
@@ -0,0 +1,105 @@
+//! Serve browser clients with grpc-web.
+//!
+//! Browsers can't use gRPC directly, so requests using the grpc-web protocol
+//! are translated to gRPC by the server. Browsers also require CORS headers
+//! to call the node from another origin.
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+use tonic::codegen::http::{header, HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// How long browsers cache the response to CORS preflight requests.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Headers sent by grpc-web clients.
+const ALLOWED_HEADERS: [&str; 6] = [
+    "x-grpc-web",
+    "x-user-agent",
+    "grpc-timeout",
+    "content-type",
+    "authorization",
+    "x-stream-name",
+];
+
+/// Headers read by grpc-web clients, including the details of quota, rate
+/// limit, and stream limit errors.
+const EXPOSED_HEADERS: [&str; 10] = [
+    "grpc-status",
+    "grpc-message",
+    "grpc-status-details-bin",
+    "retry-after",
+    "x-apibara-quota-period",
+    "x-apibara-quota-limit",
+    "x-apibara-quota-used",
+    "x-apibara-quota-reset",
+    "x-apibara-stream-limit-scope",
+    "x-apibara-stream-limit",
+];
+
+/// grpc-web configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct GrpcWebConfig {
+    /// Origins allowed to call the node. Any origin if empty.
+    #[serde(serialize_with = "serialize_origins")]
+    pub allowed_origins: Vec<HeaderValue>,
+    /// How long browsers cache the response to CORS preflight requests.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub max_age: Duration,
+}
+
+impl Default for GrpcWebConfig {
+    fn default() -> Self {
+        GrpcWebConfig {
+            allowed_origins: Vec::default(),
+            max_age: DEFAULT_MAX_AGE,
+        }
+    }
+}
+
+impl GrpcWebConfig {
+    /// Only allow requests from the given origin. Call multiple times to
+    /// allow several origins.
+    pub fn with_allowed_origin(mut self, origin: HeaderValue) -> Self {
+        self.allowed_origins.push(origin);
+        self
+    }
+
+    /// Returns the layer that adds CORS headers to responses and answers
+    /// preflight requests.
+    pub fn cors_layer(&self) -> CorsLayer {
+        let allow_origin = if self.allowed_origins.is_empty() {
+            AllowOrigin::from(Any)
+        } else {
+            AllowOrigin::list(self.allowed_origins.iter().cloned())
+        };
+        let allow_headers = ALLOWED_HEADERS
+            .into_iter()
+            .map(HeaderName::from_static)
+            .chain([header::ACCEPT])
+            .collect::<Vec<_>>();
+        let expose_headers = EXPOSED_HEADERS
+            .into_iter()
+            .map(HeaderName::from_static)
+            .collect::<Vec<_>>();
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::POST, Method::OPTIONS])
+            .allow_headers(allow_headers)
+            .expose_headers(expose_headers)
+            .max_age(self.max_age)
+    }
+}
+
+/// Serializes origins as strings, origins that are not valid strings are
+/// redacted.
+fn serialize_origins<S: Serializer>(
+    origins: &[HeaderValue],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        origins
+            .iter()
+            .map(|origin| origin.to_str().unwrap_or(crate::runtime_config::REDACTED)),
+    )
+}
@@ -0,0 +1,264 @@
+//! Public HTTP status API.
+//!
+//! Unlike the admin API, the status API is read-only and safe to expose
+//! publicly, for example to load balancers and uptime checks. It replies
+//! with `503 Service Unavailable` when the node is not healthy.
+//!
+//! The same status is rendered as a minimal HTML page at `/`, for operators
+//! checking the node from a browser.
+use std::{fmt::Write, net::SocketAddr, time::UNIX_EPOCH};
+
+use apibara_node::db::libmdbx::{self, EnvironmentKind};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use warp::{http::StatusCode, reply, Filter};
+
+use crate::{
+    core::GlobalBlockId,
+    db::{ShardedStorage, StorageReader},
+    ingestion::{IngestionHealth, IngestionHealthStatus, IngestionProgressSnapshot},
+    server::OpenStreams,
+};
+
+/// Version of the node binary.
+const NODE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub struct StatusServer<E: EnvironmentKind> {
+    storage: ShardedStorage<E>,
+    ingestion_health: IngestionHealth,
+    open_streams: Option<OpenStreams>,
+}
+
+impl<E> StatusServer<E>
+where
+    E: EnvironmentKind,
+{
+    pub fn new(storage: ShardedStorage<E>, ingestion_health: IngestionHealth) -> Self {
+        StatusServer {
+            storage,
+            ingestion_health,
+            open_streams: None,
+        }
+    }
+
+    /// Report the number of streams open on the node.
+    pub fn with_open_streams(mut self, open_streams: OpenStreams) -> Self {
+        self.open_streams = Some(open_streams);
+        self
+    }
+
+    pub async fn start(self, addr: SocketAddr, ct: CancellationToken) {
+        let storage = self.storage;
+        let health = self.ingestion_health;
+        let open_streams = self.open_streams;
+        let status = {
+            let (storage, health, open_streams) =
+                (storage.clone(), health.clone(), open_streams.clone());
+            warp::path!("status").and(warp::get()).map(move || {
+                match node_status_to_json(&storage, &health, open_streams.as_ref()) {
+                    Ok((body, healthy)) => {
+                        reply::with_status(reply::json(&body), status_code(healthy))
+                    }
+                    Err(err) => reply::with_status(
+                        reply::json(&json!({ "error": err.to_string() })),
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                    ),
+                }
+            })
+        };
+        let page = warp::path::end().and(warp::get()).map(move || {
+            match node_status_to_json(&storage, &health, open_streams.as_ref()) {
+                Ok((body, healthy)) => reply::with_status(
+                    reply::html(status_page(&body, healthy)),
+                    status_code(healthy),
+                ),
+                Err(err) => reply::with_status(
+                    reply::html(format!("<p>error: {}</p>", escape_html(&err.to_string()))),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ),
+            }
+        });
+
+        info!(addr = %addr, "starting status server");
+        let routes = status.or(page);
+        let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(addr, async move {
+            ct.cancelled().await;
+        });
+        server.await
+    }
+}
+
+/// Returns the node status and whether the node is healthy.
+fn node_status_to_json<E: EnvironmentKind>(
+    storage: &ShardedStorage<E>,
+    health: &IngestionHealth,
+    open_streams: Option<&OpenStreams>,
+) -> Result<(serde_json::Value, bool), libmdbx::Error> {
+    let chain_id = storage.chain_id()?.map(|chain_id| chain_id.to_hex());
+    let earliest = storage.earliest_available_block()?;
+    let head = storage.highest_accepted_block()?;
+    let finalized = storage.highest_finalized_block()?;
+
+    let status = health.status();
+    let progress = health.progress().snapshot();
+    let last_ingested_at = progress
+        .last_ingested_at
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    let lag = progress
+        .provider_head
+        .zip(head.as_ref())
+        .map(|(provider_head, head)| provider_head.saturating_sub(head.number()));
+
+    let body = json!({
+        "version": NODE_VERSION,
+        "chain_id": chain_id,
+        "earliest": block_id_to_json(earliest.as_ref()),
+        "head": block_id_to_json(head.as_ref()),
+        "finalized": block_id_to_json(finalized.as_ref()),
+        "sync": {
+            "state": sync_state(&status, &progress),
+            "provider_head": progress.provider_head,
+            "lag": lag,
+            "last_ingested_at": last_ingested_at,
+        },
+        "streams": open_streams.map(|open_streams| json!({ "open": open_streams.count() })),
+    });
+    Ok((body, status.is_healthy()))
+}
+
+fn block_id_to_json(id: Option<&GlobalBlockId>) -> serde_json::Value {
+    match id {
+        None => serde_json::Value::Null,
+        Some(id) => json!({
+            "number": id.number(),
+            "hash": format!("0x{}", hex::encode(id.hash().as_bytes())),
+        }),
+    }
+}
+
+fn status_code(healthy: bool) -> StatusCode {
+    if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Renders the node status as an HTML page.
+fn status_page(body: &serde_json::Value, healthy: bool) -> String {
+    let block = |id: &serde_json::Value| match (id["number"].as_u64(), id["hash"].as_str()) {
+        (Some(number), Some(hash)) => format!("{number} ({hash})"),
+        _ => "-".to_string(),
+    };
+    let value = |value: &serde_json::Value| match value {
+        serde_json::Value::Null => "-".to_string(),
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    };
+    let rows = [
+        ("Version", value(&body["version"])),
+        ("Chain", value(&body["chain_id"])),
+        ("Sync", value(&body["sync"]["state"])),
+        ("Head", block(&body["head"])),
+        ("Finalized", block(&body["finalized"])),
+        ("Earliest", block(&body["earliest"])),
+        ("Provider head", value(&body["sync"]["provider_head"])),
+        ("Ingestion lag", value(&body["sync"]["lag"])),
+        ("Last ingested at", value(&body["sync"]["last_ingested_at"])),
+        ("Open streams", value(&body["streams"]["open"])),
+    ];
+
+    let mut page = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta http-equiv=\"refresh\" content=\"10\">\n<title>Apibara node status</title>\n\
+         <style>body { font-family: sans-serif; } td { padding: 2px 12px; }</style>\n\
+         </head>\n<body>\n",
+    );
+    let _ = writeln!(
+        page,
+        "<h1>Apibara node: {}</h1>\n<table>",
+        if healthy { "healthy" } else { "unhealthy" }
+    );
+    for (name, value) in rows {
+        let _ = writeln!(
+            page,
+            "<tr><th align=\"left\">{}</th><td>{}</td></tr>",
+            name,
+            escape_html(&value)
+        );
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Summarizes ingestion in a single word.
+fn sync_state(
+    status: &IngestionHealthStatus,
+    progress: &IngestionProgressSnapshot,
+) -> &'static str {
+    match status {
+        IngestionHealthStatus::Healthy if progress.reached_head => "synced",
+        IngestionHealthStatus::Healthy => "syncing",
+        IngestionHealthStatus::Retrying { .. } => "retrying",
+        IngestionHealthStatus::Failed { .. } => "failed",
+        IngestionHealthStatus::Lagging { .. } => "lagging",
+        IngestionHealthStatus::FinalizedLagging { .. } => "finalized_lagging",
+        IngestionHealthStatus::LowDiskSpace { .. } => "low_disk_space",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::ingestion::{IngestionHealthStatus, IngestionProgressSnapshot};
+
+    use super::{status_page, sync_state};
+
+    #[test]
+    fn test_sync_state_until_head_is_reached() {
+        let mut progress = IngestionProgressSnapshot::default();
+        assert_eq!(
+            sync_state(&IngestionHealthStatus::Healthy, &progress),
+            "syncing"
+        );
+        progress.reached_head = true;
+        assert_eq!(
+            sync_state(&IngestionHealthStatus::Healthy, &progress),
+            "synced"
+        );
+        let lagging = IngestionHealthStatus::Lagging {
+            lag: 20,
+            max_lag: 10,
+        };
+        assert_eq!(sync_state(&lagging, &progress), "lagging");
+    }
+
+    #[test]
+    fn test_status_page() {
+        let body = json!({
+            "version": "1.0.0",
+            "chain_id": "0x534e5f4d41494e",
+            "earliest": null,
+            "head": { "number": 10, "hash": "0x0a" },
+            "finalized": null,
+            "sync": { "state": "synced", "provider_head": 12, "lag": 2, "last_ingested_at": null },
+            "streams": { "open": 3 },
+        });
+        let page = status_page(&body, true);
+        assert!(page.contains("<h1>Apibara node: healthy</h1>"));
+        assert!(page.contains("<th align=\"left\">Head</th><td>10 (0x0a)</td>"));
+        assert!(page.contains("<th align=\"left\">Ingestion lag</th><td>2</td>"));
+        assert!(page.contains("<th align=\"left\">Open streams</th><td>3</td>"));
+        assert!(page.contains("<th align=\"left\">Finalized</th><td>-</td>"));
+    }
+}
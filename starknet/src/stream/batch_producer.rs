@@ -1,14 +1,25 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use apibara_core::starknet::v1alpha2;
 use apibara_node::{
     async_trait,
+    o11y::set_error_context,
     server::RequestMeter,
     stream::{BatchProducer, StreamConfiguration, StreamError},
 };
-use tracing::trace;
+use tracing::{debug_span, trace, Instrument};
 
-use crate::{core::GlobalBlockId, db::StorageReader};
+use crate::{
+    core::GlobalBlockId,
+    db::{PartialBlock, StorageReader},
+};
+
+use super::{
+    load_shedding::{LoadMonitor, BACKFILL_DISTANCE},
+    restriction::FilterRestriction,
+    scheduler::{BatchScheduler, PriorityTier},
+    slow_batch::{BatchTimings, SlowBatchLog},
+};
 
 /// A [BatchProducer] that reads data from the database.
 pub struct DbBatchProducer<R>
@@ -17,6 +28,10 @@ where
 {
     storage: Arc<R>,
     inner: Option<InnerProducer<R>>,
+    scheduler: Option<(BatchScheduler, PriorityTier)>,
+    restriction: Option<FilterRestriction>,
+    load_monitor: Option<(LoadMonitor, PriorityTier)>,
+    slow_batch_log: Option<SlowBatchLog>,
 }
 
 struct InnerProducer<R>
@@ -35,10 +50,111 @@ where
         DbBatchProducer {
             inner: None,
             storage,
+            scheduler: None,
+            restriction: None,
+            load_monitor: None,
+            slow_batch_log: None,
         }
     }
 
-    fn block_data<M: RequestMeter>(
+    /// Creates a new producer that uses the given filter.
+    pub fn with_filter(storage: Arc<R>, filter: v1alpha2::Filter) -> Self {
+        let inner = InnerProducer {
+            storage: storage.clone(),
+            filter,
+        };
+        DbBatchProducer {
+            inner: Some(inner),
+            storage,
+            scheduler: None,
+            restriction: None,
+            load_monitor: None,
+            slow_batch_log: None,
+        }
+    }
+
+    /// Wait for the turn of the given tier before producing a batch.
+    pub fn with_scheduler(mut self, scheduler: BatchScheduler, tier: PriorityTier) -> Self {
+        self.scheduler = Some((scheduler, tier));
+        self
+    }
+
+    /// Reject configurations outside the given restriction.
+    pub fn with_restriction(mut self, restriction: FilterRestriction) -> Self {
+        self.restriction = Some(restriction);
+        self
+    }
+
+    /// Record the storage read latency, and slow down backfills while the
+    /// node is overloaded.
+    pub fn with_load_monitor(mut self, monitor: LoadMonitor, tier: PriorityTier) -> Self {
+        self.load_monitor = Some((monitor, tier));
+        self
+    }
+
+    /// Log the batches that are slow to produce.
+    pub fn with_slow_batch_log(mut self, log: SlowBatchLog) -> Self {
+        self.slow_batch_log = Some(log);
+        self
+    }
+
+    /// Returns true if the batch is far behind the head of the chain.
+    fn is_backfill(&self, cursors: &[GlobalBlockId]) -> Result<bool, StreamError> {
+        let first = match cursors.first() {
+            None => return Ok(false),
+            Some(first) => first,
+        };
+        let head = self
+            .storage
+            .highest_accepted_block()
+            .map_err(StreamError::internal)?;
+        Ok(head
+            .map(|head| head.number() > first.number() + BACKFILL_DISTANCE)
+            .unwrap_or(false))
+    }
+
+    /// Returns an error if the filter needs data that is missing from the
+    /// block.
+    ///
+    /// Blocks backfilled from their events never get the missing data, while
+    /// degraded blocks get it once the node ingests them again.
+    fn check_partial_block(&self, block_id: &GlobalBlockId) -> Result<(), StreamError> {
+        let filter = match self.inner {
+            None => return Ok(()),
+            Some(ref inner) => &inner.filter,
+        };
+        let partial = match self
+            .storage
+            .read_partial_block(block_id)
+            .map_err(StreamError::internal)?
+        {
+            None => return Ok(()),
+            Some(partial) => partial,
+        };
+        if partial.events_only && needs_full_block(filter) {
+            return Err(StreamError::invalid_request(format!(
+                "block {} only contains events, filter on header and events only",
+                block_id.number()
+            )));
+        }
+        if let Some(missing) = missing_data(&partial, filter) {
+            return Err(StreamError::unavailable(
+                format!(
+                    "block {} is missing its {}, retry once the node ingested it again",
+                    block_id.number(),
+                    missing
+                ),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the block data that matches the filter.
+    ///
+    /// Returns `None` if no data matches the filter or if the producer has
+    /// no filter.
+    pub fn block_data<M: RequestMeter>(
         &self,
         block_id: &GlobalBlockId,
         meter: &M,
@@ -50,10 +166,38 @@ where
     }
 }
 
+/// Returns true if the filter needs data that is missing from blocks
+/// backfilled from their events only.
+fn needs_full_block(filter: &v1alpha2::Filter) -> bool {
+    !filter.transactions.is_empty()
+        || filter.state_update.is_some()
+        || !filter.messages.is_empty()
+        || !filter.invocations.is_empty()
+        || !filter.declared_classes.is_empty()
+}
+
+/// Returns the data needed by the filter that is missing from the degraded
+/// block, if any.
+fn missing_data(partial: &PartialBlock, filter: &v1alpha2::Filter) -> Option<&'static str> {
+    if partial.missing_state_update && filter.state_update.is_some() {
+        return Some("state update");
+    }
+    if partial.missing_traces && !filter.invocations.is_empty() {
+        return Some("traces");
+    }
+    if (partial.missing_state_update || partial.missing_classes)
+        && !filter.declared_classes.is_empty()
+    {
+        return Some("declared classes");
+    }
+    None
+}
+
 impl<R> InnerProducer<R>
 where
     R: StorageReader + Send + Sync + 'static,
 {
+    #[tracing::instrument(level = "debug", skip_all, fields(block_number = block_id.number()))]
     fn block_data<M: RequestMeter>(
         &self,
         block_id: &GlobalBlockId,
@@ -78,9 +222,18 @@ where
         let l2_to_l1_messages = self.l2_to_l1_messages(block_id, &mut data_counter)?;
         has_data |= !l2_to_l1_messages.is_empty();
 
+        let invocations = self.invocations(block_id, &mut data_counter)?;
+        has_data |= !invocations.is_empty();
+
+        let declared_classes = self.declared_classes(block_id, &mut data_counter)?;
+        has_data |= !declared_classes.is_empty();
+
         let state_update = self.state_update(block_id, &mut data_counter)?;
         has_data |= state_update.is_some();
 
+        let data_availability = self.data_availability(block_id, &mut data_counter)?;
+        has_data |= data_availability.is_some();
+
         let data = v1alpha2::Block {
             status: status as i32,
             header,
@@ -88,6 +241,9 @@ where
             transactions,
             events,
             l2_to_l1_messages,
+            invocations,
+            declared_classes,
+            data_availability,
         };
 
         if has_data {
@@ -113,6 +269,7 @@ where
         self.filter.header.as_ref().map(|h| h.weak).unwrap_or(true)
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
     fn header(
         &self,
         block_id: &GlobalBlockId,
@@ -126,6 +283,7 @@ where
         }
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
     fn transactions(
         &self,
         block_id: &GlobalBlockId,
@@ -161,6 +319,7 @@ where
         Ok(transactions_with_receipts)
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
     fn events(
         &self,
         block_id: &GlobalBlockId,
@@ -231,6 +390,7 @@ where
         Ok(events)
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
     fn l2_to_l1_messages(
         &self,
         block_id: &GlobalBlockId,
@@ -269,6 +429,146 @@ where
         Ok(messages)
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn invocations(
+        &self,
+        block_id: &GlobalBlockId,
+        meter: &mut DataCounter,
+    ) -> Result<Vec<v1alpha2::FunctionInvocationWithTransaction>, R::Error> {
+        if self.filter.invocations.is_empty() {
+            return Ok(Vec::default());
+        }
+
+        let mut traces = self.storage.read_traces(block_id)?;
+        if traces.is_empty() {
+            return Ok(Vec::default());
+        }
+
+        let transactions = self.storage.read_body(block_id)?;
+        let (mut receipts, _) = self.storage.read_receipts(block_id)?;
+
+        assert!(transactions.len() == receipts.len());
+        receipts.sort_by(|a, b| a.transaction_index.cmp(&b.transaction_index));
+        traces.sort_by(|a, b| a.transaction_index.cmp(&b.transaction_index));
+
+        let mut invocations = Vec::default();
+        for trace in traces {
+            let index = trace.transaction_index as usize;
+            let (transaction, receipt) = match (transactions.get(index), receipts.get(index)) {
+                (Some(transaction), Some(receipt)) => (transaction, receipt),
+                _ => continue,
+            };
+
+            let mut matched = Vec::default();
+            for invocation in [
+                trace.validate_invocation,
+                trace.execute_invocation,
+                trace.fee_transfer_invocation,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                self.collect_invocations(invocation, &mut matched);
+            }
+
+            for invocation in matched {
+                invocations.push(v1alpha2::FunctionInvocationWithTransaction {
+                    transaction: Some(transaction.clone()),
+                    receipt: Some(receipt.clone()),
+                    invocation: Some(invocation),
+                });
+            }
+        }
+
+        meter.invocation = invocations.len();
+
+        Ok(invocations)
+    }
+
+    /// Collects the invocations matching the filter, in call order.
+    ///
+    /// A matching invocation is returned together with its nested calls, so
+    /// nested calls are not matched again.
+    fn collect_invocations(
+        &self,
+        invocation: v1alpha2::FunctionInvocation,
+        matched: &mut Vec<v1alpha2::FunctionInvocation>,
+    ) {
+        if self.filter_invocation(&invocation) {
+            matched.push(invocation);
+            return;
+        }
+        for call in invocation.calls {
+            self.collect_invocations(call, matched);
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn declared_classes(
+        &self,
+        block_id: &GlobalBlockId,
+        meter: &mut DataCounter,
+    ) -> Result<Vec<v1alpha2::DeclaredClass>, R::Error> {
+        if self.filter.declared_classes.is_empty() {
+            return Ok(Vec::default());
+        }
+
+        let state_diff = match self.storage.read_state_update(block_id)? {
+            Some(v1alpha2::StateUpdate {
+                state_diff: Some(state_diff),
+                ..
+            }) => state_diff,
+            _ => return Ok(Vec::default()),
+        };
+
+        if state_diff.declared_contracts.is_empty() {
+            return Ok(Vec::default());
+        }
+
+        let transactions = self.storage.read_body(block_id)?;
+
+        let mut classes = Vec::default();
+        for declared in state_diff.declared_contracts {
+            let class_hash = match declared.class_hash {
+                None => continue,
+                Some(class_hash) => class_hash,
+            };
+
+            let transaction = transactions
+                .iter()
+                .find(|tx| match tx.transaction.as_ref() {
+                    Some(v1alpha2::transaction::Transaction::Declare(declare)) => {
+                        declare.class_hash.as_ref() == Some(&class_hash)
+                    }
+                    _ => false,
+                })
+                .cloned();
+
+            let mut class = v1alpha2::DeclaredClass {
+                class_hash: Some(class_hash.clone()),
+                definition: Vec::default(),
+                transaction,
+            };
+
+            if !self.filter_declared_class(&class) {
+                continue;
+            }
+
+            // definitions are only stored if the node ingests classes.
+            let definition = match self.storage.read_class(&class_hash)? {
+                None => continue,
+                Some(definition) => definition,
+            };
+            class.definition = definition;
+            classes.push(class);
+        }
+
+        meter.declared_class = classes.len();
+
+        Ok(classes)
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
     fn state_update(
         &self,
         block_id: &GlobalBlockId,
@@ -345,6 +645,22 @@ where
         }
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
+    fn data_availability(
+        &self,
+        block_id: &GlobalBlockId,
+        meter: &mut DataCounter,
+    ) -> Result<Option<v1alpha2::DataAvailability>, R::Error> {
+        if !self.filter.data_availability {
+            return Ok(None);
+        }
+        let data_availability = self.storage.read_data_availability(block_id)?;
+        if data_availability.is_some() {
+            meter.data_availability = 1;
+        }
+        Ok(data_availability)
+    }
+
     fn filter_transaction(&self, tx: &v1alpha2::Transaction) -> bool {
         self.filter.transactions.iter().any(|f| f.matches(tx))
     }
@@ -357,6 +673,20 @@ where
         self.filter.messages.iter().any(|f| f.matches(message))
     }
 
+    fn filter_invocation(&self, invocation: &v1alpha2::FunctionInvocation) -> bool {
+        self.filter
+            .invocations
+            .iter()
+            .any(|f| f.matches(invocation))
+    }
+
+    fn filter_declared_class(&self, class: &v1alpha2::DeclaredClass) -> bool {
+        self.filter
+            .declared_classes
+            .iter()
+            .any(|f| f.matches(class))
+    }
+
     fn filter_storage_diff(
         &self,
         diff: &v1alpha2::StorageDiff,
@@ -402,10 +732,13 @@ struct DataCounter {
     pub transaction: usize,
     pub event: usize,
     pub message: usize,
+    pub invocation: usize,
+    pub declared_class: usize,
     pub storage_diff: usize,
     pub declared_contract: usize,
     pub deployed_contract: usize,
     pub nonce_update: usize,
+    pub data_availability: usize,
 }
 
 impl DataCounter {
@@ -414,10 +747,13 @@ impl DataCounter {
         meter.increment_counter("transaction", self.transaction as u64);
         meter.increment_counter("event", self.event as u64);
         meter.increment_counter("message", self.message as u64);
+        meter.increment_counter("invocation", self.invocation as u64);
+        meter.increment_counter("declared_class", self.declared_class as u64);
         meter.increment_counter("storage_diff", self.storage_diff as u64);
         meter.increment_counter("declared_contract", self.declared_contract as u64);
         meter.increment_counter("deployed_contract", self.deployed_contract as u64);
         meter.increment_counter("nonce_update", self.nonce_update as u64);
+        meter.increment_counter("data_availability", self.data_availability as u64);
     }
 }
 
@@ -434,11 +770,17 @@ where
         &mut self,
         configuration: &StreamConfiguration<Self::Cursor, Self::Filter>,
     ) -> Result<(), StreamError> {
+        if let Some(restriction) = &self.restriction {
+            restriction.check(configuration)?;
+        }
         let new_inner = InnerProducer {
             storage: self.storage.clone(),
             filter: configuration.filter.clone(),
         };
         self.inner = Some(new_inner);
+        if let Some(log) = &self.slow_batch_log {
+            log.set_filter(&configuration.filter);
+        }
         Ok(())
     }
 
@@ -447,10 +789,139 @@ where
         cursors: impl Iterator<Item = Self::Cursor> + Send + Sync,
         meter: &M,
     ) -> Result<Vec<Self::Block>, StreamError> {
-        let batch: Vec<_> = cursors
-            .flat_map(|cursor| self.block_data(&cursor, meter).transpose())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(StreamError::internal)?;
-        Ok(batch)
+        let cursors: Vec<_> = cursors.collect();
+        if let Some(cursor) = cursors.first() {
+            set_error_context("block_number", cursor.number());
+        }
+        let span = debug_span!(
+            "next_batch",
+            first_block = cursors.first().map(|cursor| cursor.number()),
+            blocks = cursors.len()
+        );
+        async move {
+            let mut timings = BatchTimings::default();
+            if let Some((monitor, tier)) = &self.load_monitor {
+                if let Some(delay) = monitor.backfill_delay(*tier) {
+                    if self.is_backfill(&cursors)? {
+                        tokio::time::sleep(delay)
+                            .instrument(debug_span!("backfill_delay"))
+                            .await;
+                        timings.delay = delay;
+                    }
+                }
+            }
+            let wait_started_at = Instant::now();
+            let _permit = match &self.scheduler {
+                None => None,
+                Some((scheduler, tier)) => Some(
+                    scheduler
+                        .acquire(*tier)
+                        .instrument(debug_span!("wait_turn"))
+                        .await,
+                ),
+            };
+            timings.wait = wait_started_at.elapsed();
+            let mut batch = Vec::with_capacity(cursors.len());
+            for cursor in &cursors {
+                self.check_partial_block(cursor)?;
+                let started_at = Instant::now();
+                let block = self
+                    .block_data(cursor, meter)
+                    .map_err(StreamError::internal)?;
+                let elapsed = started_at.elapsed();
+                if let Some((monitor, _)) = &self.load_monitor {
+                    monitor.record_read(elapsed);
+                }
+                timings.record_block(elapsed);
+                batch.extend(block);
+            }
+            if let Some(log) = &self.slow_batch_log {
+                log.produced(&cursors, &timings);
+            }
+            Ok(batch)
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_core::starknet::v1alpha2;
+    use apibara_node::stream::StreamErrorKind;
+    use mockall::predicate::eq;
+
+    use crate::{
+        core::{BlockHash, GlobalBlockId},
+        db::{MockStorageReader, PartialBlock},
+    };
+
+    use super::DbBatchProducer;
+
+    #[test]
+    fn test_reject_events_only_blocks() {
+        let block_id = GlobalBlockId::new(1, BlockHash::zero());
+        let mut storage = MockStorageReader::new();
+        storage
+            .expect_read_partial_block()
+            .with(eq(block_id))
+            .returning(|_| {
+                Ok(Some(PartialBlock {
+                    events_only: true,
+                    ..PartialBlock::default()
+                }))
+            });
+        let storage = Arc::new(storage);
+
+        // event filters only need data stored in events only blocks.
+        let filter = v1alpha2::Filter {
+            header: Some(v1alpha2::HeaderFilter::default()),
+            events: vec![v1alpha2::EventFilter::default()],
+            ..v1alpha2::Filter::default()
+        };
+        let producer = DbBatchProducer::with_filter(storage.clone(), filter);
+        assert!(producer.check_partial_block(&block_id).is_ok());
+
+        let filter = v1alpha2::Filter {
+            transactions: vec![v1alpha2::TransactionFilter::default()],
+            ..v1alpha2::Filter::default()
+        };
+        let producer = DbBatchProducer::with_filter(storage, filter);
+        let err = producer.check_partial_block(&block_id).unwrap_err();
+        assert_eq!(err.kind(), StreamErrorKind::InvalidRequest);
+    }
+
+    #[test]
+    fn test_reject_degraded_blocks() {
+        let block_id = GlobalBlockId::new(1, BlockHash::zero());
+        let mut storage = MockStorageReader::new();
+        storage
+            .expect_read_partial_block()
+            .with(eq(block_id))
+            .returning(|_| {
+                Ok(Some(PartialBlock {
+                    missing_traces: true,
+                    ..PartialBlock::default()
+                }))
+            });
+        let storage = Arc::new(storage);
+
+        let filter = v1alpha2::Filter {
+            transactions: vec![v1alpha2::TransactionFilter::default()],
+            state_update: Some(v1alpha2::StateUpdateFilter::default()),
+            ..v1alpha2::Filter::default()
+        };
+        let producer = DbBatchProducer::with_filter(storage.clone(), filter);
+        assert!(producer.check_partial_block(&block_id).is_ok());
+
+        let filter = v1alpha2::Filter {
+            invocations: vec![v1alpha2::FunctionInvocationFilter::default()],
+            ..v1alpha2::Filter::default()
+        };
+        let producer = DbBatchProducer::with_filter(storage, filter);
+        let err = producer.check_partial_block(&block_id).unwrap_err();
+        assert_eq!(err.kind(), StreamErrorKind::Unavailable);
     }
 }
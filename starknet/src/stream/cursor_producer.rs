@@ -7,6 +7,7 @@ use std::{
 use apibara_core::{node::v1alpha2::DataFinality, starknet::v1alpha2};
 use apibara_node::{
     async_trait,
+    core::Cursor,
     stream::{
         BatchCursor, CursorProducer, IngestionMessage, IngestionResponse, ReconfigureResponse,
         StreamConfiguration, StreamError,
@@ -60,6 +61,7 @@ where
         }
     }
 
+    #[tracing::instrument(level = "trace", skip_all)]
     fn next_cursor_with_configuration(
         &mut self,
     ) -> Result<Option<BatchCursor<GlobalBlockId>>, R::Error> {
@@ -73,7 +75,14 @@ where
         let configuration = self.configuration.as_mut().expect("configuration");
         let starting_cursor = configuration.current;
 
-        let next_block_number = configuration.current.map(|c| c.number() + 1).unwrap_or(0);
+        let next_block_number = match configuration.current {
+            Some(current) => current.order_key() + 1,
+            None => self
+                .storage
+                .earliest_available_block()?
+                .map(|c| c.order_key())
+                .unwrap_or(0),
+        };
 
         if let Some(finalized) = finalized_cursor {
             if next_block_number <= finalized.number() {
@@ -194,6 +203,34 @@ where
         Ok(self.ingestion_state.get_or_insert(new_state))
     }
 
+    /// Returns the response to a starting cursor that is not in storage.
+    ///
+    /// Cursors before the earliest available block are rejected with an
+    /// error that contains the valid range.
+    fn missing_starting_cursor(
+        &self,
+        cursor: &GlobalBlockId,
+    ) -> Result<ReconfigureResponse<GlobalBlockId>, StreamError> {
+        match self
+            .storage
+            .earliest_available_block()
+            .map_err(StreamError::internal)?
+        {
+            Some(earliest) if cursor.number() < earliest.number() => {
+                let latest = self
+                    .storage
+                    .highest_accepted_block()
+                    .map_err(StreamError::internal)?;
+                Err(StreamError::DataPruned {
+                    block_number: cursor.number(),
+                    earliest: earliest.to_proto(),
+                    latest: latest.map(|latest| latest.to_proto()),
+                })
+            }
+            _ => Ok(ReconfigureResponse::MissingStartingCursor),
+        }
+    }
+
     /// wake up the stream if it was waiting for a new block
     fn wake(&mut self) {
         if let Some(waker) = self.waker.take() {
@@ -203,7 +240,7 @@ where
 }
 
 fn lowest_cursor(a: GlobalBlockId, b: GlobalBlockId) -> GlobalBlockId {
-    if a.number() < b.number() {
+    if a.order_key() < b.order_key() {
         a
     } else {
         b
@@ -218,6 +255,7 @@ where
     type Cursor = GlobalBlockId;
     type Filter = v1alpha2::Filter;
 
+    #[tracing::instrument(level = "debug", skip_all)]
     async fn reconfigure(
         &mut self,
         configuration: &StreamConfiguration<Self::Cursor, Self::Filter>,
@@ -234,7 +272,7 @@ where
                         .map_err(StreamError::internal)?
                     {
                         Some(starting_cursor) => starting_cursor,
-                        None => return Ok(ReconfigureResponse::MissingStartingCursor),
+                        None => return self.missing_starting_cursor(&starting_cursor),
                     }
                 } else {
                     starting_cursor
@@ -246,7 +284,7 @@ where
                     .read_status(&starting_cursor)
                     .map_err(StreamError::internal)?
                 {
-                    None => return Ok(ReconfigureResponse::MissingStartingCursor),
+                    None => return self.missing_starting_cursor(&starting_cursor),
                     Some(starting_status) => starting_status,
                 };
 
@@ -263,7 +301,7 @@ where
                             .read_status(&new_root)
                             .map_err(StreamError::internal)?
                         {
-                            None => return Ok(ReconfigureResponse::MissingStartingCursor),
+                            None => return self.missing_starting_cursor(&new_root),
                             Some(status) => status,
                         };
 
@@ -276,7 +314,7 @@ where
                             .read_header(&new_root)
                             .map_err(StreamError::internal)?
                         {
-                            None => return Ok(ReconfigureResponse::MissingStartingCursor),
+                            None => return self.missing_starting_cursor(&new_root),
                             Some(header) => header,
                         };
 
@@ -302,6 +340,7 @@ where
         Ok(response)
     }
 
+    #[tracing::instrument(level = "debug", skip_all)]
     async fn handle_ingestion_message(
         &mut self,
         message: &IngestionMessage<Self::Cursor>,
@@ -449,6 +488,8 @@ mod tests {
             finality,
             starting_cursor,
             filter: Filter::default(),
+            max_data_units_per_message: None,
+            max_message_size: None,
         }
     }
 
@@ -475,6 +516,9 @@ mod tests {
     #[tokio::test]
     async fn test_produce_full_batch_finalized() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_canonical_block_id()
             .returning(|i| Ok(Some(new_block_id(i))));
@@ -507,6 +551,9 @@ mod tests {
     #[tokio::test]
     async fn test_produce_nothing_if_after_finalized_as_finalized() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .returning(|_| Ok(Some(BlockStatus::AcceptedOnL1)));
@@ -538,6 +585,9 @@ mod tests {
     #[tokio::test]
     async fn test_reach_accepted_as_finalized() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_canonical_block_id()
             .returning(|i| Ok(Some(new_block_id(i))));
@@ -576,6 +626,9 @@ mod tests {
     #[tokio::test]
     async fn test_handle_finalized_message_as_finalized() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_canonical_block_id()
             .returning(|i| Ok(Some(new_block_id(i))));
@@ -622,6 +675,9 @@ mod tests {
     #[tokio::test]
     async fn test_handle_invalidate_message_as_finalized() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .returning(|_| Ok(Some(BlockStatus::AcceptedOnL1)));
@@ -683,6 +739,9 @@ mod tests {
     #[tokio::test]
     async fn test_no_finalized_as_finalized() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_canonical_block_id()
             .returning(|i| Ok(Some(new_block_id(i))));
@@ -715,6 +774,9 @@ mod tests {
     #[tokio::test]
     async fn test_no_accepted_as_finalized() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_canonical_block_id()
             .returning(|i| Ok(Some(new_block_id(i))));
@@ -739,6 +801,9 @@ mod tests {
     #[tokio::test]
     async fn test_full_batch_as_accepted() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .returning(|_| Ok(Some(BlockStatus::AcceptedOnL1)));
@@ -782,6 +847,9 @@ mod tests {
     #[tokio::test]
     async fn test_handle_finalized_message_as_accepted() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .returning(|_| Ok(Some(BlockStatus::AcceptedOnL1)));
@@ -835,6 +903,9 @@ mod tests {
     #[tokio::test]
     async fn test_handle_accepted_message_as_accepted() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .returning(|_| Ok(Some(BlockStatus::AcceptedOnL1)));
@@ -887,6 +958,9 @@ mod tests {
     #[tokio::test]
     async fn test_handle_invalidate_message_as_accepted() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .returning(|_| Ok(Some(BlockStatus::AcceptedOnL1)));
@@ -947,6 +1021,9 @@ mod tests {
     #[tokio::test]
     async fn test_no_finalized_as_accepted() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_canonical_block_id()
             .returning(|i| Ok(Some(new_block_id(i))));
@@ -971,6 +1048,9 @@ mod tests {
     #[tokio::test]
     async fn test_no_accepted_as_accepted() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_canonical_block_id()
             .returning(|i| Ok(Some(new_block_id(i))));
@@ -995,6 +1075,9 @@ mod tests {
     #[tokio::test]
     async fn test_produce_full_batch_pending() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .returning(|_| Ok(Some(BlockStatus::AcceptedOnL1)));
@@ -1055,6 +1138,9 @@ mod tests {
     #[tokio::test]
     async fn test_configure_with_valid_starting_cursor() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .returning(|_| Ok(Some(BlockStatus::AcceptedOnL1)));
@@ -1083,6 +1169,9 @@ mod tests {
     #[tokio::test]
     async fn test_configure_with_invalidated_starting_cursor() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage
             .expect_read_status()
             .with(eq(new_block_id(8)))
@@ -1128,6 +1217,9 @@ mod tests {
     #[tokio::test]
     async fn test_configure_with_non_existing_starting_cursor() {
         let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(0))));
         storage.expect_read_status().returning(|_| Ok(None));
         storage
             .expect_canonical_block_id()
@@ -1150,4 +1242,32 @@ mod tests {
             .unwrap();
         assert_matches!(response, ReconfigureResponse::MissingStartingCursor);
     }
+
+    #[tokio::test]
+    async fn test_configure_with_starting_cursor_before_earliest_block() {
+        let mut storage = MockStorageReader::new();
+        storage
+            .expect_earliest_available_block()
+            .returning(|| Ok(Some(new_block_id(5))));
+        storage
+            .expect_highest_accepted_block()
+            .returning(|| Ok(Some(new_block_id(10))));
+        storage.expect_canonical_block_id().returning(|i| {
+            if i < 5 {
+                Ok(None)
+            } else {
+                Ok(Some(new_block_id(i)))
+            }
+        });
+
+        let cursor = GlobalBlockId::new(2, BlockHash::zero());
+        let mut producer = SequentialCursorProducer::new(Arc::new(storage));
+        let response = producer
+            .reconfigure(&new_configuration(
+                Some(cursor),
+                DataFinality::DataStatusAccepted,
+            ))
+            .await;
+        assert!(response.is_err());
+    }
 }
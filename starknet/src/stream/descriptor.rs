@@ -0,0 +1,116 @@
+//! Describe StarkNet blocks sent to v1alpha3 clients.
+
+use apibara_core::{node::v1alpha2::DataFinality, starknet::v1alpha2};
+use apibara_node::stream::{BlockDescription, BlockDescriptor};
+use prost::Message;
+
+use crate::core::BlockHash;
+
+/// The first fields of an encoded block.
+///
+/// Decoding only the header is much cheaper than decoding the whole block,
+/// the other fields are skipped.
+#[derive(Clone, PartialEq, Message)]
+struct BlockHead {
+    #[prost(enumeration = "v1alpha2::BlockStatus", tag = "1")]
+    status: i32,
+    #[prost(message, optional, tag = "2")]
+    header: Option<v1alpha2::BlockHeader>,
+}
+
+/// Reads cursor, timestamp and finality from the block header and status.
+///
+/// Blocks streamed with a filter that doesn't include the header have no
+/// cursor.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StarkNetBlockDescriptor;
+
+impl BlockDescriptor for StarkNetBlockDescriptor {
+    fn describe(&self, data: &[u8]) -> BlockDescription {
+        let head = match BlockHead::decode(data) {
+            Ok(head) => head,
+            Err(_) => return BlockDescription::default(),
+        };
+
+        let finality = match head.status() {
+            v1alpha2::BlockStatus::Pending => Some(DataFinality::DataStatusPending),
+            v1alpha2::BlockStatus::AcceptedOnL2 => Some(DataFinality::DataStatusAccepted),
+            v1alpha2::BlockStatus::AcceptedOnL1 => Some(DataFinality::DataStatusFinalized),
+            v1alpha2::BlockStatus::Unspecified | v1alpha2::BlockStatus::Rejected => None,
+        };
+
+        let header = match head.header {
+            None => {
+                return BlockDescription {
+                    finality,
+                    ..BlockDescription::default()
+                }
+            }
+            Some(header) => header,
+        };
+
+        let cursor = header.block_hash.as_ref().map(|hash| {
+            let hash: BlockHash = hash.into();
+            apibara_core::node::v1alpha2::Cursor {
+                order_key: header.block_number,
+                unique_key: hash.as_bytes().to_vec(),
+            }
+        });
+        let timestamp = header
+            .timestamp
+            .and_then(|timestamp| u64::try_from(timestamp.seconds).ok());
+
+        BlockDescription {
+            cursor,
+            timestamp,
+            finality,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::{node::v1alpha2::DataFinality, starknet::v1alpha2};
+    use apibara_node::stream::BlockDescriptor;
+    use prost::Message;
+
+    use super::StarkNetBlockDescriptor;
+
+    #[test]
+    fn test_describe_block() {
+        let hash = v1alpha2::FieldElement::from_u64(0xabc);
+        let block = v1alpha2::Block {
+            status: v1alpha2::BlockStatus::AcceptedOnL1 as i32,
+            header: Some(v1alpha2::BlockHeader {
+                block_hash: Some(hash),
+                block_number: 42,
+                timestamp: Some(pbjson_types::Timestamp {
+                    seconds: 1_700_000_000,
+                    nanos: 0,
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let description = StarkNetBlockDescriptor.describe(&block.encode_to_vec());
+        let cursor = description.cursor.unwrap();
+        assert_eq!(cursor.order_key, 42);
+        assert_eq!(cursor.unique_key.len(), 32);
+        assert_eq!(cursor.unique_key[31], 0xbc);
+        assert_eq!(description.timestamp, Some(1_700_000_000));
+        assert_eq!(
+            description.finality,
+            Some(DataFinality::DataStatusFinalized)
+        );
+
+        // no header, no cursor.
+        let block = v1alpha2::Block {
+            status: v1alpha2::BlockStatus::Pending as i32,
+            ..Default::default()
+        };
+        let description = StarkNetBlockDescriptor.describe(&block.encode_to_vec());
+        assert!(description.cursor.is_none());
+        assert_eq!(description.finality, Some(DataFinality::DataStatusPending));
+    }
+}
@@ -0,0 +1,271 @@
+//! Shed load when the node is under resource pressure.
+//!
+//! The monitor samples the CPU and memory used by the node, together with
+//! the latency of storage reads, and marks the node as overloaded when any
+//! of them is over its threshold. While overloaded, new streams are refused
+//! and the backfills of low priority streams are slowed down, so that
+//! streams at the head of the chain stay healthy.
+//!
+//! CPU and memory usage are only available on Linux.
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::scheduler::PriorityTier;
+
+/// Default interval between resource usage samples.
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Default delay before each backfill batch while overloaded.
+const DEFAULT_BACKFILL_DELAY: Duration = Duration::from_secs(1);
+
+/// Streams more than this many blocks behind the head are backfilling.
+pub const BACKFILL_DISTANCE: u64 = 100;
+
+/// Clock ticks per second in `/proc`.
+const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+
+/// Resource usage thresholds.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadSheddingConfig {
+    /// Fraction of the available CPU time used by the node, between 0 and 1.
+    pub max_cpu_usage: Option<f64>,
+    /// Resident memory of the node, in bytes.
+    pub max_memory: Option<u64>,
+    /// Average time to read the data of a block from storage.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub max_read_latency: Option<Duration>,
+    /// How often resource usage is sampled.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub check_interval: Duration,
+    /// Delay before each backfill batch while the node is overloaded.
+    #[serde(serialize_with = "crate::runtime_config::serialize_duration")]
+    pub backfill_delay: Duration,
+    /// Slow down the backfills of streams with this tier or lower.
+    pub max_slowed_tier: PriorityTier,
+}
+
+/// Resource usage of the node.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_usage: Option<f64>,
+    pub memory: Option<u64>,
+    pub read_latency: Duration,
+}
+
+/// Shared load monitor.
+#[derive(Debug, Clone)]
+pub struct LoadMonitor {
+    config: Arc<LoadSheddingConfig>,
+    overloaded: Arc<AtomicBool>,
+    /// Moving average of the read latency, in microseconds.
+    read_latency: Arc<AtomicU64>,
+}
+
+/// Computes the CPU usage between samples.
+struct CpuSampler {
+    previous: Option<(Instant, u64)>,
+    cpus: f64,
+}
+
+impl Default for LoadSheddingConfig {
+    fn default() -> Self {
+        LoadSheddingConfig {
+            max_cpu_usage: None,
+            max_memory: None,
+            max_read_latency: None,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            backfill_delay: DEFAULT_BACKFILL_DELAY,
+            max_slowed_tier: PriorityTier::Normal,
+        }
+    }
+}
+
+impl LoadSheddingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.max_cpu_usage.is_some() || self.max_memory.is_some() || self.max_read_latency.is_some()
+    }
+
+    /// Returns the first resource over its threshold, if any.
+    pub fn exceeded(&self, usage: &ResourceUsage) -> Option<&'static str> {
+        if let (Some(max), Some(cpu_usage)) = (self.max_cpu_usage, usage.cpu_usage) {
+            if cpu_usage > max {
+                return Some("cpu");
+            }
+        }
+        if let (Some(max), Some(memory)) = (self.max_memory, usage.memory) {
+            if memory > max {
+                return Some("memory");
+            }
+        }
+        match self.max_read_latency {
+            Some(max) if usage.read_latency > max => Some("read latency"),
+            _ => None,
+        }
+    }
+}
+
+impl LoadMonitor {
+    pub fn new(config: LoadSheddingConfig) -> Self {
+        LoadMonitor {
+            config: Arc::new(config),
+            overloaded: Arc::default(),
+            read_latency: Arc::default(),
+        }
+    }
+
+    /// Returns true if new streams should be refused.
+    pub fn is_overloaded(&self) -> bool {
+        self.overloaded.load(Ordering::Relaxed)
+    }
+
+    /// Records the time spent reading the data of a block.
+    pub fn record_read(&self, elapsed: Duration) {
+        let sample = elapsed.as_micros() as u64;
+        let _ = self
+            .read_latency
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+                Some(average - average / 8 + sample / 8)
+            });
+    }
+
+    /// Returns how long a backfill batch of a stream with the given tier
+    /// must wait before being produced.
+    pub fn backfill_delay(&self, tier: PriorityTier) -> Option<Duration> {
+        if self.is_overloaded() && tier <= self.config.max_slowed_tier {
+            Some(self.config.backfill_delay)
+        } else {
+            None
+        }
+    }
+
+    /// Samples the resource usage every interval until cancelled.
+    pub async fn run(self, ct: CancellationToken) {
+        let mut cpu = CpuSampler::new();
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(self.config.check_interval) => {}
+                _ = ct.cancelled() => return,
+            }
+            let usage = ResourceUsage {
+                cpu_usage: cpu.sample(),
+                memory: resident_memory(),
+                read_latency: Duration::from_micros(self.read_latency.load(Ordering::Relaxed)),
+            };
+            let exceeded = self.config.exceeded(&usage);
+            let was_overloaded = self.overloaded.swap(exceeded.is_some(), Ordering::Relaxed);
+            match (was_overloaded, exceeded) {
+                (false, Some(resource)) => {
+                    warn!(resource = %resource, usage = ?usage, "node overloaded, shedding load")
+                }
+                (true, None) => info!(usage = ?usage, "node no longer overloaded"),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl CpuSampler {
+    fn new() -> Self {
+        let cpus = std::thread::available_parallelism()
+            .map(|cpus| cpus.get())
+            .unwrap_or(1);
+        CpuSampler {
+            previous: None,
+            cpus: cpus as f64,
+        }
+    }
+
+    /// Returns the CPU usage since the previous sample.
+    fn sample(&mut self) -> Option<f64> {
+        let now = Instant::now();
+        let ticks = fs::read_to_string("/proc/self/stat")
+            .ok()
+            .and_then(|stat| parse_cpu_ticks(&stat))?;
+        let previous = self.previous.replace((now, ticks));
+        let (previous_at, previous_ticks) = previous?;
+        let elapsed = now.duration_since(previous_at).as_secs_f64();
+        if elapsed == 0.0 {
+            return None;
+        }
+        let used = ticks.saturating_sub(previous_ticks) as f64 / CLOCK_TICKS_PER_SECOND;
+        Some(used / elapsed / self.cpus)
+    }
+}
+
+/// Returns the user and system CPU time in `/proc/self/stat`, in clock
+/// ticks.
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    // the process name can contain spaces, fields are counted after it.
+    let fields: Vec<_> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Returns the resident memory of the node, in bytes.
+fn resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    parse_resident_memory(&status)
+}
+
+fn parse_resident_memory(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::stream::PriorityTier;
+
+    use super::{
+        parse_cpu_ticks, parse_resident_memory, LoadMonitor, LoadSheddingConfig, ResourceUsage,
+    };
+
+    #[test]
+    fn test_parse_proc() {
+        let stat = "1234 (apibara (node)) S 1 1234 1234 0 -1 4194560 100 0 0 0 250 50 0 0 20 0";
+        assert_eq!(parse_cpu_ticks(stat), Some(300));
+        let status = "Name:\tapibara\nVmPeak:\t  2048 kB\nVmRSS:\t  1024 kB\n";
+        assert_eq!(parse_resident_memory(status), Some(1024 * 1024));
+    }
+
+    #[test]
+    fn test_load_shedding_thresholds() {
+        let config = LoadSheddingConfig {
+            max_cpu_usage: Some(0.8),
+            max_read_latency: Some(Duration::from_millis(10)),
+            ..LoadSheddingConfig::default()
+        };
+        let mut usage = ResourceUsage {
+            cpu_usage: Some(0.5),
+            memory: Some(1 << 30),
+            read_latency: Duration::from_millis(5),
+        };
+        assert_eq!(config.exceeded(&usage), None);
+        usage.read_latency = Duration::from_millis(20);
+        assert_eq!(config.exceeded(&usage), Some("read latency"));
+        usage.cpu_usage = Some(0.9);
+        assert_eq!(config.exceeded(&usage), Some("cpu"));
+
+        let monitor = LoadMonitor::new(config);
+        assert_eq!(monitor.backfill_delay(PriorityTier::Low), None);
+        monitor
+            .overloaded
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        assert!(monitor.backfill_delay(PriorityTier::Normal).is_some());
+        assert_eq!(monitor.backfill_delay(PriorityTier::High), None);
+    }
+}
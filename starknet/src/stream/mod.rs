@@ -2,6 +2,18 @@
 mod batch_producer;
 mod cursor_producer;
 mod data;
+mod descriptor;
+mod load_shedding;
+mod restriction;
+mod scheduler;
+mod slow_batch;
 
 pub use self::batch_producer::DbBatchProducer;
 pub use self::cursor_producer::SequentialCursorProducer;
+pub use self::descriptor::StarkNetBlockDescriptor;
+pub use self::load_shedding::{LoadMonitor, LoadSheddingConfig, ResourceUsage};
+pub use self::restriction::{FilterRestriction, FilterRestrictions, RestrictionError};
+pub use self::scheduler::{
+    BatchPermit, BatchScheduler, PriorityConfig, PriorityError, PriorityTier,
+};
+pub use self::slow_batch::{BatchTimings, SlowBatchConfig, SlowBatchLog, SlowSendStream};
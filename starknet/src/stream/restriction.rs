@@ -0,0 +1,267 @@
+//! Restrict the filters of callers.
+//!
+//! Operators of a shared node can give each caller a scoped view of the
+//! chain, for example only the data of their own contracts. Restrictions are
+//! checked every time a stream is configured, and streams with a filter
+//! outside the scope of the caller are rejected.
+//!
+//! Restrictions can be replaced while the node is running, the new
+//! restrictions apply to streams configured after that.
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use apibara_core::{
+    node::v1alpha2::DataFinality,
+    starknet::v1alpha2::{transaction_filter, FieldElement, Filter},
+};
+use apibara_node::stream::{StreamConfiguration, StreamError};
+use serde::Deserialize;
+
+use crate::core::GlobalBlockId;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RestrictionError {
+    #[error("failed to read filter restrictions file")]
+    Io(#[from] io::Error),
+    #[error("invalid filter restrictions file")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Restrictions on the filters of a caller.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterRestriction {
+    /// Only allow filters that select data of these contracts. Any data if
+    /// not set.
+    pub contracts: Option<Vec<FieldElement>>,
+    /// Reject streams of pending data.
+    pub deny_pending: bool,
+}
+
+/// Shared filter restrictions of callers.
+#[derive(Debug, Clone, Default)]
+pub struct FilterRestrictions {
+    callers: Arc<RwLock<HashMap<String, FilterRestriction>>>,
+}
+
+impl FilterRestriction {
+    /// Returns an error if the configuration is outside the restriction.
+    pub fn check(
+        &self,
+        configuration: &StreamConfiguration<GlobalBlockId, Filter>,
+    ) -> Result<(), StreamError> {
+        self.check_filter(&configuration.filter, configuration.finality)
+    }
+
+    /// Returns an error if the filter or finality are outside the
+    /// restriction.
+    pub fn check_filter(&self, filter: &Filter, finality: DataFinality) -> Result<(), StreamError> {
+        if self.deny_pending && finality == DataFinality::DataStatusPending {
+            return Err(restricted("pending data is not allowed"));
+        }
+        if let Some(contracts) = &self.contracts {
+            check_filter_contracts(filter, contracts)?;
+        }
+        Ok(())
+    }
+}
+
+impl FilterRestrictions {
+    /// Restricts the filters of the caller with the given identity.
+    pub fn with_caller(self, identity: impl Into<String>, restriction: FilterRestriction) -> Self {
+        self.callers
+            .write()
+            .expect("filter restrictions lock")
+            .insert(identity.into(), restriction);
+        self
+    }
+
+    /// Reads the restrictions from a JSON file.
+    ///
+    /// The file contains an object with the restriction of each caller
+    /// identity, for example:
+    ///
+    /// ```json
+    /// { "alice": { "contracts": ["0x049d36..."], "deny_pending": true } }
+    /// ```
+    pub fn from_file(path: &Path) -> Result<Self, RestrictionError> {
+        let content = fs::read_to_string(path)?;
+        Self::from_json(&content)
+    }
+
+    fn from_json(content: &str) -> Result<Self, RestrictionError> {
+        let callers: HashMap<String, FilterRestriction> = serde_json::from_str(content)?;
+        Ok(FilterRestrictions {
+            callers: Arc::new(RwLock::new(callers)),
+        })
+    }
+
+    /// Replaces the restrictions with the ones in the file, returning the
+    /// identity of the callers whose restriction changed.
+    ///
+    /// The current restrictions are kept if the file is invalid.
+    pub fn reload_from_file(&self, path: &Path) -> Result<Vec<String>, RestrictionError> {
+        let new = Self::from_file(path)?;
+        let new = std::mem::take(&mut *new.callers.write().expect("filter restrictions lock"));
+        let mut callers = self.callers.write().expect("filter restrictions lock");
+        let mut changed: Vec<_> = callers
+            .iter()
+            .filter(|(identity, restriction)| new.get(*identity) != Some(*restriction))
+            .map(|(identity, _)| identity.clone())
+            .collect();
+        changed.extend(
+            new.keys()
+                .filter(|identity| !callers.contains_key(*identity))
+                .cloned(),
+        );
+        *callers = new;
+        Ok(changed)
+    }
+
+    /// Returns the restriction of the caller, if any.
+    pub fn caller(&self, identity: Option<&str>) -> Option<FilterRestriction> {
+        let identity = identity?;
+        self.callers
+            .read()
+            .expect("filter restrictions lock")
+            .get(identity)
+            .cloned()
+    }
+}
+
+/// Checks that all parts of the filter only select data of the given
+/// contracts.
+fn check_filter_contracts(filter: &Filter, contracts: &[FieldElement]) -> Result<(), StreamError> {
+    let allowed = |address: &Option<FieldElement>, what: &str| match address {
+        Some(address) if contracts.contains(address) => Ok(()),
+        Some(address) => Err(restricted(&format!(
+            "{what} filter on contract {address} is not allowed"
+        ))),
+        None => Err(restricted(&format!(
+            "{what} filter must select an allowed contract"
+        ))),
+    };
+
+    for transaction in &filter.transactions {
+        use transaction_filter::Filter as TxFilter;
+        match &transaction.filter {
+            Some(TxFilter::InvokeV0(filter)) => allowed(&filter.contract_address, "transaction")?,
+            Some(TxFilter::InvokeV1(filter)) => allowed(&filter.sender_address, "transaction")?,
+            Some(TxFilter::Declare(filter)) => allowed(&filter.sender_address, "transaction")?,
+            Some(TxFilter::L1Handler(filter)) => allowed(&filter.contract_address, "transaction")?,
+            Some(TxFilter::Deploy(_)) | Some(TxFilter::DeployAccount(_)) | None => {
+                return Err(restricted(
+                    "transaction filter must select an allowed contract",
+                ));
+            }
+        }
+    }
+    for event in &filter.events {
+        allowed(&event.from_address, "event")?;
+    }
+    if !filter.messages.is_empty() {
+        return Err(restricted("message filters are not allowed"));
+    }
+    for invocation in &filter.invocations {
+        allowed(&invocation.contract_address, "invocation")?;
+    }
+    for class in &filter.declared_classes {
+        allowed(&class.sender_address, "declared class")?;
+    }
+    if let Some(state_update) = &filter.state_update {
+        if !state_update.declared_contracts.is_empty() {
+            return Err(restricted("declared contract filters are not allowed"));
+        }
+        for diff in &state_update.storage_diffs {
+            allowed(&diff.contract_address, "storage diff")?;
+        }
+        for deployed in &state_update.deployed_contracts {
+            allowed(&deployed.contract_address, "deployed contract")?;
+        }
+        for nonce in &state_update.nonces {
+            allowed(&nonce.contract_address, "nonce")?;
+        }
+    }
+    Ok(())
+}
+
+fn restricted(reason: &str) -> StreamError {
+    StreamError::invalid_request(format!("filter restricted: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use apibara_core::{
+        node::v1alpha2::DataFinality,
+        starknet::v1alpha2::{EventFilter, FieldElement, Filter, L2ToL1MessageFilter},
+    };
+    use apibara_node::stream::StreamConfiguration;
+
+    use crate::core::GlobalBlockId;
+
+    use super::FilterRestrictions;
+
+    fn configuration(
+        filter: Filter,
+        finality: DataFinality,
+    ) -> StreamConfiguration<GlobalBlockId, Filter> {
+        StreamConfiguration {
+            filter,
+            finality,
+            ..StreamConfiguration::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_restrictions() {
+        let restrictions = FilterRestrictions::from_json(
+            r#"{ "alice": { "contracts": ["0x1", "0x2"], "deny_pending": true } }"#,
+        )
+        .unwrap();
+        assert!(restrictions.caller(Some("bob")).is_none());
+        assert!(restrictions.caller(None).is_none());
+        let alice = &restrictions.caller(Some("alice")).unwrap();
+
+        let events = |address: Option<FieldElement>| Filter {
+            events: vec![EventFilter {
+                from_address: address,
+                ..EventFilter::default()
+            }],
+            ..Filter::default()
+        };
+        let allowed = events(Some(FieldElement::from_u64(2)));
+        assert!(alice
+            .check(&configuration(
+                allowed.clone(),
+                DataFinality::DataStatusAccepted
+            ))
+            .is_ok());
+        assert!(alice
+            .check(&configuration(allowed, DataFinality::DataStatusPending))
+            .is_err());
+        assert!(alice
+            .check(&configuration(
+                events(Some(FieldElement::from_u64(3))),
+                DataFinality::DataStatusAccepted
+            ))
+            .is_err());
+        assert!(alice
+            .check(&configuration(
+                events(None),
+                DataFinality::DataStatusAccepted
+            ))
+            .is_err());
+
+        let messages = Filter {
+            messages: vec![L2ToL1MessageFilter::default()],
+            ..Filter::default()
+        };
+        assert!(alice
+            .check(&configuration(messages, DataFinality::DataStatusAccepted))
+            .is_err());
+    }
+}
@@ -0,0 +1,287 @@
+//! Schedule the production of batches by priority.
+//!
+//! Reading and filtering batches competes for CPU and IO. The scheduler
+//! limits how many batches are produced at the same time and, when streams
+//! wait for their turn, serves higher priority tiers first. Streams in the
+//! same tier are served in order.
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    fs, io,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+/// Priority of the streams of a caller.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityTier {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PriorityError {
+    #[error("failed to read priority tiers file")]
+    Io(#[from] io::Error),
+    #[error("invalid priority tiers file: line {0}")]
+    InvalidLine(usize),
+    #[error("invalid priority tier {0}, expected low, normal or high")]
+    InvalidTier(String),
+}
+
+/// Priority tiers configuration.
+#[derive(Debug, Clone, Serialize)]
+pub struct PriorityConfig {
+    /// Maximum number of batches produced at the same time.
+    pub max_concurrent_batches: usize,
+    /// Tier of callers without a specific tier, and of anonymous callers.
+    pub default_tier: PriorityTier,
+    /// Tier of specific callers.
+    #[serde(serialize_with = "crate::runtime_config::serialize_len")]
+    pub caller_tiers: HashMap<String, PriorityTier>,
+}
+
+/// Shared batch scheduler.
+#[derive(Debug, Clone)]
+pub struct BatchScheduler {
+    config: Arc<PriorityConfig>,
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+/// Allows the holder to produce a batch. Dropping it lets the next waiting
+/// stream produce its batch.
+#[derive(Debug)]
+pub struct BatchPermit {
+    state: Option<Arc<Mutex<SchedulerState>>>,
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+    available: usize,
+    next_seq: u64,
+    waiters: BinaryHeap<Waiter>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    tier: PriorityTier,
+    seq: u64,
+    tx: oneshot::Sender<BatchPermit>,
+}
+
+impl FromStr for PriorityTier {
+    type Err = PriorityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" => Ok(PriorityTier::Low),
+            "normal" => Ok(PriorityTier::Normal),
+            "high" => Ok(PriorityTier::High),
+            _ => Err(PriorityError::InvalidTier(s.to_string())),
+        }
+    }
+}
+
+impl PriorityConfig {
+    /// Creates a configuration where all callers have the normal tier.
+    pub fn new(max_concurrent_batches: usize) -> Self {
+        PriorityConfig {
+            max_concurrent_batches: max_concurrent_batches.max(1),
+            default_tier: PriorityTier::default(),
+            caller_tiers: HashMap::default(),
+        }
+    }
+
+    pub fn with_default_tier(mut self, tier: PriorityTier) -> Self {
+        self.default_tier = tier;
+        self
+    }
+
+    /// Use the given tier for the caller, instead of the default one.
+    pub fn with_caller_tier(mut self, identity: impl Into<String>, tier: PriorityTier) -> Self {
+        self.caller_tiers.insert(identity.into(), tier);
+        self
+    }
+
+    /// Reads the tier of callers from a file.
+    ///
+    /// Each line contains the caller identity and its tier (`low`, `normal`
+    /// or `high`), separated by whitespace. Lines starting with `#` are
+    /// ignored.
+    pub fn with_caller_tiers_file(self, path: &Path) -> Result<Self, PriorityError> {
+        let content = fs::read_to_string(path)?;
+        self.with_caller_tiers_from_str(&content)
+    }
+
+    fn with_caller_tiers_from_str(mut self, content: &str) -> Result<Self, PriorityError> {
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parts: Vec<_> = line.split_whitespace().collect();
+            match parts.as_slice() {
+                [identity, tier] => {
+                    self = self.with_caller_tier(*identity, tier.parse()?);
+                }
+                _ => return Err(PriorityError::InvalidLine(index + 1)),
+            }
+        }
+        Ok(self)
+    }
+}
+
+impl BatchScheduler {
+    pub fn new(config: PriorityConfig) -> Self {
+        let state = SchedulerState {
+            available: config.max_concurrent_batches,
+            next_seq: 0,
+            waiters: BinaryHeap::default(),
+        };
+        BatchScheduler {
+            config: Arc::new(config),
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Returns the tier of the caller with the given identity.
+    pub fn tier(&self, identity: Option<&str>) -> PriorityTier {
+        identity
+            .and_then(|identity| self.config.caller_tiers.get(identity))
+            .copied()
+            .unwrap_or(self.config.default_tier)
+    }
+
+    /// Waits for the turn of a stream with the given tier to produce a
+    /// batch.
+    pub async fn acquire(&self, tier: PriorityTier) -> BatchPermit {
+        let rx = {
+            let mut state = self.state.lock().expect("batch scheduler lock");
+            if state.available > 0 {
+                state.available -= 1;
+                return BatchPermit {
+                    state: Some(self.state.clone()),
+                };
+            }
+            let (tx, rx) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiters.push(Waiter { tier, seq, tx });
+            rx
+        };
+        // the sender is only dropped after sending a permit.
+        rx.await.expect("batch scheduler waiter")
+    }
+}
+
+impl Drop for BatchPermit {
+    fn drop(&mut self) {
+        let state = match self.state.take() {
+            None => return,
+            Some(state) => state,
+        };
+        let mut guard = state.lock().expect("batch scheduler lock");
+        while let Some(waiter) = guard.waiters.pop() {
+            let permit = BatchPermit {
+                state: Some(state.clone()),
+            };
+            match waiter.tx.send(permit) {
+                Ok(()) => return,
+                // the stream stopped waiting, try the next one.
+                Err(mut permit) => {
+                    permit.state = None;
+                }
+            }
+        }
+        guard.available += 1;
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    /// Higher tiers first, then older waiters first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.tier
+            .cmp(&other.tier)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::FutureExt;
+
+    use super::{BatchScheduler, PriorityConfig, PriorityTier};
+
+    #[test]
+    fn test_caller_tiers_from_str() {
+        let config = PriorityConfig::new(4)
+            .with_caller_tiers_from_str("# comment\nalice high\n\nbob low\n")
+            .unwrap();
+        let scheduler = BatchScheduler::new(config);
+        assert_eq!(scheduler.tier(Some("alice")), PriorityTier::High);
+        assert_eq!(scheduler.tier(Some("bob")), PriorityTier::Low);
+        assert_eq!(scheduler.tier(Some("carol")), PriorityTier::Normal);
+        assert_eq!(scheduler.tier(None), PriorityTier::Normal);
+
+        assert!(PriorityConfig::new(4)
+            .with_caller_tiers_from_str("alice")
+            .is_err());
+        assert!(PriorityConfig::new(4)
+            .with_caller_tiers_from_str("alice urgent")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_higher_tiers_first() {
+        let scheduler = BatchScheduler::new(PriorityConfig::new(1));
+        let permit = scheduler.acquire(PriorityTier::Low).await;
+
+        let low = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(PriorityTier::Low).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let high = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(PriorityTier::High).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // the low priority stream waited longer, but the high one goes first.
+        drop(permit);
+        let high_permit = high.await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!low.is_finished());
+
+        drop(high_permit);
+        let low_permit = low.await.unwrap();
+        drop(low_permit);
+
+        // permits are returned once no stream is waiting.
+        let permit = scheduler.acquire(PriorityTier::Normal).now_or_never();
+        assert!(permit.is_some());
+    }
+}
@@ -0,0 +1,230 @@
+//! Log batches that are slow to produce or send.
+//!
+//! Warnings include the hash of the stream filter, the same hash reported
+//! by the audit log, so that pathological filters can be found in the logs
+//! and matched with the streams that use them.
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{self, Poll},
+    time::{Duration, Instant},
+};
+
+use apibara_core::{
+    node::v1alpha2::{stream_data_response, StreamDataResponse},
+    starknet::v1alpha2,
+};
+use futures::Stream;
+use pin_project::pin_project;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::core::GlobalBlockId;
+
+/// Thresholds over which batches are logged.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SlowBatchConfig {
+    /// Log batches that take longer than this to produce.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub produce_threshold: Option<Duration>,
+    /// Log messages that take longer than this to send to the client.
+    #[serde(serialize_with = "crate::runtime_config::serialize_optional_duration")]
+    pub send_threshold: Option<Duration>,
+}
+
+/// Time spent in each stage of producing a batch.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BatchTimings {
+    /// Time the batch was delayed because the node is overloaded.
+    pub delay: Duration,
+    /// Time waiting for the turn of the stream priority tier.
+    pub wait: Duration,
+    /// Time reading and filtering the blocks.
+    pub read: Duration,
+    /// Longest time reading and filtering a single block.
+    pub slowest_block: Duration,
+}
+
+/// Logs the slow batches of one stream.
+#[derive(Debug, Clone)]
+pub struct SlowBatchLog {
+    config: SlowBatchConfig,
+    filter_hash: Arc<Mutex<String>>,
+}
+
+/// A stream that logs the messages that are slow to send.
+#[pin_project]
+pub struct SlowSendStream<S> {
+    #[pin]
+    inner: S,
+    log: Option<SlowBatchLog>,
+    in_flight: Option<InFlightMessage>,
+}
+
+/// A message yielded to the client, but not sent yet.
+struct InFlightMessage {
+    first_block: Option<u64>,
+    last_block: Option<u64>,
+    blocks: usize,
+    bytes: usize,
+    yielded_at: Instant,
+}
+
+impl SlowBatchConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.produce_threshold.is_some() || self.send_threshold.is_some()
+    }
+}
+
+impl BatchTimings {
+    /// Returns the total time to produce the batch.
+    pub fn total(&self) -> Duration {
+        self.delay + self.wait + self.read
+    }
+
+    /// Records the time to read and filter one block.
+    pub fn record_block(&mut self, elapsed: Duration) {
+        self.read += elapsed;
+        self.slowest_block = self.slowest_block.max(elapsed);
+    }
+}
+
+impl SlowBatchLog {
+    pub fn new(config: SlowBatchConfig) -> Self {
+        SlowBatchLog {
+            config,
+            filter_hash: Arc::default(),
+        }
+    }
+
+    /// Identify the batches that follow with the hash of `filter`.
+    pub fn set_filter(&self, filter: &v1alpha2::Filter) {
+        let hash = hex::encode(filter.canonical_hash());
+        *self.filter_hash.lock().expect("filter hash lock") = hash;
+    }
+
+    fn filter_hash(&self) -> String {
+        self.filter_hash.lock().expect("filter hash lock").clone()
+    }
+
+    /// Logs the batch of `cursors` if it took longer than the threshold to
+    /// produce.
+    pub fn produced(&self, cursors: &[GlobalBlockId], timings: &BatchTimings) {
+        let threshold = match self.config.produce_threshold {
+            None => return,
+            Some(threshold) => threshold,
+        };
+        let total = timings.total();
+        if total <= threshold {
+            return;
+        }
+        warn!(
+            filter_hash = %self.filter_hash(),
+            first_block = cursors.first().map(GlobalBlockId::number),
+            last_block = cursors.last().map(GlobalBlockId::number),
+            blocks = cursors.len(),
+            total_ms = total.as_millis() as u64,
+            delay_ms = timings.delay.as_millis() as u64,
+            wait_ms = timings.wait.as_millis() as u64,
+            read_ms = timings.read.as_millis() as u64,
+            slowest_block_ms = timings.slowest_block.as_millis() as u64,
+            "slow batch production"
+        );
+    }
+
+    /// Logs the message if it took longer than the threshold to send.
+    fn sent(&self, message: &InFlightMessage) {
+        let threshold = match self.config.send_threshold {
+            None => return,
+            Some(threshold) => threshold,
+        };
+        let elapsed = message.yielded_at.elapsed();
+        if elapsed <= threshold {
+            return;
+        }
+        warn!(
+            filter_hash = %self.filter_hash(),
+            first_block = message.first_block,
+            last_block = message.last_block,
+            blocks = message.blocks,
+            bytes = message.bytes,
+            send_ms = elapsed.as_millis() as u64,
+            "slow batch send"
+        );
+    }
+}
+
+impl InFlightMessage {
+    fn new(response: &StreamDataResponse) -> Option<Self> {
+        let data = match &response.message {
+            Some(stream_data_response::Message::Data(data)) => data,
+            _ => return None,
+        };
+        Some(InFlightMessage {
+            first_block: data.cursor.as_ref().map(|cursor| cursor.order_key),
+            last_block: data.end_cursor.as_ref().map(|cursor| cursor.order_key),
+            blocks: data.data.len(),
+            bytes: data.data.iter().map(Vec::len).sum(),
+            yielded_at: Instant::now(),
+        })
+    }
+}
+
+impl<S> SlowSendStream<S> {
+    /// Logs the messages of `inner` that are slow to send with `log`, if
+    /// any.
+    pub fn new(inner: S, log: Option<SlowBatchLog>) -> Self {
+        SlowSendStream {
+            inner,
+            log,
+            in_flight: None,
+        }
+    }
+}
+
+impl<S, E> Stream for SlowSendStream<S>
+where
+    S: Stream<Item = Result<StreamDataResponse, E>>,
+{
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let log = match this.log {
+            None => return this.inner.poll_next(cx),
+            Some(log) => log,
+        };
+        // the server asks for the next message once it sent the previous one.
+        if let Some(message) = this.in_flight.take() {
+            log.sent(&message);
+        }
+        let item = this.inner.poll_next(cx);
+        if let Poll::Ready(Some(Ok(response))) = &item {
+            *this.in_flight = InFlightMessage::new(response);
+        }
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::BatchTimings;
+
+    #[test]
+    fn test_batch_timings() {
+        let mut timings = BatchTimings {
+            delay: Duration::from_millis(100),
+            wait: Duration::from_millis(20),
+            ..BatchTimings::default()
+        };
+        timings.record_block(Duration::from_millis(5));
+        timings.record_block(Duration::from_millis(30));
+        timings.record_block(Duration::from_millis(10));
+
+        assert_eq!(timings.read, Duration::from_millis(45));
+        assert_eq!(timings.slowest_block, Duration::from_millis(30));
+        assert_eq!(timings.total(), Duration::from_millis(165));
+    }
+}
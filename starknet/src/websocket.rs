@@ -1,24 +1,95 @@
+//! Stream data over WebSocket, for clients that can't use gRPC.
+//!
+//! Clients connect to `/ws` and send their stream configuration as JSON
+//! text messages, like they would send `StreamDataRequest`s. Data is sent
+//! back as JSON text messages, or as protobuf encoded `StreamDataResponse`
+//! binary messages with `?format=binary`.
+//!
+//! When the node requires authentication, clients send their token either
+//! in the `authorization` header or, since browsers can't set headers on
+//! WebSocket connections, in the `token` query parameter.
+//!
+//! Clients are subject to the same IP filter, rate limits and quotas as
+//! gRPC clients.
 use crate::db::StorageReader;
 use crate::ingestion::IngestionStreamClient;
+use crate::server::auth::{validate_token, CALLER_METADATA_KEY};
+use crate::server::quota::{CallerQuota, QuotaLimitedStream, QuotaMeter, QuotaTracker};
 use crate::server::stream::IngestionStream;
-use crate::stream::{DbBatchProducer, SequentialCursorProducer};
+use crate::server::{retry_after_seconds, IpFilter, RateLimiter, TokenValidator};
+use crate::stream::{
+    DbBatchProducer, FilterRestriction, FilterRestrictions, SequentialCursorProducer,
+};
+use apibara_core::node::v1alpha2::StreamDataResponse;
 use apibara_core::starknet::v1alpha2::Block;
 use apibara_core::starknet::v1alpha2::Filter;
-use apibara_node::stream::{new_data_stream, StreamConfigurationStream, StreamError};
+use apibara_node::server::{RequestObserver, SimpleRequestObserver};
+use apibara_node::stream::{
+    new_data_stream, BatchSizePolicy, StreamConfigurationStream, StreamError,
+};
 use apibara_sdk::{Configuration, DataMessage};
 use futures::future;
 use futures::{SinkExt, StreamExt, TryStreamExt};
+use prost::Message as ProstMessage;
+use serde::Deserialize;
+use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::info;
+use tonic::metadata::MetadataMap;
+use tracing::{debug, info, warn, Instrument};
+use warp::http::{HeaderMap, StatusCode};
 use warp::ws::{Message, WebSocket};
 use warp::Filter as WarpFilter;
+use warp::Reply;
 
-#[derive(Clone)]
-pub struct WebsocketStreamServer<R: StorageReader + Send + Sync + 'static> {
+/// Close code sent when the configuration is invalid.
+const CLOSE_INVALID_REQUEST: u16 = 1008;
+
+/// Close code sent when a block is larger than the message limit.
+const CLOSE_MESSAGE_TOO_BIG: u16 = 1009;
+
+/// Close code sent on internal errors.
+const CLOSE_INTERNAL_ERROR: u16 = 1011;
+
+/// Close code sent when the client should reconnect later.
+const CLOSE_TRY_AGAIN_LATER: u16 = 1013;
+
+/// Close reasons are limited to 123 bytes.
+const MAX_CLOSE_REASON_SIZE: usize = 123;
+
+pub struct WebsocketStreamServer<R, O = SimpleRequestObserver>
+where
+    R: StorageReader + Send + Sync + 'static,
+    O: RequestObserver,
+{
     address: String,
     ingestion: Arc<IngestionStreamClient>,
     storage: Arc<R>,
+    request_observer: O,
+    token_validator: Option<Arc<dyn TokenValidator>>,
+    batch_size_policy: BatchSizePolicy,
+    restrictions: Option<FilterRestrictions>,
+    quota: Option<QuotaTracker>,
+    rate_limiter: Option<RateLimiter>,
+    ip_filter: Option<IpFilter>,
+}
+
+/// Encoding of the data sent to clients.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameFormat {
+    /// JSON encoded `DataMessage`s, in text messages.
+    #[default]
+    Json,
+    /// Protobuf encoded `StreamDataResponse`s, in binary messages.
+    Binary,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConnectQuery {
+    #[serde(default)]
+    format: FrameFormat,
+    token: Option<String>,
 }
 
 impl<R: StorageReader + Send + Sync + 'static> WebsocketStreamServer<R> {
@@ -32,56 +103,232 @@ impl<R: StorageReader + Send + Sync + 'static> WebsocketStreamServer<R> {
             address,
             ingestion,
             storage: db,
+            request_observer: SimpleRequestObserver::default(),
+            token_validator: None,
+            batch_size_policy: BatchSizePolicy::default(),
+            restrictions: None,
+            quota: None,
+            rate_limiter: None,
+            ip_filter: None,
         }
     }
+}
+
+impl<R, O> WebsocketStreamServer<R, O>
+where
+    R: StorageReader + Send + Sync + 'static,
+    O: RequestObserver,
+{
+    /// Creates a new server with the given request observer.
+    pub fn with_request_observer<S: RequestObserver>(
+        self,
+        request_observer: S,
+    ) -> WebsocketStreamServer<R, S> {
+        WebsocketStreamServer {
+            address: self.address,
+            ingestion: self.ingestion,
+            storage: self.storage,
+            request_observer,
+            token_validator: self.token_validator,
+            batch_size_policy: self.batch_size_policy,
+            restrictions: self.restrictions,
+            quota: self.quota,
+            rate_limiter: self.rate_limiter,
+            ip_filter: self.ip_filter,
+        }
+    }
+
+    /// Require clients to authenticate with a token checked by the given
+    /// validator.
+    pub fn with_token_validator(mut self, validator: Option<Arc<dyn TokenValidator>>) -> Self {
+        self.token_validator = validator;
+        self
+    }
+
+    /// Clamp the batch size requested by clients with the given policy.
+    pub fn with_batch_size_policy(mut self, policy: BatchSizePolicy) -> Self {
+        self.batch_size_policy = policy;
+        self
+    }
+
+    /// Restrict the filters of authenticated clients.
+    pub fn with_restrictions(mut self, restrictions: Option<FilterRestrictions>) -> Self {
+        self.restrictions = restrictions;
+        self
+    }
+
+    /// Limit the data units streamed by each authenticated caller.
+    pub fn with_quota(mut self, quota: Option<QuotaTracker>) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    /// Rate limit new streams.
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Only accept clients allowed by the given IP filter.
+    pub fn with_ip_filter(mut self, ip_filter: Option<IpFilter>) -> Self {
+        self.ip_filter = ip_filter;
+        self
+    }
 
     pub async fn start(self: Arc<Self>) {
         let socket_address: SocketAddr = self.address.parse().expect("valid socket Address");
 
-        let ws = warp::path("ws")
+        let server = warp::serve(self.routes()).try_bind(socket_address);
+
+        info!("Running websocket server at {}!", socket_address);
+
+        server.await
+    }
+
+    fn routes(
+        self: Arc<Self>,
+    ) -> impl WarpFilter<Extract = (warp::reply::Response,), Error = warp::Rejection> + Clone {
+        warp::path("ws")
             .and(warp::ws())
-            .map(move |ws: warp::ws::Ws| {
+            .and(warp::query::<ConnectQuery>())
+            .and(warp::header::headers_cloned())
+            .and(warp::addr::remote())
+            .and_then(move |ws, query, headers, remote| {
                 let self_ = self.clone();
-                ws.on_upgrade(move |websocket| self_.connect(websocket))
+                async move { self_.upgrade(ws, query, headers, remote).await }
+            })
+    }
+
+    /// Checks and authenticates the client, then upgrades the connection.
+    async fn upgrade(
+        self: Arc<Self>,
+        ws: warp::ws::Ws,
+        query: ConnectQuery,
+        headers: HeaderMap,
+        remote: Option<SocketAddr>,
+    ) -> Result<warp::reply::Response, Infallible> {
+        let peer = remote.map(|addr| addr.ip());
+        if let (Some(filter), Some(peer)) = (&self.ip_filter, peer) {
+            if !filter.allow_request(peer, &headers) {
+                let reply =
+                    warp::reply::with_status("client address not allowed", StatusCode::FORBIDDEN);
+                return Ok(reply.into_response());
+            }
+        }
+
+        let mut identity = None;
+        let mut restriction = None;
+        if let Some(validator) = &self.token_validator {
+            let token = query.token.as_deref().or_else(|| {
+                headers
+                    .get("authorization")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("Bearer "))
             });
+            let token = match token {
+                None => {
+                    let reply = warp::reply::with_status("missing token", StatusCode::UNAUTHORIZED);
+                    return Ok(reply.into_response());
+                }
+                Some(token) => token.trim(),
+            };
+            let caller = match validate_token(validator.as_ref(), token).await {
+                Ok(caller) => caller,
+                Err(status) => {
+                    debug!(status = ?status, "websocket client not authenticated");
+                    let reply = warp::reply::with_status("invalid token", StatusCode::UNAUTHORIZED);
+                    return Ok(reply.into_response());
+                }
+            };
+            restriction = self
+                .restrictions
+                .as_ref()
+                .and_then(|restrictions| restrictions.caller(Some(caller.as_str())));
+            identity = Some(caller);
+        }
+        let identity = identity.as_ref().map(|identity| identity.as_str());
 
-        let server = warp::serve(ws).try_bind(socket_address);
+        if let Some(limiter) = &self.rate_limiter {
+            if let Err(retry_after) = limiter.check_stream(peer, identity) {
+                debug!(ip = ?peer, key = ?identity, "websocket stream rate limited");
+                let seconds = retry_after_seconds(retry_after);
+                let message = format!("rate limit exceeded, retry after {}s", seconds);
+                let reply = warp::reply::with_status(message, StatusCode::TOO_MANY_REQUESTS);
+                let reply = warp::reply::with_header(reply, "retry-after", seconds.to_string());
+                return Ok(reply.into_response());
+            }
+        }
 
-        info!("Running websocket server at {}!", socket_address);
+        let quota = match (&self.quota, identity) {
+            (Some(tracker), Some(identity)) => {
+                let quota = tracker.caller(identity);
+                if let Err(exceeded) = quota.refresh_and_check().await {
+                    let message = StreamError::from(exceeded).to_string();
+                    let reply = warp::reply::with_status(message, StatusCode::TOO_MANY_REQUESTS);
+                    return Ok(reply.into_response());
+                }
+                Some(quota)
+            }
+            _ => None,
+        };
 
-        server.await
+        // the caller is only set by the server, like the gRPC auth layer does.
+        let mut metadata = MetadataMap::from_headers(headers);
+        metadata.remove(CALLER_METADATA_KEY);
+        if let Some(value) = identity.and_then(|identity| identity.parse().ok()) {
+            metadata.insert(CALLER_METADATA_KEY, value);
+        }
+
+        let format = query.format;
+        let span = self.request_observer.stream_data_span(&metadata);
+        let reply = ws.on_upgrade(move |websocket| {
+            self.connect(websocket, format, restriction, metadata, quota)
+                .instrument(span)
+        });
+        Ok(reply.into_response())
     }
 
-    async fn connect(self: Arc<Self>, ws: WebSocket) {
+    async fn connect(
+        self: Arc<Self>,
+        ws: WebSocket,
+        format: FrameFormat,
+        restriction: Option<FilterRestriction>,
+        metadata: MetadataMap,
+        quota: Option<CallerQuota>,
+    ) {
         // Establishing a connection
-        let (user_tx, user_rx) = ws.split();
+        let (mut user_tx, user_rx) = ws.split();
 
+        // the stream ends when the client closes the connection. Ping and
+        // pong messages are handled by the websocket library.
         let configuration_stream = Box::pin(
             user_rx
-                .map_err(Into::into)
-                .map_err(StreamError::Internal)
+                .map_err(StreamError::internal)
+                .try_take_while(|message| future::ready(Ok(!message.is_close())))
+                .try_filter(|message| future::ready(message.is_text() || message.is_binary()))
                 .and_then(|message| async move {
                     serde_json::from_slice::<Configuration<Filter>>(message.as_bytes())
-                        .map_err(Into::into)
-                        .map_err(StreamError::Internal)
+                        .map_err(|err| StreamError::invalid_request(err.to_string()))
                         .and_then(|message| {
                             message
                                 .to_stream_data_request()
-                                .map_err(Into::into)
-                                .map_err(StreamError::Internal)
+                                .map_err(|err| StreamError::invalid_request(err.to_string()))
                         })
                 }),
         );
 
-        let configuration_stream = StreamConfigurationStream::new(configuration_stream);
+        let configuration_stream = StreamConfigurationStream::new(configuration_stream)
+            .with_batch_size_policy(self.batch_size_policy);
 
-        let meter = apibara_node::server::SimpleMeter::default();
-        // let stream_span = self.request_observer.stream_data_span(&metadata);
-        // let stream_meter = self.request_observer.stream_data_meter(&metadata);
+        let meter = self.request_observer.stream_data_meter(&metadata);
+        let meter = QuotaMeter::new(meter, quota.clone());
 
         let ingestion_stream = self.ingestion.subscribe().await;
         let ingestion_stream = IngestionStream::new(ingestion_stream);
-        let batch_producer = DbBatchProducer::new(self.storage.clone());
+        let mut batch_producer = DbBatchProducer::new(self.storage.clone());
+        if let Some(restriction) = restriction {
+            batch_producer = batch_producer.with_restriction(restriction);
+        }
         let cursor_producer = SequentialCursorProducer::new(self.storage.clone());
 
         let data_stream = new_data_stream(
@@ -91,21 +338,154 @@ impl<R: StorageReader + Send + Sync + 'static> WebsocketStreamServer<R> {
             batch_producer,
             meter,
         );
+        let data_stream = QuotaLimitedStream::new(data_stream, quota);
 
-        // TODO: send the first decoding error downstream
-        data_stream
-            .and_then(|message| async {
-                let message = DataMessage::<Block>::from_stream_data_response(message).ok_or(
-                    StreamError::internal("Cannot convert StreamDataResponse to DataMessage"),
-                )?;
-                serde_json::to_string(&message)
-                    .map(Message::text)
-                    .map_err(Into::into)
-                    .map_err(StreamError::Internal)
-            })
-            .take_while(|result| future::ready(result.is_ok()))
-            .forward(user_tx.sink_map_err(StreamError::internal))
-            .await
-            .unwrap(); // we have to unwrap here since ws.on_upgrade expects ()
+        let mut messages = Box::pin(
+            data_stream.and_then(|response| future::ready(encode_response(response, format))),
+        );
+
+        while let Some(message) = messages.next().await {
+            let message = match message {
+                Ok(message) => message,
+                Err(err) => {
+                    let _ = user_tx.send(close_message(err)).await;
+                    break;
+                }
+            };
+            if let Err(err) = user_tx.send(message).await {
+                debug!(error = ?err, "websocket client disconnected");
+                return;
+            }
+        }
+
+        let _ = user_tx.close().await;
+    }
+}
+
+fn encode_response(
+    response: StreamDataResponse,
+    format: FrameFormat,
+) -> Result<Message, StreamError> {
+    match format {
+        FrameFormat::Binary => Ok(Message::binary(response.encode_to_vec())),
+        FrameFormat::Json => {
+            let message = DataMessage::<Block>::from_stream_data_response(response).ok_or(
+                StreamError::internal("Cannot convert StreamDataResponse to DataMessage"),
+            )?;
+            serde_json::to_string(&message)
+                .map(Message::text)
+                .map_err(StreamError::internal)
+        }
+    }
+}
+
+/// Returns the close message sent to the client when the stream fails.
+fn close_message(err: StreamError) -> Message {
+    let (code, mut reason) = match err {
+        StreamError::InvalidRequest { message } => (CLOSE_INVALID_REQUEST, message),
+        err @ StreamError::DataPruned { .. } => (CLOSE_INVALID_REQUEST, err.to_string()),
+        err @ StreamError::MessageTooLarge { .. } => (CLOSE_MESSAGE_TOO_BIG, err.to_string()),
+        err @ (StreamError::QuotaExceeded { .. } | StreamError::Unavailable { .. }) => {
+            (CLOSE_TRY_AGAIN_LATER, err.to_string())
+        }
+        StreamError::Internal(err) => {
+            warn!(err = ?err, "websocket stream error");
+            (CLOSE_INTERNAL_ERROR, "internal server error".to_string())
+        }
+    };
+    if reason.len() > MAX_CLOSE_REASON_SIZE {
+        let mut end = MAX_CLOSE_REASON_SIZE;
+        while !reason.is_char_boundary(end) {
+            end -= 1;
+        }
+        reason.truncate(end);
+    }
+    Message::close_with(code, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use apibara_node::{
+        db::{
+            libmdbx::{Environment, NoWriteMap},
+            MdbxEnvironmentExt,
+        },
+        stream::StreamError,
+    };
+    use tempfile::tempdir;
+    use warp::http::StatusCode;
+
+    use crate::{
+        db::DatabaseStorage,
+        ingestion::IngestionStreamPublisher,
+        server::{
+            auth::StaticKeyValidator,
+            parse_ip_net,
+            quota::{MemoryQuotaBackend, QuotaTracker},
+            CallerIdentity, IpFilter, IpFilterConfig, QuotaConfig, QuotaLimits,
+        },
+    };
+
+    use super::{close_message, WebsocketStreamServer};
+
+    fn handshake(path: &str) -> warp::test::RequestBuilder {
+        warp::test::request()
+            .path(path)
+            .header("connection", "upgrade")
+            .header("upgrade", "websocket")
+            .header("sec-websocket-version", "13")
+            .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+    }
+
+    #[tokio::test]
+    async fn test_quota_and_ip_filter() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Environment::<NoWriteMap>::open(dir.path()).unwrap());
+        let (ingestion, _publisher) = IngestionStreamPublisher::new();
+        let validator = StaticKeyValidator::default()
+            .with_key("dna_aaaa", CallerIdentity::new("alice"))
+            .with_key("dna_bbbb", CallerIdentity::new("bob"));
+        let quota = QuotaTracker::new(
+            QuotaConfig::new(QuotaLimits::new(Some(10), None)),
+            Arc::new(MemoryQuotaBackend::default()),
+        );
+        let ip_filter = IpFilter::new(IpFilterConfig {
+            deny: vec![parse_ip_net("10.0.0.1").unwrap()],
+            ..IpFilterConfig::default()
+        });
+        let server = WebsocketStreamServer::new(
+            "127.0.0.1:0".to_string(),
+            Arc::new(DatabaseStorage::new(db)),
+            ingestion,
+        )
+        .with_token_validator(Some(Arc::new(validator)))
+        .with_quota(Some(quota.clone()))
+        .with_ip_filter(Some(ip_filter));
+        let routes = Arc::new(server).routes();
+
+        quota.caller("alice").add(10);
+        let response = handshake("/ws?token=dna_aaaa").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = std::str::from_utf8(response.body()).unwrap();
+        assert!(body.starts_with("quota exceeded: used 10 of 10 data units this day"));
+
+        let response = handshake("/ws?token=dna_bbbb").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+
+        let response = handshake("/ws?token=dna_bbbb")
+            .remote_addr("10.0.0.1:1234".parse().unwrap())
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_close_message_reason_is_truncated() {
+        let message = close_message(StreamError::invalid_request("é".repeat(100)));
+        let (code, reason) = message.close_frame().unwrap();
+        assert_eq!(code, 1008);
+        assert_eq!(reason.len(), 122);
     }
 }
@@ -46,6 +46,7 @@ async fn test_starknet_reorgs() {
         devnet: false,
         use_metadata: Vec::default(),
         websocket_address: None,
+        ..Default::default()
     };
 
     let configuration = Configuration::<Filter>::default()
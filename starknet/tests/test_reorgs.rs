@@ -39,6 +39,7 @@ async fn test_reorg_from_client_pov() {
                 devnet: true,
                 use_metadata: Vec::default(),
                 websocket_address: None,
+                ..Default::default()
             };
             start_node(args, cts).await.unwrap();
         }
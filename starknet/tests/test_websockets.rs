@@ -43,6 +43,7 @@ async fn test_reorg_from_client_pov_websockets() {
                 devnet: true,
                 use_metadata: Vec::default(),
                 websocket_address: Some("127.0.0.1:8080".into()),
+                ..Default::default()
             };
             start_node(args, cts).await.unwrap();
         }